@@ -4,6 +4,7 @@ use criterion::{Criterion, BatchSize, criterion_group, criterion_main};
 
 use lumol::{EnergyCache, Vector3D};
 use lumol::{GlobalPotential, Ewald, SharedEwald, Wolf};
+use lumol::{Molecule, Particle, System, UnitCell};
 
 mod utils;
 
@@ -43,6 +44,38 @@ fn ewald_energy_computation(c: &mut Criterion) {
     }));
 }
 
+/// NaCl rock-salt crystal with `n x n x n` conventional cells
+fn nacl_crystal(n: usize) -> System {
+    let a = 5.64;
+    let mut system = System::with_cell(UnitCell::cubic(n as f64 * a));
+    for i in 0..(2 * n) {
+        for j in 0..(2 * n) {
+            for k in 0..(2 * n) {
+                let (name, charge) = if (i + j + k) % 2 == 0 {("Na", 1.0)} else {("Cl", -1.0)};
+                let position = Vector3D::new(i as f64, j as f64, k as f64) * a / 2.0;
+                let mut particle = Particle::with_position(name, position);
+                particle.charge = charge;
+                system.add_molecule(Molecule::new(particle));
+            }
+        }
+    }
+    return system;
+}
+
+fn ewald_scaling(c: &mut Criterion) {
+    // Using a small and constant number of k-vectors, the cost of the
+    // energy is dominated by the real space part, which should scale linearly
+    // with the number of atoms at fixed density
+    for &n in &[8, 12, 16] {
+        let system = nacl_crystal(n);
+        let ewald = SharedEwald::new(Ewald::new(9.5, 3, None));
+        let name = format!("nacl::ewald::energy_scaling::{}", system.size());
+        c.bench_function(&name, move |b| b.iter(|| {
+            let _ = ewald.energy(&system);
+        }));
+    }
+}
+
 fn ewald_monte_carlo_cache(c: &mut Criterion) {
     let mut system = utils::get_system("nacl");
     system.set_coulomb_potential(Box::new(get_ewald()));
@@ -119,7 +152,7 @@ fn wolf_monte_carlo_cache(c: &mut Criterion) {
     ));
 }
 
-criterion_group!(ewald, ewald_energy_computation, ewald_monte_carlo_cache);
+criterion_group!(ewald, ewald_energy_computation, ewald_scaling, ewald_monte_carlo_cache);
 criterion_group!(wolf, wolf_energy_computation, wolf_monte_carlo_cache);
 
 criterion_main!(ewald, wolf);
@@ -0,0 +1,65 @@
+// Lumol, an extensible molecular simulation engine
+// Copyright (C) Lumol's contributors — BSD license
+#![allow(clippy::needless_return)]
+use criterion::{Criterion, criterion_group, criterion_main};
+
+use lumol::compute::{Compute, Forces, PotentialEnergy};
+use lumol::energy::{LennardJones, PairInteraction};
+use lumol::{Molecule, Particle, System, UnitCell};
+
+/// Build a system of 4000 argon atoms on a slightly perturbed FCC lattice
+fn argon_lattice() -> System {
+    let cells = 10;
+    let lattice = 5.26;
+    let mut system = System::with_cell(UnitCell::cubic(cells as f64 * lattice));
+    let basis = [[0.0, 0.0, 0.0], [0.5, 0.5, 0.0], [0.5, 0.0, 0.5], [0.0, 0.5, 0.5]];
+    for i in 0..cells {
+        for j in 0..cells {
+            for k in 0..cells {
+                for (n, site) in basis.iter().enumerate() {
+                    let shift = 0.05 * ((i + 2 * j + 3 * k + n) % 7) as f64;
+                    let position = [
+                        lattice * (i as f64 + site[0]) + shift,
+                        lattice * (j as f64 + site[1]),
+                        lattice * (k as f64 + site[2]) - shift,
+                    ];
+                    system.add_molecule(Molecule::new(Particle::with_position("Ar", position.into())));
+                }
+            }
+        }
+    }
+
+    system.set_pair_potential(
+        ("Ar", "Ar"),
+        PairInteraction::new(Box::new(LennardJones { sigma: 3.4, epsilon: 1.0 }), 10.0),
+    );
+    return system;
+}
+
+fn parallel_speedup(c: &mut Criterion) {
+    let serial = rayon::ThreadPoolBuilder::new().num_threads(1).build().unwrap();
+
+    let system = argon_lattice();
+    c.bench_function("parallel::energy::serial", move |b| b.iter(|| {
+        serial.install(|| PotentialEnergy.compute(&system))
+    }));
+
+    let system = argon_lattice();
+    c.bench_function("parallel::energy::parallel", move |b| b.iter(|| {
+        let _ = PotentialEnergy.compute(&system);
+    }));
+
+    let serial = rayon::ThreadPoolBuilder::new().num_threads(1).build().unwrap();
+    let system = argon_lattice();
+    c.bench_function("parallel::force::serial", move |b| b.iter(|| {
+        serial.install(|| Forces.compute(&system))
+    }));
+
+    let system = argon_lattice();
+    c.bench_function("parallel::force::parallel", move |b| b.iter(|| {
+        let _ = Forces.compute(&system);
+    }));
+}
+
+criterion_group!(parallel, parallel_speedup);
+criterion_main!(parallel);
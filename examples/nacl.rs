@@ -7,6 +7,7 @@ use lumol::{TrajectoryBuilder, UnitCell};
 use lumol::units;
 
 use lumol::sim::Simulation;
+use lumol::sim::output::{EnergyOutput, TrajectoryOutput};
 use lumol::sim::md::{MolecularDynamics, RescaleThermostat};
 use lumol::sim::{BoltzmannVelocities, InitVelocities};
 
@@ -30,6 +31,11 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     md.set_thermostat(Box::new(RescaleThermostat::new(units::from(300.0, "K")?)));
 
     let mut simulation = Simulation::new(Box::new(md));
+    // Write the trajectory to `trajectory.xyz` every 100 steps, and the
+    // energy to `energy.dat` every 10 steps
+    simulation.add_output_with_frequency(Box::new(TrajectoryOutput::new("trajectory.xyz")?), 100);
+    simulation.add_output_with_frequency(Box::new(EnergyOutput::new("energy.dat")?), 10);
+
     simulation.run(&mut system, 1000);
 
     Ok(())
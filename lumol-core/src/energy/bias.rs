@@ -0,0 +1,766 @@
+// Lumol, an extensible molecular simulation engine
+// Copyright (C) Lumol's contributors — BSD license
+
+//! Biasing potentials acting on collective variables, for umbrella sampling
+//! and metadynamics.
+use std::sync::{Arc, RwLock, RwLockReadGuard};
+
+use crate::Configuration;
+use crate::{Matrix3, Vector3D};
+use crate::consts::K_BOLTZMANN;
+use crate::energy::{GlobalCache, GlobalPotential};
+
+/// A collective variable is a function of the positions of the particles,
+/// used to describe a process of interest (a distance between two atoms, a
+/// coordination number, *etc.*) in free energy calculations.
+///
+/// # Example
+///
+/// ```
+/// use lumol_core::energy::CollectiveVariable;
+/// use lumol_core::{Configuration, Molecule, Particle, System, Vector3D};
+///
+/// /// The x coordinate of a single particle
+/// #[derive(Clone)]
+/// struct PositionX(usize);
+///
+/// impl CollectiveVariable for PositionX {
+///     fn value(&self, configuration: &Configuration) -> f64 {
+///         configuration.particles().position[self.0][0]
+///     }
+///
+///     fn gradient(&self, configuration: &Configuration) -> Vec<Vector3D> {
+///         let mut gradient = vec![Vector3D::zero(); configuration.size()];
+///         gradient[self.0] = Vector3D::new(1.0, 0.0, 0.0);
+///         return gradient;
+///     }
+/// }
+///
+/// let mut system = System::new();
+/// system.add_molecule(Molecule::new(Particle::with_position("Ar", [2.0, 0.0, 0.0].into())));
+///
+/// let cv = PositionX(0);
+/// assert_eq!(cv.value(&system), 2.0);
+/// assert_eq!(cv.gradient(&system), vec![Vector3D::new(1.0, 0.0, 0.0)]);
+/// ```
+pub trait CollectiveVariable: Sync + Send + BoxCloneCollectiveVariable {
+    /// Get the value of the collective variable in the `configuration`
+    fn value(&self, configuration: &Configuration) -> f64;
+
+    /// Get the gradient of the collective variable with respect to the
+    /// position of all the particles in the `configuration`
+    fn gradient(&self, configuration: &Configuration) -> Vec<Vector3D>;
+}
+
+impl_box_clone!(CollectiveVariable, BoxCloneCollectiveVariable, box_clone_collective_variable);
+
+/// Distance between two particles, using the nearest periodic image.
+///
+/// # Examples
+///
+/// ```
+/// # use lumol_core::energy::{CollectiveVariable, DistanceCV};
+/// # use lumol_core::{Molecule, Particle, System, UnitCell};
+/// let mut system = System::with_cell(UnitCell::cubic(10.0));
+/// system.add_molecule(Molecule::new(Particle::with_position("Ar", [0.0, 0.0, 0.0].into())));
+/// system.add_molecule(Molecule::new(Particle::with_position("Ar", [0.0, 0.0, 8.0].into())));
+///
+/// let distance = DistanceCV::new(0, 1);
+/// assert_eq!(distance.value(&system), 2.0);
+/// ```
+#[derive(Clone)]
+pub struct DistanceCV {
+    /// Index of the first particle
+    i: usize,
+    /// Index of the second particle
+    j: usize,
+}
+
+impl DistanceCV {
+    /// Create a new `DistanceCV` measuring the distance between the
+    /// particles at indexes `i` and `j`.
+    ///
+    /// # Panics
+    ///
+    /// If `i` and `j` are the same particle.
+    pub fn new(i: usize, j: usize) -> DistanceCV {
+        assert!(i != j, "The two particles must be different in distance collective variable");
+        DistanceCV {
+            i: i,
+            j: j,
+        }
+    }
+}
+
+impl CollectiveVariable for DistanceCV {
+    fn value(&self, configuration: &Configuration) -> f64 {
+        configuration.distance(self.i, self.j)
+    }
+
+    fn gradient(&self, configuration: &Configuration) -> Vec<Vector3D> {
+        let mut gradient = vec![Vector3D::zero(); configuration.size()];
+        let unit = configuration.nearest_image(self.i, self.j).normalized();
+        gradient[self.i] = unit;
+        gradient[self.j] = -unit;
+        return gradient;
+    }
+}
+
+/// Coordination number of a group of particles around another group, using a
+/// smooth switching function.
+///
+/// The coordination number is defined as
+///
+/// $$ C = \sum_{i \in A} \sum_{j \in B, j \neq i} \frac{1 - (r_{ij} / r_0)^n}
+///    {1 - (r_{ij} / r_0)^m} $$
+///
+/// where $A$ and $B$ are the two groups of particles, $r_0$ is the cutoff
+/// distance and $n$ and $m$ are the exponents of the switching function, with
+/// default values of 6 and 12. The switching function goes smoothly from 1 at
+/// short distances to 0 at long distances, and is $n / m$ at $r_0$. Pairs
+/// containing a particle present in both groups are counted twice.
+///
+/// # Examples
+///
+/// ```
+/// # use lumol_core::energy::{CollectiveVariable, Coordination};
+/// # use lumol_core::{Molecule, Particle, System, UnitCell};
+/// let mut system = System::with_cell(UnitCell::cubic(10.0));
+/// system.add_molecule(Molecule::new(Particle::with_position("Zn", [0.0, 0.0, 0.0].into())));
+/// system.add_molecule(Molecule::new(Particle::with_position("O", [0.0, 0.0, 2.5].into())));
+///
+/// let coordination = Coordination::new(vec![0], vec![1], 2.5);
+/// assert_eq!(coordination.value(&system), 0.5);
+/// ```
+#[derive(Clone)]
+pub struct Coordination {
+    /// Indexes of the particles in the first group
+    first: Vec<usize>,
+    /// Indexes of the particles in the second group
+    second: Vec<usize>,
+    /// Cutoff distance of the switching function
+    cutoff: f64,
+    /// Exponent of the numerator of the switching function
+    n: i32,
+    /// Exponent of the denominator of the switching function
+    m: i32,
+}
+
+impl Coordination {
+    /// Create a new `Coordination` collective variable, counting the
+    /// particles in the `second` group around the particles in the `first`
+    /// group, using the given `cutoff` distance.
+    ///
+    /// # Panics
+    ///
+    /// If one of the groups is empty, or if the cutoff is not positive.
+    pub fn new(first: Vec<usize>, second: Vec<usize>, cutoff: f64) -> Coordination {
+        assert!(!first.is_empty() && !second.is_empty(), "The groups can not be empty in coordination collective variable");
+        assert!(cutoff > 0.0, "The cutoff must be positive in coordination collective variable");
+        Coordination {
+            first: first,
+            second: second,
+            cutoff: cutoff,
+            n: 6,
+            m: 12,
+        }
+    }
+
+    /// Set the exponents `n` and `m` of the switching function.
+    ///
+    /// # Panics
+    ///
+    /// If `n` is not positive, or if `m` is not larger than `n`.
+    pub fn set_exponents(&mut self, n: i32, m: i32) {
+        assert!(n > 0, "The exponent n must be positive in coordination collective variable");
+        assert!(m > n, "The exponent m must be larger than n in coordination collective variable");
+        self.n = n;
+        self.m = m;
+    }
+
+    /// Get the value of the switching function and of its derivative with
+    /// respect to the distance `r`
+    fn switching(&self, r: f64) -> (f64, f64) {
+        let (n, m) = (f64::from(self.n), f64::from(self.m));
+        let x = r / self.cutoff;
+        if f64::abs(x - 1.0) < 1e-6 {
+            // Use the limit of the function at x = 1, and a first order
+            // expansion around it
+            let derivative = n * (n - m) / (2.0 * m);
+            let value = n / m + derivative * (x - 1.0);
+            return (value, derivative / self.cutoff);
+        }
+
+        let numerator = 1.0 - x.powi(self.n);
+        let denominator = 1.0 - x.powi(self.m);
+        let value = numerator / denominator;
+        let derivative = (-n * x.powi(self.n - 1) * denominator + m * x.powi(self.m - 1) * numerator) / (denominator * denominator);
+        return (value, derivative / self.cutoff);
+    }
+}
+
+impl CollectiveVariable for Coordination {
+    fn value(&self, configuration: &Configuration) -> f64 {
+        let mut value = 0.0;
+        for &i in &self.first {
+            for &j in &self.second {
+                if i != j {
+                    value += self.switching(configuration.distance(i, j)).0;
+                }
+            }
+        }
+        return value;
+    }
+
+    fn gradient(&self, configuration: &Configuration) -> Vec<Vector3D> {
+        let mut gradient = vec![Vector3D::zero(); configuration.size()];
+        for &i in &self.first {
+            for &j in &self.second {
+                if i == j {
+                    continue;
+                }
+                let rij = configuration.nearest_image(i, j);
+                let (_, derivative) = self.switching(rij.norm());
+                let dcv = derivative * rij.normalized();
+                gradient[i] += dcv;
+                gradient[j] -= dcv;
+            }
+        }
+        return gradient;
+    }
+}
+
+/// Harmonic umbrella bias on a collective variable.
+///
+/// The energy of the bias is
+///
+/// $$ V(s) = \frac 12 k \left(s - s_0\right)^2 $$
+///
+/// where $s$ is the value of the collective variable and $s_0$ the center of
+/// the umbrella. The force acting on each particle is $- k (s - s_0) \nabla s$.
+/// This potential should be added to the system with
+/// `System::add_global_potential`, and the unbiased free energy along the
+/// collective variable can be recovered from multiple simulations using
+/// different centers with WHAM or MBAR.
+///
+/// The virial of the bias is computed using the nearest periodic image of the
+/// particles with respect to the first particle with a non-zero gradient,
+/// which assumes the collective variable only depends on a compact group of
+/// particles, and is invariant by translation.
+///
+/// # Examples
+///
+/// ```
+/// # use lumol_core::energy::{DistanceCV, GlobalPotential, HarmonicBias};
+/// # use lumol_core::{Molecule, Particle, System, UnitCell};
+/// let mut system = System::with_cell(UnitCell::cubic(10.0));
+/// system.add_molecule(Molecule::new(Particle::with_position("Ar", [0.0, 0.0, 0.0].into())));
+/// system.add_molecule(Molecule::new(Particle::with_position("Ar", [0.0, 0.0, 3.0].into())));
+///
+/// let bias = HarmonicBias::new(Box::new(DistanceCV::new(0, 1)), 4.0, 2.0);
+/// assert_eq!(bias.value(&system), 3.0);
+/// assert_eq!(bias.energy(&system), 1.0);
+/// ```
+#[derive(Clone)]
+pub struct HarmonicBias {
+    /// The biased collective variable
+    cv: Box<dyn CollectiveVariable>,
+    /// Center of the umbrella
+    center: f64,
+    /// Spring constant
+    k: f64,
+}
+
+impl HarmonicBias {
+    /// Create a new `HarmonicBias` on the collective variable `cv`, with the
+    /// umbrella centered at `center` and using the spring constant `k`.
+    pub fn new(cv: Box<dyn CollectiveVariable>, center: f64, k: f64) -> HarmonicBias {
+        assert!(k >= 0.0, "The spring constant must be positive in harmonic bias");
+        HarmonicBias {
+            cv: cv,
+            center: center,
+            k: k,
+        }
+    }
+
+    /// Get the center of the umbrella
+    pub fn center(&self) -> f64 {
+        self.center
+    }
+
+    /// Get the current value of the biased collective variable in the
+    /// `configuration`
+    pub fn value(&self, configuration: &Configuration) -> f64 {
+        self.cv.value(configuration)
+    }
+}
+
+impl GlobalPotential for HarmonicBias {
+    fn cutoff(&self) -> Option<f64> {
+        None
+    }
+
+    fn energy(&self, configuration: &Configuration) -> f64 {
+        let delta = self.cv.value(configuration) - self.center;
+        return 0.5 * self.k * delta * delta;
+    }
+
+    fn forces(&self, configuration: &Configuration, forces: &mut [Vector3D]) {
+        let delta = self.cv.value(configuration) - self.center;
+        for (force, gradient) in forces.iter_mut().zip(self.cv.gradient(configuration)) {
+            *force -= self.k * delta * gradient;
+        }
+    }
+
+    fn atomic_virial(&self, configuration: &Configuration) -> Matrix3 {
+        let delta = self.cv.value(configuration) - self.center;
+        let gradient = self.cv.gradient(configuration);
+        return bias_virial(configuration, &gradient, self.k * delta);
+    }
+}
+
+impl GlobalCache for HarmonicBias {
+    fn move_molecule_cost(
+        &self,
+        configuration: &Configuration,
+        molecule_id: usize,
+        new_positions: &[Vector3D],
+    ) -> f64 {
+        let old = self.energy(configuration);
+        let moved = moved_configuration(configuration, molecule_id, new_positions);
+        return self.energy(&moved) - old;
+    }
+
+    fn update(&self) {
+        // Nothing is cached
+    }
+}
+
+/// Get the virial of a bias on a collective variable, where `derivative` is
+/// the derivative of the bias energy with respect to the collective variable
+/// and `gradient` the gradient of the collective variable.
+fn bias_virial(configuration: &Configuration, gradient: &[Vector3D], derivative: f64) -> Matrix3 {
+    let positions = configuration.particles().position;
+    let origin = match gradient.iter().position(|g| g.norm2() != 0.0) {
+        Some(i) => positions[i],
+        None => return Matrix3::zero(),
+    };
+
+    let mut virial = Matrix3::zero();
+    for (gradient, position) in gradient.iter().zip(positions) {
+        let mut r = position - origin;
+        configuration.cell.vector_image(&mut r);
+        virial += (-derivative * gradient).tensorial(&r);
+    }
+    return virial;
+}
+
+/// Get a copy of the `configuration` where the molecule at `molecule_id` was
+/// moved to `new_positions`
+fn moved_configuration(configuration: &Configuration, molecule_id: usize, new_positions: &[Vector3D]) -> Configuration {
+    let mut moved = configuration.clone();
+    let indexes = configuration.molecule(molecule_id).indexes();
+    for (i, position) in indexes.zip(new_positions) {
+        moved.particles_mut().position[i] = *position;
+    }
+    return moved;
+}
+
+/// Metadynamics history-dependent bias on a collective variable.
+///
+/// The bias is built by depositing gaussian hills at the current value of the
+/// collective variable during the simulation, discouraging the system from
+/// visiting the same states again:
+///
+/// $$ V(s) = \sum_k h_k \exp\left(-\frac{(s - s_k)^2}{2 \sigma^2}\right) $$
+///
+/// where $s_k$ and $h_k$ are the center and the height of the hills, and
+/// $\sigma$ their width. In standard metadynamics, all the hills have the
+/// same height $h_0$. In well-tempered metadynamics, the height of the hills
+/// decreases as the bias grows: $h_k = h_0 \exp(-V(s_k) / k_B \Delta T)$,
+/// where $\Delta T = (\gamma - 1) T$ is computed from the bias factor
+/// $\gamma$ and the temperature $T$ of the simulation. The free energy is then
+/// $F(s) = -\gamma / (\gamma - 1) V(s)$ at convergence.
+///
+/// All the clones of a `Metadynamics` bias share the same hills, so that one
+/// clone can be added to the system as a global potential while another one
+/// is used to deposit the hills. In molecular dynamics simulations, this is
+/// done every `every` steps by using the bias as a control.
+///
+/// # Examples
+///
+/// ```
+/// # use lumol_core::energy::{DistanceCV, Metadynamics};
+/// # use lumol_core::{Molecule, Particle, System, UnitCell};
+/// let mut system = System::with_cell(UnitCell::cubic(10.0));
+/// system.add_molecule(Molecule::new(Particle::with_position("Ar", [0.0, 0.0, 0.0].into())));
+/// system.add_molecule(Molecule::new(Particle::with_position("Ar", [0.0, 0.0, 3.0].into())));
+///
+/// let metadynamics = Metadynamics::new(Box::new(DistanceCV::new(0, 1)), 0.1, 0.2, 100);
+/// system.add_global_potential(Box::new(metadynamics.clone()));
+/// assert_eq!(system.potential_energy(), 0.0);
+///
+/// metadynamics.deposit(&system);
+/// assert_eq!(metadynamics.hills(), vec![(3.0, 0.1)]);
+/// assert_eq!(system.potential_energy(), 0.1);
+/// ```
+#[derive(Clone)]
+pub struct Metadynamics {
+    /// The biased collective variable
+    cv: Box<dyn CollectiveVariable>,
+    /// Initial height of the hills
+    height: f64,
+    /// Width of the hills
+    width: f64,
+    /// Number of steps between two hills deposition
+    every: u64,
+    /// Energy scale $k_B \Delta T$ for well-tempered metadynamics
+    well_tempered: Option<f64>,
+    /// Centers and heights of the deposited hills
+    hills: Arc<RwLock<Vec<(f64, f64)>>>,
+}
+
+impl Metadynamics {
+    /// Create a new `Metadynamics` bias on the collective variable `cv`,
+    /// depositing hills with the given `height` and `width` every `every`
+    /// steps.
+    ///
+    /// # Panics
+    ///
+    /// If the height is negative, or if the width or the deposition interval
+    /// are not positive.
+    pub fn new(cv: Box<dyn CollectiveVariable>, height: f64, width: f64, every: u64) -> Metadynamics {
+        assert!(height >= 0.0, "The height of the hills must be positive in metadynamics");
+        assert!(width > 0.0, "The width of the hills must be positive in metadynamics");
+        assert!(every > 0, "The deposition interval must be positive in metadynamics");
+        Metadynamics {
+            cv: cv,
+            height: height,
+            width: width,
+            every: every,
+            well_tempered: None,
+            hills: Arc::new(RwLock::new(Vec::new())),
+        }
+    }
+
+    /// Use well-tempered metadynamics, with the given `bias_factor` for a
+    /// simulation at `temperature`.
+    ///
+    /// # Panics
+    ///
+    /// If the bias factor is not larger than 1, or if the temperature is not
+    /// positive.
+    pub fn set_well_tempered(&mut self, bias_factor: f64, temperature: f64) {
+        assert!(bias_factor > 1.0, "The bias factor must be larger than 1 in well-tempered metadynamics");
+        assert!(temperature > 0.0, "The temperature must be positive in well-tempered metadynamics");
+        self.well_tempered = Some(K_BOLTZMANN * (bias_factor - 1.0) * temperature);
+    }
+
+    /// Get the number of steps between two hills deposition
+    pub fn every(&self) -> u64 {
+        self.every
+    }
+
+    /// Get the centers and heights of all the deposited hills
+    pub fn hills(&self) -> Vec<(f64, f64)> {
+        self.read().clone()
+    }
+
+    /// Get the bias energy for the value `s` of the collective variable
+    pub fn bias(&self, s: f64) -> f64 {
+        let factor = 0.5 / (self.width * self.width);
+        return self.read().iter().map(|&(center, height)| {
+            height * f64::exp(-factor * (s - center) * (s - center))
+        }).sum();
+    }
+
+    /// Get the derivative of the bias energy with respect to the collective
+    /// variable, for the value `s` of the collective variable
+    fn bias_derivative(&self, s: f64) -> f64 {
+        let factor = 0.5 / (self.width * self.width);
+        return self.read().iter().map(|&(center, height)| {
+            -2.0 * factor * (s - center) * height * f64::exp(-factor * (s - center) * (s - center))
+        }).sum();
+    }
+
+    /// Deposit a new hill at the current value of the collective variable in
+    /// the `configuration`
+    pub fn deposit(&self, configuration: &Configuration) {
+        let s = self.cv.value(configuration);
+        let height = match self.well_tempered {
+            Some(energy) => self.height * f64::exp(-self.bias(s) / energy),
+            None => self.height,
+        };
+        self.hills.write().expect("metadynamics lock is poisonned").push((s, height));
+    }
+
+    fn read(&self) -> RwLockReadGuard<'_, Vec<(f64, f64)>> {
+        // The lock should never be poisonned, because any panic will unwind
+        // and finish the simulation.
+        self.hills.read().expect("metadynamics lock is poisonned")
+    }
+}
+
+impl GlobalPotential for Metadynamics {
+    fn cutoff(&self) -> Option<f64> {
+        None
+    }
+
+    fn energy(&self, configuration: &Configuration) -> f64 {
+        self.bias(self.cv.value(configuration))
+    }
+
+    fn forces(&self, configuration: &Configuration, forces: &mut [Vector3D]) {
+        let derivative = self.bias_derivative(self.cv.value(configuration));
+        for (force, gradient) in forces.iter_mut().zip(self.cv.gradient(configuration)) {
+            *force -= derivative * gradient;
+        }
+    }
+
+    fn atomic_virial(&self, configuration: &Configuration) -> Matrix3 {
+        let derivative = self.bias_derivative(self.cv.value(configuration));
+        let gradient = self.cv.gradient(configuration);
+        return bias_virial(configuration, &gradient, derivative);
+    }
+}
+
+impl GlobalCache for Metadynamics {
+    fn move_molecule_cost(
+        &self,
+        configuration: &Configuration,
+        molecule_id: usize,
+        new_positions: &[Vector3D],
+    ) -> f64 {
+        let old = self.energy(configuration);
+        let moved = moved_configuration(configuration, molecule_id, new_positions);
+        return self.energy(&moved) - old;
+    }
+
+    fn update(&self) {
+        // Nothing is cached
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Molecule, Particle, System, UnitCell};
+    use approx::{assert_relative_eq, assert_ulps_eq};
+
+    fn pair(distance: f64) -> System {
+        let mut system = System::with_cell(UnitCell::cubic(10.0));
+        system.add_molecule(Molecule::new(Particle::with_position("Ar", [1.0, 0.0, 0.0].into())));
+        system.add_molecule(Molecule::new(Particle::with_position("Ar", [1.0 + distance, 0.0, 0.0].into())));
+        system.add_molecule(Molecule::new(Particle::with_position("Ne", [0.0, 5.0, 0.0].into())));
+        return system;
+    }
+
+    #[test]
+    fn distance() {
+        let system = pair(3.0);
+        let cv = DistanceCV::new(0, 1);
+        assert_eq!(cv.value(&system), 3.0);
+        assert_eq!(cv.gradient(&system), vec![
+            Vector3D::new(-1.0, 0.0, 0.0), Vector3D::new(1.0, 0.0, 0.0), Vector3D::zero()
+        ]);
+
+        // Use the nearest periodic image
+        let system = pair(7.0);
+        assert_ulps_eq!(cv.value(&system), 3.0);
+        assert_eq!(cv.gradient(&system)[0], Vector3D::new(1.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn coordination() {
+        let mut system = System::with_cell(UnitCell::cubic(20.0));
+        let cluster = [[0.0, 0.0, 0.0], [2.0, 0.0, 0.0], [0.0, 2.0, 0.0], [0.0, 0.0, 2.0]];
+        for position in &cluster {
+            system.add_molecule(Molecule::new(Particle::with_position("Ar", (*position).into())));
+        }
+        system.add_molecule(Molecule::new(Particle::with_position("Ne", [12.0, 0.0, 0.0].into())));
+
+        let cv = Coordination::new(vec![4], vec![0, 1, 2, 3], 3.0);
+        let mut previous = cv.value(&system);
+        assert!(previous < 1e-2);
+        for &x in &[8.0, 6.0, 5.0, 4.0, 3.0, 2.0] {
+            system.particles_mut().position[4] = Vector3D::new(x, 0.5, 0.5);
+            let value = cv.value(&system);
+            assert!(value > previous, "{} should be larger than {}", value, previous);
+            previous = value;
+        }
+
+        // Limit at the cutoff distance
+        let cv = Coordination::new(vec![0], vec![1], 2.0);
+        assert_ulps_eq!(cv.value(&system), 0.5);
+
+        // Particles in both groups do not interact with themselves
+        let cv = Coordination::new(vec![0, 1], vec![0, 1], 2.0);
+        assert_ulps_eq!(cv.value(&system), 1.0);
+    }
+
+    #[test]
+    fn coordination_gradient() {
+        let mut system = System::with_cell(UnitCell::cubic(20.0));
+        let positions = [[0.0, 0.0, 0.0], [2.1, 0.3, 0.0], [0.2, 1.8, -0.4], [3.1, 1.2, 0.9]];
+        for position in &positions {
+            system.add_molecule(Molecule::new(Particle::with_position("Ar", (*position).into())));
+        }
+
+        let mut cv = Coordination::new(vec![3], vec![0, 1, 2], 2.5);
+        for &(n, m) in &[(6, 12), (8, 14)] {
+            cv.set_exponents(n, m);
+            let gradient = cv.gradient(&system);
+            let total = gradient.iter().fold(Vector3D::zero(), |total, &g| total + g);
+            assert_relative_eq!(total, Vector3D::zero(), epsilon = 1e-12);
+
+            let eps = 1e-6;
+            let value = cv.value(&system);
+            for i in 0..4 {
+                for axis in 0..3 {
+                    let mut moved = system.clone();
+                    moved.particles_mut().position[i][axis] += eps;
+                    let derivative = (cv.value(&moved) - value) / eps;
+                    assert_relative_eq!(derivative, gradient[i][axis], epsilon = 1e-4);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn bias_center() {
+        let system = pair(2.5);
+        let bias = HarmonicBias::new(Box::new(DistanceCV::new(0, 1)), 2.5, 10.0);
+        assert_eq!(bias.center(), 2.5);
+        assert_eq!(bias.energy(&system), 0.0);
+
+        let mut forces = vec![Vector3D::zero(); 3];
+        bias.forces(&system, &mut forces);
+        assert_eq!(forces, vec![Vector3D::zero(); 3]);
+        assert_eq!(bias.atomic_virial(&system), Matrix3::zero());
+    }
+
+    #[test]
+    fn bias_forces() {
+        let bias = HarmonicBias::new(Box::new(DistanceCV::new(0, 1)), 2.5, 10.0);
+
+        // Too far away: the bias pulls the particles together
+        let system = pair(3.0);
+        assert_relative_eq!(bias.energy(&system), 0.5 * 10.0 * 0.25, max_relative = 1e-12);
+        let mut forces = vec![Vector3D::zero(); 3];
+        bias.forces(&system, &mut forces);
+        assert_relative_eq!(forces[0], Vector3D::new(5.0, 0.0, 0.0), max_relative = 1e-12);
+        assert_relative_eq!(forces[1], Vector3D::new(-5.0, 0.0, 0.0), max_relative = 1e-12);
+        assert_eq!(forces[2], Vector3D::zero());
+
+        // Too close: the bias pushes the particles apart
+        let system = pair(2.0);
+        let mut forces = vec![Vector3D::zero(); 3];
+        bias.forces(&system, &mut forces);
+        assert_relative_eq!(forces[0], Vector3D::new(-5.0, 0.0, 0.0), max_relative = 1e-12);
+        assert_relative_eq!(forces[1], Vector3D::new(5.0, 0.0, 0.0), max_relative = 1e-12);
+
+        // The virial is the same as the one of a pair force
+        let expected = forces[0].tensorial(&system.nearest_image(0, 1));
+        assert_relative_eq!(bias.atomic_virial(&system), expected, max_relative = 1e-12);
+    }
+
+    #[test]
+    fn finite_differences() {
+        let mut system = pair(3.2);
+        system.particles_mut().position[1] = Vector3D::new(3.9, 0.7, -0.4);
+        let bias = HarmonicBias::new(Box::new(DistanceCV::new(0, 1)), 2.5, 10.0);
+
+        let mut forces = vec![Vector3D::zero(); 3];
+        bias.forces(&system, &mut forces);
+
+        let eps = 1e-6;
+        let energy = bias.energy(&system);
+        for axis in 0..3 {
+            let mut moved = system.clone();
+            moved.particles_mut().position[1][axis] += eps;
+            let derivative = (bias.energy(&moved) - energy) / eps;
+            assert_relative_eq!(-derivative, forces[1][axis], epsilon = 1e-4);
+        }
+    }
+
+    #[test]
+    fn move_molecule_cost() {
+        let system = pair(3.0);
+        let bias = HarmonicBias::new(Box::new(DistanceCV::new(0, 1)), 2.5, 10.0);
+        let cost = bias.move_molecule_cost(&system, 1, &[Vector3D::new(3.5, 0.0, 0.0)]);
+        assert_relative_eq!(cost, -0.5 * 10.0 * 0.25, max_relative = 1e-12);
+    }
+
+    #[test]
+    fn metadynamics_hills() {
+        let system = pair(3.0);
+        let metadynamics = Metadynamics::new(Box::new(DistanceCV::new(0, 1)), 0.5, 0.2, 10);
+        assert_eq!(metadynamics.every(), 10);
+        assert_eq!(metadynamics.energy(&system), 0.0);
+
+        let mut previous = 0.0;
+        for _ in 0..5 {
+            metadynamics.deposit(&system);
+            let energy = metadynamics.energy(&system);
+            assert!(energy > previous);
+            previous = energy;
+        }
+        assert_relative_eq!(previous, 5.0 * 0.5, max_relative = 1e-12);
+        assert_eq!(metadynamics.hills().len(), 5);
+
+        // Hills are shared between clones
+        let clone = metadynamics.clone();
+        clone.deposit(&pair(2.0));
+        assert_eq!(metadynamics.hills().len(), 6);
+
+        // The bias is smaller far from the hills
+        assert!(metadynamics.bias(4.0) < 0.01 * metadynamics.bias(3.0));
+    }
+
+    #[test]
+    fn well_tempered() {
+        let system = pair(3.0);
+        let mut metadynamics = Metadynamics::new(Box::new(DistanceCV::new(0, 1)), 0.01, 0.2, 10);
+        metadynamics.set_well_tempered(10.0, 300.0);
+        for _ in 0..10 {
+            metadynamics.deposit(&system);
+        }
+
+        let hills = metadynamics.hills();
+        assert_eq!(hills[0].1, 0.01);
+        for window in hills.windows(2) {
+            assert!(window[1].1 < window[0].1);
+        }
+
+        let delta_t = K_BOLTZMANN * 9.0 * 300.0;
+        let expected = 0.01 * f64::exp(-metadynamics.bias(3.0) / delta_t);
+        metadynamics.deposit(&system);
+        assert_relative_eq!(metadynamics.hills()[10].1, expected, max_relative = 1e-12);
+    }
+
+    #[test]
+    fn metadynamics_forces() {
+        let mut system = pair(3.2);
+        system.particles_mut().position[1] = Vector3D::new(3.9, 0.7, -0.4);
+        let metadynamics = Metadynamics::new(Box::new(DistanceCV::new(0, 1)), 0.5, 0.2, 10);
+        metadynamics.deposit(&pair(2.8));
+        metadynamics.deposit(&pair(3.3));
+
+        let mut forces = vec![Vector3D::zero(); 3];
+        metadynamics.forces(&system, &mut forces);
+        assert_ulps_eq!(forces[0] + forces[1], Vector3D::zero());
+        assert_eq!(forces[2], Vector3D::zero());
+
+        let eps = 1e-6;
+        let energy = metadynamics.energy(&system);
+        for axis in 0..3 {
+            let mut moved = system.clone();
+            moved.particles_mut().position[1][axis] += eps;
+            let derivative = (metadynamics.energy(&moved) - energy) / eps;
+            assert_relative_eq!(-derivative, forces[1][axis], epsilon = 1e-4);
+        }
+
+        let expected = forces[0].tensorial(&system.nearest_image(0, 1));
+        assert_relative_eq!(metadynamics.atomic_virial(&system), expected, max_relative = 1e-12);
+    }
+}
@@ -3,7 +3,7 @@
 
 use log_once::warn_once;
 
-use crate::{PairPotential, Potential};
+use crate::{PairPotential, Potential, PotentialParameters};
 use crate::math::*;
 
 /// Alternative energy and forces computation.
@@ -48,6 +48,11 @@ pub trait Computation: Sync + Send {
 
     /// Compute the force value at `r`
     fn compute_force(&self, r: f64) -> f64;
+
+    /// Get the name and the parameters of the underlying potential, if any.
+    fn parameters(&self) -> Option<PotentialParameters> {
+        None
+    }
 }
 
 impl<P: Computation + Clone + 'static> Potential for P {
@@ -60,13 +65,18 @@ impl<P: Computation + Clone + 'static> Potential for P {
     fn force(&self, r: f64) -> f64 {
         self.compute_force(r)
     }
+
+    fn parameters(&self) -> Option<PotentialParameters> {
+        Computation::parameters(self)
+    }
 }
 
 /// Computation of a potential using tabulated values.
 ///
 /// This can be faster than direct computation for smooth potentials, but will
 /// uses more memory and be less precise than direct computation. Values are
-/// tabulated in the `[0, max)` range, and a cutoff is applied after `max`.
+/// tabulated in the `[0, max)` range, and a cutoff is applied after `max`:
+/// the energy and the force are exactly zero for distances larger than `max`.
 #[derive(Clone)]
 pub struct TableComputation {
     /// Step for tabulated value. `energy_table[i]`/`force_table[i]` contains
@@ -123,6 +133,10 @@ impl TableComputation {
 impl Computation for TableComputation {
     fn compute_energy(&self, r: f64) -> f64 {
         debug_assert_eq!(self.energy_table.len(), self.force_table.len());
+        if r >= self.cutoff {
+            return 0.0;
+        }
+
         let bin = floor(r / self.delta) as usize;
         if bin < self.energy_table.len() - 1 {
             let dx = r - (bin as f64) * self.delta;
@@ -135,6 +149,10 @@ impl Computation for TableComputation {
 
     fn compute_force(&self, r: f64) -> f64 {
         debug_assert_eq!(self.energy_table.len(), self.force_table.len());
+        if r >= self.cutoff {
+            return 0.0;
+        }
+
         let bin = floor(r / self.delta) as usize;
         if bin < self.force_table.len() - 1 {
             let dx = r - (bin as f64) * self.delta;
@@ -144,6 +162,10 @@ impl Computation for TableComputation {
             return 0.0;
         }
     }
+
+    fn parameters(&self) -> Option<PotentialParameters> {
+        self.potential.parameters()
+    }
 }
 
 impl PairPotential for TableComputation {
@@ -172,6 +194,232 @@ impl PairPotential for TableComputation {
         }
         return self.potential.tail_virial(cutoff);
     }
+
+    fn du_dlambda(&self, r: f64) -> f64 {
+        if r < self.cutoff {
+            self.potential.du_dlambda(r)
+        } else {
+            0.0
+        }
+    }
+
+    fn tail_du_dlambda(&self, cutoff: f64) -> f64 {
+        self.potential.tail_du_dlambda(cutoff)
+    }
+}
+
+/// Computation of a pair potential scaled by a constant coupling parameter.
+///
+/// The energy and the force of the potential are multiplied by `lambda`, so
+/// that `lambda = 0` switches off the interaction and `lambda = 1` gives the
+/// initial potential. This can be used to couple or decouple a part of the
+/// system in free energy calculations.
+///
+/// # Examples
+///
+/// ```
+/// # use lumol_core::energy::Potential;
+/// use lumol_core::energy::ScaledComputation;
+/// use lumol_core::energy::LennardJones;
+///
+/// let potential = LennardJones { sigma: 1.0, epsilon: 2.0 };
+/// let scaled = ScaledComputation::new(Box::new(potential), 0.25);
+///
+/// assert_eq!(scaled.lambda(), 0.25);
+/// assert_eq!(scaled.energy(1.5), 0.25 * potential.energy(1.5));
+/// assert_eq!(scaled.force(1.5), 0.25 * potential.force(1.5));
+/// ```
+#[derive(Clone)]
+pub struct ScaledComputation {
+    /// Coupling parameter
+    lambda: f64,
+    /// Initial potential
+    potential: Box<dyn PairPotential>,
+}
+
+impl ScaledComputation {
+    /// Create a new `ScaledComputation` for `potential`, with the coupling
+    /// parameter `lambda`.
+    pub fn new(potential: Box<dyn PairPotential>, lambda: f64) -> ScaledComputation {
+        ScaledComputation {
+            lambda: lambda,
+            potential: potential,
+        }
+    }
+
+    /// Get the coupling parameter of this computation
+    pub fn lambda(&self) -> f64 {
+        self.lambda
+    }
+}
+
+impl Computation for ScaledComputation {
+    fn compute_energy(&self, r: f64) -> f64 {
+        self.lambda * self.potential.energy(r)
+    }
+
+    fn compute_force(&self, r: f64) -> f64 {
+        self.lambda * self.potential.force(r)
+    }
+
+    fn parameters(&self) -> Option<PotentialParameters> {
+        self.potential.parameters().map(|mut parameters| {
+            parameters.values.push(("lambda", self.lambda));
+            parameters
+        })
+    }
+}
+
+impl PairPotential for ScaledComputation {
+    fn tail_energy(&self, cutoff: f64) -> f64 {
+        self.lambda * self.potential.tail_energy(cutoff)
+    }
+
+    fn tail_virial(&self, cutoff: f64) -> f64 {
+        self.lambda * self.potential.tail_virial(cutoff)
+    }
+
+    fn du_dlambda(&self, r: f64) -> f64 {
+        self.potential.energy(r)
+    }
+
+    fn tail_du_dlambda(&self, cutoff: f64) -> f64 {
+        self.potential.tail_energy(cutoff)
+    }
+}
+
+/// Soft-core computation of a pair potential, removing the singularity at
+/// `r = 0` when the potential is partially decoupled.
+///
+/// The potential $V$ is computed at an effective distance, and scaled by the
+/// coupling parameter $\lambda$:
+///
+/// $$ V_{sc}(r) = \lambda V\left(r_{eff}\right) \qquad
+///    r_{eff} = \left(\alpha (1 - \lambda) \sigma^6 + r^6 \right)^{1/6} $$
+///
+/// where $\alpha$ controls the softness of the potential and $\sigma$ is the
+/// typical size of the interacting particles. With $\lambda = 1$, this
+/// recovers the initial potential, and with $\lambda = 0$ the interaction is
+/// switched off. For intermediate values, the energy is finite at $r = 0$.
+///
+/// The tail corrections use the initial potential scaled by $\lambda$, which
+/// is a good approximation when the cutoff is larger than $\sigma$.
+///
+/// # Examples
+///
+/// ```
+/// # use lumol_core::energy::Potential;
+/// use lumol_core::energy::SoftCore;
+/// use lumol_core::energy::LennardJones;
+///
+/// let potential = LennardJones { sigma: 1.0, epsilon: 2.0 };
+/// let softcore = SoftCore::new(Box::new(potential), 1.0, 0.5, 0.5);
+///
+/// assert_eq!(softcore.lambda(), 0.5);
+/// assert_eq!(softcore.alpha(), 0.5);
+/// assert!(softcore.energy(0.0).is_finite());
+/// ```
+#[derive(Clone)]
+pub struct SoftCore {
+    /// Coupling parameter
+    lambda: f64,
+    /// Softness parameter
+    alpha: f64,
+    /// Value of `sigma^6`
+    sigma6: f64,
+    /// Value of `alpha (1 - lambda) sigma^6`
+    shift: f64,
+    /// Initial potential
+    potential: Box<dyn PairPotential>,
+}
+
+impl SoftCore {
+    /// Create a new `SoftCore` computation for `potential`, using the
+    /// particle size `sigma`, the coupling parameter `lambda` and the
+    /// softness parameter `alpha`.
+    pub fn new(potential: Box<dyn PairPotential>, sigma: f64, lambda: f64, alpha: f64) -> SoftCore {
+        assert!((0.0..=1.0).contains(&lambda), "lambda must be between 0 and 1 in soft-core computation");
+        assert!(alpha >= 0.0, "alpha must be positive in soft-core computation");
+        let sigma6 = f64::powi(sigma, 6);
+        SoftCore {
+            lambda: lambda,
+            alpha: alpha,
+            sigma6: sigma6,
+            shift: alpha * (1.0 - lambda) * sigma6,
+            potential: potential,
+        }
+    }
+
+    /// Get the coupling parameter of this computation
+    pub fn lambda(&self) -> f64 {
+        self.lambda
+    }
+
+    /// Get the softness parameter of this computation
+    pub fn alpha(&self) -> f64 {
+        self.alpha
+    }
+
+    /// Get the effective distance corresponding to `r`
+    #[inline]
+    fn effective_distance(&self, r: f64) -> f64 {
+        if self.shift == 0.0 {
+            return r;
+        }
+        f64::powf(self.shift + f64::powi(r, 6), 1.0 / 6.0)
+    }
+}
+
+impl Computation for SoftCore {
+    fn compute_energy(&self, r: f64) -> f64 {
+        if self.lambda == 0.0 {
+            return 0.0;
+        }
+        self.lambda * self.potential.energy(self.effective_distance(r))
+    }
+
+    fn compute_force(&self, r: f64) -> f64 {
+        if self.lambda == 0.0 {
+            return 0.0;
+        }
+        // dr_eff / dr = r^5 / r_eff^5
+        let r_eff = self.effective_distance(r);
+        self.lambda * self.potential.force(r_eff) * f64::powi(r / r_eff, 5)
+    }
+
+    fn parameters(&self) -> Option<PotentialParameters> {
+        self.potential.parameters().map(|mut parameters| {
+            parameters.values.push(("lambda", self.lambda));
+            parameters.values.push(("alpha", self.alpha));
+            parameters
+        })
+    }
+}
+
+impl PairPotential for SoftCore {
+    fn tail_energy(&self, cutoff: f64) -> f64 {
+        self.lambda * self.potential.tail_energy(cutoff)
+    }
+
+    fn tail_virial(&self, cutoff: f64) -> f64 {
+        self.lambda * self.potential.tail_virial(cutoff)
+    }
+
+    fn du_dlambda(&self, r: f64) -> f64 {
+        // dU/dlambda = V(r_eff) + lambda dV/dr_eff dr_eff/dlambda, with
+        // dr_eff/dlambda = - alpha sigma^6 / (6 r_eff^5)
+        let r_eff = self.effective_distance(r);
+        let shift_derivative = self.alpha * self.sigma6 / (6.0 * f64::powi(r_eff, 5));
+        let mut derivative = self.potential.energy(r_eff);
+        if self.lambda != 0.0 {
+            derivative += self.lambda * self.potential.force(r_eff) * shift_derivative;
+        }
+        return derivative;
+    }
+
+    fn tail_du_dlambda(&self, cutoff: f64) -> f64 {
+        self.potential.tail_energy(cutoff)
+    }
 }
 
 #[cfg(test)]
@@ -179,6 +427,7 @@ mod test {
     use super::*;
     use crate::{Harmonic, LennardJones};
     use crate::PairPotential;
+    use approx::assert_relative_eq;
 
     #[test]
     fn table() {
@@ -201,6 +450,12 @@ mod test {
         assert_eq!(table.compute_energy(4.1), 0.0);
         assert_eq!(table.compute_force(4.1), 0.0);
 
+        let after = f64::from_bits(4.0f64.to_bits() + 1);
+        assert_eq!(table.compute_energy(after), 0.0);
+        assert_eq!(table.compute_force(after), 0.0);
+        assert_eq!(table.compute_energy(1e300), 0.0);
+        assert_eq!(table.compute_force(f64::INFINITY), 0.0);
+
 
         let lj = LennardJones {
             epsilon: 50.0,
@@ -210,4 +465,111 @@ mod test {
         assert_eq!(table.tail_energy(5.0), lj.tail_energy(5.0));
         assert_eq!(table.tail_virial(5.0), lj.tail_virial(5.0));
     }
+
+    #[test]
+    fn scaled() {
+        let lj = LennardJones {
+            epsilon: 50.0,
+            sigma: 2.0,
+        };
+
+        let scaled = ScaledComputation::new(Box::new(lj), 0.0);
+        for &r in &[1.8, 2.5, 4.0] {
+            assert_eq!(scaled.compute_energy(r), 0.0);
+            assert_eq!(scaled.compute_force(r), 0.0);
+        }
+        assert_eq!(scaled.tail_energy(5.0), 0.0);
+        assert_eq!(scaled.tail_virial(5.0), 0.0);
+
+        let scaled = ScaledComputation::new(Box::new(lj), 1.0);
+        for &r in &[1.8, 2.5, 4.0] {
+            assert_eq!(scaled.compute_energy(r), lj.energy(r));
+            assert_eq!(scaled.compute_force(r), lj.force(r));
+        }
+        assert_eq!(scaled.tail_energy(5.0), lj.tail_energy(5.0));
+        assert_eq!(scaled.tail_virial(5.0), lj.tail_virial(5.0));
+
+        let scaled = ScaledComputation::new(Box::new(lj), 0.4);
+        assert_eq!(scaled.compute_energy(2.5), 0.4 * lj.energy(2.5));
+        assert_eq!(scaled.compute_force(2.5), 0.4 * lj.force(2.5));
+        assert_eq!(scaled.tail_energy(5.0), 0.4 * lj.tail_energy(5.0));
+
+        let parameters = Computation::parameters(&scaled).unwrap();
+        assert_eq!(parameters.name, "lj");
+        assert_eq!(parameters.values, vec![("sigma", 2.0), ("epsilon", 50.0), ("lambda", 0.4)]);
+    }
+
+    #[test]
+    fn softcore() {
+        let lj = LennardJones {
+            epsilon: 50.0,
+            sigma: 2.0,
+        };
+
+        let softcore = SoftCore::new(Box::new(lj), 2.0, 1.0, 0.5);
+        for &r in &[1.8, 2.5, 4.0] {
+            assert_eq!(softcore.compute_energy(r), lj.energy(r));
+            assert_eq!(softcore.compute_force(r), lj.force(r));
+        }
+        assert_eq!(softcore.tail_energy(5.0), lj.tail_energy(5.0));
+        assert_eq!(softcore.tail_virial(5.0), lj.tail_virial(5.0));
+
+        let softcore = SoftCore::new(Box::new(lj), 2.0, 0.0, 0.5);
+        assert_eq!(softcore.compute_energy(0.0), 0.0);
+        assert_eq!(softcore.compute_force(1.5), 0.0);
+
+        for &lambda in &[0.1, 0.5, 0.9] {
+            let softcore = SoftCore::new(Box::new(lj), 2.0, lambda, 0.5);
+            assert!(softcore.compute_energy(0.0).is_finite());
+            assert_eq!(softcore.compute_force(0.0), 0.0);
+
+            for &r in &[0.5, 1.8, 2.5] {
+                let e0 = softcore.compute_energy(r);
+                let e1 = softcore.compute_energy(r + 1e-6);
+                assert_relative_eq!((e0 - e1) / 1e-6, softcore.compute_force(r), epsilon = 1e-3, max_relative = 1e-4);
+            }
+        }
+
+        let softcore = SoftCore::new(Box::new(lj), 2.0, 0.5, 0.5);
+        let parameters = Computation::parameters(&softcore).unwrap();
+        assert_eq!(parameters.values, vec![("sigma", 2.0), ("epsilon", 50.0), ("lambda", 0.5), ("alpha", 0.5)]);
+    }
+
+    #[test]
+    fn du_dlambda() {
+        let lj = LennardJones {
+            epsilon: 50.0,
+            sigma: 2.0,
+        };
+        assert_eq!(lj.du_dlambda(2.5), 0.0);
+        assert_eq!(lj.tail_du_dlambda(5.0), 0.0);
+
+        let scaled = ScaledComputation::new(Box::new(lj), 0.4);
+        for &r in &[1.8, 2.5, 4.0] {
+            assert_eq!(scaled.du_dlambda(r), lj.energy(r));
+        }
+        assert_eq!(scaled.tail_du_dlambda(5.0), lj.tail_energy(5.0));
+
+        let table = TableComputation::new(Box::new(scaled), 1000, 4.0);
+        assert_eq!(table.du_dlambda(2.5), lj.energy(2.5));
+        assert_eq!(table.du_dlambda(4.5), 0.0);
+
+        for &lambda in &[0.0, 0.3, 0.8] {
+            let softcore = SoftCore::new(Box::new(lj), 2.0, lambda, 0.5);
+            assert_eq!(softcore.tail_du_dlambda(5.0), lj.tail_energy(5.0));
+
+            let delta = 1e-6;
+            let next = SoftCore::new(Box::new(lj), 2.0, lambda + delta, 0.5);
+            for &r in &[0.0, 1.8, 2.5] {
+                let finite_difference = (next.compute_energy(r) - softcore.compute_energy(r)) / delta;
+                assert_relative_eq!(softcore.du_dlambda(r), finite_difference, epsilon = 1e-3, max_relative = 1e-4);
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "lambda must be between 0 and 1 in soft-core computation")]
+    fn softcore_lambda() {
+        let _ = SoftCore::new(Box::new(LennardJones { epsilon: 1.0, sigma: 1.0 }), 1.0, 1.5, 0.5);
+    }
 }
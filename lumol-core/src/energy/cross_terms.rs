@@ -0,0 +1,122 @@
+// Lumol, an extensible molecular simulation engine
+// Copyright (C) Lumol's contributors — BSD license
+
+//! Cross terms potentials, coupling the internal coordinates of bonded
+//! particles, as used in class II force fields.
+
+/// A cross term potential acts on the `i-j-k` angles formed by bonded
+/// particles, and couples the two bond lengths `r_ij` and `r_kj` with the
+/// `i-j-k` angle.
+///
+/// Class II force fields such as COMPASS or CFF use these terms in addition
+/// to the usual bonds and angles potentials to reproduce vibrational spectra.
+///
+/// # Example
+///
+/// ```
+/// use lumol_core::energy::CrossTermPotential;
+///
+/// /// A cross term proportional to the product of the bond lengths
+/// #[derive(Clone)]
+/// struct Product(f64);
+///
+/// impl CrossTermPotential for Product {
+///     fn energy(&self, r_ij: f64, r_kj: f64, _theta: f64) -> f64 {
+///         self.0 * r_ij * r_kj
+///     }
+///
+///     fn forces(&self, r_ij: f64, r_kj: f64, _theta: f64) -> (f64, f64, f64) {
+///         (-self.0 * r_kj, -self.0 * r_ij, 0.0)
+///     }
+/// }
+///
+/// let potential = Product(2.0);
+/// assert_eq!(potential.energy(1.0, 1.5, 2.0), 3.0);
+/// assert_eq!(potential.forces(1.0, 1.5, 2.0), (-3.0, -2.0, 0.0));
+/// ```
+pub trait CrossTermPotential: Sync + Send + BoxCloneCrossTerm {
+    /// Get the energy of the `i-j-k` angle, where the bond lengths are
+    /// `r_ij` and `r_kj` and the angle is `theta`.
+    fn energy(&self, r_ij: f64, r_kj: f64, theta: f64) -> f64;
+
+    /// Get the opposite of the derivatives of the energy with respect to the
+    /// bond lengths `r_ij` and `r_kj` and the angle `theta`, in this order.
+    fn forces(&self, r_ij: f64, r_kj: f64, theta: f64) -> (f64, f64, f64);
+}
+impl_box_clone!(CrossTermPotential, BoxCloneCrossTerm, box_clone_cross_term);
+
+/// Bond-angle cross term potential.
+///
+/// This potential couples the stretching of the two bonds with the bending
+/// of the angle in an `i-j-k` triplet:
+///
+/// $$ V(r_{ij}, r_{kj}, \theta) = (\theta - \theta_0)
+///    \left[k_1 (r_{ij} - r_1) + k_2 (r_{kj} - r_2)\right] $$
+///
+/// The potential is zero at the reference geometry, and when only one of the
+/// coordinates (the bonds or the angle) deviates from its reference value.
+///
+/// # Examples
+///
+/// ```
+/// use lumol_core::energy::{CrossTermPotential, BondAngle};
+///
+/// let potential = BondAngle { k1: 10.0, k2: 20.0, r1: 1.0, r2: 1.5, theta0: 2.0 };
+/// assert_eq!(potential.energy(1.0, 1.5, 2.2), 0.0);
+/// assert_eq!(potential.energy(1.1, 1.5, 2.0), 0.0);
+///
+/// assert!(f64::abs(potential.energy(1.1, 1.5, 2.5) - 0.5) < 1e-12);
+/// ```
+#[derive(Clone, Copy)]
+pub struct BondAngle {
+    /// Coupling constant for the `i-j` bond
+    pub k1: f64,
+    /// Coupling constant for the `k-j` bond
+    pub k2: f64,
+    /// Reference length of the `i-j` bond
+    pub r1: f64,
+    /// Reference length of the `k-j` bond
+    pub r2: f64,
+    /// Reference angle
+    pub theta0: f64,
+}
+
+impl CrossTermPotential for BondAngle {
+    fn energy(&self, r_ij: f64, r_kj: f64, theta: f64) -> f64 {
+        let stretch = self.k1 * (r_ij - self.r1) + self.k2 * (r_kj - self.r2);
+        return (theta - self.theta0) * stretch;
+    }
+
+    fn forces(&self, r_ij: f64, r_kj: f64, theta: f64) -> (f64, f64, f64) {
+        let bend = theta - self.theta0;
+        let stretch = self.k1 * (r_ij - self.r1) + self.k2 * (r_kj - self.r2);
+        return (-self.k1 * bend, -self.k2 * bend, -stretch);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    const EPS: f64 = 1e-7;
+
+    #[test]
+    fn bond_angle() {
+        let potential = BondAngle { k1: 10.0, k2: 20.0, r1: 1.0, r2: 1.5, theta0: 2.0 };
+
+        assert_eq!(potential.energy(1.0, 1.5, 2.0), 0.0);
+        assert_eq!(potential.forces(1.0, 1.5, 2.0), (0.0, 0.0, 0.0));
+
+        assert_eq!(potential.energy(1.2, 1.6, 2.0), 0.0);
+        assert_eq!(potential.energy(1.0, 1.5, 1.5), 0.0);
+        assert_relative_eq!(potential.energy(1.2, 1.6, 1.5), -0.5 * (10.0 * 0.2 + 20.0 * 0.1), max_relative = 1e-12);
+
+        let (r_ij, r_kj, theta) = (1.2, 1.4, 2.3);
+        let energy = potential.energy(r_ij, r_kj, theta);
+        let (f_ij, f_kj, f_theta) = potential.forces(r_ij, r_kj, theta);
+        assert_relative_eq!((energy - potential.energy(r_ij + EPS, r_kj, theta)) / EPS, f_ij, max_relative = 1e-6);
+        assert_relative_eq!((energy - potential.energy(r_ij, r_kj + EPS, theta)) / EPS, f_kj, max_relative = 1e-6);
+        assert_relative_eq!((energy - potential.energy(r_ij, r_kj, theta + EPS)) / EPS, f_theta, max_relative = 1e-6);
+    }
+}
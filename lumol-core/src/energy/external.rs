@@ -0,0 +1,256 @@
+// Lumol, an extensible molecular simulation engine
+// Copyright (C) Lumol's contributors — BSD license
+
+//! External potentials, acting independently on each particle.
+use crate::Configuration;
+use crate::Vector3D;
+
+/// An external potential acts on each particle independently of the other
+/// particles, for example to restrain particles at given positions or to
+/// apply an external field.
+///
+/// External forces do not derive from interactions between particles, and
+/// are not included in the virial.
+///
+/// # Example
+///
+/// ```
+/// use lumol_core::energy::ExternalPotential;
+/// use lumol_core::{Configuration, Molecule, Particle, System, Vector3D};
+///
+/// /// A constant force along z, like gravity
+/// #[derive(Clone)]
+/// struct Gravity(f64);
+///
+/// impl ExternalPotential for Gravity {
+///     fn energy(&self, configuration: &Configuration, i: usize, position: &Vector3D) -> f64 {
+///         configuration.particles().mass[i] * self.0 * position[2]
+///     }
+///
+///     fn force(&self, configuration: &Configuration, i: usize, _: &Vector3D) -> Vector3D {
+///         Vector3D::new(0.0, 0.0, -configuration.particles().mass[i] * self.0)
+///     }
+/// }
+///
+/// let mut system = System::new();
+/// system.add_molecule(Molecule::new(Particle::new("Ar")));
+///
+/// let gravity = Gravity(1e-3);
+/// let force = gravity.force(&system, 0, &Vector3D::new(0.0, 0.0, 2.0));
+/// assert!(force[2] < 0.0);
+/// ```
+pub trait ExternalPotential: Sync + Send + BoxCloneExternal {
+    /// Get the energy of the particle at index `i` in the `configuration` if
+    /// it was at the given `position`.
+    fn energy(&self, configuration: &Configuration, i: usize, position: &Vector3D) -> f64;
+
+    /// Get the force acting on the particle at index `i` in the
+    /// `configuration` if it was at the given `position`.
+    fn force(&self, configuration: &Configuration, i: usize, position: &Vector3D) -> Vector3D;
+}
+
+impl_box_clone!(ExternalPotential, BoxCloneExternal, box_clone_external);
+
+/// Harmonic restraint tethering a single particle to a reference position.
+///
+/// The energy of the restrained particle is
+///
+/// $$ V(\vec r) = \frac 12 k \left|\vec r - \vec r_{ref} \right|^2 $$
+///
+/// where the distance to the reference position uses the nearest periodic
+/// image. All other particles are not affected by this potential.
+///
+/// # Examples
+///
+/// ```
+/// # use lumol_core::energy::{ExternalPotential, PositionRestraint};
+/// # use lumol_core::{Molecule, Particle, System, Vector3D};
+/// let mut system = System::new();
+/// system.add_molecule(Molecule::new(Particle::new("Ar")));
+///
+/// let restraint = PositionRestraint::new(0, Vector3D::new(1.0, 0.0, 0.0), 2.0);
+/// let position = Vector3D::new(1.0, 0.0, 3.0);
+/// assert_eq!(restraint.energy(&system, 0, &position), 9.0);
+/// assert_eq!(restraint.force(&system, 0, &position), Vector3D::new(0.0, 0.0, -6.0));
+/// ```
+#[derive(Clone)]
+pub struct PositionRestraint {
+    /// Index of the restrained particle
+    index: usize,
+    /// Reference position of the particle
+    reference: Vector3D,
+    /// Spring constant
+    k: f64,
+}
+
+impl PositionRestraint {
+    /// Create a new `PositionRestraint` for the particle at `index`, using
+    /// the given `reference` position and spring constant `k`.
+    pub fn new(index: usize, reference: Vector3D, k: f64) -> PositionRestraint {
+        assert!(k >= 0.0, "The spring constant must be positive in position restraint");
+        PositionRestraint {
+            index: index,
+            reference: reference,
+            k: k,
+        }
+    }
+
+    /// Get the index of the restrained particle
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Get the reference position of the restrained particle
+    pub fn reference(&self) -> Vector3D {
+        self.reference
+    }
+
+    /// Get the displacement of `position` from the reference position
+    fn displacement(&self, configuration: &Configuration, position: &Vector3D) -> Vector3D {
+        let mut delta = position - self.reference;
+        configuration.cell.vector_image(&mut delta);
+        return delta;
+    }
+}
+
+impl ExternalPotential for PositionRestraint {
+    fn energy(&self, configuration: &Configuration, i: usize, position: &Vector3D) -> f64 {
+        if i != self.index {
+            return 0.0;
+        }
+        let delta = self.displacement(configuration, position);
+        return 0.5 * self.k * delta.norm2();
+    }
+
+    fn force(&self, configuration: &Configuration, i: usize, position: &Vector3D) -> Vector3D {
+        if i != self.index {
+            return Vector3D::zero();
+        }
+        return -self.k * self.displacement(configuration, position);
+    }
+}
+
+/// Uniform external electric field, acting on all the charged particles.
+///
+/// The force acting on a particle with charge $q$ is $\vec f = q \vec E$,
+/// and the associated energy is $V(\vec r) = - q \vec E \cdot \vec r$. This
+/// energy is not periodic, and depends on the absolute positions of the
+/// particles.
+///
+/// The field is expressed in units of energy per length per elementary
+/// charge, so that a field of 1 V/A is `units::from(1.0, "eV/A")`.
+///
+/// # Examples
+///
+/// ```
+/// # use lumol_core::energy::{ElectricField, ExternalPotential};
+/// # use lumol_core::{Molecule, Particle, System, Vector3D};
+/// let mut system = System::new();
+/// let mut particle = Particle::new("Na");
+/// particle.charge = 2.0;
+/// system.add_molecule(Molecule::new(particle));
+///
+/// let field = ElectricField::new(Vector3D::new(0.0, 0.5, 0.0));
+/// let position = Vector3D::new(1.0, 3.0, 0.0);
+/// assert_eq!(field.energy(&system, 0, &position), -3.0);
+/// assert_eq!(field.force(&system, 0, &position), Vector3D::new(0.0, 1.0, 0.0));
+/// ```
+#[derive(Clone)]
+pub struct ElectricField {
+    /// The field vector
+    field: Vector3D,
+}
+
+impl ElectricField {
+    /// Create a new uniform `ElectricField` with the given `field` vector
+    pub fn new(field: Vector3D) -> ElectricField {
+        ElectricField {
+            field: field,
+        }
+    }
+
+    /// Get the field vector
+    pub fn field(&self) -> Vector3D {
+        self.field
+    }
+}
+
+impl ExternalPotential for ElectricField {
+    fn energy(&self, configuration: &Configuration, i: usize, position: &Vector3D) -> f64 {
+        let charge = configuration.particles().charge[i];
+        if charge == 0.0 {
+            return 0.0;
+        }
+        return -charge * (self.field * position);
+    }
+
+    fn force(&self, configuration: &Configuration, i: usize, _: &Vector3D) -> Vector3D {
+        let charge = configuration.particles().charge[i];
+        if charge == 0.0 {
+            return Vector3D::zero();
+        }
+        return charge * self.field;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Molecule, Particle, System, UnitCell};
+    use approx::assert_ulps_eq;
+
+    #[test]
+    fn position_restraint() {
+        let mut system = System::with_cell(UnitCell::cubic(10.0));
+        system.add_molecule(Molecule::new(Particle::new("Ar")));
+        system.add_molecule(Molecule::new(Particle::new("Ar")));
+        let configuration: &Configuration = &system;
+
+        let reference = Vector3D::new(1.0, 2.0, 3.0);
+        let restraint = PositionRestraint::new(1, reference, 4.0);
+        assert_eq!(restraint.index(), 1);
+        assert_eq!(restraint.reference(), reference);
+
+        assert_eq!(restraint.energy(configuration, 1, &reference), 0.0);
+        assert_eq!(restraint.force(configuration, 1, &reference), Vector3D::zero());
+
+        let position = Vector3D::new(1.5, 2.0, 3.0);
+        assert_ulps_eq!(restraint.energy(configuration, 1, &position), 0.5);
+        assert_ulps_eq!(restraint.force(configuration, 1, &position), Vector3D::new(-2.0, 0.0, 0.0));
+
+        // Other particles are not affected
+        assert_eq!(restraint.energy(configuration, 0, &position), 0.0);
+        assert_eq!(restraint.force(configuration, 0, &position), Vector3D::zero());
+
+        // Use the nearest periodic image of the reference
+        let position = Vector3D::new(1.0, 2.0, 12.5);
+        assert_ulps_eq!(restraint.energy(configuration, 1, &position), 0.5);
+        assert_ulps_eq!(restraint.force(configuration, 1, &position), Vector3D::new(0.0, 0.0, 2.0));
+    }
+
+    #[test]
+    fn electric_field() {
+        let mut system = System::new();
+        system.add_molecule(Molecule::new(Particle::new("Na")));
+        system.add_molecule(Molecule::new(Particle::new("Cl")));
+        system.add_molecule(Molecule::new(Particle::new("Ar")));
+        system.particles_mut().charge[0] = 1.0;
+        system.particles_mut().charge[1] = -1.0;
+        let configuration: &Configuration = &system;
+
+        let field = ElectricField::new(Vector3D::new(0.2, 0.0, -0.1));
+        assert_eq!(field.field(), Vector3D::new(0.2, 0.0, -0.1));
+
+        let position = Vector3D::new(1.0, 4.0, 2.0);
+        assert_ulps_eq!(field.energy(configuration, 0, &position), 0.0);
+        assert_ulps_eq!(field.force(configuration, 0, &position), Vector3D::new(0.2, 0.0, -0.1));
+
+        let position = Vector3D::new(2.0, 4.0, 2.0);
+        assert_ulps_eq!(field.energy(configuration, 1, &position), 0.2);
+        assert_ulps_eq!(field.force(configuration, 1, &position), Vector3D::new(-0.2, 0.0, 0.1));
+
+        // Neutral particles are not affected
+        assert_eq!(field.energy(configuration, 2, &position), 0.0);
+        assert_eq!(field.force(configuration, 2, &position), Vector3D::zero());
+    }
+}
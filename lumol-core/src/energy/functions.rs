@@ -1,10 +1,11 @@
 // Lumol, an extensible molecular simulation engine
 // Copyright (C) Lumol's contributors — BSD license
 
-use crate::{AnglePotential, BondPotential, DihedralPotential, PairPotential};
-use crate::Potential;
+use crate::{AnglePotential, BondPotential, DihedralPotential, ImproperPotential, PairPotential};
+use crate::{Potential, PotentialParameters};
 use crate::math::*;
 use std::f64::consts::PI;
+use std::ops::Deref;
 
 /// No-op potential.
 ///
@@ -33,6 +34,13 @@ impl Potential for NullPotential {
     fn force(&self, _: f64) -> f64 {
         0.0
     }
+
+    fn parameters(&self) -> Option<PotentialParameters> {
+        Some(PotentialParameters {
+            name: "null",
+            values: vec![],
+        })
+    }
 }
 
 impl PairPotential for NullPotential {
@@ -46,6 +54,7 @@ impl PairPotential for NullPotential {
 impl BondPotential for NullPotential {}
 impl AnglePotential for NullPotential {}
 impl DihedralPotential for NullPotential {}
+impl ImproperPotential for NullPotential {}
 
 /// Lennard-Jones potential.
 ///
@@ -84,6 +93,13 @@ impl Potential for LennardJones {
         let s6 = f64::powi(self.sigma / r, 6);
         -24.0 * self.epsilon * (s6 - 2.0 * f64::powi(s6, 2)) / r
     }
+
+    fn parameters(&self) -> Option<PotentialParameters> {
+        Some(PotentialParameters {
+            name: "lj",
+            values: vec![("sigma", self.sigma), ("epsilon", self.epsilon)],
+        })
+    }
 }
 
 impl PairPotential for LennardJones {
@@ -104,6 +120,149 @@ impl PairPotential for LennardJones {
     }
 }
 
+/// Weeks-Chandler-Andersen potential.
+///
+/// This is the repulsive part of the Lennard-Jones potential, cut at the
+/// minimum of the potential and shifted to be continuous at this point:
+///
+/// $$ V(r) = 4 * \epsilon * \left[ \left(\frac \sigma r \right)^{12} -
+///    \left(\frac \sigma r \right)^6 \right] + \epsilon $$
+///
+/// for $r < 2^{1/6} \sigma$, and $V(r) = 0$ otherwise. $\sigma$ is the
+/// distance constant, and $\epsilon$ the energetic constant.
+///
+/// # Examples
+///
+/// ```
+/// # use lumol_core::energy::Potential;
+/// # use lumol_core::energy::WCA;
+/// let potential = WCA { sigma: 2.0, epsilon: 10.0 };
+/// assert_eq!(potential.energy(2.0), 10.0);
+/// assert_eq!(potential.energy(3.0), 0.0);
+///
+/// assert_eq!(potential.force(2.0), 120.0);
+/// assert_eq!(potential.force(3.0), 0.0);
+/// ```
+#[derive(Clone, Copy)]
+pub struct WCA {
+    /// Distance constant of the WCA potential
+    pub sigma: f64,
+    /// Energy constant of the WCA potential
+    pub epsilon: f64,
+}
+
+impl WCA {
+    /// Get the distance at which this potential goes to zero, `2^(1/6) sigma`
+    pub fn cutoff(&self) -> f64 {
+        f64::powf(2.0, 1.0 / 6.0) * self.sigma
+    }
+}
+
+impl Potential for WCA {
+    fn energy(&self, r: f64) -> f64 {
+        if r >= self.cutoff() {
+            return 0.0;
+        }
+        let s6 = f64::powi(self.sigma / r, 6);
+        4.0 * self.epsilon * (f64::powi(s6, 2) - s6) + self.epsilon
+    }
+
+    fn force(&self, r: f64) -> f64 {
+        if r >= self.cutoff() {
+            return 0.0;
+        }
+        let s6 = f64::powi(self.sigma / r, 6);
+        -24.0 * self.epsilon * (s6 - 2.0 * f64::powi(s6, 2)) / r
+    }
+
+    fn parameters(&self) -> Option<PotentialParameters> {
+        Some(PotentialParameters {
+            name: "wca",
+            values: vec![("sigma", self.sigma), ("epsilon", self.epsilon)],
+        })
+    }
+}
+
+impl PairPotential for WCA {
+    // The potential is exactly zero after its own cutoff, and the interaction
+    // cutoff should always be larger than this value.
+    fn tail_energy(&self, _: f64) -> f64 {
+        0.0
+    }
+
+    fn tail_virial(&self, _: f64) -> f64 {
+        0.0
+    }
+}
+
+/// 12-6-4 Lennard-Jones potential.
+///
+/// This potential adds a charge-induced dipole term to the Lennard-Jones
+/// potential, and is used to model metal ions [Li2014]:
+///
+/// $$ V(r) = 4 * \epsilon * \left[ \left(\frac \sigma r \right)^{12} -
+///    \left(\frac \sigma r \right)^6 \right] - \frac{C_4}{r^4} $$
+///
+/// where $\sigma$ is the Lennard-Jones distance constant, $\epsilon$ the
+/// energetic constant, and $C_4$ the strength of the $r^{-4}$ term.
+///
+/// # Examples
+///
+/// ```
+/// # use lumol_core::energy::Potential;
+/// # use lumol_core::energy::LennardJones124;
+/// let potential = LennardJones124 { sigma: 2.0, epsilon: 10.0, c4: 32.0 };
+/// assert_eq!(potential.energy(2.0), -2.0);
+/// assert_eq!(potential.force(2.0), 116.0);
+/// ```
+///
+/// [Li2014]: Li, P. and Merz, K. M., J. Chem. Theory Comput. 10, 289 (2014).
+#[derive(Clone, Copy)]
+pub struct LennardJones124 {
+    /// Distance constant of the Lennard-Jones potential
+    pub sigma: f64,
+    /// Energy constant of the Lennard-Jones potential
+    pub epsilon: f64,
+    /// Strength of the charge-induced dipole term
+    pub c4: f64,
+}
+
+impl LennardJones124 {
+    fn lennard_jones(&self) -> LennardJones {
+        LennardJones {
+            sigma: self.sigma,
+            epsilon: self.epsilon,
+        }
+    }
+}
+
+impl Potential for LennardJones124 {
+    fn energy(&self, r: f64) -> f64 {
+        self.lennard_jones().energy(r) - self.c4 / f64::powi(r, 4)
+    }
+
+    fn force(&self, r: f64) -> f64 {
+        self.lennard_jones().force(r) - 4.0 * self.c4 / f64::powi(r, 5)
+    }
+
+    fn parameters(&self) -> Option<PotentialParameters> {
+        Some(PotentialParameters {
+            name: "lennard-jones-124",
+            values: vec![("sigma", self.sigma), ("epsilon", self.epsilon), ("c4", self.c4)],
+        })
+    }
+}
+
+impl PairPotential for LennardJones124 {
+    fn tail_energy(&self, cutoff: f64) -> f64 {
+        self.lennard_jones().tail_energy(cutoff) - self.c4 / cutoff
+    }
+
+    fn tail_virial(&self, cutoff: f64) -> f64 {
+        self.lennard_jones().tail_virial(cutoff) - 4.0 * self.c4 / cutoff
+    }
+}
+
 /// Harmonic potential.
 ///
 /// $$ V(x) = \frac{1}{2} k (x - x_0)^2 $$
@@ -139,6 +298,13 @@ impl Potential for Harmonic {
     fn force(&self, x: f64) -> f64 {
         self.k * (self.x0 - x)
     }
+
+    fn parameters(&self) -> Option<PotentialParameters> {
+        Some(PotentialParameters {
+            name: "harmonic",
+            values: vec![("k", self.k), ("x0", self.x0)],
+        })
+    }
 }
 
 impl PairPotential for Harmonic {
@@ -156,6 +322,63 @@ impl PairPotential for Harmonic {
 impl BondPotential for Harmonic {}
 impl AnglePotential for Harmonic {}
 impl DihedralPotential for Harmonic {}
+impl ImproperPotential for Harmonic {}
+
+/// Quartic anharmonic potential.
+///
+/// This potential is intended for use with bonds, to include anharmonic
+/// corrections to the harmonic potential near the equilibrium value.
+///
+/// $$ V(x) = \frac{1}{2} k_2 (x - x_0)^2 + k_3 (x - x_0)^3 + k_4 (x - x_0)^4 $$
+///
+/// where $x_0$ is the equilibrium value, and $k_2$, $k_3$ and $k_4$ are the
+/// force constants.
+///
+/// # Examples
+///
+/// ```
+/// # use lumol_core::energy::Potential;
+/// # use lumol_core::energy::Quartic;
+/// let potential = Quartic { k2: 100.0, k3: -20.0, k4: 5.0, x0: 2.0 };
+/// assert_eq!(potential.energy(2.0), 0.0);
+/// assert_eq!(potential.energy(3.0), 35.0);
+///
+/// assert_eq!(potential.force(2.0), 0.0);
+/// assert_eq!(potential.force(3.0), -60.0);
+/// ```
+#[derive(Clone, Copy)]
+pub struct Quartic {
+    /// Harmonic force constant
+    pub k2: f64,
+    /// Cubic force constant
+    pub k3: f64,
+    /// Quartic force constant
+    pub k4: f64,
+    /// Equilibrium value
+    pub x0: f64,
+}
+
+impl Potential for Quartic {
+    fn energy(&self, x: f64) -> f64 {
+        let dx = x - self.x0;
+        0.5 * self.k2 * dx * dx + self.k3 * dx * dx * dx + self.k4 * dx * dx * dx * dx
+    }
+
+    fn force(&self, x: f64) -> f64 {
+        let dx = x - self.x0;
+        let dx2 = dx * dx;
+        -(self.k2 * dx + 3.0 * self.k3 * dx2 + 4.0 * self.k4 * dx2 * dx)
+    }
+
+    fn parameters(&self) -> Option<PotentialParameters> {
+        Some(PotentialParameters {
+            name: "quartic",
+            values: vec![("k2", self.k2), ("k3", self.k3), ("k4", self.k4), ("x0", self.x0)],
+        })
+    }
+}
+
+impl BondPotential for Quartic {}
 
 /// Cosine harmonic potential.
 ///
@@ -202,6 +425,13 @@ impl Potential for CosineHarmonic {
     fn force(&self, x: f64) -> f64 {
         self.k * (cos(x) - self.cos_x0) * sin(x)
     }
+
+    fn parameters(&self) -> Option<PotentialParameters> {
+        Some(PotentialParameters {
+            name: "cosine-harmonic",
+            values: vec![("k", self.k), ("x0", acos(self.cos_x0))],
+        })
+    }
 }
 
 impl AnglePotential for CosineHarmonic {}
@@ -251,10 +481,81 @@ impl Potential for Torsion {
         let sin = sin(n * phi - self.delta);
         self.k * n * sin
     }
+
+    fn parameters(&self) -> Option<PotentialParameters> {
+        Some(PotentialParameters {
+            name: "torsion",
+            values: vec![("k", self.k), ("delta", self.delta), ("n", self.n as f64)],
+        })
+    }
 }
 
 impl DihedralPotential for Torsion {}
 
+/// Ryckaert-Bellemans potential.
+///
+/// This potential is intended for use with dihedral angles, and is the
+/// potential used by the OPLS force field in GROMACS.
+///
+/// $$ V(\phi) = \sum_{n=0}^{5} C_n \cos^n(\phi - \pi) $$
+///
+/// where the $C_n$ are energetic constants. The $\phi - \pi$ shift comes
+/// from the polymer convention used by the original authors, where the trans
+/// conformation corresponds to an angle of 0.
+///
+/// # Examples
+///
+/// ```
+/// # use lumol_core::energy::Potential;
+/// # use lumol_core::energy::RyckaertBellemans;
+/// # use std::f64::consts::PI;
+/// let potential = RyckaertBellemans { c: [9.28, 12.16, -13.12, -3.06, 26.24, 0.0] };
+/// assert!((potential.energy(PI / 2.0) - 9.28).abs() < 1e-12);
+///
+/// assert!(potential.force(PI).abs() < 1e-12);
+/// ```
+#[derive(Clone, Copy)]
+pub struct RyckaertBellemans {
+    /// The energetic constants $C_0$ to $C_5$
+    pub c: [f64; 6],
+}
+
+impl Potential for RyckaertBellemans {
+    fn energy(&self, phi: f64) -> f64 {
+        let cos_psi = -cos(phi);
+        let mut energy = 0.0;
+        let mut power = 1.0;
+        for &c in &self.c {
+            energy += c * power;
+            power *= cos_psi;
+        }
+        return energy;
+    }
+
+    fn force(&self, phi: f64) -> f64 {
+        let cos_psi = -cos(phi);
+        let mut derivative = 0.0;
+        let mut power = 1.0;
+        for (n, &c) in self.c.iter().enumerate().skip(1) {
+            derivative += n as f64 * c * power;
+            power *= cos_psi;
+        }
+        return -sin(phi) * derivative;
+    }
+
+    fn parameters(&self) -> Option<PotentialParameters> {
+        Some(PotentialParameters {
+            name: "ryckaert-bellemans",
+            values: vec![
+                ("C0", self.c[0]), ("C1", self.c[1]), ("C2", self.c[2]),
+                ("C3", self.c[3]), ("C4", self.c[4]), ("C5", self.c[5]),
+            ],
+        })
+    }
+}
+
+impl DihedralPotential for RyckaertBellemans {}
+
 /// Buckingham potential.
 ///
 /// $$ V(x) = A \exp \left(\frac{\sigma - r}{\rho} \right) - \frac{C}{r^6} $$
@@ -295,6 +596,13 @@ impl Potential for Buckingham {
         let exp = exp(-r / self.rho);
         self.a / self.rho * exp - 6.0 * self.c / r7
     }
+
+    fn parameters(&self) -> Option<PotentialParameters> {
+        Some(PotentialParameters {
+            name: "buckingham",
+            values: vec![("A", self.a), ("C", self.c), ("rho", self.rho)],
+        })
+    }
 }
 
 impl PairPotential for Buckingham {
@@ -362,6 +670,13 @@ impl Potential for BornMayerHuggins {
         let exp = exp((self.sigma - r) / self.rho);
         self.a / self.rho * exp - 6.0 * self.c / r7 + 8.0 * self.d / (r7 * r2)
     }
+
+    fn parameters(&self) -> Option<PotentialParameters> {
+        Some(PotentialParameters {
+            name: "born",
+            values: vec![("A", self.a), ("C", self.c), ("D", self.d), ("sigma", self.sigma), ("rho", self.rho)],
+        })
+    }
 }
 
 impl PairPotential for BornMayerHuggins {
@@ -419,6 +734,13 @@ impl Potential for Morse {
         let exp = exp((self.x0 - r) * self.a);
         2.0 * self.depth * (1.0 - exp * exp) * self.a
     }
+
+    fn parameters(&self) -> Option<PotentialParameters> {
+        Some(PotentialParameters {
+            name: "morse",
+            values: vec![("A", self.a), ("x0", self.x0), ("depth", self.depth)],
+        })
+    }
 }
 
 impl PairPotential for Morse {
@@ -479,6 +801,13 @@ impl Potential for Gaussian {
     fn force(&self, r: f64) -> f64 {
         2.0 * self.b * r * self.energy(r)
     }
+
+    fn parameters(&self) -> Option<PotentialParameters> {
+        Some(PotentialParameters {
+            name: "gaussian",
+            values: vec![("A", self.a), ("B", self.b)],
+        })
+    }
 }
 
 impl PairPotential for Gaussian {
@@ -527,6 +856,8 @@ impl PairPotential for Gaussian {
 pub struct Mie {
     /// Distance constant
     sigma: f64,
+    /// Energetic constant
+    epsilon: f64,
     /// Exponent of repulsive contribution
     n: f64,
     /// Exponent of attractive contribution
@@ -544,6 +875,7 @@ impl Mie {
         let prefac = n / (n - m) * (n / m).powf(m / (n - m)) * epsilon;
         Mie {
             sigma: sigma,
+            epsilon: epsilon,
             n: n,
             m: m,
             prefac: prefac,
@@ -565,6 +897,13 @@ impl Potential for Mie {
         let attractive = f64::powf(sigma_r, self.m);
         self.prefac * (self.n * repulsive - self.m * attractive) / r
     }
+
+    fn parameters(&self) -> Option<PotentialParameters> {
+        Some(PotentialParameters {
+            name: "mie",
+            values: vec![("sigma", self.sigma), ("epsilon", self.epsilon), ("n", self.n), ("m", self.m)],
+        })
+    }
 }
 
 impl PairPotential for Mie {
@@ -594,6 +933,73 @@ impl PairPotential for Mie {
 }
 
 
+/// Scaling of another potential by a constant factor.
+///
+/// $$ V(x) = s \, V_0(x) $$
+///
+/// where $V_0$ is the wrapped potential and $s$ the scaling factor. This is
+/// mainly used to scale bonded interactions, for example the 1-4 dihedral
+/// angles terms in some force fields.
+///
+/// # Examples
+///
+/// ```
+/// # use lumol_core::energy::{Potential, DihedralPotential};
+/// # use lumol_core::energy::{Harmonic, Scaled};
+/// let harmonic = Harmonic { x0: 2.0, k: 100.0 };
+/// let dihedral: Box<dyn DihedralPotential> = Box::new(harmonic);
+/// let potential = Scaled::new(dihedral, 0.5);
+///
+/// assert_eq!(potential.scaling(), 0.5);
+/// assert_eq!(potential.energy(3.0), 0.5 * harmonic.energy(3.0));
+/// assert_eq!(potential.force(3.0), 0.5 * harmonic.force(3.0));
+/// ```
+#[derive(Clone)]
+pub struct Scaled<T> {
+    /// The wrapped potential
+    potential: T,
+    /// Scaling factor
+    scaling: f64,
+}
+
+impl<T> Scaled<T> {
+    /// Create a new `Scaled` potential, multiplying the energy and the force
+    /// of `potential` by `scaling`.
+    pub fn new(potential: T, scaling: f64) -> Scaled<T> {
+        Scaled {
+            potential: potential,
+            scaling: scaling,
+        }
+    }
+
+    /// Get the scaling factor of this potential
+    pub fn scaling(&self) -> f64 {
+        self.scaling
+    }
+}
+
+impl<T> Potential for Scaled<T> where T: Deref + Sync + Send, T::Target: Potential {
+    fn energy(&self, x: f64) -> f64 {
+        self.scaling * self.potential.energy(x)
+    }
+
+    fn force(&self, x: f64) -> f64 {
+        self.scaling * self.potential.force(x)
+    }
+
+    fn parameters(&self) -> Option<PotentialParameters> {
+        self.potential.parameters().map(|mut parameters| {
+            parameters.values.push(("scaling", self.scaling));
+            parameters
+        })
+    }
+}
+
+impl BondPotential for Scaled<Box<dyn BondPotential>> {}
+impl AnglePotential for Scaled<Box<dyn AnglePotential>> {}
+impl DihedralPotential for Scaled<Box<dyn DihedralPotential>> {}
+impl ImproperPotential for Scaled<Box<dyn ImproperPotential>> {}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -644,6 +1050,62 @@ mod tests {
         assert_relative_eq!((e0 - e1) / EPS, lj.force(4.0), epsilon = 1e-6);
     }
 
+    #[test]
+    fn wca() {
+        let wca = WCA {
+            epsilon: 0.8,
+            sigma: 2.0,
+        };
+        let rc = f64::powf(2.0, 1.0 / 6.0) * 2.0;
+        assert_eq!(wca.cutoff(), rc);
+
+        assert_eq!(wca.energy(rc), 0.0);
+        assert_eq!(wca.energy(rc + 0.1), 0.0);
+        assert_eq!(wca.energy(10.0), 0.0);
+        assert_eq!(wca.force(rc), 0.0);
+        assert_eq!(wca.force(rc + 0.1), 0.0);
+        assert_eq!(wca.force(10.0), 0.0);
+
+        assert_eq!(wca.energy(2.0), 0.8);
+        assert!(wca.energy(2.1) > 0.0);
+        assert!(wca.energy(rc - 1e-3) > 0.0);
+        assert!(wca.force(2.1) > 0.0);
+        assert!(wca.force(rc - 1e-3) > 0.0);
+
+        // The potential is continuous at the cutoff
+        assert!(wca.energy(rc - 1e-6) < 1e-10);
+
+        assert_eq!(wca.tail_energy(3.0), 0.0);
+        assert_eq!(wca.tail_virial(3.0), 0.0);
+
+        let e0 = wca.energy(2.1);
+        let e1 = wca.energy(2.1 + EPS);
+        assert_relative_eq!((e0 - e1) / EPS, wca.force(2.1), epsilon = 1e-6);
+    }
+
+
+    #[test]
+    fn lennard_jones_124() {
+        let lj = LennardJones { sigma: 2.0, epsilon: 0.8 };
+        let lj124 = LennardJones124 { sigma: 2.0, epsilon: 0.8, c4: 0.0 };
+        for &r in &[1.8, 2.0, 2.5, 3.3, 6.0] {
+            assert_eq!(lj124.energy(r), lj.energy(r));
+            assert_eq!(lj124.force(r), lj.force(r));
+        }
+        assert_eq!(lj124.tail_energy(7.0), lj.tail_energy(7.0));
+        assert_eq!(lj124.tail_virial(7.0), lj.tail_virial(7.0));
+
+        let lj124 = LennardJones124 { sigma: 2.0, epsilon: 0.8, c4: 3.0 };
+        assert_ulps_eq!(lj124.energy(2.5), lj.energy(2.5) - 3.0 / 39.0625);
+
+        let eps = 1e-9;
+        let force = -(lj124.energy(2.5 + eps) - lj124.energy(2.5)) / eps;
+        assert_relative_eq!(lj124.force(2.5), force, epsilon = 1e-6);
+
+        // tail corrections integrals
+        assert_ulps_eq!(lj124.tail_energy(8.0), lj.tail_energy(8.0) - 3.0 / 8.0);
+        assert_ulps_eq!(lj124.tail_virial(8.0), lj.tail_virial(8.0) - 12.0 / 8.0);
+    }
     #[test]
     fn harmonic() {
         let harmonic = Harmonic { k: 50.0, x0: 2.0 };
@@ -661,6 +1123,28 @@ mod tests {
         assert_relative_eq!((e0 - e1) / EPS, harmonic.force(2.1), epsilon = 1e-6);
     }
 
+    #[test]
+    fn quartic() {
+        let quartic = Quartic { k2: 50.0, k3: 0.0, k4: 0.0, x0: 2.0 };
+        let harmonic = Harmonic { k: 50.0, x0: 2.0 };
+        for &x in &[1.2, 2.0, 2.1, 2.5, 3.7] {
+            assert_eq!(quartic.energy(x), harmonic.energy(x));
+            assert_eq!(quartic.force(x), harmonic.force(x));
+        }
+
+        let quartic = Quartic { k2: 50.0, k3: -12.0, k4: 3.0, x0: 2.0 };
+        assert_eq!(quartic.energy(2.0), 0.0);
+        assert_eq!(quartic.force(2.0), 0.0);
+        assert_eq!(quartic.energy(3.0), 25.0 - 12.0 + 3.0);
+        assert_eq!(quartic.energy(1.0), 25.0 + 12.0 + 3.0);
+
+        for &x in &[1.5, 2.1, 2.8] {
+            let e0 = quartic.energy(x);
+            let e1 = quartic.energy(x + EPS);
+            assert_relative_eq!((e0 - e1) / EPS, quartic.force(x), epsilon = 1e-5);
+        }
+    }
+
     #[test]
     fn cosine_harmonic() {
         let harmonic = CosineHarmonic::new(50.0, 2.0);
@@ -695,6 +1179,27 @@ mod tests {
         assert_relative_eq!((e0 - e1) / EPS, torsion.force(4.0), epsilon = 1e-6);
     }
 
+    #[test]
+    fn ryckaert_bellemans() {
+        let rb = RyckaertBellemans {
+            c: [9.28, 12.16, -13.12, -3.06, 26.24, -31.5],
+        };
+        // trans conformation
+        assert_relative_eq!(rb.energy(PI), 9.28 + 12.16 - 13.12 - 3.06 + 26.24 - 31.5, epsilon = 1e-12);
+        // cis conformation
+        assert_relative_eq!(rb.energy(0.0), 9.28 - 12.16 - 13.12 + 3.06 + 26.24 + 31.5, epsilon = 1e-12);
+        assert_relative_eq!(rb.energy(PI / 2.0), 9.28, epsilon = 1e-12);
+
+        assert_relative_eq!(rb.force(0.0), 0.0, epsilon = 1e-12);
+        assert_relative_eq!(rb.force(PI), 0.0, epsilon = 1e-12);
+
+        for &phi in &[0.3, 1.2, 2.5, -1.8] {
+            let e0 = rb.energy(phi - EPS);
+            let e1 = rb.energy(phi + EPS);
+            assert_relative_eq!((e0 - e1) / (2.0 * EPS), rb.force(phi), max_relative = 1e-6);
+        }
+    }
+
     #[test]
     fn buckingham() {
         let buckingham = Buckingham {
@@ -771,6 +1276,25 @@ mod tests {
         assert_relative_eq!((e0 - e1) / EPS, gaussian.force(0.5), epsilon = 1e-6);
     }
 
+    #[test]
+    fn repulsive_gaussian() {
+        // Soft repulsion, as used in coarse-grained models
+        let sigma = 1.5;
+        let gaussian = Gaussian::new(-4.0, 1.0 / (sigma * sigma));
+        assert_eq!(gaussian.energy(0.0), 4.0);
+        assert_eq!(gaussian.force(0.0), 0.0);
+        assert_ulps_eq!(gaussian.energy(sigma), 4.0 * f64::exp(-1.0));
+
+        for &r in &[1e-3, 0.5, 1.0, 2.0, 5.0] {
+            assert!(gaussian.energy(r) > 0.0 && gaussian.energy(r) < 4.0);
+            assert!(gaussian.force(r) > 0.0 && gaussian.force(r).is_finite());
+        }
+
+        let e0 = gaussian.energy(0.5);
+        let e1 = gaussian.energy(0.5 + EPS);
+        assert_relative_eq!((e0 - e1) / EPS, gaussian.force(0.5), epsilon = 1e-6);
+    }
+
     #[test]
     #[should_panic(expected = "\"b\" has to be positive")]
     fn test_gaussian_wrong_input() {
@@ -813,4 +1337,23 @@ mod tests {
         assert_eq!(mie.tail_energy(2.0), 0.0);
         assert_eq!(mie.tail_virial(2.0), 0.0);
     }
+
+    #[test]
+    fn scaled() {
+        let potential: Box<dyn BondPotential> = Box::new(Harmonic { k: 50.0, x0: 2.0 });
+        let scaled = Scaled::new(potential.clone(), 0.3);
+        assert_eq!(scaled.scaling(), 0.3);
+        for &x in &[1.5, 2.0, 2.4] {
+            assert_eq!(scaled.energy(x), 0.3 * potential.energy(x));
+            assert_eq!(scaled.force(x), 0.3 * potential.force(x));
+        }
+
+        let parameters = scaled.parameters().unwrap();
+        assert_eq!(parameters.name, "harmonic");
+        assert_eq!(parameters.values, vec![("k", 50.0), ("x0", 2.0), ("scaling", 0.3)]);
+
+        let scaled = Scaled::new(potential, 0.0);
+        assert_eq!(scaled.energy(3.0), 0.0);
+        assert_eq!(scaled.force(3.0), 0.0);
+    }
 }
@@ -0,0 +1,731 @@
+// Lumol, an extensible molecular simulation engine
+// Copyright (C) Lumol's contributors — BSD license
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
+
+use rayon::prelude::*;
+
+use crate::units;
+use crate::{Configuration, UnitCell};
+use crate::{Matrix3, Vector3D};
+
+use super::{GlobalCache, GlobalPotential};
+
+/// Embedded-atom method (EAM) potential for metals.
+///
+/// In the EAM, the energy of a system is the sum of a pair potential and of
+/// an embedding energy for each atom, which depends on the electronic
+/// density created by the neighboring atoms [Daw1984]:
+///
+/// $$ E = \sum_i F_i(\rho_i) + \frac 12 \sum_{i \neq j} \phi_{ij}(r_{ij})
+///    \qquad \text{with} \qquad \rho_i = \sum_{j \neq i} \rho_j(r_{ij}) $$
+///
+/// The embedding functions $F$, the electronic densities $\rho$ and the pair
+/// potentials $\phi$ are tabulated, and read from files in the `funcfl` or
+/// `setfl` formats used by [LAMMPS] and the original DYNAMO code. They are
+/// interpolated with cubic splines. Particles are associated with the
+/// elements in the file by name, and particles with other names do not
+/// interact through this potential.
+///
+/// [Daw1984]: Daw, M. S. and Baskes, M. I., Phys. Rev. B 29, 6443 (1984).
+/// [LAMMPS]: https://lammps.sandia.gov/doc/pair_eam.html
+#[derive(Clone)]
+pub struct Eam {
+    /// Names of the elements
+    elements: Vec<String>,
+    /// Cutoff radius of the potential
+    cutoff: f64,
+    /// Embedding energy for each element, as a function of the density
+    embedding: Vec<Spline>,
+    /// Electronic density created by each element, as a function of the
+    /// distance
+    density: Vec<Spline>,
+    /// Pair potential multiplied by the distance `r φ(r)` for each pair of
+    /// elements `(i, j)` with `j <= i`, as a function of the distance. The
+    /// pair `(i, j)` is at index `i * (i + 1) / 2 + j`.
+    pair: Vec<Spline>,
+}
+
+impl Eam {
+    /// Read an EAM potential for a single element from the file at `path`,
+    /// in the `funcfl` format. The potential will apply to particles named
+    /// `element`. Energies in the file are in eV and distances in Angstroms.
+    ///
+    /// # Errors
+    ///
+    /// If the file can not be read, or if its content is not valid.
+    pub fn from_funcfl<P: AsRef<Path>>(path: P, element: &str) -> io::Result<Eam> {
+        let mut file = BufReader::new(File::open(path)?);
+        // First line is a comment, the second contains the atomic number,
+        // mass, lattice constant and lattice type
+        let _ = read_line(&mut file)?;
+        let _ = read_line(&mut file)?;
+        let grid = Grid::read(&read_line(&mut file)?)?;
+
+        let mut tokens = Tokens::read(file)?;
+        let embedding = tokens.values(grid.nrho)?;
+        let charge = tokens.values(grid.nr)?;
+        let density = tokens.values(grid.nr)?;
+
+        // The pair potential is given as the effective charge Z(r), with
+        // r φ(r) = 27.2 * 0.529 Z(r)^2, where 27.2 eV is one Hartree and
+        // 0.529 A is one Bohr radius.
+        let r_phi = charge.iter().map(|z| 27.2 * 0.529 * z * z).collect::<Vec<_>>();
+
+        let ev = units::from(1.0, "eV").expect("eV is a valid unit");
+        return Ok(Eam {
+            elements: vec![element.into()],
+            cutoff: grid.cutoff,
+            embedding: vec![Spline::new(grid.drho, scaled(embedding, ev))],
+            density: vec![Spline::new(grid.dr, density)],
+            pair: vec![Spline::new(grid.dr, scaled(r_phi, ev))],
+        });
+    }
+
+    /// Read an EAM potential for one or more elements from the file at
+    /// `path`, in the `setfl` format. The names of the elements are read
+    /// from the file. Energies in the file are in eV and distances in
+    /// Angstroms.
+    ///
+    /// # Errors
+    ///
+    /// If the file can not be read, or if its content is not valid.
+    pub fn from_setfl<P: AsRef<Path>>(path: P) -> io::Result<Eam> {
+        let mut file = BufReader::new(File::open(path)?);
+        // The first three lines are comments
+        for _ in 0..3 {
+            let _ = read_line(&mut file)?;
+        }
+
+        let line = read_line(&mut file)?;
+        let mut header = line.split_whitespace();
+        let count = header.next().ok_or_else(|| invalid_eam("missing number of elements".into()))?;
+        let count = parse::<usize>(count)?;
+        let elements = header.map(String::from).collect::<Vec<_>>();
+        if count == 0 || elements.len() != count {
+            return Err(invalid_eam(format!(
+                "expected {} element names in EAM file, got {}", count, elements.len()
+            )));
+        }
+
+        let grid = Grid::read(&read_line(&mut file)?)?;
+        let ev = units::from(1.0, "eV").expect("eV is a valid unit");
+
+        let mut tokens = Tokens::read(file)?;
+        let mut embedding = Vec::new();
+        let mut density = Vec::new();
+        for _ in 0..count {
+            // atomic number, mass, lattice constant and lattice type
+            tokens.skip(4)?;
+            embedding.push(Spline::new(grid.drho, scaled(tokens.values(grid.nrho)?, ev)));
+            density.push(Spline::new(grid.dr, tokens.values(grid.nr)?));
+        }
+
+        let mut pair = Vec::new();
+        for i in 0..count {
+            for _ in 0..=i {
+                pair.push(Spline::new(grid.dr, scaled(tokens.values(grid.nr)?, ev)));
+            }
+        }
+
+        return Ok(Eam {
+            elements: elements,
+            cutoff: grid.cutoff,
+            embedding: embedding,
+            density: density,
+            pair: pair,
+        });
+    }
+
+    /// Get the names of the elements in this potential
+    pub fn elements(&self) -> &[String] {
+        &self.elements
+    }
+
+    /// Get the element index of all the particles in the `configuration`
+    fn elements_of(&self, configuration: &Configuration) -> Vec<Option<usize>> {
+        configuration.particles().name.iter().map(|name| {
+            self.elements.iter().position(|element| element == name)
+        }).collect()
+    }
+
+    /// Get the spline for the pair potential between elements `i` and `j`
+    fn pair(&self, i: usize, j: usize) -> &Spline {
+        let (i, j) = if i >= j { (i, j) } else { (j, i) };
+        &self.pair[i * (i + 1) / 2 + j]
+    }
+
+    /// Get the pair potential and its derivative between elements `i` and
+    /// `j` at distance `r`
+    fn phi(&self, i: usize, j: usize, r: f64) -> (f64, f64) {
+        let (r_phi, r_phi_prime) = self.pair(i, j).evaluate(r);
+        let phi = r_phi / r;
+        return (phi, (r_phi_prime - phi) / r);
+    }
+
+    /// Compute the electronic density at all particles, using the given
+    /// `positions`
+    fn densities(&self, cell: &UnitCell, elements: &[Option<usize>], positions: &[Vector3D]) -> Vec<f64> {
+        (0..positions.len()).into_par_iter().map(|i| {
+            if elements[i].is_none() {
+                return 0.0;
+            }
+
+            let mut density = 0.0;
+            for (j, element_j) in elements.iter().enumerate() {
+                if let Some(element_j) = *element_j {
+                    if i == j {
+                        continue;
+                    }
+                    let r = cell.distance(&positions[i], &positions[j]);
+                    if r < self.cutoff {
+                        density += self.density[element_j].evaluate(r).0;
+                    }
+                }
+            }
+            return density;
+        }).collect()
+    }
+
+    /// Compute the total energy, using the given `positions`
+    fn energy_with(&self, cell: &UnitCell, elements: &[Option<usize>], positions: &[Vector3D]) -> f64 {
+        let densities = self.densities(cell, elements, positions);
+        let energies = (0..positions.len()).into_par_iter().map(|i| {
+            let element_i = match elements[i] {
+                Some(element) => element,
+                None => return 0.0,
+            };
+
+            let mut energy = self.embedding[element_i].evaluate(densities[i]).0;
+            for j in (i + 1)..positions.len() {
+                if let Some(element_j) = elements[j] {
+                    let r = cell.distance(&positions[i], &positions[j]);
+                    if r < self.cutoff {
+                        energy += self.phi(element_i, element_j, r).0;
+                    }
+                }
+            }
+            return energy;
+        });
+        return energies.sum();
+    }
+
+    /// Compute the force acting on particle `i` because of particle `j`,
+    /// given the derivative of the embedding energies `embedding_prime`.
+    /// `None` is returned if the particles do not interact.
+    fn pair_force(
+        &self,
+        configuration: &Configuration,
+        elements: &[Option<usize>],
+        embedding_prime: &[f64],
+        i: usize,
+        j: usize,
+    ) -> Option<Vector3D> {
+        let element_i = elements[i]?;
+        let element_j = elements[j]?;
+
+        let rij = configuration.nearest_image(i, j);
+        let r = rij.norm();
+        if r >= self.cutoff {
+            return None;
+        }
+
+        let density_i_prime = self.density[element_i].evaluate(r).1;
+        let density_j_prime = self.density[element_j].evaluate(r).1;
+        let phi_prime = self.phi(element_i, element_j, r).1;
+        let derivative = embedding_prime[i] * density_j_prime
+                       + embedding_prime[j] * density_i_prime
+                       + phi_prime;
+        return Some(-derivative / r * rij);
+    }
+
+    /// Compute the derivative of the embedding energy for all particles
+    fn embedding_prime(&self, configuration: &Configuration, elements: &[Option<usize>]) -> Vec<f64> {
+        let positions = configuration.particles().position;
+        let densities = self.densities(&configuration.cell, elements, positions);
+        return elements.iter().zip(&densities).map(|(element, &density)| {
+            match *element {
+                Some(element) => self.embedding[element].evaluate(density).1,
+                None => 0.0,
+            }
+        }).collect();
+    }
+}
+
+impl GlobalCache for Eam {
+    fn move_molecule_cost(
+        &self,
+        configuration: &Configuration,
+        molecule_id: usize,
+        new_positions: &[Vector3D],
+    ) -> f64 {
+        // Moving a single atom changes the embedding energy of all its
+        // neighbors, so we recompute the whole energy.
+        let elements = self.elements_of(configuration);
+        let old_energy = self.energy(configuration);
+
+        let mut positions = configuration.particles().position.to_vec();
+        let molecule = configuration.molecule(molecule_id);
+        for (i, part_i) in molecule.indexes().enumerate() {
+            positions[part_i] = new_positions[i];
+        }
+        let new_energy = self.energy_with(&configuration.cell, &elements, &positions);
+
+        return new_energy - old_energy;
+    }
+
+    fn update(&self) {
+        // Nothing to do
+    }
+}
+
+impl GlobalPotential for Eam {
+    fn cutoff(&self) -> Option<f64> {
+        Some(self.cutoff)
+    }
+
+    fn energy(&self, configuration: &Configuration) -> f64 {
+        let elements = self.elements_of(configuration);
+        let positions = configuration.particles().position;
+        return self.energy_with(&configuration.cell, &elements, positions);
+    }
+
+    fn forces(&self, configuration: &Configuration, forces: &mut [Vector3D]) {
+        assert_eq!(forces.len(), configuration.size());
+
+        let elements = self.elements_of(configuration);
+        let embedding_prime = self.embedding_prime(configuration, &elements);
+
+        let natoms = configuration.size();
+        let all_forces = (0..natoms).into_par_iter().map(|i| {
+            let mut force = Vector3D::zero();
+            for j in 0..natoms {
+                if i == j {
+                    continue;
+                }
+                if let Some(force_ij) = self.pair_force(configuration, &elements, &embedding_prime, i, j) {
+                    force += force_ij;
+                }
+            }
+            return force;
+        }).collect::<Vec<_>>();
+
+        for (force, computed) in forces.iter_mut().zip(all_forces) {
+            *force += computed;
+        }
+    }
+
+    fn atomic_virial(&self, configuration: &Configuration) -> Matrix3 {
+        let elements = self.elements_of(configuration);
+        let embedding_prime = self.embedding_prime(configuration, &elements);
+
+        let natoms = configuration.size();
+        let virials = (0..natoms).into_par_iter().map(|i| {
+            let mut local_virial = Matrix3::zero();
+            for j in (i + 1)..natoms {
+                if let Some(force) = self.pair_force(configuration, &elements, &embedding_prime, i, j) {
+                    let rij = configuration.nearest_image(i, j);
+                    local_virial += force.tensorial(&rij);
+                }
+            }
+            return local_virial;
+        });
+        return virials.sum();
+    }
+}
+
+/// Cubic spline interpolation of a function tabulated on a regular grid
+/// starting at 0, using the same scheme as LAMMPS.
+#[derive(Clone)]
+struct Spline {
+    /// Grid spacing
+    step: f64,
+    /// Polynomial coefficients in each interval, from the highest degree to
+    /// the constant term. The polynomials use the reduced coordinate `p` in
+    /// [0, 1] inside the interval.
+    coefficients: Vec<[f64; 4]>,
+}
+
+impl Spline {
+    fn new(step: f64, values: Vec<f64>) -> Spline {
+        let n = values.len();
+        assert!(n >= 5, "EAM tables need at least 5 points");
+
+        // derivatives with respect to the reduced coordinate
+        let mut derivatives = vec![0.0; n];
+        derivatives[0] = values[1] - values[0];
+        derivatives[1] = 0.5 * (values[2] - values[0]);
+        derivatives[n - 2] = 0.5 * (values[n - 1] - values[n - 3]);
+        derivatives[n - 1] = values[n - 1] - values[n - 2];
+        for i in 2..(n - 2) {
+            derivatives[i] = ((values[i - 2] - values[i + 2]) + 8.0 * (values[i + 1] - values[i - 1])) / 12.0;
+        }
+
+        let coefficients = (0..n).map(|i| {
+            if i == n - 1 {
+                return [0.0, 0.0, derivatives[i], values[i]];
+            }
+            let delta = values[i + 1] - values[i];
+            let c2 = 3.0 * delta - 2.0 * derivatives[i] - derivatives[i + 1];
+            let c3 = derivatives[i] + derivatives[i + 1] - 2.0 * delta;
+            [c3, c2, derivatives[i], values[i]]
+        }).collect();
+
+        Spline {
+            step: step,
+            coefficients: coefficients,
+        }
+    }
+
+    /// Get the value and the derivative of the function at `x`. Values of
+    /// `x` outside of the grid use the polynomial of the closest interval.
+    fn evaluate(&self, x: f64) -> (f64, f64) {
+        let position = x / self.step;
+        let last = self.coefficients.len() - 2;
+        let bin = if position <= 0.0 {
+            0
+        } else {
+            usize::min(position as usize, last)
+        };
+        let p = f64::min(position - bin as f64, 1.0);
+
+        let [c3, c2, c1, c0] = self.coefficients[bin];
+        let value = ((c3 * p + c2) * p + c1) * p + c0;
+        let derivative = ((3.0 * c3 * p + 2.0 * c2) * p + c1) / self.step;
+        return (value, derivative);
+    }
+}
+
+/// Tabulation grid of an EAM file
+struct Grid {
+    /// Number of points in the density grid
+    nrho: usize,
+    /// Spacing of the density grid
+    drho: f64,
+    /// Number of points in the distance grid
+    nr: usize,
+    /// Spacing of the distance grid
+    dr: f64,
+    /// Cutoff radius of the potential
+    cutoff: f64,
+}
+
+impl Grid {
+    /// Read the grid from a line containing `Nrho drho Nr dr cutoff`
+    fn read(line: &str) -> io::Result<Grid> {
+        let values = line.split_whitespace().collect::<Vec<_>>();
+        if values.len() < 5 {
+            return Err(invalid_eam(format!(
+                "expected 'Nrho drho Nr dr cutoff' in EAM file, got '{}'", line.trim()
+            )));
+        }
+
+        let grid = Grid {
+            nrho: parse(values[0])?,
+            drho: parse(values[1])?,
+            nr: parse(values[2])?,
+            dr: parse(values[3])?,
+            cutoff: parse(values[4])?,
+        };
+
+        if grid.nrho < 5 || grid.nr < 5 {
+            return Err(invalid_eam("EAM tables need at least 5 points".into()));
+        }
+        if grid.drho <= 0.0 || grid.dr <= 0.0 || grid.cutoff <= 0.0 {
+            return Err(invalid_eam("grid spacing and cutoff must be positive in EAM file".into()));
+        }
+        return Ok(grid);
+    }
+}
+
+/// Whitespace separated tokens in the tabulated part of an EAM file
+struct Tokens {
+    tokens: std::vec::IntoIter<String>,
+}
+
+impl Tokens {
+    fn read<R: BufRead>(mut reader: R) -> io::Result<Tokens> {
+        let mut content = String::new();
+        let _ = reader.read_to_string(&mut content)?;
+        let tokens = content.split_whitespace().map(String::from).collect::<Vec<_>>();
+        return Ok(Tokens {
+            tokens: tokens.into_iter(),
+        });
+    }
+
+    fn next(&mut self) -> io::Result<String> {
+        self.tokens.next().ok_or_else(|| invalid_eam("unexpected end of EAM file".into()))
+    }
+
+    fn skip(&mut self, count: usize) -> io::Result<()> {
+        for _ in 0..count {
+            let _ = self.next()?;
+        }
+        return Ok(());
+    }
+
+    fn values(&mut self, count: usize) -> io::Result<Vec<f64>> {
+        (0..count).map(|_| parse(&self.next()?)).collect()
+    }
+}
+
+fn read_line<R: BufRead>(reader: &mut R) -> io::Result<String> {
+    let mut line = String::new();
+    if reader.read_line(&mut line)? == 0 {
+        return Err(invalid_eam("unexpected end of EAM file".into()));
+    }
+    return Ok(line);
+}
+
+fn parse<T: std::str::FromStr>(value: &str) -> io::Result<T> {
+    value.parse().map_err(|_| invalid_eam(format!("invalid number '{}' in EAM file", value)))
+}
+
+fn scaled(values: Vec<f64>, factor: f64) -> Vec<f64> {
+    values.into_iter().map(|value| value * factor).collect()
+}
+
+fn invalid_eam(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Molecule, Particle, System};
+    use approx::assert_relative_eq;
+
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    /// Analytic nearest-neighbor EAM for copper, from Johnson, R. A.,
+    /// Phys. Rev. B 37, 3924 (1988). The fcc lattice constant is 3.615 A and
+    /// the cohesive energy 3.54 eV by construction. The functions are
+    /// smoothly switched off between the first and second neighbors shells.
+    struct Johnson;
+
+    impl Johnson {
+        const RE: f64 = 2.556191;
+        const EC: f64 = 3.54;
+        const PHI_E: f64 = 0.59;
+        const F_E: f64 = 0.30;
+        const ALPHA: f64 = 5.09;
+        const BETA: f64 = 5.85;
+        const GAMMA: f64 = 8.00;
+        const SWITCH: f64 = 3.0;
+        const CUTOFF: f64 = 3.5;
+
+        const NRHO: usize = 2000;
+        const DRHO: f64 = 0.005;
+        const NR: usize = 2000;
+        const DR: f64 = 0.002;
+
+        fn phi(r: f64) -> f64 {
+            Johnson::PHI_E * f64::exp(-Johnson::GAMMA * (r / Johnson::RE - 1.0))
+        }
+
+        fn density(r: f64) -> f64 {
+            Johnson::F_E * f64::exp(-Johnson::BETA * (r / Johnson::RE - 1.0))
+        }
+
+        fn embedding(rho: f64) -> f64 {
+            if rho == 0.0 {
+                return 0.0;
+            }
+            let rho_e = 12.0 * Johnson::F_E;
+            let x = rho / rho_e;
+            let ab = Johnson::ALPHA / Johnson::BETA;
+            let gb = Johnson::GAMMA / Johnson::BETA;
+            return -Johnson::EC * (1.0 - ab * f64::ln(x)) * f64::powf(x, ab) - 6.0 * Johnson::PHI_E * f64::powf(x, gb);
+        }
+
+        fn grid_line() -> String {
+            format!("{} {} {} {} {}", Johnson::NRHO, Johnson::DRHO, Johnson::NR, Johnson::DR, Johnson::CUTOFF)
+        }
+
+        /// Switch the functions smoothly to zero between `SWITCH` and `CUTOFF`
+        fn cut(r: f64, f: fn(f64) -> f64) -> f64 {
+            if r >= Johnson::CUTOFF {
+                0.0
+            } else if r <= Johnson::SWITCH {
+                f(r)
+            } else {
+                let x = (r - Johnson::SWITCH) / (Johnson::CUTOFF - Johnson::SWITCH);
+                f(r) * 0.5 * (1.0 + f64::cos(std::f64::consts::PI * x))
+            }
+        }
+
+        fn write_values(file: &mut NamedTempFile, values: impl Iterator<Item = f64>) {
+            for (i, value) in values.enumerate() {
+                write!(file, "{:.16e}", value).unwrap();
+                if i % 5 == 4 {
+                    writeln!(file).unwrap();
+                } else {
+                    write!(file, " ").unwrap();
+                }
+            }
+        }
+
+        fn setfl() -> NamedTempFile {
+            let mut file = NamedTempFile::new().unwrap();
+            writeln!(file, "Johnson analytic EAM for Cu").unwrap();
+            writeln!(file, "Phys. Rev. B 37, 3924 (1988)").unwrap();
+            writeln!(file, "generated for lumol tests").unwrap();
+            writeln!(file, "1 Cu").unwrap();
+            writeln!(file, "{}", Johnson::grid_line()).unwrap();
+            writeln!(file, "29 63.546 3.615 fcc").unwrap();
+
+            let rho = (0..Johnson::NRHO).map(|i| i as f64 * Johnson::DRHO);
+            Johnson::write_values(&mut file, rho.map(Johnson::embedding));
+            let r = (0..Johnson::NR).map(|i| i as f64 * Johnson::DR);
+            Johnson::write_values(&mut file, r.map(|r| Johnson::cut(r, Johnson::density)));
+            let r = (0..Johnson::NR).map(|i| i as f64 * Johnson::DR);
+            Johnson::write_values(&mut file, r.map(|r| r * Johnson::cut(r, Johnson::phi)));
+            return file;
+        }
+
+        fn funcfl() -> NamedTempFile {
+            let mut file = NamedTempFile::new().unwrap();
+            writeln!(file, "Johnson analytic EAM for Cu").unwrap();
+            writeln!(file, "29 63.546 3.615 fcc").unwrap();
+            writeln!(file, "{}", Johnson::grid_line()).unwrap();
+
+            let rho = (0..Johnson::NRHO).map(|i| i as f64 * Johnson::DRHO);
+            Johnson::write_values(&mut file, rho.map(Johnson::embedding));
+            let r = (0..Johnson::NR).map(|i| i as f64 * Johnson::DR);
+            Johnson::write_values(&mut file, r.map(|r| {
+                f64::sqrt(r * Johnson::cut(r, Johnson::phi) / (27.2 * 0.529))
+            }));
+            let r = (0..Johnson::NR).map(|i| i as f64 * Johnson::DR);
+            Johnson::write_values(&mut file, r.map(|r| Johnson::cut(r, Johnson::density)));
+            return file;
+        }
+    }
+
+    fn copper(n: usize, a: f64) -> System {
+        let mut system = System::with_cell(UnitCell::cubic(n as f64 * a));
+        let basis = [[0.0, 0.0, 0.0], [0.5, 0.5, 0.0], [0.5, 0.0, 0.5], [0.0, 0.5, 0.5]];
+        for i in 0..n {
+            for j in 0..n {
+                for k in 0..n {
+                    for site in &basis {
+                        let position = Vector3D::new(
+                            (i as f64 + site[0]) * a,
+                            (j as f64 + site[1]) * a,
+                            (k as f64 + site[2]) * a,
+                        );
+                        system.add_molecule(Molecule::new(Particle::with_position("Cu", position)));
+                    }
+                }
+            }
+        }
+        return system;
+    }
+
+    #[test]
+    fn cohesive_energy() {
+        let file = Johnson::setfl();
+        let eam = Eam::from_setfl(file.path()).unwrap();
+        assert_eq!(eam.elements(), &["Cu"]);
+        assert_eq!(eam.cutoff(), Some(3.5));
+
+        let mut system = copper(3, 3.615);
+        assert_eq!(system.size(), 108);
+        system.add_global_potential(Box::new(eam.clone()));
+        let energy = system.potential_energy() / system.size() as f64;
+        assert_relative_eq!(units::to(energy, "eV").unwrap(), -3.54, max_relative = 1e-5);
+
+        // Forces are zero in the perfect lattice
+        let mut forces = vec![Vector3D::zero(); system.size()];
+        eam.forces(&system, &mut forces);
+        for force in &forces {
+            assert!(force.norm() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn funcfl() {
+        let setfl = Eam::from_setfl(Johnson::setfl().path()).unwrap();
+        let funcfl = Eam::from_funcfl(Johnson::funcfl().path(), "Cu").unwrap();
+        assert_eq!(funcfl.elements(), &["Cu"]);
+
+        let system = copper(2, 3.7);
+        assert_relative_eq!(funcfl.energy(&system), setfl.energy(&system), max_relative = 1e-6);
+    }
+
+    #[test]
+    fn forces() {
+        let eam = Eam::from_setfl(Johnson::setfl().path()).unwrap();
+        let mut system = copper(2, 3.615);
+        for (i, position) in system.particles_mut().position.iter_mut().enumerate() {
+            let i = i as f64;
+            *position += 0.1 * Vector3D::new(f64::sin(i), f64::cos(3.0 * i), f64::sin(7.0 * i));
+        }
+        // This particle does not interact through the EAM potential
+        system.add_molecule(Molecule::new(Particle::with_position("Ar", Vector3D::new(1.0, 1.0, 1.0))));
+
+        let mut forces = vec![Vector3D::zero(); system.size()];
+        eam.forces(&system, &mut forces);
+        assert_eq!(forces[system.size() - 1], Vector3D::zero());
+
+        let eps = 1e-6;
+        for i in 0..(system.size() - 1) {
+            for k in 0..3 {
+                let mut moved = system.clone();
+                moved.particles_mut().position[i][k] += eps;
+                let energy_plus = eam.energy(&moved);
+                moved.particles_mut().position[i][k] -= 2.0 * eps;
+                let energy_minus = eam.energy(&moved);
+                let expected = -(energy_plus - energy_minus) / (2.0 * eps);
+                assert_relative_eq!(forces[i][k], expected, max_relative = 1e-4, epsilon = 1e-4);
+            }
+        }
+
+        // The trace of the virial is W = -3 V dE/dV
+        let virial = eam.atomic_virial(&system);
+        let trace = virial[0][0] + virial[1][1] + virial[2][2];
+
+        let mut scaled = system.clone();
+        let factor = 1.0 + eps;
+        scaled.cell.scale_mut(Matrix3::one() * factor);
+        for position in scaled.particles_mut().position {
+            *position *= factor;
+        }
+        let volume = system.cell.volume();
+        let delta_volume = scaled.cell.volume() - volume;
+        let expected = -3.0 * volume * (eam.energy(&scaled) - eam.energy(&system)) / delta_volume;
+        assert_relative_eq!(trace, expected, max_relative = 1e-4);
+    }
+
+    #[test]
+    fn move_molecule_cost() {
+        let eam = Eam::from_setfl(Johnson::setfl().path()).unwrap();
+        let system = copper(2, 3.615);
+
+        let new_positions = [system.particles().position[3] + Vector3D::new(0.2, -0.1, 0.3)];
+        let cost = eam.move_molecule_cost(&system, system.molecule(3).indexes().start, &new_positions);
+
+        let mut moved = system.clone();
+        moved.particles_mut().position[3] = new_positions[0];
+        assert_relative_eq!(cost, eam.energy(&moved) - eam.energy(&system), max_relative = 1e-10);
+    }
+
+    #[test]
+    fn errors() {
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(file, "\n\n\n2 Cu Ni\n10 0.1 10 0.1 3.0").unwrap();
+        let error = Eam::from_setfl(file.path()).err().unwrap();
+        assert_eq!(error.to_string(), "unexpected end of EAM file");
+
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(file, "\n\n\n2 Cu\n10 0.1 10 0.1 3.0").unwrap();
+        let error = Eam::from_setfl(file.path()).err().unwrap();
+        assert_eq!(error.to_string(), "expected 2 element names in EAM file, got 1");
+
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(file, "\n29 63.546 3.615 fcc\n10 0.1 foo 0.1 3.0").unwrap();
+        let error = Eam::from_funcfl(file.path(), "Cu").err().unwrap();
+        assert_eq!(error.to_string(), "invalid number 'foo' in EAM file");
+    }
+}
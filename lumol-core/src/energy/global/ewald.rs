@@ -11,10 +11,10 @@ use log::{warn, info};
 use log_once::warn_once;
 
 use crate::math::{exp, erf, erfc, sqrt};
-use crate::{Configuration, UnitCell, CellShape};
+use crate::{CellList, Configuration, UnitCell, CellShape};
 use crate::{Matrix3, Vector3D, Array3, Complex};
 use crate::consts::FOUR_PI_EPSILON_0;
-use crate::{PairRestriction, RestrictionInfo};
+use crate::{PairRestriction, RestrictionInfo, PotentialParameters};
 use crate::utils::ThreadLocalVec;
 
 use super::{GlobalPotential, CoulombicPotential, GlobalCache};
@@ -235,6 +235,10 @@ impl EwaldFactors {
 ///
 /// [FS2002] Frenkel, D. & Smith, B. Understanding molecular simulation. (Academic press, 2002).
 ///
+/// Excluded pairs are corrected in real space, and must be closer than the
+/// cutoff. `Scale14` restrictions, and `ExcludeBonded` restrictions with more
+/// than three bonds are not supported.
+///
 /// # Examples
 ///
 /// ```
@@ -271,6 +275,9 @@ pub struct Ewald {
     factors: EwaldFactors,
     /// Restriction scheme
     restriction: PairRestriction,
+    /// Should we add the energy of a uniform neutralizing background for
+    /// charged systems?
+    background_correction: bool,
     /// Cached phase factors (e^{i k r})
     eikr: Ewald3DArray,
     /// Fourier transform of the electrostatic density (\sum q_i e^{i k r})
@@ -294,6 +301,7 @@ impl Clone for Ewald {
             parameters: self.parameters.clone(),
             factors: self.factors.clone(),
             restriction: self.restriction,
+            background_correction: self.background_correction,
             eikr: self.eikr.clone(),
             rho: self.rho.clone(),
             efield: self.efield.clone(),
@@ -335,6 +343,7 @@ impl Ewald {
         Ewald {
             parameters: parameters,
             restriction: PairRestriction::None,
+            background_correction: false,
             factors: EwaldFactors::new(),
             eikr: Ewald3DArray::zeros((0..0, 0, 0)),
             rho: Vec::new(),
@@ -355,7 +364,7 @@ impl Ewald {
         } else if accuracy < 0.0 {
             panic!("accuracy can not be negative in Ewald");
         } else if accuracy > 1.0 {
-            warn!("accuracy is bigger than 1 in Ewald::with_precision")
+            warn!("accuracy is bigger than 1 in Ewald::with_accuracy")
         }
 
         // Compute squared total charge
@@ -390,6 +399,18 @@ impl Ewald {
         Ewald::new(cutoff, kmax, alpha)
     }
 
+    /// Enable or disable the charged system correction. When enabled, the
+    /// energy of a uniform neutralizing background is added to the energy
+    /// of systems with a non-zero net charge.
+    pub fn set_background_correction(&mut self, correction: bool) {
+        self.background_correction = correction;
+    }
+
+    /// Check if the charged system correction is enabled.
+    pub fn background_correction(&self) -> bool {
+        self.background_correction
+    }
+
     fn precompute(&mut self, cell: &UnitCell) {
         if let Some(ref prev_cell) = self.previous_cell {
             if cell == prev_cell {
@@ -471,6 +492,7 @@ impl Ewald {
     fn real_space_energy(&self, configuration: &Configuration) -> f64 {
         let natoms = configuration.size();
         let charges = configuration.particles().charge;
+        let cells = CellList::new(configuration, self.rc);
 
         let energies = (0..natoms).into_par_iter().map(|i| {
             let mut local_energy = 0.0;
@@ -479,18 +501,17 @@ impl Ewald {
                 return 0.0;
             }
 
-            for j in i + 1..natoms {
+            cells.for_each_neighbor(i, |j| {
                 let qj = charges[j];
                 if qj == 0.0 {
-                    continue;
+                    return;
                 }
 
-                let path = configuration.bond_path(i, j);
-                let info = self.restriction.information(path);
+                let info = self.restriction.information_between(configuration, i, j);
 
                 let r = configuration.distance(i, j);
                 local_energy += self.real_space_energy_pair(info, qi * qj, r);
-            }
+            });
 
             local_energy
         });
@@ -506,6 +527,7 @@ impl Ewald {
         // Each thread (and not each iteration of the loop below) get its own
         // storage in a `ThreadLocalVec`.
         let thread_local_forces = ThreadLocalVec::with_size(natoms);
+        let cells = CellList::new(configuration, self.rc);
 
         (0..natoms).into_par_iter().for_each(|i| {
             // Get the thread local forces Vec
@@ -517,20 +539,19 @@ impl Ewald {
                 return;
             }
 
-            for j in i + 1..natoms {
+            cells.for_each_neighbor(i, |j| {
                 let qj = charges[j];
                 if qj == 0.0 {
-                    continue;
+                    return;
                 }
 
-                let path = configuration.bond_path(i, j);
-                let info = self.restriction.information(path);
+                let info = self.restriction.information_between(configuration, i, j);
 
                 let rij = configuration.nearest_image(i, j);
                 let force = self.real_space_force_pair(info, qi * qj, rij.norm()) * rij;
                 force_i += force;
                 forces[j] -= force;
-            }
+            });
             forces[i] += force_i;
         });
 
@@ -543,6 +564,7 @@ impl Ewald {
     fn real_space_atomic_virial(&self, configuration: &Configuration) -> Matrix3 {
         let natoms = configuration.size();
         let charges = configuration.particles().charge;
+        let cells = CellList::new(configuration, self.rc);
 
         let virials = (0..natoms).into_par_iter().map(|i| {
             let qi = charges[i];
@@ -551,19 +573,18 @@ impl Ewald {
             }
             let mut local_virial = Matrix3::zero();
 
-            for j in i + 1..natoms {
+            cells.for_each_neighbor(i, |j| {
                 let qj = charges[j];
                 if qj == 0.0 {
-                    continue;
+                    return;
                 }
 
-                let path = configuration.bond_path(i, j);
-                let info = self.restriction.information(path);
+                let info = self.restriction.information_between(configuration, i, j);
 
                 let rij = configuration.nearest_image(i, j);
                 let force = self.real_space_force_pair(info, qi * qj, rij.norm()) * rij;
                 local_virial += force.tensorial(&rij);
-            }
+            });
             local_virial
         });
         return virials.sum();
@@ -593,8 +614,7 @@ impl Ewald {
                             continue;
                         }
 
-                        let path = configuration.bond_path(part_a, part_b);
-                        let info = self.restriction.information(path);
+                        let info = self.restriction.information_between(configuration, part_a, part_b);
 
                         let r_ab = configuration.nearest_image(part_a, part_b);
                         let force = self.real_space_force_pair(info, q_a * q_b, r_ab.norm()) * r_ab;
@@ -640,8 +660,7 @@ impl Ewald {
                     let old_r = configuration.distance(part_i, part_j);
                     let new_r = configuration.cell.distance(&new_positions[i], &positions[part_j]);
 
-                    let path = configuration.bond_path(part_i, part_j);
-                    let info = self.restriction.information(path);
+                    let info = self.restriction.information_between(configuration, part_i, part_j);
 
                     old_energy += self.real_space_energy_pair(info, qi * qj, old_r);
                     new_energy += self.real_space_energy_pair(info, qi * qj, new_r);
@@ -666,6 +685,33 @@ impl Ewald {
     }
 }
 
+/// Charged system correction
+impl Ewald {
+    /// Energy of the interaction between the charges and an uniform
+    /// neutralizing background, for systems with a net charge Q:
+    ///
+    /// `E_bg = - π Q² / (2 V α²) / (4 π ε_0)`
+    ///
+    /// This is zero if the correction is disabled or the system is neutral.
+    fn background_energy(&self, configuration: &Configuration) -> f64 {
+        if !self.background_correction {
+            return 0.0;
+        }
+        let charge = configuration.particles().charge.iter().sum::<f64>();
+        let volume = configuration.cell.volume();
+        let alpha2 = self.alpha * self.alpha;
+        return -PI * charge * charge / (2.0 * volume * alpha2) / FOUR_PI_EPSILON_0;
+    }
+
+    /// Contribution of the neutralizing background to the virial. The
+    /// background energy is proportional to 1 / V, and does not depend on
+    /// the particles positions: there is no force, and the virial is
+    /// `W = E_bg 𝟙`.
+    fn background_virial(&self, configuration: &Configuration) -> Matrix3 {
+        return self.background_energy(configuration) * Matrix3::one();
+    }
+}
+
 
 /// k-space part of the summation
 impl Ewald {
@@ -931,7 +977,8 @@ impl GlobalPotential for SharedEwald {
         let real = ewald.real_space_energy(configuration);
         let self_e = ewald.self_energy(configuration);
         let kspace = ewald.kspace_energy(configuration);
-        return real + self_e + kspace;
+        let background = ewald.background_energy(configuration);
+        return real + self_e + kspace + background;
     }
 
     fn forces(&self, configuration: &Configuration, forces: &mut [Vector3D])  {
@@ -940,7 +987,7 @@ impl GlobalPotential for SharedEwald {
         ewald.precompute(&configuration.cell);
 
         ewald.real_space_forces(configuration, forces);
-        // No self force
+        // No self or background force
         ewald.kspace_forces(configuration, forces);
     }
 
@@ -950,7 +997,8 @@ impl GlobalPotential for SharedEwald {
         let real = ewald.real_space_atomic_virial(configuration);
         // No self virial
         let kspace = ewald.kspace_atomic_virial(configuration);
-        return real + kspace;
+        let background = ewald.background_virial(configuration);
+        return real + kspace + background;
     }
 
     fn molecular_virial(&self, configuration: &Configuration) -> Matrix3 {
@@ -959,14 +1007,38 @@ impl GlobalPotential for SharedEwald {
         let real = ewald.real_space_molecular_virial(configuration);
         // No self virial
         let kspace = ewald.kspace_molecular_virial(configuration);
-        return real + kspace;
+        let background = ewald.background_virial(configuration);
+        return real + kspace + background;
     }
 }
 
 impl CoulombicPotential for SharedEwald {
     fn set_restriction(&mut self, restriction: PairRestriction) {
+        if let PairRestriction::ExcludeBonded(depth) = restriction {
+            assert!(
+                depth <= 3,
+                "ExcludeBonded restriction with more than three bonds are not implemented with Ewald"
+            );
+        }
         self.write().restriction = restriction;
     }
+
+    fn restriction(&self) -> PairRestriction {
+        self.read().restriction
+    }
+
+    fn parameters(&self) -> Option<PotentialParameters> {
+        let ewald = self.read();
+        Some(PotentialParameters {
+            name: "ewald",
+            values: vec![
+                ("cutoff", ewald.rc),
+                ("alpha", ewald.alpha),
+                ("kmax", ewald.kmax as f64),
+                ("background_correction", if ewald.background_correction { 1.0 } else { 0.0 }),
+            ],
+        })
+    }
 }
 
 impl GlobalCache for SharedEwald {
@@ -979,7 +1051,7 @@ impl GlobalCache for SharedEwald {
         let mut ewald = self.write();
         ewald.precompute(&configuration.cell);
         let real = ewald.real_space_move_molecule_cost(configuration, molecule_id, new_positions);
-        /* No self cost */
+        /* No self or background cost */
         let kspace = ewald.kspace_move_molecule_cost(configuration, molecule_id, new_positions);
         return real + kspace;
     }
@@ -1040,6 +1112,137 @@ mod tests {
         assert_eq!(ewald.kmax, 5);
     }
 
+    #[test]
+    fn madelung_nacl() {
+        use crate::{GlobalPotential, Molecule, Particle, UnitCell};
+        use approx::assert_relative_eq;
+
+        // NaCl rock-salt structure, with 2x2x2 conventional cells
+        let a = 5.64;
+        let mut system = System::with_cell(UnitCell::cubic(2.0 * a));
+        for i in 0..4 {
+            for j in 0..4 {
+                for k in 0..4 {
+                    let (name, charge) = if (i + j + k) % 2 == 0 {("Na", 1.0)} else {("Cl", -1.0)};
+                    let mut particle = Particle::with_position(
+                        name, Vector3D::new(i as f64, j as f64, k as f64) * a / 2.0
+                    );
+                    particle.charge = charge;
+                    system.add_molecule(Molecule::new(particle));
+                }
+            }
+        }
+        assert_eq!(system.total_charge(), 0.0);
+
+        let accuracy = 1e-6;
+        let ewald = SharedEwald::new(Ewald::with_accuracy(5.5, accuracy, &system));
+
+        const MADELUNG: f64 = 1.747564594633182;
+        let nearest = a / 2.0;
+        let expected = -(system.size() as f64) / 2.0 * MADELUNG / (FOUR_PI_EPSILON_0 * nearest);
+        // the accuracy is the estimated relative error on the forces, the
+        // error on the energy is of the same order of magnitude
+        assert_relative_eq!(ewald.energy(&system), expected, max_relative = 10.0 * accuracy);
+    }
+
+    #[test]
+    fn background_correction() {
+        use crate::{GlobalPotential, Molecule, Particle, UnitCell};
+        use approx::assert_relative_eq;
+
+        let length = 10.0;
+        let mut system = System::with_cell(UnitCell::cubic(length));
+        let mut particle = Particle::new("Na");
+        particle.charge = 1.0;
+        system.add_molecule(Molecule::new(particle));
+
+        let mut ewald = Ewald::new(4.9, 10, None);
+        assert!(!ewald.background_correction());
+        ewald.set_background_correction(true);
+        assert!(ewald.background_correction());
+        let ewald = SharedEwald::new(ewald);
+
+        // Energy of a simple cubic lattice of unit charges in a neutralizing
+        // background, using the Wigner constant for this lattice
+        const WIGNER_SC: f64 = -2.837297479;
+        let expected = WIGNER_SC / (2.0 * length) / FOUR_PI_EPSILON_0;
+        assert_relative_eq!(ewald.energy(&system), expected, max_relative = 1e-6);
+
+        // The energy scales as 1 / L, so the pressure is P = - dE/dV = E / 3V
+        let virial = ewald.atomic_virial(&system);
+        assert_relative_eq!(virial.trace(), expected, max_relative = 1e-6);
+    }
+
+    mod cell_list {
+        use super::*;
+        use crate::{Molecule, Particle, UnitCell};
+        use approx::assert_relative_eq;
+
+        /// NaCl rock-salt structure, with 4x4x4 slightly perturbed
+        /// conventional cells
+        fn nacl_crystal() -> System {
+            let a = 5.64;
+            let mut system = System::with_cell(UnitCell::cubic(4.0 * a));
+            for i in 0..8 {
+                for j in 0..8 {
+                    for k in 0..8 {
+                        let (name, charge) = if (i + j + k) % 2 == 0 {("Na", 1.0)} else {("Cl", -1.0)};
+                        let index = (i * 64 + j * 8 + k) as f64;
+                        let noise = Vector3D::new(f64::sin(index), f64::cos(index), f64::sin(2.0 * index));
+                        let position = Vector3D::new(i as f64, j as f64, k as f64) * a / 2.0 + 0.3 * noise;
+                        let mut particle = Particle::with_position(name, position);
+                        particle.charge = charge;
+                        system.add_molecule(Molecule::new(particle));
+                    }
+                }
+            }
+            return system;
+        }
+
+        #[test]
+        fn real_space_brute_force() {
+            let system = nacl_crystal();
+            let ewald = Ewald::new(5.5, 5, None);
+            // Make sure the cell list is actually used
+            let mut candidates = 0;
+            CellList::new(&system, 5.5).for_each_neighbor(0, |_| candidates += 1);
+            assert!(candidates < system.size() - 1);
+
+            let charges = system.particles().charge;
+            let mut energy = 0.0;
+            let mut forces = vec![Vector3D::zero(); system.size()];
+            let mut virial = Matrix3::zero();
+            for i in 0..system.size() {
+                for j in (i + 1)..system.size() {
+                    let info = ewald.restriction.information_between(&system, i, j);
+                    let rij = system.nearest_image(i, j);
+                    let qiqj = charges[i] * charges[j];
+                    energy += ewald.real_space_energy_pair(info, qiqj, rij.norm());
+
+                    let force = ewald.real_space_force_pair(info, qiqj, rij.norm()) * rij;
+                    forces[i] += force;
+                    forces[j] -= force;
+                    virial += force.tensorial(&rij);
+                }
+            }
+
+            assert_relative_eq!(ewald.real_space_energy(&system), energy, max_relative = 1e-12);
+
+            let mut actual = vec![Vector3D::zero(); system.size()];
+            ewald.real_space_forces(&system, &mut actual);
+            for (actual, expected) in actual.iter().zip(&forces) {
+                assert_relative_eq!(actual, expected, epsilon = 1e-12);
+            }
+
+            let actual = ewald.real_space_atomic_virial(&system);
+            for i in 0..3 {
+                for j in 0..3 {
+                    assert_relative_eq!(actual[i][j], virial[i][j], max_relative = 1e-10);
+                }
+            }
+        }
+    }
+
     mod errors {
         use super::*;
         use crate::GlobalPotential;
@@ -5,7 +5,7 @@
 //!
 //! They can be coulombic potentials, or external provided potential function
 //! for example.
-use crate::PairRestriction;
+use crate::{PairRestriction, PotentialParameters};
 use crate::Configuration;
 use crate::{Matrix3, Vector3D};
 
@@ -199,6 +199,16 @@ pub trait CoulombicPotential: GlobalPotential + BoxCloneCoulombic {
     /// future call to `GlobalPotential::energy`, `GlobalPotential::force` or
     /// `GlobalPotential::virial` should use this restriction.
     fn set_restriction(&mut self, restriction: PairRestriction);
+
+    /// Get the pair restriction scheme used by this solver.
+    fn restriction(&self) -> PairRestriction;
+
+    /// Get the name and the parameters of this solver, used to report the
+    /// interactions in a system. The default implementation returns `None`,
+    /// meaning that the solver can not be described.
+    fn parameters(&self) -> Option<PotentialParameters> {
+        None
+    }
 }
 
 impl_box_clone!(CoulombicPotential, BoxCloneCoulombic, box_clone_coulombic);
@@ -208,3 +218,6 @@ pub use self::wolf::Wolf;
 
 mod ewald;
 pub use self::ewald::{Ewald, SharedEwald};
+
+mod eam;
+pub use self::eam::Eam;
@@ -6,7 +6,7 @@ use rayon::prelude::*;
 
 use crate::math::{exp, erfc};
 use crate::consts::FOUR_PI_EPSILON_0;
-use crate::PairRestriction;
+use crate::{PairRestriction, PotentialParameters};
 use crate::utils::ThreadLocalVec;
 use crate::Configuration;
 use crate::{Matrix3, Vector3D};
@@ -146,8 +146,7 @@ impl GlobalCache for Wolf {
                         continue;
                     }
 
-                    let path = configuration.bond_path(part_i, part_j);
-                    let info = self.restriction.information(path);
+                    let info = self.restriction.information_between(configuration, part_i, part_j);
                     if info.excluded {
                         continue;
                     }
@@ -191,8 +190,7 @@ impl GlobalPotential for Wolf {
                     continue;
                 }
 
-                let path = configuration.bond_path(i, j);
-                let info = self.restriction.information(path);
+                let info = self.restriction.information_between(configuration, i, j);
                 if info.excluded {
                     continue;
                 }
@@ -229,8 +227,7 @@ impl GlobalPotential for Wolf {
                     continue;
                 }
 
-                let path = configuration.bond_path(i, j);
-                let info = self.restriction.information(path);
+                let info = self.restriction.information_between(configuration, i, j);
                 if info.excluded {
                     continue;
                 }
@@ -265,8 +262,7 @@ impl GlobalPotential for Wolf {
                     continue;
                 }
 
-                let path = configuration.bond_path(i, j);
-                let info = self.restriction.information(path);
+                let info = self.restriction.information_between(configuration, i, j);
                 if info.excluded {
                     continue;
                 }
@@ -305,8 +301,7 @@ impl GlobalPotential for Wolf {
                             continue;
                         }
 
-                        let path = configuration.bond_path(part_a, part_b);
-                        let info = self.restriction.information(path);
+                        let info = self.restriction.information_between(configuration, part_a, part_b);
                         if info.excluded {
                             continue;
                         }
@@ -328,6 +323,17 @@ impl CoulombicPotential for Wolf {
     fn set_restriction(&mut self, restriction: PairRestriction) {
         self.restriction = restriction;
     }
+
+    fn restriction(&self) -> PairRestriction {
+        self.restriction
+    }
+
+    fn parameters(&self) -> Option<PotentialParameters> {
+        Some(PotentialParameters {
+            name: "wolf",
+            values: vec![("cutoff", self.cutoff), ("alpha", self.alpha)],
+        })
+    }
 }
 
 #[cfg(test)]
@@ -20,7 +20,18 @@
 //! - [`BondPotential`][BondPotential] for covalent bonds interactions;
 //! - [`AnglePotential`][AnglePotential] for covalent angles interactions;
 //! - [`DihedralPotential`][DihedralPotential] for covalent dihedral angles
-//!   interactions.
+//!   interactions;
+//! - [`ImproperPotential`][ImproperPotential] for improper dihedral angles
+//!   (out-of-plane) interactions.
+//!
+//! Non-bonded interactions between triplets of particles, coupling distances
+//! and angles, are represented by the [`ThreeBodyPotential`][ThreeBodyPotential]
+//! trait. Cross terms between the bonds and the angles in class II force
+//! fields are represented by the [`CrossTermPotential`][CrossTermPotential]
+//! trait. Potentials acting on each particle independently, such as restraints
+//! or external fields, are represented by the
+//! [`ExternalPotential`][ExternalPotential] trait. The [`Wall`][Wall] trait is
+//! a simpler interface for external potentials confining the particles.
 //!
 //! ```
 //! use lumol_core::energy::{Potential, PairPotential, DihedralPotential};
@@ -50,15 +61,26 @@
 //! once. They are defined by implementing the [`GlobalPotential`]
 //! [GlobalPotential] trait. [`CoulombicPotential`][CoulombicPotential] are a
 //! specific version of global potentials used to compute electrostatic
-//! interactions.
+//! interactions. [`HarmonicBias`][HarmonicBias] and
+//! [`Metadynamics`][Metadynamics] are global potentials biasing a
+//! [`CollectiveVariable`][CollectiveVariable], for umbrella sampling and
+//! metadynamics.
 //!
 //! [Potential]: trait.Potential.html
 //! [PairPotential]: trait.PairPotential.html
 //! [BondPotential]: trait.BondPotential.html
 //! [AnglePotential]: trait.AnglePotential.html
 //! [DihedralPotential]: trait.DihedralPotential.html
+//! [ImproperPotential]: trait.ImproperPotential.html
+//! [ThreeBodyPotential]: trait.ThreeBodyPotential.html
+//! [CrossTermPotential]: trait.CrossTermPotential.html
+//! [ExternalPotential]: trait.ExternalPotential.html
+//! [Wall]: trait.Wall.html
 //! [GlobalPotential]: trait.GlobalPotential.html
 //! [CoulombicPotential]: trait.CoulombicPotential.html
+//! [HarmonicBias]: struct.HarmonicBias.html
+//! [Metadynamics]: struct.Metadynamics.html
+//! [CollectiveVariable]: trait.CollectiveVariable.html
 use crate::{Matrix3, Vector3D};
 
 /// A potential for force and energy computations.
@@ -100,6 +122,23 @@ pub trait Potential: Sync + Send {
     fn energy(&self, x: f64) -> f64;
     /// Get the force norm corresponding to the variable `x`
     fn force(&self, x: f64) -> f64;
+
+    /// Get the name and the parameters of this potential, used to report
+    /// the interactions in a system. The default implementation returns
+    /// `None`, meaning that the potential can not be described.
+    fn parameters(&self) -> Option<PotentialParameters> {
+        None
+    }
+}
+
+/// Description of a potential, as returned by `Potential::parameters` or
+/// `CoulombicPotential::parameters`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PotentialParameters {
+    /// Name of the potential, matching the name used in input files
+    pub name: &'static str,
+    /// Names and values of the potential parameters, in internal units
+    pub values: Vec<(&'static str, f64)>,
 }
 
 /// Marker trait for potentials that can be used for non-bonded two body
@@ -160,6 +199,25 @@ pub trait PairPotential: Potential + BoxClonePair {
     /// If this integral does not converge for the current potential, this
     /// function should then return 0.0 to disable tail corrections.
     fn tail_virial(&self, cutoff: f64) -> f64;
+
+    /// Compute the derivative of the energy with respect to the coupling
+    /// parameter `lambda` at the distance `r`, for potentials depending on
+    /// such a parameter. This is used in thermodynamic integration.
+    ///
+    /// The default implementation returns 0, which is the right value for
+    /// potentials that do not depend on a coupling parameter.
+    fn du_dlambda(&self, _r: f64) -> f64 {
+        0.0
+    }
+
+    /// Compute the tail correction to the derivative of the energy with
+    /// respect to the coupling parameter `lambda`, for the given cutoff.
+    ///
+    /// This function should return the integral from `cutoff` to infinity of
+    /// `r^2 dV/dlambda`. The default implementation returns 0.
+    fn tail_du_dlambda(&self, _cutoff: f64) -> f64 {
+        0.0
+    }
 }
 impl_box_clone!(PairPotential, BoxClonePair, box_clone_pair);
 
@@ -238,20 +296,91 @@ impl_box_clone!(AnglePotential, BoxCloneAngle, box_clone_angle);
 pub trait DihedralPotential: Potential + BoxCloneDihedral {}
 impl_box_clone!(DihedralPotential, BoxCloneDihedral, box_clone_dihedral);
 
+/// Marker trait for potentials that can be used for improper dihedral
+/// angles, keeping planar groups of atoms planar.
+///
+/// # Example
+///
+/// ```
+/// use lumol_core::energy::{Potential, ImproperPotential};
+///
+/// // A no-op potential
+/// #[derive(Clone)]
+/// struct Null;
+///
+/// impl Potential for Null {
+///     fn energy(&self, x: f64) -> f64 {0.0}
+///     fn force(&self, x: f64) -> f64 {0.0}
+/// }
+///
+/// // Now we can use the Null potential for improper dihedral angles
+/// impl ImproperPotential for Null {}
+///
+/// let potential: Box<dyn ImproperPotential> = Box::new(Null);
+/// assert_eq!(potential.energy(0.1), 0.0);
+/// ```
+pub trait ImproperPotential: Potential + BoxCloneImproper {}
+impl_box_clone!(ImproperPotential, BoxCloneImproper, box_clone_improper);
+
+/// Marker trait for potentials that can be used for planar walls, as a
+/// function of the distance between a particle and the wall plane.
+///
+/// # Example
+///
+/// ```
+/// use lumol_core::energy::{Potential, WallPotential};
+///
+/// // A no-op potential
+/// #[derive(Clone)]
+/// struct Null;
+///
+/// impl Potential for Null {
+///     fn energy(&self, x: f64) -> f64 {0.0}
+///     fn force(&self, x: f64) -> f64 {0.0}
+/// }
+///
+/// // Now we can use the Null potential for planar walls
+/// impl WallPotential for Null {}
+///
+/// let potential: Box<dyn WallPotential> = Box::new(Null);
+/// assert_eq!(potential.energy(1.0), 0.0);
+/// ```
+pub trait WallPotential: Potential + BoxCloneWallPotential {}
+impl_box_clone!(WallPotential, BoxCloneWallPotential, box_clone_wall_potential);
+
 mod functions;
 pub use self::functions::{BornMayerHuggins, Buckingham, Gaussian, Morse, Torsion};
 pub use self::functions::{CosineHarmonic, Harmonic, LennardJones, NullPotential};
-pub use self::functions::Mie;
+pub use self::functions::{LennardJones124, Mie, Quartic, Scaled, WCA};
+pub use self::functions::RyckaertBellemans;
 
 mod computations;
-pub use self::computations::{Computation, TableComputation};
+pub use self::computations::{Computation, ScaledComputation, SoftCore, TableComputation};
+
+mod tabulated;
+pub use self::tabulated::FileTable;
+
+mod three_body;
+pub use self::three_body::{ThreeBodyPotential, StillingerWeber};
+
+mod cross_terms;
+pub use self::cross_terms::{BondAngle, CrossTermPotential};
+
+mod external;
+pub use self::external::{ElectricField, ExternalPotential, PositionRestraint};
+
+mod bias;
+pub use self::bias::{CollectiveVariable, Coordination, DistanceCV, HarmonicBias, Metadynamics};
+
+mod walls;
+pub use self::walls::{LennardJones93, PlanarWall, SphericalWall, Wall};
 
 mod restrictions;
 pub use self::restrictions::{PairRestriction, RestrictionInfo, BondPath};
 
 mod global;
 pub use self::global::{CoulombicPotential, GlobalCache, GlobalPotential};
-pub use self::global::{Ewald, SharedEwald, Wolf};
+pub use self::global::{Eam, Ewald, SharedEwald, Wolf};
 
 mod pairs;
 pub use self::pairs::PairInteraction;
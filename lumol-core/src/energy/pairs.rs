@@ -1,7 +1,7 @@
 // Lumol, an extensible molecular simulation engine
 // Copyright (C) Lumol's contributors — BSD license
 
-use crate::{PairPotential, PairRestriction};
+use crate::{NullPotential, PairPotential, PairRestriction};
 use crate::{Matrix3, Vector3D};
 
 /// The different way to compute non-bonded pair interactions
@@ -35,6 +35,8 @@ pub struct PairInteraction {
     tail: bool,
     /// The computation mode
     computation: PairComputation,
+    /// Is this pair excluded from all non-bonded computations?
+    excluded: bool,
 }
 
 impl PairInteraction {
@@ -61,6 +63,7 @@ impl PairInteraction {
             restriction: PairRestriction::None,
             computation: PairComputation::Cutoff,
             tail: false,
+            excluded: false,
         }
     }
 
@@ -91,9 +94,33 @@ impl PairInteraction {
             restriction: PairRestriction::None,
             computation: PairComputation::Shifted(shift),
             tail: false,
+            excluded: false,
         }
     }
 
+    /// Create a new `PairInteraction` marking a pair of particles types as
+    /// not interacting at all. Excluded pairs are skipped before computing
+    /// the distance between the particles in the energy, forces and virial
+    /// computations, which is faster than using a
+    /// [`NullPotential`](struct.NullPotential.html) for systems with a lot
+    /// of non-interacting pairs.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lumol_core::energy::PairInteraction;
+    ///
+    /// let interaction = PairInteraction::excluded();
+    /// assert!(interaction.is_excluded());
+    /// assert_eq!(interaction.energy(1.0), 0.0);
+    /// assert_eq!(interaction.cutoff(), 0.0);
+    /// ```
+    pub fn excluded() -> PairInteraction {
+        let mut interaction = PairInteraction::new(Box::new(NullPotential), 0.0);
+        interaction.excluded = true;
+        return interaction;
+    }
+
     /// Enable the use of tail corrections for energy and virial contribution
     /// of this pair interaction.
     ///
@@ -164,6 +191,80 @@ impl PairInteraction {
     pub fn cutoff(&self) -> f64 {
         self.cutoff
     }
+
+    /// Get the potential used in this interaction
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lumol_core::energy::{PairInteraction, Potential};
+    /// use lumol_core::energy::LennardJones;
+    ///
+    /// let ar = LennardJones{sigma: 3.405, epsilon: 1.0};
+    /// let interaction = PairInteraction::new(Box::new(ar), 9.1935);
+    /// assert_eq!(interaction.potential().energy(4.0), ar.energy(4.0));
+    /// ```
+    pub fn potential(&self) -> &dyn PairPotential {
+        &*self.potential
+    }
+
+    /// Check if this interaction uses tail corrections
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lumol_core::energy::PairInteraction;
+    /// use lumol_core::energy::LennardJones;
+    ///
+    /// let ar = LennardJones{sigma: 3.405, epsilon: 1.0};
+    /// let mut interaction = PairInteraction::new(Box::new(ar), 9.1935);
+    /// assert!(!interaction.tail_corrections());
+    ///
+    /// interaction.enable_tail_corrections();
+    /// assert!(interaction.tail_corrections());
+    /// ```
+    pub fn tail_corrections(&self) -> bool {
+        self.tail
+    }
+
+    /// Check if this interaction uses a shifted potential
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lumol_core::energy::PairInteraction;
+    /// use lumol_core::energy::LennardJones;
+    ///
+    /// let ar = LennardJones{sigma: 3.405, epsilon: 1.0};
+    /// let interaction = PairInteraction::new(Box::new(ar), 9.1935);
+    /// assert!(!interaction.is_shifted());
+    ///
+    /// let interaction = PairInteraction::shifted(Box::new(ar), 9.1935);
+    /// assert!(interaction.is_shifted());
+    /// ```
+    pub fn is_shifted(&self) -> bool {
+        match self.computation {
+            PairComputation::Cutoff => false,
+            PairComputation::Shifted(_) => true,
+        }
+    }
+
+    /// Check if this interaction was created with
+    /// [`PairInteraction::excluded`](struct.PairInteraction.html#method.excluded).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lumol_core::energy::{PairInteraction, NullPotential};
+    ///
+    /// assert!(PairInteraction::excluded().is_excluded());
+    ///
+    /// let interaction = PairInteraction::new(Box::new(NullPotential), 5.0);
+    /// assert!(!interaction.is_excluded());
+    /// ```
+    pub fn is_excluded(&self) -> bool {
+        self.excluded
+    }
 }
 
 impl PairInteraction {
@@ -294,12 +395,67 @@ impl PairInteraction {
             return Matrix3::zero();
         }
     }
+
+    /// Get the derivative of the energy with respect to the coupling
+    /// parameter of the potential at the distance `r`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lumol_core::energy::{PairInteraction, Potential};
+    /// use lumol_core::energy::{LennardJones, ScaledComputation};
+    ///
+    /// let lj = LennardJones{sigma: 0.5, epsilon: 4.2};
+    /// let potential = Box::new(ScaledComputation::new(Box::new(lj), 0.5));
+    /// let interaction = PairInteraction::new(potential, 2.0);
+    ///
+    /// assert_eq!(interaction.du_dlambda(1.0), lj.energy(1.0));
+    /// assert_eq!(interaction.du_dlambda(2.0), 0.0);
+    /// ```
+    pub fn du_dlambda(&self, r: f64) -> f64 {
+        if r >= self.cutoff {
+            0.0
+        } else {
+            let derivative = self.potential.du_dlambda(r);
+            match self.computation {
+                PairComputation::Cutoff => derivative,
+                // The shift also depends on the coupling parameter
+                PairComputation::Shifted(_) => derivative - self.potential.du_dlambda(self.cutoff),
+            }
+        }
+    }
+
+    /// Get the tail correction to the derivative of the energy with respect
+    /// to the coupling parameter for this pair interaction
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lumol_core::energy::{PairInteraction, PairPotential};
+    /// use lumol_core::energy::{LennardJones, ScaledComputation};
+    ///
+    /// let lj = LennardJones{sigma: 0.5, epsilon: 4.2};
+    /// let potential = Box::new(ScaledComputation::new(Box::new(lj), 0.5));
+    /// let mut interaction = PairInteraction::new(potential, 2.0);
+    /// interaction.enable_tail_corrections();
+    ///
+    /// assert_eq!(interaction.tail_du_dlambda(), lj.tail_energy(2.0));
+    /// ```
+    pub fn tail_du_dlambda(&self) -> f64 {
+        if self.tail {
+            self.potential.tail_du_dlambda(self.cutoff)
+        } else {
+            0.0
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{LennardJones, NullPotential, PairRestriction};
+    use crate::{LennardJones, NullPotential, PairRestriction, ScaledComputation};
+    use crate::{BornMayerHuggins, Buckingham, Gaussian, Harmonic, LennardJones124, Mie, Morse, WCA};
+    use crate::{FileTable, SoftCore, TableComputation};
     use crate::Potential;
 
     use approx::assert_ulps_eq;
@@ -330,6 +486,53 @@ mod tests {
         assert_eq!(pairs.cutoff(), 4.0);
     }
 
+    #[test]
+    fn zero_after_cutoff() {
+        let lj = LennardJones {
+            sigma: 1.0,
+            epsilon: 2.0,
+        };
+        let potentials: Vec<Box<dyn PairPotential>> = vec![
+            Box::new(lj),
+            Box::new(WCA { sigma: 3.4, epsilon: 2.0 }),
+            Box::new(LennardJones124 { sigma: 1.0, epsilon: 2.0, c4: 0.5 }),
+            Box::new(Harmonic { k: 50.0, x0: 1.0 }),
+            Box::new(Buckingham { a: 2.0, c: 1.0, rho: 0.5 }),
+            Box::new(BornMayerHuggins { a: 2.0, c: 1.0, d: 0.5, sigma: 1.0, rho: 0.5 }),
+            Box::new(Morse { a: 2.0, x0: 1.0, depth: 3.0 }),
+            Box::new(Gaussian::new(3.0, 0.1)),
+            Box::new(Mie::new(1.0, 2.0, 12.0, 6.0)),
+            Box::new(FileTable::new(vec![1.0, 2.0, 3.0], vec![4.0, 2.0, 1.0], None)),
+            Box::new(TableComputation::new(Box::new(lj), 1000, 5.0)),
+            Box::new(ScaledComputation::new(Box::new(lj), 0.5)),
+            Box::new(SoftCore::new(Box::new(lj), 1.0, 0.5, 0.5)),
+        ];
+
+        let cutoff: f64 = 4.0;
+        let after = [
+            cutoff,
+            f64::from_bits(cutoff.to_bits() + 1),
+            cutoff + 1e-10,
+            1.01 * cutoff,
+            10.0 * cutoff,
+        ];
+        for potential in potentials {
+            let interactions = [
+                PairInteraction::new(potential.clone(), cutoff),
+                PairInteraction::shifted(potential, cutoff),
+            ];
+            for interaction in &interactions {
+                assert_ne!(interaction.force(0.9 * cutoff), 0.0);
+                for &r in &after {
+                    assert_eq!(interaction.energy(r), 0.0);
+                    assert_eq!(interaction.force(r), 0.0);
+                    assert_eq!(interaction.du_dlambda(r), 0.0);
+                    assert_eq!(interaction.virial(&Vector3D::new(0.0, r, 0.0)), Matrix3::zero());
+                }
+            }
+        }
+    }
+
     #[test]
     fn shifted() {
         let lj = LennardJones {
@@ -357,4 +560,23 @@ mod tests {
         assert_eq!(pairs.tail_energy(), -0.041663275824652776);
         assert_ulps_eq!(pairs.tail_virial().trace(), -0.24995930989583334);
     }
+
+    #[test]
+    fn du_dlambda() {
+        let lj = LennardJones {
+            sigma: 1.0,
+            epsilon: 2.0,
+        };
+        let pairs = PairInteraction::new(Box::new(lj), 4.0);
+        assert_eq!(pairs.du_dlambda(2.5), 0.0);
+
+        let scaled = ScaledComputation::new(Box::new(lj), 0.5);
+        let mut pairs = PairInteraction::shifted(Box::new(scaled), 4.0);
+        assert_ulps_eq!(pairs.du_dlambda(2.5), lj.energy(2.5) - lj.energy(4.0));
+        assert_eq!(pairs.du_dlambda(4.1), 0.0);
+
+        assert_eq!(pairs.tail_du_dlambda(), 0.0);
+        pairs.enable_tail_corrections();
+        assert_eq!(pairs.tail_du_dlambda(), lj.tail_energy(4.0));
+    }
 }
@@ -2,6 +2,7 @@
 // Copyright (C) Lumol's contributors — BSD license
 
 //! Encoding restrictions in interactions.
+use crate::Configuration;
 
 /// Possible restrictions on the pair interactions.
 ///
@@ -30,6 +31,12 @@ pub enum PairRestriction {
     /// position, and scale the interaction for pairs in 1-4 position (separated
     /// by three bonds).
     Scale14(f64),
+    /// Only apply the interaction to pairs which are separated by more than
+    /// the given number of bonds. `ExcludeBonded(3)` is equivalent to
+    /// `Exclude14`. For more than three bonds, the restriction information
+    /// must be computed with
+    /// [`PairRestriction::information_between`](enum.PairRestriction.html#method.information_between).
+    ExcludeBonded(usize),
 }
 
 /// Shortest bond path between two particles in a system
@@ -62,7 +69,13 @@ pub struct RestrictionInfo {
 impl PairRestriction {
     /// Get the restriction at the given [bond `path`][path].
     ///
+    /// `ExcludeBonded` restrictions with more than three bonds can not be
+    /// fully described by the bond path, and pairs separated by more than
+    /// three bonds are never excluded by this function. Use
+    /// [`PairRestriction::information_between`][between] to handle them.
+    ///
     /// [path]: ../sys/struct.System.html#method.bond_path
+    /// [between]: enum.PairRestriction.html#method.information_between
     ///
     /// # Example
     ///
@@ -81,6 +94,10 @@ impl PairRestriction {
     /// assert_eq!(restriction.information(BondPath::ThreeBonds).excluded, false);
     /// assert_eq!(restriction.information(BondPath::TwoBonds).scaling, 1.0);
     /// assert_eq!(restriction.information(BondPath::ThreeBonds).scaling, 0.5);
+    ///
+    /// let restriction = PairRestriction::ExcludeBonded(2);
+    /// assert_eq!(restriction.information(BondPath::TwoBonds).excluded, true);
+    /// assert_eq!(restriction.information(BondPath::ThreeBonds).excluded, false);
     /// ```
     pub fn information(&self, path: BondPath) -> RestrictionInfo {
         let are_in_same_molecule = path != BondPath::None;
@@ -95,6 +112,14 @@ impl PairRestriction {
             PairRestriction::Exclude14 => {
                 path == BondPath::OneBond || path == BondPath::TwoBonds || path == BondPath::ThreeBonds
             },
+            PairRestriction::ExcludeBonded(depth) => {
+                match path {
+                    BondPath::OneBond => depth >= 1,
+                    BondPath::TwoBonds => depth >= 2,
+                    BondPath::ThreeBonds => depth >= 3,
+                    BondPath::None | BondPath::SameParticle | BondPath::Far => false,
+                }
+            }
         };
 
         let scaling = if let PairRestriction::Scale14(scaling) = *self {
@@ -112,13 +137,34 @@ impl PairRestriction {
             scaling: scaling,
         }
     }
+
+    /// Get the restriction for the pair of particles `i` and `j` in the
+    /// `configuration`. This is the same as
+    /// [`PairRestriction::information`](enum.PairRestriction.html#method.information)
+    /// with the bond path between `i` and `j`, except for `ExcludeBonded`
+    /// restrictions with more than three bonds, where the pairs precomputed
+    /// by the configuration up to its
+    /// [exclusion depth](../sys/struct.Configuration.html#method.set_exclusion_depth)
+    /// are used.
+    pub fn information_between(&self, configuration: &Configuration, i: usize, j: usize) -> RestrictionInfo {
+        let path = configuration.bond_path(i, j);
+        if let PairRestriction::ExcludeBonded(depth) = *self {
+            if depth > 3 && path == BondPath::Far {
+                return RestrictionInfo {
+                    excluded: configuration.are_within_bonds(i, j, depth),
+                    scaling: 1.0,
+                };
+            }
+        }
+        return self.information(path);
+    }
 }
 
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{Molecule, Particle, System};
+    use crate::{Molecule, NullPotential, PairInteraction, Particle, System};
 
     fn testing_system() -> System {
         // Creating 2 pentane molecule
@@ -293,4 +339,67 @@ mod tests {
         assert_eq!(restriction.information(system.bond_path(0, 4)).excluded, false);
         assert_eq!(restriction.information(system.bond_path(8, 2)).excluded, false);
     }
+
+    #[test]
+    fn exclude_bonded() {
+        let system = testing_system();
+        for &depth in &[1, 2, 3, 4] {
+            let restriction = PairRestriction::ExcludeBonded(depth);
+            for i in 0..10 {
+                for j in 0..10 {
+                    let info = restriction.information_between(&system, i, j);
+                    assert_eq!(info.scaling, 1.0);
+
+                    if system.are_in_same_molecule(i, j) && i != j {
+                        // linear chains, the number of bonds is the
+                        // difference of indexes
+                        let bonds = (i as isize - j as isize).abs() as usize;
+                        assert_eq!(info.excluded, bonds <= depth);
+                    } else {
+                        assert_eq!(info.excluded, false);
+                    }
+                }
+            }
+        }
+
+        let restriction = PairRestriction::ExcludeBonded(2);
+        assert_eq!(restriction.information_between(&system, 0, 1).excluded, true);
+        assert_eq!(restriction.information_between(&system, 0, 2).excluded, true);
+        assert_eq!(restriction.information_between(&system, 0, 3).excluded, false);
+        assert_eq!(restriction.information_between(&system, 6, 9).excluded, false);
+
+        let restriction = PairRestriction::ExcludeBonded(3);
+        assert_eq!(restriction.information_between(&system, 0, 2).excluded, true);
+        assert_eq!(restriction.information_between(&system, 0, 3).excluded, true);
+        assert_eq!(restriction.information_between(&system, 6, 9).excluded, true);
+        assert_eq!(restriction.information_between(&system, 0, 4).excluded, false);
+        assert_eq!(restriction.information_between(&system, 4, 5).excluded, false);
+
+        let restriction = PairRestriction::ExcludeBonded(4);
+        assert_eq!(restriction.information_between(&system, 0, 4).excluded, true);
+        assert_eq!(restriction.information_between(&system, 5, 9).excluded, true);
+        assert_eq!(restriction.information_between(&system, 4, 5).excluded, false);
+    }
+
+    #[test]
+    fn exclusion_depth() {
+        let mut system = testing_system();
+        assert_eq!(system.exclusion_depth(), 3);
+
+        let mut pair = PairInteraction::new(Box::new(NullPotential), 10.0);
+        pair.set_restriction(PairRestriction::ExcludeBonded(4));
+        system.set_pair_potential(("CH2", "CH3"), pair);
+        assert_eq!(system.exclusion_depth(), 4);
+
+        let mut pair = PairInteraction::new(Box::new(NullPotential), 10.0);
+        pair.set_restriction(PairRestriction::Exclude13);
+        system.set_pair_potential(("CH3", "CH3"), pair);
+        assert_eq!(system.exclusion_depth(), 4);
+
+        // Molecules added later use the same exclusion depth
+        system.add_molecule(testing_system().molecule(0).to_owned());
+        let restriction = PairRestriction::ExcludeBonded(4);
+        assert_eq!(restriction.information_between(&system, 10, 14).excluded, true);
+        assert_eq!(restriction.information_between(&system, 9, 10).excluded, false);
+    }
 }
@@ -0,0 +1,320 @@
+// Lumol, an extensible molecular simulation engine
+// Copyright (C) Lumol's contributors — BSD license
+
+//! Pair potentials defined numerically, from tabulated values.
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
+
+use log_once::warn_once;
+
+use crate::{PairPotential, Potential};
+
+/// Pair potential using tabulated energy and force values, for example read
+/// from a data file.
+///
+/// The energy and the force are linearly interpolated between the tabulated
+/// points. If only the energy is available, the force is computed by finite
+/// differences from it. For distances outside of the tabulated range, the
+/// energy and the force are clamped to the values at the closest endpoint.
+///
+/// # Examples
+///
+/// ```
+/// # use lumol_core::energy::Potential;
+/// # use lumol_core::energy::FileTable;
+/// let distances = vec![1.0, 2.0, 3.0];
+/// let energies = vec![4.0, 2.0, 1.0];
+/// let potential = FileTable::new(distances, energies, None);
+///
+/// assert_eq!(potential.energy(1.5), 3.0);
+/// assert_eq!(potential.force(1.5), 1.75);
+/// ```
+#[derive(Clone)]
+pub struct FileTable {
+    /// Tabulated distances, in strictly increasing order
+    distances: Vec<f64>,
+    /// Tabulated energies at the corresponding distances
+    energies: Vec<f64>,
+    /// Tabulated forces at the corresponding distances
+    forces: Vec<f64>,
+}
+
+impl FileTable {
+    /// Create a new `FileTable` from the tabulated `distances`, `energies`
+    /// and optional `forces`. If `forces` is `None`, the forces are computed
+    /// from the energies using finite differences.
+    ///
+    /// # Panics
+    ///
+    /// If there are less than two points, if the tables do not have the same
+    /// size, or if the distances are not strictly increasing.
+    pub fn new(distances: Vec<f64>, energies: Vec<f64>, forces: Option<Vec<f64>>) -> FileTable {
+        if let Err(message) = check_tables(&distances, &energies, forces.as_deref()) {
+            panic!("{}", message);
+        }
+
+        let forces = forces.unwrap_or_else(|| finite_differences(&distances, &energies));
+        FileTable {
+            distances: distances,
+            energies: energies,
+            forces: forces,
+        }
+    }
+
+    /// Read a `FileTable` from the file at `path`. Each line of the file
+    /// should contain two (distance and energy) or three (distance, energy
+    /// and force) columns, separated by whitespace. Empty lines and lines
+    /// starting with `#` are ignored.
+    ///
+    /// The distances are multiplied by `distance_unit` and the energies by
+    /// `energy_unit` to convert them to internal units; forces are converted
+    /// with `energy_unit / distance_unit`.
+    ///
+    /// # Errors
+    ///
+    /// If the file can not be read, or if its content is not a valid table.
+    pub fn from_file<P: AsRef<Path>>(path: P, distance_unit: f64, energy_unit: f64) -> io::Result<FileTable> {
+        let file = BufReader::new(File::open(path)?);
+
+        let mut distances = Vec::new();
+        let mut energies = Vec::new();
+        let mut forces = Vec::new();
+        let mut columns = None;
+        for (i, line) in file.lines().enumerate() {
+            let line = line?;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let values = line.split_whitespace().map(|value| {
+                value.parse::<f64>().map_err(|_| invalid_table(format!(
+                    "invalid number '{}' at line {}", value, i + 1
+                )))
+            }).collect::<Result<Vec<_>, _>>()?;
+
+            if values.len() != 2 && values.len() != 3 {
+                return Err(invalid_table(format!(
+                    "expected 2 or 3 columns at line {}, got {}", i + 1, values.len()
+                )));
+            }
+
+            if *columns.get_or_insert(values.len()) != values.len() {
+                return Err(invalid_table(format!(
+                    "inconsistent number of columns at line {}", i + 1
+                )));
+            }
+
+            distances.push(values[0] * distance_unit);
+            energies.push(values[1] * energy_unit);
+            if values.len() == 3 {
+                forces.push(values[2] * energy_unit / distance_unit);
+            }
+        }
+
+        let forces = if columns == Some(3) {
+            Some(forces)
+        } else {
+            None
+        };
+        check_tables(&distances, &energies, forces.as_deref()).map_err(invalid_table)?;
+        return Ok(FileTable::new(distances, energies, forces));
+    }
+
+    /// Get the tabulated distances
+    pub fn distances(&self) -> &[f64] {
+        &self.distances
+    }
+
+    /// Linear interpolation of the `values` table at `r`
+    fn interpolate(&self, values: &[f64], r: f64) -> f64 {
+        let last = self.distances.len() - 1;
+        if r < self.distances[0] {
+            warn_once!(
+                "distance is smaller than the first tabulated distance ({}) in FileTable, \
+                using the value at the first point", self.distances[0]
+            );
+            return values[0];
+        } else if r >= self.distances[last] {
+            if r > self.distances[last] {
+                warn_once!(
+                    "distance is larger than the last tabulated distance ({}) in FileTable, \
+                    using the value at the last point", self.distances[last]
+                );
+            }
+            return values[last];
+        }
+
+        let bin = match self.distances.binary_search_by(|x| x.partial_cmp(&r).expect("got a NaN distance")) {
+            Ok(i) => return values[i],
+            Err(i) => i - 1,
+        };
+        let delta = self.distances[bin + 1] - self.distances[bin];
+        let slope = (values[bin + 1] - values[bin]) / delta;
+        return values[bin] + (r - self.distances[bin]) * slope;
+    }
+}
+
+impl Potential for FileTable {
+    fn energy(&self, r: f64) -> f64 {
+        self.interpolate(&self.energies, r)
+    }
+
+    fn force(&self, r: f64) -> f64 {
+        self.interpolate(&self.forces, r)
+    }
+}
+
+impl PairPotential for FileTable {
+    // There is no analytic expression for the potential after the last
+    // tabulated point, so we do not compute any tail correction.
+    fn tail_energy(&self, _: f64) -> f64 {
+        0.0
+    }
+
+    fn tail_virial(&self, _: f64) -> f64 {
+        0.0
+    }
+}
+
+fn invalid_table(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// Check that the given tables can be used in a `FileTable`
+fn check_tables(distances: &[f64], energies: &[f64], forces: Option<&[f64]>) -> Result<(), String> {
+    if distances.len() < 2 {
+        return Err(format!("tabulated potential needs at least two points, got {}", distances.len()));
+    }
+
+    if energies.len() != distances.len() {
+        return Err("tabulated potential must have as many energies as distances".into());
+    }
+
+    if let Some(forces) = forces {
+        if forces.len() != distances.len() {
+            return Err("tabulated potential must have as many forces as distances".into());
+        }
+    }
+
+    if distances.windows(2).any(|window| window[1] <= window[0]) {
+        return Err("distances must be strictly increasing in tabulated potential".into());
+    }
+    return Ok(());
+}
+
+/// Compute the forces `-dE/dr` from the tabulated `energies` using finite
+/// differences: centered in the middle of the table, one-sided at the ends.
+fn finite_differences(distances: &[f64], energies: &[f64]) -> Vec<f64> {
+    let n = distances.len();
+    let derivative = |i: usize, j: usize| (energies[j] - energies[i]) / (distances[j] - distances[i]);
+
+    let mut forces = Vec::with_capacity(n);
+    forces.push(-derivative(0, 1));
+    for i in 1..(n - 1) {
+        forces.push(-derivative(i - 1, i + 1));
+    }
+    forces.push(-derivative(n - 2, n - 1));
+    return forces;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::LennardJones;
+    use approx::assert_relative_eq;
+
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    fn lennard_jones_file(forces: bool) -> NamedTempFile {
+        let lj = LennardJones { sigma: 3.0, epsilon: 0.5 };
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(file, "# tabulated Lennard-Jones").unwrap();
+        for i in 0..=500 {
+            let r = 2.5 + i as f64 * 0.01;
+            if forces {
+                writeln!(file, "{} {} {}", r, lj.energy(r), lj.force(r)).unwrap();
+            } else {
+                writeln!(file, "{} {}", r, lj.energy(r)).unwrap();
+            }
+        }
+        return file;
+    }
+
+    #[test]
+    fn lennard_jones() {
+        let lj = LennardJones { sigma: 3.0, epsilon: 0.5 };
+        let file = lennard_jones_file(true);
+        let table = FileTable::from_file(file.path(), 1.0, 1.0).unwrap();
+        assert_eq!(table.distances().len(), 501);
+
+        for &r in &[2.7, 3.0, 3.3, 3.37, 4.0, 5.5, 7.45] {
+            assert_relative_eq!(table.energy(r), lj.energy(r), max_relative = 1e-3);
+            assert_relative_eq!(table.force(r), lj.force(r), max_relative = 1e-3, epsilon = 1e-4);
+        }
+
+        // only energies
+        let file = lennard_jones_file(false);
+        let table = FileTable::from_file(file.path(), 1.0, 1.0).unwrap();
+        for &r in &[2.7, 3.3, 4.0, 5.5, 7.45] {
+            assert_relative_eq!(table.energy(r), lj.energy(r), max_relative = 1e-3);
+            assert_relative_eq!(table.force(r), lj.force(r), max_relative = 1e-2, epsilon = 1e-4);
+        }
+    }
+
+    #[test]
+    fn units() {
+        let file = lennard_jones_file(true);
+        let reference = FileTable::from_file(file.path(), 1.0, 1.0).unwrap();
+        let table = FileTable::from_file(file.path(), 2.0, 10.0).unwrap();
+
+        assert_eq!(table.distances()[0], 5.0);
+        assert_relative_eq!(table.energy(8.0), 10.0 * reference.energy(4.0));
+        assert_relative_eq!(table.force(8.0), 5.0 * reference.force(4.0));
+    }
+
+    #[test]
+    fn out_of_range() {
+        let table = FileTable::new(vec![1.0, 2.0], vec![3.0, 1.0], Some(vec![4.0, 0.5]));
+        assert_eq!(table.energy(0.5), 3.0);
+        assert_eq!(table.force(0.5), 4.0);
+        assert_eq!(table.energy(1.0), 3.0);
+        assert_eq!(table.energy(2.0), 1.0);
+        assert_eq!(table.energy(12.0), 1.0);
+        assert_eq!(table.force(12.0), 0.5);
+
+        assert_eq!(table.tail_energy(2.0), 0.0);
+        assert_eq!(table.tail_virial(2.0), 0.0);
+    }
+
+    #[test]
+    #[should_panic(expected = "distances must be strictly increasing in tabulated potential")]
+    fn unsorted() {
+        let _ = FileTable::new(vec![1.0, 3.0, 2.0], vec![0.0; 3], None);
+    }
+
+    #[test]
+    fn errors() {
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(file, "1.0 2.0\n2.0 3.0 4.0").unwrap();
+        let error = FileTable::from_file(file.path(), 1.0, 1.0).err().unwrap();
+        assert_eq!(error.to_string(), "inconsistent number of columns at line 2");
+
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(file, "1.0 2.0\n2.0 foo").unwrap();
+        let error = FileTable::from_file(file.path(), 1.0, 1.0).err().unwrap();
+        assert_eq!(error.to_string(), "invalid number 'foo' at line 2");
+
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(file, "1.0").unwrap();
+        let error = FileTable::from_file(file.path(), 1.0, 1.0).err().unwrap();
+        assert_eq!(error.to_string(), "expected 2 or 3 columns at line 1, got 1");
+
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(file, "1.0 2.0").unwrap();
+        let error = FileTable::from_file(file.path(), 1.0, 1.0).err().unwrap();
+        assert_eq!(error.to_string(), "tabulated potential needs at least two points, got 1");
+    }
+}
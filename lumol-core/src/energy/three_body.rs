@@ -0,0 +1,311 @@
+// Lumol, an extensible molecular simulation engine
+// Copyright (C) Lumol's contributors — BSD license
+
+//! Three-body potentials, acting on all the triplets of particles within a
+//! cutoff distance of a central particle.
+use crate::{PairPotential, Potential, PotentialParameters};
+use crate::Vector3D;
+
+/// A three-body potential acts on triplets `j-i-k` of non-bonded particles,
+/// where the central particle `i` is within the potential cutoff of both `j`
+/// and `k`.
+///
+/// Contrary to angle potentials, three-body potentials are not restricted to
+/// bonded particles and can couple the distances `r_ij` and `r_ik` with the
+/// `j-i-k` angle. They are used for example in the Stillinger-Weber model of
+/// silicon or in the mW model of water.
+///
+/// # Example
+///
+/// ```
+/// use lumol_core::energy::ThreeBodyPotential;
+/// use lumol_core::Vector3D;
+///
+/// /// A three-body potential proportional to cos(theta_jik)
+/// #[derive(Clone)]
+/// struct Cosine(f64);
+///
+/// impl ThreeBodyPotential for Cosine {
+///     fn cutoff(&self) -> f64 {
+///         4.0
+///     }
+///
+///     fn energy(&self, r_ij: &Vector3D, r_ik: &Vector3D) -> f64 {
+///         self.0 * (r_ij * r_ik) / (r_ij.norm() * r_ik.norm())
+///     }
+///
+///     fn forces(&self, r_ij: &Vector3D, r_ik: &Vector3D) -> (Vector3D, Vector3D) {
+///         let (n_ij, n_ik) = (r_ij.norm(), r_ik.norm());
+///         let cos = (r_ij * r_ik) / (n_ij * n_ik);
+///         let f_j = -self.0 * (r_ik / (n_ij * n_ik) - cos * r_ij / (n_ij * n_ij));
+///         let f_k = -self.0 * (r_ij / (n_ij * n_ik) - cos * r_ik / (n_ik * n_ik));
+///         return (f_j, f_k);
+///     }
+/// }
+///
+/// let potential = Cosine(2.0);
+/// let energy = potential.energy(&Vector3D::new(1.0, 0.0, 0.0), &Vector3D::new(0.0, 1.0, 0.0));
+/// assert_eq!(energy, 0.0);
+/// ```
+pub trait ThreeBodyPotential: Sync + Send + BoxCloneThreeBody {
+    /// Get the cutoff distance of this potential. The triplet `j-i-k` only
+    /// interacts if both `r_ij` and `r_ik` are smaller than this distance.
+    fn cutoff(&self) -> f64;
+
+    /// Get the energy of the triplet `j-i-k` centered on `i`, where `r_ij` is
+    /// the vector from `i` to `j` and `r_ik` the vector from `i` to `k`.
+    fn energy(&self, r_ij: &Vector3D, r_ik: &Vector3D) -> f64;
+
+    /// Get the forces acting on the particles `j` and `k` of the triplet
+    /// `j-i-k` centered on `i`. The force acting on `i` is the opposite of the
+    /// sum of these two forces.
+    fn forces(&self, r_ij: &Vector3D, r_ik: &Vector3D) -> (Vector3D, Vector3D);
+}
+impl_box_clone!(ThreeBodyPotential, BoxCloneThreeBody, box_clone_three_body);
+
+/// Stillinger-Weber potential.
+///
+/// The Stillinger-Weber potential contains a two-body and a three-body term.
+/// The two-body term is used as a [`PairPotential`]:
+///
+/// $$ \phi_2(r) = A \epsilon \left[B \left(\frac \sigma r \right)^p -
+///    \left(\frac \sigma r \right)^q \right]
+///    \exp\left(\frac{\sigma}{r - a \sigma}\right) $$
+///
+/// and the three-body term, for the triplet `j-i-k`, is used as a
+/// [`ThreeBodyPotential`]:
+///
+/// $$ \phi_3(r_{ij}, r_{ik}, \theta_{jik}) = \lambda \epsilon
+///    \left[\cos\theta_{jik} - \cos\theta_0 \right]^2
+///    \exp\left(\frac{\gamma \sigma}{r_{ij} - a \sigma}\right)
+///    \exp\left(\frac{\gamma \sigma}{r_{ik} - a \sigma}\right) $$
+///
+/// Both terms go smoothly to zero at $r = a \sigma$. The same potential
+/// should be used for the pairs and the triplets of particles to get the
+/// full Stillinger-Weber model.
+///
+/// # Examples
+///
+/// ```
+/// # use lumol_core::energy::Potential;
+/// # use lumol_core::energy::StillingerWeber;
+/// // Parameters for silicon in reduced units
+/// let potential = StillingerWeber::silicon(/*epsilon*/ 1.0, /*sigma*/ 1.0);
+///
+/// // The two-body term has a minimum of -epsilon at 2^(1/6) sigma
+/// let minimum = f64::powf(2.0, 1.0 / 6.0);
+/// assert!(f64::abs(potential.energy(minimum) + 1.0) < 1e-9);
+/// assert_eq!(potential.energy(1.8), 0.0);
+/// ```
+///
+/// [`PairPotential`]: trait.PairPotential.html
+/// [`ThreeBodyPotential`]: trait.ThreeBodyPotential.html
+#[derive(Clone, Copy)]
+pub struct StillingerWeber {
+    /// Energetic constant
+    pub epsilon: f64,
+    /// Distance constant
+    pub sigma: f64,
+    /// Reduced cutoff distance, the potential is zero after `a * sigma`
+    pub a: f64,
+    /// Energetic prefactor of the two-body term
+    pub big_a: f64,
+    /// Relative strength of the repulsive part of the two-body term
+    pub big_b: f64,
+    /// Exponent of the repulsive part of the two-body term
+    pub p: f64,
+    /// Exponent of the attractive part of the two-body term
+    pub q: f64,
+    /// Strength of the three-body term
+    pub lambda: f64,
+    /// Width of the exponential decay of the three-body term
+    pub gamma: f64,
+    /// Cosine of the equilibrium angle of the three-body term
+    pub cos_theta0: f64,
+}
+
+impl StillingerWeber {
+    /// Create a Stillinger-Weber potential using the parameters for silicon
+    /// from the original publication (Stillinger and Weber, Phys. Rev. B 31,
+    /// 5262, 1985), with the given energetic and distance constants. The
+    /// original values are `epsilon = 2.1683 eV` and `sigma = 2.0951 A`.
+    pub fn silicon(epsilon: f64, sigma: f64) -> StillingerWeber {
+        StillingerWeber {
+            epsilon: epsilon,
+            sigma: sigma,
+            a: 1.80,
+            big_a: 7.049556277,
+            big_b: 0.6022245584,
+            p: 4.0,
+            q: 0.0,
+            lambda: 21.0,
+            gamma: 1.20,
+            cos_theta0: -1.0 / 3.0,
+        }
+    }
+
+    /// Get the cutoff distance of this potential, `a * sigma`
+    pub fn cutoff(&self) -> f64 {
+        self.a * self.sigma
+    }
+
+    /// Get the radial factor `exp(gamma sigma / (r - a sigma))` of the
+    /// three-body term and its derivative with respect to `r`
+    fn radial(&self, r: f64) -> (f64, f64) {
+        let dr = r - self.cutoff();
+        let value = f64::exp(self.gamma * self.sigma / dr);
+        return (value, -value * self.gamma * self.sigma / (dr * dr));
+    }
+}
+
+impl Potential for StillingerWeber {
+    fn energy(&self, r: f64) -> f64 {
+        if r >= self.cutoff() {
+            return 0.0;
+        }
+        let sigma_r = self.sigma / r;
+        let exp = f64::exp(self.sigma / (r - self.cutoff()));
+        let radial = self.big_b * f64::powf(sigma_r, self.p) - f64::powf(sigma_r, self.q);
+        self.big_a * self.epsilon * radial * exp
+    }
+
+    fn force(&self, r: f64) -> f64 {
+        if r >= self.cutoff() {
+            return 0.0;
+        }
+        let sigma_r = self.sigma / r;
+        let dr = r - self.cutoff();
+        let exp = f64::exp(self.sigma / dr);
+        let repulsive = self.big_b * f64::powf(sigma_r, self.p);
+        let attractive = f64::powf(sigma_r, self.q);
+        let radial = repulsive - attractive;
+        let radial_derivative = (self.q * attractive - self.p * repulsive) / r;
+        let derivative = radial_derivative - radial * self.sigma / (dr * dr);
+        -self.big_a * self.epsilon * derivative * exp
+    }
+
+    fn parameters(&self) -> Option<PotentialParameters> {
+        Some(PotentialParameters {
+            name: "stillinger-weber",
+            values: vec![
+                ("epsilon", self.epsilon), ("sigma", self.sigma), ("a", self.a),
+                ("A", self.big_a), ("B", self.big_b), ("p", self.p), ("q", self.q),
+                ("lambda", self.lambda), ("gamma", self.gamma), ("cos_theta0", self.cos_theta0),
+            ],
+        })
+    }
+}
+
+impl PairPotential for StillingerWeber {
+    // The potential is exactly zero after `a * sigma`
+    fn tail_energy(&self, _: f64) -> f64 {
+        0.0
+    }
+
+    fn tail_virial(&self, _: f64) -> f64 {
+        0.0
+    }
+}
+
+impl ThreeBodyPotential for StillingerWeber {
+    fn cutoff(&self) -> f64 {
+        StillingerWeber::cutoff(self)
+    }
+
+    fn energy(&self, r_ij: &Vector3D, r_ik: &Vector3D) -> f64 {
+        let (n_ij, n_ik) = (r_ij.norm(), r_ik.norm());
+        if n_ij >= self.cutoff() || n_ik >= self.cutoff() {
+            return 0.0;
+        }
+        let delta_cos = (r_ij * r_ik) / (n_ij * n_ik) - self.cos_theta0;
+        let (exp_ij, _) = self.radial(n_ij);
+        let (exp_ik, _) = self.radial(n_ik);
+        self.lambda * self.epsilon * delta_cos * delta_cos * exp_ij * exp_ik
+    }
+
+    fn forces(&self, r_ij: &Vector3D, r_ik: &Vector3D) -> (Vector3D, Vector3D) {
+        let (n_ij, n_ik) = (r_ij.norm(), r_ik.norm());
+        if n_ij >= self.cutoff() || n_ik >= self.cutoff() {
+            return (Vector3D::zero(), Vector3D::zero());
+        }
+        let cos = (r_ij * r_ik) / (n_ij * n_ik);
+        let delta_cos = cos - self.cos_theta0;
+        let (exp_ij, dexp_ij) = self.radial(n_ij);
+        let (exp_ik, dexp_ik) = self.radial(n_ik);
+
+        // Derivatives of cos(theta) with respect to r_ij and r_ik
+        let dcos_ij = r_ik / (n_ij * n_ik) - cos * r_ij / (n_ij * n_ij);
+        let dcos_ik = r_ij / (n_ij * n_ik) - cos * r_ik / (n_ik * n_ik);
+
+        let prefactor = self.lambda * self.epsilon;
+        let angular = 2.0 * delta_cos * exp_ij * exp_ik;
+        let f_j = -prefactor * (angular * dcos_ij + delta_cos * delta_cos * exp_ik * dexp_ij * r_ij / n_ij);
+        let f_k = -prefactor * (angular * dcos_ik + delta_cos * delta_cos * exp_ij * dexp_ik * r_ik / n_ik);
+        return (f_j, f_k);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::{assert_relative_eq, assert_ulps_eq};
+
+    const EPS: f64 = 1e-7;
+
+    #[test]
+    fn two_body() {
+        let sw = StillingerWeber::silicon(2.0, 1.5);
+        assert_eq!(sw.cutoff(), 2.7);
+
+        let minimum = f64::powf(2.0, 1.0 / 6.0) * 1.5;
+        assert_relative_eq!(Potential::energy(&sw, minimum), -2.0, max_relative = 1e-9);
+        assert!(f64::abs(sw.force(minimum)) < 1e-6);
+
+        assert_eq!(Potential::energy(&sw, 2.7), 0.0);
+        assert_eq!(Potential::energy(&sw, 3.0), 0.0);
+        assert_eq!(sw.force(2.7), 0.0);
+        assert!(Potential::energy(&sw, 2.7 - 1e-3).abs() < 1e-100);
+
+        for &r in &[1.2, 1.6, 2.0, 2.5] {
+            let e0 = Potential::energy(&sw, r);
+            let e1 = Potential::energy(&sw, r + EPS);
+            assert_relative_eq!((e0 - e1) / EPS, sw.force(r), epsilon = 1e-5, max_relative = 1e-5);
+        }
+    }
+
+    #[test]
+    fn three_body() {
+        let sw = StillingerWeber::silicon(2.0, 1.5);
+
+        // No energy at the equilibrium angle
+        let r_ij = Vector3D::new(2.0, 0.0, 0.0);
+        let r_ik = 2.0 * Vector3D::new(-1.0 / 3.0, f64::sqrt(8.0) / 3.0, 0.0);
+        assert_ulps_eq!(ThreeBodyPotential::energy(&sw, &r_ij, &r_ik), 0.0, epsilon = 1e-15);
+
+        // No energy after the cutoff
+        let r_ij = Vector3D::new(1.8, 0.0, 0.0);
+        let r_ik = Vector3D::new(0.0, 2.8, 0.0);
+        assert_eq!(ThreeBodyPotential::energy(&sw, &r_ij, &r_ik), 0.0);
+        let (f_j, f_k) = sw.forces(&r_ij, &r_ik);
+        assert_eq!(f_j, Vector3D::zero());
+        assert_eq!(f_k, Vector3D::zero());
+
+        // Check the forces with finite differences
+        let r_ij = Vector3D::new(1.8, 0.3, -0.1);
+        let r_ik = Vector3D::new(0.2, 2.1, 0.4);
+        let energy = ThreeBodyPotential::energy(&sw, &r_ij, &r_ik);
+        assert!(energy > 0.0);
+
+        let (f_j, f_k) = sw.forces(&r_ij, &r_ik);
+        for axis in 0..3 {
+            let mut delta = Vector3D::zero();
+            delta[axis] = EPS;
+
+            let e_j = ThreeBodyPotential::energy(&sw, &(r_ij + delta), &r_ik);
+            assert_relative_eq!((energy - e_j) / EPS, f_j[axis], epsilon = 1e-5, max_relative = 1e-5);
+
+            let e_k = ThreeBodyPotential::energy(&sw, &r_ij, &(r_ik + delta));
+            assert_relative_eq!((energy - e_k) / EPS, f_k[axis], epsilon = 1e-5, max_relative = 1e-5);
+        }
+    }
+}
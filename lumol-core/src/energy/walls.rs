@@ -0,0 +1,308 @@
+// Lumol, an extensible molecular simulation engine
+// Copyright (C) Lumol's contributors — BSD license
+
+//! Walls confining the particles in a region of space.
+use crate::{Configuration, ExternalPotential, Potential, PotentialParameters, WallPotential};
+use crate::Vector3D;
+
+/// A wall is an external potential acting in the same way on all the
+/// particles, and depending only on their position. Walls are used to
+/// confine the particles in a given region of space.
+///
+/// # Example
+///
+/// ```
+/// use lumol_core::energy::Wall;
+/// use lumol_core::Vector3D;
+///
+/// /// A hard-ish floor at z = 0
+/// #[derive(Clone)]
+/// struct Floor;
+///
+/// impl Wall for Floor {
+///     fn energy(&self, position: &Vector3D) -> f64 {
+///         if position[2] < 0.0 { 100.0 * position[2] * position[2] } else { 0.0 }
+///     }
+///
+///     fn force(&self, position: &Vector3D) -> Vector3D {
+///         if position[2] < 0.0 {
+///             Vector3D::new(0.0, 0.0, -200.0 * position[2])
+///         } else {
+///             Vector3D::zero()
+///         }
+///     }
+/// }
+///
+/// let floor = Floor;
+/// assert_eq!(floor.energy(&Vector3D::new(0.0, 0.0, 3.0)), 0.0);
+/// assert!(floor.force(&Vector3D::new(0.0, 0.0, -0.1))[2] > 0.0);
+/// ```
+pub trait Wall: Sync + Send + BoxCloneWall {
+    /// Get the energy of a particle at `position`
+    fn energy(&self, position: &Vector3D) -> f64;
+
+    /// Get the force acting on a particle at `position`
+    fn force(&self, position: &Vector3D) -> Vector3D;
+}
+
+impl_box_clone!(Wall, BoxCloneWall, box_clone_wall);
+
+impl ExternalPotential for Box<dyn Wall> {
+    fn energy(&self, _: &Configuration, _: usize, position: &Vector3D) -> f64 {
+        Wall::energy(&**self, position)
+    }
+
+    fn force(&self, _: &Configuration, _: usize, position: &Vector3D) -> Vector3D {
+        Wall::force(&**self, position)
+    }
+}
+
+/// Soft spherical wall, pushing the particles back inside a sphere.
+///
+/// Particles inside the sphere do not feel this wall, and particles outside
+/// of it are pushed back with an harmonic potential on the excess distance:
+///
+/// $$ V(r) = \frac 12 k (r - R)^2 \qquad \text{if} \quad r > R $$
+///
+/// where $r$ is the distance to the center of the sphere and $R$ the radius
+/// of the sphere. The distance to the center does not use periodic boundary
+/// conditions.
+///
+/// # Examples
+///
+/// ```
+/// # use lumol_core::energy::{Wall, SphericalWall};
+/// # use lumol_core::Vector3D;
+/// let wall = SphericalWall::new(Vector3D::zero(), 10.0, 2.0);
+///
+/// assert_eq!(wall.energy(&Vector3D::new(3.0, 0.0, 0.0)), 0.0);
+/// assert_eq!(wall.energy(&Vector3D::new(0.0, 12.0, 0.0)), 4.0);
+/// assert_eq!(wall.force(&Vector3D::new(0.0, 12.0, 0.0)), Vector3D::new(0.0, -4.0, 0.0));
+/// ```
+#[derive(Clone)]
+pub struct SphericalWall {
+    /// Center of the sphere
+    center: Vector3D,
+    /// Radius of the sphere
+    radius: f64,
+    /// Stiffness of the wall
+    stiffness: f64,
+}
+
+impl SphericalWall {
+    /// Create a new `SphericalWall` with the given `center`, `radius` and
+    /// `stiffness`.
+    pub fn new(center: Vector3D, radius: f64, stiffness: f64) -> SphericalWall {
+        assert!(radius > 0.0, "The radius must be positive in spherical wall");
+        assert!(stiffness >= 0.0, "The stiffness must be positive in spherical wall");
+        SphericalWall {
+            center: center,
+            radius: radius,
+            stiffness: stiffness,
+        }
+    }
+}
+
+impl Wall for SphericalWall {
+    fn energy(&self, position: &Vector3D) -> f64 {
+        let r = (position - self.center).norm();
+        if r <= self.radius {
+            return 0.0;
+        }
+        let excess = r - self.radius;
+        return 0.5 * self.stiffness * excess * excess;
+    }
+
+    fn force(&self, position: &Vector3D) -> Vector3D {
+        let delta = position - self.center;
+        let r = delta.norm();
+        if r <= self.radius {
+            return Vector3D::zero();
+        }
+        return -self.stiffness * (r - self.radius) / r * delta;
+    }
+}
+
+/// Planar wall, perpendicular to one of the cartesian axis.
+///
+/// The energy of a particle depends on its distance `z` to the wall plane,
+/// using a [`WallPotential`]. The wall acts on both sides of the plane, so
+/// two walls can be used to build a slit pore. The distance to the plane does
+/// not use periodic boundary conditions.
+///
+/// # Examples
+///
+/// ```
+/// # use lumol_core::energy::{Wall, PlanarWall, LennardJones93};
+/// # use lumol_core::Vector3D;
+/// let potential = LennardJones93 { sigma: 1.0, epsilon: 2.0 };
+/// // A wall perpendicular to the z axis, at z = 3
+/// let wall = PlanarWall::new(2, 3.0, Box::new(potential));
+///
+/// assert_eq!(wall.energy(&Vector3D::new(2.0, 5.0, 4.0)), 2.0 * (2.0 / 15.0 - 1.0));
+/// // repulsive close to the wall, on both sides
+/// assert!(wall.force(&Vector3D::new(0.0, 0.0, 3.5))[2] > 0.0);
+/// assert!(wall.force(&Vector3D::new(0.0, 0.0, 2.5))[2] < 0.0);
+/// ```
+///
+/// [`WallPotential`]: trait.WallPotential.html
+#[derive(Clone)]
+pub struct PlanarWall {
+    /// The axis perpendicular to the wall: 0 for x, 1 for y and 2 for z
+    axis: usize,
+    /// Position of the wall along the axis
+    position: f64,
+    /// Potential acting between the particles and the wall
+    potential: Box<dyn WallPotential>,
+}
+
+impl PlanarWall {
+    /// Create a new `PlanarWall` perpendicular to the given `axis` (0 for x,
+    /// 1 for y and 2 for z), at the given `position` along this axis, and
+    /// using the given `potential`.
+    pub fn new(axis: usize, position: f64, potential: Box<dyn WallPotential>) -> PlanarWall {
+        assert!(axis < 3, "The axis must be 0, 1 or 2 in planar wall, got {}", axis);
+        PlanarWall {
+            axis: axis,
+            position: position,
+            potential: potential,
+        }
+    }
+}
+
+impl Wall for PlanarWall {
+    fn energy(&self, position: &Vector3D) -> f64 {
+        let z = position[self.axis] - self.position;
+        return self.potential.energy(z.abs());
+    }
+
+    fn force(&self, position: &Vector3D) -> Vector3D {
+        let z = position[self.axis] - self.position;
+        let mut force = Vector3D::zero();
+        force[self.axis] = f64::signum(z) * self.potential.force(z.abs());
+        return force;
+    }
+}
+
+/// 9-3 Lennard-Jones potential, obtained by integrating the 12-6
+/// Lennard-Jones potential over a semi-infinite solid. This potential is
+/// intended to be used with planar walls.
+///
+/// $$ V(z) = \epsilon \left[ \frac{2}{15} \left(\frac \sigma z \right)^9 -
+///    \left(\frac \sigma z \right)^3 \right] $$
+///
+/// # Examples
+///
+/// ```
+/// # use lumol_core::energy::Potential;
+/// # use lumol_core::energy::LennardJones93;
+/// let potential = LennardJones93 { sigma: 2.0, epsilon: 10.0 };
+/// assert_eq!(potential.energy(2.0), 10.0 * (2.0 / 15.0 - 1.0));
+/// assert_eq!(potential.force(2.0), 5.0 * (6.0 / 5.0 - 3.0));
+/// ```
+#[derive(Clone, Copy)]
+pub struct LennardJones93 {
+    /// Distance constant of the potential
+    pub sigma: f64,
+    /// Energy constant of the potential
+    pub epsilon: f64,
+}
+
+impl Potential for LennardJones93 {
+    fn energy(&self, z: f64) -> f64 {
+        let s3 = f64::powi(self.sigma / z, 3);
+        let s9 = s3 * s3 * s3;
+        self.epsilon * (2.0 / 15.0 * s9 - s3)
+    }
+
+    fn force(&self, z: f64) -> f64 {
+        let s3 = f64::powi(self.sigma / z, 3);
+        let s9 = s3 * s3 * s3;
+        self.epsilon * (6.0 / 5.0 * s9 - 3.0 * s3) / z
+    }
+
+    fn parameters(&self) -> Option<PotentialParameters> {
+        Some(PotentialParameters {
+            name: "lj93",
+            values: vec![("sigma", self.sigma), ("epsilon", self.epsilon)],
+        })
+    }
+}
+
+impl WallPotential for LennardJones93 {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::{assert_relative_eq, assert_ulps_eq};
+
+    #[test]
+    fn spherical_wall() {
+        let center = Vector3D::new(1.0, 2.0, 3.0);
+        let wall = SphericalWall::new(center, 5.0, 3.0);
+
+        // well inside the sphere
+        let position = Vector3D::new(2.0, 1.0, 4.0);
+        assert_eq!(wall.energy(&position), 0.0);
+        assert_eq!(wall.force(&position), Vector3D::zero());
+
+        // exactly at the surface
+        let position = Vector3D::new(1.0, 2.0, 8.0);
+        assert_eq!(wall.energy(&position), 0.0);
+        assert_eq!(wall.force(&position), Vector3D::zero());
+
+        // outside of the sphere, the force points toward the center
+        let position = Vector3D::new(1.0, 2.0, 9.0);
+        assert_ulps_eq!(wall.energy(&position), 1.5);
+        assert_ulps_eq!(wall.force(&position), Vector3D::new(0.0, 0.0, -3.0));
+
+        let position = Vector3D::new(-7.0, 2.0, 3.0);
+        assert_ulps_eq!(wall.energy(&position), 13.5);
+        assert_ulps_eq!(wall.force(&position), Vector3D::new(9.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn lennard_jones_93() {
+        let potential = LennardJones93 { sigma: 2.0, epsilon: 0.5 };
+        // the minimum is at z = (2/5)^(1/6) sigma
+        let z_min = f64::powf(0.4, 1.0 / 6.0) * 2.0;
+        assert_relative_eq!(potential.force(z_min), 0.0, epsilon = 1e-12);
+        assert_relative_eq!(potential.energy(z_min), -0.5 * f64::sqrt(10.0) / 3.0, max_relative = 1e-12);
+
+        let eps = 1e-6;
+        for &z in &[1.5, 2.0, 3.2, 5.0] {
+            let fd = -(potential.energy(z + eps) - potential.energy(z - eps)) / (2.0 * eps);
+            assert_relative_eq!(potential.force(z), fd, max_relative = 1e-6);
+        }
+    }
+
+    #[test]
+    fn planar_wall() {
+        let potential = LennardJones93 { sigma: 1.0, epsilon: 1.0 };
+        let wall = PlanarWall::new(1, 2.0, Box::new(potential));
+
+        let position = Vector3D::new(5.0, 3.0, -7.0);
+        assert_ulps_eq!(wall.energy(&position), potential.energy(1.0));
+        assert_ulps_eq!(wall.force(&position), Vector3D::new(0.0, potential.force(1.0), 0.0));
+
+        // Symmetric on the other side of the wall
+        let position = Vector3D::new(5.0, 1.0, -7.0);
+        assert_ulps_eq!(wall.energy(&position), potential.energy(1.0));
+        assert_ulps_eq!(wall.force(&position), Vector3D::new(0.0, -potential.force(1.0), 0.0));
+
+        // The energy diverges when approaching the wall
+        let mut previous = wall.energy(&Vector3D::new(0.0, 3.0, 0.0));
+        for &y in &[2.5, 2.2, 2.1, 2.01, 2.001] {
+            let energy = wall.energy(&Vector3D::new(0.0, y, 0.0));
+            assert!(energy > previous);
+            previous = energy;
+        }
+        assert!(previous > 1e20);
+    }
+
+    #[test]
+    #[should_panic(expected = "The axis must be 0, 1 or 2 in planar wall, got 3")]
+    fn planar_wall_axis() {
+        let _ = PlanarWall::new(3, 0.0, Box::new(LennardJones93 { sigma: 1.0, epsilon: 1.0 }));
+    }
+}
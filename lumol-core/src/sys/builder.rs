@@ -0,0 +1,250 @@
+// Lumol, an extensible molecular simulation engine
+// Copyright (C) Lumol's contributors — BSD license
+
+use std::collections::BTreeSet;
+
+use crate::{Molecule, Particle, System, UnitCell, Vector3D};
+
+/// A [`System`](struct.System.html) builder, to create systems directly in
+/// code instead of reading them from a file.
+///
+/// Particles are added one by one, and bonds between them are given using the
+/// indexes of the particles in insertion order. The molecules are created from
+/// the bonds when building the system. In the final system, the particles of
+/// a given molecule are contiguous: if bonds connect particles which are not
+/// contiguous in insertion order, the particles will be re-ordered.
+///
+/// # Examples
+///
+/// ```
+/// # use lumol_core::{SystemBuilder, UnitCell, Vector3D};
+/// let system = SystemBuilder::new()
+///     .set_cell(UnitCell::cubic(10.0))
+///     .add_particle("O", Vector3D::new(0.0, 0.0, 0.0))
+///     .add_particle("H", Vector3D::new(1.0, 0.0, 0.0))
+///     .add_particle("H", Vector3D::new(0.0, 1.0, 0.0))
+///     .add_bond(0, 1)
+///     .add_bond(0, 2)
+///     .build();
+///
+/// assert_eq!(system.size(), 3);
+/// assert_eq!(system.molecules().count(), 1);
+/// assert_eq!(system.cell, UnitCell::cubic(10.0));
+/// ```
+#[derive(Clone, Debug)]
+pub struct SystemBuilder {
+    cell: UnitCell,
+    particles: Vec<Particle>,
+    bonds: BTreeSet<(usize, usize)>,
+}
+
+impl SystemBuilder {
+    /// Create a new builder for an empty system with an infinite unit cell.
+    pub fn new() -> SystemBuilder {
+        SystemBuilder {
+            cell: UnitCell::infinite(),
+            particles: Vec::new(),
+            bonds: BTreeSet::new(),
+        }
+    }
+
+    /// Use the given unit `cell` for the system.
+    pub fn set_cell(mut self, cell: UnitCell) -> SystemBuilder {
+        self.cell = cell;
+        return self;
+    }
+
+    /// Add a particle with the given `name` at `position`. The particle mass
+    /// is set from its name, as in `Particle::new`.
+    pub fn add_particle(mut self, name: &str, position: Vector3D) -> SystemBuilder {
+        self.particles.push(Particle::with_position(name, position));
+        return self;
+    }
+
+    /// Add an existing `particle`, keeping all its properties.
+    pub(crate) fn add_owned_particle(mut self, particle: Particle) -> SystemBuilder {
+        self.particles.push(particle);
+        return self;
+    }
+
+    /// Add a bond between the particles at indexes `i` and `j`.
+    ///
+    /// # Panics
+    ///
+    /// If `i` or `j` are not the indexes of already added particles, if `i`
+    /// and `j` are the same, or if this bond was already added.
+    pub fn add_bond(mut self, i: usize, j: usize) -> SystemBuilder {
+        let size = self.particles.len();
+        assert!(i < size && j < size, "invalid bond ({}, {}) in a system with {} particles", i, j, size);
+        assert_ne!(i, j, "can not add a bond between a particle and itself");
+
+        let bond = if i < j { (i, j) } else { (j, i) };
+        assert!(self.bonds.insert(bond), "the bond between {} and {} was already added", i, j);
+        return self;
+    }
+
+    /// Build the system, creating the molecules from the bonds.
+    pub fn build(self) -> System {
+        let size = self.particles.len();
+
+        // Find the molecules as the connected components of the bonds graph,
+        // keeping the particles in insertion order inside each molecule.
+        let mut neighbors = vec![Vec::new(); size];
+        for &(i, j) in &self.bonds {
+            neighbors[i].push(j);
+            neighbors[j].push(i);
+        }
+
+        let mut molecule_id = vec![None; size];
+        let mut molecules = Vec::new();
+        for start in 0..size {
+            if molecule_id[start].is_some() {
+                continue;
+            }
+
+            let id = molecules.len();
+            let mut members = vec![start];
+            let mut stack = vec![start];
+            molecule_id[start] = Some(id);
+            while let Some(i) = stack.pop() {
+                for &j in &neighbors[i] {
+                    if molecule_id[j].is_none() {
+                        molecule_id[j] = Some(id);
+                        members.push(j);
+                        stack.push(j);
+                    }
+                }
+            }
+            members.sort_unstable();
+            molecules.push(members);
+        }
+
+        // Position of each particle inside its molecule
+        let mut local = vec![0; size];
+        for members in &molecules {
+            for (index, &i) in members.iter().enumerate() {
+                local[i] = index;
+            }
+        }
+
+        let mut system = System::with_cell(self.cell);
+        for members in &molecules {
+            let mut molecule = Molecule::new(self.particles[members[0]].clone());
+            for &i in &members[1..] {
+                molecule.add_particle(self.particles[i].clone());
+            }
+            for &(i, j) in self.bonds.iter().filter(|&&(i, _)| members.binary_search(&i).is_ok()) {
+                molecule.add_bond(local[i], local[j]);
+            }
+            system.add_molecule(molecule);
+        }
+        return system;
+    }
+}
+
+impl Default for SystemBuilder {
+    fn default() -> SystemBuilder {
+        SystemBuilder::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::energy::{Harmonic, LennardJones, PairInteraction};
+    use crate::TrajectoryBuilder;
+    use std::io::prelude::*;
+
+    #[test]
+    fn molecules() {
+        let system = SystemBuilder::new()
+            .add_particle("C", Vector3D::new(0.0, 0.0, 0.0))
+            .add_particle("Ar", Vector3D::new(5.0, 0.0, 0.0))
+            .add_particle("H", Vector3D::new(1.0, 0.0, 0.0))
+            .add_particle("H", Vector3D::new(0.0, 1.0, 0.0))
+            .add_bond(2, 0)
+            .add_bond(0, 3)
+            .build();
+
+        assert_eq!(system.size(), 4);
+        assert_eq!(system.molecules().count(), 2);
+        assert!(system.cell.is_infinite());
+
+        // The two H are moved next to the C
+        let names = system.particles().name.iter().map(String::as_str).collect::<Vec<_>>();
+        assert_eq!(names, ["C", "H", "H", "Ar"]);
+        assert_eq!(system.particles().position[1], Vector3D::new(1.0, 0.0, 0.0));
+        assert_eq!(system.molecule(0).bonds().len(), 2);
+        assert_eq!(system.molecule(0).angles().len(), 1);
+        assert_eq!(system.molecule(1).size(), 1);
+    }
+
+    #[test]
+    fn empty() {
+        let system = SystemBuilder::new().set_cell(UnitCell::cubic(10.0)).build();
+        assert!(system.is_empty());
+        assert_eq!(system.cell, UnitCell::cubic(10.0));
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid bond (0, 2) in a system with 2 particles")]
+    fn invalid_bond() {
+        let _ = SystemBuilder::new()
+            .add_particle("Ar", Vector3D::zero())
+            .add_particle("Ar", Vector3D::zero())
+            .add_bond(0, 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "can not add a bond between a particle and itself")]
+    fn self_bond() {
+        let _ = SystemBuilder::new().add_particle("Ar", Vector3D::zero()).add_bond(0, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "the bond between 1 and 0 was already added")]
+    fn duplicated_bond() {
+        let _ = SystemBuilder::new()
+            .add_particle("Ar", Vector3D::zero())
+            .add_particle("Ar", Vector3D::zero())
+            .add_bond(0, 1)
+            .add_bond(1, 0);
+    }
+
+    fn set_interactions(system: &mut System) {
+        system.set_pair_potential(("F", "F"), PairInteraction::new(Box::new(LennardJones {
+            sigma: 1.2,
+            epsilon: 0.5,
+        }), 5.0));
+        system.set_bond_potential(("F", "F"), Box::new(Harmonic { k: 100.0, x0: 1.4 }));
+    }
+
+    #[test]
+    fn same_energy_as_chfl_file() {
+        let mut file = tempfile::Builder::new().suffix(".pdb").tempfile().unwrap();
+        write!(file, "{}", "\
+CRYST1   20.000   20.000   20.000  90.00  90.00  90.00 P 1           1
+HETATM    1  F   RES X   1       0.000   0.000   0.000  1.00  0.00           F
+HETATM    2  F   RES X   1       1.500   0.200   0.000  1.00  0.00           F
+CONECT    1    2
+CONECT    2    1
+END
+").unwrap();
+
+        let mut trajectory = TrajectoryBuilder::new().open(file.path()).unwrap();
+        let mut from_file = trajectory.read().unwrap();
+        set_interactions(&mut from_file);
+
+        let mut built = SystemBuilder::new()
+            .set_cell(UnitCell::cubic(20.0))
+            .add_particle("F", Vector3D::new(0.0, 0.0, 0.0))
+            .add_particle("F", Vector3D::new(1.5, 0.2, 0.0))
+            .add_bond(0, 1)
+            .build();
+        set_interactions(&mut built);
+
+        assert_eq!(built.molecules().count(), from_file.molecules().count());
+        assert_eq!(built.molecule(0).bonds(), from_file.molecule(0).bonds());
+        assert_eq!(built.potential_energy(), from_file.potential_energy());
+    }
+}
@@ -11,6 +11,7 @@ use std::mem;
 
 use crate::System;
 use crate::{Array2, Vector3D};
+use crate::sys::energy::{external_energy, three_body_energy};
 
 /// Callback for updating a cache. It also take an `&mut System` argument for
 /// updating the cache inside the global potentials.
@@ -30,14 +31,24 @@ pub struct EnergyCache {
     pairs_tail: f64,
     /// Energy of all the bonds in the system
     bonds: f64,
+    /// Energy of all the Drude bonds in the system
+    drude: f64,
     /// Energy of all the angles in the system
     angles: f64,
     /// Energy of all the dihedrals angles in the system
     dihedrals: f64,
+    /// Energy of all the improper dihedrals angles in the system
+    impropers: f64,
+    /// Energy of all the cross terms in the system
+    cross_terms: f64,
+    /// Energy of all the triplets interacting with three-body potentials
+    three_body: f64,
     /// Energy of coulombic interactions
     coulomb: f64,
     /// Energy of global interactions
     global: f64,
+    /// Energy of external interactions
+    external: f64,
     /// Callback to be called to update the cache if the system is modified
     updater: Option<UpdateCallback>,
 }
@@ -50,10 +61,15 @@ impl EnergyCache {
             pairs: 0.0,
             pairs_tail: 0.0,
             bonds: 0.0,
+            drude: 0.0,
             angles: 0.0,
             dihedrals: 0.0,
+            impropers: 0.0,
+            cross_terms: 0.0,
+            three_body: 0.0,
             coulomb: 0.0,
             global: 0.0,
+            external: 0.0,
             updater: None,
         }
     }
@@ -64,10 +80,15 @@ impl EnergyCache {
         self.pairs = 0.0;
         self.pairs_tail = 0.0;
         self.bonds = 0.0;
+        self.drude = 0.0;
         self.angles = 0.0;
         self.dihedrals = 0.0;
+        self.impropers = 0.0;
+        self.cross_terms = 0.0;
+        self.three_body = 0.0;
         self.coulomb = 0.0;
         self.global = 0.0;
+        self.external = 0.0;
     }
 
     /// Initialize the cache to be used with `system`. After a call to this
@@ -82,8 +103,7 @@ impl EnergyCache {
         for i in 0..system.size() {
             for j in (i + 1)..system.size() {
                 let r = system.nearest_image(i, j).norm();
-                let path = system.bond_path(i, j);
-                let energy = evaluator.pair(path, r, i, j);
+                let energy = evaluator.pair_between(r, i, j);
                 self.pairs_cache[(i, j)] = energy;
                 self.pairs_cache[(j, i)] = energy;
                 self.pairs += energy;
@@ -92,10 +112,15 @@ impl EnergyCache {
 
         self.pairs_tail = evaluator.pairs_tail();
         self.bonds = evaluator.bonds();
+        self.drude = evaluator.drude();
         self.angles = evaluator.angles();
         self.dihedrals = evaluator.dihedrals();
+        self.impropers = evaluator.impropers();
+        self.cross_terms = evaluator.cross_terms();
+        self.three_body = evaluator.three_body();
         self.coulomb = evaluator.coulomb();
         self.global = evaluator.global();
+        self.external = evaluator.external();
     }
 
     /// Get the cached energy
@@ -105,11 +130,16 @@ impl EnergyCache {
         energy += self.pairs_tail;
 
         energy += self.bonds;
+        energy += self.drude;
         energy += self.angles;
         energy += self.dihedrals;
+        energy += self.impropers;
+        energy += self.cross_terms;
+        energy += self.three_body;
 
         energy += self.coulomb;
         energy += self.global;
+        energy += self.external;
 
         return energy;
     }
@@ -162,8 +192,7 @@ impl EnergyCache {
             for (_, other_molecule) in system.molecules().enumerate().filter(|(id, _)| molecule_id != *id) {
                 for part_j in other_molecule.indexes() {
                     let r = system.cell.distance(&positions[part_j], &new_positions[i]);
-                    let path = system.bond_path(part_i, part_j);
-                    let energy = evaluator.pair(path, r, part_i, part_j);
+                    let energy = evaluator.pair_between(r, part_i, part_j);
 
                     pairs_delta += energy;
                     new_pairs[(part_i, part_j)] += energy;
@@ -176,9 +205,21 @@ impl EnergyCache {
 
         // Pairs tail correction do not change when moving a single molecule
 
-        // Bonds / Angles / Dihedrals terms do not change
+        // Bonds / Angles / Dihedrals / Impropers / Cross terms do not change
 
-        let coulomb_delta = if let Some(coulomb) = system.coulomb_potential() {
+        // Three-body terms can change for triplets involving particles
+        // outside of the molecule, we recompute all of them.
+        let three_body_delta = if system.interactions().three_body_cutoff().is_some() {
+            let mut positions = positions.to_vec();
+            for (i, part_i) in molecule.indexes().enumerate() {
+                positions[part_i] = new_positions[i];
+            }
+            three_body_energy(system, &positions) - self.three_body
+        } else {
+            0.0
+        };
+
+        let coulomb_delta = if let Some(coulomb) = system.charged_coulomb_potential() {
             coulomb.move_molecule_cost(system, molecule_id, new_positions)
         } else {
             0.0
@@ -189,12 +230,20 @@ impl EnergyCache {
             global_delta += global.move_molecule_cost(system, molecule_id, new_positions);
         }
 
-        let cost = pairs_delta + coulomb_delta + global_delta;
+        let mut external_delta = 0.0;
+        for (i, part_i) in molecule.indexes().enumerate() {
+            external_delta += external_energy(system, part_i, &new_positions[i]);
+            external_delta -= external_energy(system, part_i, &positions[part_i]);
+        }
+
+        let cost = pairs_delta + three_body_delta + coulomb_delta + global_delta + external_delta;
 
         self.updater = Some(Box::new(move |cache, system| {
             cache.pairs += pairs_delta;
+            cache.three_body += three_body_delta;
             cache.coulomb += coulomb_delta;
             cache.global += global_delta;
+            cache.external += external_delta;
 
             let (n, m) = new_pairs.dim();
             debug_assert_eq!(n, m);
@@ -212,7 +261,7 @@ impl EnergyCache {
             }
 
             // Update the cache for the global potentials
-            if let Some(coulomb) = system.coulomb_potential() {
+            if let Some(coulomb) = system.charged_coulomb_potential() {
                 coulomb.update();
             }
 
@@ -232,8 +281,10 @@ impl EnergyCache {
     /// - non bonded pairs interactions;
     /// - Coulomb interactions;
     /// - global interactions;
+    /// - external interactions;
     ///
-    /// It **DOES NOT** recompute bonds, angles and dihedral interactions. You
+    /// It **DOES NOT** recompute bonds, angles, dihedral, improper and cross terms
+    /// interactions. You
     /// must not use this function when the intramolecular configuration
     /// changed.
     ///
@@ -251,8 +302,7 @@ impl EnergyCache {
                 for part_i in mol_i.indexes() {
                     for part_j in mol_j.indexes() {
                         let r = system.distance(part_i, part_j);
-                        let path = system.bond_path(part_i, part_j);
-                        let energy = evaluator.pair(path, r, part_i, part_j);
+                        let energy = evaluator.pair_between(r, part_i, part_j);
                         pairs_delta += energy;
                         new_pairs[(part_i, part_j)] += energy;
                         new_pairs[(part_j, part_i)] += energy;
@@ -263,20 +313,25 @@ impl EnergyCache {
         }
 
         // temporarily, recompute all interactions
+        let new_three_body = evaluator.three_body();
         let new_coulomb = evaluator.coulomb();
         let new_global = evaluator.global();
+        let new_external = evaluator.external();
 
         // compute the new tail correction
         let pairs_tail = evaluator.pairs_tail();
 
-        let cost = pairs_delta + (pairs_tail - self.pairs_tail) + (new_coulomb - self.coulomb)
-            + (new_global - self.global);
+        let cost = pairs_delta + (pairs_tail - self.pairs_tail) + (new_three_body - self.three_body)
+            + (new_coulomb - self.coulomb) + (new_global - self.global)
+            + (new_external - self.external);
 
         self.updater = Some(Box::new(move |cache, system| {
             cache.pairs += pairs_delta;
             cache.pairs_tail = pairs_tail;
+            cache.three_body = new_three_body;
             cache.coulomb = new_coulomb;
             cache.global = new_global;
+            cache.external = new_external;
 
             let (n, m) = new_pairs.dim();
             debug_assert_eq!(n, m);
@@ -299,7 +354,7 @@ impl EnergyCache {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{Harmonic, LennardJones, NullPotential, Wolf};
+    use crate::{Harmonic, LennardJones, NullPotential, StillingerWeber, Wolf};
     use crate::PairInteraction;
     use crate::System;
     use crate::Vector3D;
@@ -485,4 +540,58 @@ mod tests {
         let new_energy = new_system.potential_energy();
         assert_ulps_eq!(cost, new_energy - old_energy, epsilon = 1e-12);
     }
+
+    #[test]
+    fn three_body() {
+        let mut system = system_from_xyz(
+            "4
+            cell: 20.0
+            Si 0.0 0.0 0.0
+            Si 2.3 0.2 0.0
+            Si 0.3 2.2 0.1
+            Si 0.5 0.4 2.2",
+        );
+        let sw = StillingerWeber::silicon(units::from(2.1683, "eV").unwrap(), 2.0951);
+        system.set_pair_potential(("Si", "Si"), PairInteraction::new(Box::new(sw), sw.cutoff()));
+        system.add_three_body_interaction(("Si", "Si", "Si"), Box::new(sw));
+
+        let mut cache = EnergyCache::new();
+        let old_energy = system.potential_energy();
+        cache.init(&system);
+        assert_ulps_eq!(cache.energy(), old_energy);
+
+        let new_positions = &[Vector3D::new(0.1, -0.3, 0.2)];
+        let cost = cache.move_molecule_cost(&system, 0, new_positions);
+        system.particles_mut().position[0] = new_positions[0];
+        let new_energy = system.potential_energy();
+        assert_relative_eq!(cost, new_energy - old_energy, max_relative = 1e-9);
+
+        cache.update(&mut system);
+        assert_relative_eq!(cache.energy(), new_energy, max_relative = 1e-12);
+    }
+
+    #[test]
+    fn external() {
+        let mut system = testing_system();
+        system.add_position_restraint(1, Vector3D::new(0.0, 0.0, 0.5), units::from(10.0, "kJ/mol/A^2").unwrap());
+
+        let mut cache = EnergyCache::new();
+        let old_energy = system.potential_energy();
+        cache.init(&system);
+        assert_ulps_eq!(cache.energy(), old_energy);
+
+        let molecule = system.molecule(0).indexes();
+        let new_positions = system.particles().position[molecule.clone()].iter()
+                                  .map(|position| position + Vector3D::new(0.2, 0.0, -0.1))
+                                  .collect::<Vec<_>>();
+        let cost = cache.move_molecule_cost(&system, 0, &new_positions);
+        for (i, position) in molecule.zip(&new_positions) {
+            system.particles_mut().position[i] = *position;
+        }
+        let new_energy = system.potential_energy();
+        assert_ulps_eq!(cost, new_energy - old_energy, epsilon = 1e-12);
+
+        cache.update(&mut system);
+        assert_ulps_eq!(cache.energy(), new_energy, epsilon = 1e-12);
+    }
 }
@@ -0,0 +1,469 @@
+// Lumol, an extensible molecular simulation engine
+// Copyright (C) Lumol's contributors — BSD license
+
+//! Saving and restoring the full state of a system, to restart simulations.
+//!
+//! Checkpoints use a versioned text format, storing all floating point values
+//! with enough digits to be read back exactly:
+//!
+//! ```text
+//! lumol-checkpoint 1
+//! step <step>
+//! cell <shape> <9 values of the cell matrix, row by row>
+//! molecules <count>
+//! molecule <particles count> <bonds count>
+//! <name> <mass> <charge> <x> <y> <z> <vx> <vy> <vz>
+//! ...
+//! <i> <j>
+//! ...
+//! virtual-sites <count>
+//! <site> <parent 1> <parent 2> <parent 3> <weight 1> <weight 2> <weight 3>
+//! ...
+//! drude-particles <count>
+//! <drude> <core> <k>
+//! ...
+//! ```
+//!
+//! Bonds indexes are relative to the first particle of the molecule. The
+//! Drude particles section is optional, for compatibility with checkpoints
+//! written before Drude particles were supported.
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::{Matrix3, Vector3D};
+use crate::{DrudeParticle, Molecule, Particle, System, UnitCell, CellShape, VirtualSite};
+
+/// Current version of the checkpoint format
+const VERSION: u32 = 1;
+
+impl System {
+    /// Save the state of this system to a checkpoint file at `path`. The
+    /// checkpoint contains the step, the unit cell, the particles (with names,
+    /// masses, charges, positions and velocities), the bonds, the virtual
+    /// sites and the Drude particles. The interactions are not saved.
+    ///
+    /// # Errors
+    ///
+    /// If the file can not be written, or if a particle name is empty or
+    /// contains whitespace.
+    pub fn save_checkpoint<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut file = BufWriter::new(File::create(path)?);
+        writeln!(file, "lumol-checkpoint {}", VERSION)?;
+        writeln!(file, "step {}", self.step)?;
+
+        let shape = match self.cell.shape() {
+            CellShape::Infinite => "infinite",
+            CellShape::Orthorhombic => "orthorhombic",
+            CellShape::Triclinic => "triclinic",
+        };
+        let matrix = self.cell.matrix();
+        write!(file, "cell {}", shape)?;
+        for i in 0..3 {
+            for j in 0..3 {
+                write!(file, " {:?}", matrix[i][j])?;
+            }
+        }
+        writeln!(file)?;
+
+        writeln!(file, "molecules {}", self.molecules().count())?;
+        for molecule in self.molecules() {
+            let start = molecule.start();
+            writeln!(file, "molecule {} {}", molecule.size(), molecule.bonds().len())?;
+            for particle in molecule.particles() {
+                if particle.name.is_empty() || particle.name.contains(char::is_whitespace) {
+                    return Err(invalid_data(format!(
+                        "can not save particle with name '{}' in checkpoint", particle.name
+                    )));
+                }
+                let (position, velocity) = (particle.position, particle.velocity);
+                writeln!(
+                    file, "{} {:?} {:?} {:?} {:?} {:?} {:?} {:?} {:?}",
+                    particle.name, particle.mass, particle.charge,
+                    position[0], position[1], position[2],
+                    velocity[0], velocity[1], velocity[2],
+                )?;
+            }
+            for bond in molecule.bonds() {
+                writeln!(file, "{} {}", bond.i() - start, bond.j() - start)?;
+            }
+        }
+
+        writeln!(file, "virtual-sites {}", self.virtual_sites().len())?;
+        for site in self.virtual_sites() {
+            let (parents, weights) = (site.parents(), site.weights());
+            writeln!(
+                file, "{} {} {} {} {:?} {:?} {:?}", site.site(),
+                parents[0], parents[1], parents[2], weights[0], weights[1], weights[2],
+            )?;
+        }
+
+        writeln!(file, "drude-particles {}", self.drude_particles().len())?;
+        for drude in self.drude_particles() {
+            writeln!(file, "{} {} {:?}", drude.drude(), drude.core(), drude.k())?;
+        }
+
+        return file.flush();
+    }
+
+    /// Restore the state of this system from the checkpoint file at `path`,
+    /// replacing the step, unit cell, particles, bonds, virtual sites and
+    /// Drude particles. The interactions of this system are kept unchanged.
+    ///
+    /// # Errors
+    ///
+    /// If the file can not be read, or if it is not a valid checkpoint. The
+    /// system is not modified in this case.
+    pub fn load_checkpoint<P: AsRef<Path>>(&mut self, path: P) -> io::Result<()> {
+        let mut reader = Reader::new(BufReader::new(File::open(path)?));
+
+        let version: u32 = reader.keyword_line("lumol-checkpoint")?;
+        if version != VERSION {
+            return Err(invalid_data(format!(
+                "unsupported checkpoint version {}, expected {}", version, VERSION
+            )));
+        }
+
+        let step: u64 = reader.keyword_line("step")?;
+
+        let values = reader.line()?;
+        if values.len() != 11 || values[0] != "cell" {
+            return Err(reader.error("expected the cell shape and matrix"));
+        }
+        let shape = match &*values[1] {
+            "infinite" => CellShape::Infinite,
+            "orthorhombic" => CellShape::Orthorhombic,
+            "triclinic" => CellShape::Triclinic,
+            other => return Err(reader.error(&format!("unknown cell shape '{}'", other))),
+        };
+        let mut matrix = Matrix3::zero();
+        for i in 0..3 {
+            for j in 0..3 {
+                matrix[i][j] = reader.parse(&values[2 + 3 * i + j])?;
+            }
+        }
+        let cell = UnitCell::from_matrix(matrix, shape);
+
+        let count: usize = reader.keyword_line("molecules")?;
+        let mut molecules = Vec::with_capacity(count);
+        for _ in 0..count {
+            let values = reader.line()?;
+            if values.len() != 3 || values[0] != "molecule" {
+                return Err(reader.error("expected 'molecule' followed by two integers"));
+            }
+            let size: usize = reader.parse(&values[1])?;
+            let bonds: usize = reader.parse(&values[2])?;
+            if size == 0 {
+                return Err(reader.error("molecules must contain at least one particle"));
+            }
+
+            let mut molecule: Option<Molecule> = None;
+            for _ in 0..size {
+                let particle = reader.particle()?;
+                match molecule {
+                    Some(ref mut molecule) => molecule.add_particle(particle),
+                    None => molecule = Some(Molecule::new(particle)),
+                }
+            }
+            let mut molecule = molecule.expect("empty molecule");
+
+            for _ in 0..bonds {
+                let values = reader.line()?;
+                if values.len() != 2 {
+                    return Err(reader.error("expected two particles indexes for a bond"));
+                }
+                let i: usize = reader.parse(&values[0])?;
+                let j: usize = reader.parse(&values[1])?;
+                if i >= size || j >= size || i == j {
+                    return Err(reader.error("invalid particles indexes for a bond"));
+                }
+                molecule.add_bond(i, j);
+            }
+            molecules.push(molecule);
+        }
+
+        let total: usize = molecules.iter().map(|molecule| molecule.size()).sum();
+        let count: usize = reader.keyword_line("virtual-sites")?;
+        let mut sites: Vec<VirtualSite> = Vec::with_capacity(count);
+        for _ in 0..count {
+            let values = reader.line()?;
+            if values.len() != 7 {
+                return Err(reader.error("expected 4 indexes and 3 weights for a virtual site"));
+            }
+            let mut indexes = [0; 4];
+            for (index, value) in indexes.iter_mut().zip(&values) {
+                *index = reader.parse(value)?;
+                if *index >= total {
+                    return Err(reader.error("invalid particle index for a virtual site"));
+                }
+            }
+            let mut weights = [0.0; 3];
+            for (weight, value) in weights.iter_mut().zip(&values[4..]) {
+                *weight = reader.parse(value)?;
+            }
+            let valid_weights = f64::abs(weights.iter().sum::<f64>() - 1.0) < 1e-12;
+            if indexes[1..].contains(&indexes[0]) || !valid_weights {
+                return Err(reader.error("invalid parents or weights for a virtual site"));
+            }
+            if sites.iter().any(|site| site.site() == indexes[0]) {
+                return Err(reader.error(&format!("particle {} is already a virtual site", indexes[0])));
+            }
+            sites.push(VirtualSite::new(indexes[0], [indexes[1], indexes[2], indexes[3]], weights));
+        }
+
+        let mut drudes: Vec<DrudeParticle> = Vec::new();
+        if let Some(values) = reader.optional_line()? {
+            if values.len() != 2 || values[0] != "drude-particles" {
+                return Err(reader.error("expected 'drude-particles' followed by a single value"));
+            }
+            let count: usize = reader.parse(&values[1])?;
+            let molecule_ids = molecules.iter().enumerate().flat_map(|(id, molecule)| {
+                std::iter::repeat(id).take(molecule.size())
+            }).collect::<Vec<_>>();
+            for _ in 0..count {
+                let values = reader.line()?;
+                if values.len() != 3 {
+                    return Err(reader.error("expected 2 indexes and a spring constant for a Drude particle"));
+                }
+                let drude: usize = reader.parse(&values[0])?;
+                let core: usize = reader.parse(&values[1])?;
+                let k: f64 = reader.parse(&values[2])?;
+                let valid = drude < total && core < total && drude != core && molecule_ids[drude] == molecule_ids[core];
+                if !valid || k.is_nan() || k <= 0.0 {
+                    return Err(reader.error("invalid indexes or spring constant for a Drude particle"));
+                }
+                if drudes.iter().any(|other| other.drude() == drude) {
+                    return Err(reader.error(&format!("particle {} is already a Drude particle", drude)));
+                }
+                drudes.push(DrudeParticle::new(drude, core, k));
+            }
+        }
+
+        // Everything was validated above, the system can now be modified
+        // without panicking in the functions below.
+        self.clear_configuration(cell);
+        for molecule in molecules {
+            self.add_molecule(molecule);
+        }
+        // Adding the virtual sites resets the masses and velocities, so we
+        // restore them afterwards to get exactly the saved state.
+        let masses = self.particles().mass.to_vec();
+        let velocities = self.particles().velocity.to_vec();
+        for site in sites {
+            self.add_virtual_site(site);
+        }
+        self.particles_mut().mass.copy_from_slice(&masses);
+        self.particles_mut().velocity.copy_from_slice(&velocities);
+        for drude in drudes {
+            self.add_drude_particle(drude);
+        }
+        self.step = step;
+        return Ok(());
+    }
+}
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// Line-based reader for checkpoint files
+struct Reader<R: BufRead> {
+    lines: io::Lines<R>,
+    line: usize,
+}
+
+impl<R: BufRead> Reader<R> {
+    fn new(reader: R) -> Reader<R> {
+        Reader {
+            lines: reader.lines(),
+            line: 0,
+        }
+    }
+
+    /// Create an error for the current line
+    fn error(&self, message: &str) -> io::Error {
+        invalid_data(format!("invalid checkpoint at line {}: {}", self.line, message))
+    }
+
+    /// Read the next line, and split it in whitespace separated values
+    fn line(&mut self) -> io::Result<Vec<String>> {
+        self.line += 1;
+        match self.lines.next() {
+            Some(line) => Ok(line?.split_whitespace().map(String::from).collect()),
+            None => Err(self.error("unexpected end of file")),
+        }
+    }
+
+    /// Read the next line if there is one, and split it in whitespace
+    /// separated values
+    fn optional_line(&mut self) -> io::Result<Option<Vec<String>>> {
+        match self.lines.next() {
+            Some(line) => {
+                self.line += 1;
+                Ok(Some(line?.split_whitespace().map(String::from).collect()))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Parse a single value
+    fn parse<T: FromStr>(&self, value: &str) -> io::Result<T> {
+        value.parse().map_err(|_| self.error(&format!("could not parse '{}'", value)))
+    }
+
+    /// Read a line containing `keyword` followed by a single value
+    fn keyword_line<T: FromStr>(&mut self, keyword: &str) -> io::Result<T> {
+        let values = self.line()?;
+        if values.len() != 2 || values[0] != keyword {
+            return Err(self.error(&format!("expected '{}' followed by a single value", keyword)));
+        }
+        return self.parse(&values[1]);
+    }
+
+    /// Read a line containing a particle
+    fn particle(&mut self) -> io::Result<Particle> {
+        let values = self.line()?;
+        if values.len() != 9 {
+            return Err(self.error("expected a name and 8 values for a particle"));
+        }
+        let mut numbers = [0.0; 8];
+        for (number, value) in numbers.iter_mut().zip(&values[1..]) {
+            *number = self.parse(value)?;
+        }
+
+        let mut particle = Particle::new(values[0].clone());
+        particle.mass = numbers[0];
+        particle.charge = numbers[1];
+        particle.position = Vector3D::new(numbers[2], numbers[3], numbers[4]);
+        particle.velocity = Vector3D::new(numbers[5], numbers[6], numbers[7]);
+        return Ok(particle);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    use tempfile::NamedTempFile;
+
+    fn testing_system() -> System {
+        let mut system = System::with_cell(UnitCell::triclinic(10.0, 11.0, 12.0, 80.0, 95.0, 100.0));
+        system.step = 42;
+
+        let mut molecule = Molecule::new(Particle::with_position("O", [0.1, 0.2, 0.3].into()));
+        molecule.add_particle_bonded_to(0, Particle::with_position("H", [0.9, 0.3, 0.2].into()));
+        molecule.add_particle_bonded_to(0, Particle::with_position("H", [-0.2, 1.1, 0.3].into()));
+        molecule.add_particle_bonded_to(0, Particle::with_position("M", Vector3D::zero()));
+        system.add_molecule(molecule);
+        system.add_molecule(Molecule::new(Particle::with_position("Na", [5.0, 3.0, 1.0].into())));
+
+        let mut molecule = Molecule::new(Particle::with_position("Cl", [3.0, 5.0, 1.0].into()));
+        molecule.add_particle_bonded_to(0, Particle::with_position("D", [3.1, 5.0, 1.0].into()));
+        system.add_molecule(molecule);
+
+        for (i, velocity) in system.particles_mut().velocity.iter_mut().enumerate() {
+            *velocity = Vector3D::new(0.1 / 3.0, -(i as f64) / 7.0, 1e-3);
+        }
+        for (i, charge) in system.particles_mut().charge.iter_mut().enumerate() {
+            *charge = 0.1 * i as f64 - 1.0 / 3.0;
+        }
+        system.add_virtual_site(VirtualSite::new(3, [0, 1, 2], [0.8, 0.1, 0.1]));
+        system.add_drude_particle(DrudeParticle::new(6, 5, 1.0 / 3.0));
+        return system;
+    }
+
+    #[test]
+    fn roundtrip() {
+        let system = testing_system();
+        let file = NamedTempFile::new().unwrap();
+        system.save_checkpoint(file.path()).unwrap();
+
+        let mut loaded = System::new();
+        loaded.add_molecule(Molecule::new(Particle::new("Xe")));
+        loaded.load_checkpoint(file.path()).unwrap();
+
+        assert_eq!(loaded.step, 42);
+        assert_eq!(loaded.cell, system.cell);
+        assert_eq!(loaded.size(), system.size());
+        assert_eq!(loaded.molecules().count(), 3);
+        assert_eq!(loaded.molecule(0).bonds(), system.molecule(0).bonds());
+        assert_eq!(loaded.molecule(0).angles(), system.molecule(0).angles());
+        assert_eq!(loaded.virtual_sites(), system.virtual_sites());
+        assert_eq!(loaded.drude_particles(), system.drude_particles());
+
+        let (expected, actual) = (system.particles(), loaded.particles());
+        assert_eq!(actual.name, expected.name);
+        assert_eq!(actual.mass, expected.mass);
+        assert_eq!(actual.charge, expected.charge);
+        assert_eq!(actual.position, expected.position);
+        assert_eq!(actual.velocity, expected.velocity);
+        assert_eq!(actual.kind[1], actual.kind[2]);
+        assert_ne!(actual.kind[0], actual.kind[1]);
+    }
+
+    #[test]
+    fn errors() {
+        let mut system = testing_system();
+        let file = NamedTempFile::new().unwrap();
+        system.save_checkpoint(file.path()).unwrap();
+
+        let mut content = String::new();
+        let _ = File::open(file.path()).unwrap().read_to_string(&mut content).unwrap();
+
+        let check_error = |content: &str, message: &str| {
+            let file = NamedTempFile::new().unwrap();
+            File::create(file.path()).unwrap().write_all(content.as_bytes()).unwrap();
+            let mut other = System::new();
+            let error = other.load_checkpoint(file.path()).unwrap_err();
+            assert_eq!(error.to_string(), message);
+            assert_eq!(other.size(), 0);
+        };
+
+        check_error(
+            &content.replace("lumol-checkpoint 1", "lumol-checkpoint 3"),
+            "unsupported checkpoint version 3, expected 1"
+        );
+        check_error(
+            &content.replace("step 42", "step -2"),
+            "invalid checkpoint at line 2: could not parse '-2'"
+        );
+        check_error(
+            &content.replace("triclinic", "spherical"),
+            "invalid checkpoint at line 3: unknown cell shape 'spherical'"
+        );
+        let truncated = content.lines().take(6).collect::<Vec<_>>().join("\n");
+        check_error(&truncated, "invalid checkpoint at line 7: unexpected end of file");
+        check_error(
+            &content.replace("6 5 0.3333333333333333", "6 4 0.3333333333333333"),
+            "invalid checkpoint at line 22: invalid indexes or spring constant for a Drude particle"
+        );
+        check_error(
+            &content.replace("0.8 0.1 0.1", "NaN 0.1 0.1"),
+            "invalid checkpoint at line 20: invalid parents or weights for a virtual site"
+        );
+        check_error(
+            &content.replace("virtual-sites 1\n3 0 1 2 0.8 0.1 0.1", "virtual-sites 2\n3 0 1 2 0.8 0.1 0.1\n3 0 1 2 0.8 0.1 0.1"),
+            "invalid checkpoint at line 21: particle 3 is already a virtual site"
+        );
+        check_error(
+            &content.replace("drude-particles 1\n6 5 0.3333333333333333", "drude-particles 2\n6 5 0.3333333333333333\n6 5 1.0"),
+            "invalid checkpoint at line 23: particle 6 is already a Drude particle"
+        );
+
+        // Checkpoints without Drude particles can still be loaded
+        let lines = content.lines().collect::<Vec<_>>();
+        let old = lines[..lines.len() - 2].join("\n");
+        let file = NamedTempFile::new().unwrap();
+        File::create(file.path()).unwrap().write_all(old.as_bytes()).unwrap();
+        let mut other = System::new();
+        other.load_checkpoint(file.path()).unwrap();
+        assert_eq!(other.size(), 7);
+        assert!(other.drude_particles().is_empty());
+
+        system.particles_mut().name[4] = String::from("Na ion");
+        let error = system.save_checkpoint(file.path()).unwrap_err();
+        assert_eq!(error.to_string(), "can not save particle with name 'Na ion' in checkpoint");
+    }
+}
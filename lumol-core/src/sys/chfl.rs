@@ -7,6 +7,7 @@ use std::sync::Once;
 
 use soa_derive::soa_zip;
 use log::warn;
+use log_once::warn_once;
 
 use crate::sys::Permutation;
 use crate::{Molecule, Particle, ParticleRef, System, UnitCell, CellShape};
@@ -15,8 +16,18 @@ use crate::Vector3D;
 impl<'a> From<&'a chemfiles::Atom> for Particle {
     fn from(atom: &'a chemfiles::Atom) -> Particle {
         let name = atom.atomic_type();
+        // Particle::new sets the mass from the periodic table, only use
+        // the chemfiles mass if it is known
         let mut particle = Particle::new(name);
-        particle.mass = atom.mass();
+        let mass = atom.mass();
+        if mass > 0.0 {
+            particle.mass = mass;
+        } else if particle.mass <= 0.0 {
+            warn_once!("unknown mass for particle '{}', setting it to 0", particle.name);
+        }
+        // chemfiles uses a zero charge when the format does not contain
+        // charges
+        particle.charge = atom.charge();
         return particle;
     }
 }
@@ -43,6 +54,12 @@ impl<'a> From<&'a chemfiles::UnitCell> for UnitCell {
 
 impl From<chemfiles::Frame> for System {
     fn from(frame: chemfiles::Frame) -> System {
+        System::from(&frame)
+    }
+}
+
+impl<'a> From<&'a chemfiles::Frame> for System {
+    fn from(frame: &'a chemfiles::Frame) -> System {
         let cell = UnitCell::from(&*frame.cell());
         let mut system = System::with_cell(cell);
 
@@ -97,6 +114,7 @@ impl<'a> From<ParticleRef<'a>> for chemfiles::Atom {
     fn from(particle: ParticleRef<'a>) -> chemfiles::Atom {
         let mut atom = chemfiles::Atom::new(&**particle.name);
         atom.set_mass(*particle.mass);
+        atom.set_charge(*particle.charge);
         return atom;
     }
 }
@@ -293,6 +311,94 @@ impl Trajectory {
         return Ok(frame.into());
     }
 
+    /// Read the step at index `step` in the trajectory, starting at 0. The
+    /// next call to [`read`](struct.Trajectory.html#method.read) will read
+    /// the step following this one.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use lumol_core::sys::TrajectoryBuilder;
+    /// let mut trajectory = TrajectoryBuilder::new()
+    ///     .open("file.nc")
+    ///     .unwrap();
+    ///
+    /// let system = trajectory.read_step(42).unwrap();
+    /// ```
+    pub fn read_step(&mut self, step: usize) -> Result<System, chemfiles::Error> {
+        let mut frame = chemfiles::Frame::new();
+        self.0.read_step(step as u64, &mut frame)?;
+        return Ok(frame.into());
+    }
+
+    /// Get the number of steps in this trajectory.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use lumol_core::sys::TrajectoryBuilder;
+    /// let mut trajectory = TrajectoryBuilder::new()
+    ///     .open("file.nc")
+    ///     .unwrap();
+    ///
+    /// let nsteps = trajectory.nsteps().unwrap();
+    /// ```
+    pub fn nsteps(&mut self) -> Result<usize, chemfiles::Error> {
+        return Ok(self.0.nsteps()? as usize);
+    }
+
+    /// Get an iterator over every `stride`-th step of this trajectory,
+    /// starting with the first one. The steps are read one at the time, and
+    /// are not kept in memory.
+    ///
+    /// # Panics
+    ///
+    /// If `stride` is zero.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use lumol_core::sys::TrajectoryBuilder;
+    /// let mut trajectory = TrajectoryBuilder::new()
+    ///     .open("file.nc")
+    ///     .unwrap();
+    ///
+    /// // read steps 0, 10, 20, ...
+    /// for system in trajectory.frames_with_stride(10) {
+    ///     let system = system.unwrap();
+    /// }
+    /// ```
+    pub fn frames_with_stride(&mut self, stride: usize) -> FramesWithStride<'_> {
+        assert!(stride > 0, "the stride must be positive in Trajectory::frames_with_stride");
+        FramesWithStride {
+            trajectory: self,
+            frame: chemfiles::Frame::new(),
+            stride: stride,
+            step: 0,
+            nsteps: None,
+        }
+    }
+
+    /// Get an iterator over all the steps of this trajectory, starting with
+    /// the first one. The steps are read one at the time, and the memory used
+    /// to read them is re-used from one step to the next.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use lumol_core::sys::TrajectoryBuilder;
+    /// let mut trajectory = TrajectoryBuilder::new()
+    ///     .open("file.nc")
+    ///     .unwrap();
+    ///
+    /// for system in trajectory.frames() {
+    ///     let system = system.unwrap();
+    /// }
+    /// ```
+    pub fn frames(&mut self) -> FramesWithStride<'_> {
+        self.frames_with_stride(1)
+    }
+
     /// Read the next step of the trajectory, and guess the bonds of the
     /// resulting [`System`][struct.System.html].
     ///
@@ -375,6 +481,55 @@ impl Trajectory {
     }
 }
 
+/// Iterator over the steps of a [`Trajectory`](struct.Trajectory.html) with a
+/// given stride, created by
+/// [`Trajectory::frames`](struct.Trajectory.html#method.frames) and
+/// [`Trajectory::frames_with_stride`](struct.Trajectory.html#method.frames_with_stride).
+pub struct FramesWithStride<'a> {
+    trajectory: &'a mut Trajectory,
+    /// Buffer used to read the steps
+    frame: chemfiles::Frame,
+    stride: usize,
+    /// Index of the next step to read
+    step: usize,
+    /// Number of steps in the trajectory, read on the first call to `next`
+    nsteps: Option<usize>,
+}
+
+impl<'a> Iterator for FramesWithStride<'a> {
+    type Item = Result<System, chemfiles::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let nsteps = match self.nsteps {
+            Some(nsteps) => nsteps,
+            None => match self.trajectory.nsteps() {
+                Ok(nsteps) => {
+                    self.nsteps = Some(nsteps);
+                    nsteps
+                }
+                Err(error) => {
+                    // stop the iteration after reporting the error
+                    self.nsteps = Some(0);
+                    return Some(Err(error));
+                }
+            },
+        };
+
+        if self.step >= nsteps {
+            return None;
+        }
+
+        let result = if self.step == 0 || self.stride != 1 {
+            self.trajectory.0.read_step(self.step as u64, &mut self.frame)
+        } else {
+            // the next step is the one following the previous read
+            self.trajectory.0.read(&mut self.frame)
+        };
+        self.step += self.stride;
+        return Some(result.map(|()| System::from(&self.frame)));
+    }
+}
+
 /// Read a the first molecule from the file at `path`. If no bond information
 /// exists in the file, bonds are guessed.
 pub fn read_molecule<P: AsRef<Path>>(path: P) -> Result<Molecule, chemfiles::Error> {
@@ -475,6 +630,51 @@ END
         assert_eq!(molecule.hash(), MoleculeHash::new(3988311241583852942));
     }
 
+    #[test]
+    fn read_charges() {
+        let mut file = tempfile::Builder::new().suffix(".mol2").tempfile().unwrap();
+        write!(file, "@<TRIPOS>MOLECULE
+NaCl
+ 3 0 0 0 0
+SMALL
+USER_CHARGES
+
+@<TRIPOS>ATOM
+      1 Na1        0.0000    0.0000    0.0000 Na        1 ION       1.0000
+      2 Cl1        2.8000    0.0000    0.0000 Cl        2 ION      -0.6000
+      3 Cl2        0.0000    2.8000    0.0000 Cl        3 ION      -0.4000
+").unwrap();
+
+        let system = TrajectoryBuilder::new()
+            .open(&file).unwrap()
+            .read().unwrap();
+
+        assert_eq!(system.size(), 3);
+        assert_eq!(system.particles().name[0], "Na");
+        assert_eq!(system.particles().charge[0], 1.0);
+        assert_eq!(system.particles().charge[1], -0.6);
+        assert_eq!(system.particles().charge[2], -0.4);
+        assert!(f64::abs(system.total_charge()) < 1e-12);
+    }
+
+    #[test]
+    fn read_helium_masses() {
+        let mut file = tempfile::Builder::new().suffix(".xyz").tempfile().unwrap();
+        write!(file, "3\n\nHe 0.0 0.0 0.0\nHe 3.0 0.0 0.0\nHe 0.0 3.0 0.0\n").unwrap();
+
+        let system = TrajectoryBuilder::new()
+            .open(&file).unwrap()
+            .read().unwrap();
+
+        assert_eq!(system.size(), 3);
+        let helium = crate::sys::periodic_table::mass("He").unwrap();
+        for &mass in system.particles().mass {
+            assert_eq!(mass, helium);
+        }
+        let total = system.particles().mass.iter().sum::<f64>();
+        assert_eq!(total, 3.0 * helium);
+    }
+
     #[test]
     fn read_pdb_water() {
         let mut file = tempfile::Builder::new().suffix(".pdb").tempfile().unwrap();
@@ -501,6 +701,47 @@ END
         assert_eq!(system.particles().name[5], "H");
     }
 
+    #[test]
+    fn read_with_stride() {
+        let mut file = tempfile::Builder::new().suffix(".xyz").tempfile().unwrap();
+        for step in 0..5 {
+            write!(file, "1\n\nAr {} 0.0 0.0\n", step).unwrap();
+        }
+
+        let mut trajectory = TrajectoryBuilder::new().open(&file).unwrap();
+        assert_eq!(trajectory.nsteps().unwrap(), 5);
+
+        let system = trajectory.read_step(3).unwrap();
+        assert_eq!(system.particles().position[0][0], 3.0);
+        // reading continues after the selected step
+        let system = trajectory.read().unwrap();
+        assert_eq!(system.particles().position[0][0], 4.0);
+
+        let positions = trajectory.frames_with_stride(2)
+            .map(|system| system.unwrap().particles().position[0][0])
+            .collect::<Vec<_>>();
+        assert_eq!(positions, [0.0, 2.0, 4.0]);
+
+        let positions = trajectory.frames_with_stride(3)
+            .map(|system| system.unwrap().particles().position[0][0])
+            .collect::<Vec<_>>();
+        assert_eq!(positions, [0.0, 3.0]);
+    }
+
+    #[test]
+    fn iterate_frames() {
+        let mut file = tempfile::Builder::new().suffix(".xyz").tempfile().unwrap();
+        write!(file, "{}", WATER).unwrap();
+        write!(file, "1\n\nAr 0.0 0.0 0.0\n").unwrap();
+        write!(file, "{}", PROPANE).unwrap();
+
+        let mut trajectory = TrajectoryBuilder::new().open(&file).unwrap();
+        let sizes = trajectory.frames()
+            .map(|system| system.unwrap().size())
+            .collect::<Vec<_>>();
+        assert_eq!(sizes, [3, 1, 11]);
+    }
+
     #[test]
     fn read_propane() {
         let mut file = tempfile::Builder::new().suffix(".xyz").tempfile().unwrap();
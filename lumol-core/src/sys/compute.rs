@@ -11,7 +11,8 @@ use log_once::warn_once;
 
 use crate::consts::K_BOLTZMANN;
 use crate::{Matrix3, Vector3D};
-use crate::{System, DegreesOfFreedom};
+use crate::{System, DegreesOfFreedom, PairNeighbors};
+use crate::sys::energy::for_each_triplet;
 
 use crate::utils::ThreadLocalVec;
 
@@ -31,79 +32,184 @@ pub struct Forces;
 impl Compute for Forces {
     type Output = Vec<Vector3D>;
     fn compute(&self, system: &System) -> Vec<Vector3D> {
-        let natoms = system.size();
-        let thread_local_forces = ThreadLocalVec::with_size(natoms);
+        let mut forces = pair_forces(system);
+        add_bonded_forces(system, &mut forces);
+        add_non_pair_forces(system, &mut forces);
+        distribute_virtual_sites_forces(system, &mut forces);
+        zero_frozen_forces(system, &mut forces);
+        return forces;
+    }
+}
 
-        (0..natoms).into_par_iter().for_each(|i| {
-            let mut forces = thread_local_forces.borrow_mut();
-            let mut force_i = Vector3D::zero();
-            for j in (i + 1)..system.size() {
-                let path = system.bond_path(i, j);
-                let d = system.nearest_image(i, j);
-                let dn = d.normalized();
-                let r = d.norm();
-                if let Some(potential) = system.pair_potential(i, j) {
-                    let info = potential.restriction().information(path);
-                    if !info.excluded {
-                        let force = info.scaling * potential.force(r) * dn;
-                        force_i += force;
-                        forces[j] -= force;
-                    }
-                }
-            }
-            forces[i] += force_i;
-        });
+/// Compute the forces coming from the bonded interactions (bonds, angles,
+/// dihedral angles, improper dihedral angles, cross terms and Drude bonds) in
+/// the system, and return a vector of force acting on each particles.
+pub struct BondedForces;
+impl Compute for BondedForces {
+    type Output = Vec<Vector3D>;
+    fn compute(&self, system: &System) -> Vec<Vector3D> {
+        let mut forces = vec![Vector3D::zero(); system.size()];
+        add_bonded_forces(system, &mut forces);
+        distribute_virtual_sites_forces(system, &mut forces);
+        zero_frozen_forces(system, &mut forces);
+        return forces;
+    }
+}
 
-        // At this point all the forces are computed, but the results are
-        // scattered across all thread local Vecs, here we gather them.
-        let mut forces = vec![Vector3D::zero(); natoms];
-        thread_local_forces.sum_into(&mut forces);
+/// Compute the forces coming from all the interactions except the bonded
+/// ones (pairs, three-body, coulombic, global and external potentials), and
+/// return a vector of force acting on each particles.
+///
+/// The sum of `BondedForces` and `NonBondedForces` is equal to `Forces`.
+pub struct NonBondedForces;
+impl Compute for NonBondedForces {
+    type Output = Vec<Vector3D>;
+    fn compute(&self, system: &System) -> Vec<Vector3D> {
+        let mut forces = pair_forces(system);
+        add_non_pair_forces(system, &mut forces);
+        distribute_virtual_sites_forces(system, &mut forces);
+        zero_frozen_forces(system, &mut forces);
+        return forces;
+    }
+}
 
-        for molecule in system.molecules() {
-            for bond in molecule.bonds() {
-                let (i, j) = (bond.i(), bond.j());
-                let d = system.nearest_image(i, j);
-                let dn = d.normalized();
-                let r = d.norm();
-                if let Some(potential) = system.bond_potential(i, j) {
-                    let force = potential.force(r) * dn;
-                    forces[i] += force;
+/// Get the forces coming from the pair interactions in the `system`
+fn pair_forces(system: &System) -> Vec<Vector3D> {
+    let natoms = system.size();
+    let thread_local_forces = ThreadLocalVec::with_size(natoms);
+    let neighbor_list = system.neighbor_list();
+    let neighbor_list = neighbor_list.as_deref();
+
+    (0..natoms).into_par_iter().for_each(|i| {
+        let mut forces = thread_local_forces.borrow_mut();
+        let mut force_i = Vector3D::zero();
+        for j in PairNeighbors::new(neighbor_list, i, natoms) {
+            if let Some(potential) = system.pair_potential(i, j) {
+                let info = potential.restriction().information_between(system, i, j);
+                if !info.excluded {
+                    let d = system.nearest_image(i, j);
+                    let force = info.scaling * potential.force(d.norm()) * d.normalized();
+                    force_i += force;
                     forces[j] -= force;
                 }
             }
+        }
+        forces[i] += force_i;
+    });
+
+    // At this point all the forces are computed, but the results are
+    // scattered across all thread local Vecs, here we gather them.
+    let mut forces = vec![Vector3D::zero(); natoms];
+    thread_local_forces.sum_into(&mut forces);
+    return forces;
+}
 
-            for angle in molecule.angles() {
-                let (i, j, k) = (angle.i(), angle.j(), angle.k());
-                let (theta, d1, d2, d3) = system.angle_and_derivatives(i, j, k);
-                if let Some(potential) = system.angle_potential(i, j, k) {
-                    let force = potential.force(theta);
-                    forces[i] += force * d1;
-                    forces[j] += force * d2;
-                    forces[k] += force * d3;
-                }
+/// Add the forces coming from the bonded interactions in the `system` to
+/// `forces`
+fn add_bonded_forces(system: &System, forces: &mut [Vector3D]) {
+    for molecule in system.molecules() {
+        for bond in molecule.bonds() {
+            let (i, j) = (bond.i(), bond.j());
+            let d = system.nearest_image(i, j);
+            let dn = d.normalized();
+            let r = d.norm();
+            if let Some(potential) = system.bond_potential(i, j) {
+                let force = potential.force(r) * dn;
+                forces[i] += force;
+                forces[j] -= force;
             }
+        }
 
-            for dihedral in molecule.dihedrals() {
-                let (i, j, k, m) = (dihedral.i(), dihedral.j(), dihedral.k(), dihedral.m());
+        for angle in molecule.angles() {
+            let (i, j, k) = (angle.i(), angle.j(), angle.k());
+            let (theta, d1, d2, d3) = system.angle_and_derivatives(i, j, k);
+            if let Some(potential) = system.angle_potential(i, j, k) {
+                let force = potential.force(theta);
+                forces[i] += force * d1;
+                forces[j] += force * d2;
+                forces[k] += force * d3;
+            }
+        }
+
+        for dihedral in molecule.dihedrals() {
+            let (i, j, k, m) = (dihedral.i(), dihedral.j(), dihedral.k(), dihedral.m());
+            let (phi, d1, d2, d3, d4) = system.dihedral_and_derivatives(i, j, k, m);
+            if let Some(potential) = system.dihedral_potential(i, j, k, m) {
+                let force = potential.force(phi);
+                forces[i] += force * d1;
+                forces[j] += force * d2;
+                forces[k] += force * d3;
+                forces[m] += force * d4;
+            }
+        }
+
+        for improper in molecule.impropers() {
+            let (i, j, k, m) = (improper.i(), improper.j(), improper.k(), improper.m());
+            if let Some(potential) = system.improper_potential(i, j, k, m) {
                 let (phi, d1, d2, d3, d4) = system.dihedral_and_derivatives(i, j, k, m);
-                if let Some(potential) = system.dihedral_potential(i, j, k, m) {
-                    let force = potential.force(phi);
-                    forces[i] += force * d1;
-                    forces[j] += force * d2;
-                    forces[k] += force * d3;
-                    forces[m] += force * d4;
-                }
+                let force = potential.force(phi);
+                forces[i] += force * d1;
+                forces[j] += force * d2;
+                forces[k] += force * d3;
+                forces[m] += force * d4;
             }
         }
 
-        if let Some(coulomb) = system.coulomb_potential() {
-            coulomb.forces(system, &mut forces);
+        for angle in molecule.angles() {
+            let (i, j, k) = (angle.i(), angle.j(), angle.k());
+            if let Some((force_i, force_j, force_k)) = system.cross_term_forces(i, j, k) {
+                forces[i] += force_i;
+                forces[j] += force_j;
+                forces[k] += force_k;
+            }
         }
+    }
 
-        for global in system.global_potentials() {
-            global.forces(system, &mut forces);
+    for drude in system.drude_particles() {
+        let force = drude.force(system);
+        forces[drude.drude()] += force;
+        forces[drude.core()] -= force;
+    }
+}
+
+/// Add the forces coming from the three-body, coulombic, global and external
+/// potentials in the `system` to `forces`
+fn add_non_pair_forces(system: &System, forces: &mut [Vector3D]) {
+    for_each_triplet(system, system.particles().position, |i, j, k, r_ij, r_ik, potential| {
+        let (force_j, force_k) = potential.forces(r_ij, r_ik);
+        forces[i] -= force_j + force_k;
+        forces[j] += force_j;
+        forces[k] += force_k;
+    });
+
+    if let Some(coulomb) = system.charged_coulomb_potential() {
+        coulomb.forces(system, forces);
+    }
+
+    for global in system.global_potentials() {
+        global.forces(system, forces);
+    }
+
+    for external in system.external_potentials() {
+        for (i, position) in system.particles().position.iter().enumerate() {
+            forces[i] += external.force(system, i, position);
         }
-        return forces;
+    }
+}
+
+/// Distribute the forces acting on the virtual sites of the `system` to the
+/// particles they are built on.
+fn distribute_virtual_sites_forces(system: &System, forces: &mut [Vector3D]) {
+    // Sites built on top of other sites need to be handled first
+    for site in system.virtual_sites().iter().rev() {
+        site.distribute_force(forces);
+    }
+}
+
+/// Set the forces acting on the frozen particles of the `system` to zero
+fn zero_frozen_forces(system: &System, forces: &mut [Vector3D]) {
+    for &i in system.frozen() {
+        forces[i] = Vector3D::zero();
     }
 }
 
@@ -117,16 +223,69 @@ impl Compute for PotentialEnergy {
         let mut energy = evaluator.pairs();
         energy += evaluator.pairs_tail();
         energy += evaluator.bonds();
+        energy += evaluator.drude();
         energy += evaluator.angles();
         energy += evaluator.dihedrals();
+        energy += evaluator.impropers();
+        energy += evaluator.cross_terms();
+        energy += evaluator.three_body();
         energy += evaluator.coulomb();
         energy += evaluator.global();
+        energy += evaluator.external();
 
         assert!(energy.is_finite(), "Potential energy is infinite!");
         return energy;
     }
 }
 
+/// Compute the derivative of the potential energy of the system with respect
+/// to the coupling parameter $\lambda$ of the pair potentials.
+///
+/// $$ \frac{\partial U}{\partial \lambda} = \sum_i \sum_{j > i}
+///    \frac{\partial V_{ij}(r_{ij})}{\partial \lambda} $$
+///
+/// Only the pair potentials depending on a coupling parameter (such as
+/// [`ScaledComputation`](../energy/struct.ScaledComputation.html) or
+/// [`SoftCore`](../energy/struct.SoftCore.html)) contribute to this value.
+/// Averaging it over a simulation gives the integrand of thermodynamic
+/// integration free energy calculations.
+pub struct LambdaDerivative;
+impl Compute for LambdaDerivative {
+    type Output = f64;
+    fn compute(&self, system: &System) -> f64 {
+        let derivatives = (0..system.size()).into_par_iter().map(|i| {
+            let mut local_derivative = 0.0;
+            for j in (i + 1)..system.size() {
+                if let Some(potential) = system.pair_potential(i, j) {
+                    let info = potential.restriction().information_between(system, i, j);
+                    if !info.excluded {
+                        let r = system.nearest_image(i, j).norm();
+                        local_derivative += info.scaling * potential.du_dlambda(r);
+                    }
+                }
+            }
+            local_derivative
+        });
+        let mut derivative: f64 = derivatives.sum();
+
+        if !system.cell.is_infinite() {
+            let volume = system.volume();
+            let composition = system.composition();
+            for (i, ni) in composition.all_particles() {
+                for (j, nj) in composition.all_particles() {
+                    let two_pi_density = 2.0 * PI * (ni as f64) * (nj as f64) / volume;
+                    if let Some(potential) = system.interactions().pair((i, j)) {
+                        derivative += two_pi_density * potential.tail_du_dlambda();
+                    }
+                }
+            }
+        }
+
+        assert!(derivative.is_finite(), "dU/dlambda is infinite!");
+        return derivative;
+    }
+}
+
 /// Compute the kinetic energy of the system
 ///
 /// $$ K = \sum_i m_i \vec v_i \cdot \vec v_i $$
@@ -199,12 +358,13 @@ impl Compute for AtomicVirial {
         assert!(!system.cell.is_infinite(), "Can not compute virial for infinite cell");
 
         // Pair potentials contributions
+        let neighbor_list = system.neighbor_list();
+        let neighbor_list = neighbor_list.as_deref();
         let pair_virials = (0..system.size()).into_par_iter().map(|i| {
             let mut local_virial = Matrix3::zero();
-            for j in (i + 1)..system.size() {
-                let path = system.bond_path(i, j);
+            for j in PairNeighbors::new(neighbor_list, i, system.size()) {
                 if let Some(potential) = system.pair_potential(i, j) {
-                    let info = potential.restriction().information(path);
+                    let info = potential.restriction().information_between(system, i, j);
                     if !info.excluded {
                         let d = system.nearest_image(i, j);
                         local_virial += info.scaling * potential.virial(&d);
@@ -238,11 +398,82 @@ impl Compute for AtomicVirial {
             }
         }
 
-        // Angles and dihedrals potentials do not contribute as they only have
-        // an angular part (see DL_POLY 4 manual page 18, or Smith, W., 1993,
-        // CCP5 Information Quarterly, 39, 14. 18, 21, 24).
+        // Drude bonds contributions
+        for drude in system.drude_particles() {
+            virial += drude.force(system).tensorial(&drude.displacement(system));
+        }
 
-        if let Some(coulomb) = system.coulomb_potential() {
+        // Angles, dihedrals and impropers potentials contributions. They only have an
+        // angular part, so they do not contribute to the trace of the virial
+        // (i.e. the pressure), but they do contribute to the off-diagonal
+        // parts of the tensor. The forces sum to zero, so we can use positions
+        // relative to the second particle, and use the chain rule on the
+        // internal coordinate to get the forces (see DL_POLY 4 manual, or
+        // Smith, W., 1993, CCP5 Information Quarterly, 39, 14. 18, 21, 24).
+        for molecule in system.molecules() {
+            for angle in molecule.angles() {
+                let (i, j, k) = (angle.i(), angle.j(), angle.k());
+                if let Some(potential) = system.angle_potential(i, j, k) {
+                    let (theta, d1, _, d3) = system.angle_and_derivatives(i, j, k);
+                    let force = potential.force(theta);
+                    let r_ij = system.nearest_image(i, j);
+                    let r_kj = system.nearest_image(k, j);
+                    virial += (force * d1).tensorial(&r_ij);
+                    virial += (force * d3).tensorial(&r_kj);
+                }
+            }
+
+            for dihedral in molecule.dihedrals() {
+                let (i, j, k, m) = (dihedral.i(), dihedral.j(), dihedral.k(), dihedral.m());
+                if let Some(potential) = system.dihedral_potential(i, j, k, m) {
+                    let (phi, d1, _, d3, d4) = system.dihedral_and_derivatives(i, j, k, m);
+                    let force = potential.force(phi);
+                    let r_ij = system.nearest_image(i, j);
+                    let r_kj = system.nearest_image(k, j);
+                    let r_mj = r_kj + system.nearest_image(m, k);
+                    virial += (force * d1).tensorial(&r_ij);
+                    virial += (force * d3).tensorial(&r_kj);
+                    virial += (force * d4).tensorial(&r_mj);
+                }
+            }
+
+            for improper in molecule.impropers() {
+                let (i, j, k, m) = (improper.i(), improper.j(), improper.k(), improper.m());
+                if let Some(potential) = system.improper_potential(i, j, k, m) {
+                    let (phi, d1, _, d3, d4) = system.dihedral_and_derivatives(i, j, k, m);
+                    let force = potential.force(phi);
+                    let r_ij = system.nearest_image(i, j);
+                    let r_kj = system.nearest_image(k, j);
+                    let r_mj = r_kj + system.nearest_image(m, k);
+                    virial += (force * d1).tensorial(&r_ij);
+                    virial += (force * d3).tensorial(&r_kj);
+                    virial += (force * d4).tensorial(&r_mj);
+                }
+            }
+        }
+
+        // Cross terms contributions, using positions relative to the central
+        // particle since the forces sum to zero. These terms depend on the
+        // bond lengths, and contribute to the pressure.
+        for molecule in system.molecules() {
+            for angle in molecule.angles() {
+                let (i, j, k) = (angle.i(), angle.j(), angle.k());
+                if let Some((force_i, _, force_k)) = system.cross_term_forces(i, j, k) {
+                    virial += force_i.tensorial(&system.nearest_image(i, j));
+                    virial += force_k.tensorial(&system.nearest_image(k, j));
+                }
+            }
+        }
+
+        // Three-body potentials contributions, using positions relative to
+        // the central particle since the forces sum to zero.
+        for_each_triplet(system, system.particles().position, |_, _, _, r_ij, r_ik, potential| {
+            let (force_j, force_k) = potential.forces(r_ij, r_ik);
+            virial += force_j.tensorial(r_ij);
+            virial += force_k.tensorial(r_ik);
+        });
+
+        if let Some(coulomb) = system.charged_coulomb_potential() {
             virial += coulomb.atomic_virial(system);
         }
 
@@ -294,10 +525,9 @@ impl Compute for MolecularVirial {
 
                 for part_a in molecule_i.indexes() {
                     for part_b in molecule_j.indexes() {
-                        let path = system.bond_path(part_a, part_b);
                         let r_ab = system.nearest_image(part_a, part_b);
                         if let Some(potential) = system.pair_potential(part_a, part_b) {
-                            let info = potential.restriction().information(path);
+                            let info = potential.restriction().information_between(system, part_a, part_b);
                             if !info.excluded {
                                 let w_ab = info.scaling * potential.virial(&r_ab);
                                 local_virial += w_ab * (r_ab * r_ij) / r_ab.norm2();
@@ -347,11 +577,31 @@ impl Compute for MolecularVirial {
             }
         }
 
-        // Angles and dihedrals potentials do not contribute as they only have
-        // an angular part (see DL_POLY 4 manual page 18, or Smith, W., 1993,
-        // CCP5 Information Quarterly, 39, 14. 18, 21, 24).
+        // Angles, dihedrals, impropers, cross terms and Drude bonds are
+        // intra-molecular, and do not contribute to the molecular virial.
+
+        // Three-body potentials contributions, using the molecular centers of
+        // mass. `offsets` contains the position of each particle relative to
+        // the center of mass of its molecule.
+        if system.interactions().three_body_cutoff().is_some() {
+            let mut offsets = vec![Vector3D::zero(); system.size()];
+            for molecule in system.molecules() {
+                let center = molecule.center_of_mass();
+                for i in molecule.indexes() {
+                    let mut offset = system.particles().position[i] - center;
+                    system.cell.vector_image(&mut offset);
+                    offsets[i] = offset;
+                }
+            }
+
+            for_each_triplet(system, system.particles().position, |i, j, k, r_ij, r_ik, potential| {
+                let (force_j, force_k) = potential.forces(r_ij, r_ik);
+                virial += force_j.tensorial(&(r_ij - offsets[j] + offsets[i]));
+                virial += force_k.tensorial(&(r_ik - offsets[k] + offsets[i]));
+            });
+        }
 
-        if let Some(coulomb) = system.coulomb_potential() {
+        if let Some(coulomb) = system.charged_coulomb_potential() {
             virial += coulomb.molecular_virial(system);
         }
 
@@ -484,11 +734,11 @@ mod test {
     use super::*;
     use crate::System;
     use crate::consts::K_BOLTZMANN;
-    use crate::{Harmonic, NullPotential, PairInteraction};
+    use crate::{BondAngle, Harmonic, LennardJones, NullPotential, PairInteraction, ScaledComputation};
     use crate::utils::system_from_xyz;
     use crate::units;
 
-    use approx::assert_ulps_eq;
+    use approx::{assert_relative_eq, assert_ulps_eq};
 
     fn test_pairs_system() -> System {
         let mut system = system_from_xyz(
@@ -590,6 +840,28 @@ mod test {
         assert_ulps_eq!(forces_tot.norm2(), 0.0);
     }
 
+    #[test]
+    fn bonded_and_non_bonded_forces() {
+        let mut system = test_molecular_system();
+        let lj = LennardJones {
+            sigma: units::from(1.0, "A").unwrap(),
+            epsilon: units::from(2.0, "kJ/mol").unwrap(),
+        };
+        system.set_pair_potential(("F", "F"), PairInteraction::new(Box::new(lj), 5.0));
+
+        let forces = Forces.compute(&system);
+        let bonded = BondedForces.compute(&system);
+        let non_bonded = NonBondedForces.compute(&system);
+        for i in 0..system.size() {
+            assert_ne!(bonded[i], Vector3D::zero());
+            assert_ne!(non_bonded[i], Vector3D::zero());
+            assert_ulps_eq!(bonded[i] + non_bonded[i], forces[i], epsilon = 1e-12);
+        }
+
+        let total = bonded.iter().fold(Vector3D::zero(), |acc, &f| acc + f);
+        assert_ulps_eq!(total.norm2(), 0.0);
+    }
+
     #[test]
     fn energy_pairs() {
         let system = &test_pairs_system();
@@ -611,6 +883,102 @@ mod test {
         assert_ulps_eq!(PotentialEnergy.compute(&system), units::from(1800.0, "kJ/mol").unwrap());
     }
 
+    #[test]
+    fn energy_tail_correction() {
+        // Simple cubic lattice of 4x4x4 particles
+        let mut xyz = String::from("64\ncell: 20.0\n");
+        for i in 0..4 {
+            for j in 0..4 {
+                for k in 0..4 {
+                    xyz += &format!("Ar {} {} {}\n", 5.0 * i as f64, 5.0 * j as f64, 5.0 * k as f64);
+                }
+            }
+        }
+        let mut system = system_from_xyz(&xyz);
+
+        let (sigma, epsilon, rc) = (3.4, 0.5, 8.0);
+        let lj = LennardJones { sigma: sigma, epsilon: epsilon };
+        system.set_pair_potential(("Ar", "Ar"), PairInteraction::new(Box::new(lj), rc));
+        let truncated = PotentialEnergy.compute(&system);
+
+        let mut interaction = PairInteraction::new(Box::new(lj), rc);
+        interaction.enable_tail_corrections();
+        system.set_pair_potential(("Ar", "Ar"), interaction);
+        let corrected = PotentialEnergy.compute(&system);
+
+        // E_tail = 8/3 π N ρ ε σ^3 [1/3 (σ/rc)^9 - (σ/rc)^3]
+        let natoms = 64.0;
+        let density = natoms / system.volume();
+        let ratio = sigma / rc;
+        let expected = 8.0 / 3.0 * PI * natoms * density * epsilon * sigma.powi(3)
+            * (ratio.powi(9) / 3.0 - ratio.powi(3));
+        assert_relative_eq!(corrected - truncated, expected, max_relative = 1e-9);
+    }
+
+    #[test]
+    fn lambda_derivative() {
+        let mut xyz = String::from("27\ncell: 12.0\n");
+        for i in 0..3 {
+            for j in 0..3 {
+                for k in 0..3 {
+                    let shift = 0.2 * ((i + 2 * j + k) % 3) as f64;
+                    xyz += &format!("Ar {} {} {}\n", 4.0 * i as f64 + shift, 4.0 * j as f64, 4.0 * k as f64);
+                }
+            }
+        }
+        let mut system = system_from_xyz(&xyz);
+
+        let lj = LennardJones { sigma: 3.4, epsilon: 0.5 };
+        let mut interaction = PairInteraction::new(Box::new(lj), 5.5);
+        interaction.enable_tail_corrections();
+        system.set_pair_potential(("Ar", "Ar"), interaction);
+        let unscaled = PotentialEnergy.compute(&system);
+        assert_eq!(LambdaDerivative.compute(&system), 0.0);
+
+        // For a linearly scaled potential, dU/dlambda is the unscaled energy
+        let scaled = ScaledComputation::new(Box::new(lj), 0.3);
+        let mut interaction = PairInteraction::new(Box::new(scaled), 5.5);
+        interaction.enable_tail_corrections();
+        system.set_pair_potential(("Ar", "Ar"), interaction);
+
+        let du_dlambda = LambdaDerivative.compute(&system);
+        assert_relative_eq!(du_dlambda, unscaled, max_relative = 1e-12);
+        assert_eq!(du_dlambda, system.du_dlambda());
+        assert_relative_eq!(PotentialEnergy.compute(&system), 0.3 * unscaled, max_relative = 1e-12);
+    }
+
+    #[test]
+    fn parallel_and_serial_agree() {
+        let mut xyz = String::from("125\ncell: 20.0\n");
+        for i in 0..5 {
+            for j in 0..5 {
+                for k in 0..5 {
+                    let shift = 0.1 * ((i + 2 * j + 3 * k) % 7) as f64;
+                    let (x, y, z) = (4.0 * i as f64 + shift, 4.0 * j as f64, 4.0 * k as f64 - shift);
+                    xyz += &format!("Ar {} {} {}\n", x, y, z);
+                }
+            }
+        }
+        let mut system = system_from_xyz(&xyz);
+        system.set_pair_potential(
+            ("Ar", "Ar"),
+            PairInteraction::new(Box::new(LennardJones { sigma: 3.4, epsilon: 0.5 }), 9.0),
+        );
+
+        let pool = rayon::ThreadPoolBuilder::new().num_threads(1).build().unwrap();
+        let (serial_energy, serial_forces) = pool.install(|| {
+            (PotentialEnergy.compute(&system), Forces.compute(&system))
+        });
+
+        let energy = PotentialEnergy.compute(&system);
+        let forces = Forces.compute(&system);
+
+        assert_relative_eq!(energy, serial_energy, max_relative = 1e-12);
+        for (force, serial) in forces.iter().zip(&serial_forces) {
+            assert_relative_eq!(*force, *serial, epsilon = 1e-12, max_relative = 1e-12);
+        }
+    }
+
     #[test]
     fn temperature() {
         let system = &test_pairs_system();
@@ -656,10 +1024,72 @@ mod test {
         expected[0][0] = 2.0 * w;
         expected[1][1] = 1.0 * w;
 
-        assert_ulps_eq!(virial, expected);
+        // Angles contributions, all the angles are at 90°
+        let k = units::from(100.0, "kJ/mol/deg^2").unwrap();
+        let force = -k * units::from(2.0, "deg").unwrap();
+        expected[0][1] = 2.0 * force;
+        expected[1][0] = 2.0 * force;
+
+        assert_relative_eq!(virial, expected, max_relative = 1e-12);
         assert_eq!(virial, system.virial());
     }
 
+    #[test]
+    fn virial_symmetric() {
+        let mut system = system_from_xyz(
+            "4
+            cell: 10.0
+            F 0.0 0.0 0.0
+            F 1.1 0.2 0.1
+            F 1.3 1.2 -0.3
+            F 2.1 1.6 0.5
+            ",
+        );
+        assert!(system.add_bond(0, 1).is_empty());
+        assert!(system.add_bond(1, 2).is_empty());
+        assert!(system.add_bond(2, 3).is_empty());
+
+        system.set_pair_potential(("F", "F"), PairInteraction::new(Box::new(NullPotential), 0.0));
+        system.set_bond_potential(("F", "F"), Box::new(Harmonic { k: 100.0, x0: 2.0 }));
+        system.set_angle_potential(("F", "F", "F"), Box::new(Harmonic { k: 100.0, x0: 1.5 }));
+        system.set_dihedral_potential(("F", "F", "F", "F"), Box::new(Harmonic { k: 100.0, x0: 2.0 }));
+
+        let virial = AtomicVirial.compute(&system);
+        assert_relative_eq!(virial, virial.transposed(), max_relative = 1e-12);
+
+        let stress = system.stress();
+        assert_relative_eq!(stress, stress.transposed(), max_relative = 1e-12);
+    }
+
+    #[test]
+    fn pressure_dilute_gas() {
+        let mut system = system_from_xyz(
+            "4
+            cell: 100.0
+            Ar 0.0 0.0 0.0
+            Ar 20.0 0.0 0.0
+            Ar 0.0 20.0 0.0
+            Ar 0.0 0.0 20.0
+            ",
+        );
+        system.set_pair_potential(
+            ("Ar", "Ar"),
+            PairInteraction::new(
+                Box::new(LennardJones {
+                    sigma: units::from(3.4, "A").unwrap(),
+                    epsilon: units::from(1.0, "kJ/mol").unwrap(),
+                }),
+                30.0,
+            ),
+        );
+
+        let temperature = 300.0;
+        let pressure = PressureAtTemperature { temperature: temperature }.compute(&system);
+        let ideal = 4.0 * K_BOLTZMANN * temperature / system.volume();
+        assert_relative_eq!(pressure, ideal, max_relative = 1e-3);
+        assert!(pressure != ideal);
+    }
+
     #[test]
     #[should_panic]
     fn pressure_at_temperature_negative_temperature() {
@@ -783,4 +1213,264 @@ mod test {
         assert_ulps_eq!(pressure, expected);
         assert_eq!(pressure, system.pressure());
     }
+
+    /// Create a system of silicon atoms interacting with the Stillinger-Weber
+    /// potential, using the original parameters
+    fn silicon(cell: f64, positions: &[Vector3D]) -> System {
+        use crate::{Molecule, Particle, StillingerWeber, UnitCell};
+
+        let mut system = System::with_cell(UnitCell::cubic(cell));
+        for &position in positions {
+            system.add_molecule(Molecule::new(Particle::with_position("Si", position)));
+        }
+
+        let epsilon = units::from(2.1683, "eV").unwrap();
+        let sw = StillingerWeber::silicon(epsilon, 2.0951);
+        system.set_pair_potential(("Si", "Si"), PairInteraction::new(Box::new(sw), sw.cutoff()));
+        system.add_three_body_interaction(("Si", "Si", "Si"), Box::new(sw));
+        return system;
+    }
+
+    #[test]
+    fn stillinger_weber_diamond() {
+        // 2x2x2 supercell of diamond silicon
+        let a0 = 5.431;
+        let basis = [
+            [0.0, 0.0, 0.0], [0.0, 0.5, 0.5], [0.5, 0.0, 0.5], [0.5, 0.5, 0.0],
+            [0.25, 0.25, 0.25], [0.25, 0.75, 0.75], [0.75, 0.25, 0.75], [0.75, 0.75, 0.25],
+        ];
+        let mut positions = Vec::new();
+        for i in 0..2 {
+            for j in 0..2 {
+                for k in 0..2 {
+                    for atom in &basis {
+                        let position = Vector3D::new(
+                            atom[0] + i as f64, atom[1] + j as f64, atom[2] + k as f64
+                        );
+                        positions.push(a0 * position);
+                    }
+                }
+            }
+        }
+        let system = silicon(2.0 * a0, &positions);
+
+        // The three-body term vanishes for the tetrahedral angles, and
+        // Stillinger and Weber report a cohesive energy of -2 epsilon, i.e.
+        // -4.3366 eV by atom, for the diamond structure.
+        assert!(f64::abs(system.energy_evaluator().three_body()) < 1e-10);
+        let energy = units::to(system.potential_energy() / 64.0, "eV").unwrap();
+        assert_relative_eq!(energy, -4.3366, max_relative = 1e-4);
+
+        for force in system.forces() {
+            assert!(force.norm() < 1e-10);
+        }
+    }
+
+    #[test]
+    fn stillinger_weber_cluster() {
+        let mut system = silicon(20.0, &[
+            Vector3D::new(10.0, 10.0, 10.0),
+            Vector3D::new(12.3, 10.2, 9.9),
+            Vector3D::new(9.3, 12.2, 10.1),
+            Vector3D::new(9.5, 9.4, 12.2),
+            Vector3D::new(11.6, 12.1, 11.8),
+        ]);
+        assert!(system.energy_evaluator().three_body() > 0.0);
+
+        let forces = system.forces();
+        let total = forces.iter().fold(Vector3D::zero(), |total, &force| total + force);
+        assert!(total.norm() < 1e-10);
+
+        // Check the forces with finite differences
+        const EPS: f64 = 1e-6;
+        let energy = system.potential_energy();
+        for i in 0..5 {
+            for axis in 0..3 {
+                system.particles_mut().position[i][axis] += EPS;
+                let moved = system.potential_energy();
+                system.particles_mut().position[i][axis] -= EPS;
+                assert_relative_eq!((energy - moved) / EPS, forces[i][axis], epsilon = 1e-4, max_relative = 1e-4);
+            }
+        }
+
+        // The trace of the virial is related to the energy change when
+        // scaling all the positions
+        let virial = AtomicVirial.compute(&system);
+        let delta = 1e-6;
+        let mut scaled = system.clone();
+        for position in scaled.particles_mut().position {
+            *position *= 1.0 + delta;
+        }
+        let derivative = (scaled.potential_energy() - energy) / delta;
+        assert_relative_eq!(virial.trace(), -derivative, max_relative = 1e-4);
+
+        // Atoms are molecules, so the molecular virial is the same
+        assert_relative_eq!(MolecularVirial.compute(&system), virial, max_relative = 1e-12);
+    }
+
+    #[test]
+    fn position_restraint() {
+        use crate::{Molecule, Particle, UnitCell};
+        let mut system = System::with_cell(UnitCell::cubic(10.0));
+        system.add_molecule(Molecule::new(Particle::with_position("Ar", Vector3D::new(1.0, 1.0, 1.0))));
+        system.add_molecule(Molecule::new(Particle::with_position("Ar", Vector3D::new(4.0, 1.0, 1.0))));
+        system.add_position_restraint(0, Vector3D::new(1.0, 1.0, 1.0), 3.0);
+
+        // No energy or force at the reference position
+        assert_eq!(PotentialEnergy.compute(&system), 0.0);
+        assert_eq!(Forces.compute(&system)[0], Vector3D::zero());
+
+        system.particles_mut().position[0] = Vector3D::new(1.5, 1.0, 0.0);
+        assert_ulps_eq!(PotentialEnergy.compute(&system), 0.5 * 3.0 * 1.25);
+
+        // The force points back toward the reference position
+        let forces = Forces.compute(&system);
+        assert_ulps_eq!(forces[0], Vector3D::new(-1.5, 0.0, 3.0));
+        assert_eq!(forces[1], Vector3D::zero());
+    }
+
+    #[test]
+    fn electric_field() {
+        use crate::{Molecule, Particle, UnitCell};
+        let mut system = System::with_cell(UnitCell::cubic(10.0));
+        system.add_molecule(Molecule::new(Particle::with_position("Na", Vector3D::new(1.0, 0.0, 0.0))));
+        system.add_molecule(Molecule::new(Particle::with_position("Cl", Vector3D::new(4.0, 0.0, 0.0))));
+        system.add_molecule(Molecule::new(Particle::with_position("Ar", Vector3D::new(7.0, 0.0, 0.0))));
+        system.particles_mut().charge[0] = 1.0;
+        system.particles_mut().charge[1] = -1.0;
+
+        assert_eq!(system.electric_field(), None);
+        system.set_electric_field(Vector3D::new(0.5, 0.0, 0.0));
+        assert_eq!(system.electric_field(), Some(Vector3D::new(0.5, 0.0, 0.0)));
+
+        assert_ulps_eq!(PotentialEnergy.compute(&system), -0.5 + 2.0);
+        let forces = Forces.compute(&system);
+        assert_ulps_eq!(forces[0], Vector3D::new(0.5, 0.0, 0.0));
+        assert_ulps_eq!(forces[1], Vector3D::new(-0.5, 0.0, 0.0));
+        assert_eq!(forces[2], Vector3D::zero());
+
+        // Setting a new field replaces the previous one
+        system.set_electric_field(Vector3D::new(0.0, 0.0, 1.0));
+        let forces = Forces.compute(&system);
+        assert_ulps_eq!(forces[0], Vector3D::new(0.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn spherical_wall() {
+        use crate::{Molecule, Particle, SphericalWall, UnitCell};
+        let mut system = System::with_cell(UnitCell::infinite());
+        system.add_molecule(Molecule::new(Particle::with_position("Ar", Vector3D::new(1.0, 0.0, 0.0))));
+        system.add_molecule(Molecule::new(Particle::with_position("Ar", Vector3D::new(0.0, 0.0, 12.0))));
+        system.add_wall(Box::new(SphericalWall::new(Vector3D::zero(), 10.0, 5.0)));
+
+        assert_ulps_eq!(PotentialEnergy.compute(&system), 10.0);
+        let forces = Forces.compute(&system);
+        assert_eq!(forces[0], Vector3D::zero());
+        assert_ulps_eq!(forces[1], Vector3D::new(0.0, 0.0, -10.0));
+    }
+
+    #[test]
+    fn slit_pore() {
+        use crate::{LennardJones93, Molecule, Particle, Potential, UnitCell};
+        let mut system = System::with_cell(UnitCell::ortho(10.0, 10.0, 30.0));
+        system.add_molecule(Molecule::new(Particle::with_position("Ar", Vector3D::new(1.0, 2.0, 5.0))));
+        system.add_molecule(Molecule::new(Particle::with_position("Ar", Vector3D::new(5.0, 5.0, 0.6))));
+
+        let potential = LennardJones93 { sigma: 1.0, epsilon: 2.0 };
+        system.add_planar_wall(2, 0.0, Box::new(potential));
+        system.add_planar_wall(2, 10.0, Box::new(potential));
+
+        // The particle in the middle of the pore feels no net force
+        let forces = Forces.compute(&system);
+        assert_ulps_eq!(forces[0], Vector3D::zero());
+
+        // The particle close to the lower wall is pushed away from it
+        let expected = potential.force(0.6) - potential.force(9.4);
+        assert!(expected > 0.0);
+        assert_ulps_eq!(forces[1], Vector3D::new(0.0, 0.0, expected));
+
+        let energy = 2.0 * potential.energy(5.0) + potential.energy(0.6) + potential.energy(9.4);
+        assert_ulps_eq!(PotentialEnergy.compute(&system), energy);
+    }
+
+    #[test]
+    fn impropers() {
+        let mut system = system_from_xyz(
+            "4
+            cell: 10.0
+            C 0.1 0.0 0.3
+            O 1.2 0.1 0.0
+            O -0.6 1.0 -0.1
+            N -0.5 -1.0 0.1
+            ",
+        );
+        assert!(system.add_bond(0, 1).is_empty());
+        assert!(system.add_bond(0, 2).is_empty());
+        assert!(system.add_bond(0, 3).is_empty());
+        system.add_improper_interaction(("C", "O", "O", "N"), Box::new(Harmonic { k: 100.0, x0: 0.0 }));
+
+        let energy = PotentialEnergy.compute(&system);
+        assert!(energy > 0.0);
+
+        let forces = Forces.compute(&system);
+        let eps = 1e-6;
+        for i in 0..4 {
+            for alpha in 0..3 {
+                let mut moved = system.clone();
+                moved.particles_mut().position[i][alpha] += eps;
+                let finite_difference = -(PotentialEnergy.compute(&moved) - energy) / eps;
+                assert_relative_eq!(forces[i][alpha], finite_difference, epsilon = 1e-4);
+            }
+        }
+
+        let sum = forces.iter().fold(Vector3D::zero(), |sum, &force| sum + force);
+        assert_relative_eq!(sum, Vector3D::zero(), epsilon = 1e-12);
+
+        let virial = AtomicVirial.compute(&system);
+        assert_relative_eq!(virial, virial.transposed(), epsilon = 1e-12);
+    }
+
+    #[test]
+    fn cross_terms() {
+        let mut system = system_from_xyz(
+            "4
+            cell: 10.0
+            C 0.1 0.0 0.3
+            O 1.2 0.1 0.0
+            H -0.6 1.0 -0.1
+            H -0.5 -1.0 0.1
+            ",
+        );
+        assert!(system.add_bond(0, 1).is_empty());
+        assert!(system.add_bond(0, 2).is_empty());
+        assert!(system.add_bond(0, 3).is_empty());
+        let potential = BondAngle { k1: 100.0, k2: 50.0, r1: 1.0, r2: 1.2, theta0: 2.0 };
+        system.add_cross_term_interaction(("O", "C", "H"), Box::new(potential));
+        system.add_cross_term_interaction(("H", "C", "H"), Box::new(potential));
+
+        let energy = PotentialEnergy.compute(&system);
+        assert!(energy != 0.0);
+
+        let forces = Forces.compute(&system);
+        let eps = 1e-6;
+        for i in 0..4 {
+            for alpha in 0..3 {
+                let mut moved = system.clone();
+                moved.particles_mut().position[i][alpha] += eps;
+                let finite_difference = -(PotentialEnergy.compute(&moved) - energy) / eps;
+                assert_relative_eq!(forces[i][alpha], finite_difference, epsilon = 1e-4, max_relative = 1e-4);
+            }
+        }
+
+        let sum = forces.iter().fold(Vector3D::zero(), |sum, &force| sum + force);
+        assert_relative_eq!(sum, Vector3D::zero(), epsilon = 1e-12);
+
+        // All the particles are in the same image, so the virial is the
+        // direct sum of r_i ⊗ f_i
+        let mut expected = Matrix3::zero();
+        for (position, force) in system.particles().position.iter().zip(&forces) {
+            expected += force.tensorial(position);
+        }
+        assert_relative_eq!(AtomicVirial.compute(&system), expected, epsilon = 1e-10);
+    }
 }
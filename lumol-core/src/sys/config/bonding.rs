@@ -1,16 +1,17 @@
 // Lumol, an extensible molecular simulation engine
 // Copyright (C) Lumol's contributors — BSD license
 
+use std::cmp::max;
 use std::collections::HashSet;
 use std::hash::{Hash, Hasher};
 use std::ops::Range;
 
-use crate::{Angle, Bond, BondDistances, Dihedral};
+use crate::{Angle, Bond, BondDistances, Dihedral, Improper};
 use crate::Array2;
 
 
 /// The basic building block for a topology. A `Bonding` contains data about
-/// the connectivity (bonds, angles, dihedrals) between particles in a single
+/// the connectivity (bonds, angles, dihedrals, impropers) between particles in a single
 /// molecule.
 #[derive(Debug, Clone)]
 pub struct Bonding {
@@ -21,10 +22,21 @@ pub struct Bonding {
     /// All the dihedral angles in the molecule. Rebuilt as needed from the
     /// bond list.
     dihedrals: HashSet<Dihedral>,
+    /// All the improper dihedral angles in the molecule, centered on the
+    /// particles with exactly three bonds. Rebuilt as needed from the bond
+    /// list.
+    impropers: HashSet<Improper>,
     /// Matrix of bond distances in the molecule. The item at index `i, j`
     /// encode the bond distance between the particles `i + self.first` and
     /// `j + self.first`
     distances: Array2<BondDistances>,
+    /// Maximal number of bonds between the particles of the pairs stored in
+    /// `far_pairs`
+    exclusion_depth: usize,
+    /// Sorted list of `(i, j, bonds)` for all the pairs of particles with
+    /// `i < j` separated by `bonds` bonds, with `3 < bonds <=
+    /// exclusion_depth`. Rebuilt as needed from the bond list.
+    far_pairs: Vec<(usize, usize, usize)>,
     /// Range of atomic indexes in this molecule.
     range: Range<usize>,
 }
@@ -36,7 +48,10 @@ impl Bonding {
             bonds: HashSet::new(),
             angles: HashSet::new(),
             dihedrals: HashSet::new(),
+            impropers: HashSet::new(),
             distances: Array2::default((1, 1)),
+            exclusion_depth: 3,
+            far_pairs: Vec::new(),
             range: i..i + 1,
         }
     }
@@ -74,10 +89,12 @@ impl Bonding {
         }
     }
 
-    /// Rebuild the full list of angles and dihedral angles from the list of bonds
+    /// Rebuild the full list of angles, dihedral angles and improper dihedral
+    /// angles from the list of bonds
     fn rebuild(&mut self) {
         self.angles.clear();
         self.dihedrals.clear();
+        self.impropers.clear();
         for bond1 in &self.bonds {
             // Find angles
             for bond2 in &self.bonds {
@@ -118,13 +135,37 @@ impl Bonding {
                         // improper dihedral.
                         continue;
                     };
+
+                    if dihedral.i() == dihedral.m() {
+                        // Three-membered rings do not define dihedral angles
+                        continue;
+                    }
                     let _ = self.dihedrals.insert(dihedral);
                 }
             }
         }
+        self.rebuild_impropers();
         self.rebuild_connections();
     }
 
+    /// Rebuild the list of improper dihedral angles, using all the particles
+    /// bonded to exactly three other particles as central atoms.
+    fn rebuild_impropers(&mut self) {
+        let first = self.start();
+        let mut neighbors = vec![Vec::new(); self.size()];
+        for bond in &self.bonds {
+            neighbors[bond.i() - first].push(bond.j());
+            neighbors[bond.j() - first].push(bond.i());
+        }
+
+        for (center, neighbors) in neighbors.iter().enumerate() {
+            if neighbors.len() == 3 {
+                let improper = Improper::new(center + first, neighbors[0], neighbors[1], neighbors[2]);
+                let _ = self.impropers.insert(improper);
+            }
+        }
+    }
+
     /// Recompute the connectivity matrix from the bonds, angles and dihedrals
     /// in the system.
     fn rebuild_connections(&mut self) {
@@ -152,6 +193,65 @@ impl Bonding {
             add_distance_term(dihedral.i(), dihedral.m(), BondDistances::THREE);
             add_distance_term(dihedral.m(), dihedral.i(), BondDistances::THREE);
         }
+
+        self.rebuild_far_pairs();
+    }
+
+    /// Recompute the list of pairs separated by more than three bonds and at
+    /// most `self.exclusion_depth` bonds, using a breadth-first search
+    /// starting at each particle in the molecule.
+    fn rebuild_far_pairs(&mut self) {
+        self.far_pairs.clear();
+        if self.exclusion_depth <= 3 {
+            return;
+        }
+
+        let first = self.start();
+        let mut neighbors = vec![Vec::new(); self.size()];
+        for bond in &self.bonds {
+            neighbors[bond.i() - first].push(bond.j() - first);
+            neighbors[bond.j() - first].push(bond.i() - first);
+        }
+
+        let mut visited = vec![false; self.size()];
+        for i in 0..self.size() {
+            visited[i] = true;
+            let mut all_visited = vec![i];
+            let mut current = vec![i];
+            for length in 1..=self.exclusion_depth {
+                let mut next = Vec::new();
+                for &particle in &current {
+                    for &neighbor in &neighbors[particle] {
+                        if !visited[neighbor] {
+                            visited[neighbor] = true;
+                            next.push(neighbor);
+                            if length > 3 && neighbor > i {
+                                self.far_pairs.push((i + first, neighbor + first, length));
+                            }
+                        }
+                    }
+                }
+                if next.is_empty() {
+                    break;
+                }
+                all_visited.extend_from_slice(&next);
+                current = next;
+            }
+
+            for particle in all_visited {
+                visited[particle] = false;
+            }
+        }
+        self.far_pairs.sort_unstable();
+    }
+
+    /// Set the maximal number of bonds separating the particles in pairs
+    /// excluded by a bonded restriction, and update the list of such pairs.
+    pub(crate) fn set_exclusion_depth(&mut self, depth: usize) {
+        if depth != self.exclusion_depth {
+            self.exclusion_depth = depth;
+            self.rebuild_far_pairs();
+        }
     }
 
     /// Merge this molecule with `other`. The first particle in `other` should
@@ -159,6 +259,7 @@ impl Bonding {
     pub fn merge_with(&mut self, other: Bonding) {
         assert_eq!(self.range.end, other.range.start);
         self.range.end = other.range.end;
+        self.exclusion_depth = max(self.exclusion_depth, other.exclusion_depth);
         for bond in other.bonds {
             let _ = self.bonds.insert(bond);
         }
@@ -173,6 +274,10 @@ impl Bonding {
             let _ = self.dihedrals.insert(dihedral);
         }
 
+        for improper in other.impropers {
+            let _ = self.impropers.insert(improper);
+        }
+
         self.rebuild_connections();
     }
 
@@ -180,7 +285,7 @@ impl Bonding {
     pub fn translate_by(&mut self, delta: isize) {
         if delta < 0 {
             // We should not create negative indexes
-            assert!((delta.abs() as usize) < self.start());
+            assert!((delta.abs() as usize) <= self.start());
         }
 
         // The wrapping_add are necessary here, and produce the right result,
@@ -217,6 +322,22 @@ impl Bonding {
             ));
         }
         self.dihedrals = new_dihedrals;
+
+        let mut new_impropers = HashSet::new();
+        for improper in &self.impropers {
+            let _ = new_impropers.insert(Improper::new(
+                improper.i().wrapping_add(delta),
+                improper.j().wrapping_add(delta),
+                improper.k().wrapping_add(delta),
+                improper.m().wrapping_add(delta),
+            ));
+        }
+        self.impropers = new_impropers;
+
+        for pair in &mut self.far_pairs {
+            pair.0 = pair.0.wrapping_add(delta);
+            pair.1 = pair.1.wrapping_add(delta);
+        }
     }
 
     /// Add a bond between the particles at indexes `i` and `j`. These particles
@@ -272,12 +393,71 @@ impl Bonding {
         &self.dihedrals
     }
 
+    /// Get the internal list of improper dihedral angles
+    pub fn impropers(&self) -> &HashSet<Improper> {
+        &self.impropers
+    }
+
     /// Get the all the possible bond paths the particles `i` and `j` in this molecule
     pub fn bond_distances(&self, i: usize, j: usize) -> BondDistances {
         assert!(self.contains(i) && self.contains(j));
         return self.distances[(i - self.start(), j - self.start())];
     }
 
+    /// Check if the particles `i` and `j` in this molecule are separated by
+    /// at most `depth` bonds.
+    ///
+    /// For more than three bonds, this uses the list of pairs computed when
+    /// the bonds change, up to the exclusion depth of the molecule. The
+    /// [`System`](struct.System.html) sets this depth from the restrictions
+    /// of its interactions. If `depth` is larger than the exclusion depth,
+    /// the bonds are searched instead, which is much slower.
+    pub fn are_within_bonds(&self, i: usize, j: usize, depth: usize) -> bool {
+        assert!(self.contains(i) && self.contains(j));
+        if i == j {
+            return true;
+        }
+
+        let distances = self.bond_distances(i, j);
+        if (depth >= 1 && distances.contains(BondDistances::ONE)) ||
+           (depth >= 2 && distances.contains(BondDistances::TWO)) ||
+           (depth >= 3 && distances.contains(BondDistances::THREE)) {
+            return true;
+        } else if depth <= 3 {
+            return false;
+        }
+
+        if depth <= self.exclusion_depth {
+            let pair = (usize::min(i, j), usize::max(i, j));
+            return match self.far_pairs.binary_search_by_key(&pair, |&(i, j, _)| (i, j)) {
+                Ok(index) => self.far_pairs[index].2 <= depth,
+                Err(_) => false,
+            };
+        }
+
+        let mut visited = HashSet::new();
+        let _ = visited.insert(i);
+        let mut current = vec![i];
+        for _ in 0..depth {
+            let mut next = Vec::new();
+            for bond in &self.bonds {
+                for &(from, to) in &[(bond.i(), bond.j()), (bond.j(), bond.i())] {
+                    if current.contains(&from) && visited.insert(to) {
+                        if to == j {
+                            return true;
+                        }
+                        next.push(to);
+                    }
+                }
+            }
+            if next.is_empty() {
+                return false;
+            }
+            current = next;
+        }
+        return false;
+    }
+
     /// Get the indexes of the particles in this molecule. All atoms in the
     /// returned range are inside this molecule.
     pub fn indexes(&self) -> Range<usize> {
@@ -288,7 +468,7 @@ impl Bonding {
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::{Angle, Bond, BondDistances, Dihedral};
+    use crate::{Angle, Bond, BondDistances, Dihedral, Improper};
 
     #[test]
     fn translate_by() {
@@ -409,10 +589,15 @@ mod test {
         assert!(bonding.bond_distances(3, 5).contains(BondDistances::THREE));
         assert!(bonding.bond_distances(5, 3).contains(BondDistances::THREE));
 
+        // Carbon atoms have four bonds
+        assert!(bonding.impropers().is_empty());
+
         bonding.remove_particle(6);
         assert_eq!(bonding.bonds().len(), 6);
         assert_eq!(bonding.angles().len(), 9);
         assert_eq!(bonding.dihedrals().len(), 6);
+        assert_eq!(bonding.impropers().len(), 1);
+        assert!(bonding.impropers().contains(&Improper::new(1, 6, 0, 5)));
     }
 
     #[test]
@@ -432,11 +617,104 @@ mod test {
 
         assert!(bonding.bond_distances(0, 3).contains(BondDistances::ONE));
         assert!(bonding.bond_distances(0, 3).contains(BondDistances::THREE));
+        assert!(bonding.are_within_bonds(0, 3, 1));
+        assert!(!bonding.are_within_bonds(0, 2, 1));
+        assert!(bonding.are_within_bonds(0, 2, 2));
+        assert!(bonding.are_within_bonds(1, 1, 0));
 
         assert!(bonding.angles.contains(&Angle::new(0, 3, 2)));
         assert!(bonding.angles.contains(&Angle::new(0, 1, 2)));
     }
 
+    #[test]
+    fn far_pairs() {
+        // 0 - 1 - 2 - 3 - 4 - 5 - 6
+        let mut bonding = Bonding::new(0);
+        for i in 1..7 {
+            bonding.merge_with(Bonding::new(i));
+        }
+        bonding.set_exclusion_depth(5);
+        for i in 0..6 {
+            bonding.add_bond(i, i + 1);
+        }
+
+        assert_eq!(bonding.far_pairs, vec![
+            (0, 4, 4), (0, 5, 5), (1, 5, 4), (1, 6, 5), (2, 6, 4)
+        ]);
+        assert!(bonding.are_within_bonds(0, 3, 3));
+        assert!(!bonding.are_within_bonds(0, 4, 3));
+        assert!(bonding.are_within_bonds(4, 0, 4));
+        assert!(!bonding.are_within_bonds(0, 5, 4));
+        assert!(bonding.are_within_bonds(0, 5, 5));
+        assert!(!bonding.are_within_bonds(0, 6, 5));
+        // Larger than the exclusion depth
+        assert!(bonding.are_within_bonds(0, 6, 6));
+
+        bonding.translate_by(3);
+        assert!(bonding.are_within_bonds(3, 7, 4));
+        assert!(!bonding.are_within_bonds(3, 8, 4));
+
+        bonding.set_exclusion_depth(3);
+        assert!(bonding.far_pairs.is_empty());
+        assert!(bonding.are_within_bonds(3, 7, 4));
+    }
+
+    #[test]
+    fn branched() {
+        //        2
+        //        |
+        //   1 -- 0 -- 3 -- 4
+        let mut bonding = Bonding::new(0);
+        for i in 1..5 {
+            bonding.merge_with(Bonding::new(i));
+        }
+        bonding.add_bond(0, 1);
+        bonding.add_bond(0, 2);
+        bonding.add_bond(0, 3);
+        bonding.add_bond(3, 4);
+
+        assert_eq!(bonding.bonds().len(), 4);
+        assert_eq!(bonding.angles().len(), 4);
+        assert!(bonding.angles.contains(&Angle::new(1, 0, 2)));
+        assert!(bonding.angles.contains(&Angle::new(1, 0, 3)));
+        assert!(bonding.angles.contains(&Angle::new(2, 0, 3)));
+        assert!(bonding.angles.contains(&Angle::new(0, 3, 4)));
+
+        assert_eq!(bonding.dihedrals().len(), 2);
+        assert!(bonding.dihedrals.contains(&Dihedral::new(1, 0, 3, 4)));
+        assert!(bonding.dihedrals.contains(&Dihedral::new(2, 0, 3, 4)));
+
+        assert_eq!(bonding.impropers().len(), 1);
+        assert!(bonding.impropers.contains(&Improper::new(0, 3, 1, 2)));
+
+        // Adding the same bond again does not create duplicated entries
+        bonding.add_bond(4, 3);
+        assert_eq!(bonding.bonds().len(), 4);
+        assert_eq!(bonding.angles().len(), 4);
+        assert_eq!(bonding.dihedrals().len(), 2);
+        assert_eq!(bonding.impropers().len(), 1);
+
+        bonding.translate_by(2);
+        assert!(bonding.impropers.contains(&Improper::new(2, 3, 4, 5)));
+    }
+
+    #[test]
+    fn three_membered_ring() {
+        //      0
+        //     / \
+        //    1 - 2
+        let mut bonding = Bonding::new(0);
+        for i in 1..3 {
+            bonding.merge_with(Bonding::new(i));
+        }
+        bonding.add_bond(0, 1);
+        bonding.add_bond(1, 2);
+        bonding.add_bond(2, 0);
+
+        assert_eq!(bonding.angles().len(), 3);
+        assert!(bonding.dihedrals().is_empty());
+    }
+
     #[test]
     fn remove_particle() {
         let mut bonding = Bonding::new(0);
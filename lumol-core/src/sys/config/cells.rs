@@ -92,6 +92,19 @@ impl UnitCell {
         }
     }
 
+    /// Create an unit cell with the given `shape` from the cell `matrix`, as
+    /// returned by `UnitCell::matrix`.
+    pub(crate) fn from_matrix(matrix: Matrix3, shape: CellShape) -> UnitCell {
+        if shape == CellShape::Infinite {
+            return UnitCell::infinite();
+        }
+        UnitCell {
+            cell: matrix,
+            inv: matrix.inverse(),
+            shape: shape,
+        }
+    }
+
     /// Get the cell shape
     #[inline]
     pub fn shape(&self) -> CellShape {
@@ -146,6 +159,14 @@ impl UnitCell {
         Vector3D::new(abs(na * a), abs(nb * b), abs(nc * c))
     }
 
+    /// Get the largest cutoff compatible with the minimum image convention
+    /// in this cell, *i.e.* half of the smallest distance between faces of
+    /// the cell. This is infinite for infinite cells.
+    pub fn max_cutoff(&self) -> f64 {
+        let lengths = self.lengths();
+        return 0.5 * f64::min(lengths[0], f64::min(lengths[1], lengths[2]));
+    }
+
     /// Get the first angle of the cell
     pub fn alpha(&self) -> f64 {
         match self.shape {
@@ -362,6 +383,13 @@ impl UnitCell {
 
     /// Get the dihedral angle formed by the points at `r1`, `r2`, `r3`, and `r4` using
     /// periodic boundary conditions.
+    ///
+    /// The angle follows the IUPAC convention, and is in the `[-π, π]` range:
+    /// it is 0 when `r1` and `r4` are on the same side of the `r2-r3` bond
+    /// (*cis* conformation), π when they are on opposite sides (*trans*
+    /// conformation), and positive when looking along the `r2 → r3` bond,
+    /// `r4` is rotated clockwise with respect to `r1`. This convention is used
+    /// for all the dihedral and improper dihedral potentials.
     pub fn dihedral(&self, r1: &Vector3D, r2: &Vector3D, r3: &Vector3D, r4: &Vector3D) -> f64 {
         let mut r12 = r2 - r1;
         self.vector_image(&mut r12);
@@ -541,6 +569,16 @@ mod tests {
         assert_eq!(triclinic.lengths(), Vector3D::new(2.908132319388713, 3.9373265973230853, 4.921658246653857));
     }
 
+    #[test]
+    fn max_cutoff() {
+        assert_eq!(UnitCell::infinite().max_cutoff(), f64::INFINITY);
+        assert_eq!(UnitCell::cubic(12.0).max_cutoff(), 6.0);
+        assert_eq!(UnitCell::ortho(12.0, 10.0, 12.0).max_cutoff(), 5.0);
+
+        let triclinic = UnitCell::triclinic(3.0, 4.0, 5.0, 90.0, 80.0, 100.0);
+        assert_ulps_eq!(triclinic.max_cutoff(), 0.5 * 2.908132319388713);
+    }
+
     #[test]
     fn scale() {
         let cell = UnitCell::ortho(3.0, 4.0, 5.0);
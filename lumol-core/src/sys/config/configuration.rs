@@ -12,6 +12,7 @@ use crate::{BondDistances, Bonding, ParticleKind, UnitCell};
 use crate::{ParticleSlice, ParticleSliceMut, ParticleVec, ParticlePtr, ParticlePtrMut};
 use crate::{Molecule, MoleculeRef, MoleculeRefMut};
 use crate::BondPath;
+use crate::sys::periodic_table;
 
 /// The `Permutation` struct contains the old and new particle index in a
 /// `Configuration` after the particles where moved due to a new bond being
@@ -48,6 +49,9 @@ pub struct Configuration {
     bondings: Vec<Bonding>,
     /// Molecules indexes for all the particles
     molecule_ids: Vec<usize>,
+    /// Maximal number of bonds separating the particles in pairs excluded by
+    /// a bonded restriction
+    exclusion_depth: usize,
 }
 
 impl Configuration {
@@ -57,6 +61,7 @@ impl Configuration {
             particles: ParticleVec::new(),
             bondings: Vec::new(),
             molecule_ids: Vec::new(),
+            exclusion_depth: 3,
             cell: UnitCell::infinite(),
         }
     }
@@ -143,6 +148,39 @@ impl Configuration {
         }
     }
 
+    /// Check if the particles `i` and `j` are in the same molecule and
+    /// separated by at most `depth` bonds. This is fast as long as `depth` is
+    /// not larger than the [exclusion depth](#method.set_exclusion_depth).
+    pub fn are_within_bonds(&self, i: usize, j: usize, depth: usize) -> bool {
+        assert!(i < self.size() && j < self.size());
+        if !self.are_in_same_molecule(i, j) {
+            return false;
+        }
+        return self.molecule(self.molecule_id(i)).are_within_bonds(i, j, depth);
+    }
+
+    /// Set the maximal number of bonds separating two particles in a pair
+    /// excluded by a bonded restriction such as
+    /// [`PairRestriction::ExcludeBonded`](../energy/enum.PairRestriction.html).
+    /// All the pairs of particles in the same molecule separated by up to
+    /// `depth` bonds are stored and updated when the bonds change, making
+    /// [`are_within_bonds`](#method.are_within_bonds) fast.
+    ///
+    /// The [`System`](struct.System.html) sets this depth automatically from
+    /// the restrictions of its interactions.
+    pub fn set_exclusion_depth(&mut self, depth: usize) {
+        self.exclusion_depth = depth;
+        for bonding in &mut self.bondings {
+            bonding.set_exclusion_depth(depth);
+        }
+    }
+
+    /// Get the maximal number of bonds separating two particles in a pair
+    /// excluded by a bonded restriction.
+    pub fn exclusion_depth(&self) -> usize {
+        self.exclusion_depth
+    }
+
     /// Remove the molecule at index `i`
     pub fn remove_molecule(&mut self, molid: usize) {
         let molecule = self.bondings.remove(molid);
@@ -233,6 +271,69 @@ impl Configuration {
         return permutations;
     }
 
+    /// Guess the bonds in this configuration from the distances between the
+    /// particles. A bond is added between two particles when their distance
+    /// is smaller than the sum of their [covalent radii][radius] times `(1 +
+    /// tolerance)`. Particles for which no covalent radius is known are
+    /// ignored. Distances are computed using the nearest periodic image.
+    ///
+    /// [radius]: periodic_table/fn.covalent_radius.html
+    ///
+    /// # Warning
+    ///
+    /// Adding bonds can move particles in the particles list, see
+    /// [`add_bond`](#method.add_bond). Any previously stored index is
+    /// invalidated by this function.
+    pub fn guess_bonds(&mut self, tolerance: f64) {
+        let _ = self.guess_bonds_with_indexes(tolerance);
+    }
+
+    /// Guess the bonds in this configuration as in
+    /// [`guess_bonds`](#method.guess_bonds), and return the new index of all
+    /// the particles, indexed by their index before the call.
+    pub(crate) fn guess_bonds_with_indexes(&mut self, tolerance: f64) -> Vec<usize> {
+        let radii = self.particles().name.iter().map(|name| {
+            let radius = periodic_table::covalent_radius(name);
+            if radius.is_none() {
+                warn_once!("no covalent radius for '{}', it will not be bonded", name);
+            }
+            radius
+        }).collect::<Vec<_>>();
+
+        let mut bonds = Vec::new();
+        for i in 0..self.size() {
+            let ri = match radii[i] {
+                Some(radius) => radius,
+                None => continue,
+            };
+            for j in (i + 1)..self.size() {
+                let rj = match radii[j] {
+                    Some(radius) => radius,
+                    None => continue,
+                };
+                if self.distance(i, j) < (ri + rj) * (1.0 + tolerance) {
+                    bonds.push((i, j));
+                }
+            }
+        }
+
+        // `current[i]` is the current index of the particle initially at
+        // index `i`, and `initial` is the inverse mapping
+        let mut current = (0..self.size()).collect::<Vec<_>>();
+        let mut initial = current.clone();
+        for (i, j) in bonds {
+            let permutations = self.add_bond(current[i], current[j]);
+            let moved = permutations.iter()
+                                    .map(|permutation| (initial[permutation.old], permutation.new))
+                                    .collect::<Vec<_>>();
+            for (particle, new) in moved {
+                current[particle] = new;
+                initial[new] = particle;
+            }
+        }
+        return current;
+    }
+
     /// Add a molecule to the configuration, putting the new particles at the
     /// end of the particles list
     pub fn add_molecule(&mut self, mut molecule: Molecule) {
@@ -248,6 +349,7 @@ impl Configuration {
 
         let mut bonding = molecule.bonding;
         bonding.translate_by(self.particles.len() as isize);
+        bonding.set_exclusion_depth(self.exclusion_depth);
 
         self.molecule_ids.append(&mut vec![self.bondings.len(); bonding.size()]);
         self.bondings.push(bonding);
@@ -426,7 +528,9 @@ impl Configuration {
       )
     }
 
-    /// Get the dihedral angle between the particles `i`, `j`, `k` and `m`
+    /// Get the dihedral angle between the particles `i`, `j`, `k` and `m`.
+    /// See [`UnitCell::dihedral`](struct.UnitCell.html#method.dihedral) for
+    /// the sign convention.
     pub fn dihedral(&self, i: usize, j: usize, k: usize, m: usize) -> f64 {
         self.cell.dihedral(
             &self.particles.position[i],
@@ -629,6 +733,116 @@ mod tests {
         assert_eq!(configuration.size(), 0);
     }
 
+    #[test]
+    fn molecules_from_bonds() {
+        let mut configuration = Configuration::new();
+        configuration.add_molecule(Molecule::new(particle("H")));
+        configuration.add_molecule(Molecule::new(particle("H")));
+        configuration.add_molecule(Molecule::new(particle("Cl")));
+        configuration.add_molecule(Molecule::new(particle("Cl")));
+        assert_eq!(configuration.molecules().count(), 4);
+
+        // Bonding 0-2 and 1-3 creates two diatomic molecules, and reorder
+        // the particles to keep molecules contiguous. `indexes` tracks the
+        // current index of the initial particles.
+        let mut indexes = vec![0, 1, 2, 3];
+        let mut add_bond = |configuration: &mut Configuration, i: usize, j: usize| {
+            let permutations = configuration.add_bond(indexes[i], indexes[j]);
+            for index in &mut indexes {
+                if let Some(permutation) = permutations.iter().find(|p| p.old == *index) {
+                    *index = permutation.new;
+                }
+            }
+        };
+        add_bond(&mut configuration, 0, 2);
+        add_bond(&mut configuration, 1, 3);
+
+        let molecules = configuration.molecules().map(|m| m.indexes()).collect::<Vec<_>>();
+        assert_eq!(molecules, vec![0..2, 2..4]);
+        for molecule in configuration.molecules() {
+            let names = molecule.particles().name.iter().cloned().collect::<Vec<_>>();
+            assert_eq!(names.len(), 2);
+            assert!(names.contains(&String::from("H")));
+            assert!(names.contains(&String::from("Cl")));
+        }
+
+        for i in 0..4 {
+            assert_eq!(configuration.molecule_id(i), i / 2);
+        }
+        assert!(configuration.are_in_same_molecule(0, 1));
+        assert!(!configuration.are_in_same_molecule(1, 2));
+    }
+
+    #[test]
+    fn guess_bonds() {
+        let mut configuration = Configuration::new();
+        configuration.cell = UnitCell::cubic(10.0);
+        configuration.add_molecule(Molecule::new(particle("H")));
+        configuration.add_molecule(Molecule::new(particle("O")));
+        configuration.add_molecule(Molecule::new(particle("H")));
+        configuration.particles_mut().position[0] = Vector3D::new(0.957, 0.0, 0.0);
+        configuration.particles_mut().position[1] = Vector3D::new(0.0, 0.0, 0.0);
+        configuration.particles_mut().position[2] = Vector3D::new(-0.24, 0.927, 0.0);
+
+        configuration.guess_bonds(0.1);
+        assert_eq!(configuration.molecules().count(), 1);
+
+        let molecule = configuration.molecule(0);
+        assert_eq!(molecule.bonds().len(), 2);
+        let names = configuration.particles().name;
+        for bond in molecule.bonds() {
+            let mut bonded = [&*names[bond.i()], &*names[bond.j()]];
+            bonded.sort_unstable();
+            assert_eq!(bonded, ["H", "O"]);
+        }
+
+        // Particles are moved to keep molecules contiguous
+        let mut configuration = Configuration::new();
+        configuration.cell = UnitCell::cubic(10.0);
+        let positions = [
+            ("O", Vector3D::new(0.0, 0.0, 0.0)),
+            ("O", Vector3D::new(5.0, 0.0, 0.0)),
+            ("H", Vector3D::new(0.957, 0.0, 0.0)),
+            ("H", Vector3D::new(5.957, 0.0, 0.0)),
+            ("H", Vector3D::new(-0.24, 0.927, 0.0)),
+            ("H", Vector3D::new(4.76, 0.927, 0.0)),
+        ];
+        for &(name, position) in &positions {
+            let mut particle = particle(name);
+            particle.position = position;
+            configuration.add_molecule(Molecule::new(particle));
+        }
+
+        configuration.guess_bonds(0.1);
+        assert_eq!(configuration.molecules().count(), 2);
+        for molecule in configuration.molecules() {
+            assert_eq!(molecule.size(), 3);
+            assert_eq!(molecule.bonds().len(), 2);
+            for bond in molecule.bonds() {
+                assert!(configuration.distance(bond.i(), bond.j()) < 1.0);
+            }
+        }
+
+        // Bonds are guessed across periodic boundaries
+        let mut configuration = Configuration::new();
+        configuration.cell = UnitCell::cubic(10.0);
+        configuration.add_molecule(Molecule::new(particle("H")));
+        configuration.add_molecule(Molecule::new(particle("H")));
+        configuration.particles_mut().position[0] = Vector3D::new(0.2, 0.0, 0.0);
+        configuration.particles_mut().position[1] = Vector3D::new(9.7, 0.0, 0.0);
+
+        configuration.guess_bonds(0.0);
+        assert_eq!(configuration.molecules().count(), 1);
+        assert!(configuration.molecule(0).bonds().contains(&Bond::new(0, 1)));
+
+        // Particles without covalent radius are not bonded
+        let mut configuration = Configuration::new();
+        configuration.add_molecule(Molecule::new(particle("H")));
+        configuration.add_molecule(Molecule::new(particle("HW")));
+        configuration.guess_bonds(0.1);
+        assert_eq!(configuration.molecules().count(), 2);
+    }
+
     mod iterators {
         use super::super::*;
         use super::particle;
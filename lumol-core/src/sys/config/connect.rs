@@ -138,6 +138,65 @@ impl Dihedral {
     }
 }
 
+/// An `Improper` dihedral angle formed by the particle at index `i` and three
+/// particles `j`, `k` and `m` bonded to it.
+///
+/// This structure ensure uniqueness of the `Improper` representation by
+/// enforcing `j < k < m`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Improper {
+    i: usize,
+    j: usize,
+    k: usize,
+    m: usize,
+}
+
+impl Improper {
+    /// Create a new Improper dihedral angle centered on the particle at
+    /// index `center`, with the three other particles at indexes `first`,
+    /// `second` and `third`.
+    pub fn new(center: usize, first: usize, second: usize, third: usize) -> Improper {
+        assert_ne!(center, first);
+        assert_ne!(center, second);
+        assert_ne!(center, third);
+        assert_ne!(first, second);
+        assert_ne!(first, third);
+        assert_ne!(second, third);
+        let mut others = [first, second, third];
+        others.sort_unstable();
+        Improper {
+            i: center,
+            j: others[0],
+            k: others[1],
+            m: others[2],
+        }
+    }
+
+    /// Get the central particle in the improper dihedral angle
+    #[inline]
+    pub fn i(&self) -> usize {
+        self.i
+    }
+
+    /// Get the second particle in the improper dihedral angle
+    #[inline]
+    pub fn j(&self) -> usize {
+        self.j
+    }
+
+    /// Get the third particle in the improper dihedral angle
+    #[inline]
+    pub fn k(&self) -> usize {
+        self.k
+    }
+
+    /// Get the fourth particle in the improper dihedral angle
+    #[inline]
+    pub fn m(&self) -> usize {
+        self.m
+    }
+}
+
 
 bitflags! {
     /// The `BondDistances` bitflag encode the topological distance between
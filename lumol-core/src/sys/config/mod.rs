@@ -3,8 +3,8 @@
 
 //! Configuration and related types
 
-mod mass;
-pub use self::mass::get_atomic_mass;
+pub mod periodic_table;
+pub use self::periodic_table::get_atomic_mass;
 
 mod particles;
 pub use self::particles::{Particle, ParticleKind};
@@ -19,7 +19,7 @@ mod cells;
 pub use self::cells::{CellShape, UnitCell};
 
 mod connect;
-pub use self::connect::{Angle, Bond, Dihedral};
+pub use self::connect::{Angle, Bond, Dihedral, Improper};
 pub use self::connect::BondDistances;
 
 mod bonding;
@@ -32,3 +32,7 @@ mod configuration;
 pub use self::configuration::Configuration;
 pub use self::configuration::Permutation;
 pub use self::configuration::{MoleculeIter, MoleculeIterMut};
+
+mod neighbors;
+pub use self::neighbors::{CellList, NeighborList};
+pub(crate) use self::neighbors::{PairNeighbors, SharedNeighborList};
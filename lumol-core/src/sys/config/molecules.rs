@@ -118,6 +118,14 @@ impl Molecule {
         self.bonding.add_bond(i, other);
     }
 
+    /// Add a new `particle` in this molecule, without any bond. The particle
+    /// must be bonded to the rest of the molecule before using the molecule.
+    pub(crate) fn add_particle(&mut self, particle: Particle) {
+        self.particles.push(particle);
+        let i = self.particles.len() - 1;
+        self.bonding.merge_with(Bonding::new(i));
+    }
+
     /// Add bond between particles at indexes `i` and `j` in this molecule.
     ///
     /// # Panics
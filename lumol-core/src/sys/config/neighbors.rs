@@ -0,0 +1,604 @@
+// Lumol, an extensible molecular simulation engine
+// Copyright (C) Lumol's contributors — BSD license
+
+//! Cell lists and neighbor lists, used to find the pairs of particles close
+//! to one another.
+use std::cmp::min;
+use std::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+use crate::sys::Configuration;
+use crate::{UnitCell, Vector3D};
+
+/// A `CellList` divides the unit cell in sub-cells larger than a cutoff
+/// distance, and sorts the particles in these sub-cells. All the particles
+/// closer than the cutoff to a given particle are then in the same sub-cell
+/// or in one of the 26 neighboring sub-cells, which allow to find all the
+/// pairs at a distance smaller than the cutoff in `O(N)` instead of `O(N^2)`.
+///
+/// When the unit cell is infinite, or too small to contain three sub-cells in
+/// each direction, the cell list falls back to considering all the pairs of
+/// particles.
+///
+/// The cell list is built for a given configuration, and must be built again
+/// when the particles move.
+///
+/// # Examples
+///
+/// ```
+/// # use lumol_core::{Molecule, Particle, System, UnitCell, Vector3D, CellList};
+/// let mut system = System::with_cell(UnitCell::cubic(30.0));
+/// system.add_molecule(Molecule::new(Particle::with_position("Ar", Vector3D::new(0.0, 0.0, 0.0))));
+/// system.add_molecule(Molecule::new(Particle::with_position("Ar", Vector3D::new(2.0, 0.0, 0.0))));
+/// system.add_molecule(Molecule::new(Particle::with_position("Ar", Vector3D::new(15.0, 0.0, 0.0))));
+///
+/// let cells = CellList::new(&system, 5.0);
+/// let mut neighbors = Vec::new();
+/// cells.for_each_neighbor(0, |j| neighbors.push(j));
+/// assert_eq!(neighbors, vec![1]);
+/// ```
+#[derive(Clone, Debug)]
+pub struct CellList {
+    /// Number of sub-cells in each direction, or `None` if all the pairs of
+    /// particles should be considered
+    cells: Option<[usize; 3]>,
+    /// Indexes of the particles in each sub-cell
+    particles: Vec<Vec<usize>>,
+    /// Sub-cell containing each particle
+    particle_cells: Vec<[usize; 3]>,
+}
+
+impl CellList {
+    /// Create a new cell list for the particles in the `configuration`,
+    /// using sub-cells larger than `cutoff`.
+    pub fn new(configuration: &Configuration, cutoff: f64) -> CellList {
+        let natoms = configuration.size();
+        let cell = &configuration.cell;
+        if cell.is_infinite() {
+            return CellList::all_pairs(natoms);
+        }
+
+        // The distance between opposite faces of the unit cell is the inverse
+        // of the norm of the corresponding reciprocal vector.
+        let inverse = cell.matrix().inverse();
+        let mut cells = [0; 3];
+        for (dimension, count) in cells.iter_mut().enumerate() {
+            let row = inverse[dimension];
+            let width = 1.0 / f64::sqrt(row[0] * row[0] + row[1] * row[1] + row[2] * row[2]);
+            *count = f64::floor(width / cutoff) as usize;
+        }
+
+        if cells.iter().any(|&count| count < 3) {
+            return CellList::all_pairs(natoms);
+        }
+
+        let mut particles = vec![Vec::new(); cells[0] * cells[1] * cells[2]];
+        let mut particle_cells = Vec::with_capacity(natoms);
+        for (i, position) in configuration.particles().position.iter().enumerate() {
+            let fractional = cell.fractional(position);
+            let mut index = [0; 3];
+            for dimension in 0..3 {
+                let wrapped = fractional[dimension] - f64::floor(fractional[dimension]);
+                let count = cells[dimension];
+                index[dimension] = min((wrapped * count as f64) as usize, count - 1);
+            }
+            particles[linear_index(index, cells)].push(i);
+            particle_cells.push(index);
+        }
+
+        return CellList {
+            cells: Some(cells),
+            particles: particles,
+            particle_cells: particle_cells,
+        };
+    }
+
+    /// Create a cell list considering all the pairs of `natoms` particles
+    fn all_pairs(natoms: usize) -> CellList {
+        CellList {
+            cells: None,
+            particles: vec![(0..natoms).collect()],
+            particle_cells: vec![[0, 0, 0]; natoms],
+        }
+    }
+
+    /// Call `callback` with the index `j` of all the particles that could be
+    /// closer than the cutoff to the particle at index `i`, and with `j > i`.
+    /// Calling this function for all the particles visits every pair of
+    /// particles closer than the cutoff exactly once.
+    pub fn for_each_neighbor<F: FnMut(usize)>(&self, i: usize, mut callback: F) {
+        let cells = match self.cells {
+            Some(cells) => cells,
+            None => {
+                for j in (i + 1)..self.particle_cells.len() {
+                    callback(j);
+                }
+                return;
+            }
+        };
+
+        let center = self.particle_cells[i];
+        for dx in 0..3 {
+            for dy in 0..3 {
+                for dz in 0..3 {
+                    // Shift by the number of cells to stay positive
+                    let index = [
+                        (center[0] + cells[0] + dx - 1) % cells[0],
+                        (center[1] + cells[1] + dy - 1) % cells[1],
+                        (center[2] + cells[2] + dz - 1) % cells[2],
+                    ];
+                    for &j in &self.particles[linear_index(index, cells)] {
+                        if j > i {
+                            callback(j);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// A Verlet neighbor list, storing for each particle the list of particles
+/// closer than the cutoff plus a skin distance.
+///
+/// The list is only built again when a particle moved by more than half of
+/// the skin distance since the last build, as no pair of particles can get
+/// closer than the cutoff before that. A larger skin makes the list rebuilt
+/// less often, at the cost of more memory and more pairs to check at each
+/// step. With a skin of zero, the list is built again every time the
+/// particles move.
+///
+/// # Examples
+///
+/// ```
+/// # use lumol_core::{Molecule, Particle, System, UnitCell, Vector3D, NeighborList};
+/// let mut system = System::with_cell(UnitCell::cubic(30.0));
+/// system.add_molecule(Molecule::new(Particle::with_position("Ar", Vector3D::new(0.0, 0.0, 0.0))));
+/// system.add_molecule(Molecule::new(Particle::with_position("Ar", Vector3D::new(5.2, 0.0, 0.0))));
+/// system.add_molecule(Molecule::new(Particle::with_position("Ar", Vector3D::new(15.0, 0.0, 0.0))));
+///
+/// let mut list = NeighborList::with_skin(5.0, 0.5);
+/// assert!(list.update(&system));
+/// assert_eq!(list.neighbors(0), &[1]);
+///
+/// // the list does not need to be built again for small displacements
+/// system.particles_mut().position[1][0] += 0.2;
+/// assert!(!list.update(&system));
+/// assert_eq!(list.builds(), 1);
+/// ```
+#[derive(Clone, Debug)]
+pub struct NeighborList {
+    /// Cutoff distance of the interactions
+    cutoff: f64,
+    /// Additional distance used when building the list
+    skin: f64,
+    /// Sorted neighbors of each particle
+    neighbors: Vec<Vec<usize>>,
+    /// Positions of the particles when the list was last built
+    positions: Vec<Vector3D>,
+    /// Unit cell when the list was last built, or `None` if the list was
+    /// never built
+    cell: Option<UnitCell>,
+    /// Number of times the list was built
+    builds: usize,
+}
+
+impl NeighborList {
+    /// Create a new neighbor list for interactions with the given `cutoff`,
+    /// using a skin distance of 10% of the cutoff.
+    pub fn new(cutoff: f64) -> NeighborList {
+        NeighborList::with_skin(cutoff, 0.1 * cutoff)
+    }
+
+    /// Create a new neighbor list for interactions with the given `cutoff`,
+    /// using the given `skin` distance.
+    ///
+    /// # Panics
+    ///
+    /// If the cutoff is not positive, or if the skin is negative.
+    pub fn with_skin(cutoff: f64, skin: f64) -> NeighborList {
+        assert!(cutoff > 0.0, "the cutoff must be positive in neighbor list, got {}", cutoff);
+        assert!(skin >= 0.0, "the skin can not be negative in neighbor list, got {}", skin);
+        NeighborList {
+            cutoff: cutoff,
+            skin: skin,
+            neighbors: Vec::new(),
+            positions: Vec::new(),
+            cell: None,
+            builds: 0,
+        }
+    }
+
+    /// Get the cutoff distance of this neighbor list
+    pub fn cutoff(&self) -> f64 {
+        self.cutoff
+    }
+
+    /// Get the skin distance of this neighbor list
+    pub fn skin(&self) -> f64 {
+        self.skin
+    }
+
+    /// Get the number of times this list was built
+    pub fn builds(&self) -> usize {
+        self.builds
+    }
+
+    /// Check if this list needs to be built again for the `configuration`
+    pub fn needs_update(&self, configuration: &Configuration) -> bool {
+        match self.cell {
+            None => return true,
+            Some(cell) => {
+                if cell != configuration.cell || self.positions.len() != configuration.size() {
+                    return true;
+                }
+            }
+        }
+
+        let positions = configuration.particles().position;
+        let max_displacement2 = positions.iter().zip(&self.positions).map(|(current, initial)| {
+            (current - initial).norm2()
+        }).fold(0.0, f64::max);
+        // Two particles can get closer by at most twice the maximal
+        // displacement
+        return 4.0 * max_displacement2 > self.skin * self.skin;
+    }
+
+    /// Build this list again for the `configuration` if needed, and return
+    /// whether the list was built again.
+    pub fn update(&mut self, configuration: &Configuration) -> bool {
+        return self.update_filtered(configuration, |_, _| true);
+    }
+
+    /// Build this list again for the `configuration` if needed, only
+    /// considering the pairs of particles `i` and `j` for which
+    /// `filter(i, j)` is `true`. This returns whether the list was built
+    /// again.
+    ///
+    /// The same filter should be used every time the list is updated, or the
+    /// list should be [invalidated](#method.invalidate) when the filter
+    /// changes.
+    pub fn update_filtered<F>(&mut self, configuration: &Configuration, filter: F) -> bool
+        where F: Fn(usize, usize) -> bool
+    {
+        if self.needs_update(configuration) {
+            self.build(configuration, filter);
+            return true;
+        }
+        return false;
+    }
+
+    /// Force this list to be built again the next time it is updated
+    pub fn invalidate(&mut self) {
+        self.cell = None;
+    }
+
+    /// Build the list for the `configuration`, only considering the pairs
+    /// for which `filter(i, j)` is `true`
+    fn build<F>(&mut self, configuration: &Configuration, filter: F) where F: Fn(usize, usize) -> bool {
+        let distance = self.cutoff + self.skin;
+        let cells = CellList::new(configuration, distance);
+        let mut neighbors = vec![Vec::new(); configuration.size()];
+        for i in 0..configuration.size() {
+            cells.for_each_neighbor(i, |j| {
+                if filter(i, j) && configuration.distance(i, j) < distance {
+                    neighbors[i].push(j);
+                    neighbors[j].push(i);
+                }
+            });
+        }
+        for list in &mut neighbors {
+            list.sort_unstable();
+        }
+        self.neighbors = neighbors;
+        self.positions = configuration.particles().position.to_vec();
+        self.cell = Some(configuration.cell);
+        self.builds += 1;
+    }
+
+    /// Get the neighbors of the particle at index `i` with an index larger
+    /// than `i`, as of the last time this list was built. Iterating over the
+    /// neighbors of all the particles visits every pair of particles closer
+    /// than the cutoff exactly once.
+    pub fn neighbors(&self, i: usize) -> &[usize] {
+        let neighbors = &self.neighbors[i];
+        let start = neighbors.partition_point(|&j| j < i);
+        return &neighbors[start..];
+    }
+
+    /// Get all the neighbors of the particle at index `i`, as of the last
+    /// time this list was built.
+    pub fn all_neighbors(&self, i: usize) -> &[usize] {
+        &self.neighbors[i]
+    }
+
+    /// Check if the particle at index `i` can be moved to `position` while
+    /// keeping this list valid, i.e. if the neighbors of `i` still contain
+    /// all the particles closer than the cutoff after the move. This assumes
+    /// that this list is up to date for the current positions.
+    pub fn is_valid_after_move(&self, i: usize, position: Vector3D) -> bool {
+        match self.positions.get(i) {
+            Some(initial) => 4.0 * (position - initial).norm2() <= self.skin * self.skin,
+            None => false,
+        }
+    }
+}
+
+/// A neighbor list which can be updated from a shared reference, used to
+/// store neighbor lists in systems.
+pub(crate) struct SharedNeighborList(RwLock<NeighborList>);
+
+impl SharedNeighborList {
+    /// Wrap the given neighbor `list`
+    pub fn new(list: NeighborList) -> SharedNeighborList {
+        SharedNeighborList(RwLock::new(list))
+    }
+
+    fn read(&self) -> RwLockReadGuard<'_, NeighborList> {
+        // The lock should never be poisonned, because any panic will unwind
+        // and finish the simulation.
+        self.0.read().expect("neighbor list lock is poisonned")
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, NeighborList> {
+        // The lock should never be poisonned, because any panic will unwind
+        // and finish the simulation.
+        self.0.write().expect("neighbor list lock is poisonned")
+    }
+
+    /// Get the cutoff of the neighbor list
+    pub fn cutoff(&self) -> f64 {
+        self.read().cutoff()
+    }
+
+    /// Force the neighbor list to be built again the next time it is used
+    pub fn invalidate(&mut self) {
+        // The lock should never be poisonned, because any panic will unwind
+        // and finish the simulation.
+        self.0.get_mut().expect("neighbor list lock is poisonned").invalidate();
+    }
+
+    /// Get read access to the neighbor list, after updating it for the
+    /// `configuration` if needed, using `filter` to select the pairs of
+    /// particles to consider.
+    pub fn updated<F>(&self, configuration: &Configuration, filter: F) -> RwLockReadGuard<'_, NeighborList>
+        where F: Fn(usize, usize) -> bool
+    {
+        let needs_update = self.read().needs_update(configuration);
+        if needs_update {
+            let _ = self.write().update_filtered(configuration, filter);
+        }
+        return self.read();
+    }
+}
+
+impl Clone for SharedNeighborList {
+    fn clone(&self) -> SharedNeighborList {
+        SharedNeighborList::new(self.read().clone())
+    }
+}
+
+/// Iterator over the particles `j > i` which can interact with a given
+/// particle `i` through pair interactions.
+pub(crate) enum PairNeighbors<'a> {
+    /// All the particles after `i`
+    All(std::ops::Range<usize>),
+    /// The particles in the neighbor list of `i`
+    List(std::slice::Iter<'a, usize>),
+}
+
+impl<'a> PairNeighbors<'a> {
+    /// Get the particles which can interact with the particle at index `i`,
+    /// in a system with `natoms` particles. If `list` is `None`, all the
+    /// particles after `i` are used.
+    pub fn new(list: Option<&'a NeighborList>, i: usize, natoms: usize) -> PairNeighbors<'a> {
+        match list {
+            Some(list) => PairNeighbors::List(list.neighbors(i).iter()),
+            None => PairNeighbors::All((i + 1)..natoms),
+        }
+    }
+}
+
+impl<'a> Iterator for PairNeighbors<'a> {
+    type Item = usize;
+    fn next(&mut self) -> Option<usize> {
+        match *self {
+            PairNeighbors::All(ref mut range) => range.next(),
+            PairNeighbors::List(ref mut iter) => iter.next().copied(),
+        }
+    }
+}
+
+/// Get the linear index of the sub-cell at `index`
+fn linear_index(index: [usize; 3], cells: [usize; 3]) -> usize {
+    (index[0] * cells[1] + index[1]) * cells[2] + index[2]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Molecule, Particle, UnitCell, Vector3D};
+    use crate::System;
+
+    /// Get all the pairs closer than `cutoff`, using the `cells`
+    fn close_pairs(system: &System, cells: &CellList, cutoff: f64) -> Vec<(usize, usize)> {
+        let mut pairs = Vec::new();
+        for i in 0..system.size() {
+            cells.for_each_neighbor(i, |j| {
+                assert!(j > i);
+                if system.distance(i, j) < cutoff {
+                    pairs.push((i, j));
+                }
+            });
+        }
+        pairs.sort();
+        return pairs;
+    }
+
+    /// Get all the pairs closer than `cutoff`, checking all the pairs
+    fn brute_force(system: &System, cutoff: f64) -> Vec<(usize, usize)> {
+        let mut pairs = Vec::new();
+        for i in 0..system.size() {
+            for j in (i + 1)..system.size() {
+                if system.distance(i, j) < cutoff {
+                    pairs.push((i, j));
+                }
+            }
+        }
+        return pairs;
+    }
+
+    fn random_system(cell: UnitCell) -> System {
+        let mut system = System::with_cell(cell);
+        // Simple deterministic pseudo-random positions, some of them outside
+        // of the unit cell
+        let mut state = 12345_u64;
+        let mut random = || {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            (state >> 11) as f64 / (1u64 << 53) as f64
+        };
+        for _ in 0..300 {
+            let fractional = Vector3D::new(
+                3.0 * random() - 1.0,
+                3.0 * random() - 1.0,
+                3.0 * random() - 1.0,
+            );
+            let position = system.cell.cartesian(&fractional);
+            system.add_molecule(Molecule::new(Particle::with_position("Ar", position)));
+        }
+        return system;
+    }
+
+    #[test]
+    fn orthorhombic() {
+        let system = random_system(UnitCell::ortho(20.0, 25.0, 30.0));
+        let cells = CellList::new(&system, 5.0);
+        assert_eq!(cells.cells, Some([4, 5, 6]));
+        assert_eq!(close_pairs(&system, &cells, 5.0), brute_force(&system, 5.0));
+    }
+
+    #[test]
+    fn triclinic() {
+        let system = random_system(UnitCell::triclinic(20.0, 25.0, 30.0, 80.0, 100.0, 110.0));
+        let cells = CellList::new(&system, 5.0);
+        assert!(cells.cells.is_some());
+        assert_eq!(close_pairs(&system, &cells, 5.0), brute_force(&system, 5.0));
+    }
+
+    #[test]
+    fn all_pairs() {
+        // Cell too small for three sub-cells in one direction
+        let system = random_system(UnitCell::ortho(20.0, 25.0, 12.0));
+        let cells = CellList::new(&system, 5.0);
+        assert_eq!(cells.cells, None);
+        assert_eq!(close_pairs(&system, &cells, 5.0), brute_force(&system, 5.0));
+
+        let mut system = System::new();
+        system.add_molecule(Molecule::new(Particle::with_position("Ar", Vector3D::new(0.0, 0.0, 0.0))));
+        system.add_molecule(Molecule::new(Particle::with_position("Ar", Vector3D::new(100.0, 0.0, 0.0))));
+        let cells = CellList::new(&system, 5.0);
+        assert_eq!(cells.cells, None);
+        let mut neighbors = Vec::new();
+        cells.for_each_neighbor(0, |j| neighbors.push(j));
+        assert_eq!(neighbors, vec![1]);
+    }
+
+    /// Get all the pairs closer than `cutoff` in the neighbor `list`
+    fn list_pairs(system: &System, list: &NeighborList, cutoff: f64) -> Vec<(usize, usize)> {
+        let mut pairs = Vec::new();
+        for i in 0..system.size() {
+            for &j in list.neighbors(i) {
+                assert!(j > i);
+                if system.distance(i, j) < cutoff {
+                    pairs.push((i, j));
+                }
+            }
+        }
+        pairs.sort();
+        return pairs;
+    }
+
+    #[test]
+    fn neighbor_list() {
+        let mut system = random_system(UnitCell::triclinic(20.0, 25.0, 30.0, 80.0, 100.0, 110.0));
+        let mut list = NeighborList::with_skin(4.0, 1.0);
+        assert_eq!(list.cutoff(), 4.0);
+        assert_eq!(list.skin(), 1.0);
+        assert!(list.needs_update(&system));
+
+        assert!(list.update(&system));
+        assert_eq!(list.builds(), 1);
+        assert_eq!(list_pairs(&system, &list, 4.0), brute_force(&system, 4.0));
+        for i in 0..system.size() {
+            let all = list.all_neighbors(i);
+            assert!(all.windows(2).all(|pair| pair[0] < pair[1]));
+            for &j in all {
+                assert!(list.all_neighbors(j).contains(&i));
+            }
+        }
+
+        let position = system.particles().position[3];
+        assert!(list.is_valid_after_move(3, position + Vector3D::new(0.0, 0.4, 0.0)));
+        assert!(!list.is_valid_after_move(3, position + Vector3D::new(0.0, 0.6, 0.0)));
+
+        // Moving all the particles by less than half of the skin
+        for position in system.particles_mut().position {
+            *position += Vector3D::new(0.2, -0.2, 0.2);
+        }
+        assert!(!list.update(&system));
+        assert_eq!(list.builds(), 1);
+        assert_eq!(list_pairs(&system, &list, 4.0), brute_force(&system, 4.0));
+
+        // Moving a single particle by more than half of the skin
+        system.particles_mut().position[3] += Vector3D::new(0.0, 0.8, 0.0);
+        assert!(list.update(&system));
+        assert_eq!(list.builds(), 2);
+        assert_eq!(list_pairs(&system, &list, 4.0), brute_force(&system, 4.0));
+
+        // Changing the unit cell
+        system.cell = UnitCell::cubic(25.0);
+        assert!(list.update(&system));
+        assert_eq!(list.builds(), 3);
+        assert_eq!(list_pairs(&system, &list, 4.0), brute_force(&system, 4.0));
+    }
+
+    #[test]
+    fn neighbor_list_zero_skin() {
+        let mut system = random_system(UnitCell::cubic(20.0));
+        let mut list = NeighborList::with_skin(5.0, 0.0);
+        assert!(list.update(&system));
+        assert!(!list.update(&system));
+
+        system.particles_mut().position[0][0] += 1e-6;
+        assert!(list.update(&system));
+        assert_eq!(list.builds(), 2);
+        assert_eq!(list_pairs(&system, &list, 5.0), brute_force(&system, 5.0));
+    }
+
+    #[test]
+    fn filtered_neighbor_list() {
+        let system = random_system(UnitCell::cubic(20.0));
+        let mut list = NeighborList::with_skin(5.0, 0.0);
+        assert!(list.update_filtered(&system, |i, j| (i + j) % 2 == 0));
+        let expected = brute_force(&system, 5.0).into_iter().filter(|&(i, j)| (i + j) % 2 == 0).collect::<Vec<_>>();
+        assert_eq!(list_pairs(&system, &list, 5.0), expected);
+
+        assert!(!list.update(&system));
+        list.invalidate();
+        assert!(list.update(&system));
+        assert_eq!(list.builds(), 2);
+        assert_eq!(list_pairs(&system, &list, 5.0), brute_force(&system, 5.0));
+    }
+
+    #[test]
+    fn default_skin() {
+        let list = NeighborList::new(10.0);
+        assert_eq!(list.cutoff(), 10.0);
+        assert_eq!(list.skin(), 1.0);
+    }
+
+    #[test]
+    #[should_panic(expected = "the skin can not be negative in neighbor list, got -1")]
+    fn negative_skin() {
+        let _ = NeighborList::with_skin(5.0, -1.0);
+    }
+}
@@ -3,7 +3,7 @@
 use std::fmt;
 use soa_derive::StructOfArray;
 
-use crate::sys::get_atomic_mass;
+use crate::sys::periodic_table;
 use crate::Vector3D;
 
 /// A particle kind. Particles with the same name will have the same kind. This
@@ -56,7 +56,7 @@ impl Particle {
     /// table. The charge and velocity are set to 0.
     pub fn with_position<S: Into<String>>(name: S, position: Vector3D) -> Particle {
         let name = name.into();
-        let mass = get_atomic_mass(&name).unwrap_or(0.0);
+        let mass = periodic_table::mass(&name).unwrap_or(0.0);
         Particle {
             name: name,
             mass: mass,
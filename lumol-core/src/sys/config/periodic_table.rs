@@ -0,0 +1,292 @@
+// Lumol, an extensible molecular simulation engine
+// Copyright (C) Lumol's contributors — BSD license
+
+//! Properties of the chemical elements: atomic number, mass and covalent
+//! radius.
+//!
+//! All the functions in this module take the element symbol as `name`, and
+//! return `None` for unknown elements.
+
+/// Element symbols and atomic masses, in atomic number order
+#[allow(clippy::unreadable_literal)]
+static ATOMIC_MASSES: [(&str, f64); 118] = [
+    ("H", 1.008),
+    ("He", 4.002602),
+    ("Li", 6.94),
+    ("Be", 9.012182),
+    ("B", 10.81),
+    ("C", 12.011),
+    ("N", 14.007),
+    ("O", 15.999),
+    ("F", 18.9984032),
+    ("Ne", 20.1797),
+    ("Na", 22.98976928),
+    ("Mg", 24.305),
+    ("Al", 26.9815386),
+    ("Si", 28.085),
+    ("P", 30.973762),
+    ("S", 32.06),
+    ("Cl", 35.45),
+    ("Ar", 39.948),
+    ("K", 39.0983),
+    ("Ca", 40.078),
+    ("Sc", 44.955912),
+    ("Ti", 47.867),
+    ("V", 50.9415),
+    ("Cr", 51.9961),
+    ("Mn", 54.938045),
+    ("Fe", 55.845),
+    ("Co", 58.933195),
+    ("Ni", 58.6934),
+    ("Cu", 63.546),
+    ("Zn", 65.38),
+    ("Ga", 69.723),
+    ("Ge", 72.63),
+    ("As", 74.9216),
+    ("Se", 78.96),
+    ("Br", 79.904),
+    ("Kr", 83.798),
+    ("Rb", 85.4678),
+    ("Sr", 87.62),
+    ("Y", 88.90585),
+    ("Zr", 91.224),
+    ("Nb", 92.90638),
+    ("Mo", 95.96),
+    ("Tc", 97.0),
+    ("Ru", 101.07),
+    ("Rh", 102.9055),
+    ("Pd", 106.42),
+    ("Ag", 107.8682),
+    ("Cd", 112.411),
+    ("In", 114.818),
+    ("Sn", 118.71),
+    ("Sb", 121.76),
+    ("Te", 127.6),
+    ("I", 126.90447),
+    ("Xe", 131.293),
+    ("Cs", 132.9054519),
+    ("Ba", 137.327),
+    ("La", 138.90547),
+    ("Ce", 140.116),
+    ("Pr", 140.90765),
+    ("Nd", 144.242),
+    ("Pm", 145.0),
+    ("Sm", 150.36),
+    ("Eu", 151.964),
+    ("Gd", 157.25),
+    ("Tb", 158.92535),
+    ("Dy", 162.5),
+    ("Ho", 164.93032),
+    ("Er", 167.259),
+    ("Tm", 168.93421),
+    ("Yb", 173.054),
+    ("Lu", 174.9668),
+    ("Hf", 178.49),
+    ("Ta", 180.94788),
+    ("W", 183.84),
+    ("Re", 186.207),
+    ("Os", 190.23),
+    ("Ir", 192.217),
+    ("Pt", 195.084),
+    ("Au", 196.966569),
+    ("Hg", 200.592),
+    ("Tl", 204.38),
+    ("Pb", 207.2),
+    ("Bi", 208.9804),
+    ("Po", 209.0),
+    ("At", 210.0),
+    ("Rn", 222.0),
+    ("Fr", 223.0),
+    ("Ra", 226.0),
+    ("Ac", 227.0),
+    ("Th", 232.03806),
+    ("Pa", 231.03588),
+    ("U", 238.02891),
+    ("Np", 237.0),
+    ("Pu", 244.0),
+    ("Am", 243.0),
+    ("Cm", 247.0),
+    ("Bk", 247.0),
+    ("Cf", 251.0),
+    ("Es", 252.0),
+    ("Fm", 257.0),
+    ("Md", 258.0),
+    ("No", 259.0),
+    ("Lr", 262.0),
+    ("Rf", 267.0),
+    ("Db", 270.0),
+    ("Sg", 271.0),
+    ("Bh", 270.0),
+    ("Hs", 277.0),
+    ("Mt", 276.0),
+    ("Ds", 281.0),
+    ("Rg", 282.0),
+    ("Cn", 285.0),
+    ("Uut", 285.0),
+    ("Fl", 289.0),
+    ("Mc", 289.0),
+    ("Lv", 293.0),
+    ("Ts", 294.0),
+    ("Og", 294.0),
+];
+
+/// Covalent radii in Angstroms, from Cordero et al., Dalton Trans. (2008)
+/// 2832-2838, in atomic number order. Single-bond values are used for carbon,
+/// and low-spin values for transition metals.
+static COVALENT_RADII: [(&str, f64); 54] = [
+    ("H", 0.31),
+    ("He", 0.28),
+    ("Li", 1.28),
+    ("Be", 0.96),
+    ("B", 0.84),
+    ("C", 0.76),
+    ("N", 0.71),
+    ("O", 0.66),
+    ("F", 0.57),
+    ("Ne", 0.58),
+    ("Na", 1.66),
+    ("Mg", 1.41),
+    ("Al", 1.21),
+    ("Si", 1.11),
+    ("P", 1.07),
+    ("S", 1.05),
+    ("Cl", 1.02),
+    ("Ar", 1.06),
+    ("K", 2.03),
+    ("Ca", 1.76),
+    ("Sc", 1.70),
+    ("Ti", 1.60),
+    ("V", 1.53),
+    ("Cr", 1.39),
+    ("Mn", 1.39),
+    ("Fe", 1.32),
+    ("Co", 1.26),
+    ("Ni", 1.24),
+    ("Cu", 1.32),
+    ("Zn", 1.22),
+    ("Ga", 1.22),
+    ("Ge", 1.20),
+    ("As", 1.19),
+    ("Se", 1.20),
+    ("Br", 1.20),
+    ("Kr", 1.16),
+    ("Rb", 2.20),
+    ("Sr", 1.95),
+    ("Y", 1.90),
+    ("Zr", 1.75),
+    ("Nb", 1.64),
+    ("Mo", 1.54),
+    ("Tc", 1.47),
+    ("Ru", 1.46),
+    ("Rh", 1.42),
+    ("Pd", 1.39),
+    ("Ag", 1.45),
+    ("Cd", 1.44),
+    ("In", 1.42),
+    ("Sn", 1.39),
+    ("Sb", 1.39),
+    ("Te", 1.38),
+    ("I", 1.39),
+    ("Xe", 1.40),
+];
+
+/// Get the atomic number of the element with the given `name`
+///
+/// # Example
+///
+/// ```
+/// # use lumol_core::sys::periodic_table;
+/// assert_eq!(periodic_table::atomic_number("C"), Some(6));
+/// assert_eq!(periodic_table::atomic_number("Ow"), None);
+/// ```
+pub fn atomic_number(name: &str) -> Option<u8> {
+    ATOMIC_MASSES.iter()
+                 .position(|&(symbol, _)| symbol == name)
+                 .map(|index| (index + 1) as u8)
+}
+
+/// Get the mass (in atomic mass units) of the element with the given `name`
+///
+/// # Example
+///
+/// ```
+/// # use lumol_core::sys::periodic_table;
+/// assert_eq!(periodic_table::mass("Ti"), Some(47.867));
+/// assert_eq!(periodic_table::mass("Ow"), None);
+/// ```
+pub fn mass(name: &str) -> Option<f64> {
+    for (symbol, mass) in ATOMIC_MASSES.iter() {
+        if name == *symbol {
+            return Some(*mass);
+        }
+    }
+    return None;
+}
+
+/// Get the covalent radius (in Angstroms) of the element with the given
+/// `name`. Covalent radii are only known for the elements up to Xenon.
+///
+/// # Example
+///
+/// ```
+/// # use lumol_core::sys::periodic_table;
+/// assert_eq!(periodic_table::covalent_radius("C"), Some(0.76));
+/// assert_eq!(periodic_table::covalent_radius("Ow"), None);
+/// ```
+pub fn covalent_radius(name: &str) -> Option<f64> {
+    for (symbol, radius) in COVALENT_RADII.iter() {
+        if name == *symbol {
+            return Some(*radius);
+        }
+    }
+    return None;
+}
+
+/// Get the mass of the element with the given atomic `name`. This is the
+/// same as [`periodic_table::mass`](periodic_table/fn.mass.html).
+///
+/// # Example
+///
+/// ```
+/// # use lumol_core::sys::get_atomic_mass;
+/// assert_eq!(get_atomic_mass("Ti"), Some(47.867));
+/// assert_eq!(get_atomic_mass("Ow"), None);
+/// ```
+pub fn get_atomic_mass(name: &str) -> Option<f64> {
+    mass(name)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn masses() {
+        assert_eq!(mass("He"), Some(4.002602));
+        assert_eq!(mass("Na"), Some(22.98976928));
+        assert_eq!(mass("O"), Some(15.999));
+        assert_eq!(mass("HOH"), None);
+        assert_eq!(get_atomic_mass("Na"), Some(22.98976928));
+    }
+
+    #[test]
+    fn atomic_numbers() {
+        assert_eq!(atomic_number("H"), Some(1));
+        assert_eq!(atomic_number("Na"), Some(11));
+        assert_eq!(atomic_number("Og"), Some(118));
+        assert_eq!(atomic_number("HOH"), None);
+    }
+
+    #[test]
+    fn covalent_radii() {
+        assert_eq!(covalent_radius("H"), Some(0.31));
+        assert_eq!(covalent_radius("Xe"), Some(1.40));
+        assert_eq!(covalent_radius("Cs"), None);
+        assert_eq!(covalent_radius("HOH"), None);
+
+        // The tables use the same order
+        for (&(radius_symbol, _), &(mass_symbol, _)) in COVALENT_RADII.iter().zip(ATOMIC_MASSES.iter()) {
+            assert_eq!(radius_symbol, mass_symbol);
+        }
+    }
+}
@@ -0,0 +1,175 @@
+// Lumol, an extensible molecular simulation engine
+// Copyright (C) Lumol's contributors — BSD license
+
+//! Drude oscillators are charged particles harmonically bound to a core
+//! particle, used to model electronic polarization.
+//!
+//! In a polarizable model based on Drude oscillators, the charge of a
+//! polarizable atom is split between the core atom and a Drude particle. The
+//! Drude particle is bound to the core by an harmonic spring with an
+//! equilibrium distance of zero, and is displaced by the local electric
+//! field, creating an induced dipole. For a Drude particle with charge $q_D$
+//! and spring constant $k$, the isotropic polarizability of the atom is
+//! $\alpha = q_D^2 / k$.
+//!
+//! Drude particles can either be massless, and placed at their energy
+//! minimum with [`System::relax_drude_particles`]; or carry a small mass
+//! taken from the core particle and be propagated with the other particles,
+//! using a thermostat keeping the Drude degrees of freedom cold.
+//!
+//! [`System::relax_drude_particles`]: struct.System.html#method.relax_drude_particles
+use crate::{Configuration, Vector3D};
+
+/// A Drude particle, bound to its core particle by an harmonic spring with
+/// zero equilibrium length: $U = \frac{1}{2} k |\vec r_D - \vec r_C|^2$.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DrudeParticle {
+    /// Index of the Drude particle in the configuration
+    drude: usize,
+    /// Index of the core particle in the configuration
+    core: usize,
+    /// Spring constant of the harmonic bond between the Drude particle and
+    /// the core
+    k: f64,
+}
+
+impl DrudeParticle {
+    /// Create a new Drude particle for the particle at index `drude`, bound
+    /// to the particle at index `core` with the spring constant `k`.
+    ///
+    /// # Panics
+    ///
+    /// If the spring constant is not positive, or if the Drude particle is
+    /// its own core.
+    pub fn new(drude: usize, core: usize, k: f64) -> DrudeParticle {
+        assert!(k > 0.0, "the spring constant of a Drude particle must be positive, got {}", k);
+        assert!(drude != core, "a Drude particle can not be its own core");
+        DrudeParticle {
+            drude: drude,
+            core: core,
+            k: k,
+        }
+    }
+
+    /// Get the index of the Drude particle
+    pub fn drude(&self) -> usize {
+        self.drude
+    }
+
+    /// Get the index of the core particle
+    pub fn core(&self) -> usize {
+        self.core
+    }
+
+    /// Get the spring constant of the Drude bond
+    pub fn k(&self) -> f64 {
+        self.k
+    }
+
+    /// Get the displacement of the Drude particle with respect to its core
+    /// in the `configuration`, using the nearest image convention.
+    pub fn displacement(&self, configuration: &Configuration) -> Vector3D {
+        configuration.nearest_image(self.drude, self.core)
+    }
+
+    /// Get the energy of the Drude bond in the `configuration`
+    pub fn energy(&self, configuration: &Configuration) -> f64 {
+        0.5 * self.k * self.displacement(configuration).norm2()
+    }
+
+    /// Get the force acting on the Drude particle because of the Drude bond
+    /// in the `configuration`. The opposite force acts on the core particle.
+    pub fn force(&self, configuration: &Configuration) -> Vector3D {
+        -self.k * self.displacement(configuration)
+    }
+
+    /// Get the dipole induced by the displacement of the Drude particle in
+    /// the `configuration`.
+    pub fn dipole(&self, configuration: &Configuration) -> Vector3D {
+        configuration.particles().charge[self.drude] * self.displacement(configuration)
+    }
+
+    /// Get the isotropic polarizability $\alpha = q_D^2 / k$ associated with
+    /// this Drude particle in the `configuration`.
+    pub fn polarizability(&self, configuration: &Configuration) -> f64 {
+        let charge = configuration.particles().charge[self.drude];
+        return charge * charge / self.k;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Molecule, Particle, System, UnitCell};
+    use crate::units;
+    use approx::{assert_relative_eq, assert_ulps_eq};
+
+    /// A single polarizable argon atom, with a Drude particle on top of the
+    /// core particle.
+    fn argon() -> System {
+        let mut system = System::with_cell(UnitCell::cubic(20.0));
+        let mut molecule = Molecule::new(Particle::with_position("Ar", Vector3D::new(10.0, 10.0, 10.0)));
+        molecule.add_particle_bonded_to(0, Particle::with_position("D", Vector3D::new(10.0, 10.0, 10.0)));
+        system.add_molecule(molecule);
+
+        system.particles_mut().charge[0] = 2.0;
+        system.particles_mut().charge[1] = -2.0;
+        system.particles_mut().mass[0] = 39.548;
+        system.particles_mut().mass[1] = 0.4;
+        system.add_drude_particle(DrudeParticle::new(1, 0, units::from(1000.0, "kJ/mol/A^2").unwrap()));
+        return system;
+    }
+
+    #[test]
+    #[should_panic(expected = "the spring constant of a Drude particle must be positive, got -3")]
+    fn negative_k() {
+        let _ = DrudeParticle::new(1, 0, -3.0);
+    }
+
+    #[test]
+    #[should_panic(expected = "a Drude particle can not be its own core")]
+    fn own_core() {
+        let _ = DrudeParticle::new(1, 1, 4.0);
+    }
+
+    #[test]
+    fn energy_and_force() {
+        let mut system = argon();
+        let drude = system.drude_particles()[0];
+        assert_eq!(drude.energy(&system), 0.0);
+        assert_eq!(drude.force(&system), Vector3D::zero());
+
+        system.particles_mut().position[1] = Vector3D::new(10.0, 10.1, 10.0);
+        let k = drude.k();
+        assert_relative_eq!(drude.displacement(&system), Vector3D::new(0.0, 0.1, 0.0), epsilon = 1e-12);
+        assert_relative_eq!(drude.energy(&system), 0.5 * k * 0.01, epsilon = 1e-12);
+        assert_relative_eq!(drude.force(&system), Vector3D::new(0.0, -0.1 * k, 0.0), epsilon = 1e-12);
+        assert_relative_eq!(drude.dipole(&system), Vector3D::new(0.0, -0.2, 0.0), epsilon = 1e-12);
+
+        assert_relative_eq!(system.potential_energy(), 0.5 * k * 0.01, epsilon = 1e-12);
+        let forces = system.forces();
+        assert_relative_eq!(forces[0], Vector3D::new(0.0, 0.1 * k, 0.0), epsilon = 1e-12);
+        assert_relative_eq!(forces[1], Vector3D::new(0.0, -0.1 * k, 0.0), epsilon = 1e-12);
+    }
+
+    #[test]
+    fn induced_dipole() {
+        let mut system = argon();
+        let field = Vector3D::new(units::from(0.1, "eV/A").unwrap(), 0.0, units::from(-0.05, "eV/A").unwrap());
+        system.set_electric_field(field);
+
+        let drude = system.drude_particles()[0];
+        assert_eq!(drude.dipole(&system), Vector3D::zero());
+
+        system.relax_drude_particles(1e-9);
+        let alpha = drude.polarizability(&system);
+        assert_ulps_eq!(alpha, 4.0 / drude.k());
+        assert_ulps_eq!(drude.dipole(&system), alpha * field, epsilon = 1e-12);
+
+        // The core did not move, and the forces are balanced
+        assert_eq!(system.particles().position[0], Vector3D::new(10.0, 10.0, 10.0));
+        for force in system.forces() {
+            assert_ulps_eq!(force.norm(), 0.0, epsilon = 1e-9);
+        }
+    }
+}
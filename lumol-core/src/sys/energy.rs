@@ -10,8 +10,8 @@ use std::f64::consts::PI;
 
 use rayon::prelude::*;
 
-use crate::BondPath;
-use crate::System;
+use crate::{BondPath, PairNeighbors, ThreeBodyPotential};
+use crate::{System, Vector3D};
 
 /// An helper struct to evaluate energy components of a system.
 pub struct EnergyEvaluator<'a> {
@@ -27,7 +27,11 @@ impl<'a> EnergyEvaluator<'a> {
 
 impl<'a> EnergyEvaluator<'a> {
     /// Compute the energy associated with the pair of particles `i, j` at
-    /// distance `r`
+    /// distance `r`, separated by the given bond `path`.
+    ///
+    /// Pairs separated by more than three bonds are never excluded by this
+    /// function, use [`pair_between`](#method.pair_between) to also handle
+    /// `ExcludeBonded` restrictions with more than three bonds.
     #[inline]
     pub fn pair(&self, path: BondPath, r: f64, i: usize, j: usize) -> f64 {
         match self.system.pair_potential(i, j) {
@@ -43,15 +47,41 @@ impl<'a> EnergyEvaluator<'a> {
         }
     }
 
+    /// Compute the energy associated with the pair of particles `i, j` at
+    /// distance `r`, using the bonds between `i` and `j` in the system to
+    /// get the restriction information.
+    #[inline]
+    pub fn pair_between(&self, r: f64, i: usize, j: usize) -> f64 {
+        match self.system.pair_potential(i, j) {
+            Some(potential) => {
+                let info = potential.restriction().information_between(self.system, i, j);
+                if !info.excluded {
+                    info.scaling * potential.energy(r)
+                } else {
+                    0.0
+                }
+            }
+            None => 0.0
+        }
+    }
+
     /// Compute the energy of all the pairs in the system
     pub fn pairs(&self) -> f64 {
+        let neighbor_list = self.system.neighbor_list();
+        let neighbor_list = neighbor_list.as_deref();
         let energies = (0..self.system.size()).into_par_iter().map(|i| {
             let mut local_energy = 0.0;
 
-            for j in (i + 1)..self.system.size() {
-                let r = self.system.nearest_image(i, j).norm();
-                let path = self.system.bond_path(i, j);
-                local_energy += self.pair(path, r, i, j);
+            for j in PairNeighbors::new(neighbor_list, i, self.system.size()) {
+                // look for the potential first, to skip missing and
+                // excluded pairs before computing the distance
+                if let Some(potential) = self.system.pair_potential(i, j) {
+                    let info = potential.restriction().information_between(self.system, i, j);
+                    if !info.excluded {
+                        let r = self.system.nearest_image(i, j).norm();
+                        local_energy += info.scaling * potential.energy(r);
+                    }
+                }
             }
             local_energy
         });
@@ -99,6 +129,16 @@ impl<'a> EnergyEvaluator<'a> {
         return energy;
     }
 
+    /// Compute the energy of all the harmonic bonds between Drude particles
+    /// and their cores in the system
+    pub fn drude(&self) -> f64 {
+        let mut energy = 0.0;
+        for drude in self.system.drude_particles() {
+            energy += drude.energy(self.system);
+        }
+        return energy;
+    }
+
     /// Compute the energy associated with the angle `i, j, k` at angle `theta`
     #[inline]
     pub fn angle(&self, theta: f64, i: usize, j: usize, k: usize) -> f64 {
@@ -140,10 +180,48 @@ impl<'a> EnergyEvaluator<'a> {
         return energy;
     }
 
+    /// Compute the energy associated with the improper dihedral angle `i, j,
+    /// k, m` centered on `i`, at angle `phi`
+    #[inline]
+    pub fn improper(&self, phi: f64, i: usize, j: usize, k: usize, m: usize) -> f64 {
+        self.system.improper_potential(i, j, k, m)
+                   .map_or(0.0, |potential| potential.energy(phi))
+    }
+
+    /// Compute the energy of all the improper dihedral angles in the system
+    pub fn impropers(&self) -> f64 {
+        let mut energy = 0.0;
+        for molecule in self.system.molecules() {
+            for improper in molecule.impropers() {
+                let (i, j, k, m) = (improper.i(), improper.j(), improper.k(), improper.m());
+                let phi = self.system.dihedral(i, j, k, m);
+                energy += self.improper(phi, i, j, k, m);
+            }
+        }
+        return energy;
+    }
+
+    /// Compute the energy of all the cross terms in the system
+    pub fn cross_terms(&self) -> f64 {
+        let mut energy = 0.0;
+        for molecule in self.system.molecules() {
+            for angle in molecule.angles() {
+                energy += self.system.cross_term_energy(angle.i(), angle.j(), angle.k());
+            }
+        }
+        return energy;
+    }
+
+    /// Compute the energy of all the triplets interacting with a three-body
+    /// potential in the system
+    pub fn three_body(&self) -> f64 {
+        three_body_energy(self.system, self.system.particles().position)
+    }
+
     /// Compute the energy of the electrostatic interactions
     #[inline]
     pub fn coulomb(&self) -> f64 {
-        if let Some(coulomb) = self.system.coulomb_potential() {
+        if let Some(coulomb) = self.system.charged_coulomb_potential() {
             coulomb.energy(self.system)
         } else {
             0.0
@@ -159,12 +237,81 @@ impl<'a> EnergyEvaluator<'a> {
         }
         return energy;
     }
+
+    /// Compute the energy of the external potentials
+    pub fn external(&self) -> f64 {
+        let positions = self.system.particles().position;
+        let energies = (0..self.system.size()).into_par_iter().map(|i| {
+            external_energy(self.system, i, &positions[i])
+        });
+        return energies.sum();
+    }
+}
+
+/// Compute the energy of the particle at index `i` in the `system` with all
+/// the external potentials, if it was at the given `position`.
+pub(crate) fn external_energy(system: &System, i: usize, position: &Vector3D) -> f64 {
+    let mut energy = 0.0;
+    for external in system.external_potentials() {
+        energy += external.energy(system, i, position);
+    }
+    return energy;
+}
+
+/// Compute the energy of all the triplets interacting with a three-body
+/// potential in the `system`, using the given `positions` for the particles.
+pub(crate) fn three_body_energy(system: &System, positions: &[Vector3D]) -> f64 {
+    let mut energy = 0.0;
+    for_each_triplet(system, positions, |_, _, _, r_ij, r_ik, potential| {
+        energy += potential.energy(r_ij, r_ik);
+    });
+    return energy;
+}
+
+/// Call `function(i, j, k, r_ij, r_ik, potential)` for all the triplets
+/// `j-i-k` centered on `i` with a three-body `potential` in the `system`,
+/// using the given `positions` for the particles. `r_ij` and `r_ik` are the
+/// nearest image vectors from `i` to `j` and from `i` to `k`. Each triplet is
+/// visited once, with `j < k`.
+pub(crate) fn for_each_triplet<F>(system: &System, positions: &[Vector3D], mut function: F)
+where
+    F: FnMut(usize, usize, usize, &Vector3D, &Vector3D, &dyn ThreeBodyPotential)
+{
+    let cutoff = match system.interactions().three_body_cutoff() {
+        Some(cutoff) => cutoff,
+        None => return,
+    };
+
+    let mut neighbors = Vec::new();
+    for i in 0..system.size() {
+        neighbors.clear();
+        for j in (0..system.size()).filter(|&j| j != i) {
+            let mut r_ij = positions[j] - positions[i];
+            system.cell.vector_image(&mut r_ij);
+            if r_ij.norm() < cutoff {
+                neighbors.push((j, r_ij));
+            }
+        }
+
+        for (n, &(j, r_ij)) in neighbors.iter().enumerate() {
+            for &(k, r_ik) in &neighbors[(n + 1)..] {
+                if let Some(potential) = system.three_body_potential(j, i, k) {
+                    let cutoff = potential.cutoff();
+                    if r_ij.norm() < cutoff && r_ik.norm() < cutoff {
+                        function(i, j, k, &r_ij, &r_ik, potential);
+                    }
+                }
+            }
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{Harmonic, LennardJones, NullPotential, PairInteraction};
+    use std::f64::consts::FRAC_PI_2;
+
+    use crate::{BondAngle, DihedralPotential, Harmonic, LennardJones, NeighborList, NullPotential, PairInteraction, Scaled};
     use crate::{System, UnitCell};
     use crate::utils::system_from_xyz;
     use crate::units;
@@ -263,4 +410,196 @@ mod tests {
         let evaluator = EnergyEvaluator::new(&system);
         assert_ulps_eq!(evaluator.dihedrals(), units::from(1250.0, "kJ/mol").unwrap(), max_ulps = 15);
     }
+
+    #[test]
+    fn molecule_across_boundaries() {
+        // Check that the internal vectors use the minimum image convention
+        // when the molecule is wrapped across the periodic boundaries.
+        let mut system = testing_system();
+        let bonds = EnergyEvaluator::new(&system).bonds();
+        let angles = EnergyEvaluator::new(&system).angles();
+        let dihedrals = EnergyEvaluator::new(&system).dihedrals();
+
+        let position = system.particles().position[0];
+        let delta = Vector3D::new(9.5, 9.5, 9.5) - position;
+        for position in system.particles_mut().position {
+            *position += delta;
+        }
+        let cell = system.cell;
+        for position in system.particles_mut().position {
+            cell.wrap_vector(position);
+        }
+        // The molecule is now split between the two sides of the cell
+        let positions = system.particles().position;
+        assert!(positions.iter().any(|position| position[0] < 5.0));
+        assert!(positions.iter().any(|position| position[0] > 5.0));
+
+        let evaluator = EnergyEvaluator::new(&system);
+        assert_ulps_eq!(evaluator.bonds(), bonds, max_ulps = 15);
+        assert_ulps_eq!(evaluator.angles(), angles, max_ulps = 15);
+        assert_ulps_eq!(evaluator.dihedrals(), dihedrals, max_ulps = 15);
+    }
+
+    #[test]
+    fn scaled_dihedrals() {
+        let mut system = testing_system();
+        let unscaled = EnergyEvaluator::new(&system).dihedrals();
+
+        let potential: Box<dyn DihedralPotential> = Box::new(Harmonic {
+            k: units::from(100.0, "kJ/mol/deg^2").unwrap(),
+            x0: units::from(185.0, "deg").unwrap(),
+        });
+        system.set_dihedral_potential(("F", "F", "F", "F"), Box::new(Scaled::new(potential, 0.5)));
+        let evaluator = EnergyEvaluator::new(&system);
+        assert_ulps_eq!(evaluator.dihedrals(), 0.5 * unscaled);
+    }
+
+    #[test]
+    fn impropers() {
+        let mut system = system_from_xyz(
+            "4
+            cell: 10.0
+            C 0.0 0.0 0.0
+            O 1.2 0.0 0.0
+            O -0.6 1.0 0.0
+            N -0.6 -1.0 0.0
+            ",
+        );
+        assert!(system.add_bond(0, 1).is_empty());
+        assert!(system.add_bond(0, 2).is_empty());
+        assert!(system.add_bond(0, 3).is_empty());
+        system.add_improper_interaction(("C", "O", "N", "O"), Box::new(Harmonic { k: 100.0, x0: 0.0 }));
+
+        // Planar configuration
+        assert_eq!(EnergyEvaluator::new(&system).impropers(), 0.0);
+
+        // Puckered configuration
+        system.particles_mut().position[0][2] = 0.3;
+        let phi = system.dihedral(0, 1, 2, 3);
+        assert!(phi.abs() > 0.1);
+        let energy = EnergyEvaluator::new(&system).impropers();
+        assert!(energy > 0.0);
+        assert_ulps_eq!(energy, 50.0 * phi * phi);
+
+        // Without potential
+        let mut system = system.clone();
+        system.add_improper_interaction(("C", "O", "O", "N"), Box::new(NullPotential));
+        assert_eq!(EnergyEvaluator::new(&system).impropers(), 0.0);
+    }
+
+    #[test]
+    fn cross_terms() {
+        let mut system = system_from_xyz(
+            "3
+            cell: 10.0
+            H 1.0 0.0 0.0
+            O 0.0 0.0 0.0
+            C 0.0 1.5 0.0
+            ",
+        );
+        assert!(system.add_bond(0, 1).is_empty());
+        assert!(system.add_bond(1, 2).is_empty());
+        let potential = BondAngle { k1: 10.0, k2: 20.0, r1: 1.0, r2: 1.5, theta0: FRAC_PI_2 };
+        system.add_cross_term_interaction(("H", "O", "C"), Box::new(potential));
+
+        // Reference geometry
+        assert_eq!(EnergyEvaluator::new(&system).cross_terms(), 0.0);
+
+        // Only the bonds deviate from the reference
+        system.particles_mut().position[0][0] = 1.2;
+        assert_eq!(EnergyEvaluator::new(&system).cross_terms(), 0.0);
+
+        // Both the bonds and the angle deviate from the reference
+        system.particles_mut().position[2] = Vector3D::new(0.5, 1.5, 0.0);
+        let theta = system.angle(0, 1, 2);
+        let r_2 = system.distance(1, 2);
+        let expected = (theta - FRAC_PI_2) * (10.0 * 0.2 + 20.0 * (r_2 - 1.5));
+        let energy = EnergyEvaluator::new(&system).cross_terms();
+        assert!(energy != 0.0);
+        assert_ulps_eq!(energy, expected);
+
+        // The potential is not symmetric, and is used with the right order
+        // of the bonds when particles are in the reverse order
+        let mut system = system_from_xyz(
+            "3
+            cell: 10.0
+            C 0.5 1.5 0.0
+            O 0.0 0.0 0.0
+            H 1.2 0.0 0.0
+            ",
+        );
+        assert!(system.add_bond(0, 1).is_empty());
+        assert!(system.add_bond(1, 2).is_empty());
+        system.add_cross_term_interaction(("H", "O", "C"), Box::new(potential));
+        assert_ulps_eq!(EnergyEvaluator::new(&system).cross_terms(), expected);
+    }
+
+    #[test]
+    fn excluded_pairs() {
+        use crate::{Molecule, Particle, Vector3D};
+        use std::time::{Duration, Instant};
+
+        let mut system = System::with_cell(UnitCell::cubic(40.0));
+        for i in 0..8 {
+            for j in 0..8 {
+                for k in 0..8 {
+                    // only one particle out of eight is interacting
+                    let name = if (i + j + k) % 8 == 0 { "Ar" } else { "He" };
+                    let position = Vector3D::new(5.0 * i as f64, 5.0 * j as f64, 5.0 * k as f64);
+                    system.add_molecule(Molecule::new(Particle::with_position(name, position)));
+                }
+            }
+        }
+        let lj = PairInteraction::new(Box::new(LennardJones {sigma: 3.4, epsilon: 1.0}), 12.0);
+        system.set_pair_potential(("Ar", "Ar"), lj);
+
+        let mut null = system.clone();
+        null.set_pair_potential(("Ar", "He"), PairInteraction::new(Box::new(NullPotential), 12.0));
+        null.set_pair_potential(("He", "He"), PairInteraction::new(Box::new(NullPotential), 12.0));
+
+        let mut excluded = system.clone();
+        excluded.set_pair_potential(("Ar", "He"), PairInteraction::excluded());
+        excluded.set_pair_potential(("He", "He"), PairInteraction::excluded());
+        assert!(excluded.pair_potential(0, 1).is_none());
+        assert!(excluded.missing_interactions().is_empty());
+
+        let energy = EnergyEvaluator::new(&null).pairs();
+        assert!(energy < 0.0);
+        assert_ulps_eq!(EnergyEvaluator::new(&excluded).pairs(), energy, max_ulps = 10);
+        for (force, expected) in excluded.forces().iter().zip(null.forces()) {
+            assert!((force - expected).norm() < 1e-12);
+        }
+
+        // use the fastest of multiple runs to reduce timing noise
+        let fastest = |system: &System| {
+            (0..5).map(|_| {
+                let start = Instant::now();
+                let _ = EnergyEvaluator::new(system).pairs();
+                start.elapsed()
+            }).min().unwrap_or(Duration::from_secs(0))
+        };
+        assert!(fastest(&excluded) < fastest(&null));
+
+        // Excluded pairs are not part of the neighbor list
+        excluded.set_neighbor_list(NeighborList::new(12.0));
+        assert_ulps_eq!(EnergyEvaluator::new(&excluded).pairs(), energy, max_ulps = 10);
+        {
+            let list = excluded.neighbor_list().unwrap();
+            for i in 0..excluded.size() {
+                for &j in list.all_neighbors(i) {
+                    assert_eq!(excluded.particles().name[i], "Ar");
+                    assert_eq!(excluded.particles().name[j], "Ar");
+                }
+            }
+        }
+
+        // Changing the pair interactions builds the list again
+        let builds = excluded.neighbor_list().unwrap().builds();
+        excluded.set_pair_potential(("Ar", "He"), PairInteraction::new(Box::new(NullPotential), 12.0));
+        let list = excluded.neighbor_list().unwrap();
+        assert_eq!(list.builds(), builds + 1);
+        assert!((0..excluded.size()).any(|i| list.all_neighbors(i).iter().any(|&j| {
+            excluded.particles().name[i] != excluded.particles().name[j]
+        })));
+    }
 }
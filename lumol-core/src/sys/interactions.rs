@@ -10,14 +10,16 @@ use std::f64;
 
 use log::warn;
 
-use crate::{AnglePotential, BondPotential, DihedralPotential, PairInteraction};
-use crate::{CoulombicPotential, GlobalPotential};
+use crate::{AnglePotential, BondPotential, DihedralPotential, ImproperPotential, PairInteraction};
+use crate::{CoulombicPotential, ExternalPotential, GlobalPotential, ThreeBodyPotential};
+use crate::{CrossTermPotential, ElectricField, PairRestriction, PotentialParameters};
 use crate::ParticleKind;
 
 pub type PairKind = (ParticleKind, ParticleKind);
 pub type BondKind = (ParticleKind, ParticleKind);
 pub type AngleKind = (ParticleKind, ParticleKind, ParticleKind);
 pub type DihedralKind = (ParticleKind, ParticleKind, ParticleKind, ParticleKind);
+pub type ImproperKind = (ParticleKind, ParticleKind, ParticleKind, ParticleKind);
 
 /// Normalize pair indexes to get a canonical representation
 #[inline]
@@ -57,6 +59,15 @@ fn normalize_dihedral((i, j, k, m): DihedralKind) -> DihedralKind {
     }
 }
 
+/// Normalize improper dihedral indexes to get a canonical representation.
+/// The central particle is kept first, and the other ones are sorted.
+#[inline]
+fn normalize_improper((i, j, k, m): ImproperKind) -> ImproperKind {
+    let mut others = [j, k, m];
+    others.sort_unstable();
+    (i, others[0], others[1], others[2])
+}
+
 /// The `Interaction` type hold all data about the potentials in the system.
 ///
 /// Its main role is to store and provide access
@@ -66,6 +77,10 @@ pub struct Interactions {
     pub coulomb: Option<Box<dyn CoulombicPotential>>,
     /// Global potentials
     pub globals: Vec<Box<dyn GlobalPotential>>,
+    /// External potentials
+    pub externals: Vec<Box<dyn ExternalPotential>>,
+    /// Uniform external electric field
+    pub electric_field: Option<ElectricField>,
     /// Pair potentials
     pairs: BTreeMap<PairKind, PairInteraction>,
     /// Bond potentials
@@ -74,6 +89,13 @@ pub struct Interactions {
     angles: BTreeMap<AngleKind, Box<dyn AnglePotential>>,
     /// Dihedral angles potentials
     dihedrals: BTreeMap<DihedralKind, Box<dyn DihedralPotential>>,
+    /// Improper dihedral angles potentials
+    impropers: BTreeMap<ImproperKind, Box<dyn ImproperPotential>>,
+    /// Three-body potentials, using the same ordering as angles
+    three_body: BTreeMap<AngleKind, Box<dyn ThreeBodyPotential>>,
+    /// Cross terms potentials. These potentials are not symmetric, so they
+    /// are stored in the same order as they are defined.
+    cross_terms: BTreeMap<AngleKind, Box<dyn CrossTermPotential>>,
     /// Association particles names to particle kinds
     kinds: BTreeMap<String, ParticleKind>,
 }
@@ -84,10 +106,15 @@ impl Interactions {
         Interactions {
             coulomb: None,
             globals: Vec::new(),
+            externals: Vec::new(),
+            electric_field: None,
             pairs: BTreeMap::new(),
             bonds: BTreeMap::new(),
             angles: BTreeMap::new(),
             dihedrals: BTreeMap::new(),
+            impropers: BTreeMap::new(),
+            three_body: BTreeMap::new(),
+            cross_terms: BTreeMap::new(),
             kinds: BTreeMap::new(),
         }
     }
@@ -136,6 +163,35 @@ impl Interactions {
             warn!("replaced dihedral angle potential for ({}, {}, {}, {})", i, j, k, m);
         }
     }
+
+    /// Set the improper dihedral angle interaction `potential` for atoms with
+    /// types `i`, `j`, `k`, and `m`, where `i` is the central atom.
+    pub fn set_improper(&mut self, (i, j, k, m): (&str, &str, &str, &str), potential: Box<dyn ImproperPotential>) {
+        let kind = (self.get_kind(i), self.get_kind(j), self.get_kind(k), self.get_kind(m));
+        let kind = normalize_improper(kind);
+        if self.impropers.insert(kind, potential).is_some() {
+            warn!("replaced improper dihedral angle potential for ({}, {}, {}, {})", i, j, k, m);
+        }
+    }
+
+    /// Set the three-body interaction `potential` for triplets of atoms with
+    /// types `i`, `j`, and `k`, where `j` is the central atom.
+    pub fn set_three_body(&mut self, (i, j, k): (&str, &str, &str), potential: Box<dyn ThreeBodyPotential>) {
+        let kind = normalize_angle((self.get_kind(i), self.get_kind(j), self.get_kind(k)));
+        if self.three_body.insert(kind, potential).is_some() {
+            warn!("replaced three-body potential for ({}, {}, {})", i, j, k);
+        }
+    }
+
+    /// Set the cross term interaction `potential` for angles formed by atoms
+    /// with types `i`, `j`, and `k`, where `j` is the central atom. The first
+    /// bond length given to the potential is the `i-j` one.
+    pub fn set_cross_term(&mut self, (i, j, k): (&str, &str, &str), potential: Box<dyn CrossTermPotential>) {
+        let kind = (self.get_kind(i), self.get_kind(j), self.get_kind(k));
+        if self.cross_terms.insert(kind, potential).is_some() {
+            warn!("replaced cross term potential for ({}, {}, {})", i, j, k);
+        }
+    }
 }
 
 
@@ -164,7 +220,43 @@ impl Interactions {
         self.dihedrals.get(&kind).map(|potential| &**potential)
     }
 
-    /// Get maximum cutoff from `coulomb`, `pairs` and `global` interactons.
+    /// Get the improper dihedral interactions corresponding to the
+    /// `improper`, centered on the first particle, if any exists.
+    pub fn improper(&self, improper: ImproperKind) -> Option<&dyn ImproperPotential> {
+        let kind = normalize_improper(improper);
+        self.impropers.get(&kind).map(|potential| &**potential)
+    }
+
+    /// Get the three-body interactions corresponding to the triplet `(i, j,
+    /// k)` centered on `j`, if any exists.
+    pub fn three_body(&self, triplet: AngleKind) -> Option<&dyn ThreeBodyPotential> {
+        let kind = normalize_angle(triplet);
+        self.three_body.get(&kind).map(|potential| &**potential)
+    }
+
+    /// Get the cross term interactions corresponding to the angle `(i, j, k)`
+    /// centered on `j`, if any exists. Contrary to the other interactions,
+    /// the order of `i` and `k` matters here.
+    pub fn cross_term(&self, angle: AngleKind) -> Option<&dyn CrossTermPotential> {
+        self.cross_terms.get(&angle).map(|potential| &**potential)
+    }
+
+    /// Get the maximal cutoff of all the pair interactions, if any
+    pub fn pairs_cutoff(&self) -> Option<f64> {
+        self.pairs.values().map(|potential| potential.cutoff()).fold(None, |maximum, cutoff| {
+            Some(maximum.map_or(cutoff, |maximum: f64| maximum.max(cutoff)))
+        })
+    }
+
+    /// Get the maximal cutoff of all the three-body interactions, if any
+    pub fn three_body_cutoff(&self) -> Option<f64> {
+        self.three_body.values().map(|potential| potential.cutoff()).fold(None, |maximum, cutoff| {
+            Some(maximum.map_or(cutoff, |maximum: f64| maximum.max(cutoff)))
+        })
+    }
+
+    /// Get maximum cutoff from `coulomb`, `pairs`, `three_body` and `global`
+    /// interactons.
     pub fn maximum_cutoff(&self) -> Option<f64> {
         // Coulomb potential, return cutoff
         let coulomb_cutoff = match self.coulomb {
@@ -191,6 +283,7 @@ impl Interactions {
                                .fold(f64::NAN, f64::max);
 
         maximum_cutoff = f64::max(maximum_cutoff, pairs_cutoff);
+        maximum_cutoff = f64::max(maximum_cutoff, self.three_body_cutoff().unwrap_or(f64::NAN));
         if maximum_cutoff.is_nan() {
             None
         } else {
@@ -199,11 +292,191 @@ impl Interactions {
     }
 }
 
+impl Interactions {
+    /// Get a JSON representation of all the pairs, bonds, angles and dihedral
+    /// angles interactions, with the potentials parameters in internal units.
+    pub fn to_json(&self) -> String {
+        let pairs = self.pairs.iter().map(|(&(i, j), pair)| {
+            format!(
+                "{{\"particles\": {}, {}, \"cutoff\": {}, \"shifted\": {}, \"tail_correction\": {}, \"restriction\": {}}}",
+                self.json_names(&[i, j]),
+                json_potential(pair.potential().parameters()),
+                json_number(pair.cutoff()),
+                pair.is_shifted(),
+                pair.tail_corrections(),
+                json_restriction(pair.restriction()),
+            )
+        }).collect::<Vec<_>>();
+
+        let bonds = self.bonds.iter().map(|(&(i, j), bond)| {
+            format!(
+                "{{\"particles\": {}, {}}}",
+                self.json_names(&[i, j]),
+                json_potential(bond.parameters())
+            )
+        }).collect::<Vec<_>>();
+
+        let angles = self.angles.iter().map(|(&(i, j, k), angle)| {
+            format!(
+                "{{\"particles\": {}, {}}}",
+                self.json_names(&[i, j, k]),
+                json_potential(angle.parameters())
+            )
+        }).collect::<Vec<_>>();
+
+        let dihedrals = self.dihedrals.iter().map(|(&(i, j, k, m), dihedral)| {
+            format!(
+                "{{\"particles\": {}, {}}}",
+                self.json_names(&[i, j, k, m]),
+                json_potential(dihedral.parameters())
+            )
+        }).collect::<Vec<_>>();
+
+        format!(
+            "{{\n    \"pairs\": {},\n    \"bonds\": {},\n    \"angles\": {},\n    \"dihedrals\": {}\n}}\n",
+            json_list(&pairs),
+            json_list(&bonds),
+            json_list(&angles),
+            json_list(&dihedrals),
+        )
+    }
+
+    /// Get the JSON list of particles names associated with `kinds`
+    fn json_names(&self, kinds: &[ParticleKind]) -> String {
+        let names = kinds.iter().map(|&kind| json_string(self.name(kind))).collect::<Vec<_>>();
+        format!("[{}]", names.join(", "))
+    }
+}
+
+impl Interactions {
+    /// Get the particle name associated with `kind`, or an empty string if
+    /// this kind is not known.
+    fn name(&self, kind: ParticleKind) -> &str {
+        self.kinds.iter()
+                  .find(|&(_, &other)| other == kind)
+                  .map_or("", |(name, _)| name.as_str())
+    }
+
+    /// Get an iterator over all the pair interactions, together with the
+    /// names of the associated particles.
+    pub fn all_pairs(&self) -> impl Iterator<Item = ((&str, &str), &PairInteraction)> + '_ {
+        self.pairs.iter().map(move |(&(i, j), pair)| {
+            ((self.name(i), self.name(j)), pair)
+        })
+    }
+
+    /// Get an iterator over all the bond interactions, together with the
+    /// names of the associated particles.
+    pub fn all_bonds(&self) -> impl Iterator<Item = ((&str, &str), &dyn BondPotential)> + '_ {
+        self.bonds.iter().map(move |(&(i, j), bond)| {
+            ((self.name(i), self.name(j)), &**bond)
+        })
+    }
+
+    /// Get an iterator over all the angle interactions, together with the
+    /// names of the associated particles.
+    pub fn all_angles(&self) -> impl Iterator<Item = ((&str, &str, &str), &dyn AnglePotential)> + '_ {
+        self.angles.iter().map(move |(&(i, j, k), angle)| {
+            ((self.name(i), self.name(j), self.name(k)), &**angle)
+        })
+    }
+
+    /// Get an iterator over all the dihedral angles interactions, together
+    /// with the names of the associated particles.
+    pub fn all_dihedrals(&self) -> impl Iterator<Item = ((&str, &str, &str, &str), &dyn DihedralPotential)> + '_ {
+        self.dihedrals.iter().map(move |(&(i, j, k, m), dihedral)| {
+            ((self.name(i), self.name(j), self.name(k), self.name(m)), &**dihedral)
+        })
+    }
+
+    /// Get an iterator over all the improper dihedral angles interactions,
+    /// together with the names of the associated particles. The central
+    /// particle is the first one.
+    pub fn all_impropers(&self) -> impl Iterator<Item = ((&str, &str, &str, &str), &dyn ImproperPotential)> + '_ {
+        self.impropers.iter().map(move |(&(i, j, k, m), improper)| {
+            ((self.name(i), self.name(j), self.name(k), self.name(m)), &**improper)
+        })
+    }
+
+    /// Get an iterator over all the cross terms interactions, together with
+    /// the names of the associated particles.
+    pub fn all_cross_terms(&self) -> impl Iterator<Item = ((&str, &str, &str), &dyn CrossTermPotential)> + '_ {
+        self.cross_terms.iter().map(move |(&(i, j, k), cross_term)| {
+            ((self.name(i), self.name(j), self.name(k)), &**cross_term)
+        })
+    }
+}
+
+/// Format a list of JSON values, one value by line
+fn json_list(values: &[String]) -> String {
+    if values.is_empty() {
+        String::from("[]")
+    } else {
+        format!("[\n        {}\n    ]", values.join(",\n        "))
+    }
+}
+
+/// Format a string as a JSON string, escaping special characters
+fn json_string(value: &str) -> String {
+    let mut string = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '"' => string.push_str("\\\""),
+            '\\' => string.push_str("\\\\"),
+            c if (c as u32) < 0x20 => string.push_str(&format!("\\u{:04x}", c as u32)),
+            c => string.push(c),
+        }
+    }
+    string.push('"');
+    return string;
+}
+
+/// Format a number as JSON. JSON does not support infinite or NaN values,
+/// which are represented as `null`.
+fn json_number(value: f64) -> String {
+    if value.is_finite() {
+        format!("{:?}", value)
+    } else {
+        String::from("null")
+    }
+}
+
+/// Format the type and parameters of a potential as JSON fields
+fn json_potential(parameters: Option<PotentialParameters>) -> String {
+    match parameters {
+        Some(parameters) => {
+            let values = parameters.values.iter().map(|&(name, value)| {
+                format!("{}: {}", json_string(name), json_number(value))
+            }).collect::<Vec<_>>();
+            format!(
+                "\"type\": {}, \"parameters\": {{{}}}",
+                json_string(parameters.name),
+                values.join(", ")
+            )
+        }
+        None => String::from("\"type\": null, \"parameters\": {}"),
+    }
+}
+
+/// Format a pair restriction as JSON, using the same names as input files
+fn json_restriction(restriction: PairRestriction) -> String {
+    match restriction {
+        PairRestriction::None => String::from("\"none\""),
+        PairRestriction::IntraMolecular => String::from("\"intramolecular\""),
+        PairRestriction::InterMolecular => String::from("\"intermolecular\""),
+        PairRestriction::Exclude12 => String::from("\"exclude12\""),
+        PairRestriction::Exclude13 => String::from("\"exclude13\""),
+        PairRestriction::Exclude14 => String::from("\"exclude14\""),
+        PairRestriction::Scale14(scaling) => format!("{{\"scale14\": {}}}", json_number(scaling)),
+        PairRestriction::ExcludeBonded(depth) => format!("{{\"exclude_bonded\": {}}}", depth),
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
 
-    use crate::{NullPotential, PairInteraction, Wolf};
+    use crate::{BondAngle, NullPotential, PairInteraction, StillingerWeber, Wolf};
     use crate::ParticleKind as Kind;
 
     #[test]
@@ -329,10 +602,46 @@ mod test {
         );
     }
 
+    #[test]
+    fn json() {
+        use crate::{Harmonic, LennardJones, Torsion};
+
+        let mut interactions = Interactions::new();
+        assert_eq!(
+            interactions.to_json(),
+            "{\n    \"pairs\": [],\n    \"bonds\": [],\n    \"angles\": [],\n    \"dihedrals\": []\n}\n"
+        );
+
+        let mut pair = PairInteraction::shifted(Box::new(LennardJones { sigma: 3.4, epsilon: 0.5 }), 10.0);
+        pair.set_restriction(PairRestriction::Scale14(0.5));
+        interactions.set_pair(("Ar", "Ar"), pair);
+        interactions.set_bond(("C", "H"), Box::new(Harmonic { k: 40.0, x0: 1.1 }));
+        interactions.set_angle(("H", "C", "H"), Box::new(NullPotential));
+        interactions.set_dihedral(("H", "C", "C", "H"), Box::new(Torsion { k: 2.0, delta: 0.0, n: 3 }));
+
+        let expected = "{
+    \"pairs\": [
+        {\"particles\": [\"Ar\", \"Ar\"], \"type\": \"lj\", \"parameters\": {\"sigma\": 3.4, \"epsilon\": 0.5}, \"cutoff\": 10.0, \"shifted\": true, \"tail_correction\": false, \"restriction\": {\"scale14\": 0.5}}
+    ],
+    \"bonds\": [
+        {\"particles\": [\"C\", \"H\"], \"type\": \"harmonic\", \"parameters\": {\"k\": 40.0, \"x0\": 1.1}}
+    ],
+    \"angles\": [
+        {\"particles\": [\"H\", \"C\", \"H\"], \"type\": \"null\", \"parameters\": {}}
+    ],
+    \"dihedrals\": [
+        {\"particles\": [\"H\", \"C\", \"C\", \"H\"], \"type\": \"torsion\", \"parameters\": {\"k\": 2.0, \"delta\": 0.0, \"n\": 3.0}}
+    ]
+}
+";
+        assert_eq!(interactions.to_json(), expected);
+    }
+
     #[test]
     fn pairs() {
         let mut interactions = Interactions::new();
         let pair = PairInteraction::new(Box::new(NullPotential), 0.0);
+        assert_eq!(interactions.pairs_cutoff(), None);
         interactions.set_pair(("A", "B"), pair.clone());
         assert!(interactions.pair((Kind(0), Kind(1))).is_some());
         assert!(interactions.pair((Kind(1), Kind(0))).is_some());
@@ -343,6 +652,9 @@ mod test {
 
         // 'out of bounds' kinds
         assert!(interactions.pair((Kind(55), Kind(55))).is_none());
+
+        interactions.set_pair(("B", "B"), PairInteraction::new(Box::new(NullPotential), 8.0));
+        assert_eq!(interactions.pairs_cutoff(), Some(8.0));
     }
 
     #[test]
@@ -408,6 +720,85 @@ mod test {
         assert!(interactions.dihedral((Kind(55), Kind(55), Kind(55), Kind(55))).is_none());
     }
 
+    #[test]
+    fn impropers() {
+        let mut interactions = Interactions::new();
+
+        interactions.set_improper(("A", "B", "C", "D"), Box::new(NullPotential));
+        assert!(interactions.improper((Kind(0), Kind(1), Kind(2), Kind(3))).is_some());
+        assert!(interactions.improper((Kind(0), Kind(3), Kind(1), Kind(2))).is_some());
+        assert!(interactions.improper((Kind(0), Kind(2), Kind(3), Kind(1))).is_some());
+        // The central atom is different
+        assert!(interactions.improper((Kind(1), Kind(0), Kind(2), Kind(3))).is_none());
+
+        interactions.set_improper(("C", "O", "O", "N"), Box::new(NullPotential));
+        assert!(interactions.improper((Kind(2), Kind(4), Kind(4), Kind(5))).is_some());
+        assert!(interactions.improper((Kind(2), Kind(4), Kind(5), Kind(4))).is_some());
+        assert!(interactions.improper((Kind(2), Kind(5), Kind(4), Kind(4))).is_some());
+        assert!(interactions.improper((Kind(4), Kind(2), Kind(4), Kind(5))).is_none());
+
+        // 'out of bounds' kinds
+        assert!(interactions.improper((Kind(55), Kind(55), Kind(55), Kind(55))).is_none());
+    }
+
+    #[test]
+    fn three_body() {
+        let mut interactions = Interactions::new();
+        let potential = StillingerWeber::silicon(1.0, 2.0);
+        assert_eq!(interactions.three_body_cutoff(), None);
+
+        interactions.set_three_body(("A", "B", "C"), Box::new(potential));
+        assert!(interactions.three_body((Kind(0), Kind(1), Kind(2))).is_some());
+        assert!(interactions.three_body((Kind(2), Kind(1), Kind(0))).is_some());
+        assert!(interactions.three_body((Kind(1), Kind(0), Kind(2))).is_none());
+        assert_eq!(interactions.three_body_cutoff(), Some(3.6));
+
+        interactions.set_three_body(("A", "A", "A"), Box::new(StillingerWeber::silicon(1.0, 3.0)));
+        assert_eq!(interactions.three_body_cutoff(), Some(1.8 * 3.0));
+        assert_eq!(interactions.maximum_cutoff(), Some(1.8 * 3.0));
+    }
+
+    #[test]
+    fn cross_terms() {
+        let mut interactions = Interactions::new();
+        let potential = BondAngle { k1: 1.0, k2: 2.0, r1: 1.0, r2: 1.2, theta0: 2.0 };
+
+        interactions.set_cross_term(("A", "B", "C"), Box::new(potential));
+        assert!(interactions.cross_term((Kind(0), Kind(1), Kind(2))).is_some());
+        assert!(interactions.cross_term((Kind(2), Kind(1), Kind(0))).is_none());
+        assert!(interactions.cross_term((Kind(1), Kind(0), Kind(2))).is_none());
+
+        // 'out of bounds' kinds
+        assert!(interactions.cross_term((Kind(55), Kind(55), Kind(55))).is_none());
+    }
+
+    #[test]
+    fn iterators() {
+        let mut interactions = Interactions::new();
+        assert_eq!(interactions.all_pairs().count(), 0);
+        assert_eq!(interactions.all_bonds().count(), 0);
+
+        interactions.set_pair(("B", "A"), PairInteraction::new(Box::new(NullPotential), 3.0));
+        interactions.set_pair(("A", "A"), PairInteraction::new(Box::new(NullPotential), 3.0));
+        interactions.set_bond(("A", "B"), Box::new(NullPotential));
+        interactions.set_angle(("A", "B", "C"), Box::new(NullPotential));
+        interactions.set_dihedral(("A", "B", "C", "D"), Box::new(NullPotential));
+        interactions.set_improper(("C", "D", "B", "A"), Box::new(NullPotential));
+
+        let pairs = interactions.all_pairs().map(|(names, _)| names).collect::<Vec<_>>();
+        assert_eq!(pairs, vec![("B", "A"), ("A", "A")]);
+
+        let bonds = interactions.all_bonds().map(|(names, _)| names).collect::<Vec<_>>();
+        assert_eq!(bonds, vec![("B", "A")]);
+        let angles = interactions.all_angles().map(|(names, _)| names).collect::<Vec<_>>();
+        assert_eq!(angles, vec![("A", "B", "C")]);
+        let dihedrals = interactions.all_dihedrals().map(|(names, _)| names).collect::<Vec<_>>();
+        assert_eq!(dihedrals, vec![("A", "B", "C", "D")]);
+        let impropers = interactions.all_impropers().map(|(names, _)| names).collect::<Vec<_>>();
+        assert_eq!(impropers, vec![("C", "B", "A", "D")]);
+        assert_eq!(interactions.all_cross_terms().count(), 0);
+    }
+
     #[test]
     fn test_maximum_cutoff() {
         let mut interactions = Interactions::new();
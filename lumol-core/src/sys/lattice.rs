@@ -0,0 +1,113 @@
+// Lumol, an extensible molecular simulation engine
+// Copyright (C) Lumol's contributors — BSD license
+
+use crate::{Molecule, Particle, System, UnitCell, Vector3D};
+
+/// Cubic Bravais lattices, used to build crystals with
+/// [`System::from_lattice`](struct.System.html#method.from_lattice).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Lattice {
+    /// Simple cubic lattice, with one particle per conventional cell
+    SimpleCubic,
+    /// Body-centered cubic lattice, with two particles per conventional cell
+    BodyCenteredCubic,
+    /// Face-centered cubic lattice, with four particles per conventional cell
+    FaceCenteredCubic,
+}
+
+impl Lattice {
+    /// Get the fractional coordinates of the particles in the conventional
+    /// cubic cell of this lattice
+    fn basis(self) -> &'static [[f64; 3]] {
+        match self {
+            Lattice::SimpleCubic => &[[0.0, 0.0, 0.0]],
+            Lattice::BodyCenteredCubic => &[[0.0, 0.0, 0.0], [0.5, 0.5, 0.5]],
+            Lattice::FaceCenteredCubic => &[
+                [0.0, 0.0, 0.0],
+                [0.5, 0.5, 0.0],
+                [0.5, 0.0, 0.5],
+                [0.0, 0.5, 0.5],
+            ],
+        }
+    }
+}
+
+impl System {
+    /// Create a crystal of particles named `name`, arranged on the given
+    /// `lattice` with a conventional cubic cell of side `a`. The conventional
+    /// cell is repeated `nx`, `ny` and `nz` times along the x, y and z axis,
+    /// and each particle is a separated molecule.
+    ///
+    /// # Panics
+    ///
+    /// If `a` is not positive, or if any of `nx`, `ny` or `nz` is zero.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use lumol_core::{Lattice, System, UnitCell};
+    /// let system = System::from_lattice(Lattice::FaceCenteredCubic, 5.26, 3, 3, 3, "Ar");
+    /// assert_eq!(system.size(), 108);
+    /// assert_eq!(system.cell, UnitCell::cubic(3.0 * 5.26));
+    /// ```
+    pub fn from_lattice(
+        lattice: Lattice,
+        a: f64,
+        nx: usize,
+        ny: usize,
+        nz: usize,
+        name: &str,
+    ) -> System {
+        assert!(a > 0.0, "the lattice parameter must be positive in System::from_lattice");
+        let mut cell = System::with_cell(UnitCell::cubic(a));
+        for position in lattice.basis() {
+            let position = a * Vector3D::from(*position);
+            cell.add_molecule(Molecule::new(Particle::with_position(name, position)));
+        }
+        return cell.replicate(nx, ny, nz);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    /// Get the smallest distance between two particles in the `system`
+    fn nearest_neighbors(system: &System) -> f64 {
+        let mut min = f64::INFINITY;
+        for i in 0..system.size() {
+            for j in (i + 1)..system.size() {
+                min = f64::min(min, system.distance(i, j));
+            }
+        }
+        return min;
+    }
+
+    #[test]
+    fn simple_cubic() {
+        let system = System::from_lattice(Lattice::SimpleCubic, 3.0, 2, 3, 4, "Po");
+        assert_eq!(system.size(), 24);
+        assert_eq!(system.cell, UnitCell::ortho(6.0, 9.0, 12.0));
+        assert_relative_eq!(nearest_neighbors(&system), 3.0, max_relative = 1e-12);
+    }
+
+    #[test]
+    fn body_centered_cubic() {
+        let system = System::from_lattice(Lattice::BodyCenteredCubic, 2.87, 2, 2, 2, "Fe");
+        assert_eq!(system.size(), 16);
+        assert_eq!(system.molecules().count(), 16);
+        assert_relative_eq!(nearest_neighbors(&system), 2.87 * f64::sqrt(3.0) / 2.0, max_relative = 1e-12);
+    }
+
+    #[test]
+    fn face_centered_cubic() {
+        let system = System::from_lattice(Lattice::FaceCenteredCubic, 4.05, 1, 1, 1, "Al");
+        assert_eq!(system.size(), 4);
+
+        let system = System::from_lattice(Lattice::FaceCenteredCubic, 4.05, 3, 2, 2, "Al");
+        assert_eq!(system.size(), 4 * 12);
+        assert!(system.particles().name.iter().all(|name| name == "Al"));
+        assert_relative_eq!(nearest_neighbors(&system), 4.05 / f64::sqrt(2.0), max_relative = 1e-12);
+    }
+}
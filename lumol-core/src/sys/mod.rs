@@ -10,9 +10,27 @@ mod system;
 pub use self::system::System;
 pub use self::system::DegreesOfFreedom;
 
+mod builder;
+pub use self::builder::SystemBuilder;
+
+mod lattice;
+pub use self::lattice::Lattice;
+
+mod selection;
+pub use self::selection::{Selection, SelectionError};
+
 mod interactions;
 pub use self::interactions::Interactions;
 
+mod virtual_sites;
+pub use self::virtual_sites::VirtualSite;
+
+mod drude;
+pub use self::drude::DrudeParticle;
+
+mod checkpoint;
+mod openmm;
+
 mod energy;
 pub use self::energy::EnergyEvaluator;
 
@@ -21,7 +39,7 @@ pub use self::cache::EnergyCache;
 
 mod chfl;
 pub use chemfiles::Error as TrajectoryError;
-pub use self::chfl::{OpenMode, Trajectory, TrajectoryBuilder};
+pub use self::chfl::{FramesWithStride, OpenMode, Trajectory, TrajectoryBuilder};
 pub use self::chfl::read_molecule;
 
 pub mod compute;
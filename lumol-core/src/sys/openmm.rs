@@ -0,0 +1,545 @@
+// Lumol, an extensible molecular simulation engine
+// Copyright (C) Lumol's contributors — BSD license
+
+//! Export of systems to the XML format used by OpenMM to serialize `System`
+//! objects.
+//!
+//! The exported file contains the unit cell, the particles masses, and the
+//! forces acting on the particles. Only a subset of the interactions have an
+//! equivalent in OpenMM and can be exported:
+//!
+//! - Lennard-Jones pair potentials, using Lorentz-Berthelot mixing rules;
+//! - harmonic bonds and angles;
+//! - torsion dihedral angles;
+//! - Ewald summation for electrostatic interactions, exported as PME.
+//!
+//! All values are converted to OpenMM units: nm, kJ/mol, radians, Dalton
+//! and elementary charges.
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::Path;
+
+use crate::units;
+use crate::{PairRestriction, PotentialParameters, RestrictionInfo};
+use crate::{System, Vector3D};
+
+/// Tolerance when checking that the pair potentials follow the
+/// Lorentz-Berthelot mixing rules
+const MIXING_TOLERANCE: f64 = 1e-6;
+
+/// Nonbonded methods, using the same values as OpenMM `NonbondedForce`
+const NO_CUTOFF: usize = 0;
+const CUTOFF_NON_PERIODIC: usize = 1;
+const CUTOFF_PERIODIC: usize = 2;
+const PME: usize = 4;
+
+impl System {
+    /// Save the topology and the interactions of this system to the file at
+    /// `path`, using the XML format OpenMM uses to serialize `System`
+    /// objects. The positions and velocities of the particles are not saved.
+    ///
+    /// Charges are only exported when the system uses a coulombic solver,
+    /// and OpenMM does not shift the pair potentials at the cutoff: the
+    /// energy of shifted potentials will differ by a constant.
+    ///
+    /// # Errors
+    ///
+    /// If the file can not be written, or if some of the interactions in this
+    /// system can not be represented in OpenMM.
+    pub fn save_openmm_xml<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        // Build the whole file first to avoid writing partial files in case
+        // of error.
+        let xml = self.openmm_xml()?;
+        let mut file = File::create(path)?;
+        file.write_all(xml.as_bytes())?;
+        return Ok(());
+    }
+
+    /// Get the OpenMM XML representation of this system
+    fn openmm_xml(&self) -> io::Result<String> {
+        if !self.virtual_sites().is_empty() {
+            return Err(invalid_data("virtual sites can not be exported to OpenMM"));
+        }
+        if !self.drude_particles().is_empty() {
+            return Err(invalid_data("Drude particles can not be exported to OpenMM"));
+        }
+        if self.interactions().three_body_cutoff().is_some() {
+            return Err(invalid_data("three-body potentials can not be exported to OpenMM"));
+        }
+        if self.cross_term_interactions().next().is_some() {
+            return Err(invalid_data("cross terms potentials can not be exported to OpenMM"));
+        }
+        let interactions = self.interactions();
+        if !interactions.globals.is_empty() || !interactions.externals.is_empty() || interactions.electric_field.is_some() {
+            return Err(invalid_data("global and external potentials can not be exported to OpenMM"));
+        }
+
+        let mut xml = String::from("<?xml version=\"1.0\" ?>\n");
+        xml.push_str("<System openmmVersion=\"7.0\" type=\"System\" version=\"1\">\n");
+        xml.push_str(&self.openmm_box());
+
+        xml.push_str("    <Particles>\n");
+        for &mass in self.particles().mass {
+            xml.push_str(&format!("        <Particle mass=\"{:?}\"/>\n", mass));
+        }
+        xml.push_str("    </Particles>\n");
+        xml.push_str("    <Constraints/>\n");
+
+        xml.push_str("    <Forces>\n");
+        xml.push_str(&self.openmm_bonds()?);
+        xml.push_str(&self.openmm_angles()?);
+        xml.push_str(&self.openmm_torsions()?);
+        xml.push_str(&self.openmm_nonbonded()?);
+        xml.push_str("    </Forces>\n");
+        xml.push_str("</System>\n");
+        return Ok(xml);
+    }
+
+    /// Get the `PeriodicBoxVectors` element for this system
+    fn openmm_box(&self) -> String {
+        let vectors = if self.cell.is_infinite() {
+            // OpenMM always needs box vectors, but they are not used by
+            // non-periodic methods.
+            [Vector3D::new(20.0, 0.0, 0.0), Vector3D::new(0.0, 20.0, 0.0), Vector3D::new(0.0, 0.0, 20.0)]
+        } else {
+            let matrix = self.cell.matrix();
+            [
+                Vector3D::new(matrix[0][0], matrix[1][0], matrix[2][0]),
+                Vector3D::new(matrix[0][1], matrix[1][1], matrix[2][1]),
+                Vector3D::new(matrix[0][2], matrix[1][2], matrix[2][2]),
+            ]
+        };
+
+        let mut xml = String::from("    <PeriodicBoxVectors>\n");
+        for (name, vector) in ["A", "B", "C"].iter().zip(vectors.iter()) {
+            xml.push_str(&format!(
+                "        <{} x=\"{:?}\" y=\"{:?}\" z=\"{:?}\"/>\n",
+                name, nm(vector[0]), nm(vector[1]), nm(vector[2])
+            ));
+        }
+        xml.push_str("    </PeriodicBoxVectors>\n");
+        return xml;
+    }
+
+    /// Get the `HarmonicBondForce` for this system
+    fn openmm_bonds(&self) -> io::Result<String> {
+        let mut bonds = Vec::new();
+        for molecule in self.molecules() {
+            // Sort the bonds to get a reproducible output
+            let mut molecule_bonds = molecule.bonds().iter().map(|bond| (bond.i(), bond.j())).collect::<Vec<_>>();
+            molecule_bonds.sort_unstable();
+            for (i, j) in molecule_bonds {
+                if let Some(potential) = self.bond_potential(i, j) {
+                    let parameters = harmonic(potential.parameters(), "bond")?;
+                    bonds.push(format!(
+                        "<Bond p1=\"{}\" p2=\"{}\" d=\"{:?}\" k=\"{:?}\"/>",
+                        i, j, nm(parameters.1), units::to(parameters.0, "kJ/mol/nm^2").expect("bad unit"),
+                    ));
+                }
+            }
+        }
+        return Ok(force("HarmonicBondForce", "Bonds", bonds));
+    }
+
+    /// Get the `HarmonicAngleForce` for this system
+    fn openmm_angles(&self) -> io::Result<String> {
+        let mut angles = Vec::new();
+        for molecule in self.molecules() {
+            let mut molecule_angles = molecule.angles().iter().map(|angle| (angle.i(), angle.j(), angle.k())).collect::<Vec<_>>();
+            molecule_angles.sort_unstable();
+            for (i, j, k) in molecule_angles {
+                if let Some(potential) = self.angle_potential(i, j, k) {
+                    let parameters = harmonic(potential.parameters(), "angle")?;
+                    angles.push(format!(
+                        "<Angle p1=\"{}\" p2=\"{}\" p3=\"{}\" a=\"{:?}\" k=\"{:?}\"/>",
+                        i, j, k, parameters.1, kj_mol(parameters.0),
+                    ));
+                }
+            }
+        }
+        return Ok(force("HarmonicAngleForce", "Angles", angles));
+    }
+
+    /// Get the `PeriodicTorsionForce` for this system
+    fn openmm_torsions(&self) -> io::Result<String> {
+        let mut torsions = Vec::new();
+        for molecule in self.molecules() {
+            let mut dihedrals = molecule.dihedrals().iter().map(|dihedral| {
+                (dihedral.i(), dihedral.j(), dihedral.k(), dihedral.m())
+            }).collect::<Vec<_>>();
+            dihedrals.sort_unstable();
+            for (i, j, k, m) in dihedrals {
+                if let Some(potential) = self.dihedral_potential(i, j, k, m) {
+                    let parameters = potential.parameters();
+                    let scaling = scaling(&parameters);
+                    match parameters {
+                        Some(ref parameters) if parameters.name == "torsion" => {
+                            torsions.push(format!(
+                                "<Torsion p1=\"{}\" p2=\"{}\" p3=\"{}\" p4=\"{}\" periodicity=\"{}\" phase=\"{:?}\" k=\"{:?}\"/>",
+                                i, j, k, m,
+                                value(parameters, "n") as usize,
+                                value(parameters, "delta"),
+                                kj_mol(scaling * value(parameters, "k")),
+                            ));
+                        }
+                        _ => return Err(unsupported(&parameters, "dihedral")),
+                    }
+                }
+            }
+
+            for improper in molecule.impropers() {
+                let (i, j, k, m) = (improper.i(), improper.j(), improper.k(), improper.m());
+                if self.improper_potential(i, j, k, m).is_some() {
+                    return Err(invalid_data("improper dihedral angles potentials can not be exported to OpenMM"));
+                }
+            }
+        }
+        return Ok(force("PeriodicTorsionForce", "Torsions", torsions));
+    }
+
+    /// Get the `NonbondedForce` for this system, containing the
+    /// Lennard-Jones and electrostatic interactions.
+    fn openmm_nonbonded(&self) -> io::Result<String> {
+        let lennard_jones = self.openmm_lennard_jones()?;
+        let pairs = self.pair_interactions().collect::<Vec<_>>();
+
+        let mut pairs_cutoff = None;
+        for &(names, pair) in &pairs {
+            if pair.restriction() == PairRestriction::IntraMolecular {
+                return Err(invalid_data(format!(
+                    "intra-molecular restriction for ({}, {}) can not be exported to OpenMM", names.0, names.1
+                )));
+            }
+            match pairs_cutoff {
+                None => pairs_cutoff = Some(pair.cutoff()),
+                Some(cutoff) if cutoff != pair.cutoff() => {
+                    return Err(invalid_data("pair potentials with different cutoffs can not be exported to OpenMM"));
+                }
+                Some(_) => {}
+            }
+        }
+        let dispersion_correction = pairs.iter().any(|(_, pair)| pair.tail_corrections());
+
+        let coulomb = self.interactions().coulomb.as_ref();
+        let (method, cutoff, alpha) = match coulomb.map(|coulomb| coulomb.parameters()) {
+            None => {
+                let method = match pairs_cutoff {
+                    None => NO_CUTOFF,
+                    Some(_) if self.cell.is_infinite() => CUTOFF_NON_PERIODIC,
+                    Some(_) => CUTOFF_PERIODIC,
+                };
+                (method, pairs_cutoff.unwrap_or(10.0), 0.0)
+            }
+            Some(Some(ref parameters)) if parameters.name == "ewald" => {
+                if self.cell.is_infinite() {
+                    return Err(invalid_data("Ewald summation in an infinite cell can not be exported to OpenMM"));
+                }
+                let cutoff = value(parameters, "cutoff");
+                if pairs_cutoff.is_some() && pairs_cutoff != Some(cutoff) {
+                    return Err(invalid_data(
+                        "pair potentials and Ewald summation with different cutoffs can not be exported to OpenMM"
+                    ));
+                }
+                (PME, cutoff, value(parameters, "alpha"))
+            }
+            Some(parameters) => return Err(unsupported(&parameters, "coulombic")),
+        };
+
+        let mut particles = Vec::new();
+        for (i, &charge) in self.particles().charge.iter().enumerate() {
+            let charge = if coulomb.is_some() { charge } else { 0.0 };
+            let (sigma, epsilon) = lennard_jones[self.particles().name[i].as_str()];
+            particles.push(format!(
+                "<Particle q=\"{:?}\" sig=\"{:?}\" eps=\"{:?}\"/>",
+                charge, nm(sigma), kj_mol(epsilon)
+            ));
+        }
+
+        // Exclusions and scaling of interactions inside molecules
+        let mut exceptions = Vec::new();
+        let not_restricted = RestrictionInfo { excluded: false, scaling: 1.0 };
+        for molecule in self.molecules() {
+            for i in molecule.indexes() {
+                for j in (i + 1)..molecule.end() {
+                    let pair = self.pair_potential(i, j);
+                    let pair = pair.map_or(not_restricted, |pair| pair.restriction().information_between(self, i, j));
+                    let electrostatic = coulomb.map_or(not_restricted, |coulomb| coulomb.restriction().information_between(self, i, j));
+                    let restricted = pair.excluded || electrostatic.excluded;
+                    if !restricted && pair.scaling == 1.0 && electrostatic.scaling == 1.0 {
+                        continue;
+                    }
+
+                    let particles = self.particles();
+                    let charge = if coulomb.is_some() && !electrostatic.excluded {
+                        electrostatic.scaling * particles.charge[i] * particles.charge[j]
+                    } else {
+                        0.0
+                    };
+                    let (sigma_i, epsilon_i) = lennard_jones[particles.name[i].as_str()];
+                    let (sigma_j, epsilon_j) = lennard_jones[particles.name[j].as_str()];
+                    let epsilon = if pair.excluded {
+                        0.0
+                    } else {
+                        pair.scaling * f64::sqrt(epsilon_i * epsilon_j)
+                    };
+                    exceptions.push(format!(
+                        "<Exception p1=\"{}\" p2=\"{}\" q=\"{:?}\" sig=\"{:?}\" eps=\"{:?}\"/>",
+                        i, j, charge, nm(0.5 * (sigma_i + sigma_j)), kj_mol(epsilon)
+                    ));
+                }
+            }
+        }
+
+        let mut xml = format!(
+            "        <Force type=\"NonbondedForce\" version=\"2\" forceGroup=\"0\" recipForceGroup=\"-1\" \
+            method=\"{}\" cutoff=\"{:?}\" alpha=\"{:?}\" nx=\"0\" ny=\"0\" nz=\"0\" ewaldTolerance=\"0.0005\" \
+            rfDielectric=\"78.3\" useSwitchingFunction=\"0\" switchingDistance=\"-1\" dispersionCorrection=\"{}\">\n",
+            method, nm(cutoff), alpha / nm(1.0), dispersion_correction as usize,
+        );
+        xml.push_str(&elements("Particles", particles));
+        xml.push_str(&elements("Exceptions", exceptions));
+        xml.push_str("        </Force>\n");
+        return Ok(xml);
+    }
+
+    /// Get the Lennard-Jones `(sigma, epsilon)` parameters for all the
+    /// particles names in the system, checking that the pair potentials
+    /// follow the Lorentz-Berthelot mixing rules. Particles without
+    /// Lennard-Jones interactions get `sigma = 1 nm` and `epsilon = 0`.
+    fn openmm_lennard_jones(&self) -> io::Result<BTreeMap<&str, (f64, f64)>> {
+        let mut names = self.particles().name.iter().map(String::as_str).collect::<Vec<_>>();
+        names.sort_unstable();
+        names.dedup();
+
+        let pair_parameters = |i: &str, j: &str| -> io::Result<Option<(f64, f64)>> {
+            let pair = self.pair_interactions().find(|&((a, b), _)| (a, b) == (i, j) || (a, b) == (j, i));
+            let pair = match pair {
+                Some((_, pair)) => pair,
+                None => return Ok(None),
+            };
+            let parameters = pair.potential().parameters();
+            let scaling = scaling(&parameters);
+            match parameters {
+                Some(ref parameters) if parameters.name == "lj" => {
+                    Ok(Some((value(parameters, "sigma"), scaling * value(parameters, "epsilon"))))
+                }
+                _ => Err(unsupported(&parameters, "pair")),
+            }
+        };
+
+        let mut lennard_jones = BTreeMap::new();
+        for &name in &names {
+            let parameters = pair_parameters(name, name)?;
+            let _ = lennard_jones.insert(name, parameters);
+        }
+
+        for (index, &i) in names.iter().enumerate() {
+            for &j in &names[index + 1..] {
+                let mixed = match (lennard_jones[i], lennard_jones[j]) {
+                    (Some((sigma_i, epsilon_i)), Some((sigma_j, epsilon_j))) => {
+                        Some((0.5 * (sigma_i + sigma_j), f64::sqrt(epsilon_i * epsilon_j)))
+                    }
+                    _ => None,
+                };
+                let follows_mixing_rules = match (pair_parameters(i, j)?, mixed) {
+                    (None, None) => true,
+                    (Some((sigma, epsilon)), Some((mixed_sigma, mixed_epsilon))) => {
+                        f64::abs(sigma - mixed_sigma) <= MIXING_TOLERANCE * mixed_sigma &&
+                        f64::abs(epsilon - mixed_epsilon) <= MIXING_TOLERANCE * mixed_epsilon
+                    }
+                    _ => false,
+                };
+                if !follows_mixing_rules {
+                    return Err(invalid_data(format!(
+                        "the pair potential for ({}, {}) does not follow Lorentz-Berthelot mixing rules, \
+                        and can not be exported to OpenMM", i, j
+                    )));
+                }
+            }
+        }
+
+        let default = (units::from(1.0, "nm").expect("bad unit"), 0.0);
+        return Ok(lennard_jones.into_iter().map(|(name, parameters)| (name, parameters.unwrap_or(default))).collect());
+    }
+}
+
+/// Get the `(k, x0)` parameters of an harmonic bond or angle potential
+fn harmonic(parameters: Option<PotentialParameters>, kind: &str) -> io::Result<(f64, f64)> {
+    let scaling = scaling(&parameters);
+    match parameters {
+        Some(ref parameters) if parameters.name == "harmonic" => {
+            Ok((scaling * value(parameters, "k"), value(parameters, "x0")))
+        }
+        _ => Err(unsupported(&parameters, kind)),
+    }
+}
+
+/// Get the value of the parameter with the given `name`
+fn value(parameters: &PotentialParameters, name: &str) -> f64 {
+    parameters.values.iter()
+                     .find(|&&(parameter, _)| parameter == name)
+                     .map(|&(_, value)| value)
+                     .expect("missing potential parameter")
+}
+
+/// Get the scaling factor of a potential, as added by `Scaled` potentials
+fn scaling(parameters: &Option<PotentialParameters>) -> f64 {
+    parameters.as_ref()
+              .and_then(|parameters| parameters.values.iter().find(|&&(name, _)| name == "scaling"))
+              .map_or(1.0, |&(_, value)| value)
+}
+
+/// Format a `Force` element containing a list of `elements`
+fn force(typ: &str, name: &str, elements_list: Vec<String>) -> String {
+    let mut xml = format!("        <Force type=\"{}\" version=\"2\" forceGroup=\"0\" usesPeriodic=\"0\">\n", typ);
+    xml.push_str(&elements(name, elements_list));
+    xml.push_str("        </Force>\n");
+    return xml;
+}
+
+/// Format an element named `name` containing a list of `elements`
+fn elements(name: &str, elements: Vec<String>) -> String {
+    if elements.is_empty() {
+        return format!("            <{}/>\n", name);
+    }
+    let mut xml = format!("            <{}>\n", name);
+    for element in elements {
+        xml.push_str(&format!("                {}\n", element));
+    }
+    xml.push_str(&format!("            </{}>\n", name));
+    return xml;
+}
+
+/// Convert a length from internal units to nm
+fn nm(value: f64) -> f64 {
+    units::to(value, "nm").expect("bad unit")
+}
+
+/// Convert an energy from internal units to kJ/mol
+fn kj_mol(value: f64) -> f64 {
+    units::to(value, "kJ/mol").expect("bad unit")
+}
+
+fn invalid_data<S: Into<String>>(message: S) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.into())
+}
+
+/// Get the error for a `kind` potential that can not be exported
+fn unsupported(parameters: &Option<PotentialParameters>, kind: &str) -> io::Error {
+    match *parameters {
+        Some(ref parameters) => invalid_data(format!(
+            "{} {} potential can not be exported to OpenMM", parameters.name, kind
+        )),
+        None => invalid_data(format!("unknown {} potential can not be exported to OpenMM", kind)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Molecule, Particle, UnitCell};
+    use crate::energy::{Ewald, Harmonic, LennardJones, Morse, PairInteraction, SharedEwald};
+    use std::io::Read;
+
+    use tempfile::NamedTempFile;
+
+    fn lj(sigma: f64, epsilon: f64) -> PairInteraction {
+        let lj = LennardJones {
+            sigma: units::from(sigma, "A").unwrap(),
+            epsilon: units::from(epsilon, "kJ/mol").unwrap(),
+        };
+        PairInteraction::new(Box::new(lj), 8.0)
+    }
+
+    fn nacl() -> System {
+        let mut system = System::with_cell(UnitCell::cubic(22.56));
+        for i in 0..4 {
+            for j in 0..4 {
+                for k in 0..4 {
+                    let position = Vector3D::new(i as f64, j as f64, k as f64) * 2.82;
+                    let mut particle = if (i + j + k) % 2 == 0 {
+                        Particle::with_position("Na", position)
+                    } else {
+                        Particle::with_position("Cl", position)
+                    };
+                    particle.charge = if particle.name == "Na" { 1.0 } else { -1.0 };
+                    system.add_molecule(Molecule::new(particle));
+                }
+            }
+        }
+
+        system.set_pair_potential(("Na", "Na"), lj(2.0, 0.4));
+        system.set_pair_potential(("Cl", "Cl"), lj(4.0, 0.1));
+        system.set_pair_potential(("Na", "Cl"), lj(3.0, 0.2));
+        system.set_coulomb_potential(Box::new(SharedEwald::new(Ewald::new(8.0, 5, 0.25))));
+        return system;
+    }
+
+    fn read_xml(system: &System) -> String {
+        let file = NamedTempFile::new().unwrap();
+        system.save_openmm_xml(file.path()).unwrap();
+        let mut content = String::new();
+        let _ = File::open(file.path()).unwrap().read_to_string(&mut content).unwrap();
+        return content;
+    }
+
+    #[test]
+    fn nacl_system() {
+        let xml = read_xml(&nacl());
+
+        assert_eq!(xml.matches("<Particle mass=").count(), 64);
+        assert_eq!(xml.matches("<Particle q=\"1.0\" sig=\"0.2\" eps=\"0.4").count(), 32);
+        assert_eq!(xml.matches("<Particle q=\"-1.0\" sig=\"0.4\" eps=\"0.1").count(), 32);
+        assert!(xml.contains("<A x=\"2.256\" y=\"0.0\" z=\"0.0\"/>"));
+        assert!(xml.contains("method=\"4\" cutoff=\"0.8\" alpha=\"2.5"));
+        assert!(xml.contains("<Exceptions/>"));
+        assert!(xml.contains("<Bonds/>"));
+    }
+
+    #[test]
+    fn bonded() {
+        let mut system = System::with_cell(UnitCell::cubic(20.0));
+        let mut molecule = Molecule::new(Particle::with_position("O", Vector3D::new(0.0, 0.0, 0.0)));
+        molecule.add_particle_bonded_to(0, Particle::with_position("H", Vector3D::new(1.0, 0.0, 0.0)));
+        molecule.add_particle_bonded_to(0, Particle::with_position("H", Vector3D::new(0.0, 1.0, 0.0)));
+        system.add_molecule(molecule);
+
+        system.set_bond_potential(("O", "H"), Box::new(Harmonic {
+            k: units::from(1000.0, "kJ/mol/A^2").unwrap(),
+            x0: units::from(1.0, "A").unwrap(),
+        }));
+        system.set_angle_potential(("H", "O", "H"), Box::new(Harmonic {
+            k: units::from(300.0, "kJ/mol/deg^2").unwrap(),
+            x0: units::from(109.5, "deg").unwrap(),
+        }));
+        let mut pairs = lj(3.0, 0.5);
+        pairs.set_restriction(PairRestriction::InterMolecular);
+        system.set_pair_potential(("O", "O"), pairs);
+
+        let xml = read_xml(&system);
+        assert!(xml.contains("<Bond p1=\"0\" p2=\"1\" d=\"0.1\" k=\"100000"));
+        assert!(xml.contains("<Bond p1=\"0\" p2=\"2\" d=\"0.1\" k=\"100000"));
+        assert!(xml.contains("<Angle p1=\"1\" p2=\"0\" p3=\"2\" a=\"1.911"));
+        assert!(xml.contains("method=\"2\" cutoff=\"0.8\""));
+        // Only the O-O pair has a restriction, and there is only one O
+        assert!(xml.contains("<Exceptions/>"));
+    }
+
+    #[test]
+    fn errors() {
+        let mut system = nacl();
+        system.set_pair_potential(("Na", "Cl"), lj(3.5, 0.2));
+        let error = system.save_openmm_xml(NamedTempFile::new().unwrap().path()).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "the pair potential for (Cl, Na) does not follow Lorentz-Berthelot mixing rules, and can not be exported to OpenMM"
+        );
+
+        let mut system = nacl();
+        system.set_bond_potential(("Na", "Cl"), Box::new(Morse { a: 1.0, x0: 1.0, depth: 1.0 }));
+        let _ = system.add_bond(0, 1);
+        let error = system.save_openmm_xml(NamedTempFile::new().unwrap().path()).unwrap_err();
+        assert_eq!(error.to_string(), "morse bond potential can not be exported to OpenMM");
+    }
+}
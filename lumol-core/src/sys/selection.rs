@@ -0,0 +1,297 @@
+// Lumol, an extensible molecular simulation engine
+// Copyright (C) Lumol's contributors — BSD license
+
+//! A small selection language for particles
+use std::error::Error;
+use std::fmt;
+
+use crate::{Configuration, System};
+
+/// Possible errors when parsing a selection string.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SelectionError {
+    /// The selection ended while more input was expected
+    UnexpectedEnd,
+    /// This token was not expected at this point in the selection
+    UnexpectedToken(String),
+    /// The selector was not followed by any value
+    MissingValue(String),
+    /// This value is not a valid particle index
+    InvalidIndex(String),
+}
+
+impl fmt::Display for SelectionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            SelectionError::UnexpectedEnd => write!(f, "unexpected end of selection"),
+            SelectionError::UnexpectedToken(ref token) => write!(f, "unexpected '{}' in selection", token),
+            SelectionError::MissingValue(ref selector) => write!(f, "missing value after '{}'", selector),
+            SelectionError::InvalidIndex(ref value) => write!(f, "'{}' is not a valid index", value),
+        }
+    }
+}
+
+impl Error for SelectionError {}
+
+/// Abstract syntax tree of a selection
+#[derive(Clone, Debug, PartialEq)]
+enum Ast {
+    All,
+    Name(Vec<String>),
+    Index(Vec<usize>),
+    And(Box<Ast>, Box<Ast>),
+    Or(Box<Ast>, Box<Ast>),
+    Not(Box<Ast>),
+}
+
+impl Ast {
+    /// Check if the particle at index `i` in the `configuration` matches
+    fn matches(&self, configuration: &Configuration, i: usize) -> bool {
+        match *self {
+            Ast::All => true,
+            Ast::Name(ref names) => names.iter().any(|name| *name == configuration.particles().name[i]),
+            Ast::Index(ref indexes) => indexes.contains(&i),
+            Ast::And(ref lhs, ref rhs) => lhs.matches(configuration, i) && rhs.matches(configuration, i),
+            Ast::Or(ref lhs, ref rhs) => lhs.matches(configuration, i) || rhs.matches(configuration, i),
+            Ast::Not(ref ast) => !ast.matches(configuration, i),
+        }
+    }
+}
+
+/// A `Selection` selects particles in a configuration, using a small
+/// selection language.
+///
+/// The selection is made of selectors, combined with `and`, `or` and `not`,
+/// and grouped with parentheses. `not` has the highest precedence, followed
+/// by `and` and then `or`. The following selectors are available:
+///
+/// - `all` selects all the particles;
+/// - `name <name> ...` selects the particles with any of the given names;
+/// - `index <i> ...` selects the particles at any of the given indexes.
+///
+/// # Examples
+///
+/// ```
+/// # use lumol_core::{Molecule, Particle, Selection, System};
+/// let mut system = System::new();
+/// system.add_molecule(Molecule::new(Particle::new("Na")));
+/// system.add_molecule(Molecule::new(Particle::new("O")));
+/// system.add_molecule(Molecule::new(Particle::new("Cl")));
+///
+/// let selection = Selection::new("name Na Cl and not index 0").unwrap();
+/// assert_eq!(selection.list(&system), [2]);
+///
+/// assert!(Selection::new("name Na or").is_err());
+/// ```
+#[derive(Clone, Debug)]
+pub struct Selection {
+    ast: Ast,
+}
+
+impl Selection {
+    /// Parse the `selection` string.
+    pub fn new(selection: &str) -> Result<Selection, SelectionError> {
+        let selection = selection.replace('(', " ( ").replace(')', " ) ");
+        let tokens = selection.split_whitespace().map(String::from).collect::<Vec<_>>();
+
+        let mut parser = Parser {
+            tokens: &tokens,
+            current: 0,
+        };
+        let ast = parser.or()?;
+        if let Some(token) = parser.advance() {
+            return Err(SelectionError::UnexpectedToken(token.into()));
+        }
+        return Ok(Selection { ast: ast });
+    }
+
+    /// Get the indexes of the particles matching this selection in the
+    /// `configuration`, in increasing order.
+    pub fn list(&self, configuration: &Configuration) -> Vec<usize> {
+        (0..configuration.size()).filter(|&i| self.ast.matches(configuration, i)).collect()
+    }
+}
+
+/// Words with a special meaning in selections, which can not be used as
+/// selectors values
+const KEYWORDS: &[&str] = &["and", "or", "not", "(", ")", "all", "name", "index"];
+
+/// Recursive descent parser for selections
+struct Parser<'a> {
+    tokens: &'a [String],
+    current: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<&'a str> {
+        self.tokens.get(self.current).map(|token| token.as_str())
+    }
+
+    fn advance(&mut self) -> Option<&'a str> {
+        let token = self.peek();
+        self.current += 1;
+        return token;
+    }
+
+    /// or := and ("or" and)*
+    fn or(&mut self) -> Result<Ast, SelectionError> {
+        let mut ast = self.and()?;
+        while self.peek() == Some("or") {
+            let _ = self.advance();
+            ast = Ast::Or(Box::new(ast), Box::new(self.and()?));
+        }
+        return Ok(ast);
+    }
+
+    /// and := not ("and" not)*
+    fn and(&mut self) -> Result<Ast, SelectionError> {
+        let mut ast = self.not()?;
+        while self.peek() == Some("and") {
+            let _ = self.advance();
+            ast = Ast::And(Box::new(ast), Box::new(self.not()?));
+        }
+        return Ok(ast);
+    }
+
+    /// not := "not" not | selector
+    fn not(&mut self) -> Result<Ast, SelectionError> {
+        if self.peek() == Some("not") {
+            let _ = self.advance();
+            return Ok(Ast::Not(Box::new(self.not()?)));
+        }
+        return self.selector();
+    }
+
+    /// selector := "(" or ")" | "all" | "name" value+ | "index" value+
+    fn selector(&mut self) -> Result<Ast, SelectionError> {
+        match self.advance() {
+            None => Err(SelectionError::UnexpectedEnd),
+            Some("(") => {
+                let ast = self.or()?;
+                match self.advance() {
+                    Some(")") => Ok(ast),
+                    Some(token) => Err(SelectionError::UnexpectedToken(token.into())),
+                    None => Err(SelectionError::UnexpectedEnd),
+                }
+            }
+            Some("all") => Ok(Ast::All),
+            Some("name") => {
+                let names = self.values("name")?;
+                Ok(Ast::Name(names.iter().map(|&name| String::from(name)).collect()))
+            }
+            Some("index") => {
+                let mut indexes = Vec::new();
+                for value in self.values("index")? {
+                    let index = value.parse().map_err(|_| SelectionError::InvalidIndex(value.into()))?;
+                    indexes.push(index);
+                }
+                Ok(Ast::Index(indexes))
+            }
+            Some(token) => Err(SelectionError::UnexpectedToken(token.into())),
+        }
+    }
+
+    /// Get all the values following the `selector`
+    fn values(&mut self, selector: &str) -> Result<Vec<&'a str>, SelectionError> {
+        let mut values = Vec::new();
+        while let Some(token) = self.peek() {
+            if KEYWORDS.contains(&token) {
+                break;
+            }
+            values.push(token);
+            self.current += 1;
+        }
+
+        if values.is_empty() {
+            return Err(SelectionError::MissingValue(selector.into()));
+        }
+        return Ok(values);
+    }
+}
+
+impl System {
+    /// Get the indexes of the particles matching the `selection` string, in
+    /// increasing order. See [`Selection`](struct.Selection.html) for the
+    /// selection language.
+    ///
+    /// # Panics
+    ///
+    /// If the selection string is invalid. Use
+    /// [`Selection::new`](struct.Selection.html#method.new) to handle
+    /// invalid selections.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use lumol_core::{Molecule, Particle, System};
+    /// let mut system = System::new();
+    /// system.add_molecule(Molecule::new(Particle::new("O")));
+    /// system.add_molecule(Molecule::new(Particle::new("H")));
+    /// system.add_molecule(Molecule::new(Particle::new("H")));
+    ///
+    /// assert_eq!(system.select("name H"), [1, 2]);
+    /// assert_eq!(system.select("not name H or index 2"), [0, 2]);
+    /// ```
+    pub fn select(&self, selection: &str) -> Vec<usize> {
+        match Selection::new(selection) {
+            Ok(selection) => selection.list(self),
+            Err(error) => panic!("invalid selection '{}': {}", selection, error),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::system_from_xyz;
+
+    fn nacl() -> System {
+        system_from_xyz("6
+        cell: 11.2
+        Na 0.0 0.0 0.0
+        Cl 2.8 0.0 0.0
+        Na 2.8 2.8 0.0
+        Cl 0.0 2.8 0.0
+        O 5.6 5.6 5.6
+        Na 5.6 0.0 0.0
+        ")
+    }
+
+    #[test]
+    fn names() {
+        let system = nacl();
+        assert_eq!(system.select("name Na or name Cl"), [0, 1, 2, 3, 5]);
+        assert_eq!(system.select("name Na Cl"), [0, 1, 2, 3, 5]);
+        assert_eq!(system.select("name Cl"), [1, 3]);
+        assert!(system.select("name K").is_empty());
+        assert_eq!(system.select("all"), [0, 1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn combinators() {
+        let system = nacl();
+        assert_eq!(system.select("not name Na"), [1, 3, 4]);
+        assert_eq!(system.select("name Na and index 2 3 4 5"), [2, 5]);
+        assert_eq!(system.select("index 0 or index 1 and name Na"), [0]);
+        assert_eq!(system.select("(index 0 or index 1) and name Cl"), [1]);
+        assert_eq!(system.select("not not index 4"), [4]);
+        assert!(system.select("index 42").is_empty());
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(Selection::new("").unwrap_err(), SelectionError::UnexpectedEnd);
+        assert_eq!(Selection::new("name").unwrap_err(), SelectionError::MissingValue("name".into()));
+        assert_eq!(Selection::new("name Na and").unwrap_err(), SelectionError::UnexpectedEnd);
+        assert_eq!(Selection::new("index foo").unwrap_err(), SelectionError::InvalidIndex("foo".into()));
+        assert_eq!(Selection::new("(name Na").unwrap_err(), SelectionError::UnexpectedEnd);
+        assert_eq!(Selection::new("name Na)").unwrap_err(), SelectionError::UnexpectedToken(")".into()));
+        assert_eq!(Selection::new("mass 3").unwrap_err(), SelectionError::UnexpectedToken("mass".into()));
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid selection 'name Na or'")]
+    fn invalid_select() {
+        let _ = nacl().select("name Na or");
+    }
+}
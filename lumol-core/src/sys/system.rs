@@ -2,15 +2,20 @@
 // Copyright (C) 2015-2016 Lumol's contributors — BSD license
 
 use std::ops::{Deref, DerefMut};
+use std::sync::RwLockReadGuard;
 
 use soa_derive::soa_zip;
+use log::warn;
 use log_once::warn_once;
 
 use crate::{Matrix3, Vector3D};
-use crate::{AnglePotential, BondPotential, DihedralPotential, PairInteraction};
-use crate::{CoulombicPotential, GlobalPotential};
-use crate::{Composition, EnergyEvaluator, Interactions};
-use crate::{Configuration, Molecule, UnitCell};
+use crate::{AnglePotential, BondPotential, DihedralPotential, ImproperPotential, PairInteraction};
+use crate::{CoulombicPotential, CrossTermPotential, ExternalPotential, GlobalPotential, ThreeBodyPotential};
+use crate::{ElectricField, PlanarWall, PositionRestraint, Wall, WallPotential};
+use crate::{Composition, EnergyEvaluator, Interactions, NeighborList, PairRestriction, SharedNeighborList};
+use crate::{Angle, Bond, Configuration, Dihedral, DrudeParticle, Molecule, Particle, Permutation};
+use crate::{SystemBuilder, UnitCell, VirtualSite};
+use crate::sys::periodic_table;
 
 /// The number of degrees of freedom simulated in a given system
 #[derive(Clone, PartialEq, Debug)]
@@ -44,6 +49,14 @@ pub struct System {
     interactions: Interactions,
     /// Externally managed temperature for the system
     external_temperature: Option<f64>,
+    /// Virtual sites in the system
+    virtual_sites: Vec<VirtualSite>,
+    /// Drude particles in the system
+    drude_particles: Vec<DrudeParticle>,
+    /// Indexes of the frozen particles in the system, sorted
+    frozen: Vec<usize>,
+    /// Neighbor list used for the pair interactions, if any
+    neighbor_list: Option<SharedNeighborList>,
     /// Number of degrees of freedom simulated in the system. This default to
     /// `DegreesOfFreedom::Particles`, and is set in the simulation setup.
     pub simulated_degrees_of_freedom: DegreesOfFreedom,
@@ -71,10 +84,27 @@ impl System {
             interactions: Interactions::new(),
             step: 0,
             external_temperature: None,
+            virtual_sites: Vec::new(),
+            drude_particles: Vec::new(),
+            frozen: Vec::new(),
+            neighbor_list: None,
             simulated_degrees_of_freedom: DegreesOfFreedom::Particles,
         }
     }
 
+    /// Remove all the particles, molecules, virtual sites, Drude particles
+    /// and frozen particles in this system, and set the unit cell to `cell`.
+    /// The interactions are kept.
+    pub(crate) fn clear_configuration(&mut self, cell: UnitCell) {
+        let depth = self.configuration.exclusion_depth();
+        self.configuration = Configuration::new();
+        self.configuration.cell = cell;
+        self.configuration.set_exclusion_depth(depth);
+        self.virtual_sites.clear();
+        self.drude_particles.clear();
+        self.frozen.clear();
+    }
+
     /// Add a molecule to the system
     pub fn add_molecule(&mut self, mut molecule: Molecule) {
         for (kind, name) in soa_zip!(molecule.particles_mut(), [mut kind, name]) {
@@ -83,6 +113,221 @@ impl System {
         self.configuration.add_molecule(molecule);
     }
 
+    /// Add a bond between the particles at indexes `i` and `j`, and return the
+    /// list of atomic permutations applied to keep the molecules contiguous
+    /// in memory. See
+    /// [`Configuration::add_bond`](struct.Configuration.html#method.add_bond)
+    /// for more information.
+    ///
+    /// The indexes of the virtual sites, Drude particles and frozen particles
+    /// are updated with the permutations.
+    pub fn add_bond(&mut self, i: usize, j: usize) -> Vec<Permutation> {
+        let permutations = self.configuration.add_bond(i, j);
+        if !permutations.is_empty() {
+            let mut new_indexes = (0..self.size()).map(Some).collect::<Vec<_>>();
+            for permutation in &permutations {
+                new_indexes[permutation.old] = Some(permutation.new);
+            }
+            self.update_indexes(&new_indexes);
+        }
+        return permutations;
+    }
+
+    /// Guess the bonds in this system from the distances between the
+    /// particles. See
+    /// [`Configuration::guess_bonds`](struct.Configuration.html#method.guess_bonds)
+    /// for more information.
+    ///
+    /// The indexes of the virtual sites, Drude particles and frozen particles
+    /// are updated if particles are moved.
+    pub fn guess_bonds(&mut self, tolerance: f64) {
+        let new_indexes = self.configuration.guess_bonds_with_indexes(tolerance);
+        let new_indexes = new_indexes.into_iter().map(Some).collect::<Vec<_>>();
+        self.update_indexes(&new_indexes);
+    }
+
+    /// Remove the molecule at index `molid` from the system.
+    ///
+    /// The indexes of the virtual sites, Drude particles and frozen particles
+    /// are updated, and the ones referring to particles in the removed
+    /// molecule are removed.
+    pub fn remove_molecule(&mut self, molid: usize) {
+        let removed = self.molecule(molid).indexes();
+        let new_indexes = (0..self.size()).map(|i| {
+            if i < removed.start {
+                Some(i)
+            } else if i < removed.end {
+                None
+            } else {
+                Some(i - removed.len())
+            }
+        }).collect::<Vec<_>>();
+
+        self.configuration.remove_molecule(molid);
+        self.update_indexes(&new_indexes);
+    }
+
+    /// Update the indexes of the virtual sites, Drude particles and frozen
+    /// particles after the particles moved. `new_indexes[i]` is the new index
+    /// of the particle previously at index `i`, or `None` if the particle was
+    /// removed. Virtual sites and Drude particles referring to removed
+    /// particles are removed.
+    fn update_indexes(&mut self, new_indexes: &[Option<usize>]) {
+        self.virtual_sites = self.virtual_sites.iter().filter_map(|site| {
+            let parents = site.parents();
+            let site_index = new_indexes[site.site()]?;
+            let parents = [
+                new_indexes[parents[0]]?,
+                new_indexes[parents[1]]?,
+                new_indexes[parents[2]]?,
+            ];
+            Some(VirtualSite::new(site_index, parents, site.weights()))
+        }).collect();
+
+        self.drude_particles = self.drude_particles.iter().filter_map(|drude| {
+            let drude_index = new_indexes[drude.drude()]?;
+            let core = new_indexes[drude.core()]?;
+            Some(DrudeParticle::new(drude_index, core, drude.k()))
+        }).collect();
+
+        self.frozen = self.frozen.iter().filter_map(|&i| new_indexes[i]).collect();
+        self.frozen.sort_unstable();
+    }
+
+    /// Build a supercell containing `nx`, `ny` and `nz` images of this system
+    /// along the first, second and third cell vectors respectively.
+    ///
+    /// All the molecules are copied in each image, translated by the
+    /// corresponding combination of the cell vectors, and keep their bonds.
+    /// Molecules crossing the boundaries of the cell are translated as a
+    /// whole, and stay bonded across the images. The unit cell is scaled
+    /// accordingly, and the interactions, virtual sites, Drude particles and
+    /// frozen particles are kept.
+    ///
+    /// # Panics
+    ///
+    /// If the unit cell is infinite, or if any of `nx`, `ny` or `nz` is zero.
+    pub fn replicate(&self, nx: usize, ny: usize, nz: usize) -> System {
+        assert!(!self.cell.is_infinite(), "can not replicate a system with an infinite cell");
+        assert!(nx > 0 && ny > 0 && nz > 0, "the number of images must be positive in System::replicate");
+
+        let mut cell = self.cell;
+        cell.scale_mut(Matrix3::new([
+            [nx as f64, 0.0, 0.0],
+            [0.0, ny as f64, 0.0],
+            [0.0, 0.0, nz as f64],
+        ]));
+
+        let mut system = self.clone();
+        system.clear_configuration(cell);
+
+        let size = self.size();
+        for i in 0..nx {
+            for j in 0..ny {
+                for k in 0..nz {
+                    let offset = system.size();
+                    let translation = self.cell.cartesian(&Vector3D::new(i as f64, j as f64, k as f64));
+                    for molecule in self.molecules() {
+                        let mut molecule = molecule.to_owned();
+                        for position in molecule.particles_mut().position {
+                            *position += translation;
+                        }
+                        system.configuration.add_molecule(molecule);
+                    }
+
+                    for site in &self.virtual_sites {
+                        let parents = site.parents();
+                        system.virtual_sites.push(VirtualSite::new(
+                            site.site() + offset,
+                            [parents[0] + offset, parents[1] + offset, parents[2] + offset],
+                            site.weights(),
+                        ));
+                    }
+
+                    for drude in &self.drude_particles {
+                        system.drude_particles.push(DrudeParticle::new(
+                            drude.drude() + offset,
+                            drude.core() + offset,
+                            drude.k(),
+                        ));
+                    }
+
+                    system.frozen.extend(self.frozen.iter().map(|i| i + offset));
+                    debug_assert_eq!(system.size(), offset + size);
+                }
+            }
+        }
+
+        return system;
+    }
+
+    /// Create a new system containing only the particles at the given
+    /// `indices`, with the same unit cell and interactions as this system.
+    ///
+    /// The particles keep their relative order, and the bonds between
+    /// selected particles are kept. Molecules are split if some of their
+    /// particles are not selected. Virtual sites, Drude particles and frozen
+    /// particles are not copied to the new system.
+    ///
+    /// # Panics
+    ///
+    /// If any of the `indices` is out of bounds.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use lumol_core::{Molecule, Particle, System};
+    /// let mut system = System::new();
+    /// system.add_molecule(Molecule::new(Particle::new("Ar")));
+    /// system.add_molecule(Molecule::new(Particle::new("Kr")));
+    ///
+    /// let krypton = system.subsystem(&[1]);
+    /// assert_eq!(krypton.size(), 1);
+    /// assert_eq!(krypton.particles().name[0], "Kr");
+    /// ```
+    pub fn subsystem(&self, indices: &[usize]) -> System {
+        let mut selected = vec![false; self.size()];
+        for &i in indices {
+            assert!(i < self.size(), "index {} is out of bounds in System::subsystem", i);
+            selected[i] = true;
+        }
+
+        let particles = self.particles();
+        let mut builder = SystemBuilder::new();
+        // index of the particles in the sub-system, for the selected particles
+        let mut new_indexes = vec![None; self.size()];
+        let mut count = 0;
+        for i in 0..self.size() {
+            if selected[i] {
+                new_indexes[i] = Some(count);
+                count += 1;
+                builder = builder.add_owned_particle(Particle {
+                    name: particles.name[i].clone(),
+                    kind: particles.kind[i],
+                    charge: particles.charge[i],
+                    mass: particles.mass[i],
+                    position: particles.position[i],
+                    velocity: particles.velocity[i],
+                });
+            }
+        }
+
+        for molecule in self.molecules() {
+            for bond in molecule.bonds() {
+                if let (Some(i), Some(j)) = (new_indexes[bond.i()], new_indexes[bond.j()]) {
+                    builder = builder.add_bond(i, j);
+                }
+            }
+        }
+
+        let mut system = self.clone();
+        system.clear_configuration(self.cell);
+        for molecule in builder.build().molecules() {
+            system.add_molecule(molecule.to_owned());
+        }
+        return system;
+    }
+
     /// Get the composition in particles and molecules of the configuration
     pub fn composition(&self) -> Composition {
         let mut composition = Composition::new();
@@ -95,6 +340,20 @@ impl System {
         return composition;
     }
 
+    /// Set the mass of all the particles in the system from their names,
+    /// using the [periodic table][periodic_table]. The mass of particles with
+    /// an unknown name is not changed, and a warning is emitted.
+    ///
+    /// [periodic_table]: periodic_table/index.html
+    pub fn assign_masses_from_names(&mut self) {
+        for (name, mass) in soa_zip!(self.particles_mut(), [name, mut mass]) {
+            match periodic_table::mass(name) {
+                Some(value) => *mass = value,
+                None => warn_once!("unknown mass for particle '{}', keeping the current mass", name),
+            }
+        }
+    }
+
     /// Use an external temperature for all the system properties. Calling this
     /// with `Some(temperature)` will replace all the computation of the
     /// temperature from the velocities with the given values. Calling it with
@@ -109,6 +368,214 @@ impl System {
     }
 }
 
+/// Functions related to virtual sites
+impl System {
+    /// Add a virtual site to the system. The particle at the site index is
+    /// made massless, and placed at the position computed from its parents.
+    ///
+    /// # Panics
+    ///
+    /// If any of the site or parents indexes is out of bounds, or if the
+    /// particle is already a virtual site.
+    pub fn add_virtual_site(&mut self, site: VirtualSite) {
+        let size = self.size();
+        assert!(site.site() < size, "virtual site index {} is out of bounds", site.site());
+        for &parent in &site.parents() {
+            assert!(parent < size, "virtual site parent index {} is out of bounds", parent);
+        }
+        assert!(
+            self.virtual_sites.iter().all(|other| other.site() != site.site()),
+            "particle {} is already a virtual site", site.site()
+        );
+
+        self.particles_mut().mass[site.site()] = 0.0;
+        self.particles_mut().velocity[site.site()] = Vector3D::zero();
+        self.virtual_sites.push(site);
+        self.update_virtual_sites();
+    }
+
+    /// Get the list of virtual sites in this system
+    pub fn virtual_sites(&self) -> &[VirtualSite] {
+        &self.virtual_sites
+    }
+
+    /// Recompute the positions of all the virtual sites from the positions of
+    /// their parents. This must be called every time the particles move.
+    pub fn update_virtual_sites(&mut self) {
+        for site in &self.virtual_sites {
+            let position = site.position(&self.configuration);
+            self.configuration.particles_mut().position[site.site()] = position;
+        }
+    }
+}
+
+/// Functions related to Drude particles
+impl System {
+    /// Add a Drude particle to the system. The Drude particle and its core
+    /// should be bonded together, so that the electrostatic interaction
+    /// between them can be removed with a pair restriction.
+    ///
+    /// # Panics
+    ///
+    /// If any of the Drude or core indexes is out of bounds, if the Drude
+    /// particle and its core are not in the same molecule, or if the particle
+    /// is already a Drude particle.
+    pub fn add_drude_particle(&mut self, drude: DrudeParticle) {
+        let size = self.size();
+        assert!(drude.drude() < size, "Drude particle index {} is out of bounds", drude.drude());
+        assert!(drude.core() < size, "Drude core index {} is out of bounds", drude.core());
+        assert!(
+            self.are_in_same_molecule(drude.drude(), drude.core()),
+            "a Drude particle must be in the same molecule as its core"
+        );
+        assert!(
+            self.drude_particles.iter().all(|other| other.drude() != drude.drude()),
+            "particle {} is already a Drude particle", drude.drude()
+        );
+        self.drude_particles.push(drude);
+    }
+
+    /// Get the list of Drude particles in this system
+    pub fn drude_particles(&self) -> &[DrudeParticle] {
+        &self.drude_particles
+    }
+
+    /// Move the Drude particles to the position minimizing the energy of the
+    /// system for the current positions of the other particles, as needed
+    /// for massless Drude particles.
+    ///
+    /// This uses a self-consistent iteration, displacing each Drude particle
+    /// by $\vec F_D / k$ until the norm of the force acting on all the Drude
+    /// particles is smaller than `tolerance`. The iteration is stopped with a
+    /// warning if it did not converge after 100 steps. This function returns
+    /// the number of steps used.
+    pub fn relax_drude_particles(&mut self, tolerance: f64) -> usize {
+        const MAX_ITERATIONS: usize = 100;
+        if self.drude_particles.is_empty() {
+            return 0;
+        }
+
+        for iteration in 0..MAX_ITERATIONS {
+            let forces = self.forces();
+            let converged = self.drude_particles.iter().all(|drude| {
+                forces[drude.drude()].norm() < tolerance
+            });
+            if converged {
+                return iteration;
+            }
+
+            for drude in &self.drude_particles {
+                let i = drude.drude();
+                self.configuration.particles_mut().position[i] += forces[i] / drude.k();
+            }
+        }
+
+        warn!("the positions of the Drude particles did not converge after {} steps", MAX_ITERATIONS);
+        return MAX_ITERATIONS;
+    }
+}
+
+/// Functions related to frozen particles
+impl System {
+    /// Freeze the particles at the given `indices`, so that they do not move
+    /// during molecular dynamics simulations and energy minimizations.
+    ///
+    /// The forces acting on frozen particles are always zero, and their
+    /// velocities are set to zero. Frozen particles still interact with the
+    /// other particles in the system, and barostats still scale their
+    /// positions with the unit cell.
+    ///
+    /// # Panics
+    ///
+    /// If any of the `indices` is out of bounds.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use lumol_core::{Molecule, Particle, System};
+    /// let mut system = System::new();
+    /// system.add_molecule(Molecule::new(Particle::new("Au")));
+    /// system.add_molecule(Molecule::new(Particle::new("Ar")));
+    ///
+    /// system.freeze(&[0]);
+    /// assert_eq!(system.frozen(), &[0]);
+    /// assert!(system.is_frozen(0));
+    /// assert!(!system.is_frozen(1));
+    /// ```
+    pub fn freeze(&mut self, indices: &[usize]) {
+        for &i in indices {
+            assert!(i < self.size(), "index {} is out of bounds in System::freeze", i);
+        }
+        self.frozen.extend_from_slice(indices);
+        self.frozen.sort_unstable();
+        self.frozen.dedup();
+        self.zero_frozen_velocities();
+    }
+
+    /// Get the sorted list of the indexes of frozen particles in this system
+    pub fn frozen(&self) -> &[usize] {
+        &self.frozen
+    }
+
+    /// Check if the particle at index `i` is frozen
+    pub fn is_frozen(&self, i: usize) -> bool {
+        self.frozen.binary_search(&i).is_ok()
+    }
+
+    /// Set the velocities of all the frozen particles to zero. Propagators
+    /// should call this every time the velocities are changed.
+    pub fn zero_frozen_velocities(&mut self) {
+        let velocities = self.configuration.particles_mut().velocity;
+        for &i in &self.frozen {
+            velocities[i] = Vector3D::zero();
+        }
+    }
+}
+
+/// Functions related to the neighbor list
+impl System {
+    /// Use the neighbor `list` to find the pairs of particles interacting
+    /// with pair potentials when computing the energy, the forces and the
+    /// virial of the system. The list is automatically built again when the
+    /// particles move too much.
+    ///
+    /// # Panics
+    ///
+    /// If the cutoff of the list is smaller than the cutoff of any of the
+    /// pair interactions in the system.
+    pub fn set_neighbor_list(&mut self, list: NeighborList) {
+        for ((i, j), potential) in self.interactions.all_pairs() {
+            assert!(
+                potential.cutoff() <= list.cutoff(),
+                "the cutoff of the neighbor list ({} A) is smaller than the cutoff \
+                of the ({}, {}) pair interaction ({} A)", list.cutoff(), i, j, potential.cutoff()
+            );
+        }
+        self.neighbor_list = Some(SharedNeighborList::new(list));
+    }
+
+    /// Remove the neighbor list of this system, and check all the pairs of
+    /// particles when computing pair interactions.
+    pub fn remove_neighbor_list(&mut self) {
+        self.neighbor_list = None;
+    }
+
+    /// Get the neighbor list of this system, if any. The list is built again
+    /// for the current positions of the particles if needed. Pairs of
+    /// particles with an [excluded](struct.PairInteraction.html#method.excluded)
+    /// pair interaction are not part of the list.
+    pub fn neighbor_list(&self) -> Option<RwLockReadGuard<'_, NeighborList>> {
+        let kinds = self.particles().kind;
+        let interacting = |i: usize, j: usize| {
+            match self.interactions.pair((kinds[i], kinds[j])) {
+                Some(interaction) => !interaction.is_excluded(),
+                None => true,
+            }
+        };
+        self.neighbor_list.as_ref().map(|list| list.updated(&self.configuration, interacting))
+    }
+}
+
 /// Functions related to interactions
 impl System {
     /// Get an helper struct to evaluate the energy of this system.
@@ -116,16 +583,63 @@ impl System {
         EnergyEvaluator::new(self)
     }
 
+    /// Get the change in pair energy when moving the particle at `index` to
+    /// `new_position`, without modifying the system. Only the pair
+    /// interactions involving this particle are evaluated, making this much
+    /// cheaper than a full energy computation in Monte Carlo simulations.
+    ///
+    /// If the system has a [neighbor list](#method.set_neighbor_list), and
+    /// the list stays valid after the move, only the neighbors of the
+    /// particle are visited. Else, all the other particles are visited.
+    pub fn move_particle_energy_delta(&self, index: usize, new_position: Vector3D) -> f64 {
+        let evaluator = self.energy_evaluator();
+        let positions = self.particles().position;
+        let old_position = positions[index];
+        let pair_delta = |j: usize| {
+            let old_r = self.cell.distance(&old_position, &positions[j]);
+            let new_r = self.cell.distance(&new_position, &positions[j]);
+            evaluator.pair_between(new_r, index, j) - evaluator.pair_between(old_r, index, j)
+        };
+
+        if let Some(list) = self.neighbor_list() {
+            if list.is_valid_after_move(index, new_position) {
+                return list.all_neighbors(index).iter().map(|&j| pair_delta(j)).sum();
+            }
+        }
+
+        return (0..self.size()).filter(|&j| j != index).map(pair_delta).sum();
+    }
+
+    /// Get a JSON representation of all the pairs, bonds, angles and
+    /// dihedral angles interactions in this system. This can be used to check
+    /// which potentials and parameters will be used in a simulation.
+    pub fn dump_interactions_json(&self) -> String {
+        self.interactions.to_json()
+    }
+
     /// Set the pair interaction `potential` for atoms with types `i` and `j`
+    ///
+    /// # Panics
+    ///
+    /// If the potential cutoff is bigger than the maximal cutoff for the
+    /// system unit cell, see
+    /// [`UnitCell::max_cutoff`](struct.UnitCell.html#method.max_cutoff); or
+    /// if it is bigger than the cutoff of the neighbor list of the system.
     pub fn set_pair_potential(&mut self, (i, j): (&str, &str), potential: PairInteraction) {
-        if self.cell.lengths().iter().any(|&d| 0.5 * d < potential.cutoff()) {
-            panic!(
-                "Can not add a potential with a cutoff bigger than half of the \
-                smallest cell length. Try increasing the cell size or decreasing \
-                the cutoff."
+        self.check_cutoff(potential.cutoff());
+        if let Some(ref list) = self.neighbor_list {
+            assert!(
+                potential.cutoff() <= list.cutoff(),
+                "Can not add a pair potential with a cutoff of {} A, bigger than \
+                the cutoff of the neighbor list ({} A).", potential.cutoff(), list.cutoff()
             );
         }
-        self.interactions.set_pair((i, j), potential)
+        self.interactions.set_pair((i, j), potential);
+        self.update_exclusion_depth();
+        if let Some(ref mut list) = self.neighbor_list {
+            // excluded pairs are not part of the list
+            list.invalidate();
+        }
     }
 
     /// Set the bond interaction `potential` for atoms with types `i` and `j`
@@ -152,18 +666,88 @@ impl System {
         self.interactions.set_dihedral((i, j, k, m), potential)
     }
 
+    /// Add the improper dihedral angle interaction `potential` for atoms with
+    /// types `i`, `j`, `k`, and `m`, where `i` is the central atom. This
+    /// potential will be used for all the particles of type `i` bonded to
+    /// exactly three particles with types `j`, `k` and `m`, in any order.
+    ///
+    /// The improper angle is the dihedral angle between the `i-j-k` and
+    /// `j-k-m` planes, where `j`, `k` and `m` are sorted by particle index.
+    /// This angle is zero when the four particles are in the same plane.
+    pub fn add_improper_interaction(
+        &mut self,
+        (i, j, k, m): (&str, &str, &str, &str),
+        potential: Box<dyn ImproperPotential>,
+    ) {
+        self.interactions.set_improper((i, j, k, m), potential)
+    }
+
+    /// Add the three-body interaction `potential` for triplets of atoms with
+    /// types `i`, `j`, and `k`, where `j` is the central atom. This potential
+    /// will be used for all the triplets `i-j-k` where both `i` and `k` are
+    /// within the potential cutoff of `j`, regardless of the bonds between
+    /// these atoms.
+    pub fn add_three_body_interaction(
+        &mut self,
+        (i, j, k): (&str, &str, &str),
+        potential: Box<dyn ThreeBodyPotential>,
+    ) {
+        self.interactions.set_three_body((i, j, k), potential)
+    }
+
+    /// Add the cross term interaction `potential` for angles formed by atoms
+    /// with types `i`, `j`, and `k`, where `j` is the central atom. This
+    /// potential will be used for all the bonded angles `i-j-k` in the
+    /// system, in addition to the angle and bonds potentials. The first bond
+    /// length given to the potential is the `i-j` one.
+    pub fn add_cross_term_interaction(
+        &mut self,
+        (i, j, k): (&str, &str, &str),
+        potential: Box<dyn CrossTermPotential>,
+    ) {
+        self.interactions.set_cross_term((i, j, k), potential)
+    }
+
     /// Set the coulombic interaction for all pairs to `potential`
+    ///
+    /// # Panics
+    ///
+    /// If the potential cutoff is bigger than the maximal cutoff for the
+    /// system unit cell, see
+    /// [`UnitCell::max_cutoff`](struct.UnitCell.html#method.max_cutoff).
     pub fn set_coulomb_potential(&mut self, potential: Box<dyn CoulombicPotential>) {
         if let Some(cutoff) = potential.cutoff() {
-            if self.cell.lengths().iter().any(|&d| 0.5 * d < cutoff) {
-                panic!(
-                    "Can not add a potential with a cutoff bigger than half of the \
-                    smallest cell length. Try increasing the cell size or decreasing \
-                    the cutoff."
-                );
-            }
+            self.check_cutoff(cutoff);
         }
         self.interactions.coulomb = Some(potential);
+        self.update_exclusion_depth();
+    }
+
+    /// Update the exclusion depth of the configuration, using the largest
+    /// `ExcludeBonded` restriction in the pair and coulombic interactions.
+    fn update_exclusion_depth(&mut self) {
+        let pairs = self.interactions.all_pairs().map(|(_, pair)| pair.restriction());
+        let coulomb = self.interactions.coulomb.as_ref().map(|coulomb| coulomb.restriction());
+        let depth = pairs.chain(coulomb).filter_map(|restriction| {
+            match restriction {
+                PairRestriction::ExcludeBonded(depth) => Some(depth),
+                _ => None,
+            }
+        }).fold(3, usize::max);
+        self.configuration.set_exclusion_depth(depth);
+    }
+
+    /// Check that `cutoff` respects the minimum image convention in the
+    /// current unit cell
+    fn check_cutoff(&self, cutoff: f64) {
+        let max_cutoff = self.cell.max_cutoff();
+        if cutoff > max_cutoff {
+            panic!(
+                "Can not add a potential with a cutoff of {} A, bigger than half of \
+                the smallest cell length ({} A). Try increasing the cell size or \
+                decreasing the cutoff.", cutoff, max_cutoff
+            );
+        }
     }
 
     /// Add the `potential` global interaction
@@ -171,11 +755,52 @@ impl System {
         self.interactions.globals.push(potential);
     }
 
-    /// Get the pair potential acting between the particles at indexes `i` and `j`.
+    /// Add the `potential` external interaction, acting on each particle
+    /// independently.
+    pub fn add_external_potential(&mut self, potential: Box<dyn ExternalPotential>) {
+        self.interactions.externals.push(potential);
+    }
+
+    /// Add an harmonic restraint with spring constant `k` tethering the
+    /// particle at `index` to the `reference` position.
+    pub fn add_position_restraint(&mut self, index: usize, reference: Vector3D, k: f64) {
+        assert!(index < self.size(), "particle index is out of bounds in position restraint");
+        self.add_external_potential(Box::new(PositionRestraint::new(index, reference, k)));
+    }
+
+    /// Add a `wall` confining the particles in the system
+    pub fn add_wall(&mut self, wall: Box<dyn Wall>) {
+        self.add_external_potential(Box::new(wall));
+    }
+
+    /// Add a planar wall perpendicular to the given `axis` (0 for x, 1 for y
+    /// and 2 for z), at the given `position` along this axis. Particles
+    /// interact with the wall using `potential`, as a function of their
+    /// distance to the wall plane. Adding two walls along the same axis
+    /// creates a slit pore.
+    pub fn add_planar_wall(&mut self, axis: usize, position: f64, potential: Box<dyn WallPotential>) {
+        self.add_wall(Box::new(PlanarWall::new(axis, position, potential)));
+    }
+
+    /// Apply an uniform electric `field` to all the charged particles in the
+    /// system, replacing any previous field. See `ElectricField` for the
+    /// units of the field.
+    pub fn set_electric_field(&mut self, field: Vector3D) {
+        self.interactions.electric_field = Some(ElectricField::new(field));
+    }
+
+    /// Get the uniform electric field applied to the system, if any
+    pub fn electric_field(&self) -> Option<Vector3D> {
+        self.interactions.electric_field.as_ref().map(ElectricField::field)
+    }
+
+    /// Get the pair potential acting between the particles at indexes `i` and
+    /// `j`. This returns `None` if there is no potential for this pair, or if
+    /// the pair is [excluded](struct.PairInteraction.html#method.excluded).
     pub fn pair_potential(&self, i: usize, j: usize) -> Option<&PairInteraction> {
         let kind_i = self.particles().kind[i];
         let kind_j = self.particles().kind[j];
-        return self.interactions.pair((kind_i, kind_j));
+        return self.interactions.pair((kind_i, kind_j)).filter(|interaction| !interaction.is_excluded());
     }
 
     /// Get read-only access to the interactions for this system
@@ -200,6 +825,15 @@ impl System {
         return self.interactions.angle((kind_i, kind_j, kind_k));
     }
 
+    /// Get the three-body potential acting on the triplet of particles at
+    /// indexes `i`, `j` and `k`, centered on `j`.
+    pub fn three_body_potential(&self, i: usize, j: usize, k: usize) -> Option<&dyn ThreeBodyPotential> {
+        let kind_i = self.particles().kind[i];
+        let kind_j = self.particles().kind[j];
+        let kind_k = self.particles().kind[k];
+        return self.interactions.three_body((kind_i, kind_j, kind_k));
+    }
+
     /// Get the dihedral angles potential acting between the particles at
     /// indexes `i`, `j`, `k` and `m`.
     pub fn dihedral_potential(
@@ -216,27 +850,183 @@ impl System {
         return self.interactions.dihedral((kind_i, kind_j, kind_k, kind_m));
     }
 
+    /// Get the improper dihedral angles potential acting between the
+    /// particles at indexes `i`, `j`, `k` and `m`, centered on `i`.
+    pub fn improper_potential(
+        &self,
+        i: usize,
+        j: usize,
+        k: usize,
+        m: usize,
+    ) -> Option<&dyn ImproperPotential> {
+        let kind_i = self.particles().kind[i];
+        let kind_j = self.particles().kind[j];
+        let kind_k = self.particles().kind[k];
+        let kind_m = self.particles().kind[m];
+        return self.interactions.improper((kind_i, kind_j, kind_k, kind_m));
+    }
+
+    /// Get an iterator over all the pair interactions in this system,
+    /// together with the names of the associated particles.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use lumol_core::{System, PairInteraction};
+    /// # use lumol_core::energy::LennardJones;
+    /// let mut system = System::new();
+    /// let lj = LennardJones { sigma: 2.0, epsilon: 1.0 };
+    /// system.set_pair_potential(("Ar", "Ar"), PairInteraction::new(Box::new(lj), 8.0));
+    ///
+    /// for ((i, j), interaction) in system.pair_interactions() {
+    ///     assert_eq!((i, j), ("Ar", "Ar"));
+    ///     assert_eq!(interaction.cutoff(), 8.0);
+    ///     assert_eq!(interaction.potential().parameters().unwrap().name, "lj");
+    /// }
+    /// ```
+    pub fn pair_interactions(&self) -> impl Iterator<Item = ((&str, &str), &PairInteraction)> + '_ {
+        self.interactions.all_pairs()
+    }
+
+    /// Get an iterator over all the bond interactions in this system,
+    /// together with the names of the associated particles.
+    pub fn bond_interactions(&self) -> impl Iterator<Item = ((&str, &str), &dyn BondPotential)> + '_ {
+        self.interactions.all_bonds()
+    }
+
+    /// Get an iterator over all the angle interactions in this system,
+    /// together with the names of the associated particles.
+    pub fn angle_interactions(&self) -> impl Iterator<Item = ((&str, &str, &str), &dyn AnglePotential)> + '_ {
+        self.interactions.all_angles()
+    }
+
+    /// Get an iterator over all the dihedral angles interactions in this
+    /// system, together with the names of the associated particles.
+    pub fn dihedral_interactions(
+        &self,
+    ) -> impl Iterator<Item = ((&str, &str, &str, &str), &dyn DihedralPotential)> + '_ {
+        self.interactions.all_dihedrals()
+    }
+
+    /// Get an iterator over all the improper dihedral angles interactions in
+    /// this system, together with the names of the associated particles. The
+    /// central particle is the first one.
+    pub fn improper_interactions(
+        &self,
+    ) -> impl Iterator<Item = ((&str, &str, &str, &str), &dyn ImproperPotential)> + '_ {
+        self.interactions.all_impropers()
+    }
+
+    /// Get an iterator over all the cross terms interactions in this system,
+    /// together with the names of the associated particles.
+    pub fn cross_term_interactions(
+        &self,
+    ) -> impl Iterator<Item = ((&str, &str, &str), &dyn CrossTermPotential)> + '_ {
+        self.interactions.all_cross_terms()
+    }
+
+    /// Get the cross term potential acting on the angle formed by the
+    /// particles at indexes `i`, `j` and `k`, centered on `j`. This only
+    /// returns potentials defined for the particles types in this order.
+    pub fn cross_term_potential(&self, i: usize, j: usize, k: usize) -> Option<&dyn CrossTermPotential> {
+        let kind_i = self.particles().kind[i];
+        let kind_j = self.particles().kind[j];
+        let kind_k = self.particles().kind[k];
+        return self.interactions.cross_term((kind_i, kind_j, kind_k));
+    }
+
+    /// Get the cross term potential acting on the angle `i-j-k` in any
+    /// order, together with the indexes of the first and last particles in
+    /// the order used to define the potential.
+    fn oriented_cross_term(&self, i: usize, j: usize, k: usize) -> Option<(&dyn CrossTermPotential, usize, usize)> {
+        if let Some(potential) = self.cross_term_potential(i, j, k) {
+            return Some((potential, i, k));
+        }
+        return self.cross_term_potential(k, j, i).map(|potential| (potential, k, i));
+    }
+
+    /// Get the energy of the cross term acting on the angle formed by the
+    /// particles at indexes `i`, `j` and `k`, using both bond lengths and
+    /// the angle in a single evaluation.
+    pub fn cross_term_energy(&self, i: usize, j: usize, k: usize) -> f64 {
+        match self.oriented_cross_term(i, j, k) {
+            Some((potential, first, last)) => {
+                let r_1 = self.nearest_image(first, j).norm();
+                let r_2 = self.nearest_image(last, j).norm();
+                let theta = self.angle(first, j, last);
+                potential.energy(r_1, r_2, theta)
+            }
+            None => 0.0,
+        }
+    }
+
+    /// Get the forces acting on the particles at indexes `i`, `j` and `k`
+    /// due to the cross term acting on the `i-j-k` angle, if any.
+    pub fn cross_term_forces(&self, i: usize, j: usize, k: usize) -> Option<(Vector3D, Vector3D, Vector3D)> {
+        let (potential, first, last) = self.oriented_cross_term(i, j, k)?;
+        let r_1 = self.nearest_image(first, j);
+        let r_2 = self.nearest_image(last, j);
+        let (theta, d_first, d_center, d_last) = self.angle_and_derivatives(first, j, last);
+        let (f_1, f_2, f_theta) = potential.forces(r_1.norm(), r_2.norm(), theta);
+
+        let bond_1 = f_1 * r_1.normalized();
+        let bond_2 = f_2 * r_2.normalized();
+        let force_first = bond_1 + f_theta * d_first;
+        let force_last = bond_2 + f_theta * d_last;
+        let force_center = f_theta * d_center - bond_1 - bond_2;
+        if first == i {
+            return Some((force_first, force_center, force_last));
+        } else {
+            return Some((force_last, force_center, force_first));
+        }
+    }
+
     /// Get the coulombic interaction for the system
     pub fn coulomb_potential(&self) -> Option<&dyn CoulombicPotential> {
         self.interactions.coulomb.as_ref().map(|coulomb| &**coulomb)
     }
 
+    /// Get the coulombic interaction for the system, only if at least one
+    /// particle is charged. This is used to skip the computation of the
+    /// electrostatic interactions in uncharged systems.
+    pub(crate) fn charged_coulomb_potential(&self) -> Option<&dyn CoulombicPotential> {
+        let coulomb = self.coulomb_potential()?;
+        if self.particles().charge.iter().all(|&charge| charge == 0.0) {
+            return None;
+        }
+        return Some(coulomb);
+    }
+
     /// Get all global interactions for the system
     pub fn global_potentials(&self) -> &[Box<dyn GlobalPotential>] {
         &self.interactions.globals
     }
 
+    /// Get all external interactions for the system, including the electric
+    /// field
+    pub fn external_potentials(&self) -> impl Iterator<Item = &dyn ExternalPotential> {
+        let field = self.interactions.electric_field.iter();
+        let externals = self.interactions.externals.iter().map(|external| &**external);
+        return field.map(|field| -> &dyn ExternalPotential { field }).chain(externals);
+    }
+
+    /// Get the maximal cutoff of the pair interactions in this system, if
+    /// any. This is the smallest cutoff usable for a neighbor list.
+    pub fn pairs_cutoff(&self) -> Option<f64> {
+        self.interactions.pairs_cutoff()
+    }
+
     /// Get maximum cutoff from `coulomb`, `pairs` and `global` interactions.
     pub fn maximum_cutoff(&self) -> Option<f64> {
         self.interactions.maximum_cutoff()
     }
 }
 
-use crate::compute::{KineticEnergy, PotentialEnergy, TotalEnergy};
+use crate::compute::{KineticEnergy, LambdaDerivative, PotentialEnergy, TotalEnergy};
 use crate::compute::{Pressure, Stress, Virial};
 use crate::compute::{PressureAtTemperature, StressAtTemperature};
 use crate::compute::Compute;
-use crate::compute::Forces;
+use crate::compute::{BondedForces, Forces, NonBondedForces};
 use crate::compute::Temperature;
 use crate::compute::Volume;
 
@@ -245,8 +1035,10 @@ impl System {
     /// Get the number of degrees of freedom in the system
     pub fn degrees_of_freedom(&self) -> usize {
         match self.simulated_degrees_of_freedom {
-            DegreesOfFreedom::Particles => 3 * self.size(),
-            DegreesOfFreedom::Frozen(frozen) => 3 * self.size() - frozen,
+            DegreesOfFreedom::Particles => 3 * (self.size() - self.virtual_sites.len()),
+            DegreesOfFreedom::Frozen(frozen) => {
+                3 * (self.size() - self.virtual_sites.len()) - frozen
+            }
             DegreesOfFreedom::Molecules => 3 * self.molecules().count(),
         }
     }
@@ -261,6 +1053,13 @@ impl System {
         PotentialEnergy.compute(self)
     }
 
+    /// Get the derivative of the potential energy of the system with respect
+    /// to the coupling parameter of the pair potentials, for thermodynamic
+    /// integration.
+    pub fn du_dlambda(&self) -> f64 {
+        LambdaDerivative.compute(self)
+    }
+
     /// Get the total energy of the system.
     pub fn total_energy(&self) -> f64 {
         TotalEnergy.compute(self)
@@ -274,11 +1073,179 @@ impl System {
         }
     }
 
+    /// Remove the center-of-mass velocity of the system, and scale all the
+    /// velocities so that the instantaneous kinetic temperature is exactly
+    /// `temperature`. Massless particles (virtual sites) are not changed.
+    ///
+    /// This uses the kinetic temperature even if an external temperature was
+    /// set with [`System::simulated_temperature`].
+    ///
+    /// [`System::simulated_temperature`]: struct.System.html#method.simulated_temperature
+    ///
+    /// # Panics
+    ///
+    /// If `temperature` is negative, or if the kinetic temperature of the
+    /// system is zero after removing the center-of-mass velocity.
+    pub fn rescale_to_temperature(&mut self, temperature: f64) {
+        assert!(temperature >= 0.0, "the temperature must be positive in System::rescale_to_temperature");
+
+        let total_mass: f64 = self.particles().mass.iter().sum();
+        let mut com_velocity = Vector3D::zero();
+        for (&mass, velocity) in soa_zip!(self.particles(), [mass, velocity]) {
+            com_velocity += velocity * mass / total_mass;
+        }
+        for (&mass, velocity) in soa_zip!(self.particles_mut(), [mass, mut velocity]) {
+            if mass != 0.0 {
+                *velocity -= com_velocity;
+            }
+        }
+
+        let instant_temperature = Temperature.compute(self);
+        assert!(instant_temperature > 0.0, "can not rescale the velocities of a system at zero temperature");
+        let factor = f64::sqrt(temperature / instant_temperature);
+        for velocity in self.particles_mut().velocity {
+            *velocity *= factor;
+        }
+    }
+
+    /// Get the center-of-mass of the particles at the given `molecule`
+    /// indexes, using periodic boundary conditions.
+    ///
+    /// The particles are first unwrapped to the nearest image of the first
+    /// particle, so the result is correct for molecules crossing the cell
+    /// boundaries. The center-of-mass is given close to the first particle,
+    /// and may lie outside of the unit cell.
+    ///
+    /// # Panics
+    ///
+    /// If `molecule` is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use lumol_core::{Molecule, Particle, System, UnitCell, Vector3D};
+    /// let mut system = System::with_cell(UnitCell::cubic(10.0));
+    /// system.add_molecule(Molecule::new(Particle::with_position("O", Vector3D::new(0.5, 5.0, 5.0))));
+    /// system.add_molecule(Molecule::new(Particle::with_position("O", Vector3D::new(9.5, 5.0, 5.0))));
+    ///
+    /// let com = system.molecule_center_of_mass(&[0, 1]);
+    /// assert_eq!(com, Vector3D::new(0.0, 5.0, 5.0));
+    /// ```
+    pub fn molecule_center_of_mass(&self, molecule: &[usize]) -> Vector3D {
+        assert!(!molecule.is_empty(), "can not compute the center-of-mass of an empty molecule");
+        let particles = self.particles();
+        let reference = particles.position[molecule[0]];
+
+        let mut total_mass = 0.0;
+        let mut com = Vector3D::zero();
+        for &i in molecule {
+            let mut delta = particles.position[i] - reference;
+            self.cell.vector_image(&mut delta);
+            total_mass += particles.mass[i];
+            com += particles.mass[i] * delta;
+        }
+        return reference + com / total_mass;
+    }
+
+    /// Get the value of the `dihedral` angle in the system, in radians. The
+    /// angle is 0 for *cis* conformations, π for *trans* conformations, and
+    /// positive for clockwise rotations, following the IUPAC convention. See
+    /// [`UnitCell::dihedral`](struct.UnitCell.html#method.dihedral) for more
+    /// information.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use lumol_core::{Dihedral, Molecule, Particle, System, Vector3D};
+    /// # use std::f64::consts::PI;
+    /// let mut system = System::new();
+    /// system.add_molecule(Molecule::new(Particle::with_position("C", Vector3D::new(0.0, 1.0, 0.0))));
+    /// system.add_molecule(Molecule::new(Particle::with_position("C", Vector3D::new(0.0, 0.0, 0.0))));
+    /// system.add_molecule(Molecule::new(Particle::with_position("C", Vector3D::new(1.5, 0.0, 0.0))));
+    /// system.add_molecule(Molecule::new(Particle::with_position("C", Vector3D::new(1.5, -1.0, 0.0))));
+    ///
+    /// let trans = system.dihedral_angle(Dihedral::new(0, 1, 2, 3));
+    /// assert_eq!(trans, PI);
+    /// ```
+    pub fn dihedral_angle(&self, dihedral: Dihedral) -> f64 {
+        self.dihedral(dihedral.i(), dihedral.j(), dihedral.k(), dihedral.m())
+    }
+
+    /// Get the length of all the bonds in the system, using periodic
+    /// boundary conditions. Each length is returned together with the
+    /// corresponding bond, and the bonds are in the same order as when
+    /// iterating over the bonds of each molecule.
+    pub fn bond_lengths(&self) -> Vec<(Bond, f64)> {
+        let mut lengths = Vec::new();
+        for molecule in self.molecules() {
+            for &bond in molecule.bonds() {
+                lengths.push((bond, self.distance(bond.i(), bond.j())));
+            }
+        }
+        return lengths;
+    }
+
+    /// Get the value of all the angles in the system, in radians, using
+    /// periodic boundary conditions. Each value is returned together with the
+    /// corresponding angle, and the angles are in the same order as when
+    /// iterating over the angles of each molecule.
+    pub fn angle_values(&self) -> Vec<(Angle, f64)> {
+        let mut values = Vec::new();
+        for molecule in self.molecules() {
+            for &angle in molecule.angles() {
+                values.push((angle, self.angle(angle.i(), angle.j(), angle.k())));
+            }
+        }
+        return values;
+    }
+
+    /// Get the value of all the dihedral angles in the system, in radians,
+    /// using periodic boundary conditions and the same convention as
+    /// [`System::dihedral_angle`](struct.System.html#method.dihedral_angle).
+    /// Each value is returned together with the corresponding dihedral angle,
+    /// and the dihedral angles are in the same order as when iterating over
+    /// the dihedral angles of each molecule.
+    pub fn dihedral_values(&self) -> Vec<(Dihedral, f64)> {
+        let mut values = Vec::new();
+        for molecule in self.molecules() {
+            for &dihedral in molecule.dihedrals() {
+                values.push((dihedral, self.dihedral_angle(dihedral)));
+            }
+        }
+        return values;
+    }
+
     /// Get the volume of the system.
     pub fn volume(&self) -> f64 {
         Volume.compute(self)
     }
 
+    /// Get the total charge of the system, *i.e.* the sum of all the
+    /// particles charges.
+    pub fn total_charge(&self) -> f64 {
+        self.particles().charge.iter().sum()
+    }
+
+    /// Get the electrostatic energy per particle of the system, computed
+    /// with the coulombic potential of this system.
+    ///
+    /// For an ionic crystal containing charges $\pm q$, this energy is
+    /// related to the Madelung constant $M$ of the crystal by $E = - M q^2 /
+    /// (8 \pi \epsilon_0 r_0)$, where $r_0$ is the distance between nearest
+    /// neighbors. Comparing the constant computed from this energy with the
+    /// tabulated values for simple crystals is a check of the coulombic
+    /// potential solvers and their parameters.
+    ///
+    /// # Panics
+    ///
+    /// If the system is empty, or if there is no coulombic potential in the
+    /// system.
+    pub fn madelung_energy(&self) -> f64 {
+        assert!(self.size() != 0, "can not compute the Madelung energy of an empty system");
+        assert!(self.coulomb_potential().is_some(), "can not compute the Madelung energy without a coulombic potential");
+        return self.energy_evaluator().coulomb() / self.size() as f64;
+    }
+
     /// Get the virial of the system as a tensor
     pub fn virial(&self) -> Matrix3 {
         Virial.compute(self)
@@ -313,6 +1280,20 @@ impl System {
     pub fn forces(&self) -> Vec<Vector3D> {
         Forces.compute(self)
     }
+
+    /// Get the forces coming from the bonded interactions (bonds, angles,
+    /// dihedral angles, improper dihedral angles and cross terms) acting on
+    /// all the particles in the system
+    pub fn bonded_forces(&self) -> Vec<Vector3D> {
+        BondedForces.compute(self)
+    }
+
+    /// Get the forces coming from all the interactions except the bonded ones
+    /// acting on all the particles in the system. The sum of this and
+    /// `bonded_forces` is equal to `forces`.
+    pub fn non_bonded_forces(&self) -> Vec<Vector3D> {
+        NonBondedForces.compute(self)
+    }
 }
 
 impl System {
@@ -322,16 +1303,51 @@ impl System {
     }
 
     fn check_potentials(&self) {
-        // Check pairs
-        for i in 0..self.size() {
-            let kind_i = self.particles().kind[i];
-            for j in (i + 1)..self.size() {
-                let kind_j = self.particles().kind[j];
-                if self.interactions.pair((kind_i, kind_j)).is_none() {
-                    warn_once!(
-                        "no potential defined for the pair {:?}",
-                        self.sorted_names_pair(i, j)
-                    );
+        for message in self.missing_interactions() {
+            warn_once!("{}", message);
+        }
+
+        // check the need for a coulombic potential
+        let charge2 = self.particles().charge.iter().map(|q| q * q).sum::<f64>();
+        if charge2 > 1e-3 && self.interactions.coulomb.is_none() {
+            warn_once!("no coulombic potential solver defined, but the system is charged");
+        }
+
+        if self.interactions.coulomb.is_some() && self.particles().charge.iter().all(|&charge| charge == 0.0) {
+            warn_once!("a coulombic potential solver is defined, but no particle is charged");
+        }
+    }
+
+    /// Get a description of all the interactions missing in this system,
+    /// *i.e.* the pairs of particles, bonds, angles and dihedral angles
+    /// without an associated potential. Each missing interaction is only
+    /// reported once.
+    pub fn missing_interactions(&self) -> Vec<String> {
+        let mut missing = Vec::new();
+        let mut report = |message: String| {
+            if !missing.contains(&message) {
+                missing.push(message);
+            }
+        };
+
+        // Check pairs, using the first particle of each kind and the number
+        // of particles with this kind
+        let kinds = self.particles().kind;
+        let mut representatives: Vec<(usize, usize)> = Vec::new();
+        for (i, &kind) in kinds.iter().enumerate() {
+            match representatives.iter_mut().find(|representative| kinds[representative.0] == kind) {
+                Some(representative) => representative.1 += 1,
+                None => representatives.push((i, 1)),
+            }
+        }
+
+        for (a, &(i, count)) in representatives.iter().enumerate() {
+            for &(j, _) in &representatives[a..] {
+                if i == j && count < 2 {
+                    continue;
+                }
+                if self.interactions.pair((kinds[i], kinds[j])).is_none() {
+                    report(format!("no potential defined for the pair {:?}", self.sorted_names_pair(i, j)));
                 }
             }
         }
@@ -339,49 +1355,45 @@ impl System {
         // check molecular potentials
         for molecule in self.molecules() {
             for bond in molecule.bonds() {
-                let kind_i = self.particles().kind[bond.i()];
-                let kind_j = self.particles().kind[bond.j()];
+                let kind_i = kinds[bond.i()];
+                let kind_j = kinds[bond.j()];
                 if self.interactions.bond((kind_i, kind_j)).is_none() {
-                    warn_once!(
+                    report(format!(
                         "no potential defined for the bond {:?}",
                         self.sorted_names_pair(bond.i(), bond.j())
-                    );
+                    ));
                 }
             }
 
             for angle in molecule.angles() {
-                let kind_i = self.particles().kind[angle.i()];
-                let kind_j = self.particles().kind[angle.j()];
-                let kind_k = self.particles().kind[angle.k()];
+                let kind_i = kinds[angle.i()];
+                let kind_j = kinds[angle.j()];
+                let kind_k = kinds[angle.k()];
                 if self.interactions.angle((kind_i, kind_j, kind_k)).is_none() {
-                    warn_once!(
+                    report(format!(
                         "no potential defined for the angle {:?}",
                         self.sorted_names_angle(angle.i(), angle.j(), angle.k())
-                    );
+                    ));
                 }
             }
 
             for dihedral in molecule.dihedrals() {
-                let kind_i = self.particles().kind[dihedral.i()];
-                let kind_j = self.particles().kind[dihedral.j()];
-                let kind_k = self.particles().kind[dihedral.k()];
-                let kind_m = self.particles().kind[dihedral.m()];
+                let kind_i = kinds[dihedral.i()];
+                let kind_j = kinds[dihedral.j()];
+                let kind_k = kinds[dihedral.k()];
+                let kind_m = kinds[dihedral.m()];
                 if self.interactions.dihedral((kind_i, kind_j, kind_k, kind_m)).is_none() {
-                    warn_once!(
+                    report(format!(
                         "no potential defined for the dihedral angle {:?}",
                         self.sorted_names_dihedral(
                             dihedral.i(), dihedral.j(), dihedral.k(), dihedral.m()
                         )
-                    );
+                    ));
                 }
             }
         }
 
-        // check the need for a coulombic potential
-        let charge2 = self.particles().charge.iter().map(|q| q * q).sum::<f64>();
-        if charge2 > 1e-3 && self.interactions.coulomb.is_none() {
-            warn_once!("no coulombic potential solver defined, but the system is charged");
-        }
+        return missing;
     }
 
     fn sorted_names_pair(&self, i: usize, j: usize) -> (&str, &str) {
@@ -455,6 +1467,14 @@ mod tests {
         system.simulated_temperature(Some(-1.0));
     }
 
+    #[test]
+    #[should_panic(expected = "cutoff of 6 A, bigger than half of the smallest cell length (5 A)")]
+    fn cutoff_bigger_than_cell() {
+        use crate::{NullPotential, PairInteraction, UnitCell};
+        let mut system = System::with_cell(UnitCell::ortho(12.0, 12.0, 10.0));
+        system.set_pair_potential(("A", "A"), PairInteraction::new(Box::new(NullPotential), 6.0));
+    }
+
     #[test]
     fn deref() {
         let mut system = System::new();
@@ -501,6 +1521,268 @@ mod tests {
         assert_eq!(composition.particles(ParticleKind(3)), 1);
     }
 
+    #[test]
+    fn assign_masses_from_names() {
+        let mut system = System::new();
+        system.add_molecule(Molecule::new(Particle::new("He")));
+        system.add_molecule(Molecule::new(Particle::new("He")));
+        system.add_molecule(Molecule::new(Particle::new("Xx")));
+        for mass in system.particles_mut().mass {
+            *mass = 1.0;
+        }
+
+        system.assign_masses_from_names();
+        assert_eq!(system.particles().mass[0], 4.002602);
+        assert_eq!(system.particles().mass[1], 4.002602);
+        // unknown particles keep their mass
+        assert_eq!(system.particles().mass[2], 1.0);
+    }
+
+    #[test]
+    fn total_charge() {
+        let mut system = System::new();
+        assert_eq!(system.total_charge(), 0.0);
+
+        system.add_molecule(Molecule::new(Particle::new("Na")));
+        system.add_molecule(Molecule::new(Particle::new("Cl")));
+        system.add_molecule(Molecule::new(Particle::new("Cl")));
+        system.particles_mut().charge[0] = 1.0;
+        system.particles_mut().charge[1] = -1.0;
+        assert_eq!(system.total_charge(), 0.0);
+
+        system.particles_mut().charge[2] = -1.0;
+        assert_eq!(system.total_charge(), -1.0);
+    }
+
+    #[test]
+    fn madelung_energy() {
+        use crate::{Ewald, SharedEwald, UnitCell, Vector3D};
+        use crate::consts::FOUR_PI_EPSILON_0;
+        use approx::assert_relative_eq;
+
+        // NaCl rock-salt structure, with 2x2x2 conventional cells
+        let a = 5.64;
+        let mut system = System::with_cell(UnitCell::cubic(2.0 * a));
+        for i in 0..4 {
+            for j in 0..4 {
+                for k in 0..4 {
+                    let (name, charge) = if (i + j + k) % 2 == 0 {("Na", 1.0)} else {("Cl", -1.0)};
+                    let mut particle = Particle::with_position(
+                        name, Vector3D::new(i as f64, j as f64, k as f64) * a / 2.0
+                    );
+                    particle.charge = charge;
+                    system.add_molecule(Molecule::new(particle));
+                }
+            }
+        }
+        let ewald = Ewald::with_accuracy(5.5, 1e-6, &system);
+        system.set_coulomb_potential(Box::new(SharedEwald::new(ewald)));
+
+        let nearest = a / 2.0;
+        let madelung = -2.0 * FOUR_PI_EPSILON_0 * nearest * system.madelung_energy();
+        assert_relative_eq!(madelung, 1.747564594633182, max_relative = 1e-5);
+    }
+
+    #[test]
+    #[should_panic(expected = "can not compute the Madelung energy without a coulombic potential")]
+    fn madelung_energy_without_coulomb() {
+        let mut system = System::new();
+        system.add_molecule(Molecule::new(Particle::new("Na")));
+        let _ = system.madelung_energy();
+    }
+
+    #[test]
+    fn uncharged_coulomb() {
+        use crate::{UnitCell, Vector3D};
+        use crate::energy::Wolf;
+
+        let mut system = System::with_cell(UnitCell::cubic(20.0));
+        system.add_molecule(Molecule::new(Particle::with_position("Na", Vector3D::new(0.0, 0.0, 0.0))));
+        system.add_molecule(Molecule::new(Particle::with_position("Cl", Vector3D::new(2.5, 0.0, 0.0))));
+        system.set_coulomb_potential(Box::new(Wolf::new(8.0)));
+
+        assert!(system.coulomb_potential().is_some());
+        assert!(system.charged_coulomb_potential().is_none());
+        assert_eq!(system.energy_evaluator().coulomb(), 0.0);
+        assert_eq!(system.forces(), vec![Vector3D::zero(); 2]);
+
+        system.particles_mut().charge[0] = 1.0;
+        system.particles_mut().charge[1] = -1.0;
+        assert!(system.charged_coulomb_potential().is_some());
+        assert!(system.energy_evaluator().coulomb() < 0.0);
+    }
+
+    #[test]
+    fn rescale_to_temperature() {
+        use crate::Vector3D;
+        use approx::assert_relative_eq;
+
+        let mut system = System::new();
+        for (i, name) in ["He", "Ar", "Ar", "Xe"].iter().enumerate() {
+            let mut particle = Particle::new(*name);
+            let x = i as f64;
+            particle.velocity = Vector3D::new(f64::sin(x) + 0.3, f64::cos(2.0 * x), 0.5 * x);
+            system.add_molecule(Molecule::new(particle));
+        }
+
+        let momentum = |system: &System| {
+            let mut momentum = Vector3D::zero();
+            for i in 0..system.size() {
+                momentum += system.particles().mass[i] * system.particles().velocity[i];
+            }
+            return momentum;
+        };
+
+        assert!(momentum(&system).norm() > 1.0);
+        system.rescale_to_temperature(300.0);
+        assert_relative_eq!(system.temperature(), 300.0, max_relative = 1e-12);
+        // The center-of-mass velocity is removed
+        assert!(momentum(&system).norm() < 1e-12);
+
+        system.rescale_to_temperature(42.0);
+        assert_relative_eq!(system.temperature(), 42.0, max_relative = 1e-12);
+    }
+
+    #[test]
+    fn dihedral_angle() {
+        use crate::{Dihedral, Vector3D};
+        use approx::assert_relative_eq;
+        use std::f64::consts::PI;
+
+        let mut system = System::new();
+        for position in &[[0.0, 1.0, 0.0], [0.0, 0.0, 0.0], [1.5, 0.0, 0.0], [1.5, 1.0, 0.0]] {
+            system.add_molecule(Molecule::new(Particle::with_position("C", Vector3D::from(*position))));
+        }
+        let dihedral = Dihedral::new(0, 1, 2, 3);
+
+        // cis conformation
+        assert_eq!(system.dihedral_angle(dihedral), 0.0);
+        // The value does not depend on the order of the particles
+        assert_eq!(system.dihedral_angle(Dihedral::new(3, 2, 1, 0)), 0.0);
+
+        // trans conformation
+        system.particles_mut().position[3] = Vector3D::new(1.5, -1.0, 0.0);
+        assert_eq!(system.dihedral_angle(dihedral), PI);
+
+        // Looking along the 1 -> 2 bond, the last particle is rotated
+        // clockwise by 90° with respect to the first one
+        system.particles_mut().position[3] = Vector3D::new(1.5, 0.0, 1.0);
+        assert_relative_eq!(system.dihedral_angle(dihedral), PI / 2.0, epsilon = 1e-15);
+        system.particles_mut().position[3] = Vector3D::new(1.5, 0.0, -1.0);
+        assert_relative_eq!(system.dihedral_angle(dihedral), -PI / 2.0, epsilon = 1e-15);
+    }
+
+    #[test]
+    fn internal_coordinates() {
+        use crate::{Angle, Bond, Dihedral, UnitCell, Vector3D};
+        use approx::assert_relative_eq;
+        use std::f64::consts::PI;
+
+        // A chain of four particles crossing the cell boundaries
+        let mut system = System::with_cell(UnitCell::cubic(10.0));
+        for position in &[[9.5, 1.0, 0.0], [0.5, 0.0, 0.0], [2.0, 0.0, 0.0], [2.0, 0.0, 1.0]] {
+            system.add_molecule(Molecule::new(Particle::with_position("C", Vector3D::from(*position))));
+        }
+        let _ = system.add_bond(0, 1);
+        let _ = system.add_bond(1, 2);
+        let _ = system.add_bond(2, 3);
+
+        let lengths = system.bond_lengths();
+        assert_eq!(lengths.len(), 3);
+        for (bond, length) in lengths {
+            if bond == Bond::new(0, 1) {
+                assert_relative_eq!(length, f64::sqrt(2.0), max_relative = 1e-12);
+            } else if bond == Bond::new(1, 2) {
+                assert_relative_eq!(length, 1.5, max_relative = 1e-12);
+            } else {
+                assert_eq!(bond, Bond::new(2, 3));
+                assert_relative_eq!(length, 1.0, max_relative = 1e-12);
+            }
+        }
+
+        let angles = system.angle_values();
+        assert_eq!(angles.len(), 2);
+        for (angle, value) in angles {
+            if angle == Angle::new(0, 1, 2) {
+                assert_relative_eq!(value, 3.0 * PI / 4.0, max_relative = 1e-12);
+            } else {
+                assert_eq!(angle, Angle::new(1, 2, 3));
+                assert_relative_eq!(value, PI / 2.0, max_relative = 1e-12);
+            }
+        }
+
+        let dihedrals = system.dihedral_values();
+        assert_eq!(dihedrals.len(), 1);
+        assert_eq!(dihedrals[0].0, Dihedral::new(0, 1, 2, 3));
+        assert_relative_eq!(dihedrals[0].1, PI / 2.0, max_relative = 1e-12);
+    }
+
+    #[test]
+    fn move_particle_energy_delta() {
+        use crate::{LennardJones, NeighborList, PairInteraction, Vector3D};
+        use crate::utils::system_from_xyz;
+        use approx::assert_relative_eq;
+
+        let mut xyz = String::from("27\ncell: 12.0\n");
+        for i in 0..3 {
+            for j in 0..3 {
+                for k in 0..3 {
+                    xyz += &format!("Ar {} {} {}\n", 4.0 * i as f64, 4.0 * j as f64, 4.0 * k as f64);
+                }
+            }
+        }
+        let mut system = system_from_xyz(&xyz);
+        system.set_pair_potential(
+            ("Ar", "Ar"),
+            PairInteraction::new(Box::new(LennardJones { sigma: 3.4, epsilon: 0.5 }), 5.5),
+        );
+
+        let initial = system.energy_evaluator().pairs();
+        let mut accepted = 0.0;
+        for step in 0..200 {
+            let i = (7 * step) % system.size();
+            let x = step as f64;
+            let delta = 0.3 * Vector3D::new(f64::sin(x), f64::cos(1.3 * x), f64::sin(2.1 * x + 0.5));
+            let new_position = system.particles().position[i] + delta;
+
+            let cost = system.move_particle_energy_delta(i, new_position);
+            if cost < 0.0 || step % 3 == 0 {
+                accepted += cost;
+                system.particles_mut().position[i] = new_position;
+            }
+        }
+
+        let last = system.energy_evaluator().pairs();
+        assert_relative_eq!(initial + accepted, last, epsilon = 1e-12, max_relative = 1e-10);
+
+        // Using the neighbor list gives the same result
+        let mut with_list = system.clone();
+        with_list.set_neighbor_list(NeighborList::with_skin(5.5, 1.2));
+        let builds = with_list.neighbor_list().unwrap().builds();
+        for step in 0..50 {
+            let i = (5 * step) % system.size();
+            let x = step as f64;
+            let delta = 0.3 * Vector3D::new(f64::cos(x), f64::sin(0.7 * x), f64::cos(1.9 * x));
+            let new_position = system.particles().position[i] + delta;
+            assert!(with_list.neighbor_list().unwrap().is_valid_after_move(i, new_position));
+            assert_relative_eq!(
+                with_list.move_particle_energy_delta(i, new_position),
+                system.move_particle_energy_delta(i, new_position),
+                epsilon = 1e-12, max_relative = 1e-10
+            );
+        }
+        assert_eq!(with_list.neighbor_list().unwrap().builds(), builds);
+
+        // Large moves check all the particles
+        let new_position = system.particles().position[4] + Vector3D::new(2.0, 0.0, 0.0);
+        assert!(!with_list.neighbor_list().unwrap().is_valid_after_move(4, new_position));
+        assert_relative_eq!(
+            with_list.move_particle_energy_delta(4, new_position),
+            system.move_particle_energy_delta(4, new_position),
+            epsilon = 1e-12, max_relative = 1e-10
+        );
+    }
+
     #[test]
     fn missing_interaction() {
         let mut system = System::new();
@@ -570,4 +1852,244 @@ no potential defined for the dihedral angle ("Ar", "Ar", "He", "He")
             assert!(EXPECTED_WARNINGS.contains(line));
         }
     }
+
+    #[test]
+    fn replicate() {
+        use crate::{LennardJones, PairInteraction, UnitCell, Vector3D, Wolf};
+        use crate::utils::system_from_xyz;
+        use approx::assert_relative_eq;
+
+        // NaCl crystal, using two conventional cells in each direction to be
+        // able to use a cutoff bigger than the nearest neighbors distance
+        const A: f64 = 5.64;
+        let basis = [
+            ("Na", 0.0, 0.0, 0.0), ("Na", 0.5, 0.5, 0.0), ("Na", 0.5, 0.0, 0.5), ("Na", 0.0, 0.5, 0.5),
+            ("Cl", 0.5, 0.0, 0.0), ("Cl", 0.0, 0.5, 0.0), ("Cl", 0.0, 0.0, 0.5), ("Cl", 0.5, 0.5, 0.5),
+        ];
+        let mut xyz = format!("64\ncell: {}\n", 2.0 * A);
+        for i in 0..2 {
+            for j in 0..2 {
+                for k in 0..2 {
+                    for &(name, x, y, z) in &basis {
+                        let position = A * Vector3D::new(x + i as f64, y + j as f64, z + k as f64);
+                        xyz += &format!("{} {} {} {}\n", name, position[0], position[1], position[2]);
+                    }
+                }
+            }
+        }
+
+        let mut system = system_from_xyz(&xyz);
+        for i in 0..system.size() {
+            let charge = if system.particles().name[i] == "Na" { 1.0 } else { -1.0 };
+            system.particles_mut().charge[i] = charge;
+        }
+        let lj = LennardJones { sigma: 3.0, epsilon: 0.1 };
+        system.set_pair_potential(("Na", "Na"), PairInteraction::new(Box::new(lj), 5.5));
+        system.set_pair_potential(("Na", "Cl"), PairInteraction::new(Box::new(lj), 5.5));
+        system.set_pair_potential(("Cl", "Cl"), PairInteraction::new(Box::new(lj), 5.5));
+        system.set_coulomb_potential(Box::new(Wolf::new(5.5)));
+
+        let supercell = system.replicate(2, 2, 2);
+        assert_eq!(supercell.size(), 8 * system.size());
+        assert_eq!(supercell.molecules().count(), 8 * system.molecules().count());
+        assert_eq!(supercell.cell, UnitCell::cubic(4.0 * A));
+        assert_eq!(supercell.total_charge(), 0.0);
+
+        let energy = system.potential_energy() / system.size() as f64;
+        let replicated = supercell.potential_energy() / supercell.size() as f64;
+        assert_relative_eq!(energy, replicated, max_relative = 1e-10);
+    }
+
+    #[test]
+    fn replicate_molecules() {
+        use crate::{Bond, UnitCell, Vector3D, VirtualSite};
+        use approx::assert_relative_eq;
+
+        let mut system = System::with_cell(UnitCell::triclinic(5.0, 6.0, 7.0, 80.0, 90.0, 110.0));
+        system.add_molecule(Molecule::new(Particle::with_position("O", Vector3D::new(4.5, 0.0, 0.0))));
+        system.add_molecule(Molecule::new(Particle::with_position("H", Vector3D::new(5.5, 0.0, 0.0))));
+        system.add_molecule(Molecule::new(Particle::with_position("H", Vector3D::new(4.5, 1.0, 0.0))));
+        system.add_molecule(Molecule::new(Particle::with_position("M", Vector3D::zero())));
+        let _ = system.add_bond(0, 1);
+        let _ = system.add_bond(0, 2);
+        let _ = system.add_bond(0, 3);
+        system.add_virtual_site(VirtualSite::new(3, [0, 1, 2], [0.8, 0.1, 0.1]));
+
+        let supercell = system.replicate(3, 1, 2);
+        assert_eq!(supercell.size(), 24);
+        assert_eq!(supercell.molecules().count(), 6);
+        assert_eq!(supercell.virtual_sites().len(), 6);
+        assert_relative_eq!(supercell.cell.a(), 15.0, max_relative = 1e-12);
+        assert_relative_eq!(supercell.cell.b(), 6.0, max_relative = 1e-12);
+        assert_relative_eq!(supercell.cell.c(), 14.0, max_relative = 1e-12);
+        assert_relative_eq!(supercell.cell.alpha(), 80.0, max_relative = 1e-12);
+        assert_relative_eq!(supercell.cell.gamma(), 110.0, max_relative = 1e-12);
+
+        // The image (2, 0, 1) is the last one
+        let translation = system.cell.cartesian(&Vector3D::new(2.0, 0.0, 1.0));
+        let molecule = supercell.molecule(5);
+        assert_eq!(molecule.bonds().len(), 3);
+        for bond in system.molecule(0).bonds() {
+            assert!(molecule.bonds().contains(&Bond::new(bond.i() + 20, bond.j() + 20)));
+        }
+        for i in 0..4 {
+            let expected = system.particles().position[i] + translation;
+            assert_eq!(supercell.particles().position[20 + i], expected);
+        }
+
+        assert_eq!(supercell.virtual_sites()[5].site(), 23);
+        assert_eq!(supercell.virtual_sites()[5].parents(), [20, 21, 22]);
+    }
+
+    #[test]
+    fn freeze() {
+        use crate::{Harmonic, PairInteraction, UnitCell, Vector3D};
+        let mut system = System::with_cell(UnitCell::cubic(20.0));
+        system.add_molecule(Molecule::new(Particle::with_position("Ar", [0.0, 0.0, 0.0].into())));
+        system.add_molecule(Molecule::new(Particle::with_position("Ar", [3.0, 0.0, 0.0].into())));
+        system.add_molecule(Molecule::new(Particle::with_position("Ar", [0.0, 3.0, 0.0].into())));
+        system.set_pair_potential(
+            ("Ar", "Ar"),
+            PairInteraction::new(Box::new(Harmonic { x0: 2.0, k: 1.0 }), 8.0)
+        );
+        for velocity in system.particles_mut().velocity {
+            *velocity = [1.0, 0.0, 0.0].into();
+        }
+
+        let forces = system.forces();
+        let energy = system.potential_energy();
+        system.freeze(&[2, 0, 2]);
+        assert_eq!(system.frozen(), &[0, 2]);
+        assert!(system.is_frozen(0));
+        assert!(!system.is_frozen(1));
+        assert!(system.is_frozen(2));
+
+        assert_eq!(system.particles().velocity[0], Vector3D::zero());
+        assert_eq!(system.particles().velocity[1], Vector3D::new(1.0, 0.0, 0.0));
+        assert_eq!(system.particles().velocity[2], Vector3D::zero());
+
+        // Frozen particles still interact with the other particles
+        assert_eq!(system.potential_energy(), energy);
+        let frozen_forces = system.forces();
+        assert_eq!(frozen_forces[0], Vector3D::zero());
+        assert_eq!(frozen_forces[1], forces[1]);
+        assert_eq!(frozen_forces[2], Vector3D::zero());
+
+        let replicated = system.replicate(2, 1, 1);
+        assert_eq!(replicated.frozen(), &[0, 2, 3, 5]);
+        assert!(system.subsystem(&[0, 1]).frozen().is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "index 3 is out of bounds in System::freeze")]
+    fn freeze_out_of_bounds() {
+        let mut system = System::new();
+        system.add_molecule(Molecule::new(Particle::new("Ar")));
+        system.freeze(&[3]);
+    }
+
+    #[test]
+    fn update_indexes() {
+        use crate::{DrudeParticle, VirtualSite};
+
+        let mut system = System::new();
+        system.add_molecule(Molecule::new(Particle::new("Ar")));
+        let mut molecule = Molecule::new(Particle::new("C"));
+        for i in 0..4 {
+            molecule.add_particle_bonded_to(i, Particle::new("C"));
+        }
+        system.add_molecule(molecule);
+        system.add_molecule(Molecule::new(Particle::new("Ar")));
+        assert_eq!(system.size(), 7);
+
+        system.add_virtual_site(VirtualSite::new(4, [1, 2, 3], [0.8, 0.1, 0.1]));
+        system.add_drude_particle(DrudeParticle::new(5, 3, 1.0));
+        system.freeze(&[0, 6]);
+
+        // The last particle is moved next to the first one
+        let permutations = system.add_bond(0, 6);
+        assert!(!permutations.is_empty());
+        assert_eq!(system.virtual_sites()[0].site(), 5);
+        assert_eq!(system.virtual_sites()[0].parents(), [2, 3, 4]);
+        assert_eq!(system.drude_particles()[0].drude(), 6);
+        assert_eq!(system.drude_particles()[0].core(), 4);
+        assert_eq!(system.frozen(), &[0, 1]);
+        system.update_virtual_sites();
+
+        let molid = system.molecule_id(0);
+        system.remove_molecule(molid);
+        assert_eq!(system.size(), 5);
+        assert_eq!(system.virtual_sites()[0].site(), 3);
+        assert_eq!(system.virtual_sites()[0].parents(), [0, 1, 2]);
+        assert_eq!(system.drude_particles()[0].drude(), 4);
+        assert_eq!(system.drude_particles()[0].core(), 2);
+        assert!(system.frozen().is_empty());
+        system.update_virtual_sites();
+
+        system.remove_molecule(0);
+        assert_eq!(system.size(), 0);
+        assert!(system.virtual_sites().is_empty());
+        assert!(system.drude_particles().is_empty());
+        system.update_virtual_sites();
+    }
+
+    #[test]
+    fn subsystem() {
+        use crate::{Bond, Vector3D};
+
+        let mut system = System::new();
+        system.add_molecule(Molecule::new(Particle::with_position("O", Vector3D::new(0.0, 0.0, 0.0))));
+        system.add_molecule(Molecule::new(Particle::with_position("H", Vector3D::new(1.0, 0.0, 0.0))));
+        system.add_molecule(Molecule::new(Particle::with_position("H", Vector3D::new(0.0, 1.0, 0.0))));
+        let _ = system.add_bond(0, 1);
+        let _ = system.add_bond(0, 2);
+        system.add_molecule(Molecule::new(Particle::with_position("C", Vector3D::new(5.0, 0.0, 0.0))));
+        system.add_molecule(Molecule::new(Particle::with_position("O", Vector3D::new(6.2, 0.0, 0.0))));
+        let _ = system.add_bond(3, 4);
+        system.particles_mut().charge[1] = 0.4;
+
+        let water = system.subsystem(&[0, 1, 2]);
+        assert_eq!(water.size(), 3);
+        assert_eq!(water.molecules().count(), 1);
+        assert_eq!(water.molecule(0).bonds().len(), 2);
+        assert_eq!(water.particles().charge[1], 0.4);
+        assert_eq!(water.cell, system.cell);
+
+        let co = system.subsystem(&[4, 3]);
+        assert_eq!(co.size(), 2);
+        assert_eq!(co.molecules().count(), 1);
+        assert!(co.molecule(0).bonds().contains(&Bond::new(0, 1)));
+        assert_eq!(co.particles().name[0], "C");
+        assert_eq!(co.particles().position[1], Vector3D::new(6.2, 0.0, 0.0));
+
+        // the bonds to particles which are not selected are removed
+        let hydrogens = system.subsystem(&[1, 2]);
+        assert_eq!(hydrogens.molecules().count(), 2);
+        assert!(hydrogens.molecule(0).bonds().is_empty());
+    }
+
+    #[test]
+    fn molecule_center_of_mass() {
+        use crate::{UnitCell, Vector3D};
+        use approx::assert_relative_eq;
+
+        let mut system = System::with_cell(UnitCell::ortho(10.0, 12.0, 14.0));
+        system.add_molecule(Molecule::new(Particle::with_position("C", Vector3D::new(9.5, 6.0, 7.0))));
+        system.add_molecule(Molecule::new(Particle::with_position("O", Vector3D::new(0.7, 6.0, 7.0))));
+        system.add_molecule(Molecule::new(Particle::with_position("H", Vector3D::new(5.0, 11.8, 13.9))));
+        system.add_molecule(Molecule::new(Particle::with_position("H", Vector3D::new(5.0, 0.4, 0.3))));
+
+        // the naive center-of-mass would be close to the box center
+        let masses = system.particles().mass;
+        let expected = 9.5 + masses[1] * 1.2 / (masses[0] + masses[1]);
+        let com = system.molecule_center_of_mass(&[0, 1]);
+        assert_relative_eq!(com, Vector3D::new(expected, 6.0, 7.0), epsilon = 1e-12);
+
+        // split along two directions, with the same masses
+        let com = system.molecule_center_of_mass(&[2, 3]);
+        assert_relative_eq!(com, Vector3D::new(5.0, 12.1, 14.1), epsilon = 1e-12);
+
+        let com = system.molecule_center_of_mass(&[3]);
+        assert_eq!(com, Vector3D::new(5.0, 0.4, 0.3));
+    }
 }
@@ -0,0 +1,138 @@
+// Lumol, an extensible molecular simulation engine
+// Copyright (C) Lumol's contributors — BSD license
+
+//! Virtual sites are massless interaction points, whose position is computed
+//! from the positions of other particles.
+//!
+//! Virtual sites are used in some force fields to add charges or interaction
+//! centers outside of the atomic positions, as the M site of the TIP4P water
+//! model. They are not propagated by the integrators: their positions are
+//! recomputed from the parents particles after each move, and the forces
+//! acting on them are redistributed to the parents particles.
+use crate::{Configuration, Vector3D};
+
+/// A virtual site, placed at a linear combination of the positions of three
+/// parent particles: `r_site = w_1 r_1 + w_2 r_2 + w_3 r_3`. The weights must
+/// sum to 1 for the site position to be independent of the origin.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct VirtualSite {
+    /// Index of the virtual site in the configuration
+    site: usize,
+    /// Indexes of the parents particles
+    parents: [usize; 3],
+    /// Weights of the parents particles in the linear combination
+    weights: [f64; 3],
+}
+
+impl VirtualSite {
+    /// Create a new virtual site for the particle at index `site`, with the
+    /// given `parents` particles and `weights`.
+    ///
+    /// # Panics
+    ///
+    /// If the weights do not sum to 1, or if the site is one of its own
+    /// parents.
+    pub fn new(site: usize, parents: [usize; 3], weights: [f64; 3]) -> VirtualSite {
+        let sum = weights[0] + weights[1] + weights[2];
+        assert!(
+            f64::abs(sum - 1.0) < 1e-12,
+            "the weights of a virtual site must sum to 1, got {}", sum
+        );
+        assert!(
+            !parents.contains(&site),
+            "a virtual site can not be one of its own parents"
+        );
+        VirtualSite {
+            site: site,
+            parents: parents,
+            weights: weights,
+        }
+    }
+
+    /// Get the index of the virtual site
+    pub fn site(&self) -> usize {
+        self.site
+    }
+
+    /// Get the indexes of the parents particles of this virtual site
+    pub fn parents(&self) -> [usize; 3] {
+        self.parents
+    }
+
+    /// Get the weights of the parents particles of this virtual site
+    pub fn weights(&self) -> [f64; 3] {
+        self.weights
+    }
+
+    /// Compute the position of this virtual site in the `configuration`. The
+    /// nearest images of the second and third parents with respect to the
+    /// first one are used, so that the site is placed correctly for molecules
+    /// crossing the periodic boundaries.
+    pub fn position(&self, configuration: &Configuration) -> Vector3D {
+        let [a, b, c] = self.parents;
+        let origin = configuration.particles().position[a];
+        let rab = configuration.nearest_image(b, a);
+        let rac = configuration.nearest_image(c, a);
+        return origin + self.weights[1] * rab + self.weights[2] * rac;
+    }
+
+    /// Redistribute the force acting on the virtual site to its parents
+    /// particles, and set the force on the site to zero.
+    pub fn distribute_force(&self, forces: &mut [Vector3D]) {
+        let force = forces[self.site];
+        for (&parent, &weight) in self.parents.iter().zip(&self.weights) {
+            forces[parent] += weight * force;
+        }
+        forces[self.site] = Vector3D::zero();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Molecule, Particle, System, UnitCell};
+    use approx::assert_ulps_eq;
+
+    #[test]
+    #[should_panic(expected = "the weights of a virtual site must sum to 1, got 0.5")]
+    fn bad_weights() {
+        let _ = VirtualSite::new(3, [0, 1, 2], [0.5, 0.0, 0.0]);
+    }
+
+    #[test]
+    #[should_panic(expected = "a virtual site can not be one of its own parents")]
+    fn own_parent() {
+        let _ = VirtualSite::new(1, [0, 1, 2], [0.5, 0.25, 0.25]);
+    }
+
+    #[test]
+    fn position() {
+        let mut system = System::with_cell(UnitCell::cubic(10.0));
+        system.add_molecule(Molecule::new(Particle::with_position("O", [9.5, 0.0, 0.0].into())));
+        system.add_molecule(Molecule::new(Particle::with_position("H", [0.5, 1.0, 0.0].into())));
+        system.add_molecule(Molecule::new(Particle::with_position("H", [0.5, -1.0, 0.0].into())));
+        system.add_molecule(Molecule::new(Particle::new("M")));
+
+        let site = VirtualSite::new(3, [0, 1, 2], [0.5, 0.25, 0.25]);
+        assert_ulps_eq!(site.position(&system), Vector3D::new(10.0, 0.0, 0.0));
+
+        system.add_virtual_site(site);
+        assert_eq!(system.virtual_sites(), &[site]);
+        assert_eq!(system.particles().mass[3], 0.0);
+        assert_ulps_eq!(system.particles().position[3], Vector3D::new(10.0, 0.0, 0.0));
+        assert_eq!(system.degrees_of_freedom(), 9);
+    }
+
+    #[test]
+    fn distribute_force() {
+        let site = VirtualSite::new(3, [0, 1, 2], [0.5, 0.25, 0.25]);
+        let mut forces = vec![Vector3D::zero(); 4];
+        forces[3] = Vector3D::new(4.0, 0.0, -8.0);
+        site.distribute_force(&mut forces);
+
+        assert_eq!(forces[0], Vector3D::new(2.0, 0.0, -4.0));
+        assert_eq!(forces[1], Vector3D::new(1.0, 0.0, -2.0));
+        assert_eq!(forces[2], Vector3D::new(1.0, 0.0, -2.0));
+        assert_eq!(forces[3], Vector3D::zero());
+    }
+}
@@ -35,7 +35,9 @@ lazy_static!{
         let mut map = BTreeMap::new();
         // Distances units.
         assert!(map.insert("A", 1.0).is_none());
+        assert!(map.insert("Å", 1.0).is_none());
         assert!(map.insert("nm", 10.0).is_none());
+        assert!(map.insert("cm", 1e8).is_none());
         assert!(map.insert("pm", 1e-2).is_none());
         assert!(map.insert("fm", 1e-5).is_none());
         assert!(map.insert("m", 1e10).is_none());
@@ -334,7 +336,11 @@ fn read_expr(stream: &mut Vec<Token>) -> Result<UnitExpr, ParseError> {
                 let pow = match stream.pop() {
                     Some(pow) => {
                         match pow {
-                            Token::Value(value) => value.parse()?,
+                            Token::Value(value) => value.parse().map_err(|err| {
+                                ParseError::MalformedExpr(
+                                    format!("Invalid power '{}' after '^': {}", value, err),
+                                )
+                            })?,
                             _ => {
                                 return Err(ParseError::MalformedExpr(
                                     format!("Invalid value after ^: {}", pow.as_str()),
@@ -349,7 +355,7 @@ fn read_expr(stream: &mut Vec<Token>) -> Result<UnitExpr, ParseError> {
                     }
                 };
                 let expr = read_expr(stream).map_err(|err| {
-                    ParseError::MalformedExpr(format!("Error in unit at the left of '*': {}", err))
+                    ParseError::MalformedExpr(format!("Error in unit at the left of '^': {}", err))
                 })?;
                 Ok(UnitExpr::Pow(Box::new(expr), pow))
             }
@@ -456,6 +462,7 @@ mod test {
     fn eval() {
         assert_eq!(UnitExpr::parse("A").unwrap(), UnitExpr::Val(1.0));
         assert_eq!(UnitExpr::parse("nm").unwrap(), UnitExpr::Val(10.0));
+        assert_eq!(UnitExpr::parse("Å").unwrap(), UnitExpr::Val(1.0));
 
         assert_eq!(UnitExpr::parse("bohr/fs").unwrap().eval(), 0.52917720859);
         assert_eq!(UnitExpr::parse("(Ry / rad^-3   )").unwrap().eval(), 0.1312749878912494);
@@ -464,6 +471,11 @@ mod test {
         assert_eq!(UnitExpr::parse("(kcal/mol/A)^2").unwrap().eval(), 1.7505856024515547e-7);
 
         assert_ulps_eq!(UnitExpr::parse("kcal/mol/A^2").unwrap().eval(), 4.184e-4, epsilon = 1e-9);
+        assert_ulps_eq!(UnitExpr::parse("kcal/(mol*A^2)").unwrap().eval(), 4.184e-4, epsilon = 1e-9);
+        assert_ulps_eq!(UnitExpr::parse("kcal/(mol * (A)^2)").unwrap().eval(), 4.184e-4, epsilon = 1e-9);
+        assert_ulps_eq!(UnitExpr::parse("g/(cm^3)").unwrap().eval(), 0.6022141794216764);
+        assert_ulps_eq!(UnitExpr::parse("g/cm^3").unwrap().eval(), 0.6022141794216764);
+        assert_ulps_eq!(UnitExpr::parse("kg/(m*(fs^2))").unwrap().eval(), 6.022141794216764e16);
     }
 
     #[test]
@@ -472,6 +484,20 @@ mod test {
         assert!(UnitExpr::parse("foo ^ bar").is_err());
         assert!(UnitExpr::parse("m^z4").is_err());
         assert!(UnitExpr::parse("HJK").is_err());
+
+        let error = UnitExpr::parse("kcal/(mol*foo^2)").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Malformed expression: Error in unit at the right of '/': \
+             Malformed expression: Error in unit at the right of '*': \
+             Malformed expression: Error in unit at the left of '^': Unit 'foo' not found."
+        );
+
+        let error = UnitExpr::parse("A^x").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Malformed expression: Invalid power 'x' after '^': invalid digit found in string"
+        );
     }
 
     #[test]
@@ -485,6 +511,20 @@ mod test {
         assert!(from_str("h10").is_err());
     }
 
+    #[test]
+    fn temperature_pressure() {
+        assert_eq!(from_str("300 K").unwrap(), 300.0);
+
+        assert_ulps_eq!(from_str("1 Pa").unwrap(), 6.022141794216763e-14);
+        assert_ulps_eq!(from_str("1 bar").unwrap(), 6.022141794216763e-9);
+        assert_ulps_eq!(from_str("1 atm").unwrap(), 6.101935172990135e-9);
+
+        assert_ulps_eq!(from_str("1 bar").unwrap(), from_str("1e5 Pa").unwrap());
+        assert_ulps_eq!(from_str("1 atm").unwrap(), from_str("101325 Pa").unwrap());
+        assert_ulps_eq!(from_str("1 atm").unwrap(), from_str("1.01325 bar").unwrap());
+        assert_ulps_eq!(from_str("1 MPa").unwrap(), from_str("10 bar").unwrap());
+    }
+
     #[test]
     fn unit_to() {
         assert_eq!(to(25.0, "m").unwrap(), 2.5e-9);
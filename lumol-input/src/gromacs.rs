@@ -0,0 +1,446 @@
+// Lumol, an extensible molecular simulation engine
+// Copyright (C) Lumol's contributors — BSD license
+
+//! Read interactions from GROMACS topology files (`.top` and `.itp`).
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::prelude::*;
+use std::path::PathBuf;
+
+use log::warn;
+
+use lumol_core::energy::{CosineHarmonic, Harmonic, LennardJones, NullPotential, RyckaertBellemans, Torsion};
+use lumol_core::energy::{AnglePotential, BondPotential, DihedralPotential, PairPotential};
+use lumol_core::energy::{PairInteraction, PairRestriction};
+use lumol_core::{units, System};
+
+use crate::Error;
+use crate::interactions::check_cutoff;
+
+/// A single non-empty line in a topology file, split in whitespace
+/// separated fields.
+struct Line {
+    /// Line number in the file, starting at 1
+    number: usize,
+    /// Fields in this line, without the comments
+    fields: Vec<String>,
+}
+
+/// Input file for reading interactions from a GROMACS topology.
+///
+/// The following sections are used: `[ defaults ]`, `[ atomtypes ]`,
+/// `[ moleculetype ]`, `[ atoms ]`, `[ bonds ]`, `[ bondtypes ]`,
+/// `[ angles ]`, `[ angletypes ]`, `[ dihedrals ]`, `[ dihedraltypes ]` and
+/// `[ pairs ]`. Other sections are ignored.
+///
+/// GROMACS defines bonded interactions between atom indexes inside a
+/// molecule, while Lumol defines them between particles names. The
+/// interactions are thus set for the atom types given in the `[ atoms ]`
+/// section, and the particles in the system should be named after their
+/// GROMACS atom type.
+///
+/// The supported function types are harmonic bonds (1), harmonic (1) and G96
+/// (2) angles, and proper (1) and Ryckaert-Bellemans (3) dihedral angles.
+/// Lennard-Jones interactions are generated for all the atom types using the
+/// combination rule from the `[ defaults ]` section. If the topology
+/// contains a `[ pairs ]` section, 1-4 interactions are scaled by `fudgeLJ`;
+/// explicit pair parameters are not supported and produce an error.
+pub struct GromacsInput {
+    /// The sections in the topology, in the file order
+    sections: Vec<(String, Vec<Line>)>,
+    /// Cutoff distance for the Lennard-Jones interactions
+    cutoff: f64,
+}
+
+/// Parameters from the `[ defaults ]` section
+struct Defaults {
+    /// Combination rule, from 1 to 3
+    comb_rule: u64,
+    /// Should we generate 1-4 interactions from the normal Lennard-Jones
+    /// parameters?
+    gen_pairs: bool,
+    /// Scaling factor for 1-4 Lennard-Jones interactions
+    fudge_lj: f64,
+}
+
+impl Default for Defaults {
+    fn default() -> Defaults {
+        Defaults {
+            comb_rule: 2,
+            gen_pairs: true,
+            fudge_lj: 1.0,
+        }
+    }
+}
+
+impl GromacsInput {
+    /// Read a GROMACS topology from the file at `path`. `#include` directives
+    /// are not supported and produce an error, other preprocessor directives
+    /// are ignored.
+    pub fn new<P: Into<PathBuf>>(path: P) -> Result<GromacsInput, Error> {
+        let path = path.into();
+        let mut file = try_io!(File::open(&path), path);
+        let mut buffer = String::new();
+        let _ = try_io!(file.read_to_string(&mut buffer), path);
+        return GromacsInput::from_str(&buffer);
+    }
+
+    /// Read a GROMACS topology from a string.
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(string: &str) -> Result<GromacsInput, Error> {
+        let mut sections: Vec<(String, Vec<Line>)> = Vec::new();
+        for (i, line) in string.lines().enumerate() {
+            let number = i + 1;
+            let line = match line.find(';') {
+                Some(position) => &line[..position],
+                None => line,
+            };
+            let line = line.trim();
+
+            if line.is_empty() {
+                continue;
+            } else if line.starts_with("#include") {
+                return Err(Error::from(format!(
+                    "#include is not supported in GROMACS topology, at line {}", number
+                )));
+            } else if line.starts_with('#') {
+                warn!("ignoring preprocessor directive '{}' at line {} in GROMACS topology", line, number);
+                continue;
+            } else if line.starts_with('[') {
+                if !line.ends_with(']') {
+                    return Err(Error::from(format!("invalid section header '{}' at line {}", line, number)));
+                }
+                let name = line[1..line.len() - 1].trim().to_lowercase();
+                sections.push((name, Vec::new()));
+            } else {
+                let fields = line.split_whitespace().map(String::from).collect();
+                match sections.last_mut() {
+                    Some(section) => section.1.push(Line { number: number, fields: fields }),
+                    None => {
+                        return Err(Error::from(format!("missing section header before line {}", number)));
+                    }
+                }
+            }
+        }
+
+        Ok(GromacsInput {
+            sections: sections,
+            cutoff: units::from(1.0, "nm").expect("bad unit"),
+        })
+    }
+
+    /// Set the cutoff distance used for the Lennard-Jones interactions. The
+    /// default cutoff is 1 nm.
+    pub fn set_cutoff(&mut self, cutoff: f64) {
+        self.cutoff = cutoff;
+    }
+
+    /// Read the interactions from this topology into the `system`.
+    pub fn read(&self, system: &mut System) -> Result<(), Error> {
+        let mut defaults = Defaults::default();
+        let mut atomtypes = BTreeMap::new();
+        // atom types of the current molecule, indexed by atom number
+        let mut atoms = BTreeMap::new();
+        let mut nrexcl = 3;
+        let mut has_pairs = false;
+
+        for (name, lines) in &self.sections {
+            match &**name {
+                "defaults" => {
+                    if let Some(line) = lines.first() {
+                        defaults = read_defaults(line)?;
+                    }
+                }
+                "atomtypes" => {
+                    for line in lines {
+                        let (name, v, w) = read_atomtype(line)?;
+                        let _ = atomtypes.insert(name, (v, w));
+                    }
+                }
+                "moleculetype" => {
+                    atoms.clear();
+                    if let Some(line) = lines.first() {
+                        check_fields(line, 2, "moleculetype")?;
+                        nrexcl = parse::<usize>(line, 1)?;
+                    }
+                }
+                "atoms" => {
+                    for line in lines {
+                        check_fields(line, 2, "atoms")?;
+                        let _ = atoms.insert(parse::<usize>(line, 0)?, line.fields[1].clone());
+                    }
+                }
+                "bonds" => {
+                    for line in lines {
+                        if let Some((types, funct, parameters)) = molecule_entry(line, 2, &atoms)? {
+                            let potential = read_bond(line, funct, parameters)?;
+                            system.set_bond_potential((&types[0], &types[1]), potential);
+                        }
+                    }
+                }
+                "bondtypes" => {
+                    for line in lines {
+                        let (types, funct, parameters) = types_entry(line, 2)?;
+                        let potential = read_bond(line, funct, parameters)?;
+                        system.set_bond_potential((&types[0], &types[1]), potential);
+                    }
+                }
+                "angles" => {
+                    for line in lines {
+                        if let Some((types, funct, parameters)) = molecule_entry(line, 3, &atoms)? {
+                            let potential = read_angle(line, funct, parameters)?;
+                            system.set_angle_potential((&types[0], &types[1], &types[2]), potential);
+                        }
+                    }
+                }
+                "angletypes" => {
+                    for line in lines {
+                        let (types, funct, parameters) = types_entry(line, 3)?;
+                        let potential = read_angle(line, funct, parameters)?;
+                        system.set_angle_potential((&types[0], &types[1], &types[2]), potential);
+                    }
+                }
+                "dihedrals" => {
+                    for line in lines {
+                        if let Some((types, funct, parameters)) = molecule_entry(line, 4, &atoms)? {
+                            let potential = read_dihedral(line, funct, parameters)?;
+                            system.set_dihedral_potential((&types[0], &types[1], &types[2], &types[3]), potential);
+                        }
+                    }
+                }
+                "dihedraltypes" => {
+                    for line in lines {
+                        let (types, funct, parameters) = types_entry(line, 4)?;
+                        let potential = read_dihedral(line, funct, parameters)?;
+                        system.set_dihedral_potential((&types[0], &types[1], &types[2], &types[3]), potential);
+                    }
+                }
+                "pairs" => {
+                    for line in lines {
+                        check_fields(line, 3, "pairs")?;
+                        if line.fields.len() > 3 {
+                            return Err(Error::from(format!(
+                                "explicit pair parameters are not supported in GROMACS topology, at line {}",
+                                line.number
+                            )));
+                        }
+                    }
+                    has_pairs |= !lines.is_empty();
+                }
+                other => {
+                    warn!("ignoring unsupported section [ {} ] in GROMACS topology", other);
+                }
+            }
+        }
+
+        let restriction = if has_pairs {
+            if nrexcl != 3 {
+                warn!("using 1-4 pairs with nrexcl = {}, only 1-2 and 1-3 interactions will be excluded", nrexcl);
+            }
+            if !defaults.gen_pairs {
+                warn!("gen-pairs is 'no' but pair types are not supported, 1-4 interactions will not be scaled");
+                PairRestriction::Scale14(1.0)
+            } else {
+                PairRestriction::Scale14(defaults.fudge_lj)
+            }
+        } else {
+            PairRestriction::ExcludeBonded(nrexcl)
+        };
+
+        check_cutoff(self.cutoff, system)?;
+        let types = atomtypes.iter().collect::<Vec<_>>();
+        for (i, &(name_i, parameters_i)) in types.iter().enumerate() {
+            for &(name_j, parameters_j) in &types[i..] {
+                let potential = mix_lennard_jones(defaults.comb_rule, *parameters_i, *parameters_j);
+                let mut interaction = PairInteraction::new(potential, self.cutoff);
+                interaction.set_restriction(restriction);
+                system.set_pair_potential((name_i, name_j), interaction);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Check that the `line` in the `section` contains at least `count` fields
+fn check_fields(line: &Line, count: usize, section: &str) -> Result<(), Error> {
+    if line.fields.len() < count {
+        return Err(Error::from(format!(
+            "expected at least {} values in [ {} ] at line {}, got {}",
+            count, section, line.number, line.fields.len()
+        )));
+    }
+    Ok(())
+}
+
+/// Parse the field at index `i` in `line`
+fn parse<T: std::str::FromStr>(line: &Line, i: usize) -> Result<T, Error> {
+    let field = &line.fields[i];
+    return field.parse().map_err(|_| Error::from(format!(
+        "could not parse '{}' as a number at line {}", field, line.number
+    )));
+}
+
+/// Read the combination rule, pair generation and 1-4 Lennard-Jones scaling
+/// from the `[ defaults ]` section
+fn read_defaults(line: &Line) -> Result<Defaults, Error> {
+    check_fields(line, 2, "defaults")?;
+    let nbfunc = parse::<u64>(line, 0)?;
+    if nbfunc != 1 {
+        return Err(Error::from(format!(
+            "only Lennard-Jones non-bonded function (1) is supported, got {} at line {}",
+            nbfunc, line.number
+        )));
+    }
+
+    let comb_rule = parse::<u64>(line, 1)?;
+    if !(1..=3).contains(&comb_rule) {
+        return Err(Error::from(format!("invalid combination rule {} at line {}", comb_rule, line.number)));
+    }
+
+    let gen_pairs = match line.fields.get(2) {
+        Some(value) => value.to_lowercase() == "yes",
+        None => true,
+    };
+    let fudge_lj = if line.fields.len() > 3 { parse(line, 3)? } else { 1.0 };
+
+    return Ok(Defaults {
+        comb_rule: comb_rule,
+        gen_pairs: gen_pairs,
+        fudge_lj: fudge_lj,
+    });
+}
+
+/// Read the name and the two last values (V and W) in the `[ atomtypes ]`
+/// section. The number of columns before these values depends on the force
+/// field, so we only use the last ones.
+fn read_atomtype(line: &Line) -> Result<(String, f64, f64), Error> {
+    check_fields(line, 6, "atomtypes")?;
+    let n = line.fields.len();
+    let v = parse(line, n - 2)?;
+    let w = parse(line, n - 1)?;
+    return Ok((line.fields[0].clone(), v, w));
+}
+
+/// Read an entry with `n` atom indexes in a molecule section, and convert the
+/// indexes to atom types. Entries without parameters use the corresponding
+/// `[ *types ]` section, and `None` is returned for them.
+fn molecule_entry<'a>(
+    line: &'a Line,
+    n: usize,
+    atoms: &BTreeMap<usize, String>,
+) -> Result<Option<(Vec<String>, u64, &'a [String])>, Error> {
+    check_fields(line, n + 1, "molecule")?;
+    if line.fields.len() == n + 1 {
+        return Ok(None);
+    }
+
+    let mut types = Vec::with_capacity(n);
+    for i in 0..n {
+        let index = parse::<usize>(line, i)?;
+        types.push(atoms.get(&index).cloned().ok_or(Error::from(format!(
+            "unknown atom {} at line {}", index, line.number
+        )))?);
+    }
+    let funct = parse(line, n)?;
+    return Ok(Some((types, funct, &line.fields[n + 1..])));
+}
+
+/// Read an entry with `n` atom types in a `[ *types ]` section
+fn types_entry(line: &Line, n: usize) -> Result<(Vec<String>, u64, &[String]), Error> {
+    check_fields(line, n + 1, "types")?;
+    let types = line.fields[..n].to_vec();
+    let funct = parse(line, n)?;
+    return Ok((types, funct, &line.fields[n + 1..]));
+}
+
+/// Parse the `fields` containing the parameters of an interaction, and
+/// convert them from the corresponding GROMACS `unit_names`
+fn read_parameters(line: &Line, fields: &[String], unit_names: &[&str]) -> Result<Vec<f64>, Error> {
+    if fields.len() < unit_names.len() {
+        return Err(Error::from(format!(
+            "expected {} parameters at line {}, got {}",
+            unit_names.len(), line.number, fields.len()
+        )));
+    }
+
+    let mut values = Vec::new();
+    for (value, unit) in fields.iter().zip(unit_names) {
+        let value = value.parse::<f64>().map_err(|_| Error::from(format!(
+            "could not parse '{}' as a number at line {}", value, line.number
+        )))?;
+        values.push(units::from(value, unit)?);
+    }
+    return Ok(values);
+}
+
+fn read_bond(line: &Line, funct: u64, fields: &[String]) -> Result<Box<dyn BondPotential>, Error> {
+    match funct {
+        1 => {
+            let values = read_parameters(line, fields, &["nm", "kJ/mol/nm^2"])?;
+            Ok(Box::new(Harmonic { x0: values[0], k: values[1] }))
+        }
+        other => Err(Error::from(format!("unsupported bond function type {} at line {}", other, line.number))),
+    }
+}
+
+fn read_angle(line: &Line, funct: u64, fields: &[String]) -> Result<Box<dyn AnglePotential>, Error> {
+    match funct {
+        1 => {
+            let values = read_parameters(line, fields, &["deg", "kJ/mol/rad^2"])?;
+            Ok(Box::new(Harmonic { x0: values[0], k: values[1] }))
+        }
+        2 => {
+            let values = read_parameters(line, fields, &["deg", "kJ/mol"])?;
+            Ok(Box::new(CosineHarmonic::new(values[1], values[0])))
+        }
+        other => Err(Error::from(format!("unsupported angle function type {} at line {}", other, line.number))),
+    }
+}
+
+fn read_dihedral(line: &Line, funct: u64, fields: &[String]) -> Result<Box<dyn DihedralPotential>, Error> {
+    match funct {
+        1 => {
+            let values = read_parameters(line, fields, &["deg", "kJ/mol"])?;
+            let n = match fields.get(2).map(|n| n.parse::<usize>()) {
+                Some(Ok(n)) => n,
+                _ => return Err(Error::from(format!("missing or invalid multiplicity at line {}", line.number))),
+            };
+            Ok(Box::new(Torsion { delta: values[0], k: values[1], n: n }))
+        }
+        3 => {
+            let values = read_parameters(line, fields, &["kJ/mol"; 6])?;
+            let mut c = [0.0; 6];
+            c.copy_from_slice(&values);
+            Ok(Box::new(RyckaertBellemans { c: c }))
+        }
+        other => Err(Error::from(format!("unsupported dihedral function type {} at line {}", other, line.number))),
+    }
+}
+
+/// Create the Lennard-Jones potential between two atom types with the
+/// given `(V, W)` parameters, using the GROMACS combination rule `comb_rule`.
+fn mix_lennard_jones(comb_rule: u64, (vi, wi): (f64, f64), (vj, wj): (f64, f64)) -> Box<dyn PairPotential> {
+    let nm = units::from(1.0, "nm").expect("bad unit");
+    let kj_mol = units::from(1.0, "kJ/mol").expect("bad unit");
+
+    let (sigma, epsilon) = if comb_rule == 1 {
+        // V and W are C6 and C12 coefficients, in kJ/mol nm^6 and kJ/mol nm^12
+        let c6 = f64::sqrt(vi * vj);
+        let c12 = f64::sqrt(wi * wj);
+        if c6 <= 0.0 || c12 <= 0.0 {
+            return Box::new(NullPotential);
+        }
+        (f64::powf(c12 / c6, 1.0 / 6.0), c6 * c6 / (4.0 * c12))
+    } else if comb_rule == 2 {
+        // Lorentz-Berthelot combination rule
+        ((vi + vj) / 2.0, f64::sqrt(wi * wj))
+    } else {
+        (f64::sqrt(vi * vj), f64::sqrt(wi * wj))
+    };
+
+    return Box::new(LennardJones {
+        sigma: sigma * nm,
+        epsilon: epsilon * kj_mol,
+    });
+}
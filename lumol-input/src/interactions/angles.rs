@@ -2,11 +2,13 @@
 // Copyright (C) Lumol's contributors — BSD license
 use toml::value::Table;
 
-use lumol_core::energy::{AnglePotential, DihedralPotential};
-use lumol_core::energy::{CosineHarmonic, Harmonic, Morse, NullPotential, Torsion};
+use lumol_core::energy::{AnglePotential, CrossTermPotential, DihedralPotential, ImproperPotential};
+use lumol_core::energy::{BondAngle, CosineHarmonic, Harmonic, Morse, NullPotential, Scaled, Torsion};
+use lumol_core::energy::RyckaertBellemans;
 use lumol_core::System;
 
 use crate::{InteractionsInput, FromToml, Error};
+use super::read_scaling;
 use crate::extract;
 
 impl InteractionsInput {
@@ -35,7 +37,10 @@ impl InteractionsInput {
                 ))
             )?;
 
-            let potential = read_angle_potential(table)?;
+            let mut potential = read_angle_potential(table)?;
+            if let Some(scaling) = read_scaling(table, "angle potential")? {
+                potential = Box::new(Scaled::new(potential, scaling));
+            }
             system.set_angle_potential((atoms[0], atoms[1], atoms[2]), potential);
         }
         Ok(())
@@ -66,11 +71,79 @@ impl InteractionsInput {
                 ))
             )?;
 
-            let potential = read_dihedral_potential(table)?;
+            let mut potential = read_dihedral_potential(table)?;
+            if let Some(scaling) = read_scaling(table, "dihedral potential")? {
+                potential = Box::new(Scaled::new(potential, scaling));
+            }
             system.set_dihedral_potential((atoms[0], atoms[1], atoms[2], atoms[3]), potential);
         }
         Ok(())
     }
+
+    /// Read the "impropers" section from the potential configuration.
+    pub(crate) fn read_impropers(&self, system: &mut System) -> Result<(), Error> {
+        let impropers = match self.config.get("impropers") {
+            Some(impropers) => impropers,
+            None => return Ok(()),
+        };
+
+        let impropers = impropers.as_table().ok_or(
+            Error::from("the 'impropers' section must be a table")
+        )?;
+
+        for (key, table) in impropers {
+            let atoms = key.split('-').collect::<Vec<_>>();
+            if atoms.len() != 4 {
+                return Err(Error::from(format!(
+                    "expected four atoms for improper potential, got {} ({:?})", atoms.len(), atoms
+                )));
+            }
+
+            let table = table.as_table().ok_or(
+                Error::from(format!(
+                    "improper potential associated with {} must be a table", key
+                ))
+            )?;
+
+            let mut potential = read_improper_potential(table)?;
+            if let Some(scaling) = read_scaling(table, "improper potential")? {
+                potential = Box::new(Scaled::new(potential, scaling));
+            }
+            system.add_improper_interaction((atoms[0], atoms[1], atoms[2], atoms[3]), potential);
+        }
+        Ok(())
+    }
+
+    /// Read the "cross_terms" section from the potential configuration.
+    pub(crate) fn read_cross_terms(&self, system: &mut System) -> Result<(), Error> {
+        let cross_terms = match self.config.get("cross_terms") {
+            Some(cross_terms) => cross_terms,
+            None => return Ok(()),
+        };
+
+        let cross_terms = cross_terms.as_table().ok_or(
+            Error::from("the 'cross_terms' section must be a table")
+        )?;
+
+        for (key, table) in cross_terms {
+            let atoms = key.split('-').collect::<Vec<_>>();
+            if atoms.len() != 3 {
+                return Err(Error::from(format!(
+                    "expected three atoms for cross term potential, got {} ({:?})", atoms.len(), atoms
+                )));
+            }
+
+            let table = table.as_table().ok_or(
+                Error::from(format!(
+                    "cross term potential associated with {} must be a table", key
+                ))
+            )?;
+
+            let potential = read_cross_term_potential(table)?;
+            system.add_cross_term_interaction((atoms[0], atoms[1], atoms[2]), potential);
+        }
+        Ok(())
+    }
 }
 
 fn read_angle_potential(table: &Table) -> Result<Box<dyn AnglePotential>, Error> {
@@ -89,7 +162,23 @@ fn read_dihedral_potential(table: &Table) -> Result<Box<dyn DihedralPotential>,
         "harmonic" => Ok(Box::new(Harmonic::from_toml(table)?)),
         "cosine-harmonic" => Ok(Box::new(CosineHarmonic::from_toml(table)?)),
         "torsion" => Ok(Box::new(Torsion::from_toml(table)?)),
+        "ryckaert-bellemans" => Ok(Box::new(RyckaertBellemans::from_toml(table)?)),
         "morse" => Ok(Box::new(Morse::from_toml(table)?)),
         other => Err(Error::from(format!("unknown potential type '{}'", other))),
     }
 }
+
+fn read_improper_potential(table: &Table) -> Result<Box<dyn ImproperPotential>, Error> {
+    match extract::typ(table, "improper potential")? {
+        "null" => Ok(Box::new(NullPotential::from_toml(table)?)),
+        "harmonic" => Ok(Box::new(Harmonic::from_toml(table)?)),
+        other => Err(Error::from(format!("unknown potential type '{}'", other))),
+    }
+}
+
+fn read_cross_term_potential(table: &Table) -> Result<Box<dyn CrossTermPotential>, Error> {
+    match extract::typ(table, "cross term potential")? {
+        "bond-angle" => Ok(Box::new(BondAngle::from_toml(table)?)),
+        other => Err(Error::from(format!("unknown potential type '{}'", other))),
+    }
+}
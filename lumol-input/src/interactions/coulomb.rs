@@ -2,12 +2,12 @@
 // Copyright (C) Lumol's contributors — BSD license
 use toml::Value;
 
-use lumol_core::energy::{CoulombicPotential, Ewald, SharedEwald, Wolf};
+use lumol_core::energy::{CoulombicPotential, Ewald, PairRestriction, SharedEwald, Wolf};
 use lumol_core::System;
 
 use log::{info, warn};
 
-use super::read_restriction;
+use super::{check_cutoff, read_restriction};
 use crate::{InteractionsInput, Error, FromToml, FromTomlWithRefData};
 
 impl InteractionsInput {
@@ -30,19 +30,40 @@ impl InteractionsInput {
 
         let key = &*solvers[0];
         if let Value::Table(ref table) = coulomb[key] {
+            // Is the solver able to handle charged systems?
+            let mut charged_system = false;
             let mut potential: Box<dyn CoulombicPotential> = match key {
                 "wolf" => Box::new(Wolf::from_toml(table)?),
                 "ewald" => {
                     let ewald = Ewald::from_toml(table, &system)?;
+                    charged_system = ewald.background_correction();
                     Box::new(SharedEwald::new(ewald))
                 }
                 other => return Err(Error::from(format!("unknown coulomb solver '{}'", other))),
             };
 
             if let Some(restriction) = read_restriction(coulomb)? {
+                if let PairRestriction::ExcludeBonded(depth) = restriction {
+                    if key == "ewald" && depth > 3 {
+                        return Err(Error::from(
+                            "'exclude_bonded' restriction with more than three bonds is not supported by the 'ewald' coulombic solver"
+                        ));
+                    }
+                }
                 potential.set_restriction(restriction);
             }
 
+            let total_charge = system.total_charge();
+            if !charged_system && total_charge.abs() > 1e-6 {
+                return Err(Error::from(format!(
+                    "the system is not neutral and has a net charge of {:+}, \
+                    which is not supported by the '{}' coulombic solver", total_charge, key
+                )));
+            }
+
+            if let Some(cutoff) = potential.cutoff() {
+                check_cutoff(cutoff, system)?;
+            }
             system.set_coulomb_potential(potential);
             Ok(())
         } else {
@@ -92,4 +113,41 @@ impl InteractionsInput {
         }
         Ok(())
     }
+
+    /// Read the "charges_by_index" array from the potential configuration,
+    /// setting the charge of each particle by index.
+    pub(crate) fn read_charges_by_index(&self, system: &mut System) -> Result<(), Error> {
+        let charges = match self.config.get("charges_by_index") {
+            Some(charges) => charges,
+            None => return Ok(()),
+        };
+
+        let charges = charges.as_array().ok_or(
+            Error::from("'charges_by_index' must be an array of numbers")
+        )?;
+
+        let charges = charges.iter().map(|charge| match *charge {
+            Value::Integer(val) => Ok(val as f64),
+            Value::Float(val) => Ok(val),
+            _ => Err(Error::from("'charges_by_index' must be an array of numbers")),
+        }).collect::<Result<Vec<_>, _>>()?;
+
+        if charges.len() != system.size() {
+            return Err(Error::from(format!(
+                "'charges_by_index' must contain one charge per particle, got {} charges for {} particles",
+                charges.len(), system.size()
+            )));
+        }
+
+        for (i, &charge) in charges.iter().enumerate() {
+            system.particles_mut().charge[i] = charge;
+        }
+
+        let total_charge = system.total_charge();
+        info!("Charges set by index for {} particles", charges.len());
+        if total_charge.abs() > 1e-6 {
+            warn!("System is not neutral and have a net charge of {:+}", total_charge);
+        }
+        Ok(())
+    }
 }
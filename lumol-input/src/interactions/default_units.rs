@@ -0,0 +1,234 @@
+// Lumol, an extensible molecular simulation engine
+// Copyright (C) Lumol's contributors — BSD license
+
+//! Default units for bare numeric values in the interactions input.
+//!
+//! When the input contains a `[units]` section, numeric values without units
+//! for known potentials parameters are converted to strings with the
+//! corresponding default unit, before reading the potentials.
+use toml::value::{Table, Value};
+
+use lumol_core::units;
+
+use crate::Error;
+
+/// Default units for the dimensions used in potentials parameters
+struct DefaultUnits {
+    energy: Option<String>,
+    length: Option<String>,
+    angle: Option<String>,
+}
+
+/// Dimension of a potential parameter, as `energy^energy * variable^variable
+/// * angle^angle`. The variable is a length for pairs, bonds and cross terms
+/// potentials, and an angle for angles, dihedrals and impropers potentials.
+/// The additional angle is only used by cross terms potentials, which depend
+/// on both lengths and angles.
+#[derive(Clone, Copy)]
+struct Dimension {
+    energy: i32,
+    variable: i32,
+    angle: i32,
+}
+
+const ENERGY: Dimension = Dimension { energy: 1, variable: 0, angle: 0 };
+const VARIABLE: Dimension = Dimension { energy: 0, variable: 1, angle: 0 };
+
+impl DefaultUnits {
+    fn read(config: &Table) -> Result<Option<DefaultUnits>, Error> {
+        let units = match config.get("units") {
+            Some(units) => units,
+            None => return Ok(None),
+        };
+
+        let units = units.as_table().ok_or(
+            Error::from("the 'units' section must be a table")
+        )?;
+
+        let mut defaults = DefaultUnits {
+            energy: None,
+            length: None,
+            angle: None,
+        };
+
+        for (dimension, unit) in units {
+            let unit = unit.as_str().ok_or(
+                Error::from(format!("'{}' unit must be a string in units section", dimension))
+            )?;
+            // check that the unit is valid
+            let _ = units::from(1.0, unit)?;
+
+            let unit = Some(String::from(unit));
+            match &**dimension {
+                "energy" => defaults.energy = unit,
+                "length" => defaults.length = unit,
+                "angle" => defaults.angle = unit,
+                other => {
+                    return Err(Error::from(format!("unknown dimension '{}' in units section", other)));
+                }
+            }
+        }
+
+        return Ok(Some(defaults));
+    }
+
+    /// Get the unit corresponding to `dimension`, using `variable` as the
+    /// unit for the potential variable. This returns `None` if one of the
+    /// needed units is missing.
+    fn unit(&self, dimension: Dimension, variable: Option<&String>) -> Option<String> {
+        let mut parts = Vec::new();
+        if dimension.energy != 0 {
+            parts.push(power(self.energy.as_ref()?, dimension.energy));
+        }
+        if dimension.variable != 0 {
+            parts.push(power(variable?, dimension.variable));
+        }
+        if dimension.angle != 0 {
+            parts.push(power(self.angle.as_ref()?, dimension.angle));
+        }
+        return Some(parts.join("*"));
+    }
+}
+
+fn power(unit: &str, exponent: i32) -> String {
+    if exponent == 1 {
+        format!("({})", unit)
+    } else {
+        format!("({})^{}", unit, exponent)
+    }
+}
+
+/// Get the dimension of the `key` parameter for potentials with the given
+/// `potential` type.
+fn parameter_dimension(potential: &str, key: &str) -> Option<Dimension> {
+    let dimension = match (potential, key) {
+        ("harmonic", "k") => Dimension { energy: 1, variable: -2, angle: 0 },
+        ("quartic", "k2") => Dimension { energy: 1, variable: -2, angle: 0 },
+        ("quartic", "k3") => Dimension { energy: 1, variable: -3, angle: 0 },
+        ("quartic", "k4") => Dimension { energy: 1, variable: -4, angle: 0 },
+        ("cosine-harmonic", "k") | ("torsion", "k") => ENERGY,
+        ("bond-angle", "k1") | ("bond-angle", "k2") => Dimension { energy: 1, variable: -1, angle: -1 },
+        ("bond-angle", "r1") | ("bond-angle", "r2") => VARIABLE,
+        ("bond-angle", "theta0") => Dimension { energy: 0, variable: 0, angle: 1 },
+        (_, "x0") | (_, "sigma") | (_, "rho") | (_, "delta") | (_, "cutoff") => VARIABLE,
+        (_, "epsilon") | (_, "depth") => ENERGY,
+        ("lennard-jones-124", "c4") => Dimension { energy: 1, variable: 4, angle: 0 },
+        ("morse", "A") => Dimension { energy: 0, variable: -1, angle: 0 },
+        (_, "A") => ENERGY,
+        ("gaussian", "B") => Dimension { energy: 0, variable: -2, angle: 0 },
+        (_, "C") => Dimension { energy: 1, variable: 6, angle: 0 },
+        (_, "D") => Dimension { energy: 1, variable: 8, angle: 0 },
+        _ => return None,
+    };
+    return Some(dimension);
+}
+
+/// Convert `value` to a string with the given `unit` if it is a number
+fn add_unit(value: &mut Value, unit: Option<String>) {
+    let number = match *value {
+        Value::Integer(number) => number as f64,
+        Value::Float(number) => number,
+        _ => return,
+    };
+
+    if let Some(unit) = unit {
+        *value = Value::String(format!("{} {}", number, unit));
+    }
+}
+
+/// Add the default length unit to the shifted cutoff in the `potential` table,
+/// if any
+fn add_shifted_cutoff_unit(potential: &mut Table, defaults: &DefaultUnits) {
+    if let Some(Value::Table(cutoff)) = potential.get_mut("cutoff") {
+        if let Some(shifted) = cutoff.get_mut("shifted") {
+            add_unit(shifted, defaults.length.clone());
+        }
+    }
+}
+
+/// Add the default units from the `units` section of `config` to all numeric
+/// values for known potential parameters.
+pub(crate) fn apply_default_units(config: &mut Table) -> Result<(), Error> {
+    let defaults = match DefaultUnits::read(config)? {
+        Some(defaults) => defaults,
+        None => return Ok(()),
+    };
+
+    let sections = [
+        ("pairs", defaults.length.as_ref()),
+        ("bonds", defaults.length.as_ref()),
+        ("angles", defaults.angle.as_ref()),
+        ("dihedrals", defaults.angle.as_ref()),
+        ("impropers", defaults.angle.as_ref()),
+        ("cross_terms", defaults.length.as_ref()),
+    ];
+
+    for &(section, variable) in &sections {
+        let potentials = match config.get_mut(section) {
+            Some(Value::Table(potentials)) => potentials,
+            _ => continue,
+        };
+
+        for (_, potential) in potentials.iter_mut() {
+            let potential = match *potential {
+                Value::Table(ref mut potential) => potential,
+                _ => continue,
+            };
+
+            let typ = potential.get("type").and_then(Value::as_str).map(String::from);
+            let typ = typ.unwrap_or_default();
+            for (key, value) in potential.iter_mut() {
+                if let Some(dimension) = parameter_dimension(&typ, key) {
+                    add_unit(value, defaults.unit(dimension, variable));
+                }
+            }
+            add_shifted_cutoff_unit(potential, &defaults);
+
+            if let Some(Value::Table(computation)) = potential.get_mut("computation") {
+                if let Some(Value::Table(table)) = computation.get_mut("table") {
+                    if let Some(max) = table.get_mut("max") {
+                        add_unit(max, defaults.length.clone());
+                    }
+                }
+            }
+        }
+    }
+
+    if let Some(Value::Table(matrix)) = config.get_mut("pair_matrix") {
+        let parameters = [("sigma", &defaults.length), ("epsilon", &defaults.energy)];
+        for &(key, unit) in &parameters {
+            if let Some(Value::Array(rows)) = matrix.get_mut(key) {
+                for row in rows {
+                    if let Value::Array(ref mut row) = *row {
+                        for value in row {
+                            add_unit(value, unit.clone());
+                        }
+                    }
+                }
+            }
+        }
+
+        if let Some(cutoff) = matrix.get_mut("cutoff") {
+            add_unit(cutoff, defaults.length.clone());
+        }
+        add_shifted_cutoff_unit(matrix, &defaults);
+    }
+
+    if let Some(Value::Table(global)) = config.get_mut("global") {
+        if let Some(cutoff) = global.get_mut("cutoff") {
+            add_unit(cutoff, defaults.length.clone());
+        }
+    }
+
+    if let Some(Value::Table(coulomb)) = config.get_mut("coulomb") {
+        for (_, solver) in coulomb.iter_mut() {
+            if let Value::Table(ref mut solver) = *solver {
+                if let Some(cutoff) = solver.get_mut("cutoff") {
+                    add_unit(cutoff, defaults.length.clone());
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -3,7 +3,8 @@
 use toml::de::from_str as parse;
 use toml::value::{Table, Value};
 
-use std::fs::File;
+use std::collections::BTreeSet;
+use std::fs::{self, File};
 use std::io::prelude::*;
 use std::path::PathBuf;
 
@@ -11,17 +12,28 @@ use lumol_core::energy::PairRestriction;
 use lumol_core::System;
 
 use crate::Error;
+use crate::extract;
 use crate::validate;
+use crate::simulations::get_input_path;
 
 mod potentials;
 mod pairs;
 mod angles;
 mod coulomb;
+mod default_units;
+use self::default_units::apply_default_units;
+mod registry;
+pub use self::registry::PotentialRegistry;
 
 /// Input file for reading interactions
 pub struct InteractionsInput {
     /// The TOML configuration
     config: Table,
+    /// Path of the file containing the configuration, used to resolve
+    /// included files
+    path: Option<PathBuf>,
+    /// User-defined potentials
+    registry: PotentialRegistry,
 }
 
 impl InteractionsInput {
@@ -31,37 +43,132 @@ impl InteractionsInput {
         let mut file = try_io!(File::open(&path), path);
         let mut buffer = String::new();
         let _ = try_io!(file.read_to_string(&mut buffer), path);
-        return InteractionsInput::from_str(&buffer);
+        let mut input = InteractionsInput::from_str(&buffer)?;
+        input.path = Some(path);
+        return Ok(input);
     }
 
-    /// Read the interactions from a TOML formatted string.
+    /// Read the interactions from a TOML formatted string. Included files
+    /// are resolved relatively to the current directory.
     #[allow(clippy::should_implement_trait)]
     pub fn from_str(string: &str) -> Result<InteractionsInput, Error> {
         let config = parse(string).map_err(|err| Error::TOML(Box::new(err)))?;
         validate(&config)?;
-        return Ok(InteractionsInput::from_toml(config.clone()));
+        return InteractionsInput::from_toml(config, None);
     }
 
-    /// Read the interactions from a TOML table.
-    pub(crate) fn from_toml(config: Table) -> InteractionsInput {
-        InteractionsInput {
-            config: config
-        }
+    /// Read the interactions from a TOML table, coming from the file at
+    /// `path` if any.
+    pub(crate) fn from_toml(mut config: Table, path: Option<PathBuf>) -> Result<InteractionsInput, Error> {
+        apply_default_units(&mut config)?;
+        Ok(InteractionsInput {
+            config: config,
+            path: path,
+            registry: PotentialRegistry::new(),
+        })
+    }
+
+    /// Use the potentials from the `registry` when reading this input and
+    /// all the included files, in addition to the potentials defined in
+    /// lumol.
+    pub fn set_registry(&mut self, registry: PotentialRegistry) {
+        self.registry = registry;
     }
 
     /// Read the interactions from this input into the `system`.
     pub fn read(&self, system: &mut System) -> Result<(), Error> {
+        let mut reading = BTreeSet::new();
+        // the path may not exist for inputs created from a string
+        if let Some(canonical) = self.path.as_ref().and_then(|path| fs::canonicalize(path).ok()) {
+            let _ = reading.insert(canonical);
+        }
+        return self.read_with_includes(system, &mut reading);
+    }
+
+    /// Read the interactions from this input into the `system`. `reading`
+    /// contains the canonical paths of the files currently being read, and
+    /// is used to detect include cycles.
+    fn read_with_includes(&self, system: &mut System, reading: &mut BTreeSet<PathBuf>) -> Result<(), Error> {
+        // included files must be read first, so that the interactions
+        // defined in this file override the included ones
+        self.read_includes(system, reading)?;
+        // explicit pairs override the pairs from the matrix
+        self.read_pair_matrix(system)?;
         self.read_pairs(system)?;
         self.read_bonds(system)?;
         self.read_angles(system)?;
         self.read_dihedrals(system)?;
+        self.read_impropers(system)?;
+        self.read_cross_terms(system)?;
         // charges must be read before coulomb
         self.read_charges(system)?;
+        self.read_charges_by_index(system)?;
         self.read_coulomb(system)?;
         Ok(())
     }
 }
 
+impl InteractionsInput {
+    /// Read all the files in the `include` array into the `system`. This
+    /// returns an error if one of the files is already in `reading`, i.e. if
+    /// a file includes itself directly or indirectly.
+    fn read_includes(&self, system: &mut System, reading: &mut BTreeSet<PathBuf>) -> Result<(), Error> {
+        let includes = match self.config.get("include") {
+            Some(includes) => includes,
+            None => return Ok(()),
+        };
+
+        let includes = includes.as_array().ok_or(
+            Error::from("'include' must be an array of strings")
+        )?;
+
+        for include in includes {
+            let include = include.as_str().ok_or(
+                Error::from("'include' must be an array of strings")
+            )?;
+
+            let path = match self.path {
+                Some(ref root) => get_input_path(root, include),
+                None => PathBuf::from(include),
+            };
+            let canonical = try_io!(fs::canonicalize(&path), path);
+            if !reading.insert(canonical.clone()) {
+                return Err(Error::from(format!(
+                    "'{}' includes itself, directly or through other included files", path.display()
+                )));
+            }
+
+            let mut input = InteractionsInput::new(path)?;
+            input.set_registry(self.registry.clone());
+            input.read_with_includes(system, reading)?;
+            let _ = reading.remove(&canonical);
+        }
+        Ok(())
+    }
+}
+
+/// Check that a potential `cutoff` respects the minimum image convention in
+/// the `system` unit cell.
+pub(crate) fn check_cutoff(cutoff: f64, system: &System) -> Result<(), Error> {
+    let max_cutoff = system.cell.max_cutoff();
+    if cutoff > max_cutoff {
+        return Err(Error::from(format!(
+            "the cutoff of {} A is bigger than half of the smallest cell length ({} A), \
+            try increasing the cell size or decreasing the cutoff", cutoff, max_cutoff
+        )));
+    }
+    Ok(())
+}
+
+/// Read the optional scaling factor of bonded potentials, in the `context`
+/// potential
+fn read_scaling(config: &Table, context: &str) -> Result<Option<f64>, Error> {
+    if config.get("scaling").is_none() {
+        return Ok(None);
+    }
+    return Ok(Some(extract::number("scaling", config, context)?));
+}
+
 fn read_restriction(config: &Table) -> Result<Option<PairRestriction>, Error> {
     let restriction = config.get("restriction");
     if restriction.is_none() {
@@ -87,15 +194,26 @@ fn read_restriction(config: &Table) -> Result<Option<PairRestriction>, Error> {
             }
         }
         Value::Table(ref restriction) => {
-            if restriction.keys().len() != 1 || restriction.get("scale14").is_none() {
-                return Err(Error::from("Restriction table must be 'scale14'"));
+            if restriction.keys().len() != 1 {
+                return Err(Error::from("Restriction table must be 'scale14' or 'exclude_bonded'"));
             }
 
-            let scale = restriction["scale14"].as_float().ok_or(
-                Error::from("'scale14' parameter must be a float")
-            )?;
-
-            Ok(Some(PairRestriction::Scale14(scale)))
+            if let Some(scale) = restriction.get("scale14") {
+                let scale = scale.as_float().ok_or(
+                    Error::from("'scale14' parameter must be a float")
+                )?;
+                Ok(Some(PairRestriction::Scale14(scale)))
+            } else if let Some(depth) = restriction.get("exclude_bonded") {
+                let depth = depth.as_integer().ok_or(
+                    Error::from("'exclude_bonded' parameter must be a positive integer")
+                )?;
+                if depth < 1 {
+                    return Err(Error::from("'exclude_bonded' parameter must be a positive integer"));
+                }
+                Ok(Some(PairRestriction::ExcludeBonded(depth as usize)))
+            } else {
+                Err(Error::from("Restriction table must be 'scale14' or 'exclude_bonded'"))
+            }
         }
         _ => Err(Error::from("Restriction must be a table or a string")),
     }
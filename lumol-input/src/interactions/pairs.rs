@@ -1,5 +1,6 @@
 // Lumol, an extensible molecular simulation engine
 // Copyright (C) Lumol's contributors — BSD license
+use std::path::PathBuf;
 use toml::value::{Table, Value};
 
 use lumol_core::System;
@@ -7,12 +8,13 @@ use lumol_core::units;
 
 use lumol_core::energy::{BondPotential, PairInteraction, PairPotential};
 use lumol_core::energy::{BornMayerHuggins, Buckingham, Gaussian, Morse};
-use lumol_core::energy::{Harmonic, LennardJones, NullPotential, Mie};
-use lumol_core::energy::TableComputation;
+use lumol_core::energy::{Harmonic, LennardJones, LennardJones124, NullPotential, Mie, Quartic, WCA};
+use lumol_core::energy::{FileTable, Scaled, ScaledComputation, SoftCore, TableComputation};
 
-use super::read_restriction;
+use super::{check_cutoff, read_restriction, read_scaling, PotentialRegistry};
 use crate::{Error, InteractionsInput, FromToml, FromTomlWithData};
 use crate::extract;
+use crate::simulations::get_input_path;
 
 /// Global settings for the pair interactions
 struct GlobalInformation<'a> {
@@ -80,7 +82,12 @@ impl InteractionsInput {
                 ))
             )?;
 
-            let potential = read_pair_potential(table)?;
+            if extract::typ(table, "pair potential")? == "excluded" {
+                system.set_pair_potential((atoms[0], atoms[1]), PairInteraction::excluded());
+                continue;
+            }
+
+            let potential = read_pair_potential(table, self.path.as_ref(), &self.registry)?;
             let potential = if let Some(computation) = table.get("computation") {
                 let computation = computation.as_table().ok_or(
                     Error::from("'computation' section must be a table")
@@ -90,52 +97,58 @@ impl InteractionsInput {
                 potential
             };
 
-            let cutoff = match table.get("cutoff") {
-                Some(cutoff) => cutoff,
-                None => {
-                    global.cutoff.as_ref().ok_or(
-                        Error::from("missing 'cutoff' value for pair potential")
-                    )?
-                }
-            };
+            let interaction = read_pair_interaction(potential, table, &global)?;
+            check_cutoff(interaction.cutoff(), system)?;
+            system.set_pair_potential((atoms[0], atoms[1]), interaction);
+        }
+        Ok(())
+    }
 
-            let mut interaction = match *cutoff {
-                Value::String(ref cutoff) => {
-                    let cutoff = units::from_str(cutoff)?;
-                    PairInteraction::new(potential, cutoff)
-                }
-                Value::Table(ref table) => {
-                    let shifted = table.get("shifted").ok_or(
-                        Error::from("'cutoff' table can only contain 'shifted' key")
-                    )?;
-                    let cutoff = shifted.as_str().ok_or(
-                        Error::from("'cutoff.shifted' value must be a string")
-                    )?;
-                    let cutoff = units::from_str(cutoff)?;
-                    PairInteraction::shifted(potential, cutoff)
-                }
-                _ => return Err(Error::from("'cutoff' must be a string or a table")),
-            };
+    /// Read the "pair_matrix" section from the potential configuration. This
+    /// section contains the Lennard-Jones parameters for all the pairs of the
+    /// `kinds`, as symmetric `sigma` and `epsilon` matrices.
+    pub(crate) fn read_pair_matrix(&self, system: &mut System) -> Result<(), Error> {
+        let matrix = match self.config.get("pair_matrix") {
+            Some(matrix) => matrix,
+            None => return Ok(()),
+        };
 
-            let tail = table.get("tail_correction")
-                .map(|tail| {
-                    tail.as_bool().ok_or(Error::from(
-                        "the 'tail_correction' section must be a boolean value"
-                    ))
-                })
-                .map_or(Ok(global.tail), |tail| tail.map(Some))?;
+        let matrix = matrix.as_table().ok_or(
+            Error::from("the 'pair_matrix' section must be a table")
+        )?;
+
+        let global = GlobalInformation::read(&self.config)?;
 
-            if let Some(use_tail) = tail {
-                if use_tail {
-                    interaction.enable_tail_corrections()
-                }
+        let mut kinds = Vec::new();
+        for kind in extract::slice("kinds", matrix, "pair matrix")? {
+            let kind = kind.as_str().ok_or(
+                Error::from("'kinds' must be an array of strings in pair matrix")
+            )?;
+            if kinds.contains(&kind) {
+                return Err(Error::from(format!("'{}' is present multiple times in pair matrix kinds", kind)));
             }
+            kinds.push(kind);
+        }
 
-            if let Some(restriction) = read_restriction(table)? {
-                interaction.set_restriction(restriction);
+        for name in system.particles().name.iter() {
+            if !kinds.contains(&name.as_str()) {
+                return Err(Error::from(format!("missing '{}' in pair matrix kinds", name)));
             }
+        }
 
-            system.set_pair_potential((atoms[0], atoms[1]), interaction);
+        let sigma = read_symmetric_matrix("sigma", matrix, kinds.len())?;
+        let epsilon = read_symmetric_matrix("epsilon", matrix, kinds.len())?;
+
+        for i in 0..kinds.len() {
+            for j in i..kinds.len() {
+                let potential = Box::new(LennardJones {
+                    sigma: sigma[i][j],
+                    epsilon: epsilon[i][j],
+                });
+                let interaction = read_pair_interaction(potential, matrix, &global)?;
+                check_cutoff(interaction.cutoff(), system)?;
+                system.set_pair_potential((kinds[i], kinds[j]), interaction);
+            }
         }
         Ok(())
     }
@@ -165,32 +178,169 @@ impl InteractionsInput {
                 ))
             )?;
 
-            let potential = read_bond_potential(table)?;
+            let mut potential = read_bond_potential(table)?;
+            if let Some(scaling) = read_scaling(table, "bond potential")? {
+                potential = Box::new(Scaled::new(potential, scaling));
+            }
             system.set_bond_potential((atoms[0], atoms[1]), potential);
         }
         Ok(())
     }
 }
 
-fn read_pair_potential(table: &Table) -> Result<Box<dyn PairPotential>, Error> {
-    match extract::typ(table, "pair potential")? {
+/// Create a pair interaction for the `potential`, using the cutoff, tail
+/// correction and restriction from `table`, or the `global` settings.
+fn read_pair_interaction(
+    potential: Box<dyn PairPotential>,
+    table: &Table,
+    global: &GlobalInformation<'_>,
+) -> Result<PairInteraction, Error> {
+    let cutoff = match table.get("cutoff") {
+        Some(cutoff) => cutoff,
+        None => {
+            global.cutoff.as_ref().ok_or(
+                Error::from("missing 'cutoff' value for pair potential")
+            )?
+        }
+    };
+
+    let mut interaction = match *cutoff {
+        Value::String(ref cutoff) => {
+            let cutoff = units::from_str(cutoff)?;
+            PairInteraction::new(potential, cutoff)
+        }
+        Value::Table(ref table) => {
+            let shifted = table.get("shifted").ok_or(
+                Error::from("'cutoff' table can only contain 'shifted' key")
+            )?;
+            let cutoff = shifted.as_str().ok_or(
+                Error::from("'cutoff.shifted' value must be a string")
+            )?;
+            let cutoff = units::from_str(cutoff)?;
+            PairInteraction::shifted(potential, cutoff)
+        }
+        _ => return Err(Error::from("'cutoff' must be a string or a table")),
+    };
+
+    let tail = table.get("tail_correction")
+        .map(|tail| {
+            tail.as_bool().ok_or(Error::from(
+                "the 'tail_correction' section must be a boolean value"
+            ))
+        })
+        .map_or(Ok(global.tail), |tail| tail.map(Some))?;
+
+    if let Some(use_tail) = tail {
+        if use_tail {
+            interaction.enable_tail_corrections()
+        }
+    }
+
+    if let Some(restriction) = read_restriction(table)? {
+        interaction.set_restriction(restriction);
+    }
+
+    return Ok(interaction);
+}
+
+/// Read the `key` matrix of size `n x n` in the pair matrix `table`, and
+/// check that it is symmetric.
+fn read_symmetric_matrix(key: &str, table: &Table, n: usize) -> Result<Vec<Vec<f64>>, Error> {
+    let error = || Error::from(format!(
+        "'{}' must be a {}x{} array of strings in pair matrix", key, n, n
+    ));
+
+    let mut matrix = Vec::new();
+    for row in extract::slice(key, table, "pair matrix")? {
+        let row = row.as_array().ok_or_else(error)?;
+        if row.len() != n {
+            return Err(error());
+        }
+
+        let mut values = Vec::new();
+        for value in row {
+            let value = value.as_str().ok_or_else(error)?;
+            values.push(units::from_str(value)?);
+        }
+        matrix.push(values);
+    }
+
+    if matrix.len() != n {
+        return Err(error());
+    }
+
+    for i in 0..n {
+        for j in (i + 1)..n {
+            if f64::abs(matrix[i][j] - matrix[j][i]) > 1e-12 * f64::abs(matrix[i][j]) {
+                return Err(Error::from(format!("'{}' matrix is not symmetric in pair matrix", key)));
+            }
+        }
+    }
+
+    return Ok(matrix);
+}
+
+/// Read a pair potential from the given `table`. `root` is the path of the
+/// input file, used to resolve the path to tabulated potentials.
+fn read_pair_potential(
+    table: &Table,
+    root: Option<&PathBuf>,
+    registry: &PotentialRegistry,
+) -> Result<Box<dyn PairPotential>, Error> {
+    let typ = extract::typ(table, "pair potential")?;
+    if let Some(potential) = registry.read_pair(typ, table) {
+        return potential;
+    }
+
+    match typ {
         "null" => Ok(Box::new(NullPotential::from_toml(table)?)),
         "harmonic" => Ok(Box::new(Harmonic::from_toml(table)?)),
         "lj" => Ok(Box::new(LennardJones::from_toml(table)?)),
+        "lennard-jones-124" => Ok(Box::new(LennardJones124::from_toml(table)?)),
+        "wca" => Ok(Box::new(WCA::from_toml(table)?)),
         "buckingham" => Ok(Box::new(Buckingham::from_toml(table)?)),
         "born" => Ok(Box::new(BornMayerHuggins::from_toml(table)?)),
         "morse" => Ok(Box::new(Morse::from_toml(table)?)),
         "gaussian" => Ok(Box::new(Gaussian::from_toml(table)?)),
         "mie" => Ok(Box::new(Mie::from_toml(table)?)),
+        "table" => Ok(Box::new(read_file_table(table, root)?)),
         other => Err(Error::from(format!("unknown potential type '{}'", other))),
     }
 }
 
+/// Read a tabulated pair potential from the file given in the `file` key
+fn read_file_table(table: &Table, root: Option<&PathBuf>) -> Result<FileTable, Error> {
+    let file = extract::str("file", table, "table potential")?;
+    let path = match root {
+        Some(root) => get_input_path(root, file),
+        None => PathBuf::from(file),
+    };
+
+    let distance_unit = match table.get("distance_unit") {
+        Some(unit) => unit.as_str().ok_or(
+            Error::from("'distance_unit' must be a string in table potential")
+        )?,
+        None => "A",
+    };
+    let energy_unit = match table.get("energy_unit") {
+        Some(unit) => unit.as_str().ok_or(
+            Error::from("'energy_unit' must be a string in table potential")
+        )?,
+        None => "kJ/mol",
+    };
+
+    let distance_unit = units::from(1.0, distance_unit)?;
+    let energy_unit = units::from(1.0, energy_unit)?;
+    let potential = try_io!(FileTable::from_file(&path, distance_unit, energy_unit), path);
+    return Ok(potential);
+}
+
 fn read_bond_potential(table: &Table) -> Result<Box<dyn BondPotential>, Error> {
     match extract::typ(table, "bond potential")? {
         "null" => Ok(Box::new(NullPotential::from_toml(table)?)),
         "harmonic" => Ok(Box::new(Harmonic::from_toml(table)?)),
         "morse" => Ok(Box::new(Morse::from_toml(table)?)),
+        "quartic" => Ok(Box::new(Quartic::from_toml(table)?)),
         other => Err(Error::from(format!("unknown potential type '{}'", other))),
     }
 }
@@ -202,6 +352,8 @@ fn read_pair_computation(computation: &Table, potential: Box<dyn PairPotential>)
 
     match computation.keys().map(|s| s.as_ref()).next() {
         Some("table") => Ok(Box::new(TableComputation::from_toml(computation, potential)?)),
+        Some("scaled") => Ok(Box::new(ScaledComputation::from_toml(computation, potential)?)),
+        Some("softcore") => Ok(Box::new(SoftCore::from_toml(computation, potential)?)),
         Some(other) => Err(Error::from(format!("Unknown computation type '{}'", other))),
         None => unreachable!(),
     }
@@ -29,6 +29,38 @@ impl FromToml for Harmonic {
     }
 }
 
+impl FromToml for Quartic {
+    fn from_toml(table: &Table) -> Result<Quartic, Error> {
+        let k2 = extract::str("k2", table, "quartic potential")?;
+        let k3 = extract::str("k3", table, "quartic potential")?;
+        let k4 = extract::str("k4", table, "quartic potential")?;
+        let x0 = extract::str("x0", table, "quartic potential")?;
+        Ok(Quartic {
+            k2: units::from_str(k2)?,
+            k3: units::from_str(k3)?,
+            k4: units::from_str(k4)?,
+            x0: units::from_str(x0)?,
+        })
+    }
+}
+
+impl FromToml for BondAngle {
+    fn from_toml(table: &Table) -> Result<BondAngle, Error> {
+        let k1 = extract::str("k1", table, "bond-angle potential")?;
+        let k2 = extract::str("k2", table, "bond-angle potential")?;
+        let r1 = extract::str("r1", table, "bond-angle potential")?;
+        let r2 = extract::str("r2", table, "bond-angle potential")?;
+        let theta0 = extract::str("theta0", table, "bond-angle potential")?;
+        Ok(BondAngle {
+            k1: units::from_str(k1)?,
+            k2: units::from_str(k2)?,
+            r1: units::from_str(r1)?,
+            r2: units::from_str(r2)?,
+            theta0: units::from_str(theta0)?,
+        })
+    }
+}
+
 impl FromToml for LennardJones {
     fn from_toml(table: &Table) -> Result<LennardJones, Error> {
         let sigma = extract::str("sigma", table, "Lennard-Jones potential")?;
@@ -40,6 +72,30 @@ impl FromToml for LennardJones {
     }
 }
 
+impl FromToml for LennardJones124 {
+    fn from_toml(table: &Table) -> Result<LennardJones124, Error> {
+        let sigma = extract::str("sigma", table, "12-6-4 Lennard-Jones potential")?;
+        let epsilon = extract::str("epsilon", table, "12-6-4 Lennard-Jones potential")?;
+        let c4 = extract::str("c4", table, "12-6-4 Lennard-Jones potential")?;
+        Ok(LennardJones124 {
+            sigma: units::from_str(sigma)?,
+            epsilon: units::from_str(epsilon)?,
+            c4: units::from_str(c4)?,
+        })
+    }
+}
+
+impl FromToml for WCA {
+    fn from_toml(table: &Table) -> Result<WCA, Error> {
+        let sigma = extract::str("sigma", table, "WCA potential")?;
+        let epsilon = extract::str("epsilon", table, "WCA potential")?;
+        Ok(WCA {
+            sigma: units::from_str(sigma)?,
+            epsilon: units::from_str(epsilon)?,
+        })
+    }
+}
+
 impl FromToml for Mie {
     fn from_toml(table: &Table) -> Result<Mie, Error> {
         let sigma = extract::str("sigma", table, "Mie potential")?;
@@ -81,6 +137,17 @@ impl FromToml for Torsion {
     }
 }
 
+impl FromToml for RyckaertBellemans {
+    fn from_toml(table: &Table) -> Result<RyckaertBellemans, Error> {
+        let mut c = [0.0; 6];
+        for (n, value) in c.iter_mut().enumerate() {
+            let key = format!("C{}", n);
+            *value = units::from_str(extract::str(&key, table, "Ryckaert-Bellemans potential")?)?;
+        }
+        Ok(RyckaertBellemans { c: c })
+    }
+}
+
 impl FromToml for Buckingham {
     fn from_toml(table: &Table) -> Result<Buckingham, Error> {
         let a = extract::str("A", table, "Buckingham potential")?;
@@ -129,8 +196,19 @@ impl FromToml for Morse {
 impl FromToml for Gaussian {
     fn from_toml(table: &Table) -> Result<Gaussian, Error> {
         let a = units::from_str(extract::str("A", table, "Gaussian potential")?)?;
-        let b = units::from_str(extract::str("B", table, "Gaussian potential")?)?;
+        if table.contains_key("sigma") {
+            if table.contains_key("B") {
+                return Err(Error::from("can not have both 'B' and 'sigma' in Gaussian potential"));
+            }
+
+            let sigma = units::from_str(extract::str("sigma", table, "Gaussian potential")?)?;
+            if sigma <= 0.0 {
+                return Err(Error::from("'sigma' parameter has to be positive in Gaussian potential"));
+            }
+            return Ok(Gaussian::new(a, 1.0 / (sigma * sigma)));
+        }
 
+        let b = units::from_str(extract::str("B", table, "Gaussian potential")?)?;
         if b <= 0.0 {
             Err(Error::from("'B' parameter has to be positive in Gaussian potential"))
         } else {
@@ -153,6 +231,54 @@ impl FromTomlWithData for TableComputation {
     }
 }
 
+impl FromTomlWithData for ScaledComputation {
+    type Data = Box<dyn PairPotential>;
+
+    fn from_toml(table: &Table, potential: Box<dyn PairPotential>) -> Result<ScaledComputation, Error> {
+        let table = table["scaled"].as_table().ok_or(
+            Error::from("'scaled' key in computation must be a TOML table")
+        )?;
+
+        let lambda = extract::number("lambda", table, "scaled computation")?;
+        Ok(ScaledComputation::new(potential, lambda))
+    }
+}
+
+impl FromTomlWithData for SoftCore {
+    type Data = Box<dyn PairPotential>;
+
+    fn from_toml(table: &Table, potential: Box<dyn PairPotential>) -> Result<SoftCore, Error> {
+        let table = table["softcore"].as_table().ok_or(
+            Error::from("'softcore' key in computation must be a TOML table")
+        )?;
+
+        let lambda = extract::number("lambda", table, "softcore computation")?;
+        if !(0.0..=1.0).contains(&lambda) {
+            return Err(Error::from("'lambda' must be between 0 and 1 in softcore computation"));
+        }
+        let alpha = extract::number("alpha", table, "softcore computation")?;
+        if alpha < 0.0 {
+            return Err(Error::from("'alpha' must be positive in softcore computation"));
+        }
+
+        // Use the sigma parameter of the potential by default
+        let sigma = if table.contains_key("sigma") {
+            units::from_str(extract::str("sigma", table, "softcore computation")?)?
+        } else {
+            let parameters = potential.parameters().unwrap_or_else(|| PotentialParameters {
+                name: "",
+                values: Vec::new(),
+            });
+            let sigma = parameters.values.iter().find(|&&(name, _)| name == "sigma").map(|&(_, value)| value);
+            sigma.ok_or(Error::from(
+                "missing 'sigma' key in softcore computation, and the potential does not define sigma"
+            ))?
+        };
+
+        Ok(SoftCore::new(potential, sigma, lambda, alpha))
+    }
+}
+
 impl FromToml for Wolf {
     fn from_toml(table: &Table) -> Result<Wolf, Error> {
         let cutoff = extract::str("cutoff", table, "Wolf coulombic potential")?;
@@ -167,25 +293,35 @@ impl FromTomlWithRefData for Ewald {
         let cutoff = extract::str("cutoff", table, "Ewald coulombic potential")?;
         let cutoff = units::from_str(cutoff)?;
 
+        let background_correction = match table.get("background_correction") {
+            Some(correction) => correction.as_bool().ok_or(Error::from(
+                "'background_correction' must be a boolean in Ewald coulombic potential"
+            ))?,
+            None => false,
+        };
+
         // Check first for the accuracy key
-        if table.contains_key("accuracy") {
+        let mut ewald = if table.contains_key("accuracy") {
             if table.contains_key("kmax") || table.contains_key("alpha") {
                 return Err(Error::from(
                     "can not have both accuracy and kmax/alpha in Ewald coulombic potential"
                 ));
             }
             let accuracy = extract::number("accuracy", table, "Ewald coulombic potential")?;
-            return Ok(Ewald::with_accuracy(cutoff, accuracy, configuration));
-        }
-
-        // Else use directly specified parameters
-        let kmax = extract::uint("kmax", table, "Ewald coulombic potential")?;
-        let alpha = if table.contains_key("alpha") {
-            let alpha = extract::str("alpha", table, "Ewald coulombic potential")?;
-            Some(units::from_str(alpha)?)
+            Ewald::with_accuracy(cutoff, accuracy, configuration)
         } else {
-            None
+            // Else use directly specified parameters
+            let kmax = extract::uint("kmax", table, "Ewald coulombic potential")?;
+            let alpha = if table.contains_key("alpha") {
+                let alpha = extract::str("alpha", table, "Ewald coulombic potential")?;
+                Some(units::from_str(alpha)?)
+            } else {
+                None
+            };
+            Ewald::new(cutoff, kmax as usize, alpha)
         };
-        Ok(Ewald::new(cutoff, kmax as usize, alpha))
+
+        ewald.set_background_correction(background_correction);
+        Ok(ewald)
     }
 }
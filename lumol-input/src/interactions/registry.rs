@@ -0,0 +1,79 @@
+// Lumol, an extensible molecular simulation engine
+// Copyright (C) Lumol's contributors — BSD license
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use toml::value::Table;
+
+use lumol_core::energy::PairPotential;
+
+use crate::Error;
+
+/// Function reading a pair potential from the corresponding TOML table
+type PairReader = dyn Fn(&Table) -> Result<Box<dyn PairPotential>, Error> + Send + Sync;
+
+/// A registry of user-defined potentials, used to read potentials which are
+/// not part of lumol from the input files.
+///
+/// Each potential is associated with the name used in the `type` key of the
+/// input, and a function creating the potential from the TOML table. The
+/// potentials in the registry are used before the potentials defined in
+/// lumol, and can thus replace them.
+///
+/// # Examples
+///
+/// ```
+/// # use lumol_core::energy::Harmonic;
+/// # use lumol_core::{Molecule, Particle, System};
+/// # use lumol_input::{Error, InteractionsInput, PotentialRegistry};
+/// let mut registry = PotentialRegistry::new();
+/// registry.register_pair("spring", |table| {
+///     let k = table.get("k").and_then(|k| k.as_float()).ok_or(
+///         Error::from("missing 'k' in spring potential")
+///     )?;
+///     Ok(Box::new(Harmonic { k: k, x0: 0.0 }))
+/// });
+///
+/// let mut input = InteractionsInput::from_str("
+///     [input]
+///     version = 1
+///
+///     [pairs]
+///     A-A = {type = \"spring\", k = 3.0, cutoff = \"5 A\"}
+/// ").unwrap();
+/// input.set_registry(registry);
+///
+/// let mut system = System::new();
+/// system.add_molecule(Molecule::new(Particle::new("A")));
+/// input.read(&mut system).unwrap();
+/// assert!(system.pair_potential(0, 0).is_some());
+/// ```
+#[derive(Clone, Default)]
+pub struct PotentialRegistry {
+    /// Functions reading the pair potentials, indexed by name
+    pairs: BTreeMap<String, Arc<PairReader>>,
+}
+
+impl PotentialRegistry {
+    /// Create a new empty `PotentialRegistry`
+    pub fn new() -> PotentialRegistry {
+        PotentialRegistry::default()
+    }
+
+    /// Register a new pair potential with the given `name`, using `reader`
+    /// to create the potential from the TOML table containing the potential
+    /// parameters. If a pair potential with the same name was already
+    /// registered, it is replaced.
+    pub fn register_pair<F>(&mut self, name: &str, reader: F)
+    where
+        F: Fn(&Table) -> Result<Box<dyn PairPotential>, Error> + Send + Sync + 'static,
+    {
+        let _ = self.pairs.insert(name.into(), Arc::new(reader));
+    }
+
+    /// Read the pair potential with the given `name` from `table`, or
+    /// return `None` if no pair potential with this name is registered.
+    pub(crate) fn read_pair(&self, name: &str, table: &Table) -> Option<Result<Box<dyn PairPotential>, Error>> {
+        self.pairs.get(name).map(|reader| reader(table))
+    }
+}
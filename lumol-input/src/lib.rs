@@ -42,11 +42,14 @@ mod error;
 mod interactions;
 mod simulations;
 mod alternator;
+mod gromacs;
 
 pub use self::error::Error;
-pub use self::interactions::InteractionsInput;
+pub use self::interactions::{InteractionsInput, PotentialRegistry};
+pub use self::gromacs::GromacsInput;
 pub use self::simulations::{Config, Input};
-pub use self::simulations::setup_default_logger;
+pub use self::simulations::{setup_default_logger, setup_default_logger_with_level, set_log_level};
+pub use self::simulations::{setup_file_logger, setup_file_and_stdout_logger};
 
 /// Convert a TOML table to a Rust type.
 pub trait FromToml: Sized {
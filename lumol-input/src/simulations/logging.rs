@@ -1,8 +1,10 @@
 // Lumol, an extensible molecular simulation engine
 // Copyright (C) Lumol's contributors — BSD license
+use std::path::Path;
+
 use toml::value::Table;
 
-use log::{self, Record, info};
+use log::{self, Record, info, warn};
 
 use log4rs;
 use log4rs::append::Append;
@@ -129,26 +131,112 @@ impl Input {
     }
 }
 
-/// Setup a default logger to be able to print error messages
+/// Name of the environment variable used to set the default logging level
+const LOG_LEVEL_ENV: &str = "LUMOL_LOG_LEVEL";
+
+/// Setup a default logger to be able to print error messages. The messages
+/// are printed to the standard output, using the level in the
+/// `LUMOL_LOG_LEVEL` environment variable (one of `trace`, `debug`, `info`,
+/// `warning`, `error` or `off`), or `info` if this variable is not set. An
+/// invalid value in this variable is reported as a warning, and the `info`
+/// level is used instead.
 pub fn setup_default_logger() {
-    // We just log everything to stdout
+    init_logger_with_default_level(vec![stdout_appender("stdout")]);
+}
+
+/// Setup a default logger printing all messages with the given `level` or a
+/// more important one to the standard output. The level can be changed later
+/// with [`set_log_level`](fn.set_log_level.html).
+pub fn setup_default_logger_with_level(level: log::LevelFilter) {
+    let _ = init_logger(vec![stdout_appender("stdout")], level);
+}
+
+/// Setup a logger writing the messages to the file at `path`, overwriting
+/// any existing file. The level of the messages is set in the same way as for
+/// [`setup_default_logger`](fn.setup_default_logger.html).
+pub fn setup_file_logger<P: AsRef<Path>>(path: P) -> Result<(), Error> {
+    let file = file_appender(path.as_ref(), "file")?;
+    init_logger_with_default_level(vec![file]);
+    Ok(())
+}
+
+/// Setup a logger writing the messages both to the file at `path` and to the
+/// standard output. The level of the messages is set in the same way as for
+/// [`setup_default_logger`](fn.setup_default_logger.html).
+pub fn setup_file_and_stdout_logger<P: AsRef<Path>>(path: P) -> Result<(), Error> {
+    let file = file_appender(path.as_ref(), "file")?;
+    init_logger_with_default_level(vec![file, stdout_appender("stdout")]);
+    Ok(())
+}
+
+/// Initialize the global logger with the given `appenders`, using the level
+/// from the `LUMOL_LOG_LEVEL` environment variable. An invalid level in this
+/// variable is reported as a warning once the logger is initialized.
+fn init_logger_with_default_level(appenders: Vec<Appender>) {
+    let name = std::env::var(LOG_LEVEL_ENV).unwrap_or_else(|_| String::from("info"));
+    let level = parse_level(&name);
+    if init_logger(appenders, level.unwrap_or(log::LevelFilter::Info)) && level.is_none() {
+        warn!("invalid logging level '{}' in {}, using 'info'", name, LOG_LEVEL_ENV);
+    }
+}
+
+/// Create an appender writing to the standard output
+fn stdout_appender(name: &str) -> Appender {
     let stdout = ConsoleAppender::builder()
         .target(console::Target::Stdout)
         .encoder(Box::new(LogEncoder))
         .build();
+    Appender::builder().build(name, Box::new(stdout))
+}
 
-    let appender = Appender::builder()
-        .filter(Box::new(ThresholdFilter::new(log::LevelFilter::Info)))
-        .build("main", Box::new(stdout));
+/// Create an appender writing to the file at `path`
+fn file_appender(path: &Path, name: &str) -> Result<Appender, Error> {
+    let file = FileAppender::builder()
+        .append(false)
+        .encoder(Box::new(LogEncoder));
+    let file = try_io!(file.build(path), path.into());
+    Ok(Appender::builder().build(name, Box::new(file)))
+}
 
-    let config = Config::builder()
-        .appender(appender)
-        .build(Root::builder().appender("main").build(log::LevelFilter::Info))
-        .expect("Error in logging initialization");
+/// Initialize the global logger with the given `appenders`, emitting the
+/// messages with the given `level` or a more important one. This returns
+/// `false` if a logger was already initialized.
+fn init_logger(appenders: Vec<Appender>, level: log::LevelFilter) -> bool {
+    let mut root = Root::builder();
+    let mut config = Config::builder();
+    for appender in appenders {
+        root = root.appender(appender.name());
+        config = config.appender(appender);
+    }
+    let config = config.build(root.build(log::LevelFilter::Trace))
+                       .expect("Error in logging initialization");
 
-    // We ignore the result of this call, because it can only fail if a
-    // logger has already been initialized.
-    let _ = log4rs::init_config(config);
+    // The initialization can only fail if a logger has already been
+    // initialized, in which case we do not want to change its level.
+    let initialized = log4rs::init_config(config).is_ok();
+    if initialized {
+        set_log_level(level);
+    }
+    return initialized;
+}
+
+/// Only emit the logging messages with the given `level` or a more important
+/// one, silencing the others. This applies to all the loggers.
+pub fn set_log_level(level: log::LevelFilter) {
+    log::set_max_level(level);
+}
+
+/// Get the logging level filter corresponding to the given `level` name
+fn parse_level(level: &str) -> Option<log::LevelFilter> {
+    match level {
+        "trace" => Some(log::LevelFilter::Trace),
+        "debug" => Some(log::LevelFilter::Debug),
+        "info" => Some(log::LevelFilter::Info),
+        "warning" => Some(log::LevelFilter::Warn),
+        "error" => Some(log::LevelFilter::Error),
+        "off" => Some(log::LevelFilter::Off),
+        _ => None,
+    }
 }
 
 fn read_appender(config: &Table, name: &str) -> Result<Appender, Error> {
@@ -163,13 +251,9 @@ fn read_appender(config: &Table, name: &str) -> Result<Appender, Error> {
         .map_or(Some("info"), |level| level.as_str())
         .ok_or(Error::from("'level' must be a string in log target"))?;
 
-    let level = match level {
-        "trace" => log::LevelFilter::Trace,
-        "debug" => log::LevelFilter::Debug,
-        "info" => log::LevelFilter::Info,
-        "warning" => log::LevelFilter::Warn,
-        "error" => log::LevelFilter::Error,
-        other => return Err(Error::from(format!("unknown logging level '{}'", other))),
+    let level = match parse_level(level) {
+        Some(level) if level != log::LevelFilter::Off => level,
+        _ => return Err(Error::from(format!("unknown logging level '{}'", level))),
     };
 
     let target = extract::str("target", config, "log target")?;
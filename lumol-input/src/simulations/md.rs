@@ -31,6 +31,7 @@ impl FromToml for MolecularDynamics {
                 "Verlet" => Box::new(Verlet::from_toml(integrator, timestep)?),
                 "VelocityVerlet" => Box::new(VelocityVerlet::from_toml(integrator, timestep)?),
                 "LeapFrog" => Box::new(LeapFrog::from_toml(integrator, timestep)?),
+                "Respa" => Box::new(Respa::from_toml(integrator, timestep)?),
                 other => return Err(Error::from(format!("unknown integrator '{}'", other))),
             };
 
@@ -47,12 +48,36 @@ impl FromToml for MolecularDynamics {
             let thermostat: Box<dyn Thermostat> = match extract::typ(thermostat, "thermostat")? {
                 "Berendsen" => Box::new(BerendsenThermostat::from_toml(thermostat)?),
                 "Rescale" => Box::new(RescaleThermostat::from_toml(thermostat)?),
-                "CSVR" => Box::new(CSVRThermostat::from_toml(thermostat)?),
+                "CSVR" | "Bussi" => Box::new(CSVRThermostat::from_toml(thermostat)?),
                 other => return Err(Error::from(format!("unknown thermostat type '{}'", other))),
             };
             md.set_thermostat(thermostat);
         }
 
+        if let Some(adaptive) = config.get("adaptive_timestep") {
+            let adaptive = adaptive.as_table().ok_or(
+                Error::from("'adaptive_timestep' must be a table in molecular dynamics")
+            )?;
+
+            let min = extract::str("min", adaptive, "adaptive timestep")?;
+            let min = units::from_str(min)?;
+            let max = extract::str("max", adaptive, "adaptive timestep")?;
+            let max = units::from_str(max)?;
+            let max_displacement = extract::str("max_displacement", adaptive, "adaptive timestep")?;
+            let max_displacement = units::from_str(max_displacement)?;
+
+            if min <= 0.0 {
+                return Err(Error::from("'min' must be positive in adaptive timestep"));
+            }
+            if max < min {
+                return Err(Error::from("'max' must be larger than 'min' in adaptive timestep"));
+            }
+            if max_displacement <= 0.0 {
+                return Err(Error::from("'max_displacement' must be positive in adaptive timestep"));
+            }
+            md.set_adaptive_timestep(min, max, max_displacement);
+        }
+
         if let Some(controls) = config.get("controls") {
             let controls = controls.as_array().ok_or(
                 Error::from("'controls' must be an array of tables in molecular dynamics")
@@ -71,6 +96,7 @@ impl FromToml for MolecularDynamics {
                         Box::new(Alternator::<RemoveRotation>::from_toml(control)?)
                     }
                     "Rewrap" => Box::new(Alternator::<Rewrap>::from_toml(control)?),
+                    "MullerPlathe" => Box::new(MullerPlathe::from_toml(control)?),
                     other => return Err(Error::from(format!("unknown control '{}'", other))),
                 };
                 md.add_control(control);
@@ -102,6 +128,17 @@ impl FromTomlWithData for LeapFrog {
     }
 }
 
+impl FromTomlWithData for Respa {
+    type Data = f64;
+    fn from_toml(config: &Table, timestep: f64) -> Result<Respa, Error> {
+        let inner_steps = extract::uint("inner_steps", config, "RESPA integrator")?;
+        if inner_steps == 0 {
+            return Err(Error::from("'inner_steps' must be positive in RESPA integrator"));
+        }
+        Ok(Respa::new(timestep, inner_steps as usize))
+    }
+}
+
 impl FromTomlWithData for BerendsenBarostat {
     type Data = f64;
     fn from_toml(config: &Table, timestep: f64) -> Result<BerendsenBarostat, Error> {
@@ -118,7 +155,22 @@ impl FromTomlWithData for AnisoBerendsenBarostat {
         let pressure = extract::str("pressure", config, "anisotropic Berendsen barostat")?;
         let pressure = units::from_str(pressure)?;
         let tau = extract::number("timestep", config, "anisotropic Berendsen barostat")?;
-        Ok(AnisoBerendsenBarostat::hydrostatic(timestep, pressure, tau))
+        let mut barostat = AnisoBerendsenBarostat::hydrostatic(timestep, pressure, tau);
+        if config.contains_key("constraint") {
+            let constraint = match extract::str("constraint", config, "anisotropic Berendsen barostat")? {
+                "Isotropic" => CellConstraint::Isotropic,
+                "SemiIsotropic" => CellConstraint::SemiIsotropic,
+                "Anisotropic" => CellConstraint::Anisotropic,
+                "FixedShape" => CellConstraint::FixedShape,
+                other => {
+                    return Err(Error::from(format!(
+                        "unknown cell constraint '{}' in anisotropic Berendsen barostat", other
+                    )))
+                }
+            };
+            barostat.set_constraint(constraint);
+        }
+        Ok(barostat)
     }
 }
 
@@ -190,3 +242,46 @@ impl FromToml for Alternator<Rewrap> {
         Ok(Alternator::new(every, Rewrap))
     }
 }
+
+impl FromToml for MullerPlathe {
+    fn from_toml(config: &Table) -> Result<MullerPlathe, Error> {
+        let axis = match extract::str("axis", config, "MullerPlathe control")? {
+            "x" => 0,
+            "y" => 1,
+            "z" => 2,
+            other => {
+                return Err(Error::from(format!(
+                    "'axis' must be 'x', 'y' or 'z' in MullerPlathe control, got '{}'", other
+                )))
+            }
+        };
+
+        let slabs = extract::uint("slabs", config, "MullerPlathe control")? as usize;
+        if slabs < 2 {
+            return Err(Error::from("'slabs' must be at least 2 in MullerPlathe control"));
+        }
+
+        let every = if config.contains_key("every") {
+            extract::uint("every", config, "MullerPlathe control")?
+        } else {
+            1
+        };
+        if every == 0 {
+            return Err(Error::from("'every' must be positive in MullerPlathe control"));
+        }
+
+        let mut control = MullerPlathe::new(axis, slabs, every);
+        if config.contains_key("hot") || config.contains_key("cold") {
+            let hot = extract::uint("hot", config, "MullerPlathe control")? as usize;
+            let cold = extract::uint("cold", config, "MullerPlathe control")? as usize;
+            if hot >= slabs || cold >= slabs {
+                return Err(Error::from("'hot' and 'cold' must be smaller than 'slabs' in MullerPlathe control"));
+            }
+            if hot == cold {
+                return Err(Error::from("'hot' and 'cold' must be different in MullerPlathe control"));
+            }
+            control.set_slabs(hot, cold);
+        }
+        Ok(control)
+    }
+}
@@ -10,7 +10,7 @@ use std::path::{Path, PathBuf};
 use lumol_sim::Simulation;
 use lumol_core::System;
 
-use crate::Error;
+use crate::{Error, PotentialRegistry};
 use crate::validate;
 
 mod logging;
@@ -23,7 +23,8 @@ mod min;
 mod md;
 mod mc;
 
-pub use self::logging::setup_default_logger;
+pub use self::logging::{setup_default_logger, setup_default_logger_with_level, set_log_level};
+pub use self::logging::{setup_file_logger, setup_file_and_stdout_logger};
 
 /// A configuration about how to run a single simulation. This contains the
 /// system to simulate, the simulation itself and the number of steps to run
@@ -43,6 +44,8 @@ pub struct Input {
     path: PathBuf,
     /// The TOML configuration
     config: Table,
+    /// User-defined potentials
+    registry: PotentialRegistry,
 }
 
 impl Input {
@@ -62,9 +65,16 @@ impl Input {
         Ok(Input {
             path: path,
             config: config.clone(),
+            registry: PotentialRegistry::new(),
         })
     }
 
+    /// Use the potentials from the `registry` when reading the interactions
+    /// of the system, in addition to the potentials defined in lumol.
+    pub fn set_registry(&mut self, registry: PotentialRegistry) {
+        self.registry = registry;
+    }
+
     /// Read input file and get the corresponding `Config`
     pub fn read(&self) -> Result<Config, Error> {
         self.setup_logging()?;
@@ -80,7 +90,7 @@ impl Input {
     }
 }
 
-fn get_input_path<P1: AsRef<Path>, P2: AsRef<Path>>(root: P1, path: P2) -> PathBuf {
+pub(crate) fn get_input_path<P1: AsRef<Path>, P2: AsRef<Path>>(root: P1, path: P2) -> PathBuf {
     let path = PathBuf::from(path.as_ref());
     if path.is_absolute() {
         path
@@ -3,9 +3,11 @@
 use std::path::PathBuf;
 use toml::value::Table;
 
+use lumol_core::units;
 use lumol_sim::output::Output;
 use lumol_sim::output::{TrajectoryOutput, PropertiesOutput, EnergyOutput};
 use lumol_sim::output::{ForcesOutput, CellOutput, CustomOutput, StressOutput};
+use lumol_sim::output::{AveragesOutput, EnergyDriftOutput, NumberFormat};
 
 use crate::{Input, FromToml, Error};
 use crate::extract;
@@ -29,9 +31,13 @@ impl Input {
 
                 let frequency = match output.get("frequency") {
                     Some(frequency) => {
-                        frequency.as_integer().ok_or(
+                        let frequency = frequency.as_integer().ok_or(
                             Error::from("'frequency' must be an integer in output")
-                        )? as u64
+                        )?;
+                        if frequency <= 0 {
+                            return Err(Error::from("'frequency' must be positive in output"));
+                        }
+                        frequency as u64
                     }
                     None => 1,
                 };
@@ -45,6 +51,8 @@ impl Input {
                     "forces" => Box::new(ForcesOutput::from_toml(output)?),
                     "cell" => Box::new(CellOutput::from_toml(output)?),
                     "custom" => Box::new(CustomOutput::from_toml(output)?),
+                    "averages" => Box::new(AveragesOutput::from_toml(output)?),
+                    "energydrift" => Box::new(EnergyDriftOutput::from_toml(output)?),
                     other => return Err(Error::from(format!("unknown output type '{}'", other))),
                 };
 
@@ -65,6 +73,24 @@ fn get_file(config: &Table) -> Result<&str, Error> {
     file.as_str().ok_or(Error::from("'file' must be a string in output"))
 }
 
+/// Get the format of the values in a text output, from the optional
+/// `precision`, `width` and `scientific` keys
+fn get_format(config: &Table) -> Result<NumberFormat, Error> {
+    let mut format = NumberFormat::default();
+    if config.get("precision").is_some() {
+        format.precision = extract::uint("precision", config, "output")? as usize;
+    }
+    if config.get("width").is_some() {
+        format.width = extract::uint("width", config, "output")? as usize;
+    }
+    if let Some(scientific) = config.get("scientific") {
+        format.scientific = scientific.as_bool().ok_or(
+            Error::from("'scientific' must be a boolean in output")
+        )?;
+    }
+    Ok(format)
+}
+
 impl FromToml for TrajectoryOutput {
     fn from_toml(config: &Table) -> Result<TrajectoryOutput, Error> {
         let path = get_file(config)?;
@@ -76,7 +102,8 @@ impl FromToml for TrajectoryOutput {
 impl FromToml for CellOutput {
     fn from_toml(config: &Table) -> Result<CellOutput, Error> {
         let path = get_file(config)?;
-        let output = try_io!(CellOutput::new(path), PathBuf::from(path));
+        let mut output = try_io!(CellOutput::new(path), PathBuf::from(path));
+        output.set_format(get_format(config)?);
         Ok(output)
     }
 }
@@ -84,7 +111,8 @@ impl FromToml for CellOutput {
 impl FromToml for EnergyOutput {
     fn from_toml(config: &Table) -> Result<EnergyOutput, Error> {
         let path = get_file(config)?;
-        let output = try_io!(EnergyOutput::new(path), PathBuf::from(path));
+        let mut output = try_io!(EnergyOutput::new(path), PathBuf::from(path));
+        output.set_format(get_format(config)?);
         Ok(output)
     }
 }
@@ -92,7 +120,8 @@ impl FromToml for EnergyOutput {
 impl FromToml for PropertiesOutput {
     fn from_toml(config: &Table) -> Result<PropertiesOutput, Error> {
         let path = get_file(config)?;
-        let output = try_io!(PropertiesOutput::new(path), PathBuf::from(path));
+        let mut output = try_io!(PropertiesOutput::new(path), PathBuf::from(path));
+        output.set_format(get_format(config)?);
         Ok(output)
     }
 }
@@ -100,7 +129,8 @@ impl FromToml for PropertiesOutput {
 impl FromToml for StressOutput {
     fn from_toml(config: &Table) -> Result<StressOutput, Error> {
         let path = get_file(config)?;
-        let output = try_io!(StressOutput::new(path), PathBuf::from(path));
+        let mut output = try_io!(StressOutput::new(path), PathBuf::from(path));
+        output.set_format(get_format(config)?);
         Ok(output)
     }
 }
@@ -121,3 +151,27 @@ impl FromToml for CustomOutput {
         Ok(output)
     }
 }
+
+impl FromToml for AveragesOutput {
+    fn from_toml(config: &Table) -> Result<AveragesOutput, Error> {
+        let blocks = match config.get("blocks") {
+            Some(_) => extract::uint("blocks", config, "averages output")?,
+            None => 10,
+        };
+        if blocks < 2 {
+            return Err(Error::from("'blocks' must be at least 2 in averages output"));
+        }
+        Ok(AveragesOutput::new(blocks as usize))
+    }
+}
+
+impl FromToml for EnergyDriftOutput {
+    fn from_toml(config: &Table) -> Result<EnergyDriftOutput, Error> {
+        let timestep = extract::str("timestep", config, "energy drift output")?;
+        let timestep = units::from_str(timestep)?;
+        if timestep <= 0.0 {
+            return Err(Error::from("'timestep' must be positive in energy drift output"));
+        }
+        Ok(EnergyDriftOutput::new(timestep))
+    }
+}
@@ -1,5 +1,6 @@
 // Lumol, an extensible molecular simulation engine
 // Copyright (C) Lumol's contributors — BSD license
+use lumol_core::units;
 use lumol_sim::Simulation;
 use toml::value::Table;
 
@@ -15,6 +16,23 @@ impl Input {
             simulation.add_output_with_frequency(output, frequency);
         }
 
+        if let Some(neighbor_list) = self.simulation_table()?.get("neighbor_list") {
+            let neighbor_list = neighbor_list.as_table().ok_or(
+                Error::from("'neighbor_list' must be a table in simulation")
+            )?;
+
+            if neighbor_list.contains_key("skin") {
+                let skin = extract::str("skin", neighbor_list, "neighbor list")?;
+                let skin = units::from_str(skin)?;
+                if skin < 0.0 {
+                    return Err(Error::from("'skin' can not be negative in neighbor list"));
+                }
+                simulation.set_neighbor_list_skin(skin);
+            } else {
+                simulation.enable_neighbor_list();
+            }
+        }
+
         Ok(simulation)
     }
 
@@ -143,10 +143,12 @@ impl Input {
         if let Some(potentials) = config.get("potentials") {
             if let Some(potentials) = potentials.as_str() {
                 let path = get_input_path(&self.path, potentials);
-                let input = InteractionsInput::new(path)?;
+                let mut input = InteractionsInput::new(path)?;
+                input.set_registry(self.registry.clone());
                 input.read(system)?;
             } else if let Some(potentials) = potentials.as_table() {
-                let input = InteractionsInput::from_toml(potentials.clone());
+                let mut input = InteractionsInput::from_toml(potentials.clone(), Some(self.path.clone()))?;
+                input.set_registry(self.registry.clone());
                 input.read(system)?;
             } else {
                 return Err(Error::from("'potentials' must be a string or a table in system"));
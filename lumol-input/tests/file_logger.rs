@@ -0,0 +1,38 @@
+// Lumol, an extensible molecular simulation engine
+// Copyright (C) Lumol's contributors — BSD license
+
+use std::fs;
+
+use log::{debug, info, warn};
+use tempfile::Builder;
+
+// The logger is global to the process, so all the checks are done in a single
+// test function.
+#[test]
+fn file_logger() {
+    let file = Builder::new().suffix(".log").tempfile().unwrap();
+    let path = file.path();
+    lumol_input::setup_file_logger(path).unwrap();
+
+    info!("first message");
+    info!("charge set to {} for {} particles", 1.0, 42);
+    warn!("this is a warning");
+    debug!("this is not printed at the default level");
+
+    let content = fs::read_to_string(path).unwrap();
+    let lines = content.lines().collect::<Vec<_>>();
+    assert_eq!(lines, [
+        "first message",
+        "charge set to 1 for 42 particles",
+        "[warning] this is a warning",
+    ]);
+
+    // Changing the level applies to the file logger
+    lumol_input::set_log_level(log::LevelFilter::Warn);
+    info!("this is not printed anymore");
+    warn!("another warning");
+
+    let content = fs::read_to_string(path).unwrap();
+    assert_eq!(content.lines().count(), 4);
+    assert_eq!(content.lines().last(), Some("[warning] another warning"));
+}
@@ -12,8 +12,10 @@ use walkdir::WalkDir;
 use rustc_test::{DynTestFn, DynTestName, TestDesc, TestDescAndFn};
 use rustc_test::ShouldPanic::No;
 
-use lumol_core::System;
-use lumol_input::{Error, Input, InteractionsInput};
+use lumol_core::{LennardJones, LennardJones124, Molecule, Particle, Potential, Quartic, System, UnitCell};
+use lumol_core::{BondAngle, CrossTermPotential, PairRestriction, Vector3D};
+use lumol_core::units;
+use lumol_input::{Error, GromacsInput, Input, InteractionsInput, PotentialRegistry};
 
 fn main() {
     env_logger::init();
@@ -90,9 +92,634 @@ fn all_tests() -> Vec<TestDescAndFn> {
         }).expect("Could not generate the tests"),
     );
 
+    tests.push(TestDescAndFn {
+        desc: TestDesc {
+            name: DynTestName(String::from("interactions/include/main.toml")),
+            ignore: false,
+            should_panic: No,
+            allow_fail: false
+        },
+        testfn: DynTestFn(Box::new(check_include_override)),
+    });
+
+    tests.push(TestDescAndFn {
+        desc: TestDesc {
+            name: DynTestName(String::from("interactions/include/cycle-a.toml")),
+            ignore: false,
+            should_panic: No,
+            allow_fail: false
+        },
+        testfn: DynTestFn(Box::new(check_include_cycle)),
+    });
+
+    tests.push(TestDescAndFn {
+        desc: TestDesc {
+            name: DynTestName(String::from("interactions/table/main.toml")),
+            ignore: false,
+            should_panic: No,
+            allow_fail: false
+        },
+        testfn: DynTestFn(Box::new(check_table_potential)),
+    });
+
+    tests.push(TestDescAndFn {
+        desc: TestDesc {
+            name: DynTestName(String::from("interactions/default units")),
+            ignore: false,
+            should_panic: No,
+            allow_fail: false
+        },
+        testfn: DynTestFn(Box::new(check_default_units)),
+    });
+
+    tests.push(TestDescAndFn {
+        desc: TestDesc {
+            name: DynTestName(String::from("interactions/non neutral system")),
+            ignore: false,
+            should_panic: No,
+            allow_fail: false
+        },
+        testfn: DynTestFn(Box::new(check_non_neutral)),
+    });
+
+    tests.push(TestDescAndFn {
+        desc: TestDesc {
+            name: DynTestName(String::from("interactions/charges by index")),
+            ignore: false,
+            should_panic: No,
+            allow_fail: false
+        },
+        testfn: DynTestFn(Box::new(check_charges_by_index)),
+    });
+
+    tests.push(TestDescAndFn {
+        desc: TestDesc {
+            name: DynTestName(String::from("interactions/iterators")),
+            ignore: false,
+            should_panic: No,
+            allow_fail: false
+        },
+        testfn: DynTestFn(Box::new(check_interactions_iterators)),
+    });
+
+    tests.push(TestDescAndFn {
+        desc: TestDesc {
+            name: DynTestName(String::from("interactions/pair matrix")),
+            ignore: false,
+            should_panic: No,
+            allow_fail: false
+        },
+        testfn: DynTestFn(Box::new(check_pair_matrix)),
+    });
+
+    tests.push(TestDescAndFn {
+        desc: TestDesc {
+            name: DynTestName(String::from("interactions/cutoff and cell size")),
+            ignore: false,
+            should_panic: No,
+            allow_fail: false
+        },
+        testfn: DynTestFn(Box::new(check_cutoff_cell_size)),
+    });
+
+    tests.push(TestDescAndFn {
+        desc: TestDesc {
+            name: DynTestName(String::from("interactions/gromacs/butane.itp")),
+            ignore: false,
+            should_panic: No,
+            allow_fail: false
+        },
+        testfn: DynTestFn(Box::new(check_gromacs_topology)),
+    });
+
+    tests.push(TestDescAndFn {
+        desc: TestDesc {
+            name: DynTestName(String::from("interactions/gromacs/unsupported")),
+            ignore: false,
+            should_panic: No,
+            allow_fail: false
+        },
+        testfn: DynTestFn(Box::new(check_gromacs_unsupported)),
+    });
+
+    tests.push(TestDescAndFn {
+        desc: TestDesc {
+            name: DynTestName(String::from("interactions/potential registry")),
+            ignore: false,
+            should_panic: No,
+            allow_fail: false
+        },
+        testfn: DynTestFn(Box::new(check_potential_registry)),
+    });
+
     return tests;
 }
 
+/// Check that the interactions read from a file can be listed from the
+/// system.
+fn check_interactions_iterators() {
+    let path = PathBuf::new().join(env!("CARGO_MANIFEST_DIR"))
+                             .join("..")
+                             .join("tests")
+                             .join("data")
+                             .join("md-nacl")
+                             .join("ewald.toml");
+
+    let mut system = System::with_cell(UnitCell::cubic(20.0));
+    system.add_molecule(Molecule::new(Particle::new("Na")));
+    system.add_molecule(Molecule::new(Particle::new("Cl")));
+    InteractionsInput::new(path).unwrap().read(&mut system).unwrap();
+
+    let mut pairs = system.pair_interactions().map(|((i, j), interaction)| {
+        let parameters = interaction.potential().parameters().expect("missing potential parameters");
+        assert_eq!(parameters.name, "lj");
+        assert_eq!(interaction.cutoff(), units::from(5.5, "A").unwrap());
+        let (i, j) = if i < j { (i, j) } else { (j, i) };
+        format!("{}-{}", i, j)
+    }).collect::<Vec<_>>();
+    pairs.sort();
+    assert_eq!(pairs, ["Cl-Cl", "Cl-Na", "Na-Na"]);
+
+    assert_eq!(system.bond_interactions().count(), 0);
+    assert_eq!(system.angle_interactions().count(), 0);
+    assert_eq!(system.dihedral_interactions().count(), 0);
+    assert!(system.coulomb_potential().is_some());
+}
+
+/// Check that a pair matrix gives the same energy as the corresponding
+/// explicit pairs, and that it must contain all the kinds in the system.
+fn check_pair_matrix() {
+    let mut system = System::with_cell(UnitCell::cubic(20.0));
+    system.add_molecule(Molecule::new(Particle::with_position("A", Vector3D::new(0.0, 0.0, 0.0))));
+    system.add_molecule(Molecule::new(Particle::with_position("B", Vector3D::new(3.5, 0.0, 0.0))));
+    system.add_molecule(Molecule::new(Particle::with_position("A", Vector3D::new(0.0, 4.0, 0.0))));
+    system.add_molecule(Molecule::new(Particle::with_position("B", Vector3D::new(0.0, 0.0, 3.8))));
+
+    let matrix = InteractionsInput::from_str("
+        [input]
+        version = 1
+
+        [pair_matrix]
+        kinds = [\"A\", \"B\"]
+        sigma = [[\"3 A\", \"3.2 A\"], [\"3.2 A\", \"3.4 A\"]]
+        epsilon = [[\"0.5 kJ/mol\", \"0.4 kJ/mol\"], [\"0.4 kJ/mol\", \"0.2 kJ/mol\"]]
+        cutoff = \"8 A\"
+    ").unwrap();
+
+    let pairs = InteractionsInput::from_str("
+        [input]
+        version = 1
+
+        [global]
+        cutoff = \"8 A\"
+
+        [pairs]
+        A-A = {type = \"lj\", sigma = \"3 A\", epsilon = \"0.5 kJ/mol\"}
+        A-B = {type = \"lj\", sigma = \"3.2 A\", epsilon = \"0.4 kJ/mol\"}
+        B-B = {type = \"lj\", sigma = \"3.4 A\", epsilon = \"0.2 kJ/mol\"}
+    ").unwrap();
+
+    let mut explicit = system.clone();
+    matrix.read(&mut system).unwrap();
+    pairs.read(&mut explicit).unwrap();
+
+    let energy = system.potential_energy();
+    assert!(energy < 0.0);
+    assert!(f64::abs(energy - explicit.potential_energy()) < 1e-12);
+
+    system.add_molecule(Molecule::new(Particle::new("C")));
+    match matrix.read(&mut system) {
+        Err(Error::Config(reason)) => assert_eq!(reason, "missing 'C' in pair matrix kinds"),
+        _ => panic!("This test should fail with a Config error"),
+    }
+}
+
+/// Check that cutoffs bigger than half of the smallest cell length are
+/// rejected.
+fn check_cutoff_cell_size() {
+    let input = InteractionsInput::from_str("
+        [input]
+        version = 1
+
+        [pairs]
+        A-A = {type = \"lj\", sigma = \"3 A\", epsilon = \"0.5 kJ/mol\", cutoff = \"6 A\"}
+    ").unwrap();
+
+    let mut system = System::with_cell(UnitCell::cubic(12.0));
+    system.add_molecule(Molecule::new(Particle::new("A")));
+    input.read(&mut system).unwrap();
+    assert!(system.pair_potential(0, 0).is_some());
+
+    let mut system = System::with_cell(UnitCell::ortho(12.0, 12.0, 10.0));
+    system.add_molecule(Molecule::new(Particle::new("A")));
+    match input.read(&mut system) {
+        Err(Error::Config(reason)) => assert_eq!(
+            reason,
+            "the cutoff of 6 A is bigger than half of the smallest cell length (5 A), \
+            try increasing the cell size or decreasing the cutoff"
+        ),
+        _ => panic!("This test should fail with a Config error"),
+    }
+    assert!(system.pair_potential(0, 0).is_none());
+}
+
+/// Check that the interactions are read from a GROMACS topology, and
+/// converted to lumol units.
+fn check_gromacs_topology() {
+    let path = PathBuf::new().join(env!("CARGO_MANIFEST_DIR"))
+                             .join("tests")
+                             .join("interactions")
+                             .join("gromacs")
+                             .join("butane.itp");
+
+    let mut system = System::new();
+    let mut butane = Molecule::new(Particle::with_position("CH3", Vector3D::new(0.0, 0.0, 0.0)));
+    butane.add_particle_bonded_to(0, Particle::with_position("CH2", Vector3D::new(1.5, 0.0, 0.0)));
+    butane.add_particle_bonded_to(1, Particle::with_position("CH2", Vector3D::new(2.0, 1.4, 0.0)));
+    butane.add_particle_bonded_to(2, Particle::with_position("CH3", Vector3D::new(3.5, 1.4, 0.0)));
+    system.add_molecule(butane);
+    GromacsInput::new(path).unwrap().read(&mut system).unwrap();
+
+    let bond = system.bond_potential(0, 1).expect("missing CH3-CH2 bond potential");
+    let parameters = bond.parameters().expect("missing bond parameters");
+    assert_eq!(parameters.name, "harmonic");
+    assert_eq!(parameters.values[0].0, "k");
+    assert!(f64::abs(parameters.values[0].1 - units::from(224262.4, "kJ/mol/nm^2").unwrap()) < 1e-9);
+    assert_eq!(parameters.values[1].0, "x0");
+    assert!(f64::abs(parameters.values[1].1 - 1.53) < 1e-12);
+    assert!(system.bond_potential(1, 2).is_some());
+
+    let angle = system.angle_potential(0, 1, 2).expect("missing CH3-CH2-CH2 angle potential");
+    assert!(f64::abs(angle.energy(units::from(111.0, "deg").unwrap())) < 1e-12);
+
+    let dihedral = system.dihedral_potential(0, 1, 2, 3).expect("missing dihedral potential");
+    assert_eq!(dihedral.parameters().expect("missing dihedral parameters").name, "ryckaert-bellemans");
+
+    let pair = system.pair_potential(0, 3).expect("missing CH3-CH3 pair potential");
+    assert_eq!(pair.restriction(), PairRestriction::Scale14(0.5));
+    let lj = LennardJones {
+        sigma: units::from(0.3905, "nm").unwrap(),
+        epsilon: units::from(f64::sqrt(0.7322 * 0.49162), "kJ/mol").unwrap(),
+    };
+    let pair = system.pair_potential(0, 1).expect("missing CH3-CH2 pair potential");
+    assert!(f64::abs(pair.energy(4.5) - lj.energy(4.5)) < 1e-12);
+}
+
+/// Check that unsupported features of GROMACS topologies produce errors
+/// instead of being silently ignored.
+fn check_gromacs_unsupported() {
+    match GromacsInput::from_str("#include \"oplsaa.ff/forcefield.itp\"\n") {
+        Err(error) => assert_eq!(
+            error.to_string(),
+            "#include is not supported in GROMACS topology, at line 1"
+        ),
+        Ok(_) => panic!("#include should not be accepted"),
+    }
+
+    let topology = "
+[ moleculetype ]
+  butane  3
+
+[ atoms ]
+  1   CH3
+  2   CH2
+  3   CH2
+  4   CH3
+
+[ pairs ]
+  1   4   1   0.3905   0.5
+";
+    let mut system = System::new();
+    match GromacsInput::from_str(topology).unwrap().read(&mut system) {
+        Err(error) => assert_eq!(
+            error.to_string(),
+            "explicit pair parameters are not supported in GROMACS topology, at line 12"
+        ),
+        Ok(_) => panic!("explicit pair parameters should not be accepted"),
+    }
+}
+
+/// Check that user-defined pair potentials can be read from an interactions
+/// input after registering them.
+fn check_potential_registry() {
+    let content = "
+        [input]
+        version = 1
+
+        [pairs]
+        A-A = {type = \"my-potential\", sigma = \"3 A\", epsilon = \"0.5 kJ/mol\", cutoff = \"8 A\"}
+    ";
+
+    let mut system = System::new();
+    system.add_molecule(Molecule::new(Particle::new("A")));
+
+    let input = InteractionsInput::from_str(content).unwrap();
+    match input.read(&mut system) {
+        Err(Error::Config(reason)) => assert_eq!(reason, "unknown potential type 'my-potential'"),
+        _ => panic!("This test should fail with a Config error"),
+    }
+
+    let mut registry = PotentialRegistry::new();
+    registry.register_pair("my-potential", |table| {
+        let read = |key: &str| -> Result<f64, Error> {
+            let value = table.get(key).and_then(|value| value.as_str()).ok_or(
+                Error::from(format!("missing '{}' in my-potential", key))
+            )?;
+            Ok(units::from_str(value)?)
+        };
+        Ok(Box::new(LennardJones {
+            sigma: read("sigma")?,
+            epsilon: read("epsilon")?,
+        }))
+    });
+
+    let mut input = InteractionsInput::from_str(content).unwrap();
+    input.set_registry(registry);
+    input.read(&mut system).unwrap();
+
+    let lj = LennardJones {
+        sigma: units::from(3.0, "A").unwrap(),
+        epsilon: units::from(0.5, "kJ/mol").unwrap(),
+    };
+    let potential = system.pair_potential(0, 0).expect("missing A-A potential");
+    assert_eq!(potential.energy(3.5), lj.energy(3.5));
+    assert_eq!(potential.cutoff(), 8.0);
+}
+
+/// Check that interactions defined in a file override the ones from included
+/// files.
+fn check_include_override() {
+    let path = PathBuf::new().join(env!("CARGO_MANIFEST_DIR"))
+                             .join("tests")
+                             .join("interactions")
+                             .join("include")
+                             .join("main.toml");
+
+    let mut system = System::new();
+    system.add_molecule(Molecule::new(Particle::new("A")));
+    system.add_molecule(Molecule::new(Particle::new("A")));
+    system.add_molecule(Molecule::new(Particle::new("B")));
+    InteractionsInput::new(path).unwrap().read(&mut system).unwrap();
+
+    let lj = LennardJones {
+        sigma: units::from(2.0, "A").unwrap(),
+        epsilon: units::from(1.0, "kJ/mol").unwrap(),
+    };
+    let potential = system.pair_potential(0, 1).expect("missing A-A potential");
+    assert_eq!(potential.energy(2.5), lj.energy(2.5));
+
+    // interactions only defined in the included file are still there
+    assert!(system.pair_potential(0, 2).is_some());
+}
+
+/// Check that files including themselves, directly or indirectly, produce
+/// an error.
+fn check_include_cycle() {
+    let path = PathBuf::new().join(env!("CARGO_MANIFEST_DIR"))
+                             .join("tests")
+                             .join("interactions")
+                             .join("include")
+                             .join("cycle-a.toml");
+
+    let mut system = System::new();
+    match InteractionsInput::new(path).unwrap().read(&mut system) {
+        Err(Error::Config(reason)) => assert!(reason.ends_with(
+            "cycle-a.toml' includes itself, directly or through other included files"
+        )),
+        _ => panic!("This test should fail with a Config error"),
+    }
+}
+
+/// Check that tabulated potentials are read from files relative to the
+/// input file, and match the analytic potential.
+fn check_table_potential() {
+    let path = PathBuf::new().join(env!("CARGO_MANIFEST_DIR"))
+                             .join("tests")
+                             .join("interactions")
+                             .join("table")
+                             .join("main.toml");
+
+    let mut system = System::new();
+    system.add_molecule(Molecule::new(Particle::new("A")));
+    system.add_molecule(Molecule::new(Particle::new("A")));
+    system.add_molecule(Molecule::new(Particle::new("B")));
+    system.add_molecule(Molecule::new(Particle::new("B")));
+    InteractionsInput::new(path).unwrap().read(&mut system).unwrap();
+
+    let lj = LennardJones {
+        sigma: units::from(3.4, "A").unwrap(),
+        epsilon: units::from(1.0, "kJ/mol").unwrap(),
+    };
+    let table = system.pair_potential(0, 1).expect("missing A-A potential");
+    for &r in &[3.2, 3.8, 4.5, 6.0, 9.0] {
+        assert!(f64::abs(table.energy(r) - lj.energy(r)) < 1e-3 * f64::abs(lj.energy(r)));
+        assert!(f64::abs(table.force(r) - lj.force(r)) < 1e-3 * f64::abs(lj.force(r)));
+    }
+
+    // the energy unit is used to convert energies and forces
+    let kcal = units::from(1.0, "kcal/mol").unwrap() / units::from(1.0, "kJ/mol").unwrap();
+    let table = system.pair_potential(2, 3).expect("missing B-B potential");
+    assert!(f64::abs(table.energy(4.5) - kcal * lj.energy(4.5)) < 1e-3 * f64::abs(kcal * lj.energy(4.5)));
+    assert!(f64::abs(table.force(4.5) - kcal * lj.force(4.5)) < 1e-3 * f64::abs(kcal * lj.force(4.5)));
+}
+
+/// Check that bare numbers use the default units, and that explicit units
+/// take precedence.
+fn check_default_units() {
+    let mut system = System::new();
+    system.add_molecule(Molecule::new(Particle::new("A")));
+    system.add_molecule(Molecule::new(Particle::new("A")));
+    system.add_molecule(Molecule::new(Particle::new("B")));
+    system.add_molecule(Molecule::new(Particle::new("B")));
+    system.add_molecule(Molecule::new(Particle::new("C")));
+    system.add_molecule(Molecule::new(Particle::new("C")));
+    let mut molecule = Molecule::new(Particle::new("Q"));
+    molecule.add_particle_bonded_to(0, Particle::new("Q"));
+    system.add_molecule(molecule);
+
+    let input = InteractionsInput::from_str("
+        [input]
+        version = 1
+
+        [units]
+        energy = \"kcal/mol\"
+        length = \"nm\"
+
+        [pairs]
+        A-A = {type = \"lj\", sigma = 0.34, epsilon = 0.2, cutoff = 1}
+        B-B = {type = \"lj\", sigma = \"3.4 A\", epsilon = 0.2, cutoff = \"10 A\"}
+        C-C = {type = \"lennard-jones-124\", sigma = 0.34, epsilon = 0.2, c4 = 3e-4, cutoff = 1}
+
+        [bonds]
+        Q-Q = {type = \"quartic\", k2 = 200, k3 = -500, k4 = 800, x0 = 0.15}
+    ").unwrap();
+    input.read(&mut system).unwrap();
+
+    let lj = LennardJones {
+        sigma: units::from(3.4, "A").unwrap(),
+        epsilon: units::from(0.2, "kcal/mol").unwrap(),
+    };
+
+    let default = system.pair_potential(0, 1).expect("missing A-A potential");
+    assert_eq!(default.cutoff(), 10.0);
+    assert!(f64::abs(default.energy(4.0) - lj.energy(4.0)) < 1e-12);
+
+    let explicit = system.pair_potential(2, 3).expect("missing B-B potential");
+    assert_eq!(explicit.cutoff(), 10.0);
+    assert!(f64::abs(explicit.energy(4.0) - lj.energy(4.0)) < 1e-12);
+
+    let lj124 = LennardJones124 {
+        sigma: units::from(3.4, "A").unwrap(),
+        epsilon: units::from(0.2, "kcal/mol").unwrap(),
+        c4: units::from(3e-4, "kcal/mol*nm^4").unwrap(),
+    };
+    let c4 = system.pair_potential(4, 5).expect("missing C-C potential");
+    assert!(f64::abs(c4.energy(4.0) - lj124.energy(4.0)) < 1e-12);
+
+    let quartic = Quartic {
+        k2: units::from(200.0, "kcal/mol/nm^2").unwrap(),
+        k3: units::from(-500.0, "kcal/mol/nm^3").unwrap(),
+        k4: units::from(800.0, "kcal/mol/nm^4").unwrap(),
+        x0: units::from(0.15, "nm").unwrap(),
+    };
+    let bond = system.bond_potential(6, 7).expect("missing Q-Q potential");
+    assert!(f64::abs(bond.energy(1.7) - quartic.energy(1.7)) < 1e-12);
+
+    let mut system = System::new();
+    system.add_molecule(Molecule::new(Particle::new("A")));
+    system.add_molecule(Molecule::new(Particle::new("B")));
+
+    let input = InteractionsInput::from_str("
+        [input]
+        version = 1
+
+        [units]
+        energy = \"kcal/mol\"
+        length = \"nm\"
+
+        [pair_matrix]
+        kinds = [\"A\", \"B\"]
+        sigma = [[0.34, 0.36], [0.36, \"3.8 A\"]]
+        epsilon = [[0.2, 0.1], [0.1, \"0.3 kJ/mol\"]]
+        cutoff = 1
+    ").unwrap();
+    input.read(&mut system).unwrap();
+
+    let lj = LennardJones {
+        sigma: units::from(3.6, "A").unwrap(),
+        epsilon: units::from(0.1, "kcal/mol").unwrap(),
+    };
+    let matrix = system.pair_potential(0, 1).expect("missing A-B potential");
+    assert_eq!(matrix.cutoff(), 10.0);
+    assert!(f64::abs(matrix.energy(4.0) - lj.energy(4.0)) < 1e-12);
+
+    let mut system = System::new();
+    let mut molecule = Molecule::new(Particle::new("H"));
+    molecule.add_particle_bonded_to(0, Particle::new("C"));
+    molecule.add_particle_bonded_to(1, Particle::new("O"));
+    system.add_molecule(molecule);
+
+    let input = InteractionsInput::from_str("
+        [input]
+        version = 1
+
+        [units]
+        energy = \"kcal/mol\"
+        length = \"nm\"
+        angle = \"deg\"
+
+        [cross_terms]
+        H-C-O = {type = \"bond-angle\", k1 = 181, k2 = 207, r1 = 0.11, r2 = 0.142, theta0 = 108}
+    ").unwrap();
+    input.read(&mut system).unwrap();
+
+    let bond_angle = BondAngle {
+        k1: units::from(181.0, "kcal/mol/nm/deg").unwrap(),
+        k2: units::from(207.0, "kcal/mol/nm/deg").unwrap(),
+        r1: units::from(0.11, "nm").unwrap(),
+        r2: units::from(0.142, "nm").unwrap(),
+        theta0: units::from(108.0, "deg").unwrap(),
+    };
+    let cross_term = system.cross_term_potential(0, 1, 2).expect("missing H-C-O potential");
+    let expected = bond_angle.energy(1.2, 1.3, 1.8);
+    assert!(f64::abs(cross_term.energy(1.2, 1.3, 1.8) - expected) < 1e-12 * expected.abs());
+}
+
+/// Check that coulombic solvers are not used with a non-neutral system,
+/// unless the solver can handle charged systems.
+fn check_non_neutral() {
+    for solver in &["wolf = {cutoff = \"8 A\"}", "ewald = {cutoff = \"8 A\", kmax = 3}"] {
+        let mut system = System::new();
+        system.add_molecule(Molecule::new(Particle::new("Na")));
+        system.add_molecule(Molecule::new(Particle::new("Cl")));
+        system.add_molecule(Molecule::new(Particle::new("Cl")));
+
+        let input = InteractionsInput::from_str(&format!("
+            [input]
+            version = 1
+
+            [charges]
+            Na = 1.0
+            Cl = -1.0
+
+            [coulomb]
+            {}
+        ", solver)).unwrap();
+
+        let message = match input.read(&mut system) {
+            Err(Error::Config(reason)) => reason,
+            _ => panic!("This test should fail with a Config error"),
+        };
+        assert!(message.starts_with("the system is not neutral and has a net charge of -1"));
+        assert!(system.coulomb_potential().is_none());
+    }
+
+    // Ewald summation with a neutralizing background can be used with
+    // charged systems
+    let mut system = System::new();
+    system.add_molecule(Molecule::new(Particle::new("Na")));
+    system.add_molecule(Molecule::new(Particle::new("Cl")));
+    system.add_molecule(Molecule::new(Particle::new("Cl")));
+    let input = InteractionsInput::from_str("
+        [input]
+        version = 1
+
+        [charges]
+        Na = 1.0
+        Cl = -1.0
+
+        [coulomb]
+        ewald = {cutoff = \"8 A\", kmax = 3, background_correction = true}
+    ").unwrap();
+    input.read(&mut system).unwrap();
+    assert!(system.coulomb_potential().is_some());
+}
+
+/// Check that particles with the same name can have different charges when
+/// using 'charges_by_index', and that it overrides the 'charges' section.
+fn check_charges_by_index() {
+    let mut system = System::new();
+    system.add_molecule(Molecule::new(Particle::new("O")));
+    system.add_molecule(Molecule::new(Particle::new("O")));
+    system.add_molecule(Molecule::new(Particle::new("H")));
+
+    let input = InteractionsInput::from_str("
+        charges_by_index = [-0.4, -0.6, 1]
+
+        [input]
+        version = 1
+
+        [charges]
+        O = -0.5
+        H = 1
+    ").unwrap();
+    input.read(&mut system).unwrap();
+
+    assert_eq!(system.particles().charge[0], -0.4);
+    assert_eq!(system.particles().charge[1], -0.6);
+    assert_eq!(system.particles().charge[2], 1.0);
+}
+
 /// Generate the tests by calling `callback` for every TOML files at the given
 /// `root`.
 fn generate_tests<F>(root: &str, callback: F) -> Result<Vec<TestDescAndFn>, io::Error>
@@ -0,0 +1,24 @@
+// Lumol, an extensible molecular simulation engine
+// Copyright (C) Lumol's contributors — BSD license
+
+use std::fs;
+
+use log::info;
+use tempfile::Builder;
+
+// The logger is global to the process, so this check needs its own test file.
+#[test]
+fn invalid_log_level() {
+    std::env::set_var("LUMOL_LOG_LEVEL", "verbose");
+
+    let file = Builder::new().suffix(".log").tempfile().unwrap();
+    lumol_input::setup_file_logger(file.path()).unwrap();
+    info!("using the default level");
+
+    let content = fs::read_to_string(file.path()).unwrap();
+    let lines = content.lines().collect::<Vec<_>>();
+    assert_eq!(lines, [
+        "[warning] invalid logging level 'verbose' in LUMOL_LOG_LEVEL, using 'info'",
+        "using the default level",
+    ]);
+}
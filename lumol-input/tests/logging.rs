@@ -0,0 +1,26 @@
+// Lumol, an extensible molecular simulation engine
+// Copyright (C) Lumol's contributors — BSD license
+
+use log::{log_enabled, Level, LevelFilter};
+
+// The logger is global to the process, so all the checks are done in a single
+// test function.
+#[test]
+fn default_logger_level() {
+    const COULOMB: &str = "lumol_input::interactions::coulomb";
+
+    lumol_input::setup_default_logger_with_level(LevelFilter::Warn);
+    // Charges assignment messages are sent with the info level
+    assert!(!log_enabled!(target: COULOMB, Level::Info));
+    assert!(log_enabled!(target: COULOMB, Level::Warn));
+    assert!(log_enabled!(Level::Error));
+
+    lumol_input::set_log_level(LevelFilter::Debug);
+    assert!(log_enabled!(target: COULOMB, Level::Info));
+    assert!(log_enabled!(Level::Debug));
+    assert!(!log_enabled!(Level::Trace));
+
+    // Setting up the logger a second time does not change the level
+    lumol_input::setup_default_logger_with_level(LevelFilter::Error);
+    assert!(log_enabled!(Level::Debug));
+}
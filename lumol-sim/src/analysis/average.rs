@@ -0,0 +1,164 @@
+// Lumol, an extensible molecular simulation engine
+// Copyright (C) Lumol's contributors — BSD license
+
+use std::fmt;
+
+/// Average of a scalar value, with an error bar estimated by block averaging.
+///
+/// The accumulated values are split in a fixed number of blocks of
+/// consecutive values, and the error on the mean is the standard error of
+/// the blocks averages. If the blocks are longer than the correlation time
+/// of the values, the blocks averages are independent and the error is an
+/// unbiased estimation of the uncertainty on the mean.
+///
+/// # Examples
+///
+/// ```
+/// # use lumol_sim::analysis::Average;
+/// let mut average = Average::new(4);
+/// for &value in &[1.0, 3.0, 2.0, 2.0, 3.0, 1.0, 2.0, 2.0] {
+///     average.accumulate(value);
+/// }
+///
+/// assert_eq!(average.mean(), 2.0);
+/// assert_eq!(average.error(), 0.0);
+/// assert_eq!(average.to_string(), "2 ± 0");
+/// ```
+#[derive(Clone, Debug)]
+pub struct Average {
+    /// All the accumulated values
+    values: Vec<f64>,
+    /// Number of blocks to use for the error estimation
+    blocks: usize,
+}
+
+impl Average {
+    /// Create a new empty `Average`, using the given number of `blocks` to
+    /// estimate the error.
+    ///
+    /// # Panics
+    ///
+    /// If `blocks` is smaller than 2.
+    pub fn new(blocks: usize) -> Average {
+        assert!(blocks >= 2, "block averaging needs at least two blocks");
+        Average {
+            values: Vec::new(),
+            blocks: blocks,
+        }
+    }
+
+    /// Add a new `value` to this average
+    pub fn accumulate(&mut self, value: f64) {
+        self.values.push(value);
+    }
+
+    /// Get the number of accumulated values
+    pub fn count(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Get the mean of the accumulated values, or NaN if no value was
+    /// accumulated.
+    pub fn mean(&self) -> f64 {
+        if self.values.is_empty() {
+            return f64::NAN;
+        }
+        return self.values.iter().sum::<f64>() / self.values.len() as f64;
+    }
+
+    /// Get the standard error on the mean, estimated by block averaging. If
+    /// the number of values is not a multiple of the number of blocks, the
+    /// first values are not used to compute the error. This function returns
+    /// NaN if there are less values than blocks.
+    pub fn error(&self) -> f64 {
+        let block_size = self.values.len() / self.blocks;
+        if block_size == 0 {
+            return f64::NAN;
+        }
+
+        let skip = self.values.len() - block_size * self.blocks;
+        let averages = self.values[skip..].chunks(block_size).map(|block| {
+            block.iter().sum::<f64>() / block_size as f64
+        }).collect::<Vec<_>>();
+
+        let blocks = self.blocks as f64;
+        let mean = averages.iter().sum::<f64>() / blocks;
+        let variance = averages.iter().map(|average| (average - mean) * (average - mean)).sum::<f64>();
+        return f64::sqrt(variance / (blocks * (blocks - 1.0)));
+    }
+}
+
+impl fmt::Display for Average {
+    #[allow(clippy::non_ascii_literal)]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let error = self.error();
+        if error.is_nan() {
+            write!(f, "{}", self.mean())
+        } else {
+            write!(f, "{} ± {}", self.mean(), error)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::velocities::seeded_rng;
+    use rand_distr::{Distribution, Normal};
+
+    #[test]
+    fn empty() {
+        let mut average = Average::new(10);
+        assert!(average.mean().is_nan());
+        assert!(average.error().is_nan());
+
+        for _ in 0..9 {
+            average.accumulate(1.0);
+        }
+        assert_eq!(average.mean(), 1.0);
+        assert!(average.error().is_nan());
+        assert_eq!(average.to_string(), "1");
+    }
+
+    #[test]
+    fn gaussian() {
+        let mut rng = seeded_rng(7);
+        let normal = Normal::new(3.0, 2.0).unwrap();
+
+        let count = 100_000;
+        let mut average = Average::new(100);
+        for _ in 0..count {
+            average.accumulate(normal.sample(&mut rng));
+        }
+        assert_eq!(average.count(), count);
+
+        let expected = 2.0 / f64::sqrt(count as f64);
+        assert!(f64::abs(average.error() - expected) < 0.25 * expected);
+        assert!(f64::abs(average.mean() - 3.0) < 4.0 * expected);
+    }
+
+    #[test]
+    fn correlated() {
+        // each value is repeated 100 times, giving strongly correlated values
+        let mut rng = seeded_rng(7);
+        let normal = Normal::new(0.0, 1.0).unwrap();
+        let mut average = Average::new(20);
+        for _ in 0..1000 {
+            let value = normal.sample(&mut rng);
+            for _ in 0..100 {
+                average.accumulate(value);
+            }
+        }
+
+        // the error is close to the standard error of the 1000 independent
+        // values, not of the 100000 accumulated values
+        let expected = 1.0 / f64::sqrt(1000.0);
+        assert!(f64::abs(average.error() - expected) < 0.5 * expected);
+    }
+
+    #[test]
+    #[should_panic]
+    fn one_block() {
+        let _ = Average::new(1);
+    }
+}
@@ -0,0 +1,158 @@
+// Lumol, an extensible molecular simulation engine
+// Copyright (C) Lumol's contributors — BSD license
+
+use lumol_core::System;
+
+/// Number density profile along one of the unit cell axis.
+///
+/// The unit cell is divided in slabs of equal volume along the chosen axis,
+/// and the number of particles in each slab is averaged over all the
+/// accumulated configurations. The profile can be restricted to the
+/// particles with a given name.
+///
+/// # Examples
+///
+/// ```
+/// # use lumol_core::{Molecule, Particle, System, UnitCell, Vector3D};
+/// # use lumol_sim::analysis::DensityProfile;
+/// let mut system = System::with_cell(UnitCell::cubic(10.0));
+/// system.add_molecule(Molecule::new(Particle::with_position("Ar", Vector3D::new(0.0, 0.0, 1.0))));
+///
+/// // profile along z, using 5 bins
+/// let mut profile = DensityProfile::new(2, 5);
+/// profile.accumulate(&system);
+///
+/// let bins = profile.finish();
+/// assert_eq!(bins.len(), 5);
+/// assert_eq!(bins[0], (1.0, 1.0 / 200.0));
+/// assert_eq!(bins[1], (3.0, 0.0));
+/// ```
+#[derive(Clone)]
+pub struct DensityProfile {
+    /// Axis along which to compute the profile
+    axis: usize,
+    /// Accumulated number density in each bin
+    densities: Vec<f64>,
+    /// Accumulated length of the cell along the axis
+    length: f64,
+    /// Only use particles with this name
+    name: Option<String>,
+    /// Number of accumulated configurations
+    count: u64,
+}
+
+impl DensityProfile {
+    /// Create a new `DensityProfile` along the given `axis` (0 for the first
+    /// cell vector, 1 for the second and 2 for the third), using `nbins`
+    /// slabs.
+    pub fn new(axis: usize, nbins: usize) -> DensityProfile {
+        assert!(axis < 3, "The axis must be 0, 1 or 2 in density profile, got {}", axis);
+        assert!(nbins > 0, "The number of bins must be positive in density profile");
+        DensityProfile {
+            axis: axis,
+            densities: vec![0.0; nbins],
+            length: 0.0,
+            name: None,
+            count: 0,
+        }
+    }
+
+    /// Only include the particles named `name` in this profile
+    pub fn set_name(&mut self, name: &str) {
+        self.name = Some(name.into());
+    }
+
+    /// Add the configuration of the `system` to the profile
+    pub fn accumulate(&mut self, system: &System) {
+        assert!(!system.cell.is_infinite(), "Can not compute density profile with an infinite cell");
+
+        let nbins = self.densities.len();
+        let slab_volume = system.cell.volume() / nbins as f64;
+        for (name, position) in system.particles().name.iter().zip(system.particles().position) {
+            if let Some(ref selected) = self.name {
+                if name != selected {
+                    continue;
+                }
+            }
+
+            let fractional = system.cell.fractional(position)[self.axis];
+            let fractional = fractional - f64::floor(fractional);
+            let bin = usize::min((fractional * nbins as f64) as usize, nbins - 1);
+            self.densities[bin] += 1.0 / slab_volume;
+        }
+
+        self.length += system.cell.lengths()[self.axis];
+        self.count += 1;
+    }
+
+    /// Get the averaged profile, as a list of `(position, density)` for all
+    /// the bins. The position is the center of the bin along the axis.
+    pub fn finish(&self) -> Vec<(f64, f64)> {
+        if self.count == 0 {
+            return Vec::new();
+        }
+
+        let count = self.count as f64;
+        let width = self.length / count / self.densities.len() as f64;
+        return self.densities.iter().enumerate().map(|(i, density)| {
+            ((i as f64 + 0.5) * width, density / count)
+        }).collect();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lumol_core::{Molecule, Particle, UnitCell, Vector3D};
+
+    use rand::{Rng, SeedableRng};
+    use rand_xorshift::XorShiftRng;
+
+    #[test]
+    fn uniform() {
+        let mut rng = XorShiftRng::seed_from_u64(42);
+        let mut profile = DensityProfile::new(0, 10);
+        let natoms = 1000;
+        let cell = UnitCell::ortho(20.0, 10.0, 10.0);
+        for _ in 0..20 {
+            let mut system = System::with_cell(cell);
+            for _ in 0..natoms {
+                let position = Vector3D::new(
+                    rng.gen_range(-20.0, 20.0),
+                    rng.gen_range(0.0, 10.0),
+                    rng.gen_range(0.0, 10.0),
+                );
+                system.add_molecule(Molecule::new(Particle::with_position("Ar", position)));
+            }
+            profile.accumulate(&system);
+        }
+
+        let bulk = natoms as f64 / cell.volume();
+        let bins = profile.finish();
+        assert_eq!(bins.len(), 10);
+        for (i, &(z, density)) in bins.iter().enumerate() {
+            assert_eq!(z, 1.0 + 2.0 * i as f64);
+            assert!(f64::abs(density - bulk) / bulk < 0.1, "density is {} instead of {}", density, bulk);
+        }
+    }
+
+    #[test]
+    fn names() {
+        let mut system = System::with_cell(UnitCell::cubic(10.0));
+        system.add_molecule(Molecule::new(Particle::with_position("Na", Vector3D::new(1.0, 1.0, 2.0))));
+        system.add_molecule(Molecule::new(Particle::with_position("Cl", Vector3D::new(1.0, 1.0, 7.0))));
+        system.add_molecule(Molecule::new(Particle::with_position("Cl", Vector3D::new(1.0, 1.0, -1.0))));
+
+        let mut profile = DensityProfile::new(2, 2);
+        profile.accumulate(&system);
+        assert_eq!(profile.finish(), vec![(2.5, 1.0 / 500.0), (7.5, 2.0 / 500.0)]);
+
+        let mut profile = DensityProfile::new(2, 2);
+        profile.set_name("Na");
+        profile.accumulate(&system);
+        assert_eq!(profile.finish(), vec![(2.5, 1.0 / 500.0), (7.5, 0.0)]);
+
+        let profile = DensityProfile::new(2, 2);
+        assert!(profile.finish().is_empty());
+    }
+}
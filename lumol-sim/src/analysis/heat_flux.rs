@@ -0,0 +1,276 @@
+// Lumol, an extensible molecular simulation engine
+// Copyright (C) Lumol's contributors — BSD license
+
+use lumol_core::{System, Vector3D};
+use lumol_core::consts::K_BOLTZMANN;
+
+/// Microscopic heat flux, and its autocorrelation for the computation of the
+/// thermal conductivity with the Green-Kubo relation.
+///
+/// The heat flux (or energy current) is computed from the per-atom energies
+/// $e_i$, velocities $\vec v_i$ and pair forces $\vec F_{ij}$ as
+///
+/// $$ \vec J = \sum_i e_i \vec v_i + \frac{1}{2} \sum_{i \neq j} \vec r_{ij}
+///    \left(\vec F_{ij} \cdot \vec v_i \right) $$
+///
+/// where the first term is the convective term, and the second is the virial
+/// term. The per-atom energy $e_i$ is the kinetic energy of the atom plus
+/// half of the energy of all the pairs containing this atom. With this
+/// definition, the time derivative of $\sum_i \vec r_i e_i$ is $\vec J$ for
+/// an isolated system.
+///
+/// The thermal conductivity is then given by the Green-Kubo relation:
+///
+/// $$ \kappa = \frac{1}{3 V k_B T^2} \int_0^\infty \langle \vec J(0) \cdot
+///    \vec J(t) \rangle dt $$
+///
+/// Only pair interactions are included in the per-atom energies and virial,
+/// so this observable can only be used with atomic systems, without
+/// coulombic, global or bonded interactions.
+///
+/// # Examples
+///
+/// ```
+/// # use lumol_core::{Molecule, Particle, System, UnitCell, Vector3D};
+/// # use lumol_sim::analysis::HeatFlux;
+/// let mut system = System::with_cell(UnitCell::cubic(10.0));
+/// let mut particle = Particle::new("Ar");
+/// particle.velocity = Vector3D::new(0.1, 0.0, 0.0);
+/// system.add_molecule(Molecule::new(particle));
+///
+/// let energy = system.kinetic_energy();
+/// assert_eq!(HeatFlux::compute(&system), Vector3D::new(0.1 * energy, 0.0, 0.0));
+///
+/// let mut flux = HeatFlux::new();
+/// flux.accumulate(&system);
+/// flux.accumulate(&system);
+/// let autocorrelation = flux.finish(1);
+/// assert_eq!(autocorrelation.len(), 2);
+/// ```
+#[derive(Clone, Default)]
+pub struct HeatFlux {
+    /// Heat flux of all the accumulated configurations
+    fluxes: Vec<Vector3D>,
+}
+
+impl HeatFlux {
+    /// Create a new empty `HeatFlux` observable
+    pub fn new() -> HeatFlux {
+        HeatFlux::default()
+    }
+
+    /// Get the energy of all the atoms in the `system`, *i.e.* their kinetic
+    /// energy plus half of the energy of all the pairs containing the atom.
+    pub fn energies(system: &System) -> Vec<f64> {
+        check_interactions(system);
+        let particles = system.particles();
+        let mut energies = particles.mass.iter().zip(particles.velocity).map(|(&mass, velocity)| {
+            0.5 * mass * velocity.norm2()
+        }).collect::<Vec<_>>();
+
+        for_each_pair(system, |i, j, _, energy, _| {
+            energies[i] += 0.5 * energy;
+            energies[j] += 0.5 * energy;
+        });
+        return energies;
+    }
+
+    /// Compute the instantaneous heat flux of the `system`
+    pub fn compute(system: &System) -> Vector3D {
+        let energies = HeatFlux::energies(system);
+        let velocities = system.particles().velocity;
+
+        let mut flux = Vector3D::zero();
+        for (&energy, velocity) in energies.iter().zip(velocities) {
+            flux += energy * velocity;
+        }
+
+        for_each_pair(system, |i, j, distance, _, force| {
+            flux += 0.5 * (force * (velocities[i] + velocities[j])) * distance;
+        });
+        return flux;
+    }
+
+    /// Add the heat flux of the `system` to the accumulated values. The
+    /// configurations should be accumulated at regular time intervals.
+    pub fn accumulate(&mut self, system: &System) {
+        self.fluxes.push(HeatFlux::compute(system));
+    }
+
+    /// Get the number of accumulated configurations
+    pub fn count(&self) -> usize {
+        self.fluxes.len()
+    }
+
+    /// Get the autocorrelation $\langle \vec J(0) \cdot \vec J(t) \rangle$ of
+    /// the heat flux, for all the time lags from 0 to `max_lag` accumulated
+    /// configurations. The lags with no data are set to 0.
+    pub fn finish(&self, max_lag: usize) -> Vec<f64> {
+        let count = self.fluxes.len();
+        return (0..=max_lag).map(|lag| {
+            if lag >= count {
+                return 0.0;
+            }
+            let sum = self.fluxes[..(count - lag)].iter().zip(&self.fluxes[lag..]).map(|(first, second)| {
+                first * second
+            }).sum::<f64>();
+            sum / (count - lag) as f64
+        }).collect();
+    }
+
+    /// Get the thermal conductivity from the Green-Kubo relation, integrating
+    /// the autocorrelation of the heat flux up to `max_lag` with the
+    /// trapezoidal rule. `interval` is the time between two accumulated
+    /// configurations, and `volume` and `temperature` are the average volume
+    /// and temperature of the system.
+    pub fn thermal_conductivity(&self, max_lag: usize, interval: f64, volume: f64, temperature: f64) -> f64 {
+        let autocorrelation = self.finish(max_lag);
+        let mut integral = 0.0;
+        for window in autocorrelation.windows(2) {
+            integral += 0.5 * (window[0] + window[1]) * interval;
+        }
+        return integral / (3.0 * volume * K_BOLTZMANN * temperature * temperature);
+    }
+}
+
+/// Check that the `system` only contains interactions supported by the heat
+/// flux computation
+fn check_interactions(system: &System) {
+    assert!(
+        system.coulomb_potential().is_none() &&
+        system.global_potentials().is_empty() &&
+        system.molecules().all(|molecule| molecule.bonds().is_empty()),
+        "the heat flux is only implemented for atomic systems with pair interactions"
+    );
+}
+
+/// Call `function` with `(i, j, r_ij, energy, force)` for all the pairs of
+/// interacting particles in the `system`, where `force` is the force acting
+/// on `i` due to `j`.
+fn for_each_pair<F>(system: &System, mut function: F)
+where
+    F: FnMut(usize, usize, Vector3D, f64, Vector3D),
+{
+    for i in 0..system.size() {
+        for j in (i + 1)..system.size() {
+            if let Some(potential) = system.pair_potential(i, j) {
+                let info = potential.restriction().information_between(system, i, j);
+                if info.excluded {
+                    continue;
+                }
+                let distance = system.nearest_image(i, j);
+                let r = distance.norm();
+                let energy = info.scaling * potential.energy(r);
+                let force = info.scaling * potential.force(r) * distance.normalized();
+                function(i, j, distance, energy, force);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lumol_core::{Molecule, Particle, UnitCell};
+    use lumol_core::energy::{LennardJones, PairInteraction};
+    use lumol_core::units;
+    use crate::md::{Integrator, VelocityVerlet};
+    use approx::assert_relative_eq;
+
+    /// A small cluster of argon atoms, in an infinite cell
+    fn cluster() -> System {
+        let mut system = System::with_cell(UnitCell::infinite());
+        let positions = [
+            [0.0, 0.0, 0.0], [3.8, 0.1, 0.0], [0.2, 3.7, -0.1],
+            [-0.1, 0.3, 3.9], [3.6, 3.9, 0.2], [2.1, 1.8, 2.5],
+        ];
+        let velocities = [
+            [0.002, -0.001, 0.0], [-0.001, 0.003, 0.001], [0.0, -0.002, 0.002],
+            [0.001, 0.001, -0.003], [-0.002, 0.0, 0.001], [0.0, -0.001, -0.001],
+        ];
+        for (position, velocity) in positions.iter().zip(&velocities) {
+            let mut particle = Particle::with_position("Ar", (*position).into());
+            particle.velocity = (*velocity).into();
+            system.add_molecule(Molecule::new(particle));
+        }
+
+        let lj = Box::new(LennardJones {
+            sigma: units::from(3.4, "A").unwrap(),
+            epsilon: units::from(1.0, "kJ/mol").unwrap(),
+        });
+        system.set_pair_potential(("Ar", "Ar"), PairInteraction::new(lj, 10.0));
+        return system;
+    }
+
+    /// Get the sum of `r_i e_i` for all atoms in the `system`
+    fn energy_moment(system: &System) -> Vector3D {
+        let energies = HeatFlux::energies(system);
+        let mut moment = Vector3D::zero();
+        for (&energy, position) in energies.iter().zip(system.particles().position) {
+            moment += energy * position;
+        }
+        return moment;
+    }
+
+    #[test]
+    fn energies() {
+        let system = cluster();
+        let energies = HeatFlux::energies(&system);
+        assert_eq!(energies.len(), 6);
+        assert_relative_eq!(energies.iter().sum::<f64>(), system.total_energy(), max_relative = 1e-12);
+    }
+
+    #[test]
+    fn energy_conservation() {
+        let mut system = cluster();
+        let flux = HeatFlux::compute(&system);
+        assert!(flux[0].is_finite() && flux[1].is_finite() && flux[2].is_finite());
+
+        // the heat flux is the time derivative of sum(r_i e_i), which we
+        // compute with centered finite differences
+        let timestep = 1e-3;
+        let mut integrator = VelocityVerlet::new(timestep);
+        integrator.setup(&system);
+        let before = energy_moment(&system);
+        integrator.integrate(&mut system);
+        let middle = HeatFlux::compute(&system);
+        integrator.integrate(&mut system);
+        let after = energy_moment(&system);
+
+        let derivative = (after - before) / (2.0 * timestep);
+        assert_relative_eq!(derivative[0], middle[0], max_relative = 1e-4, epsilon = 1e-12);
+        assert_relative_eq!(derivative[1], middle[1], max_relative = 1e-4, epsilon = 1e-12);
+        assert_relative_eq!(derivative[2], middle[2], max_relative = 1e-4, epsilon = 1e-12);
+    }
+
+    #[test]
+    fn autocorrelation() {
+        let mut flux = HeatFlux::new();
+        flux.fluxes = vec![
+            Vector3D::new(1.0, 0.0, 0.0),
+            Vector3D::new(0.0, 2.0, 0.0),
+            Vector3D::new(3.0, 0.0, 0.0),
+        ];
+        assert_eq!(flux.count(), 3);
+
+        let autocorrelation = flux.finish(3);
+        assert_relative_eq!(autocorrelation[0], 14.0 / 3.0);
+        assert_relative_eq!(autocorrelation[1], 0.0);
+        assert_relative_eq!(autocorrelation[2], 3.0);
+        assert_eq!(autocorrelation[3], 0.0);
+
+        let expected = (0.5 * (14.0 / 3.0) + 0.5 * 3.0) * 2.0;
+        let conductivity = flux.thermal_conductivity(2, 2.0, 1000.0, 300.0);
+        assert_relative_eq!(conductivity, expected / (3.0 * 1000.0 * K_BOLTZMANN * 300.0 * 300.0));
+    }
+
+    #[test]
+    #[should_panic(expected = "the heat flux is only implemented for atomic systems with pair interactions")]
+    fn bonds() {
+        let mut system = cluster();
+        let mut molecule = Molecule::new(Particle::new("H"));
+        molecule.add_particle_bonded_to(0, Particle::with_position("H", [0.0, 0.0, 0.74].into()));
+        system.add_molecule(molecule);
+        let _ = HeatFlux::compute(&system);
+    }
+}
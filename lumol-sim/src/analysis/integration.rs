@@ -0,0 +1,113 @@
+// Lumol, an extensible molecular simulation engine
+// Copyright (C) Lumol's contributors — BSD license
+
+use lumol_core::System;
+
+/// Average of the derivative of the potential energy with respect to the
+/// coupling parameter `lambda`, for thermodynamic integration.
+///
+/// The free energy difference between the uncoupled (`lambda = 0`) and the
+/// coupled (`lambda = 1`) states is the integral over `lambda` of
+/// `<dU/dlambda>`. This observable accumulates `dU/dlambda` over the
+/// configurations of a simulation running at a fixed `lambda`, giving one of
+/// the points to integrate.
+///
+/// # Examples
+///
+/// ```
+/// # use lumol_core::{Molecule, Particle, System, UnitCell, Vector3D};
+/// # use lumol_core::energy::{PairInteraction, Potential, LennardJones, ScaledComputation};
+/// # use lumol_sim::analysis::ThermodynamicIntegration;
+/// let mut system = System::with_cell(UnitCell::cubic(10.0));
+/// system.add_molecule(Molecule::new(Particle::with_position("Ar", Vector3D::new(0.0, 0.0, 0.0))));
+/// system.add_molecule(Molecule::new(Particle::with_position("Ar", Vector3D::new(2.0, 0.0, 0.0))));
+///
+/// let lj = LennardJones { sigma: 2.0, epsilon: 1.0 };
+/// let scaled = ScaledComputation::new(Box::new(lj), 0.5);
+/// system.set_pair_potential(("Ar", "Ar"), PairInteraction::new(Box::new(scaled), 4.0));
+///
+/// let mut integration = ThermodynamicIntegration::new();
+/// integration.accumulate(&system);
+/// assert_eq!(integration.finish(), lj.energy(2.0));
+/// ```
+#[derive(Clone, Default)]
+pub struct ThermodynamicIntegration {
+    /// Accumulated values of dU/dlambda
+    sum: f64,
+    /// Accumulated values of (dU/dlambda)^2
+    sum_squared: f64,
+    /// Number of accumulated configurations
+    count: u64,
+}
+
+impl ThermodynamicIntegration {
+    /// Create a new empty `ThermodynamicIntegration` observable
+    pub fn new() -> ThermodynamicIntegration {
+        ThermodynamicIntegration::default()
+    }
+
+    /// Add the value of `dU/dlambda` for the `system` to the average
+    pub fn accumulate(&mut self, system: &System) {
+        let du_dlambda = system.du_dlambda();
+        self.sum += du_dlambda;
+        self.sum_squared += du_dlambda * du_dlambda;
+        self.count += 1;
+    }
+
+    /// Get the number of accumulated configurations
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Get the standard deviation of `dU/dlambda` over the accumulated
+    /// configurations, or 0 if no configuration was accumulated.
+    pub fn deviation(&self) -> f64 {
+        if self.count == 0 {
+            return 0.0;
+        }
+        let count = self.count as f64;
+        let average = self.sum / count;
+        return f64::sqrt(f64::max(self.sum_squared / count - average * average, 0.0));
+    }
+
+    /// Get the average value of `dU/dlambda`, or 0 if no configuration was
+    /// accumulated.
+    pub fn finish(&self) -> f64 {
+        if self.count == 0 {
+            return 0.0;
+        }
+        return self.sum / self.count as f64;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lumol_core::{Molecule, Particle, UnitCell, Vector3D};
+    use lumol_core::energy::{LennardJones, PairInteraction, Potential, ScaledComputation};
+
+    #[test]
+    fn average() {
+        let lj = LennardJones { sigma: 2.0, epsilon: 1.0 };
+        let mut integration = ThermodynamicIntegration::new();
+        assert_eq!(integration.finish(), 0.0);
+        assert_eq!(integration.deviation(), 0.0);
+
+        for &distance in &[2.0, 2.5, 3.0] {
+            let mut system = System::with_cell(UnitCell::cubic(10.0));
+            system.add_molecule(Molecule::new(Particle::with_position("Ar", Vector3D::new(0.0, 0.0, 0.0))));
+            system.add_molecule(Molecule::new(Particle::with_position("Ar", Vector3D::new(distance, 0.0, 0.0))));
+            let scaled = ScaledComputation::new(Box::new(lj), 0.2);
+            system.set_pair_potential(("Ar", "Ar"), PairInteraction::new(Box::new(scaled), 4.0));
+            integration.accumulate(&system);
+        }
+
+        assert_eq!(integration.count(), 3);
+        let energies = [lj.energy(2.0), lj.energy(2.5), lj.energy(3.0)];
+        let average = energies.iter().sum::<f64>() / 3.0;
+        assert!(f64::abs(integration.finish() - average) < 1e-12);
+
+        let variance = energies.iter().map(|e| (e - average) * (e - average)).sum::<f64>() / 3.0;
+        assert!(f64::abs(integration.deviation() - f64::sqrt(variance)) < 1e-12);
+    }
+}
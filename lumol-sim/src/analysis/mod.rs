@@ -0,0 +1,30 @@
+// Lumol, an extensible molecular simulation engine
+// Copyright (C) Lumol's contributors — BSD license
+
+//! Structural analysis of systems, accumulated over the configurations of a
+//! simulation.
+//!
+//! Observables in this module are created empty, then updated with the
+//! `accumulate` function for each configuration to analyze. The averaged
+//! results are returned by the `finish` function.
+
+mod density;
+pub use self::density::DensityProfile;
+
+mod temperature;
+pub use self::temperature::TemperatureProfile;
+
+mod structure_factor;
+pub use self::structure_factor::StructureFactor;
+
+mod surface_tension;
+pub use self::surface_tension::SurfaceTension;
+
+mod heat_flux;
+pub use self::heat_flux::HeatFlux;
+
+mod integration;
+pub use self::integration::ThermodynamicIntegration;
+
+mod average;
+pub use self::average::Average;
@@ -0,0 +1,208 @@
+// Lumol, an extensible molecular simulation engine
+// Copyright (C) Lumol's contributors — BSD license
+use std::f64::consts::PI;
+
+use lumol_core::{System, UnitCell, Vector3D};
+
+/// Static structure factor `S(k)`, directly comparable to scattering
+/// experiments.
+///
+/// The structure factor is computed for all the reciprocal vectors of the
+/// unit cell with a norm smaller than a maximal value `k_max`, as
+///
+/// ```text
+/// S(k) = < |sum_j exp(i k·r_j)|^2 > / N
+/// ```
+///
+/// where the sum runs over all the `N` particles. The values are then
+/// averaged over vectors with similar norms, using bins of equal width
+/// between 0 and `k_max`.
+///
+/// # Examples
+///
+/// ```
+/// # use lumol_core::{Molecule, Particle, System, UnitCell, Vector3D};
+/// # use lumol_sim::analysis::StructureFactor;
+/// let mut system = System::with_cell(UnitCell::cubic(10.0));
+/// system.add_molecule(Molecule::new(Particle::with_position("Ar", Vector3D::new(0.0, 0.0, 0.0))));
+///
+/// let mut structure_factor = StructureFactor::new(2.0, 10);
+/// structure_factor.accumulate(&system);
+///
+/// // With a single particle, S(k) is 1 for all k
+/// for (k, s) in structure_factor.finish() {
+///     assert!(k > 0.0 && k <= 2.0);
+///     assert!(f64::abs(s - 1.0) < 1e-12);
+/// }
+/// ```
+#[derive(Clone)]
+pub struct StructureFactor {
+    /// Maximal norm of the k-vectors
+    k_max: f64,
+    /// Accumulated values of S(k) in each bin
+    values: Vec<f64>,
+    /// Accumulated norm of k-vectors in each bin
+    norms: Vec<f64>,
+    /// Number of k-vectors accumulated in each bin
+    counts: Vec<u64>,
+}
+
+impl StructureFactor {
+    /// Create a new `StructureFactor` using all the reciprocal vectors with
+    /// a norm smaller than `k_max`, and `nbins` bins for the norms.
+    pub fn new(k_max: f64, nbins: usize) -> StructureFactor {
+        assert!(k_max > 0.0, "The maximal k must be positive in structure factor");
+        assert!(nbins > 0, "The number of bins must be positive in structure factor");
+        StructureFactor {
+            k_max: k_max,
+            values: vec![0.0; nbins],
+            norms: vec![0.0; nbins],
+            counts: vec![0; nbins],
+        }
+    }
+
+    /// Add the configuration of the `system` to the structure factor
+    pub fn accumulate(&mut self, system: &System) {
+        assert!(!system.cell.is_infinite(), "Can not compute structure factor with an infinite cell");
+        if system.size() == 0 {
+            return;
+        }
+
+        let natoms = system.size() as f64;
+        let nbins = self.values.len();
+        let positions = system.particles().position;
+        for k in self.k_vectors(&system.cell) {
+            let norm = k.norm();
+            let bin = usize::min((norm / self.k_max * nbins as f64) as usize, nbins - 1);
+
+            let (mut real, mut imaginary) = (0.0, 0.0);
+            for position in positions {
+                let phase = k * position;
+                real += f64::cos(phase);
+                imaginary += f64::sin(phase);
+            }
+
+            self.values[bin] += (real * real + imaginary * imaginary) / natoms;
+            self.norms[bin] += norm;
+            self.counts[bin] += 1;
+        }
+    }
+
+    /// Get the averaged structure factor, as a list of `(k, S(k))`. `k` is
+    /// the average norm of the reciprocal vectors in each bin, and bins
+    /// without any reciprocal vector are not included.
+    pub fn finish(&self) -> Vec<(f64, f64)> {
+        self.counts.iter().zip(&self.values).zip(&self.norms).filter(|((&count, _), _)| count != 0).map(
+            |((&count, value), norm)| (norm / count as f64, value / count as f64)
+        ).collect()
+    }
+
+    /// Get all the non-zero reciprocal vectors of the `cell` with a norm
+    /// smaller than `k_max`.
+    fn k_vectors(&self, cell: &UnitCell) -> Vec<Vector3D> {
+        // For a reciprocal vector k = sum n_i b_i, we have k·a_i = 2π n_i,
+        // so |n_i| <= |k| |a_i| / 2π.
+        let lengths = cell.lengths();
+        let max = |i: usize| (self.k_max * lengths[i] / (2.0 * PI)) as i64;
+        let (max_x, max_y, max_z) = (max(0), max(1), max(2));
+
+        let mut vectors = Vec::new();
+        for nx in -max_x..=max_x {
+            for ny in -max_y..=max_y {
+                for nz in -max_z..=max_z {
+                    if nx == 0 && ny == 0 && nz == 0 {
+                        continue;
+                    }
+                    let k = cell.k_vector([nx as f64, ny as f64, nz as f64]);
+                    if k.norm() <= self.k_max {
+                        vectors.push(k);
+                    }
+                }
+            }
+        }
+        return vectors;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lumol_core::{Molecule, Particle};
+
+    use rand::{Rng, SeedableRng};
+    use rand_xorshift::XorShiftRng;
+
+    #[test]
+    fn k_vectors() {
+        let structure_factor = StructureFactor::new(1.0, 10);
+        let vectors = structure_factor.k_vectors(&UnitCell::cubic(2.0 * PI));
+        // all integer vectors inside the unit sphere
+        assert_eq!(vectors.len(), 6);
+        for k in vectors {
+            assert!(f64::abs(k.norm() - 1.0) < 1e-12);
+        }
+
+        let structure_factor = StructureFactor::new(f64::sqrt(2.0) + 1e-6, 10);
+        let vectors = structure_factor.k_vectors(&UnitCell::cubic(2.0 * PI));
+        assert_eq!(vectors.len(), 18);
+
+        let vectors = structure_factor.k_vectors(&UnitCell::triclinic(10.0, 11.0, 12.0, 80.0, 100.0, 110.0));
+        let all = StructureFactor::new(100.0, 10).k_vectors(&UnitCell::triclinic(10.0, 11.0, 12.0, 80.0, 100.0, 110.0));
+        let expected = all.iter().filter(|k| k.norm() <= f64::sqrt(2.0) + 1e-6).count();
+        assert_eq!(vectors.len(), expected);
+    }
+
+    #[test]
+    fn ideal_gas() {
+        let mut rng = XorShiftRng::seed_from_u64(129);
+        let mut structure_factor = StructureFactor::new(3.0, 6);
+        for _ in 0..20 {
+            let mut system = System::with_cell(UnitCell::cubic(15.0));
+            for _ in 0..200 {
+                let position = Vector3D::new(
+                    rng.gen_range(0.0, 15.0),
+                    rng.gen_range(0.0, 15.0),
+                    rng.gen_range(0.0, 15.0),
+                );
+                system.add_molecule(Molecule::new(Particle::with_position("Ar", position)));
+            }
+            structure_factor.accumulate(&system);
+        }
+
+        let values = structure_factor.finish();
+        assert_eq!(values.len(), 6);
+        for (i, &(k, s)) in values.iter().enumerate() {
+            assert!(k > 0.5 * i as f64 && k <= 0.5 * (i + 1) as f64);
+            assert!(f64::abs(s - 1.0) < 0.2, "S({}) = {}", k, s);
+        }
+    }
+
+    #[test]
+    fn crystal() {
+        // Simple cubic crystal: S(k) is N at the Bragg peaks, and 0 for the
+        // other reciprocal vectors of the simulation cell
+        let mut system = System::with_cell(UnitCell::cubic(8.0));
+        for i in 0..4 {
+            for j in 0..4 {
+                for k in 0..4 {
+                    let position = Vector3D::new(2.0 * i as f64, 2.0 * j as f64, 2.0 * k as f64);
+                    system.add_molecule(Molecule::new(Particle::with_position("Ar", position)));
+                }
+            }
+        }
+
+        // The first Bragg peak is at 2π / 2 = π
+        let mut structure_factor = StructureFactor::new(3.0, 3);
+        structure_factor.accumulate(&system);
+        for (_, s) in structure_factor.finish() {
+            assert!(s < 1e-10);
+        }
+
+        let mut structure_factor = StructureFactor::new(3.2, 1);
+        structure_factor.accumulate(&system);
+        let values = structure_factor.finish();
+        // Only the 6 Bragg vectors contribute to this bin, with S = N = 64
+        let nvectors = structure_factor.k_vectors(&system.cell).len() as f64;
+        assert!(f64::abs(values[0].1 - 6.0 * 64.0 / nvectors) < 1e-10);
+    }
+}
@@ -0,0 +1,126 @@
+// Lumol, an extensible molecular simulation engine
+// Copyright (C) Lumol's contributors — BSD license
+
+use lumol_core::System;
+
+use super::Average;
+
+/// Surface tension of a system containing planar interfaces, computed from
+/// the anisotropy of the stress tensor.
+///
+/// For a slab geometry with two interfaces normal to the chosen axis, the
+/// surface tension is given by
+///
+/// $$ \gamma = \frac{L_n}{2} \left(\sigma_{nn} - \frac{\sigma_{t_1 t_1} +
+///    \sigma_{t_2 t_2}}{2}\right) $$
+///
+/// where $L_n$ is the length of the unit cell along the normal axis $n$,
+/// $\sigma_{nn}$ the normal component of the stress tensor and $\sigma_{t_1
+/// t_1}$, $\sigma_{t_2 t_2}$ the tangential components. The unit cell should
+/// be orthorhombic. The surface tension is averaged over all the accumulated
+/// configurations, and is zero on average for an isotropic system.
+///
+/// # Examples
+///
+/// ```
+/// # use lumol_core::{Molecule, Particle, System, UnitCell};
+/// # use lumol_sim::analysis::SurfaceTension;
+/// let mut system = System::with_cell(UnitCell::cubic(10.0));
+/// system.add_molecule(Molecule::new(Particle::new("Ar")));
+///
+/// // interfaces normal to the z axis
+/// let mut tension = SurfaceTension::new(2);
+/// tension.accumulate(&system);
+///
+/// assert_eq!(tension.finish().count(), 1);
+/// assert_eq!(tension.finish().mean(), 0.0);
+/// ```
+#[derive(Clone, Debug)]
+pub struct SurfaceTension {
+    /// Axis normal to the interfaces
+    axis: usize,
+    /// Accumulated surface tension
+    average: Average,
+}
+
+impl SurfaceTension {
+    /// Create a new `SurfaceTension` for interfaces normal to the given `axis`
+    /// (0 for x, 1 for y and 2 for z).
+    pub fn new(axis: usize) -> SurfaceTension {
+        assert!(axis < 3, "The axis must be 0, 1 or 2 in surface tension, got {}", axis);
+        SurfaceTension {
+            axis: axis,
+            average: Average::new(10),
+        }
+    }
+
+    /// Compute the instantaneous surface tension of the `system`
+    pub fn compute(&self, system: &System) -> f64 {
+        assert!(!system.cell.is_infinite(), "Can not compute surface tension with an infinite cell");
+
+        let stress = system.stress();
+        let (first, second) = ((self.axis + 1) % 3, (self.axis + 2) % 3);
+        let normal = stress[self.axis][self.axis];
+        let tangential = 0.5 * (stress[first][first] + stress[second][second]);
+        let length = system.cell.lengths()[self.axis];
+        return 0.5 * length * (normal - tangential);
+    }
+
+    /// Add the configuration of the `system` to the average surface tension
+    pub fn accumulate(&mut self, system: &System) {
+        let tension = self.compute(system);
+        self.average.accumulate(tension);
+    }
+
+    /// Get the average surface tension, with an error estimated by block
+    /// averaging over 10 blocks.
+    pub fn finish(&self) -> &Average {
+        &self.average
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lumol_core::{Molecule, Particle, UnitCell, Vector3D};
+    use approx::assert_relative_eq;
+
+    fn moving_particle(velocity: Vector3D) -> System {
+        let mut system = System::with_cell(UnitCell::ortho(10.0, 12.0, 20.0));
+        let mut particle = Particle::new("Ar");
+        particle.velocity = velocity;
+        system.add_molecule(Molecule::new(particle));
+        return system;
+    }
+
+    #[test]
+    fn anisotropic() {
+        // Only the kinetic part of the stress contributes here
+        let system = moving_particle(Vector3D::new(0.0, 0.0, 0.1));
+        let mass = system.particles().mass[0];
+        let volume = 10.0 * 12.0 * 20.0;
+        let stress_zz = mass * 0.01 / volume;
+
+        let tension = SurfaceTension::new(2);
+        assert_relative_eq!(tension.compute(&system), 0.5 * 20.0 * stress_zz, max_relative = 1e-12);
+
+        let tension = SurfaceTension::new(0);
+        assert_relative_eq!(tension.compute(&system), -0.25 * 10.0 * stress_zz, max_relative = 1e-12);
+    }
+
+    #[test]
+    fn isotropic() {
+        let system = moving_particle(Vector3D::new(0.1, 0.1, 0.1));
+        let mut tension = SurfaceTension::new(1);
+        tension.accumulate(&system);
+        tension.accumulate(&system);
+        assert_eq!(tension.finish().count(), 2);
+        assert_relative_eq!(tension.finish().mean(), 0.0, epsilon = 1e-15);
+    }
+
+    #[test]
+    #[should_panic(expected = "The axis must be 0, 1 or 2 in surface tension, got 3")]
+    fn bad_axis() {
+        let _ = SurfaceTension::new(3);
+    }
+}
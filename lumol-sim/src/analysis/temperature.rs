@@ -0,0 +1,191 @@
+// Lumol, an extensible molecular simulation engine
+// Copyright (C) Lumol's contributors — BSD license
+
+use lumol_core::System;
+use lumol_core::consts::K_BOLTZMANN;
+
+/// Kinetic temperature profile along one of the unit cell axis, for
+/// non-equilibrium simulations with a temperature gradient.
+///
+/// The unit cell is divided in slabs of equal volume along the chosen axis.
+/// The local temperature in each slab is computed from the kinetic energy of
+/// the particles in this slab and their number of degrees of freedom (three
+/// for each particle), accumulated over all the configurations. Massless
+/// particles, such as virtual sites, are not included.
+///
+/// # Examples
+///
+/// ```
+/// # use lumol_core::{Molecule, Particle, System, UnitCell, Vector3D};
+/// # use lumol_sim::analysis::TemperatureProfile;
+/// let mut system = System::with_cell(UnitCell::cubic(10.0));
+/// let mut particle = Particle::with_position("Ar", Vector3D::new(1.0, 0.0, 0.0));
+/// particle.velocity = Vector3D::new(0.01, 0.0, 0.0);
+/// system.add_molecule(Molecule::new(particle));
+///
+/// // profile along x, using 5 bins
+/// let mut profile = TemperatureProfile::new(0, 5);
+/// profile.accumulate(&system);
+///
+/// let bins = profile.finish();
+/// assert_eq!(bins.len(), 5);
+/// assert_eq!(bins[0].0, 1.0);
+/// assert!(bins[0].1 > 0.0);
+/// assert_eq!(bins[1], (3.0, 0.0));
+/// ```
+#[derive(Clone)]
+pub struct TemperatureProfile {
+    /// Axis along which to compute the profile
+    axis: usize,
+    /// Accumulated kinetic energy in each bin
+    kinetic: Vec<f64>,
+    /// Accumulated number of degrees of freedom in each bin
+    dof: Vec<usize>,
+    /// Accumulated length of the cell along the axis
+    length: f64,
+    /// Number of accumulated configurations
+    count: u64,
+}
+
+impl TemperatureProfile {
+    /// Create a new `TemperatureProfile` along the given `axis` (0 for the
+    /// first cell vector, 1 for the second and 2 for the third), using
+    /// `nbins` slabs.
+    pub fn new(axis: usize, nbins: usize) -> TemperatureProfile {
+        assert!(axis < 3, "The axis must be 0, 1 or 2 in temperature profile, got {}", axis);
+        assert!(nbins > 0, "The number of bins must be positive in temperature profile");
+        TemperatureProfile {
+            axis: axis,
+            kinetic: vec![0.0; nbins],
+            dof: vec![0; nbins],
+            length: 0.0,
+            count: 0,
+        }
+    }
+
+    /// Add the configuration of the `system` to the profile
+    pub fn accumulate(&mut self, system: &System) {
+        assert!(!system.cell.is_infinite(), "Can not compute temperature profile with an infinite cell");
+
+        let nbins = self.kinetic.len();
+        let particles = system.particles();
+        for ((&mass, position), velocity) in particles.mass.iter().zip(particles.position).zip(particles.velocity) {
+            if mass <= 0.0 {
+                continue;
+            }
+
+            let fractional = system.cell.fractional(position)[self.axis];
+            let fractional = fractional - f64::floor(fractional);
+            let bin = usize::min((fractional * nbins as f64) as usize, nbins - 1);
+            self.kinetic[bin] += 0.5 * mass * velocity.norm2();
+            self.dof[bin] += 3;
+        }
+
+        self.length += system.cell.lengths()[self.axis];
+        self.count += 1;
+    }
+
+    /// Get the averaged profile, as a list of `(position, temperature)` for
+    /// all the bins. The position is the center of the bin along the axis,
+    /// and the temperature is 0 for bins which never contained any particle.
+    pub fn finish(&self) -> Vec<(f64, f64)> {
+        if self.count == 0 {
+            return Vec::new();
+        }
+
+        let width = self.length / self.count as f64 / self.kinetic.len() as f64;
+        return self.kinetic.iter().zip(&self.dof).enumerate().map(|(i, (&kinetic, &dof))| {
+            let temperature = if dof == 0 {
+                0.0
+            } else {
+                2.0 * kinetic / (dof as f64 * K_BOLTZMANN)
+            };
+            ((i as f64 + 0.5) * width, temperature)
+        }).collect();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lumol_core::{Molecule, Particle, UnitCell, Vector3D};
+    use approx::assert_relative_eq;
+
+    use rand::{Rng, SeedableRng};
+    use rand_xorshift::XorShiftRng;
+    use rand_distr::{Distribution, Normal};
+
+    /// Get the standard deviation of the velocity of argon atoms at the given
+    /// temperature
+    fn thermal_velocity(temperature: f64) -> f64 {
+        let mass = Particle::new("Ar").mass;
+        return f64::sqrt(K_BOLTZMANN * temperature / mass);
+    }
+
+    #[test]
+    fn gradient() {
+        let mut rng = XorShiftRng::seed_from_u64(42);
+        let cell = UnitCell::ortho(40.0, 10.0, 10.0);
+        let mut profile = TemperatureProfile::new(0, 8);
+        for _ in 0..10 {
+            let mut system = System::with_cell(cell);
+            for _ in 0..2000 {
+                let x = rng.gen_range(0.0, 40.0);
+                // hot region at 400 K for x < 20, cold region at 100 K for
+                // x > 20
+                let temperature = if x < 20.0 { 400.0 } else { 100.0 };
+                let normal = Normal::new(0.0, thermal_velocity(temperature)).unwrap();
+
+                let position = Vector3D::new(x, rng.gen_range(0.0, 10.0), rng.gen_range(0.0, 10.0));
+                let mut particle = Particle::with_position("Ar", position);
+                particle.velocity = Vector3D::new(
+                    normal.sample(&mut rng), normal.sample(&mut rng), normal.sample(&mut rng)
+                );
+                system.add_molecule(Molecule::new(particle));
+            }
+            profile.accumulate(&system);
+        }
+
+        let bins = profile.finish();
+        assert_eq!(bins.len(), 8);
+        for (i, &(x, temperature)) in bins.iter().enumerate() {
+            assert_eq!(x, 2.5 + 5.0 * i as f64);
+            let expected = if x < 20.0 { 400.0 } else { 100.0 };
+            assert!(
+                f64::abs(temperature - expected) / expected < 0.05,
+                "temperature is {} instead of {}", temperature, expected
+            );
+        }
+        // the profile goes from hot to cold
+        assert!(bins[0].1 > 3.0 * bins[7].1);
+    }
+
+    #[test]
+    fn exact() {
+        let mut system = System::with_cell(UnitCell::cubic(10.0));
+        let mut particle = Particle::with_position("Ar", Vector3D::new(1.0, 1.0, 2.0));
+        particle.velocity = Vector3D::new(0.01, 0.0, 0.0);
+        system.add_molecule(Molecule::new(particle.clone()));
+        particle.position = Vector3D::new(1.0, 1.0, 12.0);
+        particle.velocity = Vector3D::new(0.0, 0.02, 0.0);
+        system.add_molecule(Molecule::new(particle));
+        // massless particles are ignored
+        let mut site = Particle::with_position("X", Vector3D::new(1.0, 1.0, 7.0));
+        site.mass = 0.0;
+        site.velocity = Vector3D::new(0.1, 0.0, 0.0);
+        system.add_molecule(Molecule::new(site));
+
+        let mut profile = TemperatureProfile::new(2, 2);
+        profile.accumulate(&system);
+        let mass = system.particles().mass[0];
+        let expected = 2.0 * 0.5 * mass * (0.01 * 0.01 + 0.02 * 0.02) / (6.0 * K_BOLTZMANN);
+
+        let bins = profile.finish();
+        assert_eq!(bins[0].0, 2.5);
+        assert_relative_eq!(bins[0].1, expected, max_relative = 1e-12);
+        assert_eq!(bins[1], (7.5, 0.0));
+
+        let profile = TemperatureProfile::new(2, 2);
+        assert!(profile.finish().is_empty());
+    }
+}
@@ -30,6 +30,7 @@ mod propagator;
 pub use self::propagator::Propagator;
 pub use self::propagator::TemperatureStrategy;
 
+pub mod analysis;
 pub mod output;
 pub mod md;
 pub mod mc;
@@ -39,7 +40,13 @@ mod simulations;
 pub use self::mc::MonteCarlo;
 pub use self::md::MolecularDynamics;
 pub use self::min::Minimization;
-pub use self::simulations::Simulation;
+pub use self::simulations::{Simulation, Control, DivergenceError, Performance};
+
+mod replica_exchange;
+pub use self::replica_exchange::ReplicaExchange;
 
 mod velocities;
 pub use self::velocities::{InitVelocities, BoltzmannVelocities, UniformVelocities};
+
+mod packing;
+pub use self::packing::{pack_molecules, PackingError};
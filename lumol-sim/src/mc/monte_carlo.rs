@@ -12,6 +12,7 @@ use lumol_core::consts::K_BOLTZMANN;
 use lumol_core::{DegreesOfFreedom, EnergyCache, System};
 
 use crate::propagator::{Propagator, TemperatureStrategy};
+use crate::velocities::seeded_rng;
 use super::{MCDegreeOfFreedom, MCMove};
 
 /// This struct keeps a move and some statistics on the move (number of times
@@ -278,6 +279,10 @@ impl Propagator for MonteCarlo {
         }
     }
 
+    fn seed(&mut self, seed: u64) {
+        self.rng = Box::new(seeded_rng(seed));
+    }
+
     fn setup(&mut self, system: &System) {
         self.cache.init(system);
         for mc_move in &mut self.moves {
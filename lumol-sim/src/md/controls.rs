@@ -4,9 +4,14 @@
 //! Control algorithms operate on the system state after the integration, and
 //! can be used to adjust the simulated system in various ways.
 
+use log::info;
 use soa_derive::soa_zip;
 
-use lumol_core::System;
+use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+use lumol_core::{Configuration, System};
+use lumol_core::energy::{ExternalPotential, Metadynamics};
+use lumol_core::units;
 use lumol_core::{Matrix3, Vector3D};
 
 /// Trait for controlling some parameters in a system during a simulation.
@@ -86,10 +91,405 @@ impl Control for Rewrap {
     }
 }
 
+/// Müller-Plathe reverse non-equilibrium molecular dynamics, imposing a
+/// known heat flux through the system to compute its thermal conductivity.
+///
+/// The unit cell is divided in slabs of equal width along an axis. Every
+/// `every` steps, the atom with the highest kinetic energy in the cold slab
+/// exchanges its velocity with the atom with the lowest kinetic energy in
+/// the hot slab. This transfers kinetic energy from the cold slab to the hot
+/// slab, and creates a temperature gradient between them; while the total
+/// energy and the total momentum of the system are conserved.
+///
+/// For particles with different masses, the velocities are exchanged as in
+/// an elastic collision, reversing the relative velocity of the two
+/// particles in their center-of-mass frame. For particles with the same mass,
+/// this is the same as swapping the velocities. The exchange is skipped if it
+/// would transfer energy from the hot slab to the cold slab.
+///
+/// The heat flux through the system is then given by the total transferred
+/// energy (see [`MullerPlathe::transferred`]) divided by twice the area of
+/// the slabs and the duration of the simulation, since the energy flows back
+/// in both directions with periodic boundary conditions.
+///
+/// [`MullerPlathe::transferred`]: struct.MullerPlathe.html#method.transferred
+pub struct MullerPlathe {
+    /// Axis along which the slabs are defined
+    axis: usize,
+    /// Number of slabs
+    slabs: usize,
+    /// Index of the hot slab
+    hot: usize,
+    /// Index of the cold slab
+    cold: usize,
+    /// Number of steps between two exchanges
+    every: u64,
+    /// Number of calls to `control` since the beginning of the simulation
+    count: u64,
+    /// Total kinetic energy transferred from the cold to the hot slab
+    transferred: f64,
+}
+
+impl MullerPlathe {
+    /// Create a new `MullerPlathe` control dividing the unit cell in `slabs`
+    /// slabs along the given `axis` (0 for the first cell vector, 1 for the
+    /// second and 2 for the third), and exchanging velocities every `every`
+    /// steps. The cold slab is the first one, and the hot slab the one in the
+    /// middle of the cell.
+    ///
+    /// # Panics
+    ///
+    /// If the axis is not 0, 1 or 2, if there are less than two slabs or if
+    /// `every` is zero.
+    pub fn new(axis: usize, slabs: usize, every: u64) -> MullerPlathe {
+        assert!(axis < 3, "The axis must be 0, 1 or 2 in Müller-Plathe control, got {}", axis);
+        assert!(slabs >= 2, "Müller-Plathe control needs at least two slabs");
+        assert!(every > 0, "The exchange interval must be positive in Müller-Plathe control");
+        MullerPlathe {
+            axis: axis,
+            slabs: slabs,
+            hot: slabs / 2,
+            cold: 0,
+            every: every,
+            count: 0,
+            transferred: 0.0,
+        }
+    }
+
+    /// Use the slabs at index `hot` and `cold` as the hot and cold slabs.
+    ///
+    /// # Panics
+    ///
+    /// If the slabs are the same, or if one of them does not exist.
+    pub fn set_slabs(&mut self, hot: usize, cold: usize) {
+        assert!(hot != cold, "The hot and cold slabs must be different in Müller-Plathe control");
+        assert!(
+            hot < self.slabs && cold < self.slabs,
+            "Invalid slab index in Müller-Plathe control, there are only {} slabs", self.slabs
+        );
+        self.hot = hot;
+        self.cold = cold;
+    }
+
+    /// Get the total kinetic energy transferred from the cold slab to the
+    /// hot slab since the beginning of the simulation
+    pub fn transferred(&self) -> f64 {
+        self.transferred
+    }
+
+    /// Get the index of the slab containing the given `position`
+    fn slab(&self, system: &System, position: &Vector3D) -> usize {
+        let fractional = system.cell.fractional(position)[self.axis];
+        let fractional = fractional - f64::floor(fractional);
+        return usize::min((fractional * self.slabs as f64) as usize, self.slabs - 1);
+    }
+}
+
+impl Control for MullerPlathe {
+    fn setup(&mut self, system: &System) {
+        assert!(!system.cell.is_infinite(), "Can not use Müller-Plathe control with an infinite cell");
+        self.count = 0;
+        self.transferred = 0.0;
+    }
+
+    fn control(&mut self, system: &mut System) {
+        self.count += 1;
+        if self.count % self.every != 0 {
+            return;
+        }
+
+        // hottest particle in the cold slab, and coldest particle in the hot
+        // slab, with their kinetic energies
+        let mut hottest: Option<(usize, f64)> = None;
+        let mut coldest: Option<(usize, f64)> = None;
+        for (i, (&mass, position, velocity)) in soa_zip!(system.particles(), [mass, position, velocity]).enumerate() {
+            if mass <= 0.0 {
+                continue;
+            }
+
+            let kinetic = 0.5 * mass * velocity.norm2();
+            let slab = self.slab(system, position);
+            if slab == self.cold && hottest.map_or(true, |(_, energy)| kinetic > energy) {
+                hottest = Some((i, kinetic));
+            } else if slab == self.hot && coldest.map_or(true, |(_, energy)| kinetic < energy) {
+                coldest = Some((i, kinetic));
+            }
+        }
+
+        if let (Some((i, _)), Some((j, cold_energy))) = (hottest, coldest) {
+            let particles = system.particles_mut();
+            let (mass_i, mass_j) = (particles.mass[i], particles.mass[j]);
+            let com_velocity = (mass_i * particles.velocity[i] + mass_j * particles.velocity[j]) / (mass_i + mass_j);
+            let velocity_i = 2.0 * com_velocity - particles.velocity[i];
+            let velocity_j = 2.0 * com_velocity - particles.velocity[j];
+
+            let transferred = 0.5 * mass_j * velocity_j.norm2() - cold_energy;
+            if transferred <= 0.0 {
+                // exchanging the velocities would transfer energy in the
+                // wrong direction
+                return;
+            }
+
+            particles.velocity[i] = velocity_i;
+            particles.velocity[j] = velocity_j;
+            self.transferred += transferred;
+        }
+    }
+
+    fn finish(&mut self, _: &System) {
+        let transferred = units::to(self.transferred, "kJ/mol").expect("bad unit");
+        info!("Müller-Plathe control transferred {} kJ/mol", transferred);
+    }
+}
+
+/// Metadynamics can be used as a control, depositing a new hill at the end of
+/// every `Metadynamics::every()` steps. A clone of the same `Metadynamics`
+/// bias should also be added to the system as a global potential.
+impl Control for Metadynamics {
+    fn control(&mut self, system: &mut System) {
+        // The step counter of the system is only incremented after the
+        // propagation of the system
+        if (system.step + 1) % self.every() == 0 {
+            self.deposit(system);
+        }
+    }
+
+    fn finish(&mut self, _: &System) {
+        info!("Metadynamics deposited {} hills", self.hills().len());
+    }
+}
+
+/// State shared between a `SteeredRestraint` and the corresponding potential
+#[derive(Default)]
+struct SteeringState {
+    /// Current reference position, or `None` before the simulation setup
+    reference: Option<Vector3D>,
+    /// Recorded pulling distances and forces
+    records: Vec<(f64, f64)>,
+}
+
+/// Steered molecular dynamics, pulling a particle or the center of mass of a
+/// group of particles with an harmonic spring whose reference position moves
+/// at constant velocity.
+///
+/// The energy of the restraint is
+///
+/// $$ V = \frac 12 k \left|\vec R - \vec r_{ref}(t) \right|^2 $$
+///
+/// where $\vec R$ is the center of mass of the group, and $\vec r_{ref}(t) =
+/// \vec R(0) + v t \vec u$ the reference position, starting at the initial
+/// center of mass and moving at the pulling velocity $v$ along the direction
+/// $\vec u$. The force $-\partial V / \partial \vec R$ acting on the center of
+/// mass is distributed to the particles in the group proportionally to their
+/// mass.
+///
+/// The restraint acts on the system through an
+/// [`ExternalPotential`](../../lumol_core/energy/trait.ExternalPotential.html)
+/// returned by [`SteeredRestraint::potential`], which must be added to the
+/// system. After each step, the reference position is moved, and the pulling
+/// distance $v t$ and the component of the spring force along the pulling
+/// direction are recorded, giving the force-extension curve of the system.
+///
+/// All the clones of a `SteeredRestraint` share the same reference position
+/// and records, so that a clone can be used as a control in the simulation
+/// while the original is used to access the records afterward. The full
+/// energy of the restraint is attributed to the first particle of the group,
+/// so this restraint should only be used with molecular dynamics.
+///
+/// [`SteeredRestraint::potential`]: struct.SteeredRestraint.html#method.potential
+#[derive(Clone)]
+pub struct SteeredRestraint {
+    /// Indexes of the pulled particles
+    indices: Vec<usize>,
+    /// Spring constant
+    k: f64,
+    /// Pulling direction, normalized
+    direction: Vector3D,
+    /// Distance the reference moves at each step
+    displacement: f64,
+    /// Initial reference position
+    start: Vector3D,
+    /// Number of steps since the beginning of the simulation
+    steps: u64,
+    /// Reference position and records
+    state: Arc<RwLock<SteeringState>>,
+}
+
+impl SteeredRestraint {
+    /// Create a new `SteeredRestraint` with spring constant `k`, pulling the
+    /// center of mass of the particles at `indices` along `direction` at the
+    /// given `velocity`, for a simulation using the given `timestep`.
+    ///
+    /// # Panics
+    ///
+    /// If `indices` is empty, if `k` is negative, if `direction` is the null
+    /// vector or if the timestep is not positive.
+    pub fn new(indices: Vec<usize>, k: f64, direction: Vector3D, velocity: f64, timestep: f64) -> SteeredRestraint {
+        assert!(!indices.is_empty(), "Steered restraint needs at least one particle");
+        assert!(k >= 0.0, "The spring constant must be positive in steered restraint");
+        assert!(direction.norm() > 0.0, "The pulling direction can not be the null vector in steered restraint");
+        assert!(timestep > 0.0, "The timestep must be positive in steered restraint");
+        SteeredRestraint {
+            indices: indices,
+            k: k,
+            direction: direction.normalized(),
+            displacement: velocity * timestep,
+            start: Vector3D::zero(),
+            steps: 0,
+            state: Arc::new(RwLock::new(SteeringState::default())),
+        }
+    }
+
+    /// Get the external potential applying this restraint, to be added to
+    /// the system with `System::add_external_potential`.
+    pub fn potential(&self) -> Box<dyn ExternalPotential> {
+        Box::new(MovingRestraint {
+            indices: self.indices.clone(),
+            k: self.k,
+            state: Arc::clone(&self.state),
+        })
+    }
+
+    /// Get the current reference position, or `None` if the simulation did
+    /// not start yet.
+    pub fn reference(&self) -> Option<Vector3D> {
+        self.read().reference
+    }
+
+    /// Get the recorded pulling distances and spring forces along the
+    /// pulling direction, one for each simulation step.
+    pub fn records(&self) -> Vec<(f64, f64)> {
+        self.read().records.clone()
+    }
+
+    fn read(&self) -> RwLockReadGuard<'_, SteeringState> {
+        // The lock should never be poisonned, because any panic will unwind
+        // and finish the simulation.
+        self.state.read().expect("steered restraint lock is poisonned")
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, SteeringState> {
+        self.state.write().expect("steered restraint lock is poisonned")
+    }
+}
+
+impl Control for SteeredRestraint {
+    fn setup(&mut self, system: &System) {
+        for &i in &self.indices {
+            assert!(i < system.size(), "particle index {} is out of bounds in steered restraint", i);
+        }
+        let mass = self.indices.iter().map(|&i| system.particles().mass[i]).sum::<f64>();
+        assert!(mass > 0.0, "Can not use steered restraint with massless particles");
+
+        self.start = center_of_mass(system, &self.indices, None);
+        self.steps = 0;
+        let mut state = self.write();
+        state.reference = Some(self.start);
+        state.records.clear();
+    }
+
+    fn control(&mut self, system: &mut System) {
+        self.steps += 1;
+        let distance = self.steps as f64 * self.displacement;
+        let reference = self.start + distance * self.direction;
+
+        let mut delta = reference - center_of_mass(system, &self.indices, None);
+        system.cell.vector_image(&mut delta);
+        let force = self.k * (delta * self.direction);
+
+        let mut state = self.write();
+        state.reference = Some(reference);
+        state.records.push((distance, force));
+    }
+
+    fn finish(&mut self, _: &System) {
+        let state = self.read();
+        if let Some(&(distance, _)) = state.records.last() {
+            let max = state.records.iter().map(|record| record.1).fold(f64::NEG_INFINITY, f64::max);
+            let max = units::to(max, "kJ/mol/A").expect("bad unit");
+            info!("Steered restraint pulled by {} A, with a maximal force of {} kJ/mol/A", distance, max);
+        }
+    }
+}
+
+/// Get the center of mass of the particles at `indices` in the
+/// `configuration`, using the nearest periodic image of the first particle
+/// for all the others. If `moved` is `Some((i, position))`, the particle `i`
+/// is assumed to be at `position`.
+fn center_of_mass(configuration: &Configuration, indices: &[usize], moved: Option<(usize, &Vector3D)>) -> Vector3D {
+    let particles = configuration.particles();
+    let position = |i: usize| match moved {
+        Some((j, position)) if i == j => *position,
+        _ => particles.position[i],
+    };
+
+    let origin = position(indices[0]);
+    let mut total_mass = 0.0;
+    let mut center = Vector3D::zero();
+    for &i in indices {
+        let mass = particles.mass[i];
+        let mut delta = position(i) - origin;
+        configuration.cell.vector_image(&mut delta);
+        center += mass * delta;
+        total_mass += mass;
+    }
+    return origin + center / total_mass;
+}
+
+/// Harmonic restraint on the center of mass of a group of particles, with a
+/// reference position shared with a `SteeredRestraint`
+#[derive(Clone)]
+struct MovingRestraint {
+    /// Indexes of the restrained particles
+    indices: Vec<usize>,
+    /// Spring constant
+    k: f64,
+    /// State shared with the `SteeredRestraint`
+    state: Arc<RwLock<SteeringState>>,
+}
+
+impl MovingRestraint {
+    /// Get the displacement of the center of mass from the reference, if the
+    /// particle `i` is at `position`.
+    fn displacement(&self, configuration: &Configuration, i: usize, position: &Vector3D) -> Option<Vector3D> {
+        if !self.indices.contains(&i) {
+            return None;
+        }
+        let reference = self.state.read().expect("steered restraint lock is poisonned").reference?;
+        let mut delta = center_of_mass(configuration, &self.indices, Some((i, position))) - reference;
+        configuration.cell.vector_image(&mut delta);
+        return Some(delta);
+    }
+}
+
+impl ExternalPotential for MovingRestraint {
+    fn energy(&self, configuration: &Configuration, i: usize, position: &Vector3D) -> f64 {
+        if i != self.indices[0] {
+            return 0.0;
+        }
+        match self.displacement(configuration, i, position) {
+            Some(delta) => 0.5 * self.k * delta.norm2(),
+            None => 0.0,
+        }
+    }
+
+    fn force(&self, configuration: &Configuration, i: usize, position: &Vector3D) -> Vector3D {
+        match self.displacement(configuration, i, position) {
+            Some(delta) => {
+                let masses = configuration.particles().mass;
+                let total_mass = self.indices.iter().map(|&j| masses[j]).sum::<f64>();
+                -self.k * masses[i] / total_mass * delta
+            }
+            None => Vector3D::zero(),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use lumol_core::{Particle, Molecule, System, UnitCell};
+    use approx::{assert_relative_eq, assert_ulps_eq};
 
     #[test]
     fn remove_translation() {
@@ -129,4 +529,212 @@ mod tests {
         assert_eq!(system.particles().position[0], Vector3D::new(0.0, 0.0, 0.0));
         assert_eq!(system.particles().position[1], Vector3D::new(5.0, 0.0, 0.0));
     }
+
+    /// Get the kinetic energy of the particles in the hot (z > 10) and cold
+    /// (z < 10) slabs, and the total momentum of the system
+    fn slabs_energy(system: &System) -> (f64, f64, Vector3D) {
+        let mut hot = 0.0;
+        let mut cold = 0.0;
+        let mut momentum = Vector3D::zero();
+        for (&mass, position, velocity) in soa_zip!(system.particles(), [mass, position, velocity]) {
+            if position[2] < 10.0 {
+                cold += 0.5 * mass * velocity.norm2();
+            } else {
+                hot += 0.5 * mass * velocity.norm2();
+            }
+            momentum += mass * velocity;
+        }
+        return (hot, cold, momentum);
+    }
+
+    #[test]
+    fn muller_plathe() {
+        let mut system = System::with_cell(UnitCell::ortho(10.0, 10.0, 20.0));
+        system.add_molecule(Molecule::new(Particle::with_position("Ar", [0.0, 0.0, 2.0].into())));
+        system.add_molecule(Molecule::new(Particle::with_position("Ar", [0.0, 0.0, 7.0].into())));
+        system.add_molecule(Molecule::new(Particle::with_position("Ar", [0.0, 0.0, 12.0].into())));
+        system.add_molecule(Molecule::new(Particle::with_position("Ar", [0.0, 0.0, 17.0].into())));
+        system.particles_mut().velocity[0] = [0.01, 0.0, 0.0].into();
+        system.particles_mut().velocity[1] = [0.0, 0.03, 0.0].into();
+        system.particles_mut().velocity[2] = [0.0, 0.0, -0.02].into();
+        system.particles_mut().velocity[3] = [0.005, 0.0, 0.0].into();
+
+        let mut control = MullerPlathe::new(2, 2, 2);
+        control.setup(&system);
+        assert_eq!(control.transferred(), 0.0);
+
+        // nothing happens on the first step
+        control.control(&mut system);
+        assert_eq!(control.transferred(), 0.0);
+        assert_eq!(system.particles().velocity[1], Vector3D::new(0.0, 0.03, 0.0));
+
+        let mass = system.particles().mass[0];
+        control.control(&mut system);
+        assert!((system.particles().velocity[1] - Vector3D::new(0.005, 0.0, 0.0)).norm() < 1e-15);
+        assert!((system.particles().velocity[3] - Vector3D::new(0.0, 0.03, 0.0)).norm() < 1e-15);
+        assert_relative_eq!(control.transferred(), 0.5 * mass * (0.03 * 0.03 - 0.005 * 0.005), max_relative = 1e-12);
+    }
+
+    #[test]
+    fn muller_plathe_repeated() {
+        let mut system = System::with_cell(UnitCell::ortho(10.0, 10.0, 20.0));
+        for i in 0..100 {
+            let position = Vector3D::new(0.0, 0.0, 0.2 * i as f64 + 0.1);
+            // use different masses to check the conservation laws
+            let name = if i % 2 == 0 { "Ar" } else { "Ne" };
+            let mut particle = Particle::with_position(name, position);
+            let i = i as f64;
+            particle.velocity = Vector3D::new(f64::sin(i), f64::cos(3.0 * i), f64::sin(7.0 * i + 1.0)) * 0.01;
+            system.add_molecule(Molecule::new(particle));
+        }
+
+        let mut control = MullerPlathe::new(2, 2, 1);
+        control.set_slabs(1, 0);
+        control.setup(&system);
+
+        let (initial_hot, initial_cold, initial_momentum) = slabs_energy(&system);
+        let mut previous = initial_hot;
+        for _ in 0..20 {
+            control.control(&mut system);
+            let (hot, _, _) = slabs_energy(&system);
+            // kinetic energy always goes from the cold to the hot slab
+            assert!(hot >= previous);
+            previous = hot;
+        }
+
+        let (hot, cold, momentum) = slabs_energy(&system);
+        assert!(control.transferred() > 0.0);
+        assert_relative_eq!(hot - initial_hot, control.transferred(), max_relative = 1e-9);
+        assert_relative_eq!(initial_cold - cold, control.transferred(), max_relative = 1e-9);
+        assert!((momentum - initial_momentum).norm() < 1e-12);
+    }
+
+    #[test]
+    #[should_panic(expected = "The hot and cold slabs must be different in Müller-Plathe control")]
+    fn muller_plathe_same_slabs() {
+        let mut control = MullerPlathe::new(2, 4, 1);
+        control.set_slabs(1, 1);
+    }
+
+    #[test]
+    fn steered_reference() {
+        let mut system = System::with_cell(UnitCell::cubic(20.0));
+        system.add_molecule(Molecule::new(Particle::with_position("Ar", [1.0, 2.0, 3.0].into())));
+
+        let direction = Vector3D::new(1.0, 1.0, 0.0);
+        let mut steered = SteeredRestraint::new(vec![0], 2.0, direction, 0.01, 0.5);
+        assert_eq!(steered.reference(), None);
+        let potential = steered.potential();
+        assert_eq!(potential.force(&system, 0, &Vector3D::new(3.0, 2.0, 3.0)), Vector3D::zero());
+
+        steered.setup(&system);
+        assert_eq!(steered.reference(), Some(Vector3D::new(1.0, 2.0, 3.0)));
+        for _ in 0..100 {
+            steered.control(&mut system);
+        }
+
+        // The reference moved by 100 * 0.01 * 0.5 along the direction
+        let expected = Vector3D::new(1.0, 2.0, 3.0) + 0.5 * direction.normalized();
+        assert!((steered.reference().unwrap() - expected).norm() < 1e-12);
+
+        let records = steered.records();
+        assert_eq!(records.len(), 100);
+        for (i, &(distance, force)) in records.iter().enumerate() {
+            let distance_i = (i + 1) as f64 * 0.005;
+            assert_relative_eq!(distance, distance_i, max_relative = 1e-12);
+            // the particle did not move, so the extension is the distance
+            assert_relative_eq!(force, 2.0 * distance_i, max_relative = 1e-12);
+        }
+
+        // The potential uses the shared reference
+        let position = system.particles().position[0];
+        assert_relative_eq!(potential.energy(&system, 0, &position), 0.5 * 2.0 * 0.25, max_relative = 1e-12);
+        assert!((potential.force(&system, 0, &position) - 2.0 * (expected - position)).norm() < 1e-12);
+    }
+
+    #[test]
+    fn steered_center_of_mass() {
+        let mut system = System::with_cell(UnitCell::cubic(20.0));
+        system.add_molecule(Molecule::new(Particle::with_position("He", [0.0, 0.0, 0.0].into())));
+        system.add_molecule(Molecule::new(Particle::with_position("Ar", [19.0, 0.0, 0.0].into())));
+        system.add_molecule(Molecule::new(Particle::with_position("Ne", [5.0, 0.0, 0.0].into())));
+
+        let mut steered = SteeredRestraint::new(vec![0, 1], 3.0, Vector3D::new(0.0, 0.0, 1.0), 1.0, 1.0);
+        steered.setup(&system);
+
+        // The center of mass uses the nearest image of the particles
+        let masses = system.particles().mass.to_vec();
+        let total = masses[0] + masses[1];
+        let center = Vector3D::new(-masses[1] / total, 0.0, 0.0);
+        assert!((steered.reference().unwrap() - center).norm() < 1e-12);
+
+        steered.control(&mut system);
+        let potential = steered.potential();
+        let forces = (0..3).map(|i| {
+            potential.force(&system, i, &system.particles().position[i])
+        }).collect::<Vec<_>>();
+        // The force is distributed according to the masses
+        assert!((forces[0] - Vector3D::new(0.0, 0.0, 3.0 * masses[0] / total)).norm() < 1e-12);
+        assert!((forces[1] - Vector3D::new(0.0, 0.0, 3.0 * masses[1] / total)).norm() < 1e-12);
+        assert_eq!(forces[2], Vector3D::zero());
+        assert_relative_eq!(steered.records()[0].1, 3.0, max_relative = 1e-12);
+
+        // The energy is only counted once
+        assert_relative_eq!(potential.energy(&system, 0, &system.particles().position[0]), 1.5, max_relative = 1e-12);
+        assert_eq!(potential.energy(&system, 1, &system.particles().position[1]), 0.0);
+    }
+
+    #[test]
+    fn steered_dynamics() {
+        use crate::md::{Integrator, VelocityVerlet};
+
+        let mut system = System::with_cell(UnitCell::cubic(20.0));
+        system.add_molecule(Molecule::new(Particle::with_position("Ar", [0.0, 0.0, 0.0].into())));
+
+        let direction = Vector3D::new(0.0, 1.0, 0.0);
+        let mut steered = SteeredRestraint::new(vec![0], 1e-3, direction, 1e-3, 1.0);
+        system.add_external_potential(steered.potential());
+
+        let mut integrator = VelocityVerlet::new(1.0);
+        integrator.setup(&system);
+        steered.setup(&system);
+        for step in 0..500 {
+            integrator.integrate(&mut system);
+            steered.control(&mut system);
+
+            // The recorded force tracks the extension of the spring
+            let reference = steered.reference().unwrap();
+            let extension = (reference - system.particles().position[0]) * direction;
+            let (distance, force) = steered.records()[step];
+            assert_relative_eq!(distance, (step + 1) as f64 * 1e-3, max_relative = 1e-12);
+            assert_relative_eq!(force, 1e-3 * extension, max_relative = 1e-9);
+        }
+
+        // The particle is pulled along the direction
+        let position = system.particles().position[0];
+        assert!(position[1] > 0.0);
+        assert_eq!(position[0], 0.0);
+        assert_eq!(position[2], 0.0);
+    }
+
+    #[test]
+    fn metadynamics() {
+        use lumol_core::energy::{DistanceCV, GlobalPotential};
+
+        let mut system = System::with_cell(UnitCell::cubic(20.0));
+        system.add_molecule(Molecule::new(Particle::with_position("Ar", [0.0, 0.0, 0.0].into())));
+        system.add_molecule(Molecule::new(Particle::with_position("Ar", [3.0, 0.0, 0.0].into())));
+
+        let mut metadynamics = Metadynamics::new(Box::new(DistanceCV::new(0, 1)), 0.1, 0.2, 5);
+        system.add_global_potential(Box::new(metadynamics.clone()));
+        metadynamics.setup(&system);
+
+        for _ in 0..23 {
+            metadynamics.control(&mut system);
+            system.step += 1;
+        }
+        assert_eq!(metadynamics.hills().len(), 4);
+        assert_ulps_eq!(metadynamics.energy(&system), 0.4);
+        assert_ulps_eq!(system.potential_energy(), 0.4);
+    }
 }
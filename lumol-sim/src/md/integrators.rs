@@ -14,6 +14,46 @@ pub trait Integrator {
     /// Integrate the equations of motion. This is called at every step of the
     /// simulation.
     fn integrate(&mut self, system: &mut System);
+    /// Get the current timestep of the integrator, or `None` if this
+    /// integrator does not support changing its timestep. The default
+    /// implementation returns `None`.
+    fn timestep(&self) -> Option<f64> {
+        None
+    }
+    /// Change the timestep of the integrator to `timestep`, or return an
+    /// error message if the timestep can not be changed. The new timestep is
+    /// used for all subsequent steps. Integrators returning `Some` from
+    /// [`Integrator::timestep`](trait.Integrator.html#method.timestep) must
+    /// implement this function. The default implementation always returns an
+    /// error.
+    fn set_timestep(&mut self, _: f64) -> Result<(), String> {
+        return Err("this integrator does not support changing the timestep".into());
+    }
+}
+
+/// Get the acceleration of a particle with the given `mass` under the action
+/// of `force`. Massless particles, such as virtual sites, are not accelerated.
+fn compute_acceleration(force: Vector3D, mass: f64) -> Vector3D {
+    if mass == 0.0 {
+        Vector3D::zero()
+    } else {
+        force / mass
+    }
+}
+
+/// Get the accelerations of all the particles in the `system`, from the
+/// current forces. Integrators use this in their setup, so that running a
+/// simulation in multiple parts gives the same result as a single run.
+fn current_accelerations(system: &System) -> Vec<Vector3D> {
+    accelerations(system, &system.forces())
+}
+
+/// Get the accelerations corresponding to the `forces` acting on the
+/// particles of the `system`.
+fn accelerations(system: &System, forces: &[Vector3D]) -> Vec<Vector3D> {
+    forces.iter().zip(system.particles().mass).map(|(&force, &mass)| {
+        compute_acceleration(force, mass)
+    }).collect()
 }
 
 /// Velocity-Verlet integrator.
@@ -38,7 +78,19 @@ impl VelocityVerlet {
 
 impl Integrator for VelocityVerlet {
     fn setup(&mut self, system: &System) {
-        self.accelerations = vec![Vector3D::zero(); system.size()];
+        self.accelerations = current_accelerations(system);
+    }
+
+    fn timestep(&self) -> Option<f64> {
+        Some(self.timestep)
+    }
+
+    fn set_timestep(&mut self, timestep: f64) -> Result<(), String> {
+        if timestep <= 0.0 {
+            return Err("the timestep must be positive".into());
+        }
+        self.timestep = timestep;
+        return Ok(());
     }
 
     fn integrate(&mut self, system: &mut System) {
@@ -51,13 +103,14 @@ impl Integrator for VelocityVerlet {
             *velocity += 0.5 * dt * acceleration;
             *position += velocity * dt;
         }
+        system.update_virtual_sites();
 
         let forces = system.forces();
         // Update accelerations at t + ∆t
         for (&mass, acceleration, force) in soa_zip!(
             system.particles(), [mass], &mut self.accelerations, forces
         ) {
-            *acceleration = force / mass;
+            *acceleration = compute_acceleration(force, mass);
         }
 
         // Update velocities at t + ∆t
@@ -77,6 +130,8 @@ pub struct Verlet {
     timestep: f64,
     /// Previous positions
     prevpos: Vec<Vector3D>,
+    /// Timestep between the previous and the current positions
+    prevpos_timestep: f64,
 }
 
 impl Verlet {
@@ -85,6 +140,7 @@ impl Verlet {
         Verlet {
             timestep: timestep,
             prevpos: Vec::new(),
+            prevpos_timestep: timestep,
         }
     }
 }
@@ -100,6 +156,19 @@ impl Integrator for Verlet {
         ) {
             *prevpos = position - velocity * dt;
         }
+        self.prevpos_timestep = dt;
+    }
+
+    fn timestep(&self) -> Option<f64> {
+        Some(self.timestep)
+    }
+
+    fn set_timestep(&mut self, timestep: f64) -> Result<(), String> {
+        if timestep <= 0.0 {
+            return Err("the timestep must be positive".into());
+        }
+        self.timestep = timestep;
+        return Ok(());
     }
 
     fn integrate(&mut self, system: &mut System) {
@@ -107,18 +176,29 @@ impl Integrator for Verlet {
         let dt = self.timestep;
         let dt2 = dt * dt;
 
+        if self.prevpos_timestep != dt {
+            // The timestep changed, rescale the previous positions to get
+            // positions at t - ∆t with the new timestep
+            let scaling = dt / self.prevpos_timestep;
+            for (position, prevpos) in soa_zip!(system.particles(), [position], &mut self.prevpos) {
+                *prevpos = position - scaling * (position - *prevpos);
+            }
+            self.prevpos_timestep = dt;
+        }
+
         for (position, velocity, mass, prevpos, force) in soa_zip!(
             system.particles_mut(), [mut position, mut velocity, mass], &mut self.prevpos, forces
         ) {
             // Save positions at t
             let tmp = *position;
             // Update positions at t + ∆t
-            *position = 2.0 * (*position) - (*prevpos) + dt2 / mass * force;
+            *position = 2.0 * (*position) - (*prevpos) + dt2 * compute_acceleration(force, *mass);
             // Update velocities at t
             *velocity = ((*position) - (*prevpos)) / (2.0 * dt);
             // Update saved position
             *prevpos = tmp;
         }
+        system.update_virtual_sites();
     }
 }
 
@@ -144,7 +224,19 @@ impl LeapFrog {
 
 impl Integrator for LeapFrog {
     fn setup(&mut self, system: &System) {
-        self.accelerations = vec![Vector3D::zero(); system.size()];
+        self.accelerations = current_accelerations(system);
+    }
+
+    fn timestep(&self) -> Option<f64> {
+        Some(self.timestep)
+    }
+
+    fn set_timestep(&mut self, timestep: f64) -> Result<(), String> {
+        if timestep <= 0.0 {
+            return Err("the timestep must be positive".into());
+        }
+        self.timestep = timestep;
+        return Ok(());
     }
 
     fn integrate(&mut self, system: &mut System) {
@@ -156,18 +248,126 @@ impl Integrator for LeapFrog {
         ) {
             *position += velocity * dt + 0.5 * acceleration * dt2;
         }
+        system.update_virtual_sites();
 
         let forces = system.forces();
         for (velocity, &mass, acceleration, force) in soa_zip!(
             system.particles_mut(), [mut velocity, mass], &mut self.accelerations, &forces
         ) {
-            let new_acceleration = force / mass;
+            let new_acceleration = compute_acceleration(*force, mass);
             *velocity += 0.5 * ((*acceleration) + new_acceleration) * dt;
             *acceleration = new_acceleration;
         }
     }
 }
 
+/// Multiple timestep integrator, using the reversible reference system
+/// propagator algorithm (RESPA).
+///
+/// The forces are split in fast forces, coming from the bonded interactions
+/// (bonds, angles, dihedral angles, improper dihedral angles and cross
+/// terms); and slow forces, coming from all the other interactions. The fast
+/// forces are integrated with a velocity-Verlet algorithm using a small inner
+/// timestep, while the slow forces are only evaluated once every outer
+/// timestep. Each outer timestep contains `inner_steps` inner steps.
+///
+/// This integrator is time-reversible and symplectic (volume preserving).
+pub struct Respa {
+    /// Outer timestep for the integrator
+    timestep: f64,
+    /// Number of inner steps in each outer step
+    inner_steps: usize,
+    /// Accelerations coming from the fast forces
+    fast_accelerations: Vec<Vector3D>,
+    /// Accelerations coming from the slow forces
+    slow_accelerations: Vec<Vector3D>,
+}
+
+impl Respa {
+    /// Create a new RESPA integrator with an outer timestep of `timestep`,
+    /// and `inner_steps` steps of the fast forces in each outer step.
+    ///
+    /// # Panics
+    ///
+    /// If `inner_steps` is zero.
+    pub fn new(timestep: f64, inner_steps: usize) -> Respa {
+        assert!(inner_steps > 0, "RESPA needs at least one inner step");
+        Respa {
+            timestep: timestep,
+            inner_steps: inner_steps,
+            fast_accelerations: Vec::new(),
+            slow_accelerations: Vec::new(),
+        }
+    }
+
+    /// Get the number of inner steps in each outer step
+    pub fn inner_steps(&self) -> usize {
+        self.inner_steps
+    }
+
+    /// Get the inner timestep, used to integrate the fast forces
+    pub fn inner_timestep(&self) -> f64 {
+        self.timestep / self.inner_steps as f64
+    }
+}
+
+impl Integrator for Respa {
+    fn setup(&mut self, system: &System) {
+        self.fast_accelerations = accelerations(system, &system.bonded_forces());
+        self.slow_accelerations = accelerations(system, &system.non_bonded_forces());
+    }
+
+    fn timestep(&self) -> Option<f64> {
+        Some(self.timestep)
+    }
+
+    fn set_timestep(&mut self, timestep: f64) -> Result<(), String> {
+        if timestep <= 0.0 {
+            return Err("the timestep must be positive".into());
+        }
+        self.timestep = timestep;
+        return Ok(());
+    }
+
+    fn integrate(&mut self, system: &mut System) {
+        let dt = self.timestep;
+        let inner_dt = self.inner_timestep();
+
+        // Update velocities at t + ∆t/2 with the slow forces
+        for (velocity, acceleration) in soa_zip!(
+            system.particles_mut(), [mut velocity], &self.slow_accelerations
+        ) {
+            *velocity += 0.5 * dt * acceleration;
+        }
+
+        // Velocity-Verlet integration with the fast forces
+        for _ in 0..self.inner_steps {
+            for (position, velocity, acceleration) in soa_zip!(
+                system.particles_mut(), [mut position, mut velocity], &self.fast_accelerations
+            ) {
+                *velocity += 0.5 * inner_dt * acceleration;
+                *position += velocity * inner_dt;
+            }
+            system.update_virtual_sites();
+
+            self.fast_accelerations = accelerations(system, &system.bonded_forces());
+            for (velocity, acceleration) in soa_zip!(
+                system.particles_mut(), [mut velocity], &self.fast_accelerations
+            ) {
+                *velocity += 0.5 * inner_dt * acceleration;
+            }
+        }
+
+        // Update velocities at t + ∆t with the slow forces
+        self.slow_accelerations = accelerations(system, &system.non_bonded_forces());
+        for (velocity, acceleration) in soa_zip!(
+            system.particles_mut(), [mut velocity], &self.slow_accelerations
+        ) {
+            *velocity += 0.5 * dt * acceleration;
+        }
+    }
+}
+
 /// This is needed for the `BerendsenBarostat` implementation. The value comes
 /// from the DL_POLY source code.
 const WATER_COMPRESSIBILITY: f64 = 7372.0;
@@ -205,7 +405,19 @@ impl BerendsenBarostat {
 
 impl Integrator for BerendsenBarostat {
     fn setup(&mut self, system: &System) {
-        self.accelerations = vec![Vector3D::zero(); system.size()];
+        self.accelerations = current_accelerations(system);
+    }
+
+    fn timestep(&self) -> Option<f64> {
+        Some(self.timestep)
+    }
+
+    fn set_timestep(&mut self, timestep: f64) -> Result<(), String> {
+        if timestep <= 0.0 {
+            return Err("the timestep must be positive".into());
+        }
+        self.timestep = timestep;
+        return Ok(());
     }
 
     fn integrate(&mut self, system: &mut System) {
@@ -222,6 +434,7 @@ impl Integrator for BerendsenBarostat {
         }
 
         system.cell.scale_mut(self.eta * self.eta * self.eta * Matrix3::one());
+        system.update_virtual_sites();
 
         if let Some(maximum_cutoff) = system.maximum_cutoff() {
             if system.cell.lengths().iter().any(|&d| 0.5 * d <= maximum_cutoff) {
@@ -242,14 +455,73 @@ impl Integrator for BerendsenBarostat {
         for (velocity, &mass, acceleration, force) in soa_zip!(
             system.particles_mut(), [mut velocity, mass], &mut self.accelerations, &forces
         ) {
-            *acceleration = force / mass;
+            *acceleration = compute_acceleration(*force, mass);
             *velocity += 0.5 * dt * acceleration;
         }
     }
 }
 
+/// Constraints on the degrees of freedom of the unit cell which are allowed
+/// to fluctuate in an anisotropic barostat.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CellConstraint {
+    /// All the lengths of the cell are scaled by the same factor, using the
+    /// average of the diagonal stress. The shape of the cell is conserved.
+    Isotropic,
+    /// The x and y lengths of the cell are scaled together, using the average
+    /// of the xx and yy stress, while the z length fluctuates independently.
+    /// This is useful for systems with an interface normal to the z axis,
+    /// such as membranes.
+    SemiIsotropic,
+    /// All the components of the cell fluctuate independently, including the
+    /// angles.
+    Anisotropic,
+    /// The lengths of the cell fluctuate independently, but the angles between
+    /// the cell vectors of an orthorhombic cell are fixed.
+    FixedShape,
+}
+
+impl CellConstraint {
+    /// Apply this constraint to the `eta` cell scaling matrix
+    fn apply(self, eta: &mut Matrix3) {
+        match self {
+            CellConstraint::Isotropic => {
+                *eta = eta.trace() / 3.0 * Matrix3::one();
+            }
+            CellConstraint::SemiIsotropic => {
+                let xy = 0.5 * (eta[0][0] + eta[1][1]);
+                *eta = Matrix3::new([
+                    [xy, 0.0, 0.0],
+                    [0.0, xy, 0.0],
+                    [0.0, 0.0, eta[2][2]],
+                ]);
+            }
+            CellConstraint::Anisotropic => {
+                // Make the eta matrix symmetric here
+                for i in 0..3 {
+                    for j in 0..i {
+                        eta[i][j] = 0.5 * (eta[i][j] + eta[j][i]);
+                        eta[j][i] = eta[i][j];
+                    }
+                }
+            }
+            CellConstraint::FixedShape => {
+                *eta = Matrix3::new([
+                    [eta[0][0], 0.0, 0.0],
+                    [0.0, eta[1][1], 0.0],
+                    [0.0, 0.0, eta[2][2]],
+                ]);
+            }
+        }
+    }
+}
+
 /// Anisotropic Berendsen barostat integrator based on velocity-Verlet.
 ///
+/// The cell degrees of freedom allowed to fluctuate are controlled by a
+/// [`CellConstraint`](enum.CellConstraint.html), which defaults to
+/// `CellConstraint::Anisotropic`.
+///
 /// This integrator is **neither** time-reversible nor symplectic.
 pub struct AnisoBerendsenBarostat {
     /// Timestep for the integrator
@@ -262,6 +534,8 @@ pub struct AnisoBerendsenBarostat {
     accelerations: Vec<Vector3D>,
     /// Storing the scaling factor
     eta: Matrix3,
+    /// Constraint on the cell degrees of freedom
+    constraint: CellConstraint,
 }
 
 impl AnisoBerendsenBarostat {
@@ -275,6 +549,7 @@ impl AnisoBerendsenBarostat {
             tau: tau,
             accelerations: Vec::new(),
             eta: Matrix3::one(),
+            constraint: CellConstraint::Anisotropic,
         }
     }
 
@@ -284,11 +559,28 @@ impl AnisoBerendsenBarostat {
     pub fn hydrostatic(timestep: f64, pressure: f64, tau: f64) -> AnisoBerendsenBarostat {
         AnisoBerendsenBarostat::new(timestep, pressure * Matrix3::one(), tau)
     }
+
+    /// Set the constraint on the cell degrees of freedom to `constraint`
+    pub fn set_constraint(&mut self, constraint: CellConstraint) {
+        self.constraint = constraint;
+    }
 }
 
 impl Integrator for AnisoBerendsenBarostat {
     fn setup(&mut self, system: &System) {
-        self.accelerations = vec![Vector3D::zero(); system.size()];
+        self.accelerations = current_accelerations(system);
+    }
+
+    fn timestep(&self) -> Option<f64> {
+        Some(self.timestep)
+    }
+
+    fn set_timestep(&mut self, timestep: f64) -> Result<(), String> {
+        if timestep <= 0.0 {
+            return Err("the timestep must be positive".into());
+        }
+        self.timestep = timestep;
+        return Ok(());
     }
 
     fn integrate(&mut self, system: &mut System) {
@@ -305,6 +597,7 @@ impl Integrator for AnisoBerendsenBarostat {
         }
 
         system.cell.scale_mut(self.eta);
+        system.update_virtual_sites();
 
         if let Some(maximum_cutoff) = system.maximum_cutoff() {
             if system.cell.lengths().iter().any(|&d| 0.5 * d <= maximum_cutoff) {
@@ -319,21 +612,14 @@ impl Integrator for AnisoBerendsenBarostat {
 
         let factor = self.timestep * WATER_COMPRESSIBILITY / self.tau;
         self.eta = Matrix3::one() - factor * (self.stress - system.stress());
-
-        // Make the eta matrix symmetric here
-        for i in 0..3 {
-            for j in 0..i {
-                self.eta[i][j] = 0.5 * (self.eta[i][j] + self.eta[j][i]);
-                self.eta[j][i] = self.eta[i][j];
-            }
-        }
+        self.constraint.apply(&mut self.eta);
 
         let forces = system.forces();
         // Update accelerations at t + ∆t and velocities at t + ∆t
         for (velocity, &mass, acceleration, force) in soa_zip!(
             system.particles_mut(), [mut velocity, mass], &mut self.accelerations, &forces
         ) {
-            *acceleration = force / mass;
+            *acceleration = compute_acceleration(*force, mass);
             *velocity += 0.5 * dt * acceleration;
         }
     }
@@ -20,10 +20,13 @@
 //! - [`VelocityVerlet`](struct.VelocityVerlet.html): simple velocity-Verlet
 //!   integrator;
 //! - [`LeapFrog`](struct.LeapFrog.html): Leap-Frog integrator;
+//! - [`Respa`](struct.Respa.html): multiple timestep integrator, integrating
+//!   the bonded forces with a smaller timestep;
 //! - [`BerendsenBarostat`](struct.BerendsenBarostat.html): isotropic Berendsen
 //!   barostat coupled to a velocity-Verlet integrator;
 //! - [`AnisoBerendsenBarostat`](struct.AnisoBerendsenBarostat.html) anisotropic
-//!   Berendsen barostat coupled to a velocity-Verlet integrator
+//!   Berendsen barostat coupled to a velocity-Verlet integrator, with an
+//!   optional [`CellConstraint`](enum.CellConstraint.html) on the cell shape
 //!
 //! # Themostats
 //!
@@ -35,9 +38,14 @@
 //!   be usefull for equilibration;
 //! - [`CSVRThermostat`](struct.CSVRThermostat.html): Canonical Sampling through
 //!   Velocities Rescaling is a well-behaved thermostating algorithm generating
-//!   the expected canonical ensemble distribution of states.
+//!   the expected canonical ensemble distribution of states. It is also
+//!   available under the name [`BussiThermostat`](type.BussiThermostat.html);
 //! - [`BerendsenThermostat`](struct.BerendsenThermostat.html): berendsen or
 //!   weak-coupling thermostat;
+//! - [`AnnealingThermostat`](struct.AnnealingThermostat.html): simulated
+//!   annealing, changing the temperature of another thermostat over time;
+//! - [`DrudeThermostat`](struct.DrudeThermostat.html): dual thermostat for
+//!   polarizable systems, keeping the Drude particles cold;
 //!
 //! # Control
 //!
@@ -50,29 +58,42 @@
 //!   rotational momentum of a system;
 //! - [`Rewrap`](struct.Rewrap.html): wrap all atoms from a system inside the
 //!   unit cell;
+//! - [`MullerPlathe`](struct.MullerPlathe.html): exchange velocities between
+//!   a hot and a cold slab, to impose a heat flux in the system;
+//! - [`SteeredRestraint`](struct.SteeredRestraint.html): pull a group of
+//!   particles with a moving harmonic spring, for steered molecular dynamics;
+//!
+//! The [`Metadynamics`](../../lumol_core/energy/struct.Metadynamics.html) bias
+//! also implements `Control`, to deposit new hills during the simulation.
 
 mod integrators;
 pub use self::integrators::Integrator;
 
 pub use self::integrators::AnisoBerendsenBarostat;
+pub use self::integrators::CellConstraint;
 pub use self::integrators::BerendsenBarostat;
 pub use self::integrators::LeapFrog;
+pub use self::integrators::Respa;
 pub use self::integrators::VelocityVerlet;
 pub use self::integrators::Verlet;
 
 mod controls;
 pub use self::controls::Control;
 
+pub use self::controls::MullerPlathe;
 pub use self::controls::RemoveRotation;
 pub use self::controls::RemoveTranslation;
 pub use self::controls::Rewrap;
+pub use self::controls::SteeredRestraint;
 
 mod thermostats;
 pub use self::thermostats::Thermostat;
 
 pub use self::thermostats::RescaleThermostat;
 pub use self::thermostats::BerendsenThermostat;
-pub use self::thermostats::CSVRThermostat;
+pub use self::thermostats::{CSVRThermostat, BussiThermostat};
+pub use self::thermostats::{AnnealingThermostat, AnnealingSchedule};
+pub use self::thermostats::DrudeThermostat;
 
 mod molecular_dynamics;
 pub use self::molecular_dynamics::MolecularDynamics;
@@ -2,7 +2,9 @@
 // Copyright (C) Lumol's contributors — BSD license
 
 use crate::propagator::{Propagator, TemperatureStrategy};
-use lumol_core::{System, DegreesOfFreedom};
+use soa_derive::soa_zip;
+
+use lumol_core::{System, DegreesOfFreedom, UnitCell, Vector3D};
 
 use super::{Control, Integrator, Thermostat};
 use super::VelocityVerlet;
@@ -15,6 +17,77 @@ pub struct MolecularDynamics {
     thermostat: Option<Box<dyn Thermostat>>,
     /// Control algorithms in the simulation.
     controls: Vec<Box<dyn Control>>,
+    /// Optional adaptive timestep
+    adaptive: Option<AdaptiveTimestep>,
+}
+
+/// When using an adaptive timestep, steps are done again if the timestep
+/// should be reduced by more than this factor.
+const REJECT_STEP_FACTOR: f64 = 0.5;
+
+/// Adjust the timestep of an integrator, so that the displacement caused by
+/// the forces during one step stays below a target value.
+struct AdaptiveTimestep {
+    /// Minimal timestep
+    min: f64,
+    /// Maximal timestep
+    max: f64,
+    /// Target for the maximal displacement caused by the forces in one step
+    max_displacement: f64,
+    /// Positions before the current step
+    positions: Vec<Vector3D>,
+    /// Velocities before the current step
+    velocities: Vec<Vector3D>,
+    /// Unit cell before the current step
+    cell: UnitCell,
+}
+
+impl AdaptiveTimestep {
+    /// Save the state of the `system` before a step
+    fn save(&mut self, system: &System) {
+        self.positions.clear();
+        self.positions.extend_from_slice(system.particles().position);
+        self.velocities.clear();
+        self.velocities.extend_from_slice(system.particles().velocity);
+        self.cell = system.cell;
+    }
+
+    /// Restore the state of the `system` saved before the current step
+    fn restore(&self, system: &mut System) {
+        for (position, velocity, saved_position, saved_velocity) in soa_zip!(
+            system.particles_mut(), [mut position, mut velocity], &self.positions, &self.velocities
+        ) {
+            *position = *saved_position;
+            *velocity = *saved_velocity;
+        }
+        system.cell = self.cell;
+        system.update_virtual_sites();
+    }
+
+    /// Get the timestep to use for the next step, after a step using
+    /// `timestep` that went from the saved velocities to the current
+    /// velocities of the `system`.
+    fn next_timestep(&self, system: &System, timestep: f64) -> f64 {
+        // The change in velocity gives the acceleration over the step
+        let mut max_acceleration = 0.0;
+        for (&mass, velocity, old_velocity) in soa_zip!(system.particles(), [mass, velocity], &self.velocities) {
+            if mass == 0.0 {
+                continue;
+            }
+            let acceleration = (velocity - old_velocity).norm() / timestep;
+            if acceleration > max_acceleration {
+                max_acceleration = acceleration;
+            }
+        }
+
+        let timestep = if max_acceleration == 0.0 {
+            self.max
+        } else {
+            // The displacement caused by the forces is 1/2 a ∆t^2
+            f64::sqrt(2.0 * self.max_displacement / max_acceleration)
+        };
+        return timestep.max(self.min).min(self.max);
+    }
 }
 
 impl MolecularDynamics {
@@ -31,6 +104,7 @@ impl MolecularDynamics {
             integrator: integrator,
             thermostat: None,
             controls: Vec::new(),
+            adaptive: None,
         }
     }
 
@@ -43,6 +117,43 @@ impl MolecularDynamics {
     pub fn set_thermostat(&mut self, thermostat: Box<dyn Thermostat>) {
         self.thermostat = Some(thermostat);
     }
+
+    /// Get the current timestep of the integrator, or `None` if the
+    /// integrator does not expose its timestep.
+    pub fn timestep(&self) -> Option<f64> {
+        self.integrator.timestep()
+    }
+
+    /// Use an adaptive timestep in this simulation. After each step, the
+    /// timestep is adjusted inside the `[dt_min, dt_max]` range, so that the
+    /// displacement of any particle caused by the forces (`1/2 F/m ∆t^2`)
+    /// stays below `max_displacement`. This uses a small timestep during
+    /// collisions and other events with large forces, and a large timestep
+    /// the rest of the time. Steps where the timestep was much too large are
+    /// discarded and done again with a smaller timestep.
+    ///
+    /// # Panics
+    ///
+    /// If `dt_min` is not positive, if `dt_max` is smaller than `dt_min`, if
+    /// `max_displacement` is not positive or if the integrator does not
+    /// support changing its timestep.
+    pub fn set_adaptive_timestep(&mut self, dt_min: f64, dt_max: f64, max_displacement: f64) {
+        assert!(
+            self.integrator.timestep().is_some(),
+            "the integrator does not support changing the timestep"
+        );
+        assert!(dt_min > 0.0, "the minimal timestep must be positive");
+        assert!(dt_max >= dt_min, "the maximal timestep must be larger than the minimal one");
+        assert!(max_displacement > 0.0, "the maximal displacement must be positive");
+        self.adaptive = Some(AdaptiveTimestep {
+            min: dt_min,
+            max: dt_max,
+            max_displacement: max_displacement,
+            positions: Vec::new(),
+            velocities: Vec::new(),
+            cell: UnitCell::infinite(),
+        });
+    }
 }
 
 impl Propagator for MolecularDynamics {
@@ -50,10 +161,12 @@ impl Propagator for MolecularDynamics {
         TemperatureStrategy::Velocities
     }
 
-    fn degrees_of_freedom(&self, _: &System) -> DegreesOfFreedom {
-        // default to particles for now. change this if/when constrains are
-        // implemented
-        DegreesOfFreedom::Particles
+    fn degrees_of_freedom(&self, system: &System) -> DegreesOfFreedom {
+        if system.frozen().is_empty() {
+            DegreesOfFreedom::Particles
+        } else {
+            DegreesOfFreedom::Frozen(3 * system.frozen().len())
+        }
     }
 
     fn setup(&mut self, system: &System) {
@@ -63,8 +176,35 @@ impl Propagator for MolecularDynamics {
         }
     }
 
+    fn seed(&mut self, seed: u64) {
+        if let Some(ref mut thermostat) = self.thermostat {
+            thermostat.seed(seed);
+        }
+    }
+
     fn propagate(&mut self, system: &mut System) {
-        self.integrator.integrate(system);
+        // Frozen particles must not move during the integration, even if
+        // their velocities were changed since the last step
+        system.zero_frozen_velocities();
+        if let Some(ref mut adaptive) = self.adaptive {
+            loop {
+                adaptive.save(system);
+                let timestep = self.integrator.timestep().expect("missing timestep in adaptive integrator");
+                self.integrator.integrate(system);
+
+                let next = adaptive.next_timestep(system, timestep);
+                self.integrator.set_timestep(next).expect("invalid timestep in adaptive integrator");
+                if next >= REJECT_STEP_FACTOR * timestep {
+                    break;
+                }
+                // The timestep was too large for this step, try again with
+                // the smaller one
+                adaptive.restore(system);
+                self.integrator.setup(system);
+            }
+        } else {
+            self.integrator.integrate(system);
+        }
 
         if let Some(ref mut thermostat) = self.thermostat {
             thermostat.apply(system);
@@ -73,6 +213,7 @@ impl Propagator for MolecularDynamics {
         for control in &mut self.controls {
             control.control(system);
         }
+        system.zero_frozen_velocities();
     }
 
     fn finish(&mut self, system: &System) {
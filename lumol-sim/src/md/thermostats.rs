@@ -7,7 +7,7 @@ use lumol_core::consts::K_BOLTZMANN;
 use rand::{self, SeedableRng};
 use rand_distr::{Distribution, Normal, Gamma};
 
-use crate::velocities;
+use crate::velocities::seeded_rng;
 
 /// Trait for thermostat algorithms some parameters in a system during a simulation.
 pub trait Thermostat {
@@ -21,6 +21,84 @@ pub trait Thermostat {
 
     /// Function called once at the end of the simulation.
     fn finish(&mut self, _: &System) {}
+
+    /// Get the target temperature of this thermostat, or `None` if this
+    /// thermostat does not have a single target temperature. The default
+    /// implementation returns `None`.
+    fn temperature(&self) -> Option<f64> {
+        None
+    }
+
+    /// Set the target temperature of this thermostat to `temperature`, or
+    /// return an error message if the temperature can not be changed to this
+    /// value. Thermostats returning `Some` from
+    /// [`Thermostat::temperature`](trait.Thermostat.html#method.temperature)
+    /// must implement this function. The default implementation always
+    /// returns an error.
+    fn set_temperature(&mut self, _: f64) -> Result<(), String> {
+        return Err("this thermostat does not have a target temperature".into());
+    }
+
+    /// Seed the random number generator used by this thermostat, if any.
+    fn seed(&mut self, _: u64) {}
+}
+
+/// Check that all the indexes in the thermostat `group` are valid for the
+/// `system`.
+fn check_group(system: &System, group: Option<&[usize]>) {
+    if let Some(group) = group {
+        for &i in group {
+            assert!(
+                i < system.size(),
+                "particle index {} in thermostat group is out of bounds", i
+            );
+        }
+    }
+}
+
+/// Get the kinetic energy and the number of degrees of freedom of the particles
+/// in `group`, or of the whole system if `group` is `None`.
+fn kinetic_and_dof(system: &System, group: Option<&[usize]>) -> (f64, usize) {
+    match group {
+        Some(group) => {
+            let particles = system.particles();
+            let kinetic = group.iter().map(|&i| {
+                0.5 * particles.mass[i] * particles.velocity[i].norm2()
+            }).sum();
+            (kinetic, 3 * group.len())
+        }
+        None => (system.kinetic_energy(), system.degrees_of_freedom()),
+    }
+}
+
+/// Get the instant temperature of the particles in `group`, or of the whole
+/// system if `group` is `None`.
+fn temperature(system: &System, group: Option<&[usize]>) -> f64 {
+    match group {
+        Some(_) => {
+            let (kinetic, dof) = kinetic_and_dof(system, group);
+            2.0 * kinetic / (dof as f64 * K_BOLTZMANN)
+        }
+        None => system.temperature(),
+    }
+}
+
+/// Multiply the velocities of the particles in `group`, or of all the particles
+/// in the system if `group` is `None`, by `factor`.
+fn scale_velocities(system: &mut System, group: Option<&[usize]>, factor: f64) {
+    let velocities = system.particles_mut().velocity;
+    match group {
+        Some(group) => {
+            for &i in group {
+                velocities[i] *= factor;
+            }
+        }
+        None => {
+            for velocity in velocities {
+                *velocity *= factor;
+            }
+        }
+    }
 }
 
 
@@ -42,6 +120,8 @@ pub struct RescaleThermostat {
     temperature: f64,
     /// Tolerance in temperature
     tol: f64,
+    /// Indexes of the thermostated particles, or `None` for all particles
+    group: Option<Vec<usize>>,
 }
 
 impl RescaleThermostat {
@@ -59,17 +139,50 @@ impl RescaleThermostat {
         RescaleThermostat {
             temperature: temperature,
             tol: tol,
+            group: None,
         }
     }
+
+    /// Create a new `RescaleThermostat` acting at temperature `temperature`
+    /// only on the particles in `group`, with a tolerance of
+    /// `5% * temperature`. The temperature of the group is computed using
+    /// only the kinetic energy and degrees of freedom of these particles.
+    pub fn for_group(temperature: f64, group: Vec<usize>) -> RescaleThermostat {
+        let mut thermostat = RescaleThermostat::new(temperature);
+        thermostat.group = Some(group);
+        return thermostat;
+    }
 }
 
 impl Thermostat for RescaleThermostat {
+    fn setup(&mut self, system: &System) {
+        check_group(system, self.group.as_deref());
+    }
+
     fn apply(&mut self, system: &mut System) {
-        let instant_temperature = system.temperature();
+        let instant_temperature = temperature(system, self.group.as_deref());
         if f64::abs(instant_temperature - self.temperature) > self.tol {
-            velocities::scale(system, self.temperature);
+            let factor = f64::sqrt(self.temperature / instant_temperature);
+            scale_velocities(system, self.group.as_deref(), factor);
         }
     }
+
+    fn temperature(&self) -> Option<f64> {
+        Some(self.temperature)
+    }
+
+    /// Setting the temperature of a rescale thermostat also scales the
+    /// tolerance by the same factor, keeping the same relative tolerance.
+    fn set_temperature(&mut self, temperature: f64) -> Result<(), String> {
+        if temperature < 0.0 {
+            return Err("The temperature must be positive in thermostats.".into());
+        }
+        if self.temperature > 0.0 {
+            self.tol *= temperature / self.temperature;
+        }
+        self.temperature = temperature;
+        return Ok(());
+    }
 }
 
 /// Berendsen (or weak coupling) thermostat.
@@ -94,6 +207,8 @@ pub struct BerendsenThermostat {
     /// Timestep of the thermostat, expressed as a multiplicative factor of the
     /// integrator timestep.
     tau: f64,
+    /// Indexes of the thermostated particles, or `None` for all particles
+    group: Option<Vec<usize>>,
 }
 
 impl BerendsenThermostat {
@@ -105,17 +220,41 @@ impl BerendsenThermostat {
         BerendsenThermostat {
             temperature: temperature,
             tau: tau,
+            group: None,
         }
     }
+
+    /// Create a new `BerendsenThermostat` acting at the given `temperature`
+    /// only on the particles in `group`, with a timestep of `tau` times the
+    /// integrator timestep.
+    pub fn for_group(temperature: f64, tau: f64, group: Vec<usize>) -> BerendsenThermostat {
+        let mut thermostat = BerendsenThermostat::new(temperature, tau);
+        thermostat.group = Some(group);
+        return thermostat;
+    }
 }
 
 impl Thermostat for BerendsenThermostat {
+    fn setup(&mut self, system: &System) {
+        check_group(system, self.group.as_deref());
+    }
+
     fn apply(&mut self, system: &mut System) {
-        let instant_temperature = system.temperature();
+        let instant_temperature = temperature(system, self.group.as_deref());
         let factor = f64::sqrt(1.0 + (self.temperature / instant_temperature - 1.0) / self.tau);
-        for velocity in system.particles_mut().velocity {
-            *velocity *= factor;
+        scale_velocities(system, self.group.as_deref(), factor);
+    }
+
+    fn temperature(&self) -> Option<f64> {
+        Some(self.temperature)
+    }
+
+    fn set_temperature(&mut self, temperature: f64) -> Result<(), String> {
+        if temperature < 0.0 {
+            return Err("The temperature must be positive in thermostats.".into());
         }
+        self.temperature = temperature;
+        return Ok(());
     }
 }
 
@@ -140,6 +279,8 @@ pub struct CSVRThermostat {
     rng: Box<dyn rand::RngCore>,
     /// normal (i.e. gaussian) distribution
     normal: Normal<f64>,
+    /// Indexes of the thermostated particles, or `None` for all particles
+    group: Option<Vec<usize>>,
 }
 
 impl CSVRThermostat {
@@ -164,9 +305,19 @@ impl CSVRThermostat {
             tau: tau,
             rng: rng,
             normal: Normal::new(0.0, 1.0).expect("bad normal distribution"),
+            group: None,
         }
     }
 
+    /// Create a new `CSVRThermostat` enforcing the given `temperature` only on
+    /// the particles in `group`, with a timestep of `tau` times the integrator
+    /// timestep.
+    pub fn for_group(temperature: f64, tau: f64, group: Vec<usize>) -> CSVRThermostat {
+        let mut thermostat = CSVRThermostat::new(temperature, tau);
+        thermostat.group = Some(group);
+        return thermostat;
+    }
+
     /// Get the sum of n independent gaussian noises squared, i.e. the Wiener
     /// noise in equation 4 of Bussi2007.
     ///
@@ -193,26 +344,307 @@ impl CSVRThermostat {
 }
 
 impl Thermostat for CSVRThermostat {
+    fn setup(&mut self, system: &System) {
+        check_group(system, self.group.as_deref());
+    }
+
     fn apply(&mut self, system: &mut System) {
-        let kinetic = system.kinetic_energy();
+        let (kinetic, dof) = kinetic_and_dof(system, self.group.as_deref());
         let kinetic_factor = self.target_kinetic_per_dof / kinetic;
         let exp_1 = f64::exp(-1.0/self.tau);
         let exp_2 = (1.0 - exp_1) * kinetic_factor;
 
-        let dof = system.degrees_of_freedom();
         let (gauss, wiener) = self.sum_noises(dof - 1);
 
         let scale = exp_1 + exp_2 * (gauss * gauss + wiener) + 2.0 * gauss * f64::sqrt(exp_1 * exp_2);
         let alpha = f64::sqrt(scale);
-        for velocity in system.particles_mut().velocity {
-            *velocity *= alpha;
+        scale_velocities(system, self.group.as_deref(), alpha);
+    }
+
+    fn temperature(&self) -> Option<f64> {
+        Some(2.0 * self.target_kinetic_per_dof / K_BOLTZMANN)
+    }
+
+    fn set_temperature(&mut self, temperature: f64) -> Result<(), String> {
+        if temperature < 0.0 {
+            return Err("The temperature must be positive in thermostats.".into());
         }
+        self.target_kinetic_per_dof = K_BOLTZMANN * temperature / 2.0;
+        return Ok(());
+    }
+
+    fn seed(&mut self, seed: u64) {
+        self.rng = Box::new(seeded_rng(seed));
+    }
+}
+
+/// The Bussi-Donadio-Parrinello thermostat, also known as the stochastic
+/// velocity rescaling thermostat. This is the same algorithm as the
+/// [`CSVRThermostat`](struct.CSVRThermostat.html), and
+/// `BussiThermostat::new(temperature, tau)` uses the same parameters.
+pub type BussiThermostat = CSVRThermostat;
+
+/// Schedule for the target temperature in an
+/// [`AnnealingThermostat`](struct.AnnealingThermostat.html).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AnnealingSchedule {
+    /// Decrease the temperature linearly with the number of steps
+    Linear,
+    /// Decrease the temperature geometrically with the number of steps, *i.e.*
+    /// multiply it by a constant factor at each step
+    Geometric,
+}
+
+/// Simulated annealing thermostat.
+///
+/// This thermostat wraps another thermostat, and changes the target
+/// temperature of the wrapped thermostat from a starting temperature to a
+/// final temperature over a given number of steps, following an
+/// [`AnnealingSchedule`](enum.AnnealingSchedule.html). After this number of
+/// steps, the target temperature stays at the final temperature.
+pub struct AnnealingThermostat {
+    /// The thermostat doing the actual work
+    inner: Box<dyn Thermostat>,
+    /// Starting temperature
+    start: f64,
+    /// Final temperature
+    end: f64,
+    /// Number of steps to go from the starting to the final temperature
+    steps: u64,
+    /// Temperature schedule
+    schedule: AnnealingSchedule,
+    /// Current step
+    step: u64,
+}
+
+impl AnnealingThermostat {
+    /// Create a new `AnnealingThermostat` using the `inner` thermostat, and
+    /// changing the target temperature from `start` to `end` in `steps` steps,
+    /// using the given `schedule`.
+    pub fn new(
+        inner: Box<dyn Thermostat>,
+        start: f64,
+        end: f64,
+        steps: u64,
+        schedule: AnnealingSchedule
+    ) -> AnnealingThermostat {
+        assert!(start >= 0.0 && end >= 0.0, "The temperature must be positive in thermostats.");
+        assert!(steps > 0, "The number of steps must be positive in annealing thermostat.");
+        if schedule == AnnealingSchedule::Geometric {
+            assert!(
+                start > 0.0 && end > 0.0,
+                "The temperatures must be strictly positive for geometric annealing."
+            );
+        }
+
+        assert!(
+            inner.temperature().is_some(),
+            "The thermostat used in annealing thermostat must have a target temperature."
+        );
+        let mut inner = inner;
+        inner.set_temperature(start).expect("invalid starting temperature in annealing thermostat");
+        AnnealingThermostat {
+            inner: inner,
+            start: start,
+            end: end,
+            steps: steps,
+            schedule: schedule,
+            step: 0,
+        }
+    }
+
+    /// Get the current target temperature of the wrapped thermostat, following
+    /// the annealing schedule.
+    pub fn current_temperature(&self) -> f64 {
+        self.target(self.step)
+    }
+
+    /// Get the target temperature at the given `step`
+    fn target(&self, step: u64) -> f64 {
+        if step >= self.steps {
+            return self.end;
+        }
+
+        let progress = step as f64 / self.steps as f64;
+        match self.schedule {
+            AnnealingSchedule::Linear => self.start + (self.end - self.start) * progress,
+            AnnealingSchedule::Geometric => self.start * f64::powf(self.end / self.start, progress),
+        }
+    }
+}
+
+impl Thermostat for AnnealingThermostat {
+    fn setup(&mut self, system: &System) {
+        self.step = 0;
+        self.inner.set_temperature(self.start).expect("invalid starting temperature in annealing thermostat");
+        self.inner.setup(system);
+    }
+
+    fn apply(&mut self, system: &mut System) {
+        self.step += 1;
+        let temperature = self.target(self.step);
+        self.inner.set_temperature(temperature).expect("invalid temperature in annealing thermostat");
+        self.inner.apply(system);
+    }
+
+    fn finish(&mut self, system: &System) {
+        self.inner.finish(system);
+    }
+
+    /// The target temperature of an annealing thermostat is the final
+    /// temperature of the schedule.
+    fn temperature(&self) -> Option<f64> {
+        Some(self.end)
+    }
+
+    /// Setting the temperature of an annealing thermostat changes the final
+    /// temperature of the schedule.
+    fn set_temperature(&mut self, temperature: f64) -> Result<(), String> {
+        if temperature < 0.0 {
+            return Err("The temperature must be positive in thermostats.".into());
+        }
+        if self.schedule == AnnealingSchedule::Geometric && temperature == 0.0 {
+            return Err("The temperatures must be strictly positive for geometric annealing.".into());
+        }
+        self.end = temperature;
+        return Ok(());
+    }
+
+    fn seed(&mut self, seed: u64) {
+        self.inner.seed(seed);
+    }
+}
+
+/// Dual Berendsen thermostat for polarizable systems using Drude particles.
+///
+/// The motion of each Drude particle and its core is split in the motion of
+/// their center of mass, and the relative motion of the Drude particle with
+/// respect to the core. The centers of mass and the other particles are
+/// coupled to a thermostat at the target temperature, while the relative
+/// motions are coupled to a separate thermostat at a much lower temperature.
+/// This keeps the Drude degrees of freedom cold, so that the Drude particles
+/// stay close to their energy minimum, as in the self-consistent treatment
+/// of polarization. See [1] for more information.
+///
+/// Both thermostats use the Berendsen weak-coupling algorithm, and share the
+/// same limitations (see [`BerendsenThermostat`]).
+///
+/// [1] Lamoureux and Roux, J. Chem. Phys. 119, 3025 (2003) doi: 10.1063/1.1589749
+///
+/// [`BerendsenThermostat`]: struct.BerendsenThermostat.html
+pub struct DrudeThermostat {
+    /// Target temperature for the centers of mass and the other particles
+    temperature: f64,
+    /// Target temperature for the relative motion of the Drude particles
+    drude_temperature: f64,
+    /// Timestep of the thermostat, expressed as a multiplicative factor of the
+    /// integrator timestep.
+    tau: f64,
+}
+
+impl DrudeThermostat {
+    /// Create a new `DrudeThermostat` acting at the given `temperature`,
+    /// keeping the Drude degrees of freedom at `drude_temperature`, with a
+    /// timestep of `tau` times the integrator timestep. A typical value for
+    /// the `drude_temperature` is 1 K.
+    pub fn new(temperature: f64, drude_temperature: f64, tau: f64) -> DrudeThermostat {
+        assert!(temperature >= 0.0, "The temperature must be positive in thermostats.");
+        assert!(drude_temperature >= 0.0, "The Drude temperature must be positive in thermostats.");
+        assert!(tau >= 1.0, "The timestep must be larger than 1 in Drude thermostat.");
+        DrudeThermostat {
+            temperature: temperature,
+            drude_temperature: drude_temperature,
+            tau: tau,
+        }
+    }
+
+    /// Get the target temperature for the Drude degrees of freedom
+    pub fn drude_temperature(&self) -> f64 {
+        self.drude_temperature
+    }
+}
+
+/// Get the Berendsen factor scaling the velocities from the `instant`
+/// temperature toward the `target` temperature, with a timestep of `tau`.
+/// The velocities are not scaled if the instant temperature is zero.
+fn berendsen_factor(target: f64, instant: f64, tau: f64) -> f64 {
+    if instant > 0.0 {
+        f64::sqrt(1.0 + (target / instant - 1.0) / tau)
+    } else {
+        1.0
+    }
+}
+
+impl Thermostat for DrudeThermostat {
+    fn setup(&mut self, system: &System) {
+        for drude in system.drude_particles() {
+            let masses = system.particles().mass;
+            assert!(
+                masses[drude.drude()] > 0.0 && masses[drude.core()] > 0.0,
+                "Drude particles and their cores must have a mass to be used with a Drude thermostat"
+            );
+        }
+    }
+
+    fn apply(&mut self, system: &mut System) {
+        let drudes = system.drude_particles().to_vec();
+
+        let mut relative_velocities = Vec::with_capacity(drudes.len());
+        let mut relative_kinetic = 0.0;
+        {
+            let particles = system.particles();
+            for drude in &drudes {
+                let (core, drude) = (drude.core(), drude.drude());
+                let total_mass = particles.mass[core] + particles.mass[drude];
+                let reduced_mass = particles.mass[core] * particles.mass[drude] / total_mass;
+                let velocity = particles.velocity[drude] - particles.velocity[core];
+                relative_kinetic += 0.5 * reduced_mass * velocity.norm2();
+                relative_velocities.push(velocity);
+            }
+        }
+
+        let drude_dof = 3 * drudes.len();
+        let dof = system.degrees_of_freedom().saturating_sub(drude_dof);
+        let kinetic = system.kinetic_energy() - relative_kinetic;
+        let instant_temperature = 2.0 * kinetic / (dof as f64 * K_BOLTZMANN);
+        let drude_temperature = 2.0 * relative_kinetic / (drude_dof as f64 * K_BOLTZMANN);
+
+        let factor = berendsen_factor(self.temperature, instant_temperature, self.tau);
+        let drude_factor = berendsen_factor(self.drude_temperature, drude_temperature, self.tau);
+
+        // Scaling all the velocities also scales the relative velocities, so
+        // we only need to correct them afterward.
+        scale_velocities(system, None, factor);
+
+        let particles = system.particles_mut();
+        let (masses, velocities) = (particles.mass, particles.velocity);
+        for (drude, velocity) in drudes.iter().zip(relative_velocities) {
+            let (core, drude) = (drude.core(), drude.drude());
+            let total_mass = masses[core] + masses[drude];
+            let delta = (drude_factor - factor) * velocity;
+            velocities[drude] += masses[core] / total_mass * delta;
+            velocities[core] -= masses[drude] / total_mass * delta;
+        }
+    }
+
+    fn temperature(&self) -> Option<f64> {
+        Some(self.temperature)
+    }
+
+    fn set_temperature(&mut self, temperature: f64) -> Result<(), String> {
+        if temperature < 0.0 {
+            return Err("The temperature must be positive in thermostats.".into());
+        }
+        self.temperature = temperature;
+        return Ok(());
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use lumol_core::{Molecule, Particle};
+    use approx::assert_ulps_eq;
 
     // The actual thermostating part is tested in lumol-sim/tests/thermostats.rs
 
@@ -222,6 +654,108 @@ mod tests {
         let _ = RescaleThermostat::new(-56.0);
     }
 
+    #[test]
+    #[should_panic(expected = "particle index 3 in thermostat group is out of bounds")]
+    fn bad_group() {
+        let mut system = System::new();
+        system.add_molecule(Molecule::new(Particle::new("He")));
+        let mut thermostat = RescaleThermostat::for_group(300.0, vec![0, 3]);
+        thermostat.setup(&system);
+    }
+
+    #[test]
+    fn set_temperature() {
+        let mut thermostats: Vec<Box<dyn Thermostat>> = vec![
+            Box::new(RescaleThermostat::new(300.0)),
+            Box::new(BerendsenThermostat::new(300.0, 10.0)),
+            Box::new(CSVRThermostat::new(300.0, 10.0)),
+            Box::new(DrudeThermostat::new(300.0, 1.0, 10.0)),
+        ];
+
+        for thermostat in &mut thermostats {
+            assert_ulps_eq!(thermostat.temperature().unwrap(), 300.0);
+            thermostat.set_temperature(120.0).unwrap();
+            assert_ulps_eq!(thermostat.temperature().unwrap(), 120.0);
+            assert!(thermostat.set_temperature(-1.0).is_err());
+            assert_ulps_eq!(thermostat.temperature().unwrap(), 120.0);
+        }
+
+        let mut rescale = RescaleThermostat::new(300.0);
+        assert_ulps_eq!(rescale.tol, 15.0);
+        rescale.set_temperature(120.0).unwrap();
+        assert_ulps_eq!(rescale.tol, 6.0);
+
+        let inner = Box::new(RescaleThermostat::new(300.0));
+        let mut annealing = AnnealingThermostat::new(inner, 400.0, 100.0, 4, AnnealingSchedule::Linear);
+        assert_eq!(annealing.temperature(), Some(100.0));
+        annealing.set_temperature(200.0).unwrap();
+        assert_eq!(annealing.temperature(), Some(200.0));
+        assert_eq!(annealing.current_temperature(), 400.0);
+        annealing.set_temperature(0.0).unwrap();
+        assert_eq!(annealing.temperature(), Some(0.0));
+
+        let inner = Box::new(RescaleThermostat::new(300.0));
+        let mut annealing = AnnealingThermostat::new(inner, 400.0, 100.0, 4, AnnealingSchedule::Geometric);
+        assert!(annealing.set_temperature(0.0).is_err());
+        assert_eq!(annealing.temperature(), Some(100.0));
+    }
+
+    struct NoTarget;
+    impl Thermostat for NoTarget {
+        fn apply(&mut self, _: &mut System) {}
+    }
+
+    #[test]
+    #[should_panic(expected = "The thermostat used in annealing thermostat must have a target temperature.")]
+    fn annealing_without_target() {
+        assert_eq!(NoTarget.temperature(), None);
+        assert!(NoTarget.set_temperature(300.0).is_err());
+        let _ = AnnealingThermostat::new(Box::new(NoTarget), 300.0, 100.0, 10, AnnealingSchedule::Linear);
+    }
+
+    fn annealing_temperatures(schedule: AnnealingSchedule) -> Vec<f64> {
+        let mut system = System::new();
+        system.add_molecule(Molecule::new(Particle::new("He")));
+        system.particles_mut().velocity[0] = [1.0, 0.0, 0.0].into();
+
+        let inner = Box::new(RescaleThermostat::new(300.0));
+        let mut thermostat = AnnealingThermostat::new(inner, 400.0, 100.0, 4, schedule);
+        thermostat.setup(&system);
+        assert_eq!(thermostat.current_temperature(), 400.0);
+
+        let mut temperatures = Vec::new();
+        for _ in 0..6 {
+            thermostat.apply(&mut system);
+            temperatures.push(thermostat.current_temperature());
+        }
+        return temperatures;
+    }
+
+    #[test]
+    fn annealing() {
+        let temperatures = annealing_temperatures(AnnealingSchedule::Linear);
+        assert_ulps_eq!(temperatures[0], 325.0);
+        assert_ulps_eq!(temperatures[1], 250.0);
+        assert_ulps_eq!(temperatures[2], 175.0);
+        assert_eq!(temperatures[3], 100.0);
+        assert_eq!(temperatures[4], 100.0);
+        assert_eq!(temperatures[5], 100.0);
+
+        let temperatures = annealing_temperatures(AnnealingSchedule::Geometric);
+        assert_ulps_eq!(temperatures[0], 400.0 / f64::sqrt(2.0));
+        assert_ulps_eq!(temperatures[1], 200.0);
+        assert_ulps_eq!(temperatures[2], 100.0 * f64::sqrt(2.0));
+        assert_eq!(temperatures[3], 100.0);
+        assert_eq!(temperatures[4], 100.0);
+    }
+
+    #[test]
+    #[should_panic(expected = "The temperatures must be strictly positive for geometric annealing.")]
+    fn zero_temperature_geometric_annealing() {
+        let inner = Box::new(RescaleThermostat::new(300.0));
+        let _ = AnnealingThermostat::new(inner, 300.0, 0.0, 10, AnnealingSchedule::Geometric);
+    }
+
     #[test]
     #[should_panic]
     fn negative_temperature_berendsen() {
@@ -0,0 +1,71 @@
+// Lumol, an extensible molecular simulation engine
+// Copyright (C) Lumol's contributors — BSD license
+
+use std::path::Path;
+
+use super::{Output, TrajectoryOutput};
+
+use lumol_core::{System, TrajectoryError};
+
+/// The `NetCDFOutput` writes the trajectory of the system to a NetCDF file,
+/// following the [AMBER convention][amber]. The positions are written in the
+/// `coordinates` variable, and the unit cell in the `cell_lengths` and
+/// `cell_angles` variables.
+///
+/// The file is written by the [Chemfiles][chemfiles] library, using the
+/// `Amber NetCDF` format.
+///
+/// [amber]: http://ambermd.org/netcdf/nctraj.xhtml
+/// [chemfiles]: http://chemfiles.github.io
+pub struct NetCDFOutput {
+    trajectory: TrajectoryOutput,
+}
+
+impl NetCDFOutput {
+    /// Create a new `NetCDFOutput` writing to `filename`. The file is replaced
+    /// if it already exists.
+    pub fn new<P: AsRef<Path>>(filename: P) -> Result<NetCDFOutput, TrajectoryError> {
+        Ok(NetCDFOutput {
+            trajectory: TrajectoryOutput::with_format(filename, "Amber NetCDF")?,
+        })
+    }
+}
+
+impl Output for NetCDFOutput {
+    fn write(&mut self, system: &System) {
+        self.trajectory.write(system);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::tests::testing_system;
+    use lumol_core::{OpenMode, TrajectoryBuilder};
+    use tempfile::Builder;
+
+    use crate::{MolecularDynamics, Simulation};
+
+    #[test]
+    fn frames() {
+        let tempfile = Builder::new().suffix(".nc").tempfile().unwrap();
+        let mut system = testing_system();
+        {
+            let output = NetCDFOutput::new(tempfile.path()).unwrap();
+            let mut simulation = Simulation::new(Box::new(MolecularDynamics::new(1.0)));
+            simulation.add_output_with_frequency(Box::new(output), 2);
+            simulation.run(&mut system, 10);
+        }
+
+        let mut trajectory = TrajectoryBuilder::new()
+            .mode(OpenMode::Read)
+            .format("Amber NetCDF")
+            .open(tempfile.path())
+            .unwrap();
+        assert_eq!(trajectory.nsteps().unwrap(), 5);
+
+        let frame = trajectory.read().unwrap();
+        assert_eq!(frame.size(), 2);
+        assert_eq!(frame.cell.lengths(), system.cell.lengths());
+    }
+}
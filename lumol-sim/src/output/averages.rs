@@ -0,0 +1,89 @@
+// Lumol, an extensible molecular simulation engine
+// Copyright (C) Lumol's contributors — BSD license
+
+use log::info;
+
+use super::Output;
+use crate::analysis::Average;
+use lumol_core::System;
+use lumol_core::units;
+
+/// The `AveragesOutput` accumulates the total energy, the temperature and the
+/// pressure of the system during the simulation, and logs their averages
+/// with error bars at the end of the simulation.
+///
+/// The errors are estimated by block averaging, see
+/// [`Average`](../analysis/struct.Average.html) for more information.
+pub struct AveragesOutput {
+    energy: Average,
+    temperature: Average,
+    pressure: Average,
+}
+
+impl AveragesOutput {
+    /// Create a new `AveragesOutput`, using the given number of `blocks` to
+    /// estimate the errors.
+    ///
+    /// # Panics
+    ///
+    /// If `blocks` is smaller than 2.
+    pub fn new(blocks: usize) -> AveragesOutput {
+        AveragesOutput {
+            energy: Average::new(blocks),
+            temperature: Average::new(blocks),
+            pressure: Average::new(blocks),
+        }
+    }
+
+    /// Get the average total energy, in kJ/mol
+    pub fn energy(&self) -> &Average {
+        &self.energy
+    }
+
+    /// Get the average temperature, in K
+    pub fn temperature(&self) -> &Average {
+        &self.temperature
+    }
+
+    /// Get the average pressure, in bar
+    pub fn pressure(&self) -> &Average {
+        &self.pressure
+    }
+}
+
+impl Output for AveragesOutput {
+    fn write(&mut self, system: &System) {
+        self.energy.accumulate(units::to(system.total_energy(), "kJ/mol").expect("bad unit"));
+        self.temperature.accumulate(units::to(system.temperature(), "K").expect("bad unit"));
+        self.pressure.accumulate(units::to(system.pressure(), "bar").expect("bad unit"));
+    }
+
+    fn finish(&mut self, _: &System) {
+        info!("Averages over {} steps:", self.energy.count());
+        info!("    E = {} kJ/mol", self.energy);
+        info!("    T = {} K", self.temperature);
+        info!("    P = {} bar", self.pressure);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::tests::testing_system;
+
+    #[test]
+    fn averages() {
+        let mut system = testing_system();
+        let mut output = AveragesOutput::new(2);
+        output.setup(&system);
+        output.write(&system);
+        system.particles_mut().velocity[0] = [0.2, 0.0, 0.0].into();
+        output.write(&system);
+        output.finish(&system);
+
+        assert_eq!(output.energy().count(), 2);
+        let temperature = units::to(system.temperature(), "K").unwrap();
+        assert!(f64::abs(output.temperature().mean() - 5.0 / 8.0 * temperature) < 1e-9);
+        assert!(output.pressure().error() > 0.0);
+    }
+}
@@ -8,14 +8,23 @@ use std::path::{Path, PathBuf};
 
 use log::error;
 
-use super::Output;
+use super::{NumberFormat, Output};
 use lumol_core::System;
+use lumol_core::units;
 
-/// The `CellOutput` writes all the components of a cell to a file . The columns
-/// in the file contain the following values: `step A B C α β γ`.
+/// The `CellOutput` writes all the components of a cell to a file, together
+/// with the volume of the cell and the density of the system. The columns in
+/// the file contain the following values: `step A B C α β γ volume density`.
+///
+/// The density is the total mass of the particles divided by the volume of
+/// the cell, in g/cm<sup>3</sup>. The cell is only changing during a
+/// simulation when using a barostat (for example `BerendsenBarostat`,
+/// `AnisoBerendsenBarostat` or Monte Carlo `Resize` moves): this output is
+/// mostly useful for constant pressure simulations.
 pub struct CellOutput {
     file: BufWriter<File>,
     path: PathBuf,
+    format: NumberFormat,
 }
 
 impl CellOutput {
@@ -25,43 +34,94 @@ impl CellOutput {
         Ok(CellOutput {
             file: BufWriter::new(File::create(filename.as_ref())?),
             path: filename.as_ref().to_owned(),
+            format: NumberFormat::default(),
         })
     }
+
+    /// Set the format used for the values in this output
+    pub fn set_format(&mut self, format: NumberFormat) {
+        self.format = format;
+    }
 }
 
 impl Output for CellOutput {
     #[allow(clippy::non_ascii_literal)]
     fn setup(&mut self, _: &System) {
         writeln_or_log!(self, "# Unit cell of the simulation");
-        writeln_or_log!(self, "# Step A/Å B/Å C/Å α/deg β/deg γ/deg");
+        writeln_or_log!(self, "# Step A/Å B/Å C/Å α/deg β/deg γ/deg Volume/Å^3 Density/(g/cm^3)");
     }
 
     fn write(&mut self, system: &System) {
-        writeln_or_log!(self, "{} {} {} {} {} {} {}",
-            system.step,
+        let volume = system.cell.volume();
+        let mass = system.particles().mass.iter().sum::<f64>();
+        let density = units::to(mass / volume, "g/cm^3").expect("bad unit");
+        writeln_or_log!(self, "{}", self.format.line(system.step, &[
             system.cell.a(),
             system.cell.b(),
             system.cell.c(),
             system.cell.alpha(),
             system.cell.beta(),
-            system.cell.gamma()
-        )
+            system.cell.gamma(),
+            units::to(volume, "A^3").expect("bad unit"),
+            density,
+        ]))
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use super::super::tests::test_output;
+    use super::super::tests::{test_output, testing_system};
+    use lumol_core::{UnitCell, Vector3D};
+    use approx::assert_relative_eq;
+    use tempfile::NamedTempFile;
 
     #[test]
     fn cell() {
+        let system = testing_system();
+        let mass = system.particles().mass.iter().sum::<f64>();
+        let density = units::to(mass / 1000.0, "g/cm^3").unwrap();
         test_output(
             |path| Box::new(CellOutput::new(path).unwrap()),
-            "# Unit cell of the simulation
-            # Step A/Å B/Å C/Å α/deg β/deg γ/deg
-            42 10 10 10 90 90 90
-            ",
+            &format!("# Unit cell of the simulation
+            # Step A/Å B/Å C/Å α/deg β/deg γ/deg Volume/Å^3 Density/(g/cm^3)
+            42 10.000000 10.000000 10.000000 90.000000 90.000000 90.000000 1000.000000 {:.6}
+            ", density),
         );
     }
+
+    #[test]
+    fn constant_volume() {
+        let mut system = testing_system();
+        system.cell = UnitCell::triclinic(10.0, 11.0, 12.0, 80.0, 90.0, 100.0);
+        let (cos_alpha, cos_gamma) = (f64::cos(80.0_f64.to_radians()), f64::cos(100.0_f64.to_radians()));
+        let expected = 10.0 * 11.0 * 12.0 * f64::sqrt(1.0 - cos_alpha * cos_alpha - cos_gamma * cos_gamma);
+
+        let tempfile = NamedTempFile::new().unwrap();
+        {
+            let mut output = CellOutput::new(tempfile.path()).unwrap();
+            output.set_format(NumberFormat {
+                precision: 16,
+                width: 0,
+                scientific: true,
+            });
+            output.setup(&system);
+            for _ in 0..5 {
+                system.step += 1;
+                system.particles_mut().position[0] += Vector3D::new(0.3, -0.2, 0.5);
+                output.write(&system);
+            }
+            output.finish(&system);
+        }
+
+        let mut content = String::new();
+        let _ = tempfile.reopen().unwrap().read_to_string(&mut content).unwrap();
+        let lines = content.lines().filter(|line| !line.starts_with('#')).collect::<Vec<_>>();
+        assert_eq!(lines.len(), 5);
+        for line in lines {
+            let values = line.split_whitespace().map(|value| value.parse().unwrap()).collect::<Vec<f64>>();
+            assert_eq!(values.len(), 9);
+            assert_relative_eq!(values[7], expected, max_relative = 1e-12);
+        }
+    }
 }
@@ -0,0 +1,144 @@
+// Lumol, an extensible molecular simulation engine
+// Copyright (C) Lumol's contributors — BSD license
+
+use log::info;
+
+use super::Output;
+use lumol_core::System;
+use lumol_core::units;
+
+/// The `EnergyDriftOutput` tracks the relative drift of the total energy of
+/// the system from its initial value, $(E(t) - E(0)) / |E(0)|$, and logs the
+/// slope of this drift at the end of the simulation.
+///
+/// The slope is obtained by a linear least squares fit of the drift as a
+/// function of the simulation time, and is given in ns<sup>-1</sup>. It
+/// provides a single number to judge the quality of a constant energy
+/// simulation, for example to validate the choice of a timestep or a cutoff:
+/// it should be close to zero for a well-behaved simulation.
+///
+/// The simulation time is computed from the system step and the timestep
+/// given at construction, and will not be correct with an adaptive timestep.
+pub struct EnergyDriftOutput {
+    /// Timestep of the simulation
+    timestep: f64,
+    /// Step and total energy of the system when the output was set up
+    initial: Option<(u64, f64)>,
+    /// Simulation times of the accumulated values, in ns
+    times: Vec<f64>,
+    /// Accumulated relative drift of the energy
+    drifts: Vec<f64>,
+}
+
+impl EnergyDriftOutput {
+    /// Create a new `EnergyDriftOutput` for a simulation using the given
+    /// `timestep`.
+    ///
+    /// # Panics
+    ///
+    /// If the `timestep` is not positive.
+    pub fn new(timestep: f64) -> EnergyDriftOutput {
+        assert!(timestep > 0.0, "the timestep must be positive in energy drift output, got {}", timestep);
+        EnergyDriftOutput {
+            timestep: timestep,
+            initial: None,
+            times: Vec::new(),
+            drifts: Vec::new(),
+        }
+    }
+
+    /// Get the last relative drift of the total energy from its initial
+    /// value, or NaN if no value was accumulated.
+    pub fn drift(&self) -> f64 {
+        self.drifts.last().copied().unwrap_or(f64::NAN)
+    }
+
+    /// Get the slope of the relative energy drift, in ns<sup>-1</sup>, or NaN
+    /// if less than two values were accumulated.
+    pub fn slope(&self) -> f64 {
+        if self.times.len() < 2 {
+            return f64::NAN;
+        }
+
+        let count = self.times.len() as f64;
+        let mean_time = self.times.iter().sum::<f64>() / count;
+        let mean_drift = self.drifts.iter().sum::<f64>() / count;
+
+        let mut covariance = 0.0;
+        let mut variance = 0.0;
+        for (time, drift) in self.times.iter().zip(&self.drifts) {
+            covariance += (time - mean_time) * (drift - mean_drift);
+            variance += (time - mean_time) * (time - mean_time);
+        }
+        return covariance / variance;
+    }
+}
+
+impl Output for EnergyDriftOutput {
+    fn setup(&mut self, system: &System) {
+        self.initial = Some((system.step, system.total_energy()));
+        self.times.clear();
+        self.drifts.clear();
+    }
+
+    fn write(&mut self, system: &System) {
+        let energy = system.total_energy();
+        let (step, initial) = *self.initial.get_or_insert((system.step, energy));
+
+        let time = (system.step - step) as f64 * self.timestep;
+        self.times.push(units::to(time, "ns").expect("bad unit"));
+        self.drifts.push((energy - initial) / initial.abs());
+    }
+
+    fn finish(&mut self, _: &System) {
+        info!("Energy drift over {} steps:", self.times.len());
+        info!("    relative drift = {:e}", self.drift());
+        info!("    drift slope = {:e} / ns", self.slope());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::tests::testing_system;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn drift() {
+        let mut system = testing_system();
+        let mut output = EnergyDriftOutput::new(units::from(2.0, "fs").unwrap());
+        assert!(output.drift().is_nan());
+        assert!(output.slope().is_nan());
+
+        output.setup(&system);
+        output.write(&system);
+        assert_eq!(output.drift(), 0.0);
+        assert!(output.slope().is_nan());
+
+        let initial = system.total_energy();
+        system.particles_mut().velocity[0] = [0.2, 0.0, 0.0].into();
+        system.step += 500;
+        output.write(&system);
+        output.finish(&system);
+
+        let drift = (system.total_energy() - initial) / initial;
+        assert!(drift > 0.0);
+        assert_relative_eq!(output.drift(), drift);
+        // 500 steps of 2 fs = 1e-3 ns
+        assert_relative_eq!(output.slope(), drift / 1e-3, max_relative = 1e-12);
+    }
+
+    #[test]
+    fn linear_fit() {
+        let mut output = EnergyDriftOutput::new(1.0);
+        output.times = vec![0.0, 1.0, 2.0, 3.0];
+        output.drifts = vec![0.0, 1.5, 2.5, 4.0];
+        assert_relative_eq!(output.slope(), 1.3, max_relative = 1e-12);
+    }
+
+    #[test]
+    #[should_panic(expected = "the timestep must be positive in energy drift output, got 0")]
+    fn zero_timestep() {
+        let _ = EnergyDriftOutput::new(0.0);
+    }
+}
@@ -8,7 +8,7 @@ use std::path::{Path, PathBuf};
 
 use log::error;
 
-use super::Output;
+use super::{NumberFormat, Output};
 use lumol_core::System;
 use lumol_core::units;
 
@@ -17,6 +17,7 @@ use lumol_core::units;
 pub struct EnergyOutput {
     file: BufWriter<File>,
     path: PathBuf,
+    format: NumberFormat,
 }
 
 impl EnergyOutput {
@@ -26,8 +27,14 @@ impl EnergyOutput {
         Ok(EnergyOutput {
             file: BufWriter::new(File::create(filename.as_ref())?),
             path: filename.as_ref().to_owned(),
+            format: NumberFormat::default(),
         })
     }
+
+    /// Set the format used for the values in this output
+    pub fn set_format(&mut self, format: NumberFormat) {
+        self.format = format;
+    }
 }
 
 impl Output for EnergyOutput {
@@ -40,7 +47,7 @@ impl Output for EnergyOutput {
         let potential = units::to(system.potential_energy(), "kJ/mol").expect("bad unit");
         let kinetic = units::to(system.kinetic_energy(), "kJ/mol").expect("bad unit");
         let total = units::to(system.total_energy(), "kJ/mol").expect("bad unit");
-        writeln_or_log!(self, "{} {} {} {}", system.step, potential, kinetic, total);
+        writeln_or_log!(self, "{}", self.format.line(system.step, &[potential, kinetic, total]));
     }
 }
 
@@ -55,7 +62,26 @@ mod tests {
             |path| Box::new(EnergyOutput::new(path).unwrap()),
             "# Energy of the simulation (kJ/mol)
             # Step Potential Kinetic Total
-            42 1.5000000000000027 949.9201593348566 951.4201593348566
+            42 1.500000 949.920159 951.420159
+            ",
+        );
+    }
+
+    #[test]
+    fn precision() {
+        test_output(
+            |path| {
+                let mut output = EnergyOutput::new(path).unwrap();
+                output.set_format(NumberFormat {
+                    precision: 3,
+                    width: 0,
+                    scientific: false,
+                });
+                Box::new(output)
+            },
+            "# Energy of the simulation (kJ/mol)
+            # Step Potential Kinetic Total
+            42 1.500 949.920 951.420
             ",
         );
     }
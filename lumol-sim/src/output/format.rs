@@ -0,0 +1,107 @@
+// Lumol, an extensible molecular simulation engine
+// Copyright (C) Lumol's contributors — BSD license
+
+/// Formatting of the numeric values written by text outputs, such as
+/// `EnergyOutput` or `PropertiesOutput`.
+///
+/// Values are written with a fixed number of digits after the decimal point,
+/// either in plain or in scientific notation, and are right-aligned in columns
+/// of a minimal width. The default format uses 6 digits after the decimal
+/// point, plain notation and no padding.
+///
+/// # Examples
+///
+/// ```
+/// # use lumol_sim::output::NumberFormat;
+/// let format = NumberFormat::default();
+/// assert_eq!(format.format(3.14159265), "3.141593");
+///
+/// let format = NumberFormat {
+///     precision: 2,
+///     width: 10,
+///     scientific: true,
+/// };
+/// assert_eq!(format.format(31415.9265), "    3.14e4");
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct NumberFormat {
+    /// Number of digits after the decimal point
+    pub precision: usize,
+    /// Minimal width of the values, which are padded with spaces on the left
+    pub width: usize,
+    /// Should we use scientific notation for the values?
+    pub scientific: bool,
+}
+
+impl Default for NumberFormat {
+    fn default() -> NumberFormat {
+        NumberFormat {
+            precision: 6,
+            width: 0,
+            scientific: false,
+        }
+    }
+}
+
+impl NumberFormat {
+    /// Format a single `value` using this format
+    pub fn format(&self, value: f64) -> String {
+        if self.scientific {
+            format!("{:>width$.precision$e}", value, width = self.width, precision = self.precision)
+        } else {
+            format!("{:>width$.precision$}", value, width = self.width, precision = self.precision)
+        }
+    }
+
+    /// Format a line of output, containing the `step` and all the `values`
+    /// separated by spaces
+    pub(crate) fn line(&self, step: u64, values: &[f64]) -> String {
+        let mut line = format!("{:>width$}", step, width = self.width);
+        for &value in values {
+            line.push(' ');
+            line.push_str(&self.format(value));
+        }
+        return line;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn precision() {
+        let format = NumberFormat {
+            precision: 3,
+            width: 0,
+            scientific: false,
+        };
+        assert_eq!(format.format(1.0), "1.000");
+        assert_eq!(format.format(-2.71828), "-2.718");
+        assert_eq!(format.format(12345.6789), "12345.679");
+        assert_eq!(format.line(42, &[0.5, 1e-5]), "42 0.500 0.000");
+    }
+
+    #[test]
+    fn scientific() {
+        let format = NumberFormat {
+            precision: 3,
+            width: 0,
+            scientific: true,
+        };
+        assert_eq!(format.format(12345.6789), "1.235e4");
+        assert_eq!(format.format(-0.00025), "-2.500e-4");
+    }
+
+    #[test]
+    fn width() {
+        let format = NumberFormat {
+            precision: 1,
+            width: 8,
+            scientific: false,
+        };
+        assert_eq!(format.line(7, &[3.0, -12.5]), "       7      3.0    -12.5");
+        // Values larger than the width are not truncated
+        assert_eq!(format.format(123456789.0), "123456789.0");
+    }
+}
@@ -13,7 +13,10 @@ pub trait Output {
     /// for some setup of the output if needed.
     fn setup(&mut self, _: &System) {}
 
-    /// Write the output from the system.
+    /// Write the output from the system. The current step of the simulation
+    /// is available as `system.step`. When the output is added to a
+    /// simulation with a given frequency, this function is only called for
+    /// the steps that are multiple of the frequency.
     fn write(&mut self, system: &System);
 
     /// Function called once at the end of the simulation.
@@ -31,6 +34,9 @@ macro_rules! writeln_or_log {
     );
 }
 
+mod format;
+pub use self::format::NumberFormat;
+
 mod cell;
 pub use self::cell::CellOutput;
 
@@ -51,3 +57,12 @@ pub use self::properties::PropertiesOutput;
 
 mod trajectory;
 pub use self::trajectory::TrajectoryOutput;
+
+mod averages;
+pub use self::averages::AveragesOutput;
+
+mod drift;
+pub use self::drift::EnergyDriftOutput;
+
+mod amber;
+pub use self::amber::NetCDFOutput;
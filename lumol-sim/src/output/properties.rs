@@ -8,7 +8,7 @@ use std::path::{Path, PathBuf};
 
 use log::error;
 
-use super::Output;
+use super::{NumberFormat, Output};
 
 use lumol_core::System;
 use lumol_core::units;
@@ -22,6 +22,7 @@ use lumol_core::units;
 pub struct PropertiesOutput {
     file: BufWriter<File>,
     path: PathBuf,
+    format: NumberFormat,
 }
 
 impl PropertiesOutput {
@@ -31,8 +32,14 @@ impl PropertiesOutput {
         Ok(PropertiesOutput {
             file: BufWriter::new(File::create(filename.as_ref())?),
             path: filename.as_ref().to_owned(),
+            format: NumberFormat::default(),
         })
     }
+
+    /// Set the format used for the values in this output
+    pub fn set_format(&mut self, format: NumberFormat) {
+        self.format = format;
+    }
 }
 
 impl Output for PropertiesOutput {
@@ -45,7 +52,7 @@ impl Output for PropertiesOutput {
         let volume = units::to(system.volume(), "A^3").expect("bad unit");
         let temperature = units::to(system.temperature(), "K").expect("bad unit");
         let pressure = units::to(system.pressure(), "bar").expect("bad unit");
-        writeln_or_log!(self, "{} {} {} {}", system.step, volume, temperature, pressure);
+        writeln_or_log!(self, "{}", self.format.line(system.step, &[volume, temperature, pressure]));
     }
 }
 
@@ -60,7 +67,7 @@ mod tests {
             |path| Box::new(PropertiesOutput::new(path).unwrap()),
             "# Physical properties of the simulation
             # Step Volume/A^3 Temperature/K Pressure/bar
-            42 1000 38083.04389172312 10299.991728079816
+            42 1000.000000 38083.043892 10299.991728
             ",
         );
     }
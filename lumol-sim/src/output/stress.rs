@@ -8,15 +8,24 @@ use std::path::{Path, PathBuf};
 
 use log::error;
 
-use super::Output;
+use super::{NumberFormat, Output};
 use lumol_core::System;
 use lumol_core::units;
 
 /// The `StressOutput` writes the stress of the system to a text file, organized
 /// as: `step stress.xx stress.yy stress.zz stress.xy stress.xz stress.yz`.
+///
+/// The stress tensor is computed from the virial equation (see
+/// `System::stress`), and its trace is three times the pressure of the
+/// system. The six independent components of the tensor can be used to
+/// compute mechanical properties, such as elastic constants from the
+/// fluctuations of the off-diagonal components. For a slab with interfaces
+/// normal to the z axis, the surface tension is
+/// $\gamma = \frac{L_z}{2} \left(\sigma_{zz} - \frac{\sigma_{xx} + \sigma_{yy}}{2}\right)$.
 pub struct StressOutput {
     file: BufWriter<File>,
     path: PathBuf,
+    format: NumberFormat,
 }
 
 impl StressOutput {
@@ -26,21 +35,20 @@ impl StressOutput {
         Ok(StressOutput {
             file: BufWriter::new(File::create(filename.as_ref())?),
             path: filename.as_ref().to_owned(),
+            format: NumberFormat::default(),
         })
     }
+
+    /// Set the format used for the values in this output
+    pub fn set_format(&mut self, format: NumberFormat) {
+        self.format = format;
+    }
 }
 
 impl Output for StressOutput {
     fn setup(&mut self, _: &System) {
-        if let Err(err) = writeln!(&mut self.file, "# Stress tensor of the simulation (bar)") {
-            panic!("Could not write to file '{}': {}", self.path.display(), err);
-        }
-        if let Err(err) = writeln!(
-            &mut self.file,
-            "# step stress.xx stress.yy stress.zz stress.xy stress.xz stress.yz"
-        ) {
-            panic!("Could not write to file '{}': {}", self.path.display(), err);
-        }
+        writeln_or_log!(self, "# Stress tensor of the simulation (bar)");
+        writeln_or_log!(self, "# step stress.xx stress.yy stress.zz stress.xy stress.xz stress.yz");
     }
 
     fn write(&mut self, system: &System) {
@@ -52,23 +60,57 @@ impl Output for StressOutput {
         let xy = stress[0][1] * conversion;
         let xz = stress[0][2] * conversion;
         let yz = stress[1][2] * conversion;
-        writeln_or_log!(self, "{} {} {} {} {} {} {}", system.step, xx, yy, zz, xy, xz, yz);
+        writeln_or_log!(self, "{}", self.format.line(system.step, &[xx, yy, zz, xy, xz, yz]));
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use super::super::tests::test_output;
+    use super::super::tests::{test_output, testing_system};
+    use approx::assert_relative_eq;
+    use tempfile::NamedTempFile;
 
     #[test]
-    fn energy() {
+    fn stress() {
         test_output(
             |path| Box::new(StressOutput::new(path).unwrap()),
             "# Stress tensor of the simulation (bar)
             # step stress.xx stress.yy stress.zz stress.xy stress.xz stress.yz
-            42 30899.975184239443 0 0 0 0 0
+            42 30899.975184 0.000000 0.000000 0.000000 0.000000 0.000000
             ",
         );
     }
+
+    #[test]
+    fn trace() {
+        let mut system = testing_system();
+        system.particles_mut().velocity[0] = [0.1, -0.05, 0.02].into();
+        system.particles_mut().velocity[1] = [-0.03, 0.04, 0.01].into();
+        system.particles_mut().position[1] = [0.9, 0.7, -0.4].into();
+
+        let tempfile = NamedTempFile::new().unwrap();
+        {
+            let mut output = StressOutput::new(tempfile.path()).unwrap();
+            output.set_format(NumberFormat {
+                precision: 16,
+                width: 0,
+                scientific: true,
+            });
+            output.setup(&system);
+            output.write(&system);
+            output.finish(&system);
+        }
+
+        let mut content = String::new();
+        let _ = tempfile.reopen().unwrap().read_to_string(&mut content).unwrap();
+        let line = content.lines().find(|line| !line.starts_with('#')).unwrap();
+        let values = line.split_whitespace().map(|value| value.parse().unwrap()).collect::<Vec<f64>>();
+        assert_eq!(values.len(), 7);
+
+        let pressure = units::to(system.pressure(), "bar").unwrap();
+        assert_relative_eq!(values[1] + values[2] + values[3], 3.0 * pressure, max_relative = 1e-12);
+        // the off-diagonal components are not zero for this configuration
+        assert!(values[4].abs() > 1.0);
+    }
 }
@@ -0,0 +1,172 @@
+// Lumol, an extensible molecular simulation engine
+// Copyright (C) Lumol's contributors — BSD license
+
+//! Random packing of molecules in a system, to create initial configurations
+use std::error;
+use std::f64::consts::PI;
+use std::fmt;
+
+use rand::Rng;
+use rand_distr::{Distribution, UnitSphere};
+
+use lumol_core::{Matrix3, Molecule, System, Vector3D};
+
+use crate::velocities::seeded_rng;
+
+/// Maximal number of insertion attempts for a single molecule in
+/// `pack_molecules`.
+const MAX_TRIALS: usize = 10_000;
+
+/// Error returned by `pack_molecules` when it was not possible to insert all
+/// the requested molecules.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PackingError {
+    /// Number of molecules successfully inserted in the system
+    pub inserted: usize,
+    /// Number of molecules that were requested
+    pub requested: usize,
+}
+
+impl fmt::Display for PackingError {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        write!(
+            fmt, "could only insert {} molecules out of {} after {} trials",
+            self.inserted, self.requested, MAX_TRIALS
+        )
+    }
+}
+
+impl error::Error for PackingError {}
+
+/// Insert `count` copies of the `template` molecule in the `system`, at random
+/// positions and with random orientations.
+///
+/// A position is rejected if any particle of the new molecule is closer than
+/// `min_distance` from any particle already in the system, using periodic
+/// boundary conditions. Each molecule is tried at most 10 000 times, and a
+/// `PackingError` is returned if one of them can not be placed. The
+/// molecules inserted before the error are kept in the system.
+///
+/// # Panics
+///
+/// If the system unit cell is infinite.
+///
+/// # Examples
+///
+/// ```
+/// # use lumol_core::{Molecule, Particle, System, UnitCell};
+/// # use lumol_sim::pack_molecules;
+/// let mut system = System::with_cell(UnitCell::cubic(20.0));
+/// let helium = Molecule::new(Particle::new("He"));
+///
+/// pack_molecules(&mut system, &helium, 50, 2.0).unwrap();
+/// assert_eq!(system.size(), 50);
+/// ```
+pub fn pack_molecules(
+    system: &mut System,
+    template: &Molecule,
+    count: usize,
+    min_distance: f64,
+) -> Result<(), PackingError> {
+    assert!(!system.cell.is_infinite(), "can not pack molecules in an infinite cell");
+
+    let mut rng = seeded_rng(42);
+    let com = template.center_of_mass();
+    let centered = template.particles().position.iter().map(|&position| position - com).collect::<Vec<_>>();
+
+    let mut positions = vec![Vector3D::zero(); centered.len()];
+    for inserted in 0..count {
+        let mut success = false;
+        for _ in 0..MAX_TRIALS {
+            let fractional = Vector3D::new(rng.gen(), rng.gen(), rng.gen());
+            let center = system.cell.cartesian(&fractional);
+            let axis = Vector3D::from(UnitSphere.sample(&mut rng));
+            let rotation = Matrix3::rotation(&axis, rng.gen_range(0.0, 2.0 * PI));
+            for (position, &relative) in positions.iter_mut().zip(&centered) {
+                *position = center + rotation * relative;
+            }
+
+            if !overlaps(system, &positions, min_distance) {
+                success = true;
+                break;
+            }
+        }
+
+        if !success {
+            return Err(PackingError {
+                inserted: inserted,
+                requested: count,
+            });
+        }
+
+        let mut molecule = template.clone();
+        for (position, &new) in molecule.particles_mut().position.iter_mut().zip(&positions) {
+            *position = new;
+        }
+        system.add_molecule(molecule);
+    }
+
+    Ok(())
+}
+
+/// Check if any of the `positions` is closer than `min_distance` from a
+/// particle in the `system`.
+fn overlaps(system: &System, positions: &[Vector3D], min_distance: f64) -> bool {
+    for position in positions {
+        for other in system.particles().position {
+            if system.cell.distance(position, other) < min_distance {
+                return true;
+            }
+        }
+    }
+    return false;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lumol_core::{Particle, UnitCell};
+
+    #[test]
+    fn helium() {
+        let mut system = System::with_cell(UnitCell::cubic(15.0));
+        let helium = Molecule::new(Particle::new("He"));
+        pack_molecules(&mut system, &helium, 50, 2.5).unwrap();
+
+        assert_eq!(system.size(), 50);
+        for i in 0..system.size() {
+            for j in (i + 1)..system.size() {
+                assert!(system.distance(i, j) >= 2.5);
+            }
+        }
+    }
+
+    #[test]
+    fn molecules() {
+        let mut system = System::with_cell(UnitCell::cubic(20.0));
+        let mut water = Molecule::new(Particle::with_position("O", Vector3D::zero()));
+        water.add_particle_bonded_to(0, Particle::with_position("H", Vector3D::new(0.8, 0.6, 0.0)));
+        water.add_particle_bonded_to(0, Particle::with_position("H", Vector3D::new(-0.8, 0.6, 0.0)));
+        pack_molecules(&mut system, &water, 20, 2.0).unwrap();
+
+        assert_eq!(system.size(), 60);
+        assert_eq!(system.molecules().count(), 20);
+        for molecule in system.molecules() {
+            assert_eq!(molecule.bonds().len(), 2);
+            let positions = molecule.particles().position;
+            assert!(f64::abs((positions[1] - positions[0]).norm() - 1.0) < 1e-12);
+            assert!(f64::abs((positions[2] - positions[0]).norm() - 1.0) < 1e-12);
+        }
+    }
+
+    #[test]
+    fn too_many_molecules() {
+        let mut system = System::with_cell(UnitCell::cubic(5.0));
+        let helium = Molecule::new(Particle::new("He"));
+        let error = pack_molecules(&mut system, &helium, 50, 2.5).unwrap_err();
+
+        assert_eq!(error.requested, 50);
+        assert!(error.inserted < 50);
+        assert_eq!(system.size(), error.inserted);
+    }
+}
@@ -41,4 +41,8 @@ pub trait Propagator {
 
     /// Finish the simulation, and maybe output some information about it
     fn finish(&mut self, _: &System) {}
+
+    /// Seed all the random number generators used by this propagator with
+    /// the given `seed`. Deterministic propagators can ignore this.
+    fn seed(&mut self, _: u64) {}
 }
@@ -0,0 +1,196 @@
+// Lumol, an extensible molecular simulation engine
+// Copyright (C) Lumol's contributors — BSD license
+
+//! Replica exchange, also called parallel tempering, runs multiple copies
+//! (replicas) of a system at different temperatures, and periodically tries
+//! to exchange the configurations between replicas at adjacent temperatures.
+use log::info;
+use rand::{self, Rng, SeedableRng};
+
+use lumol_core::System;
+use lumol_core::consts::K_BOLTZMANN;
+
+use crate::Simulation;
+use crate::velocities::seeded_rng;
+
+/// Replica exchange driver.
+///
+/// Each replica is a `System` associated with a [`Simulation`] running at a
+/// given temperature. The simulations are run for a given number of steps,
+/// and then swaps between the configurations of adjacent replicas are
+/// attempted using the Metropolis criterion:
+///
+/// ```text
+/// acc(i <-> j) = min(1, exp[(β_i - β_j) (U_i - U_j)])
+/// ```
+///
+/// where `β = 1 / kT` and `U` is the potential energy of the configuration.
+/// When a swap is accepted, the systems are exchanged between the simulations
+/// and the velocities are rescaled to the new temperature. Swaps are
+/// attempted alternatively between the even (0-1, 2-3, ...) and odd (1-2,
+/// 3-4, ...) pairs of replicas.
+///
+/// [`Simulation`]: struct.Simulation.html
+pub struct ReplicaExchange {
+    /// The systems, ordered by temperature
+    systems: Vec<System>,
+    /// The simulations, ordered by temperature
+    simulations: Vec<Simulation>,
+    /// The temperatures, in ascending order
+    temperatures: Vec<f64>,
+    /// For each temperature, the index of the replica (in insertion order)
+    /// currently simulated at this temperature
+    replicas: Vec<usize>,
+    /// Number of attempted swaps between the replicas at temperatures `i`
+    /// and `i + 1`
+    attempted: Vec<u64>,
+    /// Number of accepted swaps between the replicas at temperatures `i`
+    /// and `i + 1`
+    accepted: Vec<u64>,
+    /// Number of exchange steps performed so far
+    exchanges: u64,
+    /// Random number generator for the acceptance criterion
+    rng: Box<dyn rand::RngCore>,
+}
+
+impl ReplicaExchange {
+    /// Create a new empty `ReplicaExchange` driver
+    pub fn new() -> ReplicaExchange {
+        let rng = Box::new(rand_xorshift::XorShiftRng::from_seed([
+            0xeb, 0xa8, 0xe4, 0x29, 0xca, 0x60, 0x44, 0xb0,
+            0xd3, 0x77, 0xc6, 0xa0, 0x21, 0x71, 0x37, 0xf7,
+        ]));
+        return ReplicaExchange::from_rng(rng);
+    }
+
+    /// Create a new empty `ReplicaExchange` driver, using the given `rng` in
+    /// the swaps acceptance criterion.
+    pub fn from_rng(rng: Box<dyn rand::RngCore>) -> ReplicaExchange {
+        ReplicaExchange {
+            systems: Vec::new(),
+            simulations: Vec::new(),
+            temperatures: Vec::new(),
+            replicas: Vec::new(),
+            attempted: Vec::new(),
+            accepted: Vec::new(),
+            exchanges: 0,
+            rng: rng,
+        }
+    }
+
+    /// Add a new replica, containing the `system` and running the
+    /// `simulation` at the given `temperature`. The temperature should match
+    /// the one used in the simulation propagator, and replicas must be added
+    /// in ascending temperature order.
+    pub fn add_replica(&mut self, system: System, simulation: Simulation, temperature: f64) {
+        assert!(temperature > 0.0, "The temperature must be positive in replica exchange");
+        if let Some(&last) = self.temperatures.last() {
+            assert!(
+                temperature > last,
+                "replicas must be added in ascending temperature order in replica exchange"
+            );
+            self.attempted.push(0);
+            self.accepted.push(0);
+        }
+
+        self.replicas.push(self.systems.len());
+        self.systems.push(system);
+        self.simulations.push(simulation);
+        self.temperatures.push(temperature);
+    }
+
+    /// Seed the random number generator used for the swaps, and the ones used
+    /// by all the simulations. Each simulation gets a different seed, derived
+    /// from `seed`.
+    pub fn set_seed(&mut self, seed: u64) {
+        self.rng = Box::new(seeded_rng(seed));
+        for (i, simulation) in self.simulations.iter_mut().enumerate() {
+            simulation.set_seed(seed.wrapping_add(i as u64 + 1));
+        }
+    }
+
+    /// Run all the replicas for `steps` steps, then attempt swaps between
+    /// adjacent replicas. This is repeated `exchanges` times.
+    pub fn run(&mut self, steps: usize, exchanges: usize) {
+        for _ in 0..exchanges {
+            for (system, simulation) in self.systems.iter_mut().zip(&mut self.simulations) {
+                simulation.run(system, steps);
+            }
+            self.exchange();
+        }
+
+        for (i, acceptance) in self.acceptances().iter().enumerate() {
+            info!(
+                "Replica exchange acceptance between {} K and {} K: {:.2} %",
+                self.temperatures[i], self.temperatures[i + 1], acceptance * 100.0
+            );
+        }
+    }
+
+    /// Attempt swaps between either the even or the odd pairs of adjacent
+    /// replicas, alternating at each call.
+    pub fn exchange(&mut self) {
+        let start = (self.exchanges % 2) as usize;
+        self.exchanges += 1;
+        let mut i = start;
+        while i + 1 < self.systems.len() {
+            self.attempt(i);
+            i += 2;
+        }
+    }
+
+    /// Attempt a swap between the replicas at temperatures `i` and `i + 1`
+    fn attempt(&mut self, i: usize) {
+        let j = i + 1;
+        self.attempted[i] += 1;
+
+        let beta_i = 1.0 / (K_BOLTZMANN * self.temperatures[i]);
+        let beta_j = 1.0 / (K_BOLTZMANN * self.temperatures[j]);
+        let energy_i = self.systems[i].potential_energy();
+        let energy_j = self.systems[j].potential_energy();
+        let delta = (beta_i - beta_j) * (energy_i - energy_j);
+
+        if delta >= 0.0 || self.rng.gen::<f64>() < f64::exp(delta) {
+            self.accepted[i] += 1;
+            self.systems.swap(i, j);
+            self.replicas.swap(i, j);
+
+            let factor = f64::sqrt(self.temperatures[i] / self.temperatures[j]);
+            for velocity in self.systems[i].particles_mut().velocity {
+                *velocity *= factor;
+            }
+            for velocity in self.systems[j].particles_mut().velocity {
+                *velocity /= factor;
+            }
+        }
+    }
+
+    /// Get the temperatures of the replicas, in ascending order
+    pub fn temperatures(&self) -> &[f64] {
+        &self.temperatures
+    }
+
+    /// Get the system currently simulated at the temperature with index `i`
+    pub fn system(&self, i: usize) -> &System {
+        &self.systems[i]
+    }
+
+    /// Get, for each temperature, the index of the replica currently
+    /// simulated at this temperature. Replicas are indexed in the order they
+    /// were added.
+    pub fn replicas(&self) -> &[usize] {
+        &self.replicas
+    }
+
+    /// Get the acceptance ratio of the swaps between the replicas at
+    /// temperatures `i` and `i + 1`, for all `i`.
+    pub fn acceptances(&self) -> Vec<f64> {
+        self.attempted.iter().zip(&self.accepted).map(|(&attempted, &accepted)| {
+            if attempted == 0 {
+                0.0
+            } else {
+                accepted as f64 / attempted as f64
+            }
+        }).collect()
+    }
+}
@@ -1,8 +1,12 @@
 // Lumol, an extensible molecular simulation engine
 // Copyright (C) Lumol's contributors — BSD license
-use log::{info, warn};
+use std::error::Error;
+use std::fmt;
+use std::time::{Duration, Instant};
 
-use lumol_core::{System, DegreesOfFreedom, Vector3D};
+use log::{error, info, warn};
+
+use lumol_core::{System, DegreesOfFreedom, NeighborList, Vector3D};
 
 use crate::output::Output;
 use crate::propagator::{Propagator, TemperatureStrategy};
@@ -48,12 +52,76 @@ impl Output for OutputFrequency {
     }
 }
 
+/// Value returned by the callback in
+/// [`Simulation::run_with_callback`](struct.Simulation.html#method.run_with_callback),
+/// to decide whether the simulation should continue.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Control {
+    /// Continue the simulation
+    Continue,
+    /// Stop the simulation after the current step
+    Stop,
+}
+
+/// Error returned by [`Simulation::try_run`](struct.Simulation.html#method.try_run)
+/// when a simulation diverges, *i.e.* when a particle gets a non-finite
+/// position or velocity.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DivergenceError {
+    /// Value of the system step when the divergence was detected
+    pub step: u64,
+    /// Index of the first particle with a non-finite position or velocity
+    pub particle: usize,
+}
+
+impl fmt::Display for DivergenceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f, "the simulation diverged at step {}: particle {} has a non-finite position or velocity",
+            self.step, self.particle
+        )
+    }
+}
+
+impl Error for DivergenceError {}
+
+/// Performance of a simulation run, as returned by
+/// [`Simulation::last_run_performance`](struct.Simulation.html#method.last_run_performance).
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Performance {
+    /// Number of steps performed during the run
+    pub steps: usize,
+    /// Wall clock time used by the run
+    pub elapsed: Duration,
+}
+
+impl Performance {
+    /// Get the number of steps performed per second of wall clock time, or 0
+    /// if no time elapsed.
+    pub fn steps_per_second(&self) -> f64 {
+        let seconds = self.elapsed.as_secs_f64();
+        if seconds > 0.0 {
+            self.steps as f64 / seconds
+        } else {
+            0.0
+        }
+    }
+}
+
 /// The Simulation struct holds all the needed algorithms for running the
 /// simulation. It should be use together with a `System` to perform the
 /// simulation.
 pub struct Simulation {
     propagator: Box<dyn Propagator>,
     outputs: Vec<OutputFrequency>,
+    performance: Performance,
+    divergence: Option<DivergenceError>,
+    /// Step of the system during the last run
+    step: u64,
+    /// Should we use a neighbor list for the pair interactions?
+    neighbor_list: bool,
+    /// Skin distance of the neighbor list, or `None` to use the default one
+    neighbor_list_skin: Option<f64>,
 }
 
 impl Simulation {
@@ -62,11 +130,63 @@ impl Simulation {
         Simulation {
             propagator: propagator,
             outputs: Vec::new(),
+            performance: Performance::default(),
+            divergence: None,
+            step: 0,
+            neighbor_list: false,
+            neighbor_list_skin: None,
         }
     }
 
+    /// Seed all the random number generators used by this simulation, *i.e.*
+    /// the ones used by the propagator and its components such as Monte Carlo
+    /// moves or stochastic thermostats.
+    ///
+    /// Two runs using the same seed and the same inputs are bit-identical.
+    /// Velocities initialization happens outside of the simulation, and
+    /// should be seeded separately with `InitVelocities::seed`.
+    pub fn set_seed(&mut self, seed: u64) {
+        self.propagator.seed(seed);
+    }
+
     /// Run the simulation on System for `nsteps` steps.
     pub fn run(&mut self, system: &mut System, nsteps: usize) {
+        self.run_with_callback(system, nsteps, |_, _| Control::Continue);
+    }
+
+    /// Run the simulation on System for `nsteps` steps, returning an error if
+    /// the simulation diverged. See
+    /// [`run_with_callback`](struct.Simulation.html#method.run_with_callback)
+    /// for more information.
+    pub fn try_run(&mut self, system: &mut System, nsteps: usize) -> Result<(), DivergenceError> {
+        self.run(system, nsteps);
+        match self.divergence {
+            Some(divergence) => Err(divergence),
+            None => Ok(()),
+        }
+    }
+
+    /// Run the simulation on System for up to `nsteps` steps, calling
+    /// `callback` after each step with the system and the number of steps
+    /// performed so far in this run. The simulation stops early if the
+    /// callback returns `Control::Stop`.
+    ///
+    /// This can be used to report progress, or to stop the simulation once
+    /// some convergence criteria is reached.
+    ///
+    /// The speed of the simulation and the estimated remaining time are
+    /// logged every tenth of the run.
+    ///
+    /// After each step, the positions and velocities of all the particles are
+    /// checked, and the simulation stops with an error message if any of them
+    /// is not finite. This usually comes from infinite or NaN forces, for
+    /// example with overlapping particles in the initial configuration.
+    pub fn run_with_callback(
+        &mut self,
+        system: &mut System,
+        nsteps: usize,
+        mut callback: impl FnMut(&System, usize) -> Control,
+    ) {
         match self.propagator.temperature_strategy() {
             TemperatureStrategy::External(temperature) => {
                 system.simulated_temperature(Some(temperature))
@@ -84,9 +204,26 @@ impl Simulation {
 
         system.check();
         self.setup(system);
+        let start = Instant::now();
+        let mut steps = 0;
+        self.divergence = None;
+        self.step = system.step;
         for i in 0..nsteps {
             self.propagator.propagate(system);
             system.step += 1;
+            self.step = system.step;
+            steps = i + 1;
+
+            if let Some(particle) = first_non_finite(system) {
+                let divergence = DivergenceError {
+                    step: system.step,
+                    particle: particle,
+                };
+                error!("{}", divergence);
+                self.divergence = Some(divergence);
+                break;
+            }
+
             for output in &mut self.outputs {
                 output.write(system);
             }
@@ -94,10 +231,122 @@ impl Simulation {
             if i % 10_000 == 0 {
                 self.sanity_check(system);
             }
+
+            let report = nsteps / 10;
+            if report > 0 && steps % report == 0 && steps < nsteps {
+                let performance = Performance {
+                    steps: steps,
+                    elapsed: start.elapsed(),
+                };
+                let speed = performance.steps_per_second();
+                info!(
+                    "Step {}/{}: {:.1} steps/s, estimated remaining time {:.0} s",
+                    steps, nsteps, speed, (nsteps - steps) as f64 / speed
+                );
+            }
+
+            if callback(system, steps) == Control::Stop {
+                info!("Simulation stopped by the callback after {} steps", steps);
+                break;
+            }
         }
+
+        self.performance = Performance {
+            steps: steps,
+            elapsed: start.elapsed(),
+        };
+        info!(
+            "{} steps done in {:.2} s ({:.1} steps/s)",
+            steps, self.performance.elapsed.as_secs_f64(), self.performance.steps_per_second()
+        );
         self.finish(system);
     }
 
+    /// Get the current step of the simulation, *i.e.* the step of the system
+    /// at the end of the last call to `run` or `run_with_callback`, or 0 if
+    /// the simulation did not run yet.
+    ///
+    /// The step counter is stored in the `System` and saved in checkpoints,
+    /// so a simulation restarted from a checkpoint continues the step
+    /// numbering of the initial simulation, and all the outputs use this
+    /// global step.
+    pub fn current_step(&self) -> u64 {
+        self.step
+    }
+
+    /// Get the performance of the last call to `run` or `run_with_callback`,
+    /// or an empty `Performance` if the simulation did not run yet.
+    pub fn last_run_performance(&self) -> Performance {
+        self.performance
+    }
+
+    /// Check the `system` for common setup mistakes before running this
+    /// simulation, without modifying the system.
+    ///
+    /// This checks for missing interactions, charged systems without a
+    /// suitable coulombic solver, particles without mass, unset or zero
+    /// volume unit cells, and NaN positions or velocities. All the problems
+    /// found are returned at once, as a list of human-readable messages.
+    pub fn validate(&self, system: &System) -> Result<(), Vec<String>> {
+        let mut problems = system.missing_interactions();
+
+        if system.cell.is_infinite() {
+            problems.push("the unit cell is not set".into());
+        } else if system.cell.volume() < 1e-6 {
+            problems.push("the unit cell has a zero volume".into());
+        }
+
+        let charge = system.total_charge();
+        match system.coulomb_potential() {
+            Some(coulomb) => {
+                if let Some(parameters) = coulomb.parameters() {
+                    let background = parameters.values.iter().any(|&(name, value)| {
+                        name == "background_correction" && value > 0.0
+                    });
+                    if parameters.name == "ewald" && !background && charge.abs() > 1e-6 {
+                        problems.push(format!(
+                            "the system has a net charge of {:+}, which is not supported by \
+                            Ewald summation without background correction", charge
+                        ));
+                    }
+                }
+            }
+            None => {
+                if system.particles().charge.iter().any(|charge| charge.abs() > 1e-6) {
+                    problems.push("the system is charged, but no coulombic solver is defined".into());
+                }
+            }
+        }
+
+        let sites = system.virtual_sites().iter().map(|site| site.site()).collect::<Vec<_>>();
+        let mut massless = Vec::new();
+        let particles = system.particles();
+        for (i, (name, &mass)) in particles.name.iter().zip(particles.mass).enumerate() {
+            if mass <= 0.0 && !sites.contains(&i) && !massless.contains(name) {
+                massless.push(name.clone());
+            }
+        }
+        for name in massless {
+            problems.push(format!("no mass defined for the particles named '{}'", name));
+        }
+
+        let is_nan = |vector: &Vector3D| any(vector, f64::is_nan);
+        let positions = system.particles().position.iter().filter(|&position| is_nan(position)).count();
+        if positions != 0 {
+            problems.push(format!("{} particles have NaN positions", positions));
+        }
+        let velocities = system.particles().velocity.iter().filter(|&velocity| is_nan(velocity)).count();
+        if velocities != 0 {
+            problems.push(format!("{} particles have NaN velocities", velocities));
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(problems)
+        }
+    }
+
     /// Add a new `Output` algorithm in the outputs list
     pub fn add_output(&mut self, output: Box<dyn Output>) {
         self.outputs.push(OutputFrequency::new(output));
@@ -106,11 +355,52 @@ impl Simulation {
     /// Add a new `Output` algorithm in the outputs list, which will be used
     /// at the given frequency. The output will be used every time the system
     /// step matches this frequency.
+    ///
+    /// # Panics
+    ///
+    /// If `frequency` is zero.
     pub fn add_output_with_frequency(&mut self, output: Box<dyn Output>, frequency: u64) {
+        assert!(frequency > 0, "the frequency of an output must be positive");
         self.outputs.push(OutputFrequency::with_frequency(output, frequency));
     }
 
+    /// Use a neighbor list to compute the pair interactions during this
+    /// simulation, with the default skin distance of 10% of the cutoff. The
+    /// cutoff of the list is the maximal cutoff of the pair interactions in
+    /// the system.
+    pub fn enable_neighbor_list(&mut self) {
+        self.neighbor_list = true;
+        self.neighbor_list_skin = None;
+    }
+
+    /// Use a neighbor list to compute the pair interactions during this
+    /// simulation, with the given `skin` distance. A bigger skin means that
+    /// the list is built less often, but contains more pairs.
+    ///
+    /// # Panics
+    ///
+    /// If `skin` is negative.
+    pub fn set_neighbor_list_skin(&mut self, skin: f64) {
+        assert!(skin >= 0.0, "the skin of a neighbor list must be positive or zero, got {}", skin);
+        self.neighbor_list = true;
+        self.neighbor_list_skin = Some(skin);
+    }
+
     fn setup(&mut self, system: &mut System) {
+        if self.neighbor_list {
+            let cutoff = system.pairs_cutoff().unwrap_or(0.0);
+            if cutoff > 0.0 {
+                let list = match self.neighbor_list_skin {
+                    Some(skin) => NeighborList::with_skin(cutoff, skin),
+                    None => NeighborList::new(cutoff),
+                };
+                info!("Using a neighbor list with a cutoff of {} A and a skin of {} A", list.cutoff(), list.skin());
+                system.set_neighbor_list(list);
+            } else {
+                warn!("No pair interactions with a cutoff in the system, the neighbor list will not be used");
+            }
+        }
+
         self.propagator.setup(system);
         for output in &mut self.outputs {
             output.setup(system);
@@ -154,6 +444,94 @@ impl Simulation {
     }
 }
 
+/// Get the index of the first particle in the `system` with a non-finite
+/// position or velocity, if any.
+fn first_non_finite(system: &System) -> Option<usize> {
+    let particles = system.particles();
+    particles.position.iter().zip(particles.velocity).position(|(position, velocity)| {
+        any(position, |x| !x.is_finite()) || any(velocity, |x| !x.is_finite())
+    })
+}
+
 fn any<F: Fn(f64) -> bool>(vector: &Vector3D, function: F) -> bool {
     function(vector[0]) || function(vector[1]) || function(vector[2])
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MolecularDynamics;
+    use lumol_core::{LennardJones, Molecule, NullPotential, PairInteraction, Particle, UnitCell};
+
+    fn helium() -> System {
+        let mut system = System::with_cell(UnitCell::cubic(10.0));
+        system.add_molecule(Molecule::new(Particle::with_position("He", Vector3D::new(0.0, 0.0, 0.0))));
+        system.add_molecule(Molecule::new(Particle::with_position("He", Vector3D::new(3.0, 0.0, 0.0))));
+        return system;
+    }
+
+    #[test]
+    fn performance() {
+        let mut system = helium();
+        let mut simulation = Simulation::new(Box::new(MolecularDynamics::new(1.0)));
+        assert_eq!(simulation.last_run_performance(), Performance::default());
+        assert_eq!(simulation.last_run_performance().steps_per_second(), 0.0);
+
+        simulation.run(&mut system, 50);
+        let performance = simulation.last_run_performance();
+        assert_eq!(performance.steps, 50);
+        assert!(performance.elapsed > Duration::from_secs(0));
+        assert!(performance.steps_per_second() > 0.0);
+
+        simulation.run_with_callback(&mut system, 50, |_, steps| {
+            if steps == 3 { Control::Stop } else { Control::Continue }
+        });
+        assert_eq!(simulation.last_run_performance().steps, 3);
+    }
+
+    #[test]
+    fn divergence() {
+        let mut system = helium();
+        system.particles_mut().position[1] = Vector3D::new(0.0, 0.0, 0.0);
+        system.set_pair_potential(("He", "He"), PairInteraction::new(Box::new(LennardJones {
+            sigma: 2.0,
+            epsilon: 1.0,
+        }), 5.0));
+
+        let mut simulation = Simulation::new(Box::new(MolecularDynamics::new(1.0)));
+        let error = simulation.try_run(&mut system, 10).unwrap_err();
+        assert_eq!(error, DivergenceError { step: 1, particle: 0 });
+        assert_eq!(simulation.last_run_performance().steps, 1);
+
+        let mut system = helium();
+        system.set_pair_potential(("He", "He"), PairInteraction::new(Box::new(NullPotential), 5.0));
+        assert!(simulation.try_run(&mut system, 10).is_ok());
+    }
+
+    #[test]
+    fn validate() {
+        let simulation = Simulation::new(Box::new(MolecularDynamics::new(1.0)));
+
+        let mut system = System::new();
+        system.add_molecule(Molecule::new(Particle::new("He")));
+        system.add_molecule(Molecule::new(Particle::new("He")));
+        let problems = simulation.validate(&system).unwrap_err();
+        assert_eq!(problems, [
+            "no potential defined for the pair (\"He\", \"He\")",
+            "the unit cell is not set",
+        ]);
+
+        let mut system = helium();
+        system.set_pair_potential(("He", "He"), PairInteraction::new(Box::new(NullPotential), 5.0));
+        assert!(simulation.validate(&system).is_ok());
+
+        system.add_molecule(Molecule::new(Particle::new("X")));
+        system.particles_mut().velocity[0] = Vector3D::new(f64::NAN, 0.0, 0.0);
+        let problems = simulation.validate(&system).unwrap_err();
+        assert_eq!(problems, [
+            "no potential defined for the pair (\"He\", \"X\")",
+            "no mass defined for the particles named 'X'",
+            "1 particles have NaN velocities",
+        ]);
+    }
+}
@@ -11,6 +11,24 @@ use lumol_core::{System, Vector3D};
 
 use crate::md::{Control, RemoveRotation, RemoveTranslation};
 
+/// Create a new random number generator from the given `seed`. This is used
+/// by all the stochastic algorithms when seeding them, so that two runs with
+/// the same seed are identical.
+pub(crate) fn seeded_rng(seed: u64) -> XorShiftRng {
+    let b1 = ((seed >> 56) & 0xff) as u8;
+    let b2 = ((seed >> 48) & 0xff) as u8;
+    let b3 = ((seed >> 40) & 0xff) as u8;
+    let b4 = ((seed >> 32) & 0xff) as u8;
+    let b5 = ((seed >> 24) & 0xff) as u8;
+    let b6 = ((seed >> 16) & 0xff) as u8;
+    let b7 = ((seed >> 8) & 0xff) as u8;
+    let b8 = (seed & 0xff) as u8;
+    let seed = [
+        b1, 0xa8, b2, 0x29, b3, 0x60, b4, 0xb0, b5, 0x77, b6, 0xa0, b7, 0x71, b8, 0xf7,
+    ];
+    return XorShiftRng::from_seed(seed);
+}
+
 /// Scale all velocities in the `System` such that the `system` temperature
 /// is `temperature`.
 pub fn scale(system: &mut System, temperature: f64) {
@@ -55,6 +73,11 @@ impl BoltzmannVelocities {
 impl InitVelocities for BoltzmannVelocities {
     fn init(&mut self, system: &mut System) {
         for particle in system.particles_mut() {
+            if *particle.mass == 0.0 {
+                // massless particles (virtual sites) do not move by themselves
+                *particle.velocity = Vector3D::zero();
+                continue;
+            }
             let m_inv = 1.0 / (*particle.mass);
             let x = f64::sqrt(m_inv) * self.dist.sample(&mut self.rng);
             let y = f64::sqrt(m_inv) * self.dist.sample(&mut self.rng);
@@ -67,18 +90,7 @@ impl InitVelocities for BoltzmannVelocities {
     }
 
     fn seed(&mut self, seed: u64) {
-        let b1 = ((seed >> 56) & 0xff) as u8;
-        let b2 = ((seed >> 48) & 0xff) as u8;
-        let b3 = ((seed >> 40) & 0xff) as u8;
-        let b4 = ((seed >> 32) & 0xff) as u8;
-        let b5 = ((seed >> 24) & 0xff) as u8;
-        let b6 = ((seed >> 16) & 0xff) as u8;
-        let b7 = ((seed >> 8) & 0xff) as u8;
-        let b8 = (seed & 0xff) as u8;
-        let seed = [
-            b1, 0xa8, b2, 0x29, b3, 0x60, b4, 0xb0, b5, 0x77, b6, 0xa0, b7, 0x71, b8, 0xf7,
-        ];
-        self.rng = XorShiftRng::from_seed(seed);
+        self.rng = seeded_rng(seed);
     }
 }
 
@@ -107,6 +119,11 @@ impl UniformVelocities {
 impl InitVelocities for UniformVelocities {
     fn init(&mut self, system: &mut System) {
         for particle in system.particles_mut() {
+            if *particle.mass == 0.0 {
+                // massless particles (virtual sites) do not move by themselves
+                *particle.velocity = Vector3D::zero();
+                continue;
+            }
             let m_inv = 1.0 / (*particle.mass);
             *particle.velocity = f64::sqrt(m_inv) * Vector3D::new(
                 self.dist.sample(&mut self.rng),
@@ -120,18 +137,7 @@ impl InitVelocities for UniformVelocities {
     }
 
     fn seed(&mut self, seed: u64) {
-        let b1 = ((seed >> 56) & 0xff) as u8;
-        let b2 = ((seed >> 48) & 0xff) as u8;
-        let b3 = ((seed >> 40) & 0xff) as u8;
-        let b4 = ((seed >> 32) & 0xff) as u8;
-        let b5 = ((seed >> 24) & 0xff) as u8;
-        let b6 = ((seed >> 16) & 0xff) as u8;
-        let b7 = ((seed >> 8) & 0xff) as u8;
-        let b8 = (seed & 0xff) as u8;
-        let seed = [
-            b1, 0xa8, b2, 0x29, b3, 0x60, b4, 0xb0, b5, 0x77, b6, 0xa0, b7, 0x71, b8, 0xf7,
-        ];
-        self.rng = XorShiftRng::from_seed(seed);
+        self.rng = seeded_rng(seed);
     }
 }
 
@@ -0,0 +1,102 @@
+// Lumol, an extensible molecular simulation engine
+// Copyright (C) Lumol's contributors — BSD license
+
+use lumol_core::{Particle, Molecule, System, UnitCell};
+use lumol_core::{LennardJones, PairInteraction};
+use lumol_core::units;
+
+use lumol_sim::{BoltzmannVelocities, InitVelocities};
+use lumol_sim::{MolecularDynamics, Simulation};
+use lumol_sim::output::Output;
+
+use tempfile::NamedTempFile;
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+fn set_interactions(system: &mut System) {
+    let lj = Box::new(LennardJones {
+        sigma: units::from(3.4, "A").unwrap(),
+        epsilon: units::from(1.0, "kJ/mol").unwrap(),
+    });
+    system.set_pair_potential(("Ar", "Ar"), PairInteraction::new(lj, 6.0));
+}
+
+fn testing_system() -> System {
+    let mut system = System::with_cell(UnitCell::cubic(14.0));
+    for i in 0..3 {
+        for j in 0..3 {
+            for k in 0..3 {
+                let position = [i as f64 * 4.5, j as f64 * 4.5, k as f64 * 4.5];
+                system.add_molecule(Molecule::new(Particle::with_position("Ar", position.into())));
+            }
+        }
+    }
+    set_interactions(&mut system);
+
+    let mut velocities = BoltzmannVelocities::new(300.0);
+    velocities.init(&mut system);
+    return system;
+}
+
+fn simulation() -> Simulation {
+    let timestep = units::from(1.0, "fs").unwrap();
+    return Simulation::new(Box::new(MolecularDynamics::new(timestep)));
+}
+
+#[test]
+fn restart() {
+    let mut continuous = testing_system();
+    simulation().run(&mut continuous, 200);
+
+    let mut first = testing_system();
+    simulation().run(&mut first, 100);
+    let checkpoint = NamedTempFile::new().unwrap();
+    first.save_checkpoint(checkpoint.path()).unwrap();
+
+    let mut second = System::new();
+    set_interactions(&mut second);
+    second.load_checkpoint(checkpoint.path()).unwrap();
+    assert_eq!(second.step, 100);
+    simulation().run(&mut second, 100);
+
+    assert_eq!(second.step, continuous.step);
+    let (expected, actual) = (continuous.particles(), second.particles());
+    for i in 0..continuous.size() {
+        assert!((expected.position[i] - actual.position[i]).norm() < 1e-9);
+        assert!((expected.velocity[i] - actual.velocity[i]).norm() < 1e-12);
+    }
+}
+
+/// Output recording the steps at which it was called
+#[derive(Clone, Default)]
+struct Steps(Rc<RefCell<Vec<u64>>>);
+
+impl Output for Steps {
+    fn write(&mut self, system: &System) {
+        self.0.borrow_mut().push(system.step);
+    }
+}
+
+#[test]
+fn restart_step() {
+    let mut first = testing_system();
+    let mut initial = simulation();
+    assert_eq!(initial.current_step(), 0);
+    initial.run(&mut first, 500);
+    assert_eq!(initial.current_step(), 500);
+    let checkpoint = NamedTempFile::new().unwrap();
+    first.save_checkpoint(checkpoint.path()).unwrap();
+
+    let mut second = System::new();
+    set_interactions(&mut second);
+    second.load_checkpoint(checkpoint.path()).unwrap();
+
+    let steps = Steps::default();
+    let mut restarted = simulation();
+    restarted.add_output(Box::new(steps.clone()));
+    restarted.run(&mut second, 100);
+
+    assert_eq!(restarted.current_step(), 600);
+    assert_eq!(*steps.0.borrow(), (501..=600).collect::<Vec<_>>());
+}
@@ -0,0 +1,67 @@
+// Lumol, an extensible molecular simulation engine
+// Copyright (C) Lumol's contributors — BSD license
+
+use lumol_core::{DrudeParticle, Molecule, Particle, System, UnitCell, Vector3D};
+use lumol_core::units;
+
+use lumol_sim::md::{Integrator, VelocityVerlet};
+use lumol_sim::md::{DrudeThermostat, Thermostat};
+
+use approx::assert_relative_eq;
+
+/// A single polarizable atom, with a light Drude particle on top of the core
+fn polarizable_atom() -> System {
+    let mut system = System::with_cell(UnitCell::cubic(20.0));
+    let position = Vector3D::new(10.0, 10.0, 10.0);
+    let mut molecule = Molecule::new(Particle::with_position("Ar", position));
+    molecule.add_particle_bonded_to(0, Particle::with_position("D", position));
+    system.add_molecule(molecule);
+
+    system.particles_mut().charge[0] = 2.0;
+    system.particles_mut().charge[1] = -2.0;
+    system.particles_mut().mass[0] = 39.548;
+    system.particles_mut().mass[1] = 0.4;
+
+    let k = units::from(1000.0, "kJ/mol/A^2").unwrap();
+    system.add_drude_particle(DrudeParticle::new(1, 0, k));
+    return system;
+}
+
+#[test]
+fn induced_dipole_md() {
+    let mut system = polarizable_atom();
+    let field = Vector3D::new(units::from(0.5, "eV/A").unwrap(), 0.0, 0.0);
+    system.set_electric_field(field);
+
+    let drude = system.drude_particles()[0];
+    let expected = drude.polarizability(&system) * field;
+
+    let mut integrator = VelocityVerlet::new(units::from(0.5, "fs").unwrap());
+    let mut thermostat = DrudeThermostat::new(300.0, 1.0, 10.0);
+    integrator.setup(&system);
+    thermostat.setup(&system);
+
+    // Let the Drude oscillations be damped by the thermostat
+    for _ in 0..2000 {
+        integrator.integrate(&mut system);
+        thermostat.apply(&mut system);
+    }
+
+    let mut dipole = Vector3D::zero();
+    for _ in 0..1000 {
+        integrator.integrate(&mut system);
+        thermostat.apply(&mut system);
+        dipole += drude.dipole(&system) / 1000.0;
+    }
+
+    assert_relative_eq!(dipole[0], expected[0], max_relative = 0.05);
+    assert!(dipole[1].abs() < 0.05 * expected[0]);
+    assert!(dipole[2].abs() < 0.05 * expected[0]);
+
+    // The Drude particle stays close to the equilibrium displacement relative
+    // to the core. The absolute positions are not checked, since the whole
+    // atom can drift under the thermostat.
+    let equilibrium = expected / system.particles().charge[1];
+    let displacement = drude.displacement(&system);
+    assert!((displacement - equilibrium).norm() < 0.2 * equilibrium.norm());
+}
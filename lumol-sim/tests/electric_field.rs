@@ -0,0 +1,39 @@
+// Lumol, an extensible molecular simulation engine
+// Copyright (C) Lumol's contributors — BSD license
+
+use lumol_core::{Molecule, Particle, System, UnitCell, Vector3D};
+use lumol_core::units;
+
+use lumol_sim::{MolecularDynamics, Simulation};
+
+#[test]
+fn opposite_charges() {
+    let mut system = System::with_cell(UnitCell::cubic(30.0));
+    let mut sodium = Particle::with_position("Na", Vector3D::new(5.0, 5.0, 5.0));
+    sodium.charge = 1.0;
+    system.add_molecule(Molecule::new(sodium));
+
+    let mut chloride = Particle::with_position("Cl", Vector3D::new(15.0, 15.0, 15.0));
+    chloride.charge = -1.0;
+    system.add_molecule(Molecule::new(chloride));
+
+    system.add_molecule(Molecule::new(Particle::with_position("Ar", Vector3D::new(25.0, 5.0, 5.0))));
+
+    system.set_electric_field(Vector3D::new(units::from(0.1, "eV/A").unwrap(), 0.0, 0.0));
+
+    let mut simulation = Simulation::new(Box::new(MolecularDynamics::new(units::from(1.0, "fs").unwrap())));
+    simulation.run(&mut system, 100);
+
+    let velocities = system.particles().velocity;
+    assert!(velocities[0][0] > 0.0);
+    assert!(velocities[1][0] < 0.0);
+    assert_eq!(velocities[2], Vector3D::zero());
+
+    // Only the x component of the velocity changes
+    assert_eq!(velocities[0][1], 0.0);
+    assert_eq!(velocities[1][2], 0.0);
+
+    let positions = system.particles().position;
+    assert!(positions[0][0] > 5.0);
+    assert!(positions[1][0] < 15.0);
+}
@@ -0,0 +1,288 @@
+// Lumol, an extensible molecular simulation engine
+// Copyright (C) Lumol's contributors — BSD license
+
+use lumol_core::{Configuration, DegreesOfFreedom, Matrix3, Molecule, Particle, System, UnitCell, Vector3D};
+use lumol_core::energy::{ExternalPotential, Harmonic, LennardJones, PairInteraction, PairRestriction};
+use lumol_core::units;
+
+use lumol_sim::{BoltzmannVelocities, InitVelocities, Propagator};
+use lumol_sim::md::{AnisoBerendsenBarostat, CellConstraint, Integrator, MolecularDynamics, Respa, VelocityVerlet};
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Two argon atoms going towards one another, and colliding after ~60 fs
+fn collision() -> System {
+    let mut system = System::with_cell(UnitCell::cubic(40.0));
+    let mut first = Particle::with_position("Ar", Vector3D::new(0.0, 0.0, 0.0));
+    first.velocity = Vector3D::new(0.05, 0.0, 0.0);
+    system.add_molecule(Molecule::new(first));
+
+    let mut second = Particle::with_position("Ar", Vector3D::new(10.0, 0.0, 0.0));
+    second.velocity = Vector3D::new(-0.05, 0.0, 0.0);
+    system.add_molecule(Molecule::new(second));
+
+    let lj = LennardJones {
+        sigma: units::from(3.4, "A").unwrap(),
+        epsilon: units::from(1.0, "kJ/mol").unwrap(),
+    };
+    system.set_pair_potential(("Ar", "Ar"), PairInteraction::new(Box::new(lj), 12.0));
+    return system;
+}
+
+/// Run the `md` simulation for `duration`, and return the maximal deviation
+/// of the total energy from its initial value, and the smallest timestep
+/// used.
+fn run(md: &mut MolecularDynamics, system: &mut System, duration: f64) -> (f64, f64) {
+    md.setup(system);
+    let initial = system.total_energy();
+    let mut max_deviation = 0.0;
+    let mut min_timestep = md.timestep().unwrap();
+
+    let mut time = 0.0;
+    while time < duration {
+        time += md.timestep().unwrap();
+        min_timestep = f64::min(min_timestep, md.timestep().unwrap());
+        md.propagate(system);
+        max_deviation = f64::max(max_deviation, f64::abs(system.total_energy() - initial));
+    }
+    md.finish(system);
+    return (max_deviation, min_timestep);
+}
+
+#[test]
+fn adaptive_timestep() {
+    let mut system = collision();
+    let mut md = MolecularDynamics::new(5.0);
+    let (fixed_deviation, _) = run(&mut md, &mut system, 400.0);
+
+    let mut system = collision();
+    let mut md = MolecularDynamics::new(5.0);
+    md.set_adaptive_timestep(0.01, 5.0, 1e-4);
+    let (adaptive_deviation, min_timestep) = run(&mut md, &mut system, 400.0);
+
+    // The timestep is reduced during the collision, and goes back to the
+    // maximal value afterward
+    assert!(min_timestep < 0.2);
+    assert_eq!(md.timestep(), Some(5.0));
+    // The collision happened
+    assert!(system.particles().velocity[0][0] < 0.0);
+
+    assert!(adaptive_deviation < 0.05 * fixed_deviation);
+}
+
+#[test]
+fn set_timestep() {
+    let mut integrator = VelocityVerlet::new(1.0);
+    assert_eq!(integrator.timestep(), Some(1.0));
+    integrator.set_timestep(2.0).unwrap();
+    assert_eq!(integrator.timestep(), Some(2.0));
+    assert!(integrator.set_timestep(0.0).is_err());
+    assert_eq!(integrator.timestep(), Some(2.0));
+}
+
+/// Integrator without a timestep, which does not move the particles
+struct NoTimestep;
+
+impl Integrator for NoTimestep {
+    fn integrate(&mut self, _: &mut System) {}
+}
+
+#[test]
+#[should_panic(expected = "the integrator does not support changing the timestep")]
+fn adaptive_timestep_unsupported_integrator() {
+    assert!(NoTimestep.set_timestep(1.0).is_err());
+    let mut md = MolecularDynamics::from_integrator(Box::new(NoTimestep));
+    assert_eq!(md.timestep(), None);
+    md.set_adaptive_timestep(0.01, 5.0, 1e-4);
+}
+
+/// A gas of nitrogen molecules, with stiff bonds
+fn nitrogen() -> System {
+    let mut system = System::with_cell(UnitCell::cubic(16.0));
+    for i in 0..4 {
+        for j in 0..4 {
+            let position = Vector3D::new(4.0 * i as f64, 4.0 * j as f64, 0.0);
+            let mut molecule = Molecule::new(Particle::with_position("N", position));
+            let position = position + Vector3D::new(0.0, 0.0, 1.1);
+            molecule.add_particle_bonded_to(0, Particle::with_position("N", position));
+            system.add_molecule(molecule);
+        }
+    }
+
+    let lj = LennardJones {
+        sigma: units::from(3.3, "A").unwrap(),
+        epsilon: units::from(0.3, "kJ/mol").unwrap(),
+    };
+    let mut pairs = PairInteraction::shifted(Box::new(lj), 7.5);
+    pairs.set_restriction(PairRestriction::InterMolecular);
+    system.set_pair_potential(("N", "N"), pairs);
+    system.set_bond_potential(("N", "N"), Box::new(Harmonic {
+        k: units::from(3000.0, "kJ/mol/A^2").unwrap(),
+        x0: units::from(1.1, "A").unwrap(),
+    }));
+
+    let mut velocities = BoltzmannVelocities::new(units::from(300.0, "K").unwrap());
+    velocities.init(&mut system);
+    return system;
+}
+
+/// Run `nsteps` steps of the `integrator`, and return the maximal deviation
+/// of the total energy from its initial value.
+fn energy_deviation(integrator: &mut dyn Integrator, system: &mut System, nsteps: usize) -> f64 {
+    integrator.setup(system);
+    let initial = system.total_energy();
+    let mut max_deviation = 0.0;
+    for _ in 0..nsteps {
+        integrator.integrate(system);
+        max_deviation = f64::max(max_deviation, f64::abs(system.total_energy() - initial));
+    }
+    return max_deviation;
+}
+
+#[test]
+fn respa_energy_conservation() {
+    let mut system = nitrogen();
+    let kinetic = system.kinetic_energy();
+
+    let mut respa = Respa::new(2.0, 8);
+    assert_eq!(respa.inner_steps(), 8);
+    assert_eq!(respa.inner_timestep(), 0.25);
+    let respa_deviation = energy_deviation(&mut respa, &mut system, 500);
+
+    let mut system = nitrogen();
+    let mut verlet = VelocityVerlet::new(2.0);
+    let verlet_deviation = energy_deviation(&mut verlet, &mut system, 500);
+
+    let mut system = nitrogen();
+    let mut small_verlet = VelocityVerlet::new(0.25);
+    let small_verlet_deviation = energy_deviation(&mut small_verlet, &mut system, 4000);
+
+    // RESPA conserves the energy as well as a velocity-Verlet integrator
+    // using the inner timestep, and much better than velocity-Verlet with the
+    // outer timestep.
+    assert!(respa_deviation < 1e-2 * kinetic);
+    assert!(respa_deviation < 2.0 * small_verlet_deviation);
+    assert!(respa_deviation < 0.1 * verlet_deviation);
+}
+
+/// External potential counting how many times the forces were computed
+#[derive(Clone)]
+struct CountForces(Arc<AtomicUsize>);
+
+impl ExternalPotential for CountForces {
+    fn energy(&self, _: &Configuration, _: usize, _: &Vector3D) -> f64 {
+        0.0
+    }
+
+    fn force(&self, _: &Configuration, _: usize, _: &Vector3D) -> Vector3D {
+        let _ = self.0.fetch_add(1, Ordering::SeqCst);
+        Vector3D::zero()
+    }
+}
+
+#[test]
+fn respa_slow_forces() {
+    let mut system = nitrogen();
+    let count = Arc::new(AtomicUsize::new(0));
+    system.add_external_potential(Box::new(CountForces(count.clone())));
+
+    let mut respa = Respa::new(2.0, 4);
+    respa.setup(&system);
+    assert_eq!(count.load(Ordering::SeqCst), system.size());
+
+    for _ in 0..10 {
+        respa.integrate(&mut system);
+    }
+    // The slow forces are computed once for every outer step
+    assert_eq!(count.load(Ordering::SeqCst), 11 * system.size());
+}
+
+/// Argon atoms on a cubic lattice, in an orthorhombic cell elongated along z
+fn argon_slab() -> System {
+    let mut system = System::with_cell(UnitCell::ortho(15.2, 15.2, 17.0));
+    for i in 0..4 {
+        for j in 0..4 {
+            for k in 0..4 {
+                let position = Vector3D::new(i as f64 * 3.8, j as f64 * 3.8, k as f64 * 3.8);
+                system.add_molecule(Molecule::new(Particle::with_position("Ar", position)));
+            }
+        }
+    }
+
+    let lj = LennardJones {
+        sigma: units::from(3.4, "A").unwrap(),
+        epsilon: units::from(1.0, "kJ/mol").unwrap(),
+    };
+    system.set_pair_potential(("Ar", "Ar"), PairInteraction::new(Box::new(lj), 6.0));
+
+    let mut velocities = BoltzmannVelocities::new(units::from(100.0, "K").unwrap());
+    velocities.seed(42);
+    velocities.init(&mut system);
+    return system;
+}
+
+/// Run `nsteps` steps of an anisotropic Berendsen barostat with the given
+/// `constraint`, and return the cell lengths at each step
+fn barostat_lengths(constraint: CellConstraint, nsteps: usize) -> Vec<Vector3D> {
+    let mut system = argon_slab();
+    let stress = Matrix3::new([
+        [units::from(1000.0, "bar").unwrap(), 0.0, 0.0],
+        [0.0, units::from(1000.0, "bar").unwrap(), 0.0],
+        [0.0, 0.0, units::from(3000.0, "bar").unwrap()],
+    ]);
+    let timestep = units::from(2.0, "fs").unwrap();
+    let mut barostat = AnisoBerendsenBarostat::new(timestep, stress, 1000.0);
+    barostat.set_constraint(constraint);
+
+    barostat.setup(&system);
+    let mut lengths = Vec::new();
+    for _ in 0..nsteps {
+        barostat.integrate(&mut system);
+        lengths.push(system.cell.lengths());
+    }
+    return lengths;
+}
+
+#[test]
+fn semi_isotropic_barostat() {
+    let lengths = barostat_lengths(CellConstraint::SemiIsotropic, 200);
+    for length in &lengths {
+        assert_eq!(length[0], length[1]);
+    }
+
+    // The cell changed, and the z length is not scaled with x and y
+    let last = lengths[lengths.len() - 1];
+    let (x, z) = (last[0], last[2]);
+    assert!(f64::abs(x - 15.2) > 1e-6);
+    assert!(f64::abs(z / x - 17.0 / 15.2) > 1e-6);
+
+    // With an isotropic constraint, the cell shape is conserved
+    let lengths = barostat_lengths(CellConstraint::Isotropic, 200);
+    let last = lengths[lengths.len() - 1];
+    let (x, z) = (last[0], last[2]);
+    assert!(f64::abs(x - 15.2) > 1e-6);
+    assert_eq!(x, last[1]);
+    assert!(f64::abs(z / x - 17.0 / 15.2) < 1e-12);
+}
+
+#[test]
+fn frozen_particles() {
+    let mut system = collision();
+    system.freeze(&[0]);
+    assert_eq!(system.particles().velocity[0], Vector3D::zero());
+
+    let mut md = MolecularDynamics::new(1.0);
+    assert_eq!(md.degrees_of_freedom(&system), DegreesOfFreedom::Frozen(3));
+    md.setup(&system);
+    for _ in 0..200 {
+        md.propagate(&mut system);
+    }
+    md.finish(&system);
+
+    // The frozen atom did not move, but it still repelled the mobile one
+    assert_eq!(system.particles().position[0], Vector3D::zero());
+    assert_eq!(system.particles().velocity[0], Vector3D::zero());
+    assert!(system.particles().velocity[1][0] > 0.0);
+    assert!(system.particles().position[1][0] > 4.0);
+}
@@ -0,0 +1,95 @@
+// Lumol, an extensible molecular simulation engine
+// Copyright (C) Lumol's contributors — BSD license
+
+use lumol_core::{Particle, Molecule, System, UnitCell};
+use lumol_core::{LennardJones, PairInteraction};
+use lumol_core::units;
+
+use lumol_sim::{BoltzmannVelocities, InitVelocities};
+use lumol_sim::{Control, MolecularDynamics, Simulation};
+
+use approx::assert_relative_eq;
+
+fn argon() -> System {
+    let mut system = System::with_cell(UnitCell::cubic(27.0));
+    for i in 0..6 {
+        for j in 0..6 {
+            for k in 0..6 {
+                let position = [i as f64 * 4.5, j as f64 * 4.5, k as f64 * 4.5];
+                system.add_molecule(Molecule::new(Particle::with_position("Ar", position.into())));
+            }
+        }
+    }
+
+    let lj = Box::new(LennardJones {
+        sigma: units::from(3.4, "A").unwrap(),
+        epsilon: units::from(1.0, "kJ/mol").unwrap(),
+    });
+    system.set_pair_potential(("Ar", "Ar"), PairInteraction::new(lj, 8.0));
+
+    let mut velocities = BoltzmannVelocities::new(300.0);
+    velocities.seed(42);
+    velocities.init(&mut system);
+    return system;
+}
+
+fn simulation() -> Simulation {
+    let timestep = units::from(1.0, "fs").unwrap();
+    return Simulation::new(Box::new(MolecularDynamics::new(timestep)));
+}
+
+/// Run a simulation for `nsteps` steps, and get the potential energy after
+/// each step
+fn energies(simulation: &mut Simulation, system: &mut System, nsteps: usize) -> Vec<f64> {
+    let mut energies = Vec::new();
+    simulation.run_with_callback(system, nsteps, |system, _| {
+        energies.push(system.potential_energy());
+        Control::Continue
+    });
+    return energies;
+}
+
+#[test]
+fn large_skin() {
+    let mut reference = argon();
+    let mut system = reference.clone();
+    let expected = energies(&mut simulation(), &mut reference, 200);
+    assert!(reference.neighbor_list().is_none());
+
+    let mut simulation = simulation();
+    simulation.set_neighbor_list_skin(2.0);
+    let actual = energies(&mut simulation, &mut system, 200);
+
+    let list = system.neighbor_list().unwrap();
+    assert_eq!(list.cutoff(), 8.0);
+    assert_eq!(list.skin(), 2.0);
+    assert!(list.builds() < 20);
+
+    for (actual, expected) in actual.iter().zip(&expected) {
+        assert_relative_eq!(actual, expected, max_relative = 1e-12);
+    }
+}
+
+#[test]
+fn zero_skin() {
+    let mut system = argon();
+    let mut simulation = simulation();
+    simulation.set_neighbor_list_skin(0.0);
+    let _ = energies(&mut simulation, &mut system, 50);
+
+    // Once during the setup, and then once per step
+    let list = system.neighbor_list().unwrap();
+    assert_eq!(list.builds(), 51);
+}
+
+#[test]
+fn default_skin() {
+    let mut system = argon();
+    let mut simulation = simulation();
+    simulation.enable_neighbor_list();
+    simulation.run(&mut system, 10);
+
+    let list = system.neighbor_list().unwrap();
+    assert_eq!(list.cutoff(), 8.0);
+    assert_relative_eq!(list.skin(), 0.8);
+}
@@ -0,0 +1,101 @@
+// Lumol, an extensible molecular simulation engine
+// Copyright (C) Lumol's contributors — BSD license
+
+use lumol_core::{Vector3D, Particle, Molecule, System, UnitCell};
+use lumol_core::{LennardJones, PairInteraction};
+use lumol_core::units;
+
+use lumol_sim::{ReplicaExchange, Simulation};
+use lumol_sim::mc::{MonteCarloBuilder, Translate};
+
+use approx::assert_ulps_eq;
+
+fn argon(positions: &[[f64; 3]]) -> System {
+    let mut system = System::with_cell(UnitCell::cubic(12.0));
+    for &position in positions {
+        system.add_molecule(Molecule::new(Particle::with_position("Ar", position.into())));
+    }
+
+    let lj = Box::new(LennardJones {
+        sigma: units::from(3.4, "A").unwrap(),
+        epsilon: units::from(1.0, "kJ/mol").unwrap(),
+    });
+    system.set_pair_potential(("Ar", "Ar"), PairInteraction::new(lj, 5.5));
+    return system;
+}
+
+fn monte_carlo(temperature: f64) -> Simulation {
+    let mut mc = MonteCarloBuilder::new(temperature);
+    mc.add(Box::new(Translate::new(1.0, None)), 1.0, None);
+    return Simulation::new(Box::new(mc.finish()));
+}
+
+#[test]
+fn ideal_gas() {
+    // Without interactions, all the swaps are accepted
+    let mut exchange = ReplicaExchange::new();
+    for &temperature in &[100.0, 200.0, 300.0] {
+        let mut system = System::with_cell(UnitCell::cubic(10.0));
+        let mut particle = Particle::new("He");
+        particle.velocity = Vector3D::new(1.0, 0.0, 0.0);
+        system.add_molecule(Molecule::new(particle));
+        exchange.add_replica(system, monte_carlo(temperature), temperature);
+    }
+
+    exchange.exchange();
+    assert_eq!(exchange.replicas(), &[1, 0, 2]);
+    assert_ulps_eq!(exchange.system(0).particles().velocity[0][0], f64::sqrt(0.5));
+    assert_ulps_eq!(exchange.system(1).particles().velocity[0][0], f64::sqrt(2.0));
+
+    exchange.exchange();
+    assert_eq!(exchange.replicas(), &[1, 2, 0]);
+    assert_eq!(exchange.acceptances(), vec![1.0, 1.0]);
+}
+
+#[test]
+#[should_panic(expected = "replicas must be added in ascending temperature order in replica exchange")]
+fn unsorted_temperatures() {
+    let mut exchange = ReplicaExchange::new();
+    exchange.add_replica(System::new(), monte_carlo(200.0), 200.0);
+    exchange.add_replica(System::new(), monte_carlo(100.0), 100.0);
+}
+
+#[test]
+fn visited_temperatures() {
+    let temperatures = [80.0, 100.0, 125.0];
+    let positions = [
+        [0.0, 0.0, 0.0], [4.0, 0.0, 0.0], [0.0, 4.0, 0.0], [0.0, 0.0, 4.0],
+        [4.0, 4.0, 0.0], [4.0, 0.0, 4.0], [0.0, 4.0, 4.0], [4.0, 4.0, 4.0],
+    ];
+
+    let mut exchange = ReplicaExchange::new();
+    for &temperature in &temperatures {
+        exchange.add_replica(argon(&positions), monte_carlo(temperature), temperature);
+    }
+
+    // equilibrate
+    exchange.run(20, 200);
+
+    // Count the number of times each replica is at each temperature
+    let mut visits = [[0.0; 3]; 3];
+    let attempts = 3000;
+    for _ in 0..attempts {
+        exchange.run(20, 1);
+        for (temperature, &replica) in exchange.replicas().iter().enumerate() {
+            visits[replica][temperature] += 1.0;
+        }
+    }
+
+    for acceptance in exchange.acceptances() {
+        assert!(acceptance > 0.05 && acceptance < 1.0);
+    }
+
+    // All replicas are equivalent, so they should spend the same time at
+    // each temperature
+    for replica in &visits {
+        for &count in replica {
+            let fraction = count / attempts as f64;
+            assert!(f64::abs(fraction - 1.0 / 3.0) < 0.1, "fraction is {}", fraction);
+        }
+    }
+}
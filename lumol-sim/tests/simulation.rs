@@ -0,0 +1,152 @@
+// Lumol, an extensible molecular simulation engine
+// Copyright (C) Lumol's contributors — BSD license
+
+use lumol_core::{Particle, Molecule, System, UnitCell};
+use lumol_core::{LennardJones, PairInteraction};
+use lumol_core::units;
+
+use lumol_sim::{Control, MolecularDynamics, Simulation};
+use lumol_sim::mc::{MonteCarloBuilder, Translate};
+use lumol_sim::output::Output;
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+fn testing_system() -> System {
+    let mut system = System::with_cell(UnitCell::cubic(20.0));
+    system.add_molecule(Molecule::new(Particle::new("He")));
+    return system;
+}
+
+#[test]
+fn run_with_callback() {
+    let mut system = testing_system();
+    let mut simulation = Simulation::new(Box::new(MolecularDynamics::new(1.0)));
+
+    let mut steps = Vec::new();
+    simulation.run_with_callback(&mut system, 100, |system, step| {
+        assert_eq!(system.step, step as u64);
+        steps.push(step);
+        if step == 10 {
+            Control::Stop
+        } else {
+            Control::Continue
+        }
+    });
+
+    assert_eq!(system.step, 10);
+    assert_eq!(steps, (1..=10).collect::<Vec<_>>());
+
+    // The step counter passed to the callback starts again for each run
+    let mut calls = 0;
+    simulation.run_with_callback(&mut system, 5, |_, step| {
+        calls += 1;
+        assert_eq!(step, calls);
+        Control::Continue
+    });
+    assert_eq!(calls, 5);
+    assert_eq!(system.step, 15);
+
+    simulation.run(&mut system, 3);
+    assert_eq!(system.step, 18);
+}
+
+/// Output recording the steps at which it was called
+#[derive(Clone, Default)]
+struct Recorder {
+    setup: Rc<RefCell<Vec<u64>>>,
+    steps: Rc<RefCell<Vec<u64>>>,
+    finish: Rc<RefCell<Vec<u64>>>,
+}
+
+impl Output for Recorder {
+    fn setup(&mut self, system: &System) {
+        self.setup.borrow_mut().push(system.step);
+    }
+
+    fn write(&mut self, system: &System) {
+        self.steps.borrow_mut().push(system.step);
+    }
+
+    fn finish(&mut self, system: &System) {
+        self.finish.borrow_mut().push(system.step);
+    }
+}
+
+#[test]
+fn outputs_frequency() {
+    let mut system = testing_system();
+    let mut simulation = Simulation::new(Box::new(MolecularDynamics::new(1.0)));
+
+    let every_step = Recorder::default();
+    let every_three = Recorder::default();
+    simulation.add_output(Box::new(every_step.clone()));
+    simulation.add_output_with_frequency(Box::new(every_three.clone()), 3);
+
+    simulation.run(&mut system, 10);
+    assert_eq!(*every_step.steps.borrow(), (1..=10).collect::<Vec<_>>());
+    assert_eq!(*every_three.steps.borrow(), vec![3, 6, 9]);
+    assert_eq!(*every_three.setup.borrow(), vec![0]);
+    assert_eq!(*every_three.finish.borrow(), vec![10]);
+
+    // The frequency is relative to the system step, not to the run
+    simulation.run(&mut system, 5);
+    assert_eq!(*every_step.steps.borrow(), (1..=15).collect::<Vec<_>>());
+    assert_eq!(*every_three.steps.borrow(), vec![3, 6, 9, 12, 15]);
+    assert_eq!(*every_three.setup.borrow(), vec![0, 10]);
+    assert_eq!(*every_three.finish.borrow(), vec![10, 15]);
+}
+
+#[test]
+#[should_panic(expected = "the frequency of an output must be positive")]
+fn zero_frequency() {
+    let mut simulation = Simulation::new(Box::new(MolecularDynamics::new(1.0)));
+    simulation.add_output_with_frequency(Box::new(Recorder::default()), 0);
+}
+
+fn argon() -> System {
+    let mut system = System::with_cell(UnitCell::cubic(12.0));
+    for i in 0..2 {
+        for j in 0..2 {
+            for k in 0..2 {
+                let position = [i as f64 * 4.0, j as f64 * 4.0, k as f64 * 4.0];
+                system.add_molecule(Molecule::new(Particle::with_position("Ar", position.into())));
+            }
+        }
+    }
+
+    let lj = Box::new(LennardJones {
+        sigma: units::from(3.4, "A").unwrap(),
+        epsilon: units::from(1.0, "kJ/mol").unwrap(),
+    });
+    system.set_pair_potential(("Ar", "Ar"), PairInteraction::new(lj, 5.5));
+    return system;
+}
+
+/// Run a Monte Carlo simulation with the given seed, and get the potential
+/// energy after each step
+fn seeded_monte_carlo(seed: u64) -> Vec<f64> {
+    let mut system = argon();
+    let mut mc = MonteCarloBuilder::new(100.0);
+    mc.add(Box::new(Translate::new(1.0, None)), 1.0, None);
+    let mut simulation = Simulation::new(Box::new(mc.finish()));
+    simulation.set_seed(seed);
+
+    let mut energies = Vec::new();
+    simulation.run_with_callback(&mut system, 500, |system, _| {
+        energies.push(system.potential_energy());
+        Control::Continue
+    });
+    return energies;
+}
+
+#[test]
+fn seed() {
+    let first = seeded_monte_carlo(42);
+    let second = seeded_monte_carlo(42);
+    // Identical acceptance sequence and final energy
+    assert_eq!(first, second);
+
+    let other = seeded_monte_carlo(1234);
+    assert_ne!(first, other);
+}
@@ -0,0 +1,56 @@
+// Lumol, an extensible molecular simulation engine
+// Copyright (C) Lumol's contributors — BSD license
+
+use lumol_core::{Particle, Molecule, System, UnitCell};
+use lumol_core::{LennardJones, PairInteraction};
+use lumol_core::units;
+
+use lumol_sim::{BoltzmannVelocities, InitVelocities};
+use lumol_sim::{Control, MolecularDynamics, Simulation};
+use lumol_sim::analysis::SurfaceTension;
+
+/// Bulk liquid argon, without any interface
+fn argon() -> System {
+    let mut system = System::with_cell(UnitCell::cubic(15.2));
+    for i in 0..4 {
+        for j in 0..4 {
+            for k in 0..4 {
+                let position = [i as f64 * 3.8, j as f64 * 3.8, k as f64 * 3.8];
+                system.add_molecule(Molecule::new(Particle::with_position("Ar", position.into())));
+            }
+        }
+    }
+
+    let lj = Box::new(LennardJones {
+        sigma: units::from(3.4, "A").unwrap(),
+        epsilon: units::from(1.0, "kJ/mol").unwrap(),
+    });
+    system.set_pair_potential(("Ar", "Ar"), PairInteraction::new(lj, 7.0));
+
+    let mut velocities = BoltzmannVelocities::new(120.0);
+    velocities.seed(7);
+    velocities.init(&mut system);
+    return system;
+}
+
+#[test]
+fn isotropic_bulk() {
+    let mut system = argon();
+    let timestep = units::from(2.0, "fs").unwrap();
+    let mut simulation = Simulation::new(Box::new(MolecularDynamics::new(timestep)));
+
+    let mut tensions = vec![SurfaceTension::new(0), SurfaceTension::new(1), SurfaceTension::new(2)];
+    simulation.run_with_callback(&mut system, 5000, |system, _| {
+        for tension in &mut tensions {
+            tension.accumulate(system);
+        }
+        Control::Continue
+    });
+
+    for tension in &tensions {
+        let average = tension.finish();
+        assert_eq!(average.count(), 5000);
+        assert!(average.error() > 0.0);
+        assert!(average.mean().abs() < 4.0 * average.error());
+    }
+}
@@ -7,9 +7,20 @@ use lumol_core::consts::K_BOLTZMANN;
 use lumol_sim::{BoltzmannVelocities, InitVelocities};
 use lumol_sim::md::{Integrator, VelocityVerlet};
 use lumol_sim::md::{Thermostat, RescaleThermostat, BerendsenThermostat, CSVRThermostat};
+use lumol_sim::md::{BussiThermostat, MolecularDynamics};
+use lumol_sim::Propagator;
 
 use approx::{assert_ulps_eq, assert_relative_eq};
 
+/// Get the temperature of the particles in `group`
+fn group_temperature(system: &System, group: &[usize]) -> f64 {
+    let particles = system.particles();
+    let kinetic: f64 = group.iter().map(|&i| {
+        0.5 * particles.mass[i] * particles.velocity[i].norm2()
+    }).sum();
+    return 2.0 * kinetic / (3.0 * group.len() as f64 * K_BOLTZMANN);
+}
+
 // An ideal gas system
 fn testing_system() -> System {
     let mut system = System::with_cell(UnitCell::cubic(20.0));
@@ -115,3 +126,110 @@ fn csvr_thermostat() {
     let expected = dof * (K_BOLTZMANN * temperature) * (K_BOLTZMANN * temperature) / 2.0;
     assert_relative_eq!(variance, expected, epsilon=1e-3);
 }
+
+#[test]
+fn bussi_kinetic_energy_distribution() {
+    let mut system = System::with_cell(UnitCell::cubic(20.0));
+    for i in 0..10 {
+        let mut particle = Particle::new("He");
+        particle.position = Vector3D::new(i as f64 * 2.0, 0.0, 0.0);
+        system.add_molecule(Molecule::new(particle));
+    }
+    let mut velocities = BoltzmannVelocities::new(300.0);
+    velocities.seed(129);
+    velocities.init(&mut system);
+
+    let temperature = 250.0;
+    let tau = 2.0;
+    let mut thermostat = BussiThermostat::new(temperature, tau);
+    thermostat.seed(7);
+    let mut md = MolecularDynamics::new(1.0);
+    md.set_thermostat(Box::new(thermostat));
+    md.setup(&system);
+
+    // equilibrate
+    for _ in 0..1000 {
+        md.propagate(&mut system);
+    }
+
+    // accumulate, keeping only one value every few relaxation times of the
+    // thermostat to reduce the correlation between samples
+    let stride = 5 * tau as usize;
+    let mut kinetic = Vec::new();
+    for step in 0..100000 {
+        md.propagate(&mut system);
+        if step % stride == 0 {
+            kinetic.push(system.kinetic_energy());
+        }
+    }
+
+    // The kinetic energy should follow a chi-squared distribution with one
+    // degree of freedom per particle coordinate, i.e. a gamma distribution
+    // with shape dof / 2 and scale kT.
+    let dof = system.degrees_of_freedom() as f64;
+    let kt = K_BOLTZMANN * temperature;
+    let n = kinetic.len() as f64;
+    let mean = kinetic.iter().sum::<f64>() / n;
+    assert_relative_eq!(mean, dof * kt / 2.0, max_relative = 0.01);
+
+    let variance = kinetic.iter().map(|k| (k - mean) * (k - mean)).sum::<f64>() / n;
+    assert_relative_eq!(variance, dof * kt * kt / 2.0, max_relative = 0.05);
+}
+
+#[test]
+fn group_thermostats() {
+    let mut system = testing_system();
+    let cold = (0..500).collect::<Vec<_>>();
+    let hot = (500..700).collect::<Vec<_>>();
+    let free = (700..1000).collect::<Vec<_>>();
+    let free_temperature = group_temperature(&system, &free);
+
+    let mut cold_thermostat = BerendsenThermostat::for_group(250.0, 10.0, cold.clone());
+    let mut hot_thermostat = RescaleThermostat::for_group(350.0, hot.clone());
+    let mut integrator = VelocityVerlet::new(1.0);
+    integrator.setup(&system);
+    cold_thermostat.setup(&system);
+    hot_thermostat.setup(&system);
+
+    for _ in 0..200 {
+        integrator.integrate(&mut system);
+        cold_thermostat.apply(&mut system);
+        hot_thermostat.apply(&mut system);
+    }
+
+    assert_relative_eq!(group_temperature(&system, &cold), 250.0, max_relative = 1e-6);
+    assert_relative_eq!(group_temperature(&system, &hot), 350.0, max_relative = 1e-6);
+    // The other particles are not thermostated
+    assert_relative_eq!(group_temperature(&system, &free), free_temperature, max_relative = 1e-12);
+}
+
+#[test]
+fn csvr_group_thermostat() {
+    let mut system = testing_system();
+    let group = (0..500).collect::<Vec<_>>();
+    let free = (500..1000).collect::<Vec<_>>();
+    let free_temperature = group_temperature(&system, &free);
+
+    let mut thermostat = CSVRThermostat::for_group(250.0, 10.0, group.clone());
+    let mut integrator = VelocityVerlet::new(1.0);
+    integrator.setup(&system);
+    thermostat.setup(&system);
+
+    // equilibrate
+    for _ in 0..100 {
+        integrator.integrate(&mut system);
+        thermostat.apply(&mut system);
+    }
+
+    // accumulate
+    let mut temperatures = Vec::new();
+    for _ in 0..100 {
+        integrator.integrate(&mut system);
+        thermostat.apply(&mut system);
+        temperatures.push(group_temperature(&system, &group));
+    }
+
+    let mean = temperatures.iter().sum::<f64>() / temperatures.len() as f64;
+    assert_relative_eq!(mean, 250.0, max_relative = 0.02);
+    assert_relative_eq!(group_temperature(&system, &free), free_temperature, max_relative = 1e-12);
+}
@@ -0,0 +1,132 @@
+// Lumol, an extensible molecular simulation engine
+// Copyright (C) Lumol's contributors — BSD license
+
+use lumol_core::{Vector3D, Particle, Molecule, System, UnitCell, VirtualSite};
+use lumol_core::{Harmonic, PairRestriction, CoulombicPotential, Wolf};
+use lumol_core::units;
+
+use lumol_sim::{BoltzmannVelocities, InitVelocities};
+use lumol_sim::md::{Integrator, VelocityVerlet};
+
+use approx::assert_ulps_eq;
+
+const R_OH: f64 = 0.9572;
+const THETA: f64 = 104.52;
+const R_OM: f64 = 0.15;
+
+/// Weight of each hydrogen in the position of the TIP4P M site
+fn hydrogen_weight() -> f64 {
+    let half_theta = f64::to_radians(0.5 * THETA);
+    R_OM / (2.0 * R_OH * f64::cos(half_theta))
+}
+
+fn tip4p(origin: Vector3D) -> Molecule {
+    let half_theta = f64::to_radians(0.5 * THETA);
+    let h1 = Vector3D::new(R_OH * f64::sin(half_theta), R_OH * f64::cos(half_theta), 0.0);
+    let h2 = Vector3D::new(-R_OH * f64::sin(half_theta), R_OH * f64::cos(half_theta), 0.0);
+
+    let mut molecule = Molecule::new(Particle::with_position("O", origin));
+    molecule.add_particle_bonded_to(0, Particle::with_position("H", origin + h1));
+    molecule.add_particle_bonded_to(0, Particle::with_position("H", origin + h2));
+    molecule.add_particle_bonded_to(0, Particle::with_position("M", origin));
+
+    let masses = [15.999, 1.008, 1.008, 0.0];
+    let charges = [0.0, 0.52, 0.52, -1.04];
+    for (i, particle) in molecule.particles_mut().iter_mut().enumerate() {
+        *particle.mass = masses[i];
+        *particle.charge = charges[i];
+    }
+    return molecule;
+}
+
+fn testing_system() -> System {
+    let mut system = System::with_cell(UnitCell::cubic(20.0));
+    system.add_molecule(tip4p(Vector3D::new(5.0, 5.0, 5.0)));
+    system.add_molecule(tip4p(Vector3D::new(8.0, 5.5, 4.0)));
+
+    let a = hydrogen_weight();
+    for molecule in 0..2 {
+        let oxygen = 4 * molecule;
+        let parents = [oxygen, oxygen + 1, oxygen + 2];
+        system.add_virtual_site(VirtualSite::new(oxygen + 3, parents, [1.0 - 2.0 * a, a, a]));
+    }
+
+    system.set_bond_potential(
+        ("O", "H"),
+        Box::new(Harmonic {
+            k: units::from(1000.0, "kJ/mol/A^2").unwrap(),
+            x0: R_OH,
+        }),
+    );
+    system.set_angle_potential(
+        ("H", "O", "H"),
+        Box::new(Harmonic {
+            k: units::from(300.0, "kJ/mol/rad^2").unwrap(),
+            x0: f64::to_radians(THETA),
+        }),
+    );
+
+    let mut wolf = Wolf::new(8.0);
+    wolf.set_restriction(PairRestriction::InterMolecular);
+    system.set_coulomb_potential(Box::new(wolf));
+
+    let mut velocities = BoltzmannVelocities::new(300.0);
+    velocities.init(&mut system);
+    return system;
+}
+
+/// Check that the M site of the water molecule starting at `oxygen` is at the
+/// right place with respect to the other atoms.
+fn check_m_site(system: &System, oxygen: usize) {
+    let positions = &system.particles().position;
+    let (o, h1, h2, m) = (
+        positions[oxygen], positions[oxygen + 1], positions[oxygen + 2], positions[oxygen + 3]
+    );
+
+    let a = hydrogen_weight();
+    let expected = o + a * (h1 - o) + a * (h2 - o);
+    assert_ulps_eq!((m - expected).norm(), 0.0, epsilon = 1e-12);
+
+    // The M site is in the H-O-H plane
+    let normal = (h1 - o) ^ (h2 - o);
+    assert_ulps_eq!((m - o) * normal, 0.0, epsilon = 1e-12);
+}
+
+#[test]
+fn tip4p_m_site() {
+    let mut system = testing_system();
+    assert_eq!(system.degrees_of_freedom(), 18);
+
+    for &oxygen in &[0, 4] {
+        check_m_site(&system, oxygen);
+        assert_ulps_eq!(system.distance(oxygen, oxygen + 3), R_OM, epsilon = 1e-12);
+    }
+
+    // The virtual sites carry a charge, so there are forces between the
+    // molecules; but these forces are redistributed to the other atoms.
+    let forces = system.forces();
+    assert_ne!(forces[0], Vector3D::zero());
+    assert_eq!(forces[3], Vector3D::zero());
+    assert_eq!(forces[7], Vector3D::zero());
+
+    let initial = system.particles().position[0];
+    let mut integrator = VelocityVerlet::new(units::from(0.5, "fs").unwrap());
+    integrator.setup(&system);
+    for _ in 0..200 {
+        integrator.integrate(&mut system);
+
+        for &oxygen in &[0, 4] {
+            check_m_site(&system, oxygen);
+        }
+
+        let mut momentum = Vector3D::zero();
+        for particle in system.particles() {
+            assert!(particle.velocity.norm().is_finite());
+            momentum += (*particle.mass) * (*particle.velocity);
+        }
+        assert_ulps_eq!(momentum.norm(), 0.0, epsilon = 1e-10);
+    }
+
+    // The molecules did move
+    assert!((system.particles().position[0] - initial).norm() > 1e-3);
+}
@@ -6,6 +6,9 @@
 use lumol::input::Input;
 use lumol::consts::K_BOLTZMANN;
 use lumol::units;
+use lumol::System;
+use lumol::sim::{MolecularDynamics, Propagator};
+use lumol::sim::output::{EnergyDriftOutput, Output};
 
 use std::path::Path;
 use std::sync::Once;
@@ -142,3 +145,42 @@ fn table_computation() {
     let e_final = config.system.total_energy();
     assert!(f64::abs((e_initial - e_final) / e_final) < 5e-3);
 }
+
+/// Run NVE molecular dynamics with the given `timestep` for `nsteps` steps,
+/// and get the slope of the energy drift
+fn energy_drift(system: &System, timestep: f64, nsteps: usize) -> f64 {
+    let mut system = system.clone();
+    let mut md = MolecularDynamics::new(timestep);
+    let mut drift = EnergyDriftOutput::new(timestep);
+
+    md.setup(&system);
+    drift.setup(&system);
+    for _ in 0..nsteps {
+        md.propagate(&mut system);
+        system.step += 1;
+        drift.write(&system);
+    }
+    md.finish(&system);
+    drift.finish(&system);
+    return drift.slope();
+}
+
+#[test]
+fn energy_drift_slope() {
+    START.call_once(::env_logger::init);
+    let path = Path::new(file!()).parent()
+                                 .unwrap()
+                                 .join("data")
+                                 .join("md-helium")
+                                 .join("nve-velocity-verlet.toml");
+    let config = Input::new(path).unwrap().read().unwrap();
+
+    let small = energy_drift(&config.system, units::from(1.0, "fs").unwrap(), 2000);
+    assert!(f64::abs(small) < 1.0);
+
+    // A large timestep makes the particles go too far in the repulsive part
+    // of the potential, and heats the system up
+    let large = energy_drift(&config.system, units::from(6.0, "fs").unwrap(), 2000);
+    assert!(large > 0.0);
+    assert!(large > 10.0 * f64::abs(small));
+}